@@ -0,0 +1,350 @@
+use anyhow::{Context, Result, anyhow};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use super::ls::{TreeNode, walk_sizes};
+use super::sync::local_tree;
+use crate::pikpak::{Backend, Entry, EntryKind, pikpak_hash};
+
+const USAGE: &str = "Usage: pikpaktui backup [-n|--dry-run] [--include <glob>]... [--exclude <glob>]... <local> <remote>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    /// Not present remotely.
+    Upload,
+    /// Present remotely with a different size or hash; the old copy is
+    /// trashed once the new one is up.
+    Replace,
+    /// A remote folder has the file's name; left alone.
+    Conflict,
+}
+
+#[derive(Debug)]
+struct Step {
+    action: Action,
+    rel: PathBuf,
+    /// The remote file being replaced.
+    old: Option<String>,
+}
+
+/// `--include` / `--exclude` globs, matched against both the name and the
+/// path relative to the backup root (`a/b.txt`).
+#[derive(Debug, Default)]
+struct Filter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl Filter {
+    fn matches(patterns: &[String], rel: &Path) -> bool {
+        let name = rel
+            .file_name()
+            .map(|n| n.to_string_lossy())
+            .unwrap_or_default();
+        let path = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        patterns
+            .iter()
+            .any(|p| super::glob_match(p, &name) || super::glob_match(p, &path))
+    }
+
+    /// Excludes apply to files and folders (skipping everything inside);
+    /// includes only to files.
+    fn allows(&self, rel: &Path, is_dir: bool) -> bool {
+        if Self::matches(&self.exclude, rel) {
+            return false;
+        }
+        is_dir || self.include.is_empty() || Self::matches(&self.include, rel)
+    }
+}
+
+/// Remote items by their path relative to the backup root. When PikPak has
+/// several items of one name, the first listed stands for it.
+fn index(nodes: Vec<TreeNode>, prefix: &Path, out: &mut BTreeMap<PathBuf, Entry>) {
+    for node in nodes {
+        let rel = prefix.join(&node.entry.name);
+        if out.contains_key(&rel) {
+            continue;
+        }
+        index(node.children, &rel, out);
+        out.insert(rel, node.entry);
+    }
+}
+
+/// Files to upload so `remote` holds everything in `local` the filter
+/// allows. Same-sized files count as unchanged unless PikPak has a hash for
+/// the remote copy and the local one differs.
+fn plan(
+    local: &BTreeMap<PathBuf, bool>,
+    remote: &BTreeMap<PathBuf, Entry>,
+    filter: &Filter,
+    mut local_hash: impl FnMut(&Path) -> Option<String>,
+    local_size: impl Fn(&Path) -> u64,
+) -> Vec<Step> {
+    let mut steps = Vec::new();
+    let mut skipped: Vec<&PathBuf> = Vec::new();
+    for (rel, &is_dir) in local {
+        if skipped.iter().any(|s| rel.starts_with(s)) {
+            continue;
+        }
+        if !filter.allows(rel, is_dir) {
+            if is_dir {
+                skipped.push(rel);
+            }
+            continue;
+        }
+        if is_dir {
+            continue;
+        }
+        let (action, old) = match remote.get(rel) {
+            None => (Action::Upload, None),
+            Some(e) if e.kind == EntryKind::Folder => (Action::Conflict, None),
+            Some(e) => {
+                let same = local_size(rel) == e.size
+                    && (e.hash.is_empty()
+                        || local_hash(rel).is_some_and(|h| h.eq_ignore_ascii_case(&e.hash)));
+                if same {
+                    continue;
+                }
+                (Action::Replace, Some(e.id.clone()))
+            }
+        };
+        steps.push(Step {
+            action,
+            rel: rel.clone(),
+            old,
+        });
+    }
+    steps
+}
+
+/// The id of the remote folder at `rel`, creating it (and its parents) on
+/// first use.
+fn ensure_folder(
+    client: &dyn Backend,
+    folders: &mut HashMap<PathBuf, String>,
+    rel: &Path,
+) -> Result<String> {
+    if let Some(id) = folders.get(rel) {
+        return Ok(id.clone());
+    }
+    let parent = rel.parent().unwrap_or(Path::new(""));
+    let parent_id = ensure_folder(client, folders, parent)?;
+    let name = rel.file_name().unwrap_or_default().to_string_lossy();
+    let id = client
+        .mkdir(&parent_id, &name)
+        .with_context(|| format!("cannot create folder '{}'", rel.display()))?
+        .id;
+    folders.insert(rel.to_path_buf(), id.clone());
+    Ok(id)
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    let mut dry_run = super::dry_run();
+    let mut filter = Filter::default();
+    let mut paths: Vec<&str> = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-n" | "--dry-run" => dry_run = true,
+            "--include" | "--exclude" => {
+                let glob = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("{arg} requires a glob pattern"))?
+                    .clone();
+                if arg == "--include" {
+                    filter.include.push(glob);
+                } else {
+                    filter.exclude.push(glob);
+                }
+            }
+            s if s.starts_with('-') => {
+                return Err(anyhow!("unknown option for backup: {s}\n{USAGE}"));
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let [local_path, remote_path] = paths[..] else {
+        return Err(anyhow!(
+            "backup needs a local directory and a remote folder\n{USAGE}"
+        ));
+    };
+    let local_root = PathBuf::from(local_path);
+    if !local_root.is_dir() {
+        return Err(anyhow!("'{}' is not a directory", local_root.display()));
+    }
+
+    let client = super::cli_client()?;
+    let root_id = client.resolve_path(remote_path)?;
+    let spinner = super::Spinner::new("Comparing...");
+    let mut remote = BTreeMap::new();
+    index(walk_sizes(&client, &root_id)?, Path::new(""), &mut remote);
+    let local = local_tree(&local_root)?;
+    let steps = plan(
+        &local,
+        &remote,
+        &filter,
+        |rel| pikpak_hash(&local_root.join(rel)).ok(),
+        |rel| {
+            local_root
+                .join(rel)
+                .metadata()
+                .map(|m| m.len())
+                .unwrap_or(0)
+        },
+    );
+    drop(spinner);
+
+    if steps.is_empty() {
+        super::status(format!("'{}' is up to date", remote_path));
+        return Ok(());
+    }
+
+    let mut folders: HashMap<PathBuf, String> = remote
+        .iter()
+        .filter(|(_, e)| e.kind == EntryKind::Folder)
+        .map(|(rel, e)| (rel.clone(), e.id.clone()))
+        .collect();
+    folders.insert(PathBuf::new(), root_id);
+
+    let (mut uploaded, mut instant, mut failed) = (0usize, 0usize, 0usize);
+    for step in &steps {
+        let path = local_root.join(&step.rel);
+        let size = path.metadata().map(|m| m.len()).unwrap_or(0);
+        let mark = match step.action {
+            Action::Upload => "\x1b[32m+\x1b[0m",
+            Action::Replace => "\x1b[33m~\x1b[0m",
+            Action::Conflict => {
+                eprintln!(
+                    "  [skip] '{}': a remote folder has this name",
+                    step.rel.display()
+                );
+                continue;
+            }
+        };
+        if dry_run || !super::quiet() {
+            println!(
+                "{}{} {} ({})",
+                if dry_run { "[dry-run] " } else { "" },
+                mark,
+                step.rel.display(),
+                super::format_size(size)
+            );
+        }
+        if dry_run {
+            continue;
+        }
+
+        let result = (|| -> Result<bool> {
+            let parent = step.rel.parent().unwrap_or(Path::new(""));
+            let parent_id = ensure_folder(&client, &mut folders, parent)?;
+            let (_, dedup) = client.upload_file(Some(&parent_id), &path)?;
+            // Trash the old copy only once the new one is safely up.
+            if let Some(old) = &step.old {
+                client.remove(&[old.as_str()])?;
+            }
+            Ok(dedup)
+        })();
+        match result {
+            Ok(dedup) => {
+                uploaded += 1;
+                instant += usize::from(dedup);
+            }
+            Err(e) => {
+                eprintln!("  [error] '{}': {e:#}", step.rel.display());
+                failed += 1;
+            }
+        }
+    }
+
+    let count = |a: Action| steps.iter().filter(|s| s.action == a).count();
+    if dry_run {
+        println!(
+            "Would back up: {} new, {} changed",
+            count(Action::Upload),
+            count(Action::Replace)
+        );
+        return Ok(());
+    }
+    super::status(format!(
+        "Backed up '{}' -> '{}': {} file(s) uploaded, {} of them instantly (already on PikPak)",
+        local_root.display(),
+        remote_path,
+        uploaded,
+        instant
+    ));
+    if failed > 0 {
+        return Err(anyhow!("{} file(s) failed to back up", failed));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::ls::node;
+
+    #[test]
+    fn plan_uploads_new_and_changed_files_through_filters() {
+        let mut remote = BTreeMap::new();
+        index(
+            vec![
+                node(
+                    Entry::folder("src"),
+                    vec![
+                        node(
+                            Entry::file("main.rs").with_size(10).with_hash("AAA"),
+                            vec![],
+                        ),
+                        node(Entry::file("lib.rs").with_size(10).with_hash("BBB"), vec![]),
+                    ],
+                ),
+                node(Entry::folder("notes"), vec![]),
+            ],
+            Path::new(""),
+            &mut remote,
+        );
+        let local: BTreeMap<PathBuf, bool> = [
+            ("notes", false),
+            ("src", true),
+            ("src/lib.rs", false),
+            ("src/main.rs", false),
+            ("src/new.rs", false),
+            ("src/scratch.tmp", false),
+            ("target", true),
+            ("target/app", false),
+        ]
+        .into_iter()
+        .map(|(p, d)| (PathBuf::from(p), d))
+        .collect();
+        let filter = Filter {
+            include: vec![],
+            exclude: vec!["*.tmp".into(), "target".into()],
+        };
+
+        let steps = plan(&local, &remote, &filter, |_| Some("aaa".into()), |_| 10);
+        let got: Vec<(Action, &str, Option<&str>)> = steps
+            .iter()
+            .map(|s| (s.action, s.rel.to_str().unwrap(), s.old.as_deref()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (Action::Conflict, "notes", None),
+                (Action::Replace, "src/lib.rs", Some("lib.rs")),
+                (Action::Upload, "src/new.rs", None),
+            ]
+        );
+
+        let only_rs = Filter {
+            include: vec!["src/*.rs".into()],
+            exclude: vec![],
+        };
+        assert!(only_rs.allows(Path::new("src/new.rs"), false));
+        assert!(!only_rs.allows(Path::new("notes"), false));
+        assert!(only_rs.allows(Path::new("target"), true));
+    }
+}
@@ -1,12 +1,15 @@
 use anyhow::{Result, anyhow};
 
 pub fn run(args: &[String]) -> Result<()> {
-    if args.is_empty() {
-        return Err(anyhow!("Usage: pikpaktui cat <path>"));
-    }
+    let raw = args.iter().any(|a| a == "--raw");
+    let Some(path) = args.iter().find(|a| *a != "--raw") else {
+        return Err(anyhow!("Usage: pikpaktui cat [--raw] <path>"));
+    };
 
-    let path = &args[0];
     let client = super::cli_client()?;
+    if raw {
+        return super::download::to_stdout(&client, path);
+    }
     let config = super::cli_config();
 
     let (parent_path, name) = super::split_parent_name(path)?;
@@ -19,7 +22,7 @@ pub fn run(args: &[String]) -> Result<()> {
 
     print!("{}", content);
     if truncated {
-        eprintln!("\n(truncated at {} bytes)", max_bytes);
+        super::notice(format!("\n(truncated at {} bytes)", max_bytes));
     }
 
     Ok(())
@@ -0,0 +1,296 @@
+use crate::pikpak::{QuotaInfo, TransferQuotaBase, VipInfoResponse};
+use anyhow::{Result, anyhow};
+use std::process::Command;
+
+const USAGE: &str = "Usage: pikpaktui check [--notify] [--storage <pct>] [--bandwidth <pct>] [--vip-days <n>] [-J|--plain]";
+
+/// Exit status when a threshold is crossed, clear of the failure codes of
+/// `super::exit_code` so an alerting pipeline can tell "needs attention"
+/// from "couldn't check".
+pub const ALERT_EXIT: i32 = 10;
+
+/// What `check` compares against; 0 turns a check off.
+struct Thresholds {
+    storage_percent: u64,
+    bandwidth_percent: u64,
+    vip_days: i64,
+}
+
+/// One crossed threshold.
+#[derive(Debug, PartialEq)]
+struct Alert {
+    kind: &'static str,
+    message: String,
+}
+
+/// Returns the exit status: 0, or `ALERT_EXIT` when a threshold was crossed.
+pub fn run(args: &[String]) -> Result<i32> {
+    let config = super::cli_config();
+    let mut limits = Thresholds {
+        storage_percent: config.alert_storage_percent,
+        bandwidth_percent: config.alert_bandwidth_percent,
+        vip_days: config.alert_vip_days,
+    };
+    let mut notify = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f, Some(v.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |name: &str| {
+            inline
+                .clone()
+                .or_else(|| iter.next().cloned())
+                .ok_or_else(|| anyhow!("{name} requires a number"))
+        };
+        match flag {
+            "--notify" => notify = true,
+            "--storage" => limits.storage_percent = parse_percent(&value("--storage")?)?,
+            "--bandwidth" => limits.bandwidth_percent = parse_percent(&value("--bandwidth")?)?,
+            "--vip-days" => {
+                let v = value("--vip-days")?;
+                limits.vip_days = v
+                    .parse()
+                    .ok()
+                    .filter(|d| *d >= 0)
+                    .ok_or_else(|| anyhow!("--vip-days: '{v}' is not a number of days"))?;
+            }
+            _ => return Err(anyhow!(USAGE)),
+        }
+    }
+
+    let client = super::cli_client()?;
+    let quota = client.quota()?;
+    // Only what a threshold that's on needs is fetched.
+    let transfer = match limits.bandwidth_percent {
+        0 => None,
+        _ => Some(client.transfer_quota()?),
+    };
+    let vip = match limits.vip_days {
+        0 => None,
+        _ => Some(client.vip_info()?),
+    };
+
+    let alerts = evaluate(
+        &quota,
+        transfer.as_ref().and_then(|t| t.base.as_ref()),
+        vip.as_ref(),
+        &limits,
+        super::now_unix(),
+    );
+
+    if super::output_format().is_machine() {
+        let list: Vec<_> = alerts
+            .iter()
+            .map(|a| serde_json::json!({ "kind": a.kind, "message": a.message }))
+            .collect();
+        let out = serde_json::json!({ "ok": alerts.is_empty(), "alerts": list });
+        super::print_report(&out, "alerts");
+    } else if alerts.is_empty() {
+        println!("All within thresholds");
+    } else {
+        for a in &alerts {
+            println!("{}", a.message);
+        }
+    }
+
+    if alerts.is_empty() {
+        return Ok(0);
+    }
+    if notify {
+        let body: Vec<&str> = alerts.iter().map(|a| a.message.as_str()).collect();
+        if let Err(e) = send_notification("PikPak needs attention", &body.join("\n")) {
+            eprintln!("Warning: desktop notification failed: {e:#}");
+        }
+    }
+    Ok(ALERT_EXIT)
+}
+
+fn parse_percent(v: &str) -> Result<u64> {
+    v.trim_end_matches('%')
+        .parse()
+        .ok()
+        .filter(|p| *p <= 100)
+        .ok_or_else(|| anyhow!("'{v}' is not a percentage (0-100)"))
+}
+
+/// Compare the account against `limits`, at `now` (unix seconds).
+fn evaluate(
+    quota: &QuotaInfo,
+    transfer: Option<&TransferQuotaBase>,
+    vip: Option<&VipInfoResponse>,
+    limits: &Thresholds,
+    now: i64,
+) -> Vec<Alert> {
+    let mut alerts = Vec::new();
+    let over = |used: u64, total: u64, pct: u64| {
+        pct > 0 && total > 0 && u128::from(used) * 100 >= u128::from(total) * u128::from(pct)
+    };
+    let percent = |used: u64, total: u64| u128::from(used) * 100 / u128::from(total.max(1));
+
+    if let Some(d) = quota.quota.as_ref().filter(|d| !d.is_unlimited) {
+        let num = |s: &Option<String>| s.as_deref().unwrap_or("0").parse::<u64>().unwrap_or(0);
+        let (limit, used) = (num(&d.limit), num(&d.usage));
+        if over(used, limit, limits.storage_percent) {
+            alerts.push(Alert {
+                kind: "storage",
+                message: format!(
+                    "Storage {}% full: {} of {}",
+                    percent(used, limit),
+                    super::format_size(used),
+                    super::format_size(limit)
+                ),
+            });
+        }
+    }
+
+    if let Some(base) = transfer {
+        let bands = [
+            ("download", "Download", &base.download),
+            ("download_daily", "Daily download", &base.download_daily),
+            ("upload", "Upload", &base.upload),
+            ("offline", "Offline", &base.offline),
+        ];
+        for (kind, label, band) in bands {
+            let Some(band) = band else { continue };
+            let (used, total) = (band.assets.unwrap_or(0), band.total_assets.unwrap_or(0));
+            if over(used, total, limits.bandwidth_percent) {
+                alerts.push(Alert {
+                    kind,
+                    message: format!(
+                        "{label} allowance {}% used: {} of {}",
+                        percent(used, total),
+                        super::format_size(used),
+                        super::format_size(total)
+                    ),
+                });
+            }
+        }
+    }
+
+    // Only a paid plan can run out; `novip` has nothing to renew.
+    if limits.vip_days > 0
+        && let Some(data) = vip.and_then(|v| v.data.as_ref())
+        && let Some(plan) = data.vip_type.as_deref().filter(|t| *t != "novip")
+        && let Some(expire) = data.expire.as_deref().and_then(crate::pikpak::iso_to_unix)
+    {
+        let left = expire - now;
+        if left <= limits.vip_days * 86_400 {
+            let message = if left <= 0 {
+                format!("VIP ({plan}) has expired")
+            } else {
+                let days = left / 86_400;
+                format!("VIP ({plan}) expires in {days} day(s)")
+            };
+            alerts.push(Alert {
+                kind: "vip",
+                message,
+            });
+        }
+    }
+
+    alerts
+}
+
+/// Show a desktop notification: `osascript` on macOS, `notify-send`
+/// elsewhere. Windows has no command-line notifier to lean on.
+fn send_notification(title: &str, body: &str) -> Result<()> {
+    let status = if cfg!(target_os = "macos") {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let script = format!(
+            "display notification {} with title {}",
+            quote(body),
+            quote(title)
+        );
+        Command::new("osascript").arg("-e").arg(script).status()
+    } else if cfg!(windows) {
+        return Err(anyhow!("not supported on Windows; use the exit status"));
+    } else {
+        Command::new("notify-send")
+            .args(["-a", "pikpaktui", title, body])
+            .status()
+    };
+    match status {
+        Ok(s) if s.success() => Ok(()),
+        Ok(s) => Err(anyhow!("notifier exited with {s}")),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits() -> Thresholds {
+        Thresholds {
+            storage_percent: 90,
+            bandwidth_percent: 80,
+            vip_days: 7,
+        }
+    }
+
+    fn quota(limit: u64, used: u64) -> QuotaInfo {
+        serde_json::from_value(serde_json::json!({
+            "quota": { "limit": limit.to_string(), "usage": used.to_string() }
+        }))
+        .unwrap()
+    }
+
+    fn vip(plan: &str, expire: &str) -> VipInfoResponse {
+        serde_json::from_value(serde_json::json!({
+            "data": { "type": plan, "status": "ok", "expire": expire }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn storage_alerts_at_the_threshold_only() {
+        assert!(evaluate(&quota(100, 89), None, None, &limits(), 0).is_empty());
+        let alerts = evaluate(&quota(100, 90), None, None, &limits(), 0);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, "storage");
+
+        let off = Thresholds {
+            storage_percent: 0,
+            ..limits()
+        };
+        assert!(evaluate(&quota(100, 100), None, None, &off, 0).is_empty());
+    }
+
+    #[test]
+    fn bandwidth_skips_uncapped_bands() {
+        let base: TransferQuotaBase = serde_json::from_value(serde_json::json!({
+            "download": { "assets": 85, "total_assets": 100 },
+            "download_daily": { "assets": 500, "total_assets": 0 },
+            "upload": { "assets": 10, "total_assets": 100 },
+        }))
+        .unwrap();
+        let alerts = evaluate(&quota(0, 0), Some(&base), None, &limits(), 0);
+        let kinds: Vec<&str> = alerts.iter().map(|a| a.kind).collect();
+        assert_eq!(kinds, ["download"]);
+    }
+
+    #[test]
+    fn vip_alerts_inside_the_window_and_ignores_free_accounts() {
+        // 2026-12-01T00:00:00Z
+        let expire = 1_796_083_200;
+        let v = vip("platinum", "2026-12-01T08:00:00+08:00");
+        let day = 86_400;
+        assert!(evaluate(&quota(0, 0), None, Some(&v), &limits(), expire - 8 * day).is_empty());
+        let alerts = evaluate(&quota(0, 0), None, Some(&v), &limits(), expire - 3 * day);
+        assert_eq!(alerts[0].message, "VIP (platinum) expires in 3 day(s)");
+        let alerts = evaluate(&quota(0, 0), None, Some(&v), &limits(), expire + 1);
+        assert_eq!(alerts[0].message, "VIP (platinum) has expired");
+
+        let free = vip("novip", "2026-12-01T08:00:00+08:00");
+        assert!(evaluate(&quota(0, 0), None, Some(&free), &limits(), expire).is_empty());
+    }
+
+    #[test]
+    fn percent_accepts_trailing_sign() {
+        assert_eq!(parse_percent("85%").unwrap(), 85);
+        assert!(parse_percent("150").is_err());
+    }
+}
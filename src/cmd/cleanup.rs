@@ -0,0 +1,310 @@
+use crate::config::{CleanupAction, CleanupRule};
+use crate::pikpak::{Backend, Entry, EntryKind, PikPak, iso_to_unix};
+use crate::store::set_meta;
+use anyhow::{Result, anyhow};
+use std::io::{self, IsTerminal};
+
+const USAGE: &str = "Usage: pikpaktui cleanup [-n] [-y] [-J|--plain]";
+
+/// `meta` key: when the TUI last ran the rules.
+const LAST_AUTO_RUN: &str = "cleanup_last_auto_run";
+
+/// An item a rule has found old enough to go.
+pub struct Expired {
+    /// Full path on the drive.
+    pub path: String,
+    pub entry: Entry,
+    /// When it arrived on the drive, in unix seconds.
+    pub created: i64,
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    let mut dry_run = super::dry_run();
+    let mut yes = false;
+    let json = super::output_format().is_machine();
+    for arg in args {
+        match arg.as_str() {
+            "-n" | "--dry-run" => dry_run = true,
+            "-y" | "--yes" => yes = true,
+            _ => return Err(anyhow!(USAGE)),
+        }
+    }
+
+    let config = super::cli_config();
+    if config.cleanup.is_empty() {
+        return Err(anyhow!(
+            "no cleanup rules; add [[cleanup]] tables to config.toml (see the configuration docs)"
+        ));
+    }
+    let client = super::cli_client()?;
+    let now = super::now_unix();
+
+    let mut out = Vec::new();
+    let mut failed = 0;
+    for rule in &config.cleanup {
+        let spinner = super::Spinner::new(&format!("Checking {}...", rule.path));
+        let found = expired(&client, rule, now);
+        drop(spinner);
+        let found = match found {
+            Ok(found) => found,
+            Err(e) => {
+                eprintln!("{}: {e:#}", rule.path);
+                failed += 1;
+                continue;
+            }
+        };
+
+        if !json {
+            print_rule(rule, &found, now, dry_run);
+        }
+        let mut applied = false;
+        if !dry_run && !found.is_empty() {
+            if rule.action == CleanupAction::Delete && !yes && !confirm_delete(json)? {
+                if !json {
+                    println!("Skipped.");
+                }
+            } else {
+                match apply(&client, rule, &found) {
+                    Ok(()) => applied = true,
+                    Err(e) => {
+                        eprintln!("{}: {e:#}", rule.path);
+                        failed += 1;
+                    }
+                }
+                if applied && !json {
+                    let size: u64 = found.iter().map(|e| e.entry.size).sum();
+                    super::status(format!(
+                        "{} {} item(s), {}",
+                        match rule.action {
+                            CleanupAction::Trash => "Trashed",
+                            CleanupAction::Delete => "Permanently deleted",
+                        },
+                        found.len(),
+                        super::format_size(size)
+                    ));
+                }
+            }
+        }
+        if json {
+            out.push(serde_json::json!({
+                "path": rule.path,
+                "older_than_days": rule.older_than_days,
+                "action": rule.action,
+                "applied": applied,
+                "items": found.iter().map(|e| serde_json::json!({
+                    "id": e.entry.id,
+                    "path": e.path,
+                    "size": e.entry.size,
+                    "created_time": e.entry.created_time,
+                })).collect::<Vec<_>>(),
+            }));
+        }
+    }
+
+    match super::output_format() {
+        super::OutputFormat::Human => {}
+        super::OutputFormat::Json => super::print_data(&out.into()),
+        // One row per item, tagged with the rule that found it.
+        super::OutputFormat::Plain => {
+            let rows: Vec<serde_json::Value> = out
+                .iter()
+                .flat_map(|rule| {
+                    rule["items"].as_array().into_iter().flatten().map(|item| {
+                        let mut row = item.clone();
+                        row["rule"] = rule["path"].clone();
+                        row["action"] = rule["action"].clone();
+                        row["applied"] = rule["applied"].clone();
+                        row
+                    })
+                })
+                .collect();
+            super::print_data(&rows.into());
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow!("{failed} rule(s) failed"));
+    }
+    Ok(())
+}
+
+fn print_rule(rule: &CleanupRule, found: &[Expired], now: i64, dry_run: bool) {
+    let mut what = format!("older than {} days", rule.older_than_days);
+    if let Some(pattern) = &rule.pattern {
+        what.push_str(&format!(", matching {pattern}"));
+    }
+    if rule.recursive {
+        what.push_str(", in subfolders too");
+    }
+    let action = match rule.action {
+        CleanupAction::Trash => "trash",
+        CleanupAction::Delete => "delete permanently",
+    };
+    let prefix = if dry_run { "[dry-run] " } else { "" };
+    println!(
+        "{prefix}\x1b[1m{}\x1b[0m  \x1b[2m{what}: {action}\x1b[0m",
+        rule.path
+    );
+    if found.is_empty() {
+        println!("  nothing to do");
+        return;
+    }
+    for e in found {
+        let size = if e.entry.kind == EntryKind::Folder {
+            "folder".to_string()
+        } else {
+            super::format_size(e.entry.size)
+        };
+        println!(
+            "  {}  \x1b[2m{size}, {} days old\x1b[0m",
+            e.path,
+            (now - e.created) / 86_400
+        );
+    }
+}
+
+/// Permanent deletes need `--yes`, or the word typed at a terminal, as
+/// for `rm -f`.
+fn confirm_delete(json: bool) -> Result<bool> {
+    if json || !io::stdin().is_terminal() {
+        return Err(anyhow!(
+            "Refusing to permanently delete without confirmation; pass --yes"
+        ));
+    }
+    super::rm::confirm_typed()
+}
+
+/// What `rule` finds old enough to go, by path. Without `recursive` a
+/// folder is judged as a whole by its own age; with it, only files are,
+/// wherever they are below `rule.path`.
+pub fn expired(client: &dyn Backend, rule: &CleanupRule, now: i64) -> Result<Vec<Expired>> {
+    if rule.older_than_days == 0 {
+        return Err(anyhow!("older_than_days must be at least 1"));
+    }
+    let cutoff = now - rule.older_than_days as i64 * 86_400;
+    let root = rule.path.trim_end_matches('/');
+    let mut pending = vec![(client.resolve_path(&rule.path)?, root.to_string())];
+    let mut found = Vec::new();
+    while let Some((folder_id, path)) = pending.pop() {
+        for entry in client.ls(&folder_id)? {
+            let child = format!("{path}/{}", entry.name);
+            if rule.recursive && entry.kind == EntryKind::Folder {
+                pending.push((entry.id, child));
+                continue;
+            }
+            if let Some(created) = expires(rule, &entry, cutoff) {
+                found.push(Expired {
+                    path: child,
+                    entry,
+                    created,
+                });
+            }
+        }
+    }
+    found.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(found)
+}
+
+/// When `entry` arrived, if that's before `cutoff` and it matches the
+/// rule's pattern. An unreadable date never expires.
+fn expires(rule: &CleanupRule, entry: &Entry, cutoff: i64) -> Option<i64> {
+    let created = iso_to_unix(&entry.created_time)?;
+    let matches = rule
+        .pattern
+        .as_deref()
+        .is_none_or(|p| super::glob_match(p, &entry.name));
+    (created < cutoff && matches).then_some(created)
+}
+
+/// Trash (or delete) what a rule found, a batch at a time.
+pub fn apply(client: &PikPak, rule: &CleanupRule, found: &[Expired]) -> Result<()> {
+    for batch in found.chunks(100) {
+        let ids: Vec<&str> = batch.iter().map(|e| e.entry.id.as_str()).collect();
+        match rule.action {
+            CleanupAction::Trash => client.remove(&ids)?,
+            CleanupAction::Delete => client.delete_permanent(&ids)?,
+        }
+    }
+    Ok(())
+}
+
+/// The TUI's daily run: the trash rules only, as permanent deletes want
+/// confirming. `None` when the last run was under a day ago; otherwise a
+/// log line for each rule that removed something or failed.
+pub fn auto_run(client: &PikPak, rules: &[CleanupRule]) -> Result<Option<Vec<String>>> {
+    let now = super::now_unix();
+    let store = client.store()?;
+    if store.meta(LAST_AUTO_RUN)?.is_some_and(|t| now - t < 86_400) {
+        return Ok(None);
+    }
+    set_meta(store.conn(), LAST_AUTO_RUN, now)?;
+    drop(store);
+
+    let mut log = Vec::new();
+    for rule in rules.iter().filter(|r| r.action == CleanupAction::Trash) {
+        let result = expired(client, rule, now).and_then(|found| {
+            apply(client, rule, &found)?;
+            Ok(found)
+        });
+        match result {
+            Ok(found) if found.is_empty() => {}
+            Ok(found) => log.push(format!(
+                "Cleanup: trashed {} item(s) older than {} days from {}",
+                found.len(),
+                rule.older_than_days,
+                rule.path
+            )),
+            Err(e) => log.push(format!("Cleanup of {} failed: {e:#}", rule.path)),
+        }
+    }
+    Ok(Some(log))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_old_matching_items_expire() {
+        let rule = CleanupRule {
+            path: "/Offline".into(),
+            older_than_days: 30,
+            pattern: Some("*.mkv".into()),
+            recursive: false,
+            action: CleanupAction::Trash,
+        };
+        // 2026-03-01T00:00:00Z
+        let cutoff = 1_772_323_200;
+        let old = Entry::file("Old.MKV")
+            .with_size(1)
+            .with_time("2026-02-01T08:00:00.000+08:00");
+        assert_eq!(expires(&rule, &old, cutoff), Some(1_769_904_000));
+        assert_eq!(
+            expires(
+                &rule,
+                &Entry::file("new.mkv")
+                    .with_size(1)
+                    .with_time("2026-03-02T00:00:00Z"),
+                cutoff
+            ),
+            None
+        );
+        assert_eq!(
+            expires(
+                &rule,
+                &Entry::file("old.txt")
+                    .with_size(1)
+                    .with_time("2026-02-01T00:00:00Z"),
+                cutoff
+            ),
+            None
+        );
+        assert_eq!(
+            expires(
+                &rule,
+                &Entry::file("odd.mkv").with_size(1).with_time(""),
+                cutoff
+            ),
+            None
+        );
+    }
+}
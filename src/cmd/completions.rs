@@ -66,27 +66,43 @@ _pikpaktui() {
     local -a commands
     commands=(
         'ls:List files (colored grid; -l for long)'
+        'tree:Show a folder as a tree'
         'mv:Move file(s) (-t for batch)'
         'cp:Copy file(s) (-t for batch)'
         'rename:Rename a file or folder'
         'rm:Remove to trash (-r folder, -f permanent)'
         'mkdir:Create folder (-p recursive)'
+        'touch:Create empty files'
+        'write:Create a small text file'
         'download:Download a file (-o output path)'
+        'sync:Mirror a remote folder locally'
+        'backup:Upload new and changed local files'
         'upload:Upload file(s) (-t for batch)'
         'share:Share file(s) as PikPak links'
         'offline:Cloud download a URL or magnet link'
         'tasks:Manage offline download tasks'
+        'queue:Export or import the download queue'
         'star:Star files'
         'unstar:Unstar files'
         'starred:List starred files'
+        'note:Notes and labels on files'
         'events:Recent file events'
         'trash:List trashed files'
         'untrash:Restore files from trash'
+        'cleanup:Trash old files by the cleanup rules'
         'info:Show detailed file/folder info'
         'cat:Preview text file contents'
+        'open:Open in the PikPak web UI'
         'play:Play video with external player'
         'quota:Show storage quota'
         'vip:Show VIP & account info'
+        'check:Alert on low storage, bandwidth or VIP'
+        'du:Size of each subfolder'
+        'index:Local index of file hashes'
+        'login:Log in and save credentials'
+        'logout:Forget the saved session and credentials'
+        'import-rclone:Import the login of an rclone PikPak remote'
+        'export-rclone:Write the current login into an rclone remote'
         'completions:Generate shell completions'
         'help:Show help message'
         'version:Show version'
@@ -99,9 +115,37 @@ _pikpaktui() {
 
     local cmd="${words[2]}"
     case "$cmd" in
-        ls)
+        quota|vip)
+            compadd -- '-J' '--json' '--plain'
+            ;;
+        cleanup)
+            compadd -- '-n' '--dry-run' '-y' '--yes' '-J' '--json' '--plain'
+            ;;
+        check)
+            compadd -- '--notify' '--storage' '--bandwidth' '--vip-days' '-J' '--json' '--plain'
+            ;;
+        events)
+            compadd -- '-J' '--json' '--plain' '--since' '--type' '-a' '--all' '-n' '--limit'
+            ;;
+        du)
+            if [[ "${words[CURRENT]}" == -* ]]; then
+                compadd -- '-a' '--all' '-J' '--json' '--plain'
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        index)
+            if [[ "${words[CURRENT]}" == -* ]]; then
+                compadd -- '--interval' '--restart' '-J' '--json' '--plain'
+            elif (( CURRENT == 3 )); then
+                compadd -- status update dupes find diff
+            else
+                _files
+            fi
+            ;;
+        ls|tree)
             if [[ "${words[CURRENT]}" == -* ]]; then
-                compadd -- '-l' '--long' '-s' '--sort' '-r' '--reverse'
+                compadd -- '-l' '--long' '-J' '--json' '--plain' '-s' '--sort' '-r' '--reverse' '--tree' '--depth' '--dirs-only' '--pattern' '--du'
             elif [[ "${words[CURRENT-1]}" == "-s" ]] || [[ "${words[CURRENT-1]}" == "--sort" ]]; then
                 compadd -- 'name' 'size' 'created' 'type' 'extension' 'none'
             else
@@ -110,7 +154,7 @@ _pikpaktui() {
             ;;
         mv|cp)
             if [[ "${words[CURRENT]}" == -* ]]; then
-                compadd -- '-t'
+                compadd -- '-t' '--parents'
             elif [[ "${words[CURRENT-1]}" == "-t" ]]; then
                 _pikpaktui_cloud_path
             else
@@ -124,50 +168,98 @@ _pikpaktui() {
             ;;
         rm)
             if [[ "${words[CURRENT]}" == -* ]]; then
-                compadd -- '-r' '-f' '-rf' '-fr'
+                compadd -- '-r' '--recursive' '-f' '--permanent' '-y' '--yes' '-n' '--dry-run' '-rf' '-fr'
             else
                 _pikpaktui_cloud_path
             fi
             ;;
         mkdir)
             if [[ "${words[CURRENT]}" == -* ]]; then
-                compadd -- '-p'
+                compadd -- '-p' '--parents'
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        touch)
+            if [[ "${words[CURRENT]}" == -* ]]; then
+                compadd -- '-n' '--dry-run'
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        write)
+            if [[ "${words[CURRENT]}" == -* ]]; then
+                compadd -- '--from' '-f' '--force' '-n' '--dry-run'
+            elif [[ "${words[CURRENT-1]}" == "--from" ]]; then
+                _files
             else
                 _pikpaktui_cloud_path
             fi
             ;;
         download)
             if [[ "${words[CURRENT]}" == -* ]]; then
-                compadd -- '-o'
+                compadd -- '-o' '--stdout'
             elif [[ "${words[CURRENT-1]}" == "-o" ]]; then
                 _files
             else
                 _pikpaktui_cloud_path
             fi
             ;;
+        sync)
+            if [[ "${words[CURRENT]}" == -* ]]; then
+                compadd -- '-n' '--dry-run' '--delete'
+            elif (( CURRENT > 3 )) && [[ "${words[CURRENT-1]}" != -* ]]; then
+                _files -/
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        backup)
+            if [[ "${words[CURRENT]}" == -* ]]; then
+                compadd -- '-n' '--dry-run' '--include' '--exclude'
+            elif [[ "${words[CURRENT-1]}" == --include || "${words[CURRENT-1]}" == --exclude ]]; then
+                return
+            elif (( CURRENT > 3 )) && [[ "${words[CURRENT-1]}" != -* ]]; then
+                _pikpaktui_cloud_path
+            else
+                _files -/
+            fi
+            ;;
         upload)
             if [[ "${words[CURRENT]}" == -* ]]; then
-                compadd -- '-t'
+                compadd -- '-t' '--parents'
             elif [[ "${words[CURRENT-1]}" == "-t" ]]; then
                 _pikpaktui_cloud_path
             else
                 _files
             fi
             ;;
+        import-rclone|export-rclone)
+            if [[ "${words[CURRENT]}" == -* ]]; then
+                compadd -- '--remote'
+            elif [[ "${words[CURRENT-1]}" != "--remote" ]]; then
+                _files
+            fi
+            ;;
         share)
             if [[ "${words[CURRENT]}" == -* ]]; then
-                compadd -- '-o'
+                compadd -- '-o' '-p' '--password' '-d' '--days' '--expires' '-l' '-S' '-D' '-J' '--json' '--plain'
             elif [[ "${words[CURRENT-1]}" == "-o" ]]; then
                 _files
             else
+                (( CURRENT == 3 )) && compadd -- create list import revoke
                 _pikpaktui_cloud_path
             fi
             ;;
         offline)
             if [[ "${words[CURRENT]}" == -* ]]; then
-                compadd -- '-t' '--to' '-n' '--name'
+                compadd -- '-t' '--to' '-n' '--name' '-b' '--batch' '--torrent' '--dry-run'
             elif [[ "${words[CURRENT-1]}" == "-t" ]] || [[ "${words[CURRENT-1]}" == "--to" ]]; then
                 _pikpaktui_cloud_path
+            elif [[ "${words[CURRENT-1]}" == "-b" ]] || [[ "${words[CURRENT-1]}" == "--batch" ]]; then
+                _files
+            elif [[ "${words[CURRENT-1]}" == "--torrent" ]]; then
+                _files -g '*.torrent'
             fi
             ;;
         tasks)
@@ -179,13 +271,57 @@ _pikpaktui() {
                     'retry:Retry a failed task'
                     'delete:Delete task(s)'
                     'rm:Delete task(s)'
+                    'stats:Transfer totals per day or week'
                 )
                 _describe -t subcmds 'tasks subcommand' subcmds
+                compadd -- '--auto-fetch'
+            elif [[ "${words[CURRENT-1]}" == "--auto-fetch" ]]; then
+                _files -/
+            elif [[ "${words[CURRENT]}" == -* ]] && (( ${words[(I)--auto-fetch]} )); then
+                compadd -- '--pattern' '--interval' '--once' '--metrics'
             fi
             ;;
-        star|unstar|info|cat|play)
+        queue)
+            if (( CURRENT == 3 )); then
+                local -a subcmds
+                subcmds=(
+                    'list:Downloads waiting in the queue'
+                    'export:Write the queue as JSON'
+                    'import:Queue downloads from a JSON file'
+                )
+                _describe -t subcmds 'queue subcommand' subcmds
+            elif [[ "${words[CURRENT-1]}" == "-t" ]] || [[ "${words[CURRENT-1]}" == "--to" ]]; then
+                _files -/
+            elif [[ "${words[CURRENT]}" == -* ]]; then
+                compadd -- '-o' '--output' '-t' '--to'
+            else
+                _files
+            fi
+            ;;
+        play)
+            if [[ "${words[CURRENT]}" == -* ]]; then
+                compadd -- '-q' '--quality' '--list-streams' '--player-args'
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        cat)
+            if [[ "${words[CURRENT]}" == -* ]]; then
+                compadd -- '--raw'
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        star|unstar|info|note)
             _pikpaktui_cloud_path
             ;;
+        open)
+            if [[ "${words[CURRENT]}" == -* ]]; then
+                compadd -- '-p' '--print'
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
         completions)
             if (( CURRENT == 3 )); then
                 local -a shells
@@ -256,9 +392,9 @@ _pikpaktui() {
     local cmd="${COMP_WORDS[1]}"
     COMPREPLY=()
 
-    local commands="ls mv cp rename rm mkdir download upload share offline tasks \
-star unstar starred events trash untrash info link cat play quota vip login \
-update completions help version"
+    local commands="ls tree mv cp rename rm mkdir touch write download sync upload backup share offline tasks queue \
+star unstar starred note events trash untrash cleanup info link cat open play quota vip check du index login logout \
+import-rclone export-rclone update completions help version"
 
     if [[ ${COMP_CWORD} -eq 1 ]]; then
         COMPREPLY=($(compgen -W "$commands" -- "$cur"))
@@ -266,9 +402,37 @@ update completions help version"
     fi
 
     case "$cmd" in
-        ls)
+        quota|vip)
+            COMPREPLY=($(compgen -W "-J --json --plain" -- "$cur"))
+            ;;
+        cleanup)
+            COMPREPLY=($(compgen -W "-n --dry-run -y --yes -J --json --plain" -- "$cur"))
+            ;;
+        check)
+            COMPREPLY=($(compgen -W "--notify --storage --bandwidth --vip-days -J --json --plain" -- "$cur"))
+            ;;
+        events)
+            COMPREPLY=($(compgen -W "-J --json --plain --since --type -a --all -n --limit" -- "$cur"))
+            ;;
+        du)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "-a --all -J --json --plain" -- "$cur"))
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        index)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "--interval --restart -J --json --plain" -- "$cur"))
+            elif [[ ${COMP_CWORD} -eq 2 ]]; then
+                COMPREPLY=($(compgen -W "status update dupes find diff" -- "$cur"))
+            else
+                COMPREPLY=($(compgen -f -- "$cur"))
+            fi
+            ;;
+        ls|tree)
             if [[ "$cur" == -* ]]; then
-                COMPREPLY=($(compgen -W "-l --long -J --json -s --sort -r --reverse --tree --depth" -- "$cur"))
+                COMPREPLY=($(compgen -W "-l --long -J --json --plain -s --sort -r --reverse --tree --depth --dirs-only --pattern --du" -- "$cur"))
             elif [[ "$prev" == "-s" ]] || [[ "$prev" == "--sort" ]]; then
                 COMPREPLY=($(compgen -W "name size created type extension none" -- "$cur"))
             else
@@ -277,7 +441,7 @@ update completions help version"
             ;;
         mv|cp)
             if [[ "$cur" == -* ]]; then
-                COMPREPLY=($(compgen -W "-t -n --dry-run" -- "$cur"))
+                COMPREPLY=($(compgen -W "-t -n --dry-run --parents" -- "$cur"))
             else
                 _pikpaktui_cloud_path
             fi
@@ -287,58 +451,139 @@ update completions help version"
             ;;
         rm)
             if [[ "$cur" == -* ]]; then
-                COMPREPLY=($(compgen -W "-r --recursive -f --force -rf -fr" -- "$cur"))
+                COMPREPLY=($(compgen -W "-r --recursive -f --force --permanent -y --yes -n --dry-run -rf -fr" -- "$cur"))
             else
                 _pikpaktui_cloud_path
             fi
             ;;
         mkdir)
             if [[ "$cur" == -* ]]; then
-                COMPREPLY=($(compgen -W "-p -n --dry-run" -- "$cur"))
+                COMPREPLY=($(compgen -W "-p --parents -n --dry-run" -- "$cur"))
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        touch)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "-n --dry-run" -- "$cur"))
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        write)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "--from -f --force -n --dry-run" -- "$cur"))
+            elif [[ "$prev" == "--from" ]]; then
+                COMPREPLY=($(compgen -f -- "$cur"))
             else
                 _pikpaktui_cloud_path
             fi
             ;;
         download)
             if [[ "$cur" == -* ]]; then
-                COMPREPLY=($(compgen -W "-o --output -t -j --jobs -n --dry-run" -- "$cur"))
+                COMPREPLY=($(compgen -W "-o --output -t -j --jobs -n --dry-run --stdout" -- "$cur"))
             elif [[ "$prev" == "-o" ]] || [[ "$prev" == "--output" ]]; then
                 COMPREPLY=($(compgen -f -- "$cur"))
             else
                 _pikpaktui_cloud_path
             fi
             ;;
+        sync)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "-n --dry-run --delete" -- "$cur"))
+            elif [[ ${COMP_CWORD} -gt 2 ]] && [[ "$prev" != -* ]]; then
+                COMPREPLY=($(compgen -d -- "$cur"))
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        backup)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "-n --dry-run --include --exclude" -- "$cur"))
+            elif [[ "$prev" == "--include" ]] || [[ "$prev" == "--exclude" ]]; then
+                return
+            elif [[ ${COMP_CWORD} -gt 2 ]] && [[ "$prev" != -* ]]; then
+                _pikpaktui_cloud_path
+            else
+                COMPREPLY=($(compgen -d -- "$cur"))
+            fi
+            ;;
         upload)
             if [[ "$cur" == -* ]]; then
-                COMPREPLY=($(compgen -W "-t -n --dry-run" -- "$cur"))
+                COMPREPLY=($(compgen -W "-t -n --dry-run -P --provenance --parents" -- "$cur"))
             elif [[ "$prev" == "-t" ]]; then
                 _pikpaktui_cloud_path
             else
                 COMPREPLY=($(compgen -f -- "$cur"))
             fi
             ;;
+        import-rclone|export-rclone)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "--remote" -- "$cur"))
+            elif [[ "$prev" != "--remote" ]]; then
+                COMPREPLY=($(compgen -f -- "$cur"))
+            fi
+            ;;
         share)
             if [[ "$cur" == -* ]]; then
-                COMPREPLY=($(compgen -W "-p --password -d --days -o -l -S -D -J --json -n --dry-run" -- "$cur"))
-            elif [[ "$prev" == "-t" ]]; then
-                _pikpaktui_cloud_path
+                COMPREPLY=($(compgen -W "-p --password -d --days --expires -o -l -S -D -J --json --plain -n --dry-run" -- "$cur"))
+            elif [[ ${COMP_CWORD} -eq 2 && "$cur" != /* ]]; then
+                COMPREPLY=($(compgen -W "create list import revoke" -- "$cur"))
             else
                 _pikpaktui_cloud_path
             fi
             ;;
         offline)
             if [[ "$cur" == -* ]]; then
-                COMPREPLY=($(compgen -W "-t --to -n --dry-run" -- "$cur"))
+                COMPREPLY=($(compgen -W "-t --to -n --dry-run --name -b --batch --torrent" -- "$cur"))
             elif [[ "$prev" == "-t" ]] || [[ "$prev" == "--to" ]]; then
                 _pikpaktui_cloud_path
+            elif [[ "$prev" == "-b" ]] || [[ "$prev" == "--batch" ]] || [[ "$prev" == "--torrent" ]]; then
+                COMPREPLY=($(compgen -f -- "$cur"))
             fi
             ;;
         tasks)
+            if [[ "$prev" == "--auto-fetch" ]]; then
+                COMPREPLY=($(compgen -d -- "$cur"))
+            elif [[ ${COMP_CWORD} -eq 2 ]]; then
+                COMPREPLY=($(compgen -W "list ls retry delete rm stats --auto-fetch" -- "$cur"))
+            elif [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "--pattern --interval --once --metrics" -- "$cur"))
+            fi
+            ;;
+        queue)
             if [[ ${COMP_CWORD} -eq 2 ]]; then
-                COMPREPLY=($(compgen -W "list ls retry delete rm" -- "$cur"))
+                COMPREPLY=($(compgen -W "list export import" -- "$cur"))
+            elif [[ "$prev" == "-t" ]] || [[ "$prev" == "--to" ]]; then
+                COMPREPLY=($(compgen -d -- "$cur"))
+            elif [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "-o --output -t --to" -- "$cur"))
+            else
+                COMPREPLY=($(compgen -f -- "$cur"))
+            fi
+            ;;
+        play)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "-q --quality --list-streams --player-args" -- "$cur"))
+            elif [[ "$prev" != "-q" && "$prev" != "--quality" && "$prev" != "--player-args" ]]; then
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        cat)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "--raw" -- "$cur"))
+            else
+                _pikpaktui_cloud_path
             fi
             ;;
-        star|unstar|info|link|cat|play|trash)
+        open)
+            if [[ "$cur" == -* ]]; then
+                COMPREPLY=($(compgen -W "-p --print" -- "$cur"))
+            else
+                _pikpaktui_cloud_path
+            fi
+            ;;
+        star|unstar|info|link|trash|note)
             _pikpaktui_cloud_path
             ;;
         completions)
@@ -382,41 +627,56 @@ end
 
 function __pikpaktui_using_command
     set -l cmd (commandline -opc)
-    test (count $cmd) -ge 2 -a "$cmd[2]" = "$argv[1]"
+    test (count $cmd) -ge 2; and contains -- $cmd[2] $argv
 end
 
 # Disable default file completion for pikpaktui
 complete -c pikpaktui -f
 
 # Top-level commands
-set -l subcommands ls mv cp rename rm mkdir download upload share offline tasks \
-    star unstar starred events trash untrash info link cat play quota vip login \
-    update completions help version
+set -l subcommands ls tree mv cp rename rm mkdir touch write download sync upload backup share offline tasks queue \
+    star unstar starred note events trash untrash cleanup info link cat open play quota vip check du index login logout \
+    import-rclone export-rclone update completions help version
 
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a ls         -d "List files"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a tree       -d "Folder tree"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a mv         -d "Move files"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a cp         -d "Copy files"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a rename     -d "Rename file"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a rm         -d "Remove to trash"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a mkdir      -d "Create folder"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a touch      -d "Create empty files"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a write      -d "Create a text file"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a download   -d "Download files"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a sync       -d "Mirror a folder locally"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a backup     -d "Back up a local folder"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a upload     -d "Upload files"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a share      -d "Share files"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a offline    -d "Cloud download"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a tasks      -d "Manage tasks"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a queue      -d "Export/import the download queue"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a star       -d "Star files"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a unstar     -d "Unstar files"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a starred    -d "List starred"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a note       -d "Notes and labels"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a events     -d "Recent events"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a trash      -d "Trashed files"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a untrash    -d "Restore from trash"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a cleanup    -d "Trash old files by rule"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a info       -d "File info"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a link       -d "Direct download URL"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a open       -d "Open in web UI"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a cat        -d "Preview text file"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a play       -d "Play video"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a quota      -d "Storage quota"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a vip        -d "VIP info"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a check      -d "Quota / VIP alerts"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a du         -d "Folder sizes"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a index      -d "Hash index"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a login      -d "Login"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a logout     -d "Logout"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a import-rclone -d "Import rclone login"
+complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a export-rclone -d "Export login to rclone"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a update     -d "Update binary"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a completions -d "Generate completions"
 complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a help       -d "Show help"
@@ -425,16 +685,107 @@ complete -c pikpaktui -n "not __fish_seen_subcommand_from $subcommands" -a versi
 # completions: shell name
 complete -c pikpaktui -n "__pikpaktui_using_command completions" -a "bash zsh fish powershell"
 
-# ls options
-complete -c pikpaktui -n "__pikpaktui_using_command ls" -s l -l long    -d "Long format"
-complete -c pikpaktui -n "__pikpaktui_using_command ls" -s J -l json    -d "JSON output"
-complete -c pikpaktui -n "__pikpaktui_using_command ls" -s s -l sort    -d "Sort by field" -a "name size created type extension none"
-complete -c pikpaktui -n "__pikpaktui_using_command ls" -s r -l reverse -d "Reverse sort"
-complete -c pikpaktui -n "__pikpaktui_using_command ls" -l tree         -d "Tree view"
-complete -c pikpaktui -n "__pikpaktui_using_command ls" -l depth        -d "Max depth"
+# ls / tree options
+complete -c pikpaktui -n "__pikpaktui_using_command ls tree" -s l -l long    -d "Long format"
+complete -c pikpaktui -n "__pikpaktui_using_command ls tree" -s J -l json    -d "JSON output"
+complete -c pikpaktui -n "__pikpaktui_using_command ls tree" -l plain -d "Tab-separated output"
+complete -c pikpaktui -n "__pikpaktui_using_command ls tree" -s s -l sort    -d "Sort by field" -a "name size created type extension none"
+complete -c pikpaktui -n "__pikpaktui_using_command ls tree" -s r -l reverse -d "Reverse sort"
+complete -c pikpaktui -n "__pikpaktui_using_command ls tree" -l tree         -d "Tree view"
+complete -c pikpaktui -n "__pikpaktui_using_command ls tree" -l depth        -d "Max depth"
+complete -c pikpaktui -n "__pikpaktui_using_command ls tree" -l dirs-only    -d "Tree: folders only"
+complete -c pikpaktui -n "__pikpaktui_using_command ls tree" -l pattern      -d "Tree: only files matching glob"
+complete -c pikpaktui -n "__pikpaktui_using_command ls tree" -l du           -d "Tree: rolled-up folder sizes"
+
+# import-rclone / export-rclone: rclone.conf path
+complete -c pikpaktui -n "__pikpaktui_using_command import-rclone export-rclone" -F
+complete -c pikpaktui -n "__pikpaktui_using_command import-rclone export-rclone" -l remote -d "rclone remote name" -r
 
 # tasks subcommands
-complete -c pikpaktui -n "__pikpaktui_using_command tasks" -a "list ls retry delete rm"
+complete -c pikpaktui -n "__pikpaktui_using_command tasks" -a "list ls retry delete rm stats"
+complete -c pikpaktui -n "__pikpaktui_using_command tasks" -l auto-fetch -r -a "(__fish_complete_directories)" -d "Download completed tasks into dir"
+complete -c pikpaktui -n "__pikpaktui_using_command tasks" -l pattern -r -d "Only fetch matching task names"
+complete -c pikpaktui -n "__pikpaktui_using_command tasks" -l interval -r -d "Seconds between checks"
+complete -c pikpaktui -n "__pikpaktui_using_command tasks" -l once -d "Check once and exit"
+complete -c pikpaktui -n "__pikpaktui_using_command tasks" -l metrics -r -d "Serve Prometheus metrics on addr"
+
+# queue subcommands
+complete -c pikpaktui -n "__pikpaktui_using_command queue" -a "list export import"
+complete -c pikpaktui -n "__pikpaktui_using_command queue" -s o -l output -r -F -d "Write the queue to file"
+complete -c pikpaktui -n "__pikpaktui_using_command queue" -s t -l to -r -a "(__fish_complete_directories)" -d "Download into dir instead"
+complete -c pikpaktui -n "__pikpaktui_using_command queue" -F
+
+# share options
+complete -c pikpaktui -n "__pikpaktui_using_command share" -a create -d "Create a share link"
+complete -c pikpaktui -n "__pikpaktui_using_command share" -a list -d "List your shares"
+complete -c pikpaktui -n "__pikpaktui_using_command share" -a import -d "Save shares listed in a file"
+complete -c pikpaktui -n "__pikpaktui_using_command share" -a revoke -d "Delete shares"
+complete -c pikpaktui -n "__pikpaktui_using_command share" -s p -l password -d "Protect with a password"
+complete -c pikpaktui -n "__pikpaktui_using_command share" -s d -l days -r -d "Expiry in days"
+complete -c pikpaktui -n "__pikpaktui_using_command share" -l expires -r -a "1d 7d 2w never" -d "Expiry (7d, 2w, never)"
+complete -c pikpaktui -n "__pikpaktui_using_command share" -s J -l json -d "JSON output"
+complete -c pikpaktui -n "__pikpaktui_using_command share" -l plain -d "Tab-separated output"
+
+# download / cat: raw bytes to stdout
+complete -c pikpaktui -n "__pikpaktui_using_command download" -l stdout -d "Write the file to stdout"
+complete -c pikpaktui -n "__pikpaktui_using_command cat" -l raw -d "Stream the whole file as-is"
+
+# write options
+complete -c pikpaktui -n "__pikpaktui_using_command write" -l from -r -F -d "Local file or template to copy"
+complete -c pikpaktui -n "__pikpaktui_using_command write" -s f -l force -d "Replace an existing file"
+
+# open options
+complete -c pikpaktui -n "__pikpaktui_using_command open" -s p -l print -d "Print the URL instead"
+
+# sync options
+complete -c pikpaktui -n "__pikpaktui_using_command sync" -s n -l dry-run -d "Print the plan only"
+complete -c pikpaktui -n "__pikpaktui_using_command sync" -l delete -d "Remove extra local files"
+
+# backup options
+complete -c pikpaktui -n "__pikpaktui_using_command backup" -s n -l dry-run -d "Print the plan only"
+complete -c pikpaktui -n "__pikpaktui_using_command backup" -l include -r -d "Only back up matching files"
+complete -c pikpaktui -n "__pikpaktui_using_command backup" -l exclude -r -d "Skip matching files and folders"
+
+# du options
+complete -c pikpaktui -n "__pikpaktui_using_command du" -s a -l all  -d "List files individually"
+complete -c pikpaktui -n "__pikpaktui_using_command du" -s J -l json -d "JSON output"
+complete -c pikpaktui -n "__pikpaktui_using_command du" -l plain -d "Tab-separated output"
+
+# cleanup options
+complete -c pikpaktui -n "__pikpaktui_using_command cleanup" -s n -l dry-run -d "Only show what would go"
+complete -c pikpaktui -n "__pikpaktui_using_command cleanup" -s y -l yes -d "Don't ask before permanent deletes"
+complete -c pikpaktui -n "__pikpaktui_using_command cleanup" -s J -l json -d "JSON output"
+complete -c pikpaktui -n "__pikpaktui_using_command cleanup" -l plain -d "Tab-separated output"
+
+# index options
+complete -c pikpaktui -n "__pikpaktui_using_command index" -a "status update dupes find diff" -d "Index command"
+complete -c pikpaktui -n "__pikpaktui_using_command index" -l interval -r -d "Seconds between folder listings"
+complete -c pikpaktui -n "__pikpaktui_using_command index" -l restart -d "Start the update over"
+complete -c pikpaktui -n "__pikpaktui_using_command index" -s J -l json -d "JSON output"
+complete -c pikpaktui -n "__pikpaktui_using_command index" -l plain -d "Tab-separated output"
+
+# quota / vip options
+complete -c pikpaktui -n "__pikpaktui_using_command quota vip check" -s J -l json -d "JSON output"
+complete -c pikpaktui -n "__pikpaktui_using_command quota vip check" -l plain -d "Tab-separated output"
+
+# events options
+complete -c pikpaktui -n "__pikpaktui_using_command events" -s J -l json  -d "JSON output"
+complete -c pikpaktui -n "__pikpaktui_using_command events" -l plain -d "Tab-separated output"
+complete -c pikpaktui -n "__pikpaktui_using_command events" -l since      -r -d "Only events since (7d, 2026-03-01)"
+complete -c pikpaktui -n "__pikpaktui_using_command events" -l type       -r -d "Only these event types"
+complete -c pikpaktui -n "__pikpaktui_using_command events" -s a -l all   -d "Walk the whole feed"
+complete -c pikpaktui -n "__pikpaktui_using_command events" -s n -l limit -r -d "Max results"
+
+# check options
+complete -c pikpaktui -n "__pikpaktui_using_command check" -l notify    -d "Desktop notification"
+complete -c pikpaktui -n "__pikpaktui_using_command check" -l storage   -r -d "Storage percent to alert at"
+complete -c pikpaktui -n "__pikpaktui_using_command check" -l bandwidth -r -d "Allowance percent to alert at"
+complete -c pikpaktui -n "__pikpaktui_using_command check" -l vip-days  -r -d "Days before VIP expiry"
+
+# play options
+complete -c pikpaktui -n "__pikpaktui_using_command play" -s q -l quality -r -d "Stream (720, 1080, original, number)"
+complete -c pikpaktui -n "__pikpaktui_using_command play" -l list-streams -d "List streams and exit"
+complete -c pikpaktui -n "__pikpaktui_using_command play" -l player-args -r -d "Extra player arguments"
 "##;
 
 const POWERSHELL_COMPLETION: &str = r##"# PowerShell completion for pikpaktui - PikPak cloud storage CLI/TUI
@@ -472,9 +823,9 @@ Register-ArgumentCompleter -Native -CommandName @('pikpaktui') -ScriptBlock {
     }
 
     $allCommands = @(
-        'ls','mv','cp','rename','rm','mkdir','download','upload','share',
-        'offline','tasks','star','unstar','starred','events','trash','untrash',
-        'info','link','cat','play','quota','vip','login','update','completions',
+        'ls','tree','mv','cp','rename','rm','mkdir','touch','write','download','sync','upload','backup','share',
+        'offline','tasks','queue','star','unstar','starred','note','events','trash','untrash','cleanup',
+        'info','link','cat','open','play','quota','vip','check','du','index','login','logout','import-rclone','export-rclone','update','completions',
         'help','version'
     )
 
@@ -495,26 +846,80 @@ Register-ArgumentCompleter -Native -CommandName @('pikpaktui') -ScriptBlock {
                 }
         }
         "tasks" {
-            @('list','ls','retry','delete','rm') |
+            @('list','ls','retry','delete','rm','stats','--auto-fetch','--pattern','--interval','--once','--metrics') |
+                Where-Object { $_ -like "$wordToComplete*" } |
+                ForEach-Object {
+                    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+                }
+        }
+        "queue" {
+            @('list','export','import','-o','--output','-t','--to') |
+                Where-Object { $_ -like "$wordToComplete*" } |
+                ForEach-Object {
+                    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+                }
+        }
+        { $_ -in @('quota','vip') } {
+            @('-J','--json','--plain') | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
+                [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+            }
+        }
+        "events" {
+            @('-J','--json','--plain','--since','--type','-a','--all','-n','--limit') |
+                Where-Object { $_ -like "$wordToComplete*" } |
+                ForEach-Object {
+                    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+                }
+        }
+        "cleanup" {
+            @('-n','--dry-run','-y','--yes','-J','--json','--plain') |
+                Where-Object { $_ -like "$wordToComplete*" } |
+                ForEach-Object {
+                    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+                }
+        }
+        "check" {
+            @('--notify','--storage','--bandwidth','--vip-days','-J','--json','--plain') |
                 Where-Object { $_ -like "$wordToComplete*" } |
                 ForEach-Object {
                     [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
                 }
         }
-        { $_ -in @('ls','mv','cp','rename','rm','mkdir','download','upload',
-                    'share','offline','star','unstar','info','link','cat','play','trash') } {
+        { $_ -in @('import-rclone','export-rclone') } {
+            # Local rclone.conf path: fall back to PowerShell's file completion.
+            if ($wordToComplete.StartsWith('-')) {
+                @('--remote') | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
+                    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+                }
+            }
+        }
+        { $_ -eq 'share' -and $elements.Count -le 3 -and -not $wordToComplete.StartsWith('/') -and -not $wordToComplete.StartsWith('-') } {
+            @('create','list','import','revoke') | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
+                [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+            }
+        }
+        { $_ -in @('ls','tree','du','mv','cp','rename','rm','mkdir','touch','write','download','sync','upload','backup',
+                    'share','offline','star','unstar','info','link','cat','open','play','trash','note') } {
             if ($wordToComplete.StartsWith('-')) {
                 $opts = switch ($command) {
-                    'ls'       { @('-l','--long','-J','--json','-s','--sort','-r','--reverse','--tree','--depth') }
-                    'mv'       { @('-t','-n','--dry-run') }
-                    'cp'       { @('-t','-n','--dry-run') }
+                    'du'       { @('-a','--all','-J','--json','--plain') }
+                    'sync'     { @('-n','--dry-run','--delete') }
+                    'backup'   { @('-n','--dry-run','--include','--exclude') }
+                    { $_ -in @('ls','tree') } { @('-l','--long','-J','--json','--plain','-s','--sort','-r','--reverse','--tree','--depth','--dirs-only','--pattern','--du') }
+                    'mv'       { @('-t','-n','--dry-run','--parents') }
+                    'cp'       { @('-t','-n','--dry-run','--parents') }
                     'rename'   { @('-n','--dry-run') }
-                    'rm'       { @('-r','--recursive','-f','--force','-rf','-fr') }
-                    'mkdir'    { @('-p','-n','--dry-run') }
-                    'download' { @('-o','--output','-t','-j','--jobs','-n','--dry-run') }
-                    'upload'   { @('-t','-n','--dry-run') }
-                    'share'    { @('-p','--password','-d','--days','-o','-l','-S','-D','-J','--json','-n','--dry-run') }
-                    'offline'  { @('-t','--to','-n','--dry-run') }
+                    'rm'       { @('-r','--recursive','-f','--force','--permanent','-y','--yes','-n','--dry-run','-rf','-fr') }
+                    'mkdir'    { @('-p','--parents','-n','--dry-run') }
+                    'touch'    { @('-n','--dry-run') }
+                    'write'    { @('--from','-f','--force','-n','--dry-run') }
+                    'download' { @('-o','--output','-t','-j','--jobs','-n','--dry-run','--stdout') }
+                    'cat'      { @('--raw') }
+                    'open'     { @('-p','--print') }
+                    'upload'   { @('-t','-n','--dry-run','-P','--provenance','--parents') }
+                    'share'    { @('-p','--password','-d','--days','--expires','-o','-l','-S','-D','-J','--json','--plain','-n','--dry-run') }
+                    'offline'  { @('-t','--to','-n','--dry-run','--name','-b','--batch','--torrent') }
+                    'play'     { @('-q','--quality','--list-streams','--player-args') }
                     default    { @() }
                 }
                 $opts | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
@@ -592,14 +997,18 @@ mod tests {
             "share:",
             "offline:",
             "tasks:",
+            "queue:",
             "star:",
             "unstar:",
             "starred:",
+            "note:",
             "events:",
             "trash:",
             "untrash:",
+            "cleanup:",
             "info:",
             "cat:",
+            "open:",
             "play:",
             "quota:",
             "vip:",
@@ -659,14 +1068,18 @@ mod tests {
             "share",
             "offline",
             "tasks",
+            "queue",
             "star",
             "unstar",
             "starred",
+            "note",
             "events",
             "trash",
             "untrash",
+            "cleanup",
             "info",
             "cat",
+            "open",
             "play",
             "quota",
             "vip",
@@ -708,14 +1121,18 @@ mod tests {
             "share",
             "offline",
             "tasks",
+            "queue",
             "star",
             "unstar",
             "starred",
+            "note",
             "events",
             "trash",
             "untrash",
+            "cleanup",
             "info",
             "cat",
+            "open",
             "play",
             "quota",
             "vip",
@@ -736,6 +1153,20 @@ mod tests {
         assert!(FISH_COMPLETION.contains("bash zsh fish powershell"));
     }
 
+    // `__fish_seen_subcommand_from` also matches an argument, e.g. a file
+    // named `tree`, so command options key on the command word instead.
+    #[test]
+    fn fish_options_key_on_the_command_word() {
+        for line in FISH_COMPLETION.lines() {
+            assert!(
+                !line.contains("\"__fish_seen_subcommand_from"),
+                "use __pikpaktui_using_command: {line}"
+            );
+        }
+        assert!(FISH_COMPLETION.contains("__pikpaktui_using_command ls tree\""));
+        assert!(FISH_COMPLETION.contains("__pikpaktui_using_command quota vip check\""));
+    }
+
     // ── PowerShell ────────────────────────────────────────────────────────────
 
     #[test]
@@ -767,14 +1198,18 @@ mod tests {
             "'share'",
             "'offline'",
             "'tasks'",
+            "'queue'",
             "'star'",
             "'unstar'",
             "'starred'",
+            "'note'",
             "'events'",
             "'trash'",
             "'untrash'",
+            "'cleanup'",
             "'info'",
             "'cat'",
+            "'open'",
             "'play'",
             "'quota'",
             "'vip'",
@@ -1,16 +1,18 @@
-use crate::pikpak::EntryKind;
+use crate::pikpak::{EntryKind, PikPak};
 use anyhow::{Result, anyhow};
+use std::io::Write as _;
 
 pub fn run(args: &[String]) -> Result<()> {
     if args.is_empty() {
         return Err(anyhow!(
-            "Usage: pikpaktui download [-n] [-j <n>] [-o <output>] <path>\n       pikpaktui download [-n] [-j <n>] -t <local_dir> <path...>\n\nIf <path> is a folder, the entire directory tree is downloaded recursively.\n-j / --jobs <n>  concurrent file downloads (default: 1)"
+            "Usage: pikpaktui download [-n] [-j <n>] [-o <output>] <path>\n       pikpaktui download [-n] [-j <n>] -t <local_dir> <path...>\n       pikpaktui download --stdout <path>\n\nIf <path> is a folder, the entire directory tree is downloaded recursively.\n-j / --jobs <n>  concurrent file downloads (default: 1)"
         ));
     }
 
     let mut output: Option<&str> = None;
     let mut target_dir: Option<&str> = None;
-    let mut dry_run = false;
+    let mut dry_run = super::dry_run();
+    let mut stdout = false;
     let mut jobs: usize = 1;
     let mut paths: Vec<&str> = Vec::new();
     let mut iter = args.iter();
@@ -18,6 +20,7 @@ pub fn run(args: &[String]) -> Result<()> {
     while let Some(arg) = iter.next() {
         match arg.as_str() {
             "-n" | "--dry-run" => dry_run = true,
+            "--stdout" => stdout = true,
             "-j" | "--jobs" => {
                 let val = iter.next().ok_or_else(|| anyhow!("-j requires a number"))?;
                 jobs = val
@@ -52,14 +55,43 @@ pub fn run(args: &[String]) -> Result<()> {
         return Err(anyhow!("no file path specified"));
     }
 
+    if stdout {
+        if target_dir.is_some() || output.is_some() || paths.len() > 1 {
+            return Err(anyhow!("--stdout takes a single file and no -o / -t"));
+        }
+        let client = super::cli_client()?;
+        if dry_run {
+            let entry = remote_file(&client, paths[0])?;
+            eprintln!(
+                "[dry-run] Would write '{}' ({}) to stdout",
+                entry.name,
+                super::format_size(entry.size)
+            );
+            return Ok(());
+        }
+        return to_stdout(&client, paths[0]);
+    }
+
     let client = super::cli_client()?;
 
     if let Some(dir) = target_dir {
         let dir = std::path::Path::new(dir);
+        let mut taken = std::collections::HashSet::new();
         for path in &paths {
-            let (parent, name) = super::split_parent_name(path)?;
+            let (parent, remote_name) = super::split_parent_name(path)?;
             let parent_id = client.resolve_path(&parent)?;
-            let entry = super::find_entry(&client, &parent_id, &name)?;
+            let entry = super::find_entry(&client, &parent_id, &remote_name)?;
+            // Same-named items from different folders mustn't overwrite
+            // each other in the one target dir.
+            let name = crate::pikpak::unique_name(&remote_name, &mut taken);
+            if name != remote_name {
+                super::notice(format!(
+                    "'{}' is already taken in '{}', saving as '{}'",
+                    path,
+                    dir.display(),
+                    name
+                ));
+            }
 
             if dry_run {
                 let kind_tag = if entry.kind == EntryKind::Folder {
@@ -77,7 +109,7 @@ pub fn run(args: &[String]) -> Result<()> {
             }
 
             if entry.kind == EntryKind::Folder {
-                println!(
+                super::status(format!(
                     "Downloading folder '{}' -> '{}'{}",
                     name,
                     dir.display(),
@@ -86,12 +118,12 @@ pub fn run(args: &[String]) -> Result<()> {
                     } else {
                         String::new()
                     }
-                );
+                ));
                 let (ok, failed) = client.download_dir(&entry.id, &name, dir, jobs)?;
-                println!(
+                super::status(format!(
                     "Folder '{}' done: {} file(s) ok, {} failed",
                     name, ok, failed
-                );
+                ));
                 if failed > 0 {
                     return Err(anyhow!("{} file(s) failed in '{}'", failed, name));
                 }
@@ -102,18 +134,22 @@ pub fn run(args: &[String]) -> Result<()> {
                 {
                     std::fs::create_dir_all(parent)?;
                 }
-                eprintln!(
+                super::notice(format!(
                     "{} ({}) downloading...",
                     name,
                     super::format_size(entry.size)
-                );
-                let total = client.download_to(&entry.id, &dest)?;
-                println!(
+                ));
+                let mut progress = super::TransferProgress::new();
+                let total = client.download_to_with_progress(&entry.id, &dest, |done, total| {
+                    progress.update(done, total)
+                })?;
+                drop(progress);
+                super::status(format!(
                     "Downloaded '{}' -> '{}' ({})",
                     name,
                     dest.display(),
                     super::format_size(total)
-                );
+                ));
             }
         }
     } else {
@@ -150,7 +186,7 @@ pub fn run(args: &[String]) -> Result<()> {
                 .file_name()
                 .map(|n| n.to_string_lossy().into_owned())
                 .unwrap_or_else(|| name.clone());
-            println!(
+            super::status(format!(
                 "Downloading folder '{}' -> '{}'{}",
                 name,
                 dest.display(),
@@ -159,12 +195,12 @@ pub fn run(args: &[String]) -> Result<()> {
                 } else {
                     String::new()
                 }
-            );
+            ));
             let (ok, failed) = client.download_dir(&entry.id, &folder_name, &parent_dest, jobs)?;
-            println!(
+            super::status(format!(
                 "Folder '{}' done: {} file(s) ok, {} failed",
                 name, ok, failed
-            );
+            ));
             if failed > 0 {
                 return Err(anyhow!("{} file(s) failed in '{}'", failed, name));
             }
@@ -174,19 +210,58 @@ pub fn run(args: &[String]) -> Result<()> {
             {
                 std::fs::create_dir_all(parent)?;
             }
-            eprintln!(
+            super::notice(format!(
                 "{} ({}) downloading...",
                 name,
                 super::format_size(entry.size)
-            );
-            let total = client.download_to(&entry.id, &dest)?;
-            println!(
+            ));
+            let mut progress = super::TransferProgress::new();
+            let total = client.download_to_with_progress(&entry.id, &dest, |done, total| {
+                progress.update(done, total)
+            })?;
+            drop(progress);
+            super::status(format!(
                 "Downloaded '{}' -> '{}' ({})",
                 name,
                 dest.display(),
                 super::format_size(total)
-            );
+            ));
         }
     }
     Ok(())
 }
+
+fn remote_file(client: &PikPak, path: &str) -> Result<crate::pikpak::Entry> {
+    let (parent, name) = super::split_parent_name(path)?;
+    let parent_id = client.resolve_path(&parent)?;
+    let entry = super::find_entry(client, &parent_id, &name)?;
+    if entry.kind == EntryKind::Folder {
+        return Err(anyhow!(
+            "'{}' is a folder; only files can go to stdout",
+            name
+        ));
+    }
+    Ok(entry)
+}
+
+/// Write the raw bytes of a remote file to stdout, with no size cap and
+/// nothing else on stdout. A reader that stops early (`| head`) isn't an
+/// error.
+pub(super) fn to_stdout(client: &PikPak, path: &str) -> Result<()> {
+    let entry = remote_file(client, path)?;
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    let result = client
+        .download_to_writer(&entry.id, &mut out)
+        .and_then(|_| Ok(out.flush()?));
+    match result {
+        Err(e)
+            if e.chain().any(|c| {
+                c.downcast_ref::<std::io::Error>()
+                    .is_some_and(|io| io.kind() == std::io::ErrorKind::BrokenPipe)
+            }) =>
+        {
+            Ok(())
+        }
+        other => other,
+    }
+}
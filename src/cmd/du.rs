@@ -0,0 +1,181 @@
+use anyhow::{Result, anyhow};
+
+use super::ls::{TreeNode, walk_sizes};
+use crate::pikpak::EntryKind;
+
+const USAGE: &str = "Usage: pikpaktui du [-a|--all] [-J|--json|--plain] [path]";
+
+/// One line of the report: a subfolder, a file (with `--all`), or the
+/// files directly in the folder lumped together.
+#[derive(Debug, PartialEq, Eq)]
+struct DuRow {
+    name: String,
+    folder: bool,
+    size: u64,
+    files: usize,
+}
+
+fn count_files(nodes: &[TreeNode]) -> usize {
+    nodes
+        .iter()
+        .map(|n| match n.entry.kind {
+            EntryKind::Folder => count_files(&n.children),
+            EntryKind::File => 1,
+        })
+        .sum()
+}
+
+/// Rows for the folder's immediate children, largest first.
+fn rows(nodes: &[TreeNode], all: bool) -> Vec<DuRow> {
+    let mut rows = Vec::new();
+    let mut loose = DuRow {
+        name: "(files)".into(),
+        folder: false,
+        size: 0,
+        files: 0,
+    };
+    for node in nodes {
+        match node.entry.kind {
+            EntryKind::Folder => rows.push(DuRow {
+                name: node.entry.name.clone(),
+                folder: true,
+                size: node.entry.size,
+                files: count_files(&node.children),
+            }),
+            EntryKind::File if all => rows.push(DuRow {
+                name: node.entry.name.clone(),
+                folder: false,
+                size: node.entry.size,
+                files: 1,
+            }),
+            EntryKind::File => {
+                loose.size += node.entry.size;
+                loose.files += 1;
+            }
+        }
+    }
+    if loose.files > 0 {
+        rows.push(loose);
+    }
+    rows.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+    rows
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    let mut all = false;
+    let mut path: Option<&str> = None;
+    for arg in args {
+        match arg.as_str() {
+            "-a" | "--all" => all = true,
+            s if s.starts_with('-') => return Err(anyhow!("unknown option for du: {s}\n{USAGE}")),
+            _ if path.is_some() => return Err(anyhow!("du accepts at most one path\n{USAGE}")),
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.unwrap_or("/");
+
+    let client = super::cli_drive()?;
+    let folder_id = client.resolve_path(path)?;
+    let spinner = super::Spinner::new("Walking folders...");
+    let nodes = walk_sizes(&*client, &folder_id)?;
+    drop(spinner);
+
+    let rows = rows(&nodes, all);
+    let total: u64 = rows.iter().map(|r| r.size).sum();
+    let total_files: usize = rows.iter().map(|r| r.files).sum();
+
+    if super::output_format().is_machine() {
+        let items: Vec<_> = rows
+            .iter()
+            .map(|r| {
+                serde_json::json!({
+                    "name": r.name,
+                    "kind": if r.folder { "folder" } else { "file" },
+                    "size": r.size,
+                    "files": r.files,
+                })
+            })
+            .collect();
+        let out = serde_json::json!({
+            "path": path,
+            "size": total,
+            "files": total_files,
+            "items": items,
+        });
+        super::print_report(&out, "items");
+        return Ok(());
+    }
+
+    if rows.is_empty() {
+        println!("(empty)");
+        return Ok(());
+    }
+
+    println!(
+        "\x1b[2m{:>9}  {:>6}  {:>7}  NAME\x1b[0m",
+        "SIZE", "SHARE", "FILES"
+    );
+    for r in &rows {
+        let share = if total > 0 {
+            r.size as f64 * 100.0 / total as f64
+        } else {
+            0.0
+        };
+        let name = if r.folder {
+            format!("\x1b[1;34m{}/\x1b[0m", r.name)
+        } else {
+            r.name.clone()
+        };
+        println!(
+            "\x1b[1;32m{:>9}\x1b[0m  {:>5.1}%  {:>7}  {}",
+            super::format_size(r.size),
+            share,
+            r.files,
+            name
+        );
+    }
+    println!(
+        "\x1b[1m{:>9}\x1b[0m  {:>6}  {:>7}  \x1b[1mtotal\x1b[0m ({})",
+        super::format_size(total),
+        "",
+        total_files,
+        path
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::ls::node;
+    use crate::pikpak::Entry;
+
+    #[test]
+    fn rows_sort_largest_first_and_lump_loose_files() {
+        let nodes = vec![
+            node(Entry::file("a.txt").with_size(5), vec![]),
+            node(
+                Entry::folder("Movies").with_size(300),
+                vec![
+                    node(Entry::file("x.mkv").with_size(100), vec![]),
+                    node(
+                        Entry::folder("Extras").with_size(200),
+                        vec![node(Entry::file("y.mkv").with_size(200), vec![])],
+                    ),
+                ],
+            ),
+            node(Entry::file("b.txt").with_size(10), vec![]),
+            node(Entry::folder("Empty"), vec![]),
+        ];
+
+        let summary = rows(&nodes, false);
+        let names: Vec<&str> = summary.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["Movies", "(files)", "Empty"]);
+        assert_eq!((summary[0].size, summary[0].files), (300, 2));
+        assert_eq!((summary[1].size, summary[1].files), (15, 2));
+
+        let every = rows(&nodes, true);
+        let names: Vec<&str> = every.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["Movies", "b.txt", "a.txt", "Empty"]);
+    }
+}
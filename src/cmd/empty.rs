@@ -5,7 +5,7 @@ use std::io::{self, Write};
 pub fn run(args: &[String]) -> Result<()> {
     let mut all = false;
     let mut force = false;
-    let mut dry_run = false;
+    let mut dry_run = super::dry_run();
     let mut names: Vec<&str> = Vec::new();
 
     for arg in args {
@@ -37,7 +37,7 @@ fn empty_all(client: &PikPak, dry_run: bool, force: bool) -> Result<()> {
     drop(spinner);
 
     if batch.is_empty() {
-        println!("Trash is already empty.");
+        super::status("Trash is already empty.");
         return Ok(());
     }
 
@@ -55,29 +55,14 @@ fn empty_all(client: &PikPak, dry_run: bool, force: bool) -> Result<()> {
         return Ok(());
     }
 
-    // ls_trash is single-page, so drain it: delete a page, re-list, repeat. The
-    // progress guard stops us if a stale listing keeps returning the same ids.
-    let mut deleted = 0usize;
-    let mut batch = batch;
-    loop {
-        let ids: Vec<&str> = batch.iter().map(|e| e.id.as_str()).collect();
-        client.delete_permanent(&ids)?;
-        deleted += ids.len();
-
-        let next = client.ls_trash(500)?;
-        if next.is_empty() {
-            break;
-        }
-        if next.iter().map(|e| &e.id).eq(batch.iter().map(|e| &e.id)) {
-            eprintln!(
-                "warning: trash did not shrink after delete; stopping at {} item(s).",
-                deleted
-            );
-            break;
-        }
-        batch = next;
+    let emptied = client.empty_trash()?;
+    if emptied.stalled {
+        eprintln!(
+            "warning: trash did not shrink after delete; stopping at {} item(s).",
+            emptied.deleted
+        );
     }
-    println!("Permanently deleted {} item(s)", deleted);
+    super::status(format!("Permanently deleted {} item(s)", emptied.deleted));
     Ok(())
 }
 
@@ -98,8 +83,7 @@ fn empty_named(client: &PikPak, names: &[&str], dry_run: bool) -> Result<()> {
         .collect();
 
     if targets.is_empty() {
-        println!("No matching trash items.");
-        return Ok(());
+        return Err(crate::pikpak::not_found("no matching trash items"));
     }
 
     if dry_run {
@@ -115,7 +99,7 @@ fn empty_named(client: &PikPak, names: &[&str], dry_run: bool) -> Result<()> {
 
     let ids: Vec<&str> = targets.iter().map(|e| e.id.as_str()).collect();
     client.delete_permanent(&ids)?;
-    println!("Permanently deleted {} item(s)", ids.len());
+    super::status(format!("Permanently deleted {} item(s)", ids.len()));
     Ok(())
 }
 
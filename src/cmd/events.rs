@@ -1,37 +1,69 @@
-use anyhow::Result;
+use crate::pikpak::EventEntry;
+use anyhow::{Result, anyhow};
 use unicode_width::UnicodeWidthStr;
 
-pub fn run(args: &[String]) -> Result<()> {
-    let client = super::cli_client()?;
-    let config = super::cli_config();
-    let nerd_font = config.cli_nerd_font;
+const USAGE: &str = "Usage: pikpaktui events [-J|--plain] [--since <30m|12h|7d|2w|YYYY-MM-DD>] [--type <t,...>] [--all] [limit]";
 
-    let mut json = false;
-    let mut limit = 20u32;
+pub fn run(args: &[String]) -> Result<()> {
+    let mut limit: Option<usize> = None;
+    let mut all = false;
+    let mut since: Option<i64> = None;
+    let mut types: Vec<String> = Vec::new();
 
-    for arg in args {
-        match arg.as_str() {
-            "-J" | "--json" => json = true,
-            _ => {
-                if let Ok(n) = arg.parse::<u32>() {
-                    limit = n;
-                }
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f, Some(v.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = |name: &str| {
+            inline
+                .clone()
+                .or_else(|| iter.next().cloned())
+                .ok_or_else(|| anyhow!("{name} requires a value"))
+        };
+        match flag {
+            "-a" | "--all" => all = true,
+            "--since" => since = Some(parse_since(&value("--since")?, super::now_unix())?),
+            "--type" => types.extend(
+                value("--type")?
+                    .split(',')
+                    .map(|t| t.trim().to_lowercase())
+                    .filter(|t| !t.is_empty()),
+            ),
+            "-n" | "--limit" => {
+                let v = value("--limit")?;
+                limit = Some(v.parse().map_err(|_| anyhow!("invalid limit '{v}'"))?);
             }
+            _ => match arg.parse::<usize>() {
+                Ok(n) => limit = Some(n),
+                Err(_) => return Err(anyhow!(USAGE)),
+            },
         }
     }
+    // With --since or --all the walk goes as far back as that reaches;
+    // otherwise the newest 20 (or `limit`).
+    let max = match limit {
+        Some(n) => n,
+        None if all || since.is_some() => usize::MAX,
+        None => 20,
+    };
+
+    let client = super::cli_client()?;
+    let config = super::cli_config();
+    let nerd_font = config.cli_nerd_font;
 
     let spinner = super::Spinner::new("Fetching events...");
-    let resp = client.events(limit)?;
+    let events = client.events_matching(since, max, |ev| type_matches(ev, &types))?;
     drop(spinner);
 
-    if json {
-        let out = serde_json::to_string_pretty(&resp.events).unwrap_or_else(|_| "[]".into());
-        println!("{}", out);
+    if super::output_format().is_machine() {
+        super::print_data(&serde_json::to_value(&events)?);
         return Ok(());
     }
 
-    if resp.events.is_empty() {
-        println!("No recent events");
+    if events.is_empty() {
+        println!("No matching events");
         return Ok(());
     }
 
@@ -43,8 +75,7 @@ pub fn run(args: &[String]) -> Result<()> {
         date: String,
     }
 
-    let rows: Vec<Row> = resp
-        .events
+    let rows: Vec<Row> = events
         .iter()
         .map(|ev| {
             // API returns "TYPE_RESTORE", "TYPE_DELETE", etc. — use type_name for display
@@ -125,3 +156,64 @@ pub fn run(args: &[String]) -> Result<()> {
 
     Ok(())
 }
+
+/// Whether `ev` is one of `types`, each matched case-insensitively as part
+/// of the API type (`TYPE_DELETE`) or its display name. No types: all match.
+fn type_matches(ev: &EventEntry, types: &[String]) -> bool {
+    if types.is_empty() {
+        return true;
+    }
+    let raw = ev.event_type.as_deref().unwrap_or("").to_lowercase();
+    let name = ev.type_name.as_deref().unwrap_or("").to_lowercase();
+    types.iter().any(|t| raw.contains(t) || name.contains(t))
+}
+
+/// `--since`: an age (`30m`, `12h`, `7d`, `2w`) counted back from `now`,
+/// or a date (`2026-03-01`, midnight UTC) or full timestamp.
+fn parse_since(s: &str, now: i64) -> Result<i64> {
+    let unit = match s.chars().last() {
+        Some('m') => Some(60),
+        Some('h') => Some(3600),
+        Some('d') => Some(86_400),
+        Some('w') => Some(7 * 86_400),
+        _ => None,
+    };
+    if let Some(unit) = unit
+        && let Ok(n) = s[..s.len() - 1].parse::<i64>()
+    {
+        return Ok(now - n * unit);
+    }
+    let iso = if s.len() == 10 {
+        format!("{s}T00:00:00Z")
+    } else {
+        s.to_string()
+    };
+    crate::pikpak::iso_to_unix(&iso)
+        .ok_or_else(|| anyhow!("invalid --since '{s}': use e.g. 12h, 7d, 2w or 2026-03-01"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn since_takes_ages_and_dates() {
+        let now = 1_000_000;
+        assert_eq!(parse_since("30m", now).unwrap(), now - 1800);
+        assert_eq!(parse_since("7d", now).unwrap(), now - 7 * 86_400);
+        assert_eq!(parse_since("2026-03-01", now).unwrap(), 1_772_323_200);
+        assert!(parse_since("soon", now).is_err());
+    }
+
+    #[test]
+    fn type_filter_matches_raw_type_or_display_name() {
+        let ev: EventEntry = serde_json::from_value(serde_json::json!({
+            "type": "TYPE_DELETE", "type_name": "Moved to trash"
+        }))
+        .unwrap();
+        assert!(type_matches(&ev, &[]));
+        assert!(type_matches(&ev, &["delete".into()]));
+        assert!(type_matches(&ev, &["upload".into(), "trash".into()]));
+        assert!(!type_matches(&ev, &["rename".into()]));
+    }
+}
@@ -0,0 +1,102 @@
+use crate::config::{AppConfig, write_owner_only};
+use crate::rclone;
+use anyhow::{Context, Result, anyhow};
+use std::fs;
+use std::path::PathBuf;
+
+const DEFAULT_REMOTE: &str = "pikpak";
+
+pub fn run(args: &[String]) -> Result<()> {
+    // Per-command --help is handled by the dispatcher in main.rs before run().
+    let mut remote_name: Option<String> = None;
+    let mut path: Option<PathBuf> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--remote" => {
+                i += 1;
+                remote_name = Some(
+                    args.get(i)
+                        .ok_or_else(|| anyhow!("missing value for --remote"))?
+                        .clone(),
+                );
+            }
+            other if other.starts_with('-') => {
+                return Err(anyhow!(
+                    "unknown flag: {other}\nRun `pikpaktui export-rclone --help` for usage."
+                ));
+            }
+            other if path.is_none() => path = Some(PathBuf::from(other)),
+            _ => {
+                return Err(anyhow!(
+                    "too many arguments\nRun `pikpaktui export-rclone --help` for usage."
+                ));
+            }
+        }
+        i += 1;
+    }
+    let name = remote_name.as_deref().unwrap_or(DEFAULT_REMOTE);
+    let path = match path {
+        Some(p) => p,
+        None => rclone::default_config_path()?,
+    };
+
+    // cli_client() logs in again if the stored session has expired, so the
+    // exported token is always current.
+    let client = super::cli_client()?;
+    let session = client
+        .load_session()?
+        .ok_or_else(|| anyhow!("not logged in, please login first"))?;
+    let token = rclone::token_for_session(&session)?;
+
+    let raw = if path.exists() {
+        fs::read_to_string(&path)
+            .with_context(|| format!("failed to read rclone config {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let remotes = rclone::parse_config(&raw)?;
+    if let Some(existing) = remotes.iter().find(|r| r.name == name)
+        && !existing.is_pikpak()
+    {
+        return Err(anyhow!(
+            "remote '{name}' exists and is not a pikpak remote; choose another with --remote"
+        ));
+    }
+
+    let user = AppConfig::load().ok().and_then(|c| c.active_username());
+    let mut values = vec![("type", "pikpak")];
+    if let Some(ref user) = user {
+        values.push(("user", user.as_str()));
+    }
+    values.push(("token", token.as_str()));
+    if !session.device_id.is_empty() {
+        values.push(("device_id", session.device_id.as_str()));
+    }
+    let updated = rclone::upsert_remote(&raw, name, &values);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create dir {}", parent.display()))?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    write_owner_only(&tmp_path, updated.as_bytes())
+        .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path).with_context(|| format!("failed to rename {}", path.display()))?;
+
+    if super::quiet() {
+        return Ok(());
+    }
+    println!(
+        "\x1b[32m✓\x1b[0m Wrote rclone remote \x1b[1m{}\x1b[0m to {}",
+        name,
+        path.display()
+    );
+    println!(
+        "\x1b[2mTry it with `rclone lsd {name}:`. PikPak rotates the refresh token,\n\
+         so after either tool refreshes, re-run export-rclone or import-rclone to resync.\x1b[0m"
+    );
+
+    Ok(())
+}
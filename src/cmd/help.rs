@@ -62,6 +62,23 @@ pub fn run() -> Result<()> {
     println!("{BOLD}OPTIONS:{RESET}");
     println!("  {GREEN}-h{RESET}, {GREEN}--help{RESET}                   Show this help message");
     println!("  {GREEN}-V{RESET}, {GREEN}--version{RESET}                Show version");
+    println!("  {GREEN}--profile{RESET} <name>           Run as another account from login.toml");
+    println!(
+        "  {GREEN}--dry-run{RESET}                  Print what mv/cp/rm/sync/upload/... would do"
+    );
+    println!(
+        "  {GREEN}--color{RESET}=<when>             Colour listings: auto (default), always, never"
+    );
+    println!("  {GREEN}--no-icons{RESET}                 Leave Nerd Font icons out of listings");
+    println!(
+        "  {GREEN}-J{RESET}, {GREEN}--json{RESET}                   Print data as JSON (ls, info, quota, tasks, ...)"
+    );
+    println!("  {GREEN}--plain{RESET}                    Print data as tab-separated text");
+    println!("  {GREEN}--quiet{RESET}                    Only print errors and the data asked for");
+    println!();
+
+    println!("{BOLD}EXIT CODES:{RESET}");
+    println!("  0 ok   1 error   2 not found   3 not logged in   4 network   5 over quota");
     println!();
     println!(
         "{DIM}TIP: Run {RESET}{GREEN}pikpaktui <command> --help{RESET}{DIM} for detailed command help.{RESET}"
@@ -0,0 +1,75 @@
+use crate::pikpak::PikPak;
+use crate::rclone;
+use anyhow::{Context, Result, anyhow};
+use std::path::PathBuf;
+
+pub fn run(args: &[String]) -> Result<()> {
+    // Per-command --help is handled by the dispatcher in main.rs before run().
+    let mut remote_name: Option<String> = None;
+    let mut path: Option<PathBuf> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--remote" => {
+                i += 1;
+                remote_name = Some(
+                    args.get(i)
+                        .ok_or_else(|| anyhow!("missing value for --remote"))?
+                        .clone(),
+                );
+            }
+            other if other.starts_with('-') => {
+                return Err(anyhow!(
+                    "unknown flag: {other}\nRun `pikpaktui import-rclone --help` for usage."
+                ));
+            }
+            other if path.is_none() => path = Some(PathBuf::from(other)),
+            _ => {
+                return Err(anyhow!(
+                    "too many arguments\nRun `pikpaktui import-rclone --help` for usage."
+                ));
+            }
+        }
+        i += 1;
+    }
+
+    let path = match path {
+        Some(p) => p,
+        None => rclone::default_config_path()?,
+    };
+    let raw = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read rclone config {}", path.display()))?;
+    let remotes = rclone::parse_config(&raw)?;
+    let remote = rclone::find_pikpak_remote(&remotes, remote_name.as_deref())?;
+    let session = rclone::session_from_remote(remote)?;
+
+    let spinner = super::Spinner::new("Importing session...");
+    let client = PikPak::new()?;
+    client.import_session(&session).map_err(|e| {
+        crate::pikpak::auth_error(format!(
+            "imported token could not be refreshed: {e:#}\nLog in with `pikpaktui login` instead."
+        ))
+    })?;
+    drop(spinner);
+
+    if super::quiet() {
+        return Ok(());
+    }
+    match remote.get("user").filter(|u| !u.is_empty()) {
+        Some(user) => println!(
+            "\x1b[32m✓\x1b[0m Imported rclone remote \x1b[1m{}\x1b[0m ({})",
+            remote.name, user
+        ),
+        None => println!(
+            "\x1b[32m✓\x1b[0m Imported rclone remote \x1b[1m{}\x1b[0m",
+            remote.name
+        ),
+    }
+    println!(
+        "\x1b[2mSession saved; rclone's password is obscured and was not imported,\n\
+         so run `pikpaktui login` if the session ever expires for good.\x1b[0m"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,273 @@
+use anyhow::{Result, anyhow};
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::pikpak::{IndexStep, PikPak, duplicates, pikpak_hash};
+
+const USAGE: &str = "Usage:\n  pikpaktui index [status]\n  pikpaktui index update [--interval <secs>] [--restart]\n  pikpaktui index dupes [-J|--plain]\n  pikpaktui index find <hash|local file> [-J|--plain]\n  pikpaktui index diff <local dir> [-J|--plain]";
+
+pub fn run(args: &[String]) -> Result<()> {
+    let machine = super::output_format().is_machine();
+    let rest: Vec<&String> = args.iter().collect();
+    match rest.first().map(|s| s.as_str()) {
+        None | Some("status") => run_status(machine),
+        Some("update") => {
+            super::refuse_machine_output("index update")?;
+            run_update(&rest[1..])
+        }
+        Some("dupes") => run_dupes(machine),
+        Some("find") => match rest.get(1) {
+            Some(target) => run_find(target, machine),
+            None => Err(anyhow!("index find needs a hash or a local file\n{USAGE}")),
+        },
+        Some("diff") => match rest.get(1) {
+            Some(dir) => run_diff(Path::new(dir), machine),
+            None => Err(anyhow!("index diff needs a local folder\n{USAGE}")),
+        },
+        Some(other) => Err(anyhow!("unknown index command: {other}\n{USAGE}")),
+    }
+}
+
+/// An error saying how to build the index if there's none yet.
+fn require_index(client: &PikPak) -> Result<()> {
+    if client.hash_index_status()?.built.is_none() {
+        return Err(anyhow!(
+            "no hash index yet; build one with `pikpaktui index update` (or `hash_index = true` for the TUI)"
+        ));
+    }
+    Ok(())
+}
+
+fn run_status(machine: bool) -> Result<()> {
+    let client = super::cli_client()?;
+    let status = client.hash_index_status()?;
+    let built = status
+        .built
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64);
+    if machine {
+        let out = serde_json::json!({
+            "files": status.files,
+            "size": status.bytes,
+            "built_at": built,
+            "crawl": status.crawl.map(|(done, pending)| serde_json::json!({
+                "folders_done": done,
+                "folders_pending": pending,
+            })),
+        });
+        super::print_data(&out);
+        return Ok(());
+    }
+    match built {
+        Some(at) => println!(
+            "\x1b[36mIndex:\x1b[0m    {} file(s), {}  \x1b[2mbuilt {}\x1b[0m",
+            status.files,
+            super::format_size(status.bytes),
+            super::format_unix_time(at)
+        ),
+        None => println!("\x1b[36mIndex:\x1b[0m    none yet"),
+    }
+    if let Some((done, pending)) = status.crawl {
+        println!(
+            "\x1b[36mUpdating:\x1b[0m {done} folder(s) listed, {pending} to go (`pikpaktui index update` carries on)"
+        );
+    }
+    Ok(())
+}
+
+fn run_update(args: &[&String]) -> Result<()> {
+    let config = super::cli_config();
+    let mut interval = config.hash_index_interval;
+    let mut restart = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--restart" => restart = true,
+            "--interval" => {
+                interval = iter
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .ok_or_else(|| anyhow!("--interval requires a number of seconds"))?;
+            }
+            other => return Err(anyhow!("unexpected argument: {other}\n{USAGE}")),
+        }
+    }
+
+    let client = super::cli_client()?;
+    if restart {
+        client.restart_hash_index()?;
+    }
+    let pause = Duration::from_secs(interval);
+    loop {
+        match client.hash_index_step()? {
+            IndexStep::Listed {
+                folder,
+                files,
+                pending,
+            } => {
+                super::notice(format!(
+                    "{folder}  \x1b[2m{files} file(s), {pending} folder(s) to go\x1b[0m"
+                ));
+                std::thread::sleep(pause);
+            }
+            IndexStep::Finished { files } => {
+                super::status(format!("Indexed {files} file(s)"));
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn run_dupes(machine: bool) -> Result<()> {
+    let client = super::cli_client()?;
+    require_index(&client)?;
+    let files = client.hash_index()?;
+    let groups = duplicates(&files);
+    if machine {
+        let out: Vec<_> = groups
+            .iter()
+            .map(|g| {
+                serde_json::json!({
+                    "hash": g[0].hash,
+                    "size": g[0].size,
+                    "files": g.iter().map(|f| serde_json::json!({
+                        "id": f.id,
+                        "path": f.path,
+                    })).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        if super::output_format() == super::OutputFormat::Plain {
+            // A row per file; the hash column groups them.
+            let rows: Vec<_> = groups
+                .iter()
+                .flat_map(|g| {
+                    g.iter().map(|f| {
+                        serde_json::json!({
+                            "hash": f.hash,
+                            "size": f.size,
+                            "id": f.id,
+                            "path": f.path,
+                        })
+                    })
+                })
+                .collect();
+            super::print_data(&rows.into());
+        } else {
+            super::print_data(&out.into());
+        }
+        return Ok(());
+    }
+    if groups.is_empty() {
+        println!("No duplicates.");
+        return Ok(());
+    }
+    let mut wasted = 0;
+    for group in &groups {
+        let extra = group[0].size * (group.len() as u64 - 1);
+        wasted += extra;
+        println!(
+            "\x1b[1m{}\x1b[0m \u{00d7} {}  \x1b[2m{} extra\x1b[0m",
+            super::format_size(group[0].size),
+            group.len(),
+            super::format_size(extra)
+        );
+        for file in group {
+            println!("  {}", file.path);
+        }
+    }
+    println!(
+        "{} group(s); {} in extra copies",
+        groups.len(),
+        super::format_size(wasted)
+    );
+    Ok(())
+}
+
+fn run_find(target: &str, machine: bool) -> Result<()> {
+    // A local file is looked up by its hash; anything else is a hash.
+    let hash = if Path::new(target).is_file() {
+        pikpak_hash(Path::new(target))?
+    } else {
+        target.to_string()
+    };
+    let client = super::cli_client()?;
+    require_index(&client)?;
+    let found = client.hash_index_find(&hash)?;
+    if machine {
+        super::print_data(&serde_json::to_value(&found)?);
+        return Ok(());
+    }
+    if found.is_empty() {
+        return Err(crate::pikpak::not_found(format!(
+            "no file with hash {hash} (as of the index)"
+        )));
+    }
+    for file in &found {
+        println!(
+            "{}  \x1b[2m{}\x1b[0m",
+            file.path,
+            super::format_size(file.size)
+        );
+    }
+    Ok(())
+}
+
+/// Local files under `dir` whose content isn't anywhere on the drive.
+fn run_diff(dir: &Path, machine: bool) -> Result<()> {
+    if !dir.is_dir() {
+        return Err(anyhow!("'{}' is not a folder", dir.display()));
+    }
+    let client = super::cli_client()?;
+    require_index(&client)?;
+    let files = client.hash_index()?;
+    let hashes: HashSet<&str> = files.iter().map(|f| f.hash.as_str()).collect();
+    // A size the drive doesn't have at all can't match, so only files of a
+    // size seen there are hashed.
+    let sizes: HashSet<u64> = files.iter().map(|f| f.size).collect();
+
+    let mut missing = Vec::new();
+    let mut present = 0usize;
+    for (rel, is_dir) in super::sync::local_tree(dir)? {
+        if is_dir {
+            continue;
+        }
+        let path = dir.join(&rel);
+        let size = std::fs::metadata(&path)?.len();
+        let found = sizes.contains(&size)
+            && pikpak_hash(&path)
+                .ok()
+                .is_some_and(|h| hashes.contains(h.as_str()));
+        if found {
+            present += 1;
+        } else {
+            missing.push((rel, size));
+        }
+    }
+
+    if machine {
+        let out = serde_json::json!({
+            "on_drive": present,
+            "missing": missing.iter().map(|(rel, size)| serde_json::json!({
+                "path": rel.to_string_lossy(),
+                "size": size,
+            })).collect::<Vec<_>>(),
+        });
+        super::print_report(&out, "missing");
+        return Ok(());
+    }
+    for (rel, size) in &missing {
+        println!(
+            "\x1b[32m+\x1b[0m {}  \x1b[2m{}\x1b[0m",
+            rel.display(),
+            super::format_size(*size)
+        );
+    }
+    println!(
+        "{} file(s) not on the drive, {} already there",
+        missing.len(),
+        present
+    );
+    Ok(())
+}
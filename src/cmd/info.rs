@@ -1,12 +1,10 @@
 use anyhow::{Result, anyhow};
 
 pub fn run(args: &[String]) -> Result<()> {
-    let mut json = false;
     let mut path_arg: Option<&String> = None;
 
     for arg in args {
         match arg.as_str() {
-            "-J" | "--json" => json = true,
             s if s.starts_with('-') && s != "-" => {
                 return Err(anyhow!("unknown option: {s}"));
             }
@@ -18,17 +16,25 @@ pub fn run(args: &[String]) -> Result<()> {
         }
     }
 
-    let path = path_arg.ok_or_else(|| anyhow!("Usage: pikpaktui info [-J|--json] <path>"))?;
+    let path =
+        path_arg.ok_or_else(|| anyhow!("Usage: pikpaktui info [-J|--json|--plain] <path>"))?;
     let client = super::cli_client()?;
 
     let (parent_path, name) = super::split_parent_name(path)?;
     let parent_id = client.resolve_path(&parent_path)?;
     let entry = super::find_entry(&client, &parent_id, &name)?;
     let info = client.file_info(&entry.id)?;
+    let provenance = client.provenance(&entry.id);
 
-    if json {
-        let out = serde_json::to_string_pretty(&info).unwrap_or_else(|_| "{}".into());
-        println!("{}", out);
+    if super::output_format().is_machine() {
+        let mut value = serde_json::to_value(&info).unwrap_or_default();
+        if let (Some(p), Some(obj)) = (&provenance, value.as_object_mut()) {
+            obj.insert(
+                "provenance".into(),
+                serde_json::to_value(p).unwrap_or_default(),
+            );
+        }
+        super::print_data(&value);
         return Ok(());
     }
 
@@ -70,6 +76,20 @@ pub fn run(args: &[String]) -> Result<()> {
         println!("\x1b[36mCreated:\x1b[0m  \x1b[34m{}\x1b[0m", date);
     }
 
+    if let Some(p) = &provenance {
+        println!("\x1b[36mSource:\x1b[0m   {}", p.source_path);
+        if let Some(mtime) = p.mtime {
+            println!(
+                "\x1b[36mMtime:\x1b[0m    \x1b[34m{}\x1b[0m",
+                super::format_unix_time(mtime)
+            );
+        }
+        println!(
+            "\x1b[36mUploaded:\x1b[0m \x1b[34m{}\x1b[0m",
+            super::format_unix_time(p.uploaded_at)
+        );
+    }
+
     if let Some(medias) = &info.medias {
         for media in medias {
             if let Some(video) = &media.video {
@@ -78,26 +98,8 @@ pub fn run(args: &[String]) -> Result<()> {
                     "\x1b[36mMedia:\x1b[0m    {}",
                     media.media_name.as_deref().unwrap_or("-")
                 );
-                if let (Some(w), Some(h)) = (video.width, video.height) {
-                    println!("  \x1b[36mResolution:\x1b[0m {}x{}", w, h);
-                }
-                if let Some(dur) = video.duration {
-                    let total = dur as u64;
-                    let (h, m, s) = (total / 3600, (total % 3600) / 60, total % 60);
-                    if h > 0 {
-                        println!("  \x1b[36mDuration:\x1b[0m   {}:{:02}:{:02}", h, m, s);
-                    } else {
-                        println!("  \x1b[36mDuration:\x1b[0m   {}:{:02}", m, s);
-                    }
-                }
-                if let Some(br) = video.bit_rate {
-                    println!("  \x1b[36mBitrate:\x1b[0m    {} kbps", br / 1000);
-                }
-                if let Some(vc) = &video.video_codec {
-                    println!("  \x1b[36mVideo:\x1b[0m      {}", vc);
-                }
-                if let Some(ac) = &video.audio_codec {
-                    println!("  \x1b[36mAudio:\x1b[0m      {}", ac);
+                for (label, value) in video.details() {
+                    println!("  \x1b[36m{:<11}\x1b[0m {}", format!("{label}:"), value);
                 }
             }
         }
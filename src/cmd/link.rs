@@ -1,14 +1,12 @@
 use anyhow::{Result, anyhow};
 
 pub fn run(args: &[String]) -> Result<()> {
-    let mut json = false;
     let mut media = false;
     let mut copy = false;
     let mut path_arg: Option<&String> = None;
 
     for arg in args {
         match arg.as_str() {
-            "-J" | "--json" => json = true,
             "--media" | "-m" => media = true,
             "--copy" | "-c" => copy = true,
             s if s.starts_with('-') && s != "-" => {
@@ -65,7 +63,7 @@ pub fn run(args: &[String]) -> Result<()> {
         Vec::new()
     };
 
-    if json {
+    if super::output_format().is_machine() {
         let size = info
             .size
             .as_deref()
@@ -87,7 +85,7 @@ pub fn run(args: &[String]) -> Result<()> {
             );
         }
 
-        println!("{}", serde_json::to_string_pretty(&out).unwrap_or_default());
+        super::print_data(&out);
     } else {
         println!("{}", download_url);
 
@@ -98,18 +96,20 @@ pub fn run(args: &[String]) -> Result<()> {
 
     if copy {
         copy_to_clipboard(download_url)?;
-        eprintln!("Copied to clipboard.");
+        super::notice("Copied to clipboard.");
     }
 
     Ok(())
 }
 
-fn copy_to_clipboard(text: &str) -> Result<()> {
+pub(super) fn copy_to_clipboard(text: &str) -> Result<()> {
     use std::io::Write;
     use std::process::{Command, Stdio};
 
     let candidates: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
         &[("pbcopy", &[] as &[&str])]
+    } else if cfg!(windows) {
+        &[("clip", &[] as &[&str])]
     } else {
         &[
             ("wl-copy", &[] as &[&str]),
@@ -132,6 +132,6 @@ fn copy_to_clipboard(text: &str) -> Result<()> {
     }
 
     Err(anyhow!(
-        "no clipboard tool found (need pbcopy on macOS, wl-copy on Wayland, or xclip on X11)"
+        "no clipboard tool found (need pbcopy on macOS, clip on Windows, wl-copy on Wayland, or xclip on X11)"
     ))
 }
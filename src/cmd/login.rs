@@ -1,11 +1,15 @@
 use crate::config::AppConfig;
-use crate::pikpak::PikPak;
-use anyhow::{Result, anyhow};
+use crate::pikpak::{LoginStep, PikPak, auth_error};
+use anyhow::{Context, Result, anyhow};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use std::io::{self, IsTerminal, Read, Write};
 
 pub fn run(args: &[String]) -> Result<()> {
     // Per-command --help is handled by the dispatcher in main.rs before run().
     let mut user: Option<String> = None;
     let mut password: Option<String> = None;
+    let mut token_file: Option<String> = None;
+    let mut browser = false;
 
     let mut i = 0;
     while i < args.len() {
@@ -26,6 +30,15 @@ pub fn run(args: &[String]) -> Result<()> {
                         .clone(),
                 );
             }
+            "--browser" => browser = true,
+            "--token-file" => {
+                i += 1;
+                token_file = Some(
+                    args.get(i)
+                        .ok_or_else(|| anyhow!("missing value for --token-file"))?
+                        .clone(),
+                );
+            }
             other => {
                 return Err(anyhow!(
                     "unknown flag: {other}\nRun `pikpaktui login --help` for usage."
@@ -35,35 +48,201 @@ pub fn run(args: &[String]) -> Result<()> {
         i += 1;
     }
 
-    let user = user
-        .or_else(|| std::env::var("PIKPAK_USER").ok())
-        .ok_or_else(|| {
-            anyhow!(
+    if let Some(path) = token_file {
+        return login_with_token_file(&path);
+    }
+    // Anything not given on the command line or in the environment is asked
+    // for, when there's someone at the terminal to ask.
+    let interactive = io::stdin().is_terminal() && io::stderr().is_terminal();
+    let user = match user.or_else(|| std::env::var("PIKPAK_USER").ok()) {
+        Some(user) => user,
+        None if interactive => prompt("Email: ")?,
+        None => {
+            return Err(anyhow!(
                 "no username provided.\n\
                  Use -u <email> or set the PIKPAK_USER environment variable.\n\
                  Run `pikpaktui login --help` for usage."
-            )
-        })?;
+            ));
+        }
+    };
 
-    let password = password
-        .or_else(|| std::env::var("PIKPAK_PASS").ok())
-        .ok_or_else(|| {
-            anyhow!(
+    let password = match password.or_else(|| std::env::var("PIKPAK_PASS").ok()) {
+        Some(password) => password,
+        None if interactive => prompt_hidden("Password: ")?,
+        None => {
+            return Err(anyhow!(
                 "no password provided.\n\
                  Use -p <password> or set the PIKPAK_PASS environment variable.\n\
                  Run `pikpaktui login --help` for usage."
-            )
-        })?;
+            ));
+        }
+    };
 
-    let spinner = super::Spinner::new("Logging in...");
     let mut client = PikPak::new()?;
-    client.login(&user, &password)?;
-    drop(spinner);
+    let config = super::cli_config();
+    super::configure_network(&mut client, &config)?;
+    let step = if browser {
+        login_in_browser(&mut client, &user, &password)?
+    } else {
+        let _spinner = super::Spinner::new("Logging in...");
+        client.start_login(&user, &password)?
+    };
+    if let LoginStep::NeedsCode(pending) = step {
+        if !interactive {
+            return Err(auth_error(format!(
+                "PikPak sent a verification code to {user}; run `pikpaktui login` at a terminal to enter it"
+            )));
+        }
+        let code = prompt(&format!("Verification code sent to {user}: "))?;
+        let spinner = super::Spinner::new("Checking code...");
+        client.finish_login(&pending, &code)?;
+        drop(spinner);
+    }
 
     AppConfig::save_credentials(&user, &password)?;
 
+    if super::quiet() {
+        return Ok(());
+    }
     println!("\x1b[32m✓\x1b[0m Logged in as \x1b[1m{}\x1b[0m", user);
-    println!("\x1b[2mCredentials saved to login.toml\x1b[0m");
+    let place = if crate::secrets::use_keyring() {
+        "the keyring"
+    } else {
+        "login.toml"
+    };
+    match crate::config::profile() {
+        Some(name) => println!("\x1b[2mCredentials saved to {place} as profile '{name}'\x1b[0m"),
+        None => println!("\x1b[2mCredentials saved to {place}\x1b[0m"),
+    }
+
+    Ok(())
+}
+
+/// `--token-file`: sign in with a refresh token read from `path` (`-` for
+/// stdin), for CI where there's no one to type a password. Only the session
+/// is saved, never credentials.
+fn login_with_token_file(path: &str) -> Result<()> {
+    let raw = if path == "-" {
+        let mut raw = String::new();
+        io::stdin()
+            .read_to_string(&mut raw)
+            .context("failed to read token from stdin")?;
+        raw
+    } else {
+        std::fs::read_to_string(path).with_context(|| format!("failed to read {path}"))?
+    };
+    let refresh_token = refresh_token_from(&raw)?;
+
+    let spinner = super::Spinner::new("Logging in...");
+    let mut client = PikPak::new()?;
+    let config = super::cli_config();
+    super::configure_network(&mut client, &config)?;
+    client.login_with_refresh_token(&refresh_token)?;
+    drop(spinner);
 
+    super::status("\x1b[32m✓\x1b[0m Logged in with refresh token (session saved)");
     Ok(())
 }
+
+/// `--browser`: log in with the password, solving the captcha PikPak asks
+/// for in the browser, which hands it back to a local page.
+fn login_in_browser(client: &mut PikPak, user: &str, password: &str) -> Result<LoginStep> {
+    client.login_in_browser(
+        user,
+        password,
+        crate::pikpak::BROWSER_LOGIN_TIMEOUT,
+        || false,
+        |url| {
+            // Printed even with --quiet: it's the only way in without a
+            // browser to open.
+            eprintln!("Solve the captcha at {url}");
+            if let Err(e) = crate::local_path::open_default(url.as_ref()) {
+                super::notice(format!(
+                    "Couldn't open a browser ({e:#}); open the address above"
+                ));
+            }
+            super::notice("Waiting for the captcha (Ctrl-C to cancel)...");
+        },
+    )
+}
+
+/// The refresh token in a token file: either the bare token, or JSON with a
+/// `refresh_token` field (a saved session or an rclone `token = {...}`).
+fn refresh_token_from(raw: &str) -> Result<String> {
+    let raw = raw.trim();
+    let token = if raw.starts_with('{') {
+        let json: serde_json::Value =
+            serde_json::from_str(raw).context("token file is not valid JSON")?;
+        json.get("refresh_token")
+            .and_then(|t| t.as_str())
+            .ok_or_else(|| anyhow!("token file has no \"refresh_token\""))?
+            .trim()
+            .to_string()
+    } else {
+        raw.to_string()
+    };
+    if token.is_empty() {
+        return Err(anyhow!("token file is empty"));
+    }
+    Ok(token)
+}
+
+fn prompt(label: &str) -> Result<String> {
+    eprint!("{label}");
+    io::stderr().flush()?;
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+/// Read a line without echoing it.
+fn prompt_hidden(label: &str) -> Result<String> {
+    eprint!("{label}");
+    io::stderr().flush()?;
+    crossterm::terminal::enable_raw_mode()?;
+    let result = read_hidden();
+    let _ = crossterm::terminal::disable_raw_mode();
+    eprintln!();
+    result
+}
+
+fn read_hidden() -> Result<String> {
+    let mut line = String::new();
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind == KeyEventKind::Release {
+            continue;
+        }
+        match key.code {
+            KeyCode::Enter => return Ok(line),
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Err(anyhow!("cancelled"));
+            }
+            KeyCode::Esc => return Err(anyhow!("cancelled")),
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            KeyCode::Char(c) => line.push(c),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_files_hold_a_bare_token_or_json() {
+        assert_eq!(refresh_token_from("  abc123\n").unwrap(), "abc123");
+        assert_eq!(
+            refresh_token_from(r#"{"access_token":"x","refresh_token":"r1","expiry":"2026"}"#)
+                .unwrap(),
+            "r1"
+        );
+        assert!(refresh_token_from("\n").is_err());
+        assert!(refresh_token_from(r#"{"access_token":"x"}"#).is_err());
+    }
+}
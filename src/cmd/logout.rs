@@ -0,0 +1,22 @@
+use crate::config::AppConfig;
+use crate::pikpak::PikPak;
+use anyhow::{Result, anyhow};
+
+pub fn run(args: &[String]) -> Result<()> {
+    // Per-command --help is handled by the dispatcher in main.rs before run().
+    if let Some(other) = args.first() {
+        return Err(anyhow!(
+            "unknown flag: {other}\nRun `pikpaktui logout --help` for usage."
+        ));
+    }
+
+    let had_session = PikPak::new()?.logout()?;
+    let had_credentials = AppConfig::forget_credentials()?;
+
+    if had_session || had_credentials {
+        super::status("\x1b[32m✓\x1b[0m Logged out");
+    } else {
+        super::notice("Not logged in");
+    }
+    Ok(())
+}
@@ -1,19 +1,22 @@
 use anyhow::{Result, anyhow};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use crate::config::SortField;
-use crate::pikpak::{EntryKind, PikPak};
+use crate::pikpak::{Backend, Entry, EntryKind};
 
-const USAGE: &str = "Usage: pikpaktui ls [-l|--long] [-J|--json] [-s|--sort=<field>] [-r|--reverse] [--tree] [--depth=N] [path]\n\nSort fields: name, size, created, type, extension, none";
+const USAGE: &str = "Usage: pikpaktui ls [-l|--long] [-J|--json|--plain] [-s|--sort=<field>] [-r|--reverse] [--tree] [--depth=N] [--dirs-only] [--pattern=GLOB] [--du] [path]\n\nSort fields: name, size, created, type, extension, none";
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq, Eq)]
 struct LsArgs {
     path: String,
     long: bool,
-    json: bool,
     sort_field: SortField,
     reverse: bool,
     tree: bool,
     max_depth: Option<usize>,
+    dirs_only: bool,
+    pattern: Option<String>,
+    du: bool,
 }
 
 fn parse_sort_field(s: &str) -> Result<SortField> {
@@ -33,14 +36,17 @@ fn parse_sort_field(s: &str) -> Result<SortField> {
 fn parse_args(args: &[String]) -> Result<LsArgs> {
     let mut path: Option<String> = None;
     let mut long = false;
-    let mut json = false;
     let mut sort_field = SortField::default();
     let mut reverse = false;
     let mut tree = false;
     let mut max_depth: Option<usize> = None;
+    let mut dirs_only = false;
+    let mut pattern: Option<String> = None;
+    let mut du = false;
     let mut options_done = false;
     let mut expect_sort = false;
     let mut expect_depth = false;
+    let mut expect_pattern = false;
 
     for arg in args {
         if expect_sort {
@@ -56,6 +62,11 @@ fn parse_args(args: &[String]) -> Result<LsArgs> {
             expect_depth = false;
             continue;
         }
+        if expect_pattern {
+            pattern = Some(arg.clone());
+            expect_pattern = false;
+            continue;
+        }
 
         if !options_done {
             match arg.as_str() {
@@ -63,10 +74,6 @@ fn parse_args(args: &[String]) -> Result<LsArgs> {
                     long = true;
                     continue;
                 }
-                "-J" | "--json" => {
-                    json = true;
-                    continue;
-                }
                 "-r" | "--reverse" => {
                     reverse = true;
                     continue;
@@ -83,6 +90,18 @@ fn parse_args(args: &[String]) -> Result<LsArgs> {
                     expect_depth = true;
                     continue;
                 }
+                "--dirs-only" => {
+                    dirs_only = true;
+                    continue;
+                }
+                "--pattern" => {
+                    expect_pattern = true;
+                    continue;
+                }
+                "--du" => {
+                    du = true;
+                    continue;
+                }
                 "--" => {
                     options_done = true;
                     continue;
@@ -103,6 +122,10 @@ fn parse_args(args: &[String]) -> Result<LsArgs> {
                     );
                     continue;
                 }
+                _ if arg.starts_with("--pattern=") => {
+                    pattern = Some(arg["--pattern=".len()..].to_string());
+                    continue;
+                }
                 _ if arg.starts_with('-') => {
                     return Err(anyhow!("unknown option for ls: {arg}\n{USAGE}"));
                 }
@@ -122,52 +145,208 @@ fn parse_args(args: &[String]) -> Result<LsArgs> {
     if expect_depth {
         return Err(anyhow!("--depth requires a value\n{USAGE}"));
     }
-    if max_depth.is_some() {
+    if expect_pattern {
+        return Err(anyhow!("--pattern requires a value\n{USAGE}"));
+    }
+    if max_depth.is_some() || dirs_only || pattern.is_some() || du {
         tree = true;
     }
 
     Ok(LsArgs {
         path: path.unwrap_or_else(|| "/".to_string()),
         long,
-        json,
         sort_field,
         reverse,
         tree,
         max_depth,
+        dirs_only,
+        pattern,
+        du,
     })
 }
 
-#[allow(clippy::too_many_arguments)]
-fn print_tree(
-    client: &PikPak,
+/// Folder listings fetched in parallel while walking a tree.
+const TREE_WALKERS: usize = 4;
+
+/// A folder's contents after applying the tree filters.
+pub(super) struct TreeNode {
+    /// With `--du`, a folder's `size` holds its rolled-up subtree size.
+    pub(super) entry: Entry,
+    pub(super) children: Vec<TreeNode>,
+}
+
+/// `entry` with `children` under it, for tests.
+#[cfg(test)]
+pub(super) fn node(entry: Entry, children: Vec<TreeNode>) -> TreeNode {
+    TreeNode { entry, children }
+}
+
+/// The whole tree under `folder_id` with folder sizes rolled up, as `du`
+/// reports it.
+pub(super) fn walk_sizes(client: &dyn Backend, folder_id: &str) -> Result<Vec<TreeNode>> {
+    let args = LsArgs {
+        tree: true,
+        du: true,
+        ..LsArgs::default()
+    };
+    build_tree(client, folder_id, &args, 1, &AtomicUsize::new(TREE_WALKERS))
+}
+
+/// Walk `folder_id` into a filtered tree. `--pattern` and `--du` need the
+/// whole subtree (to prune empty folders / total sizes), so the depth limit
+/// is only applied when printing in that case.
+///
+/// Subfolders are listed on scoped threads while one of the `idle` walker
+/// slots is free, and inline otherwise.
+fn build_tree(
+    client: &dyn Backend,
     folder_id: &str,
-    prefix: &str,
-    sort_field: SortField,
-    reverse: bool,
-    long: bool,
-    nerd_font: bool,
+    args: &LsArgs,
     depth: usize,
-    max_depth: Option<usize>,
-) -> Result<()> {
-    use crate::theme;
+    idle: &AtomicUsize,
+) -> Result<Vec<TreeNode>> {
+    let full_walk = args.du || args.pattern.is_some();
+    if !full_walk && args.max_depth.is_some_and(|d| depth > d) {
+        return Ok(Vec::new());
+    }
 
-    if max_depth.is_some_and(|d| depth > d) {
-        return Ok(());
+    let entries = client.ls(folder_id)?;
+    let walk = |entry: &Entry| build_tree(client, &entry.id, args, depth + 1, idle);
+    let subtrees: Vec<Option<Result<Vec<TreeNode>>>> = std::thread::scope(|s| {
+        let handles: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                let claimed = entry.kind == EntryKind::Folder
+                    && idle
+                        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                        .is_ok();
+                claimed.then(|| {
+                    s.spawn(move || {
+                        let subtree = walk(entry);
+                        idle.fetch_add(1, Ordering::SeqCst);
+                        subtree
+                    })
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .zip(&entries)
+            .map(|(handle, entry)| match handle {
+                Some(h) => Some(
+                    h.join()
+                        .unwrap_or_else(|_| Err(anyhow!("tree walker panicked"))),
+                ),
+                None if entry.kind == EntryKind::Folder => Some(walk(entry)),
+                None => None,
+            })
+            .collect()
+    });
+
+    let mut nodes = Vec::new();
+    for (mut entry, subtree) in entries.into_iter().zip(subtrees) {
+        if let Some(subtree) = subtree {
+            let children = subtree?;
+            if args.pattern.is_some() && children.is_empty() {
+                continue;
+            }
+            if args.du {
+                entry.size = children.iter().map(|c| c.entry.size).sum();
+            }
+            nodes.push(TreeNode { entry, children });
+        } else {
+            let matches = args
+                .pattern
+                .as_deref()
+                .is_none_or(|p| super::glob_match(p, &entry.name));
+            if !matches {
+                continue;
+            }
+            // --dirs-only still counts hidden files towards --du totals.
+            nodes.push(TreeNode {
+                entry,
+                children: Vec::new(),
+            });
+        }
+    }
+
+    let mut entries: Vec<Entry> = nodes.iter().map(|n| n.entry.clone()).collect();
+    crate::config::sort_entries(&mut entries, args.sort_field, args.reverse);
+    let mut sorted = Vec::with_capacity(nodes.len());
+    for e in entries {
+        if let Some(pos) = nodes.iter().position(|n| n.entry.id == e.id) {
+            sorted.push(nodes.swap_remove(pos));
+        }
     }
+    Ok(sorted)
+}
+
+/// The tree as nested JSON entries, folders carrying a `children` array,
+/// with the same depth and `--dirs-only` cut as the printed tree.
+fn tree_json(nodes: &[TreeNode], depth: usize, args: &LsArgs) -> Vec<serde_json::Value> {
+    nodes
+        .iter()
+        .filter(|n| !args.dirs_only || n.entry.kind == EntryKind::Folder)
+        .map(|node| {
+            let mut value = serde_json::to_value(&node.entry).unwrap_or_default();
+            if node.entry.kind == EntryKind::Folder
+                && let Some(obj) = value.as_object_mut()
+            {
+                let children = if args.max_depth.is_some_and(|d| depth >= d) {
+                    Vec::new()
+                } else {
+                    tree_json(&node.children, depth + 1, args)
+                };
+                obj.insert("children".into(), children.into());
+            }
+            value
+        })
+        .collect()
+}
 
-    let mut entries = client.ls(folder_id)?;
-    crate::config::sort_entries(&mut entries, sort_field, reverse);
+/// `tree_json` flattened for `--plain`: one row per entry, with its `path`
+/// under `dir` in place of the `children` nesting.
+fn tree_rows(nodes: Vec<serde_json::Value>, dir: &str, out: &mut Vec<serde_json::Value>) {
+    for mut node in nodes {
+        let children = node.as_object_mut().and_then(|o| o.remove("children"));
+        let path = format!("{dir}/{}", node["name"].as_str().unwrap_or_default());
+        node["path"] = path.clone().into();
+        out.push(node);
+        if let Some(serde_json::Value::Array(children)) = children {
+            tree_rows(children, &path, out);
+        }
+    }
+}
+
+fn print_tree(nodes: &[TreeNode], prefix: &str, depth: usize, args: &LsArgs, nerd_font: bool) {
+    use crate::theme;
 
-    let count = entries.len();
-    for (i, entry) in entries.iter().enumerate() {
+    if args.max_depth.is_some_and(|d| depth > d) {
+        return;
+    }
+
+    let visible: Vec<&TreeNode> = nodes
+        .iter()
+        .filter(|n| !args.dirs_only || n.entry.kind == EntryKind::Folder)
+        .collect();
+    let count = visible.len();
+    for (i, node) in visible.into_iter().enumerate() {
+        let entry = &node.entry;
         let is_last = i + 1 == count;
         let connector = if is_last { "└── " } else { "├── " };
         let cat = theme::categorize(entry);
         let icon = theme::cli_icon(cat, nerd_font);
         let name_display = format!("{}{}", icon, entry.name);
-        let colored_name = theme::cli_colored(&name_display, cat);
+        let mut colored_name = theme::cli_colored(&name_display, cat);
+        if args.du && entry.kind == EntryKind::Folder {
+            colored_name.push(' ');
+            colored_name.push_str(&theme::cli_paint(
+                "2",
+                &format!("({})", super::format_size(entry.size)),
+            ));
+        }
 
-        if long {
+        if args.long {
             println!(
                 "{}{}{}{}",
                 super::long_entry_prefix(entry),
@@ -185,28 +364,23 @@ fn print_tree(
             } else {
                 format!("{}│   ", prefix)
             };
-            print_tree(
-                client,
-                &entry.id,
-                &child_prefix,
-                sort_field,
-                reverse,
-                long,
-                nerd_font,
-                depth + 1,
-                max_depth,
-            )?;
+            print_tree(&node.children, &child_prefix, depth + 1, args, nerd_font);
         }
     }
+}
 
-    Ok(())
+/// `pikpaktui tree ...`: `ls --tree` under its own name.
+pub fn run_tree(args: &[String]) -> Result<()> {
+    let mut ls_args = vec!["--tree".to_string()];
+    ls_args.extend_from_slice(args);
+    run(&ls_args)
 }
 
 pub fn run(args: &[String]) -> Result<()> {
     let parsed = parse_args(args)?;
     let config = super::cli_config();
     let nerd_font = config.cli_nerd_font;
-    let client = super::cli_client()?;
+    let client = super::cli_drive()?;
     let folder_id = client.resolve_path(&parsed.path)?;
 
     if parsed.tree {
@@ -216,26 +390,41 @@ pub fn run(args: &[String]) -> Result<()> {
         } else {
             root_label
         };
-        println!("{}", root_label);
-        print_tree(
-            &client,
-            &folder_id,
-            "",
-            parsed.sort_field,
-            parsed.reverse,
-            parsed.long,
-            nerd_font,
-            1,
-            parsed.max_depth,
-        )?;
+        let idle = AtomicUsize::new(TREE_WALKERS);
+        let nodes = build_tree(&*client, &folder_id, &parsed, 1, &idle)?;
+        match super::output_format() {
+            super::OutputFormat::Human => {}
+            super::OutputFormat::Json => {
+                super::print_data(&tree_json(&nodes, 1, &parsed).into());
+                return Ok(());
+            }
+            super::OutputFormat::Plain => {
+                let mut rows = Vec::new();
+                tree_rows(
+                    tree_json(&nodes, 1, &parsed),
+                    root_label.trim_end_matches('/'),
+                    &mut rows,
+                );
+                super::print_data(&rows.into());
+                return Ok(());
+            }
+        }
+        if parsed.du {
+            let total: u64 = nodes.iter().map(|n| n.entry.size).sum();
+            let total = format!("({})", super::format_size(total));
+            println!("{} {}", root_label, crate::theme::cli_paint("2", &total));
+        } else {
+            println!("{}", root_label);
+        }
+        print_tree(&nodes, "", 1, &parsed, nerd_font);
         return Ok(());
     }
 
     let mut entries = client.ls(&folder_id)?;
     crate::config::sort_entries(&mut entries, parsed.sort_field, parsed.reverse);
 
-    if parsed.json {
-        super::print_entries_json(&entries);
+    if super::output_format().is_machine() {
+        super::print_data(&serde_json::to_value(&entries)?);
         return Ok(());
     }
 
@@ -255,7 +444,7 @@ pub fn run(args: &[String]) -> Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use super::super::format_date;
+    use super::super::{format_date, format_date_with, format_size_in, glob_match};
     use super::{LsArgs, parse_args};
     use crate::config::SortField;
 
@@ -270,11 +459,13 @@ mod tests {
             LsArgs {
                 path: "/".to_string(),
                 long: false,
-                json: false,
                 sort_field: SortField::Name,
                 reverse: false,
                 tree: false,
                 max_depth: None,
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
     }
@@ -286,11 +477,13 @@ mod tests {
             LsArgs {
                 path: "/foo".to_string(),
                 long: true,
-                json: false,
                 sort_field: SortField::Name,
                 reverse: false,
                 tree: false,
                 max_depth: None,
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
         assert_eq!(
@@ -298,11 +491,13 @@ mod tests {
             LsArgs {
                 path: "/foo".to_string(),
                 long: true,
-                json: false,
                 sort_field: SortField::Name,
                 reverse: false,
                 tree: false,
                 max_depth: None,
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
     }
@@ -314,11 +509,13 @@ mod tests {
             LsArgs {
                 path: "/".to_string(),
                 long: false,
-                json: false,
                 sort_field: SortField::Size,
                 reverse: false,
                 tree: false,
                 max_depth: None,
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
         assert_eq!(
@@ -326,11 +523,13 @@ mod tests {
             LsArgs {
                 path: "/".to_string(),
                 long: false,
-                json: false,
                 sort_field: SortField::Created,
                 reverse: false,
                 tree: false,
                 max_depth: None,
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
         assert_eq!(
@@ -338,11 +537,13 @@ mod tests {
             LsArgs {
                 path: "/".to_string(),
                 long: false,
-                json: false,
                 sort_field: SortField::Extension,
                 reverse: false,
                 tree: false,
                 max_depth: None,
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
     }
@@ -354,11 +555,13 @@ mod tests {
             LsArgs {
                 path: "/".to_string(),
                 long: false,
-                json: false,
                 sort_field: SortField::Size,
                 reverse: true,
                 tree: false,
                 max_depth: None,
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
         assert_eq!(
@@ -366,11 +569,13 @@ mod tests {
             LsArgs {
                 path: "/".to_string(),
                 long: false,
-                json: false,
                 sort_field: SortField::Name,
                 reverse: true,
                 tree: false,
                 max_depth: None,
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
     }
@@ -382,11 +587,13 @@ mod tests {
             LsArgs {
                 path: "/Movies".to_string(),
                 long: false,
-                json: false,
                 sort_field: SortField::Name,
                 reverse: false,
                 tree: true,
                 max_depth: None,
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
         assert_eq!(
@@ -394,11 +601,13 @@ mod tests {
             LsArgs {
                 path: "/Movies".to_string(),
                 long: false,
-                json: false,
                 sort_field: SortField::Name,
                 reverse: false,
                 tree: true,
                 max_depth: Some(2),
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
         assert_eq!(
@@ -406,11 +615,13 @@ mod tests {
             LsArgs {
                 path: "/".to_string(),
                 long: false,
-                json: false,
                 sort_field: SortField::Name,
                 reverse: false,
                 tree: true,
                 max_depth: Some(3),
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
         assert_eq!(
@@ -418,15 +629,32 @@ mod tests {
             LsArgs {
                 path: "/".to_string(),
                 long: true,
-                json: false,
                 sort_field: SortField::Name,
                 reverse: false,
                 tree: true,
                 max_depth: None,
+                dirs_only: false,
+                pattern: None,
+                du: false,
             }
         );
     }
 
+    #[test]
+    fn parse_tree_filter_flags_imply_tree() {
+        let parsed = parse_args(&s(&["--pattern", "*.mkv", "--du", "/Movies"])).unwrap();
+        assert!(parsed.tree && parsed.du && !parsed.dirs_only);
+        assert_eq!(parsed.pattern.as_deref(), Some("*.mkv"));
+        assert_eq!(parsed.path, "/Movies");
+
+        let parsed = parse_args(&s(&["--dirs-only", "--pattern=ep*"])).unwrap();
+        assert!(parsed.tree && parsed.dirs_only);
+        assert_eq!(parsed.pattern.as_deref(), Some("ep*"));
+
+        let err = parse_args(&s(&["--pattern"])).unwrap_err();
+        assert!(err.to_string().contains("--pattern requires a value"));
+    }
+
     #[test]
     fn parse_sort_rejects_invalid_field() {
         let err = parse_args(&s(&["--sort=bogus"])).unwrap_err();
@@ -460,32 +688,73 @@ mod tests {
     fn format_date_handles_empty() {
         assert_eq!(format_date(""), "-");
     }
+
+    #[test]
+    fn format_date_with_custom_pattern() {
+        let iso = "2026-01-15T12:30:45.000Z";
+        assert_eq!(
+            format_date_with(iso, "%d/%m/%Y %H:%M:%S"),
+            "15/01/2026 12:30:45"
+        );
+        assert_eq!(format_date_with(iso, "%Y%m%d 100%%"), "20260115 100%");
+        assert_eq!(format_date_with("yesterday", "%Y"), "yesterday");
+    }
+
+    #[test]
+    fn format_size_binary_vs_decimal() {
+        use crate::config::SizeUnits;
+        assert_eq!(format_size_in(512, SizeUnits::Binary), "512 B");
+        assert_eq!(format_size_in(1536, SizeUnits::Binary), "1.5 KiB");
+        assert_eq!(format_size_in(1536, SizeUnits::Decimal), "1.5 KB");
+        assert_eq!(format_size_in(1_000_000, SizeUnits::Decimal), "1.0 MB");
+        assert_eq!(format_size_in(1_000_000, SizeUnits::Binary), "976.6 KiB");
+        assert_eq!(format_size_in(3 << 40, SizeUnits::Binary), "3.0 TiB");
+        assert_eq!(format_size_in(1536, SizeUnits::default()), "1.5 KiB");
+    }
+
+    #[test]
+    fn glob_matches_whole_name() {
+        assert!(glob_match("*.mkv", "Movie.MKV"));
+        assert!(glob_match("ep??.*", "ep01.mp4"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "axxbyyc"));
+        assert!(!glob_match("*.mkv", "movie.mkv.part"));
+        assert!(!glob_match("ep?.mp4", "ep01.mp4"));
+    }
+
+    #[test]
+    fn du_walk_rolls_folder_sizes_up() {
+        let root = std::env::temp_dir().join(format!("pikpaktui-du-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("Show/S01")).unwrap();
+        std::fs::write(root.join("Show/S01/e1.mkv"), [0; 300]).unwrap();
+        std::fs::write(root.join("Show/poster.jpg"), [0; 20]).unwrap();
+        std::fs::write(root.join("notes.txt"), [0; 5]).unwrap();
+
+        let tree = super::walk_sizes(&crate::pikpak::LocalBackend::new(&root), "").unwrap();
+        let sizes: Vec<(&str, u64)> = tree
+            .iter()
+            .map(|n| (n.entry.name.as_str(), n.entry.size))
+            .collect();
+        assert_eq!(sizes, [("Show", 320), ("notes.txt", 5)]);
+        assert_eq!(tree[0].children[0].entry.size, 300);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
 }
 
 #[cfg(test)]
 mod sort_tests {
+    use super::{node, parse_args, tree_json, tree_rows};
     use crate::config::{SortField, sort_entries};
     use crate::pikpak::{Entry, EntryKind};
 
-    fn entry(name: &str, kind: EntryKind, size: u64, created: &str) -> Entry {
-        Entry {
-            id: name.to_string(),
-            name: name.to_string(),
-            kind,
-            size,
-            created_time: created.to_string(),
-            modified_time: String::new(),
-            starred: false,
-            thumbnail_link: None,
-        }
-    }
-
     #[test]
     fn sort_by_name_case_insensitive() {
         let mut entries = vec![
-            entry("Bravo", EntryKind::File, 100, ""),
-            entry("alpha", EntryKind::File, 200, ""),
-            entry("Charlie", EntryKind::File, 50, ""),
+            Entry::file("Bravo").with_size(100),
+            Entry::file("alpha").with_size(200),
+            Entry::file("Charlie").with_size(50),
         ];
         sort_entries(&mut entries, SortField::Name, false);
         let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
@@ -495,10 +764,10 @@ mod sort_tests {
     #[test]
     fn sort_folders_before_files() {
         let mut entries = vec![
-            entry("file_a", EntryKind::File, 100, ""),
-            entry("dir_b", EntryKind::Folder, 0, ""),
-            entry("file_c", EntryKind::File, 200, ""),
-            entry("dir_a", EntryKind::Folder, 0, ""),
+            Entry::file("file_a").with_size(100),
+            Entry::folder("dir_b"),
+            Entry::file("file_c").with_size(200),
+            Entry::folder("dir_a"),
         ];
         sort_entries(&mut entries, SortField::Name, false);
         assert_eq!(entries[0].kind, EntryKind::Folder);
@@ -510,9 +779,9 @@ mod sort_tests {
     #[test]
     fn sort_by_size_largest_first() {
         let mut entries = vec![
-            entry("small", EntryKind::File, 10, ""),
-            entry("big", EntryKind::File, 1000, ""),
-            entry("medium", EntryKind::File, 500, ""),
+            Entry::file("small").with_size(10),
+            Entry::file("big").with_size(1000),
+            Entry::file("medium").with_size(500),
         ];
         sort_entries(&mut entries, SortField::Size, false);
         assert_eq!(entries[0].name, "big");
@@ -523,9 +792,9 @@ mod sort_tests {
     #[test]
     fn sort_by_created_newest_first() {
         let mut entries = vec![
-            entry("old", EntryKind::File, 0, "2024-01-01T00:00:00Z"),
-            entry("new", EntryKind::File, 0, "2026-01-01T00:00:00Z"),
-            entry("mid", EntryKind::File, 0, "2025-06-01T00:00:00Z"),
+            Entry::file("old").with_time("2024-01-01T00:00:00Z"),
+            Entry::file("new").with_time("2026-01-01T00:00:00Z"),
+            Entry::file("mid").with_time("2025-06-01T00:00:00Z"),
         ];
         sort_entries(&mut entries, SortField::Created, false);
         assert_eq!(entries[0].name, "new");
@@ -535,11 +804,7 @@ mod sort_tests {
 
     #[test]
     fn sort_none_preserves_order() {
-        let mut entries = vec![
-            entry("c", EntryKind::File, 0, ""),
-            entry("a", EntryKind::File, 0, ""),
-            entry("b", EntryKind::File, 0, ""),
-        ];
+        let mut entries = vec![Entry::file("c"), Entry::file("a"), Entry::file("b")];
         sort_entries(&mut entries, SortField::None, false);
         let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
         assert_eq!(names, vec!["c", "a", "b"]);
@@ -548,10 +813,10 @@ mod sort_tests {
     #[test]
     fn sort_reverse_within_groups() {
         let mut entries = vec![
-            entry("a", EntryKind::File, 10, ""),
-            entry("b", EntryKind::File, 20, ""),
-            entry("dir_a", EntryKind::Folder, 0, ""),
-            entry("dir_b", EntryKind::Folder, 0, ""),
+            Entry::file("a").with_size(10),
+            Entry::file("b").with_size(20),
+            Entry::folder("dir_a"),
+            Entry::folder("dir_b"),
         ];
         sort_entries(&mut entries, SortField::Name, true);
         assert_eq!(entries[0].name, "dir_b");
@@ -563,13 +828,58 @@ mod sort_tests {
     #[test]
     fn sort_by_extension() {
         let mut entries = vec![
-            entry("file.zip", EntryKind::File, 0, ""),
-            entry("doc.txt", EntryKind::File, 0, ""),
-            entry("pic.jpg", EntryKind::File, 0, ""),
+            Entry::file("file.zip"),
+            Entry::file("doc.txt"),
+            Entry::file("pic.jpg"),
         ];
         sort_entries(&mut entries, SortField::Extension, false);
         assert_eq!(entries[0].name, "pic.jpg");
         assert_eq!(entries[1].name, "doc.txt");
         assert_eq!(entries[2].name, "file.zip");
     }
+
+    #[test]
+    fn tree_json_nests_children_and_honours_depth_and_dirs_only() {
+        let nodes = vec![
+            node(
+                Entry::folder("Movies"),
+                vec![
+                    node(Entry::file("a.mkv"), vec![]),
+                    node(
+                        Entry::folder("Extras"),
+                        vec![node(Entry::file("b.mkv"), vec![])],
+                    ),
+                ],
+            ),
+            node(Entry::file("notes.txt"), vec![]),
+        ];
+
+        let full = tree_json(&nodes, 1, &parse_args(&["--tree".to_string()]).unwrap());
+        assert_eq!(full.len(), 2);
+        assert_eq!(full[0]["children"][1]["children"][0]["name"], "b.mkv");
+        assert!(full[1].get("children").is_none());
+
+        let mut rows = Vec::new();
+        tree_rows(full, "/Media", &mut rows);
+        let paths: Vec<&str> = rows.iter().map(|r| r["path"].as_str().unwrap()).collect();
+        assert_eq!(
+            paths,
+            [
+                "/Media/Movies",
+                "/Media/Movies/a.mkv",
+                "/Media/Movies/Extras",
+                "/Media/Movies/Extras/b.mkv",
+                "/Media/notes.txt"
+            ]
+        );
+        assert!(rows[0].get("children").is_none());
+
+        let cut = tree_json(
+            &nodes,
+            1,
+            &parse_args(&["--depth=1".to_string(), "--dirs-only".to_string()]).unwrap(),
+        );
+        assert_eq!(cut.len(), 1);
+        assert_eq!(cut[0]["children"], serde_json::json!([]));
+    }
 }
@@ -0,0 +1,216 @@
+//! A Prometheus text-format endpoint for `tasks --auto-fetch --metrics`, so
+//! a dashboard can graph what the watcher is doing. Served from a plain
+//! `TcpListener` thread: one small response per scrape needs no HTTP stack.
+
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+#[derive(Default)]
+pub struct Metrics {
+    pub tasks_fetched: AtomicU64,
+    pub tasks_failed: AtomicU64,
+    /// Completed tasks found on the last poll that are still to be fetched.
+    pub queue_depth: AtomicU64,
+    pub download_bytes: AtomicU64,
+    /// Download speed over the last second or so; 0 when idle.
+    pub download_rate: AtomicU64,
+    pub storage_used: AtomicU64,
+    pub storage_limit: AtomicU64,
+    /// Mirror `PikPak::api_errors` and `api_retries`, copied over as the
+    /// watcher runs.
+    pub api_errors: AtomicU64,
+    pub api_retries: AtomicU64,
+    pub last_poll: AtomicU64,
+}
+
+impl Metrics {
+    /// The exposition-format page for one scrape.
+    pub fn render(&self) -> String {
+        let get = |m: &AtomicU64| m.load(Ordering::Relaxed);
+        let metrics: [(&str, &str, &str, u64); 10] = [
+            (
+                "pikpaktui_tasks_fetched_total",
+                "counter",
+                "Offline tasks downloaded",
+                get(&self.tasks_fetched),
+            ),
+            (
+                "pikpaktui_tasks_failed_total",
+                "counter",
+                "Offline tasks whose download failed",
+                get(&self.tasks_failed),
+            ),
+            (
+                "pikpaktui_queue_depth",
+                "gauge",
+                "Completed offline tasks waiting to be downloaded",
+                get(&self.queue_depth),
+            ),
+            (
+                "pikpaktui_download_bytes_total",
+                "counter",
+                "Bytes downloaded",
+                get(&self.download_bytes),
+            ),
+            (
+                "pikpaktui_download_bytes_per_second",
+                "gauge",
+                "Current download speed",
+                get(&self.download_rate),
+            ),
+            (
+                "pikpaktui_storage_used_bytes",
+                "gauge",
+                "Cloud storage in use",
+                get(&self.storage_used),
+            ),
+            (
+                "pikpaktui_storage_limit_bytes",
+                "gauge",
+                "Cloud storage quota",
+                get(&self.storage_limit),
+            ),
+            (
+                "pikpaktui_api_errors_total",
+                "counter",
+                "PikPak API calls that failed",
+                get(&self.api_errors),
+            ),
+            (
+                "pikpaktui_api_retries_total",
+                "counter",
+                "PikPak API calls sent again after a transient failure",
+                get(&self.api_retries),
+            ),
+            (
+                "pikpaktui_last_poll_timestamp_seconds",
+                "gauge",
+                "When the task list was last checked",
+                get(&self.last_poll),
+            ),
+        ];
+        let mut out = String::new();
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            let _ = writeln!(out, "{name} {value}");
+        }
+        out
+    }
+
+    /// A progress callback for one download, feeding `download_bytes` and
+    /// `download_rate`; it takes the `(bytes_on_disk, total)` pairs that
+    /// `PikPak::download_to_with_progress` reports. Dropping it, once the
+    /// download is over, sets the rate back to 0.
+    pub fn download_progress(&self) -> impl FnMut(u64, u64) + '_ {
+        let rate = ZeroOnDrop(&self.download_rate);
+        let mut last: Option<u64> = None;
+        let mut sample = (Instant::now(), 0u64);
+        move |on_disk, _total| {
+            // The first report is what a resume starts from, not new data.
+            let prev = last.replace(on_disk).unwrap_or(on_disk);
+            let delta = on_disk.saturating_sub(prev);
+            self.download_bytes.fetch_add(delta, Ordering::Relaxed);
+            sample.1 += delta;
+            let elapsed = sample.0.elapsed();
+            if elapsed >= Duration::from_secs(1) {
+                let per_sec = sample.1 as f64 / elapsed.as_secs_f64();
+                rate.set(per_sec as u64);
+                sample = (Instant::now(), 0);
+            }
+        }
+    }
+}
+
+/// A gauge that goes back to 0 when this is dropped.
+struct ZeroOnDrop<'a>(&'a AtomicU64);
+
+impl ZeroOnDrop<'_> {
+    fn set(&self, value: u64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ZeroOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.store(0, Ordering::Relaxed);
+    }
+}
+
+/// Listen on `addr` (`127.0.0.1:9464`, or `:9464` for every interface) and
+/// answer `GET /metrics` from a background thread. Returns the bound address.
+pub fn serve(addr: &str, metrics: Arc<Metrics>) -> Result<SocketAddr> {
+    let addr = match addr.strip_prefix(':') {
+        Some(port) => format!("0.0.0.0:{port}"),
+        None => addr.to_string(),
+    };
+    let listener =
+        TcpListener::bind(&addr).with_context(|| format!("can't listen for metrics on {addr}"))?;
+    let bound = listener.local_addr()?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = respond(stream, &metrics);
+        }
+    });
+    Ok(bound)
+}
+
+fn respond(mut stream: TcpStream, metrics: &Metrics) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut buf = [0u8; 1024];
+    let n = stream.read(&mut buf)?;
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let path = request.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if request.starts_with("GET ") && (path == "/metrics" || path == "/") {
+        ("200 OK", metrics.render())
+    } else {
+        ("404 Not Found", "Not found; try /metrics\n".to_string())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_counts_only_new_bytes() {
+        let metrics = Metrics::default();
+        {
+            let mut progress = metrics.download_progress();
+            // Resuming at 100: those bytes were fetched by an earlier run.
+            progress(100, 500);
+            progress(300, 500);
+            progress(500, 500);
+            metrics.download_rate.store(200, Ordering::Relaxed);
+        }
+        assert_eq!(metrics.download_bytes.load(Ordering::Relaxed), 400);
+        // Nothing is downloading any more.
+        assert_eq!(metrics.download_rate.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn serves_metrics_over_http() {
+        let metrics = Arc::new(Metrics::default());
+        metrics.queue_depth.store(3, Ordering::Relaxed);
+        let addr = serve("127.0.0.1:0", Arc::clone(&metrics)).unwrap();
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"GET /metrics HTTP/1.1\r\nHost: x\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("# TYPE pikpaktui_queue_depth gauge\npikpaktui_queue_depth 3\n"));
+    }
+}
@@ -7,19 +7,19 @@ pub fn run(args: &[String]) -> Result<()> {
         ));
     }
 
-    let mut dry_run = false;
+    let mut dry_run = super::dry_run();
     let mut recursive = false;
     let mut rest: Vec<&str> = Vec::new();
 
     for arg in args {
         match arg.as_str() {
             "-n" | "--dry-run" => dry_run = true,
-            "-p" => recursive = true,
+            "-p" | "--parents" => recursive = true,
             _ => rest.push(arg),
         }
     }
 
-    let client = super::cli_client()?;
+    let client = super::cli_drive()?;
 
     if recursive {
         if rest.len() != 1 {
@@ -35,10 +35,8 @@ pub fn run(args: &[String]) -> Result<()> {
             return Err(anyhow!("invalid path"));
         }
 
-        let mut current_id = String::new();
-        let mut created_count = 0u32;
-
         if dry_run {
+            let mut current_id = String::new();
             println!(
                 "[dry-run] Would create folder(s) at '/{}':",
                 segments.join("/")
@@ -66,21 +64,12 @@ pub fn run(args: &[String]) -> Result<()> {
             return Ok(());
         }
 
-        for seg in &segments {
-            let entries = client.ls(&current_id)?;
-            if let Some(existing) = entries.into_iter().find(|e| e.name == *seg) {
-                current_id = existing.id;
-            } else {
-                let entry = client.mkdir(&current_id, seg)?;
-                current_id = entry.id;
-                created_count += 1;
-            }
-        }
-        println!(
+        let (_, created_count) = client.mkdir_p(full_path)?;
+        super::status(format!(
             "Created {} folder(s) at '/{}'",
             created_count,
             segments.join("/")
-        );
+        ));
     } else {
         if rest.len() != 2 {
             return Err(anyhow!(
@@ -1,36 +1,52 @@
+pub mod backup;
 pub mod cat;
+pub mod check;
+pub mod cleanup;
 pub mod complete_path;
 pub mod completions;
 pub mod cp;
 pub mod download;
+pub mod du;
 pub mod empty;
 pub mod events;
+pub mod export_rclone;
 pub mod help;
+pub mod import_rclone;
+pub mod index;
 pub mod info;
 pub mod link;
 pub mod login;
+pub mod logout;
 pub mod ls;
+pub mod metrics;
 pub mod mkdir;
 pub mod mv;
+pub mod note;
 pub mod offline;
+pub mod open;
 pub mod play;
+pub mod queue;
 pub mod quota;
 pub mod rename;
 pub mod rm;
 pub mod share;
 pub mod star;
 pub mod starred;
+pub mod sync;
 pub mod tasks;
+pub mod touch;
 pub mod trash;
 pub mod unstar;
 pub mod untrash;
 pub mod update;
 pub mod upload;
 pub mod vip;
+pub mod write;
 
 use crate::config::AppConfig;
-use crate::pikpak::{self, PikPak};
+use crate::pikpak::{self, Backend, PikPak};
 use anyhow::{Result, anyhow};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 
 const G: &str = "\x1b[32m"; // green
 const D: &str = "\x1b[2m"; // dim
@@ -43,19 +59,26 @@ pub const COMMAND_GROUPS: &[(&str, &[&str])] = &[
     (
         "File Management",
         &[
-            "ls", "mv", "cp", "rename", "rm", "mkdir", "info", "link", "cat",
+            "ls", "tree", "mv", "cp", "rename", "rm", "mkdir", "touch", "write", "info", "link",
+            "cat", "open",
         ],
     ),
     ("Playback", &["play"]),
-    ("Transfer", &["download", "upload", "share"]),
+    (
+        "Transfer",
+        &["download", "sync", "upload", "backup", "share", "queue"],
+    ),
     ("Cloud Download", &["offline", "tasks"]),
-    ("Trash", &["trash", "untrash", "empty"]),
+    ("Trash", &["trash", "untrash", "empty", "cleanup"]),
     (
         "Starred & Activity",
-        &["star", "unstar", "starred", "events"],
+        &["star", "unstar", "starred", "note", "events"],
     ),
-    ("Auth", &["login"]),
-    ("Account", &["quota", "vip"]),
+    (
+        "Auth",
+        &["login", "logout", "import-rclone", "export-rclone"],
+    ),
+    ("Account", &["quota", "vip", "check", "du", "index"]),
     ("Utility", &["update", "completions"]),
 ];
 
@@ -87,14 +110,83 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                 "{B}OPTIONS:{R}\n\
                  {opt}  -l, --long       {d}Long format (id, size, date, name){R}\n\
                  {opt}  -J, --json       {d}Output as JSON{R}\n\
+                 {opt}  --plain          {d}Tab-separated output{R}\n\
                  {opt}  -s, --sort=FIELD {d}Sort by: name, size, created, type, extension, none{R}\n\
                  {opt}  -r, --reverse    {d}Reverse sort order{R}\n\
                  {opt}  --tree           {d}Tree view{R}\n\
                  {opt}  --depth=N        {d}Max tree depth{R}\n\
+                 {opt}  --dirs-only      {d}Tree: show folders only{R}\n\
+                 {opt}  --pattern=GLOB   {d}Tree: only files matching GLOB (e.g. *.mkv){R}\n\
+                 {opt}  --du             {d}Tree: show rolled-up folder sizes{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui ls{R}\n\
                  {ex}  pikpaktui ls -l /Movies{R}\n\
-                 {ex}  pikpaktui ls --tree --depth=2 /{R}\n",
+                 {ex}  pikpaktui ls --tree --depth=2 /{R}\n\
+                 {ex}  pikpaktui ls --du --dirs-only --depth=1 /{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
+        "tree" => (
+            "tree [options] [path]",
+            "Show a folder as a tree (same as ls --tree)",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  --depth=N        {d}Max depth{R}\n\
+                 {opt}  --dirs-only      {d}Show folders only{R}\n\
+                 {opt}  -l, --long       {d}Long format (id, size, date) before each name{R}\n\
+                 {opt}  -J, --json       {d}Nested JSON, folders with a children array{R}\n\
+                 {opt}  --plain          {d}Tab-separated output{R}\n\
+                 {opt}  --pattern=GLOB   {d}Only files matching GLOB{R}\n\
+                 {opt}  --du             {d}Show rolled-up folder sizes{R}\n\
+                 {opt}  -s, --sort=FIELD {d}Sort by: name, size, created, type, extension, none{R}\n\
+                 {opt}  -r, --reverse    {d}Reverse sort order{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui tree --depth=2 /Movies{R}\n\
+                 {ex}  pikpaktui tree --dirs-only -l /{R}\n\
+                 {ex}  pikpaktui tree --json /Photos{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
+        "index" => (
+            "index [status|update|dupes|find|diff]",
+            "Local index of file hashes: duplicates, diffs, lookups",
+            format!(
+                "{B}MODES:{R}\n\
+                 {opt}  index [status]          {d}Size and age of the index{R}\n\
+                 {opt}  index update            {d}Build or refresh it (resumes if interrupted){R}\n\
+                 {opt}  index dupes             {d}Files with the same content{R}\n\
+                 {opt}  index find <hash|file>  {d}Where a hash (or a local file's) is on the drive{R}\n\
+                 {opt}  index diff <dir>        {d}Local files not on the drive{R}\n\
+                 \n{B}OPTIONS:{R}\n\
+                 {opt}  --interval <secs>  {d}update: pause between folder listings{R}\n\
+                 {opt}  --restart          {d}update: start over instead of resuming{R}\n\
+                 {opt}  -J, --json         {d}Output as JSON{R}\n\
+                 {opt}  --plain            {d}Tab-separated output{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui index update{R}\n\
+                 {ex}  pikpaktui index dupes{R}\n\
+                 {ex}  pikpaktui index find ~/Videos/clip.mp4{R}\n\
+                 {ex}  pikpaktui index diff ~/Photos{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
+        "du" => (
+            "du [options] [path]",
+            "Size of each subfolder, largest first",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  -a, --all    {d}List files one by one instead of lumping them{R}\n\
+                 {opt}  -J, --json   {d}Output as JSON{R}\n\
+                 {opt}  --plain      {d}Tab-separated output{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui du{R}\n\
+                 {ex}  pikpaktui du -a /Movies{R}\n",
                 opt = G,
                 d = D,
                 ex = D,
@@ -107,6 +199,7 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                 "{B}OPTIONS:{R}\n\
                  {opt}  -n, --dry-run    {d}Preview without executing{R}\n\
                  {opt}  -t <dst>         {d}Batch mode: move multiple <src> into <dst>{R}\n\
+                 {opt}  --parents        {d}Create <dst> and missing parents if needed{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui mv /file.txt /Archive/{R}\n\
                  {ex}  pikpaktui mv -t /Dest /a.txt /b.txt{R}\n",
@@ -122,6 +215,7 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                 "{B}OPTIONS:{R}\n\
                  {opt}  -n, --dry-run    {d}Preview without executing{R}\n\
                  {opt}  -t <dst>         {d}Batch mode: copy multiple <src> into <dst>{R}\n\
+                 {opt}  --parents        {d}Create <dst> and missing parents if needed{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui cp /file.txt /Backup/{R}\n\
                  {ex}  pikpaktui cp -t /Dest /a.txt /b.txt{R}\n",
@@ -148,11 +242,14 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
             "Remove files or folders",
             format!(
                 "{B}OPTIONS:{R}\n\
-                 {opt}  -r, --recursive  {d}Remove folders recursively{R}\n\
-                 {opt}  -f, --force      {d}Permanently delete (skip trash){R}\n\
+                 {opt}  -r, --recursive    {d}Remove non-empty folders with their contents{R}\n\
+                 {opt}  -f, --permanent    {d}Permanently delete (skip trash), after typing 'delete'{R}\n\
+                 {opt}  -y, --yes          {d}Skip the permanent-delete confirmation{R}\n\
+                 {opt}  -n, --dry-run      {d}Preview without executing{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui rm /file.txt{R}\n\
-                 {ex}  pikpaktui rm -rf /old-folder{R}\n",
+                 {ex}  pikpaktui rm -r /old-folder{R}\n\
+                 {ex}  pikpaktui rm -rf -y /old-folder{R}\n",
                 opt = G,
                 d = D,
                 ex = D,
@@ -164,7 +261,7 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
             format!(
                 "{B}OPTIONS:{R}\n\
                  {opt}  -n, --dry-run    {d}Preview without executing{R}\n\
-                 {opt}  -p               {d}Create intermediate directories{R}\n\
+                 {opt}  -p, --parents    {d}Create intermediate directories{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui mkdir / NewFolder{R}\n\
                  {ex}  pikpaktui mkdir -p /path/to/deep/folder{R}\n",
@@ -173,6 +270,41 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                 ex = D,
             ),
         ),
+        "touch" => (
+            "touch [options] <path...>",
+            "Create empty files",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  -n, --dry-run    {d}Preview without executing{R}\n\
+                 \n{B}NOTES:{R}\n\
+                 {d}  Files that already exist are left as they are.{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui touch /Shared/.keep{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
+        "write" => (
+            "write [options] <path> [text...]",
+            "Create a small text file from text, a template or stdin",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  --from <file>    {d}Local file or template name to copy{R}\n\
+                 {opt}  -f, --force      {d}Replace an existing file (old one goes to trash){R}\n\
+                 {opt}  -n, --dry-run    {d}Preview without executing{R}\n\
+                 \n{B}NOTES:{R}\n\
+                 {d}  Without text or --from, the content is read from stdin.{R}\n\
+                 {d}  Templates are files in ~/.config/pikpaktui/templates/.{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui write /Shared/README.txt \"Ask me before deleting\"{R}\n\
+                 {ex}  pikpaktui write --from notes.md /Shared/notes.md{R}\n\
+                 {ex}  git log -1 | pikpaktui write -f /Shared/VERSION{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
         "download" => (
             "download [options] <path> [output]",
             "Download files or folders",
@@ -182,9 +314,26 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                  {opt}  -t <local_dir>      {d}Batch: download multiple paths into dir{R}\n\
                  {opt}  -j, --jobs <n>      {d}Concurrent downloads (default: 1){R}\n\
                  {opt}  -n, --dry-run       {d}Preview without downloading{R}\n\
+                 {opt}  --stdout            {d}Write a single file's bytes to stdout{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui download /movie.mkv{R}\n\
-                 {ex}  pikpaktui download -j 4 -t ./local /Movies{R}\n",
+                 {ex}  pikpaktui download -j 4 -t ./local /Movies{R}\n\
+                 {ex}  pikpaktui download --stdout /iso.img | sha256sum{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
+        "sync" => (
+            "sync [options] <remote> <local>",
+            "Mirror a remote folder into a local directory",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  -n, --dry-run  {d}Print the plan without changing anything{R}\n\
+                 {opt}  --delete       {d}Remove local files that aren't in the remote folder{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui sync /Photos ~/Pictures/pikpak{R}\n\
+                 {ex}  pikpaktui sync -n --delete /Backup ./backup{R}\n",
                 opt = G,
                 d = D,
                 ex = D,
@@ -197,6 +346,12 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                 "{B}OPTIONS:{R}\n\
                  {opt}  -t <remote_dir>  {d}Batch: upload multiple files into dir{R}\n\
                  {opt}  -n, --dry-run    {d}Preview without uploading{R}\n\
+                 {opt}  -P, --provenance {d}Record source path, mtime and hash locally{R}\n\
+                 {opt}  --parents        {d}Create the remote dir and missing parents{R}\n\
+                 \n{B}NOTES:{R}\n\
+                 {d}  Provenance is kept only in this machine's local database, not on the{R}\n\
+                 {d}  drive: other machines and the web UI don't see it, and it's lost with{R}\n\
+                 {d}  the config directory.{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui upload file.txt{R}\n\
                  {ex}  pikpaktui upload -t /Remote a.txt b.txt{R}\n",
@@ -205,68 +360,134 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                 ex = D,
             ),
         ),
+        "backup" => (
+            "backup [options] <local> <remote>",
+            "Upload new and changed local files to a remote folder",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  -n, --dry-run       {d}Print the plan without uploading{R}\n\
+                 {opt}  --include <glob>    {d}Only back up matching files (repeatable){R}\n\
+                 {opt}  --exclude <glob>    {d}Skip matching files and folders (repeatable){R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui backup ~/Documents /Backup/Documents{R}\n\
+                 {ex}  pikpaktui backup --exclude target --exclude '*.tmp' ./code /Backup/code{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
         "share" => (
-            "share [options] <path...>",
+            "share [create] [options] <path...>",
             "Create, list, save, or delete share links",
             format!(
                 "{B}MODES:{R}\n\
-                 {opt}  share <path...>        {d}Create a share link{R}\n\
-                 {opt}  share -l               {d}List your shares{R}\n\
+                 {opt}  share <path...>        {d}Create a share link (also: share create){R}\n\
+                 {opt}  share list             {d}List your shares (also: -l){R}\n\
                  {opt}  share -S <url>         {d}Save a share to your drive{R}\n\
-                 {opt}  share -D <id...>       {d}Delete share(s){R}\n\
+                 {opt}  share import -f <file> {d}Save every share listed in a file{R}\n\
+                 {opt}  share revoke <id...>   {d}Delete share(s) by id or URL (also: -D){R}\n\
                  \n{B}OPTIONS (create):{R}\n\
                  {opt}  -p, --password   {d}Protect with a password{R}\n\
                  {opt}  -d, --days <n>   {d}Expiry in days (-1 = permanent){R}\n\
+                 {opt}  --expires <t>    {d}Expiry as 7d, 2w, or never{R}\n\
                  {opt}  -o <file>        {d}Write share URL to file{R}\n\
                  {opt}  -J, --json       {d}Output as JSON{R}\n\
+                 {opt}  --plain          {d}Tab-separated output{R}\n\
                  \n{B}OPTIONS (save):{R}\n\
                  {opt}  -p <code>        {d}Pass code for protected shares{R}\n\
                  {opt}  -t, --to <path>  {d}Destination folder{R}\n\
                  {opt}  -n, --dry-run    {d}Preview without saving{R}\n\
+                 \n{B}OPTIONS (import):{R}\n\
+                 {opt}  -f, --from-file <file>  {d}Share URLs, one per line, optionally with a pass code{R}\n\
+                 {opt}  -t, --to <path>         {d}Destination folder{R}\n\
+                 {opt}  --restart               {d}Forget progress from an earlier run{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui share /movie.mkv{R}\n\
                  {ex}  pikpaktui share -p -d 7 /folder{R}\n\
-                 {ex}  pikpaktui share -l{R}\n\
+                 {ex}  pikpaktui share create --expires 2w /folder{R}\n\
+                 {ex}  pikpaktui share list{R}\n\
                  {ex}  pikpaktui share -S https://mypikpak.com/s/abc123{R}\n\
-                 {ex}  pikpaktui share -D abc123{R}\n",
+                 {ex}  pikpaktui share import --from-file links.txt --to /Imports{R}\n\
+                 {ex}  pikpaktui share revoke abc123{R}\n",
                 opt = G,
                 d = D,
                 ex = D,
             ),
         ),
         "offline" => (
-            "offline [options] <url>",
+            "offline [options] <url> | --torrent <file> | --batch <file>",
             "Cloud download a URL or magnet link",
             format!(
                 "{B}OPTIONS:{R}\n\
                  {opt}  -t, --to <path>  {d}Destination folder in PikPak{R}\n\
                  {opt}  --name <name>    {d}Custom name for the task{R}\n\
+                 {opt}  --torrent <file> {d}Submit a local .torrent file{R}\n\
+                 {opt}  -b, --batch <file> {d}Submit every link in file (one per line, - = stdin){R}\n\
                  {opt}  -n, --dry-run    {d}Preview without creating task{R}\n\
+                 {opt}  -J, --json       {d}Print the created task(s) as JSON{R}\n\
+                 {opt}  --plain          {d}Tab-separated output{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui offline https://example.com/file.zip{R}\n\
-                 {ex}  pikpaktui offline magnet:?xt=... --to /Downloads{R}\n",
+                 {ex}  pikpaktui offline magnet:?xt=... --to /Downloads{R}\n\
+                 {ex}  pikpaktui offline --torrent ubuntu.torrent --to /ISOs{R}\n\
+                 {ex}  pikpaktui offline --batch links.txt --to /Downloads{R}\n",
                 opt = G,
                 d = D,
                 ex = D,
             ),
         ),
         "tasks" => (
-            "tasks [subcommand] [options]",
+            "tasks [subcommand] [options] | --auto-fetch <dir>",
             "Manage offline download tasks",
             format!(
                 "{B}SUBCOMMANDS:{R}\n\
                  {opt}  list, ls         {d}List tasks (default){R}\n\
                  {opt}  retry <id>       {d}Retry a failed task{R}\n\
                  {opt}  delete, rm <id...> {d}Delete task(s){R}\n\
+                 {opt}  stats [-w]       {d}Completed uploads/downloads per day (-w: per week){R}\n\
                  \n{B}OPTIONS:{R}\n\
                  {opt}  -J, --json       {d}Output as JSON{R}\n\
+                 {opt}  --plain          {d}Tab-separated output{R}\n\
                  {opt}  -n, --dry-run    {d}Preview without executing{R}\n\
                  {opt}  <number>         {d}Limit results (default: 50){R}\n\
+                 \n{B}AUTO-FETCH:{R}\n\
+                 {opt}  --auto-fetch <dir> {d}Download completed tasks into dir, then remove them{R}\n\
+                 {opt}  --pattern <glob> {d}Only fetch tasks whose name matches{R}\n\
+                 {opt}  --interval <secs> {d}Seconds between checks (default: 60){R}\n\
+                 {opt}  --once           {d}Check once and exit{R}\n\
+                 {opt}  --metrics <addr> {d}Serve Prometheus metrics, e.g. 127.0.0.1:9464{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui tasks{R}\n\
                  {ex}  pikpaktui tasks list 10{R}\n\
                  {ex}  pikpaktui tasks retry abc12345{R}\n\
-                 {ex}  pikpaktui tasks delete abc12345{R}\n",
+                 {ex}  pikpaktui tasks delete abc12345{R}\n\
+                 {ex}  pikpaktui tasks stats --week{R}\n\
+                 {ex}  pikpaktui tasks --auto-fetch ~/Downloads --pattern '*.mkv'{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
+        "queue" => (
+            "queue [list] | export [-o <file>] | import [-t <dir>] <file>",
+            "Export or import the download queue",
+            format!(
+                "{B}SUBCOMMANDS:{R}\n\
+                 {opt}  list              {d}Downloads waiting in the TUI's queue (default){R}\n\
+                 {opt}  export            {d}Write them as JSON, to stdout or -o <file>{R}\n\
+                 {opt}  import <file|->   {d}Queue the downloads in a JSON file{R}\n\
+                 \n{B}OPTIONS:{R}\n\
+                 {opt}  -o, --output <file> {d}Export to file instead of stdout{R}\n\
+                 {opt}  -t, --to <dir>    {d}Import into dir, keeping the subfolders below it{R}\n\
+                 {opt}  -J, --json        {d}List as JSON{R}\n\
+                 {opt}  --plain           {d}List tab-separated{R}\n\
+                 \n{B}NOTES:{R}\n\
+                 {d}  An import goes to the running TUI, which starts it; with none running{R}\n\
+                 {d}  it's added to the saved queue, and the TUI loads it paused next time.{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui queue export -o queue.json{R}\n\
+                 {ex}  pikpaktui queue import -t /srv/media queue.json{R}\n\
+                 {ex}  pikpaktui queue export | ssh server pikpaktui queue import -t /srv/media -{R}\n",
                 opt = G,
                 d = D,
                 ex = D,
@@ -278,6 +499,7 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
             format!(
                 "{B}OPTIONS:{R}\n\
                  {opt}  -J, --json       {d}Output as JSON{R}\n\
+                 {opt}  --plain          {d}Tab-separated output{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui info /movie.mkv{R}\n",
                 opt = G,
@@ -293,6 +515,7 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                  {opt}  -m, --media      {d}Show media stream URLs{R}\n\
                  {opt}  -c, --copy       {d}Copy URL to clipboard{R}\n\
                  {opt}  -J, --json       {d}Output as JSON{R}\n\
+                 {opt}  --plain          {d}Tab-separated output{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui link /movie.mkv{R}\n\
                  {ex}  pikpaktui link -m -c /movie.mkv{R}\n",
@@ -301,24 +524,50 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                 ex = D,
             ),
         ),
+        "open" => (
+            "open [-p] <path>",
+            "Open in the PikPak web UI",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  -p, --print      {d}Print the URL instead of opening it{R}\n\
+                 \n{B}NOTES:{R}\n\
+                 {d}  A file opens the folder it's in; the web UI has no page for one file.{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui open /Movies{R}\n\
+                 {ex}  pikpaktui open -p /movie.mkv{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
         "cat" => (
-            "cat <path>",
+            "cat [--raw] <path>",
             "Preview text file contents",
             format!(
-                "{B}EXAMPLES:{R}\n\
-                 {ex}  pikpaktui cat /notes.txt{R}\n",
+                "{B}OPTIONS:{R}\n\
+                 {opt}  --raw   {d}Stream the whole file as-is (binary, no size cap){R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui cat /notes.txt{R}\n\
+                 {ex}  pikpaktui cat --raw /backup.tar | tar -x{R}\n",
+                opt = G,
+                d = D,
                 ex = D,
             ),
         ),
         "play" => (
-            "play <path> [quality]",
+            "play <path> [quality] [options]",
             "Play video with external player",
             format!(
                 "{B}ARGUMENTS:{R}\n\
                  {opt}  quality          {d}Stream quality (e.g. 720, 1080, original){R}\n\
+                 \n{B}OPTIONS:{R}\n\
+                 {opt}  -q, --quality <q>      {d}Same as the quality argument{R}\n\
+                 {opt}  --list-streams         {d}List streams and exit, even with a quality{R}\n\
+                 {opt}  --player-args <args>   {d}Extra arguments for the player{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui play /movie.mkv{R}\n\
-                 {ex}  pikpaktui play /movie.mkv 1080{R}\n",
+                 {ex}  pikpaktui play /movie.mkv 1080{R}\n\
+                 {ex}  pikpaktui play /movie.mkv -q original --player-args \"--fs --volume=50\"{R}\n",
                 opt = G,
                 d = D,
                 ex = D,
@@ -330,6 +579,7 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
             format!(
                 "{B}OPTIONS:{R}\n\
                  {opt}  -J, --json       {d}Output as JSON{R}\n\
+                 {opt}  --plain          {d}Tab-separated output{R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui quota{R}\n",
                 opt = G,
@@ -343,10 +593,15 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
             format!(
                 "{B}OPTIONS:{R}\n\
                  {opt}  -J, --json       {d}Output as JSON{R}\n\
-                 {opt}  <number>         {d}Limit results (default: 20){R}\n\
+                 {opt}  --plain          {d}Tab-separated output{R}\n\
+                 {opt}  --since <when>   {d}Only events since 30m / 12h / 7d / 2w / YYYY-MM-DD{R}\n\
+                 {opt}  --type <t,...>   {d}Only these types (upload, delete, rename, ...){R}\n\
+                 {opt}  -a, --all        {d}Walk the whole feed, not just the newest 20{R}\n\
+                 {opt}  <number>         {d}Limit results (default: 20; none with --since/--all){R}\n\
                  \n{B}EXAMPLES:{R}\n\
                  {ex}  pikpaktui events{R}\n\
-                 {ex}  pikpaktui events 50{R}\n",
+                 {ex}  pikpaktui events 50{R}\n\
+                 {ex}  pikpaktui events --since 7d --type delete,trash{R}\n",
                 opt = G,
                 d = D,
                 ex = D,
@@ -363,11 +618,13 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
             ),
         ),
         "untrash" => (
-            "untrash <name...>",
+            "untrash <name|id...>",
             "Restore files from trash",
             format!(
                 "{B}EXAMPLES:{R}\n\
-                 {ex}  pikpaktui untrash file.txt{R}\n",
+                 {ex}  pikpaktui untrash file.txt{R}\n\
+                 {ex}  pikpaktui untrash VNxxxxxxxxxxxx   {d}# by the id rm printed{R}\n",
+                d = D,
                 ex = D,
             ),
         ),
@@ -388,6 +645,30 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                 ex = D,
             ),
         ),
+        "cleanup" => (
+            "cleanup [-n] [-y] [-J]",
+            "Trash old files by the [[cleanup]] rules",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  -n, --dry-run   {d}List what each rule would remove{R}\n\
+                 {opt}  -y, --yes       {d}Skip the confirmation for permanent-delete rules{R}\n\
+                 {opt}  -J, --json      {d}Output as JSON{R}\n\
+                 {opt}  --plain         {d}Tab-separated output{R}\n\
+                 \n{B}CONFIG:{R}\n\
+                 {d}  [[cleanup]]\n\
+                 \x20   path = \"/Offline\"\n\
+                 \x20   older_than_days = 30\n\
+                 \x20   pattern = \"*.mkv\"     # optional\n\
+                 \x20   recursive = false     # judge files in subfolders too\n\
+                 \x20   action = \"trash\"      # or \"delete\"{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui cleanup -n{R}\n\
+                 {ex}  pikpaktui cleanup{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
         "star" => (
             "star <path...>",
             "Star files",
@@ -416,6 +697,30 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                 ex = D,
             ),
         ),
+        "note" => (
+            "note [options] <path>",
+            "Local notes and labels on files",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  -l, --label <labels>   {d}Add labels (comma or space separated){R}\n\
+                 {opt}  -r, --remove <labels>  {d}Remove labels{R}\n\
+                 {opt}  -t, --text <text>      {d}Set the note text (\"\" clears it){R}\n\
+                 {opt}  --clear                {d}Remove the note and all labels{R}\n\
+                 {opt}  -s, --search [query]   {d}List notes; a query matches labels, text or path{R}\n\
+                 {opt}  -J, --json             {d}Output as JSON{R}\n\
+                 {opt}  --plain                {d}Tab-separated output{R}\n\
+                 \n{B}NOTES:{R}\n\
+                 {d}  Notes are kept only on this computer, by file id, so they follow{R}\n\
+                 {d}  renames and moves. A query of #name matches labels only.{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui note /Movies/Alien.mkv -l watched,seeding{R}\n\
+                 {ex}  pikpaktui note /Movies/Alien.mkv -t \"director's cut\"{R}\n\
+                 {ex}  pikpaktui note -s '#to-sort'{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
         "login" => (
             "login [options]",
             "Log in to PikPak and save credentials",
@@ -423,20 +728,110 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
                 "{B}OPTIONS:{R}\n\
                  {opt}  -u, --user <email>     {d}PikPak account email{R}\n\
                  {opt}  -p, --password <pass>  {d}PikPak account password{R}\n\
+                 {opt}  --token-file <path>    {d}Log in with a refresh token instead ('-' = stdin){R}\n\
+                 {opt}  --browser              {d}Solve the captcha in the browser (captcha trouble){R}\n\
                  \n{B}ENVIRONMENT:{R}\n\
                  {opt}  PIKPAK_USER            {d}Account email (fallback){R}\n\
                  {opt}  PIKPAK_PASS            {d}Account password (fallback){R}\n\
                  \n{B}PRIORITY:{R}\n\
                  {d}  CLI flags take precedence over environment variables.{R}\n\
+                 {d}  Anything still missing is prompted for at a terminal (password hidden).{R}\n\
+                 \n{B}NOTES:{R}\n\
+                 {d}  --token-file takes a bare refresh token or JSON with a refresh_token{R}\n\
+                 {d}  field. Only the session is saved, not credentials; meant for CI.{R}\n\
+                 {d}  A verification code PikPak emails is asked for at a terminal.{R}\n\
                  \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui login{R}\n\
                  {ex}  pikpaktui login -u user@example.com -p mypassword{R}\n\
-                 {ex}  PIKPAK_USER=user@example.com PIKPAK_PASS=pass pikpaktui login{R}\n",
+                 {ex}  PIKPAK_USER=user@example.com PIKPAK_PASS=pass pikpaktui login{R}\n\
+                 {ex}  echo \"$PIKPAK_REFRESH_TOKEN\" | pikpaktui login --token-file -{R}\n\
+                 {ex}  pikpaktui login --browser{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
+        "logout" => (
+            "logout",
+            "Forget the saved session and credentials",
+            format!(
+                "{B}NOTES:{R}\n\
+                 {d}  Clears the active profile only (see --profile).{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui logout{R}\n\
+                 {ex}  pikpaktui --profile work logout{R}\n",
+                d = D,
+                ex = D,
+            ),
+        ),
+        "import-rclone" => (
+            "import-rclone [options] [rclone.conf]",
+            "Reuse the login of an rclone PikPak remote",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  --remote <name>        {d}Remote to import (needed if several are pikpak){R}\n\
+                 \n{B}NOTES:{R}\n\
+                 {d}  Defaults to $RCLONE_CONFIG, then ~/.config/rclone/rclone.conf.{R}\n\
+                 {d}  Only the session token is imported; rclone's obscured password is not.{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui import-rclone{R}\n\
+                 {ex}  pikpaktui import-rclone --remote mypikpak ~/rclone.conf{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
+        "export-rclone" => (
+            "export-rclone [options] [rclone.conf]",
+            "Write the current login into an rclone PikPak remote",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  --remote <name>        {d}Remote to create or update (default: pikpak){R}\n\
+                 \n{B}NOTES:{R}\n\
+                 {d}  Defaults to $RCLONE_CONFIG, then ~/.config/rclone/rclone.conf.{R}\n\
+                 {d}  Other remotes and keys in the file are left untouched.{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui export-rclone{R}\n\
+                 {ex}  pikpaktui export-rclone --remote mypikpak{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
+        "vip" => (
+            "vip [options]",
+            "Show VIP and account info",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  -J, --json       {d}Output as JSON (plan, expiry, bandwidth){R}\n\
+                 {opt}  --plain          {d}Tab-separated output{R}\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui vip --json{R}\n",
+                opt = G,
+                d = D,
+                ex = D,
+            ),
+        ),
+        "check" => (
+            "check [options]",
+            "Alert when storage, bandwidth or VIP runs low",
+            format!(
+                "{B}OPTIONS:{R}\n\
+                 {opt}  --notify         {d}Also show a desktop notification{R}\n\
+                 {opt}  --storage <n>    {d}Storage use to alert at (alert_storage_percent, 90){R}\n\
+                 {opt}  --bandwidth <n>  {d}Allowance use to alert at (alert_bandwidth_percent, 90){R}\n\
+                 {opt}  --vip-days <n>   {d}Days before VIP expiry to alert (alert_vip_days, 7){R}\n\
+                 {opt}  -J, --json       {d}Output as JSON{R}\n\
+                 {opt}  --plain          {d}Tab-separated output{R}\n\
+                 \nExits 0 when all is well, 10 when a threshold is crossed, 1-5 on errors.\n\
+                 \n{B}EXAMPLES:{R}\n\
+                 {ex}  pikpaktui check --notify{R}\n\
+                 {ex}  pikpaktui check --storage 80 --vip-days 14{R}\n",
                 opt = G,
                 d = D,
                 ex = D,
             ),
         ),
-        "vip" => ("vip", "Show VIP and account info", String::new()),
         "update" => ("update", "Check for updates and self-update", String::new()),
         "completions" => (
             "completions <shell>",
@@ -464,27 +859,388 @@ pub fn command_help_text(cmd: &str) -> (&'static str, &'static str, String) {
     }
 }
 
+/// Set by the global `--dry-run`.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Commands the global `--dry-run` applies to. Anything else refuses it
+/// rather than risk changing something.
+pub const DRY_RUN_COMMANDS: &[&str] = &[
+    "mv", "cp", "rm", "rename", "mkdir", "touch", "write", "sync", "upload", "download", "backup",
+    "offline", "star", "unstar", "untrash", "empty", "cleanup",
+];
+
+/// Make every command in `DRY_RUN_COMMANDS` act as if given `-n`.
+pub fn set_dry_run() {
+    DRY_RUN.store(true, Ordering::Relaxed);
+}
+
+/// Whether the global `--dry-run` was given; the starting value of each
+/// command's own `-n`.
+pub fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// `--json` / `--plain`: how a command that reports data prints it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Human,
+    Json,
+    /// Tab-separated: one row per item under a header of field names, or
+    /// `field<TAB>value` lines for a single record.
+    Plain,
+}
+
+impl OutputFormat {
+    /// Anything but the coloured, aligned output meant for people.
+    pub fn is_machine(self) -> bool {
+        self != Self::Human
+    }
+}
+
+/// Set from the global `--json` / `--plain`.
+static FORMAT: AtomicU8 = AtomicU8::new(0);
+
+/// Commands that take `--json` and `--plain`. The others only change
+/// things and say so in prose, so they refuse both rather than print
+/// something a script can't parse.
+pub const DATA_COMMANDS: &[&str] = &[
+    "ls", "tree", "du", "info", "link", "quota", "vip", "tasks", "events", "trash", "starred",
+    "share", "offline", "cleanup", "check", "index", "note", "queue",
+];
+
+pub fn set_output_format(format: OutputFormat) {
+    FORMAT.store(format as u8, Ordering::Relaxed);
+}
+
+pub fn output_format() -> OutputFormat {
+    match FORMAT.load(Ordering::Relaxed) {
+        1 => OutputFormat::Json,
+        2 => OutputFormat::Plain,
+        _ => OutputFormat::Human,
+    }
+}
+
+/// Set by `--quiet`.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet() {
+    QUIET.store(true, Ordering::Relaxed);
+}
+
+/// Whether `--quiet` was given: no spinners, progress bars, notices or
+/// "done" lines, only errors and what a command was asked to print.
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Print a confirmation or notice line to stdout, unless `--quiet`.
+pub fn status(line: impl std::fmt::Display) {
+    if !quiet() {
+        println!("{line}");
+    }
+}
+
+/// Like `status`, on stderr, for notices beside output a pipe may take.
+pub fn notice(line: impl std::fmt::Display) {
+    if !quiet() {
+        eprintln!("{line}");
+    }
+}
+
+/// The exit status for a failed command, so a script can tell why without
+/// parsing the message: 2 not found, 3 not logged in or rejected, 4 network,
+/// 5 over quota, 1 anything else.
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    match pikpak::failure_kind(err) {
+        Some(pikpak::FailureKind::NotFound) => 2,
+        Some(pikpak::FailureKind::Auth) => 3,
+        Some(pikpak::FailureKind::Network) => 4,
+        Some(pikpak::FailureKind::Quota) => 5,
+        None => 1,
+    }
+}
+
+/// An error for a sub-command of one of `DATA_COMMANDS` that only reports
+/// in prose, e.g. `tasks retry`, when `--json` or `--plain` was given.
+pub fn refuse_machine_output(what: &str) -> Result<()> {
+    match output_format() {
+        OutputFormat::Human => Ok(()),
+        OutputFormat::Json => Err(anyhow!("`{what}` doesn't support --json")),
+        OutputFormat::Plain => Err(anyhow!("`{what}` doesn't support --plain")),
+    }
+}
+
+/// Print a command's result as `--json` or `--plain` asked for. Field names
+/// are the same in both, and stay put between releases.
+pub fn print_data(value: &serde_json::Value) {
+    match output_format() {
+        OutputFormat::Plain => print!("{}", plain_text(value)),
+        _ => println!(
+            "{}",
+            serde_json::to_string_pretty(value).unwrap_or_else(|_| "null".into())
+        ),
+    }
+}
+
+/// Like `print_data`, but `--plain` prints only the `list` field's rows: a
+/// pipeline wants the items of a report, not its totals.
+pub fn print_report(value: &serde_json::Value, list: &str) {
+    if output_format() == OutputFormat::Plain {
+        print!("{}", plain_text(&value[list]));
+    } else {
+        print_data(value);
+    }
+}
+
+/// `--plain` rendering. An array becomes a header of field names (nested
+/// objects joined with dots, sorted) and one row per element; an object
+/// becomes `field<TAB>value` lines. Tabs, newlines and backslashes in values
+/// are escaped as `\t`, `\n` and `\\`, null is empty, and a nested array is
+/// written as compact JSON.
+pub fn plain_text(value: &serde_json::Value) -> String {
+    use serde_json::Value;
+
+    fn flatten(prefix: String, value: &Value, out: &mut Vec<(String, String)>) {
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (k, v) in map {
+                    let key = if prefix.is_empty() {
+                        k.clone()
+                    } else {
+                        format!("{prefix}.{k}")
+                    };
+                    flatten(key, v, out);
+                }
+            }
+            _ => out.push((prefix, field(value))),
+        }
+    }
+
+    fn field(value: &Value) -> String {
+        match value {
+            Value::Null => String::new(),
+            Value::String(s) => s
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r"),
+            other => field(&Value::String(other.to_string())),
+        }
+    }
+
+    let mut out = String::new();
+    match value {
+        Value::Array(items) => {
+            let rows: Vec<Vec<(String, String)>> = items
+                .iter()
+                .map(|item| {
+                    let mut row = Vec::new();
+                    flatten(String::new(), item, &mut row);
+                    row
+                })
+                .collect();
+            let mut header: Vec<&str> = Vec::new();
+            for (key, _) in rows.iter().flatten() {
+                if !header.contains(&key.as_str()) {
+                    header.push(key);
+                }
+            }
+            header.sort_unstable();
+            if header != [""] && !header.is_empty() {
+                out.push_str(&header.join("\t"));
+                out.push('\n');
+            }
+            for row in &rows {
+                let cells: Vec<&str> = header
+                    .iter()
+                    .map(|h| {
+                        row.iter()
+                            .find(|(k, _)| k == h)
+                            .map_or("", |(_, v)| v.as_str())
+                    })
+                    .collect();
+                out.push_str(&cells.join("\t"));
+                out.push('\n');
+            }
+        }
+        Value::Object(_) => {
+            let mut fields = Vec::new();
+            flatten(String::new(), value, &mut fields);
+            for (k, v) in fields {
+                out.push_str(&format!("{k}\t{v}\n"));
+            }
+        }
+        Value::Null => {}
+        other => {
+            out.push_str(&field(other));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// `--color`: when listings use ANSI colours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    fn parse(s: &str) -> Result<Self> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "always" => Ok(Self::Always),
+            "never" => Ok(Self::Never),
+            _ => Err(anyhow!("--color takes auto, always or never, not '{s}'")),
+        }
+    }
+}
+
+/// Cleared by `--no-icons` or when output isn't a terminal.
+static ICONS: AtomicBool = AtomicBool::new(true);
+
+/// The flags `take_output_flags` found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutputFlags {
+    pub color: ColorChoice,
+    pub no_icons: bool,
+    pub format: OutputFormat,
+    pub quiet: bool,
+}
+
+/// Pull `--color[=WHEN]`, `--no-icons`, `-J`/`--json`, `--plain` and
+/// `--quiet` out of `args`; a bare `--color` means `always`. They're taken
+/// from before the command, and for one of `DATA_COMMANDS` from anywhere
+/// before a `--`. Other commands get the rest of their arguments untouched.
+pub fn take_output_flags(args: &mut Vec<String>) -> Result<OutputFlags> {
+    let mut flags = OutputFlags {
+        color: ColorChoice::Auto,
+        no_icons: false,
+        format: OutputFormat::Human,
+        quiet: false,
+    };
+    let end = args.iter().position(|a| a == "--").unwrap_or(args.len());
+    let mut kept: Vec<String> = Vec::with_capacity(args.len());
+    for (i, arg) in args.drain(..).enumerate() {
+        let scanned = kept
+            .first()
+            .is_none_or(|cmd| DATA_COMMANDS.contains(&cmd.as_str()));
+        if i < end && scanned {
+            match arg.as_str() {
+                "--no-icons" => {
+                    flags.no_icons = true;
+                    continue;
+                }
+                "--quiet" => {
+                    flags.quiet = true;
+                    continue;
+                }
+                "--color" => {
+                    flags.color = ColorChoice::Always;
+                    continue;
+                }
+                _ => {}
+            }
+            let asked = match arg.as_str() {
+                "-J" | "--json" => Some(OutputFormat::Json),
+                "--plain" => Some(OutputFormat::Plain),
+                _ => None,
+            };
+            if let Some(asked) = asked {
+                if flags.format != OutputFormat::Human && flags.format != asked {
+                    return Err(anyhow!("--json and --plain can't be used together"));
+                }
+                flags.format = asked;
+                continue;
+            }
+            if let Some(when) = arg.strip_prefix("--color=") {
+                flags.color = ColorChoice::parse(when)?;
+                continue;
+            }
+        }
+        kept.push(arg);
+    }
+    *args = kept;
+    Ok(flags)
+}
+
+/// Settle colours and icons for this run. `auto` colours a terminal unless
+/// `NO_COLOR` is set. Icons need a terminal too (or `--color=always`), so
+/// neither ends up in a file or a pipe.
+pub fn set_output_style(color: ColorChoice, no_icons: bool) {
+    let tty = std::io::stdout().is_terminal();
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    crate::theme::set_cli_color(match color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => tty && !no_color,
+    });
+    ICONS.store(
+        !no_icons && (tty || color == ColorChoice::Always),
+        Ordering::Relaxed,
+    );
+}
+
 pub fn cli_config() -> crate::config::TuiConfig {
-    crate::config::TuiConfig::load()
+    let mut config = crate::config::TuiConfig::load();
+    config.cli_nerd_font &= ICONS.load(Ordering::Relaxed);
+    config
+}
+
+/// Apply `config`'s network settings to `client`, noting any it skipped.
+pub fn configure_network(client: &mut PikPak, config: &crate::config::TuiConfig) -> Result<()> {
+    let (options, warnings) = config.net_options();
+    for warning in warnings {
+        notice(format!("Warning: {warning}"));
+    }
+    client.configure_network(&options)
+}
+
+/// Set to a directory to run the file commands on it instead of PikPak.
+const DEMO_ENV: &str = "PIKPAK_DEMO_DIR";
+
+/// The drive the file commands that only list and rearrange work on:
+/// PikPak, or with `PIKPAK_DEMO_DIR` set, that directory, to try them out
+/// without an account.
+pub fn cli_drive() -> Result<Box<dyn Backend>> {
+    match std::env::var_os(DEMO_ENV) {
+        Some(dir) if !dir.is_empty() => Ok(Box::new(pikpak::LocalBackend::new(dir))),
+        _ => Ok(Box::new(cli_client()?)),
+    }
 }
 
 pub fn cli_client() -> Result<PikPak> {
     let mut client = PikPak::new()?;
-    client.thumbnail_size = cli_config().thumbnail_size.as_api_str().to_string();
+    let config = cli_config();
+    client.thumbnail_size = config.thumbnail_size.as_api_str().to_string();
+    client.record_provenance = config.upload_provenance;
+    client.download_limiter.set_rate(config.max_download_speed);
+    client.upload_limiter.set_rate(config.max_upload_speed);
+    configure_network(&mut client, &config)?;
+    client.quiet = quiet();
 
     if client.has_valid_session() {
         return Ok(client);
     }
 
-    let cfg = AppConfig::load()?;
-    match (cfg.username, cfg.password) {
-        (Some(u), Some(p)) if !u.is_empty() && !p.is_empty() => {
+    match AppConfig::load()?.credentials() {
+        Some((u, p)) => {
             client.login(&u, &p)?;
             Ok(client)
         }
-        _ => Err(anyhow!(
-            "not logged in. Run `pikpaktui` (TUI) to login first, or set credentials in login.toml"
-        )),
+        // A session past its access token still has a refresh token
+        // (e.g. from `login --token-file`); the first call renews it.
+        None if client.has_session() => Ok(client),
+        None => match crate::config::profile() {
+            Some(name) => Err(pikpak::auth_error(format!(
+                "not logged in to profile '{name}'. Run `pikpaktui --profile {name} login` first"
+            ))),
+            None => Err(pikpak::auth_error(
+                "not logged in. Run `pikpaktui login` first, or set credentials in login.toml",
+            )),
+        },
     }
 }
 
@@ -500,12 +1256,12 @@ pub fn split_parent_name(path: &str) -> Result<(String, String)> {
     }
 }
 
-pub fn find_entry(client: &PikPak, parent_id: &str, name: &str) -> Result<pikpak::Entry> {
+pub fn find_entry(client: &dyn Backend, parent_id: &str, name: &str) -> Result<pikpak::Entry> {
     let entries = client.ls_cached(parent_id)?;
     entries
         .into_iter()
         .find(|e| e.name == name)
-        .ok_or_else(|| anyhow!("'{}' not found", name))
+        .ok_or_else(|| pikpak::not_found(format!("'{}' not found", name)))
 }
 
 /// Shared body for the star/unstar commands: parse `[-n] <path...>`, resolve
@@ -522,7 +1278,7 @@ pub fn run_star_toggle(
         return Err(usage());
     }
 
-    let mut dry_run = false;
+    let mut dry_run = dry_run();
     let mut paths: Vec<&str> = Vec::new();
     for arg in args {
         match arg.as_str() {
@@ -553,10 +1309,47 @@ pub fn run_star_toggle(
 
     let id_refs: Vec<&str> = resolved.iter().map(|(_, id)| id.as_str()).collect();
     action(&client, &id_refs)?;
-    println!("{} {} item(s)", past, resolved.len());
+    status(format!("{} {} item(s)", past, resolved.len()));
     Ok(())
 }
 
+/// Resolve a destination folder. With `parents` a missing one is created,
+/// along with any missing folders above it; a dry run only says so and
+/// returns an empty id. Errors other than the folder not being there are
+/// returned as they are.
+pub fn resolve_dest(
+    client: &dyn Backend,
+    path: &str,
+    parents: bool,
+    dry_run: bool,
+) -> Result<String> {
+    match client.resolve_path(path) {
+        Ok(id) => Ok(id),
+        Err(e) if !parents || pikpak::failure_kind(&e) != Some(pikpak::FailureKind::NotFound) => {
+            Err(e)
+        }
+        Err(_) if dry_run => {
+            println!("[dry-run] Would create folder '{}'", path);
+            Ok(String::new())
+        }
+        Err(_) => {
+            let (id, created) = client.mkdir_p(path)?;
+            notice(format!("Created {} folder(s) for '{}'", created, path));
+            Ok(id)
+        }
+    }
+}
+
+/// How a dry run names a destination folder: its id, or that it would be
+/// created (see `resolve_dest`).
+fn dest_label(dest_id: &str) -> String {
+    if dest_id.is_empty() {
+        "new folder".to_string()
+    } else {
+        format!("folder id: {dest_id}")
+    }
+}
+
 /// Shared body for the mv/cp commands (single `<src> <dst>` and batch
 /// `-t <dst> <src...>` forms). `cmd` is the command name for usage text,
 /// `action`/`past` are the lowercase/past-tense verbs, and `apply` is the
@@ -566,21 +1359,23 @@ pub fn run_transfer(
     cmd: &str,
     action: &str,
     past: &str,
-    apply: impl Fn(&PikPak, &[&str], &str) -> Result<()>,
+    apply: impl Fn(&dyn Backend, &[&str], &str) -> Result<()>,
 ) -> Result<()> {
     if args.len() < 2 {
         return Err(anyhow!(
-            "Usage: pikpaktui {cmd} [-n] <src> <dst>\n       pikpaktui {cmd} [-n] -t <dst> <src...>"
+            "Usage: pikpaktui {cmd} [-n] [--parents] <src> <dst>\n       pikpaktui {cmd} [-n] [--parents] -t <dst> <src...>"
         ));
     }
 
     let mut target: Option<&str> = None;
-    let mut dry_run = false;
+    let mut dry_run = dry_run();
+    let mut parents = false;
     let mut paths: Vec<&str> = Vec::new();
     let mut iter = args.iter();
     while let Some(arg) = iter.next() {
         match arg.as_str() {
             "-n" | "--dry-run" => dry_run = true,
+            "--parents" => parents = true,
             "-t" => {
                 target = Some(
                     iter.next()
@@ -595,27 +1390,29 @@ pub fn run_transfer(
         }
     }
 
-    let client = cli_client()?;
+    let client = cli_drive()?;
+    let client = &*client;
 
     if let Some(dst) = target {
         if paths.is_empty() {
             return Err(anyhow!("Usage: pikpaktui {cmd} [-n] -t <dst> <src...>"));
         }
-        let dest_id = client.resolve_path(dst)?;
+        let dest_id = resolve_dest(client, dst, parents, dry_run)?;
         let mut ids: Vec<String> = Vec::new();
         for path in &paths {
             let (parent, name) = split_parent_name(path)?;
             let parent_id = client.resolve_path(&parent)?;
-            let entry = find_entry(&client, &parent_id, &name)?;
+            let entry = find_entry(client, &parent_id, &name)?;
             ids.push(entry.id);
         }
 
         if dry_run {
             println!(
-                "[dry-run] Would {} {} item(s) -> '{}':",
+                "[dry-run] Would {} {} item(s) -> '{}' ({}):",
                 action,
                 paths.len(),
-                dst
+                dst,
+                dest_label(&dest_id)
             );
             for (path, id) in paths.iter().zip(ids.iter()) {
                 println!("  {} (id: {})", path, id);
@@ -624,8 +1421,8 @@ pub fn run_transfer(
         }
 
         let id_refs: Vec<&str> = ids.iter().map(|s| s.as_str()).collect();
-        apply(&client, &id_refs, &dest_id)?;
-        println!("{} {} item(s) -> '{}'", past, paths.len(), dst);
+        apply(client, &id_refs, &dest_id)?;
+        status(format!("{} {} item(s) -> '{}'", past, paths.len(), dst));
     } else {
         if paths.len() != 2 {
             return Err(anyhow!(
@@ -634,19 +1431,23 @@ pub fn run_transfer(
         }
         let (src_parent, src_name) = split_parent_name(paths[0])?;
         let src_parent_id = client.resolve_path(&src_parent)?;
-        let entry = find_entry(&client, &src_parent_id, &src_name)?;
-        let dest_id = client.resolve_path(paths[1])?;
+        let entry = find_entry(client, &src_parent_id, &src_name)?;
+        let dest_id = resolve_dest(client, paths[1], parents, dry_run)?;
 
         if dry_run {
             println!(
-                "[dry-run] Would {} '{}' -> '{}' (id: {})",
-                action, paths[0], paths[1], entry.id
+                "[dry-run] Would {} '{}' (id: {}) -> '{}' ({})",
+                action,
+                paths[0],
+                entry.id,
+                paths[1],
+                dest_label(&dest_id)
             );
             return Ok(());
         }
 
-        apply(&client, &[entry.id.as_str()], &dest_id)?;
-        println!("{} '{}' -> '{}'", past, paths[0], paths[1]);
+        apply(client, &[entry.id.as_str()], &dest_id)?;
+        status(format!("{} '{}' -> '{}'", past, paths[0], paths[1]));
     }
     Ok(())
 }
@@ -706,9 +1507,9 @@ pub fn long_entry_prefix(e: &pikpak::Entry) -> String {
         format!("{:>9}", format_size(e.size))
     };
     let date = format_date(&e.created_time);
-    let colored_id = format!("\x1b[2m{}\x1b[0m", e.id);
-    let colored_size = format!("\x1b[1;32m{}\x1b[0m", size_str);
-    let colored_date = format!("\x1b[34m{:16}\x1b[0m", date);
+    let colored_id = crate::theme::cli_paint("2", &e.id);
+    let colored_size = crate::theme::cli_paint("1;32", &size_str);
+    let colored_date = crate::theme::cli_paint("34", &format!("{:16}", date));
     format!("{}  {}  {}  ", colored_id, colored_size, colored_date)
 }
 
@@ -725,20 +1526,94 @@ pub fn print_entries_long(entries: &[pikpak::Entry], nerd_font: bool) {
     }
 }
 
-pub fn print_entries_json(entries: &[pikpak::Entry]) {
-    let json = serde_json::to_string_pretty(entries).unwrap_or_else(|_| "[]".into());
-    println!("{}", json);
+pub fn format_date(iso: &str) -> String {
+    format_date_with(iso, &crate::config::date_format())
 }
 
-pub fn format_date(iso: &str) -> String {
-    if iso.len() >= 16 {
-        let s = iso.replace('T', " ");
-        s[..16].to_string()
-    } else if iso.is_empty() {
-        "-".to_string()
-    } else {
-        iso.to_string()
+/// Case-insensitive shell-style glob match: `*` matches any run of
+/// characters, `?` exactly one. The whole name must match.
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let n: Vec<char> = name.chars().flat_map(char::to_lowercase).collect();
+    // Iterative matcher with single-star backtracking.
+    let (mut pi, mut ni) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if let Some((sp, sn)) = star {
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
     }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// Render an API timestamp (`2026-01-15T12:30:45.000Z`) with a strftime-style
+/// pattern. Only the numeric fields `%Y %m %d %H %M %S` (plus `%%`) are
+/// supported; anything unparsable is shown as-is.
+pub fn format_date_with(iso: &str, pattern: &str) -> String {
+    if iso.is_empty() {
+        return "-".to_string();
+    }
+    let field = |range: std::ops::Range<usize>| {
+        iso.get(range)
+            .filter(|f| f.bytes().all(|b| b.is_ascii_digit()))
+    };
+    let (Some(year), Some(month), Some(day), Some(hour), Some(minute)) = (
+        field(0..4),
+        field(5..7),
+        field(8..10),
+        field(11..13),
+        field(14..16),
+    ) else {
+        return iso.to_string();
+    };
+    let second = field(17..19).unwrap_or("00");
+
+    let mut out = String::with_capacity(pattern.len() + 8);
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => out.push_str(year),
+            Some('m') => out.push_str(month),
+            Some('d') => out.push_str(day),
+            Some('H') => out.push_str(hour),
+            Some('M') => out.push_str(minute),
+            Some('S') => out.push_str(second),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+/// Seconds since the epoch, now.
+pub fn now_unix() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Format a unix timestamp (UTC) the same way `format_date` renders API dates.
+pub fn format_unix_time(secs: i64) -> String {
+    format_date(&crate::pikpak::unix_to_iso(secs))
 }
 
 /// A simple CLI loading spinner on stderr.
@@ -754,7 +1629,7 @@ impl Spinner {
         use std::sync::atomic::{AtomicBool, Ordering};
 
         // Only show spinner if stderr is a terminal
-        if !std::io::stderr().is_terminal() {
+        if quiet() || !std::io::stderr().is_terminal() {
             return Self {
                 running: Arc::new(AtomicBool::new(false)),
                 handle: None,
@@ -796,6 +1671,74 @@ impl Drop for Spinner {
 
 use std::io::IsTerminal;
 
+/// A one-line `42%  3.1 MB/s  ETA 1m 5s` readout on stderr for a single
+/// download, redrawn twice a second from a smoothed speed. Silent when
+/// stderr isn't a terminal; the line is cleared on drop.
+pub struct TransferProgress {
+    enabled: bool,
+    meter: crate::ratelimit::SpeedMeter,
+    /// When and at how many bytes the speed was last sampled.
+    last: Option<(std::time::Instant, u64)>,
+    drawn: bool,
+}
+
+impl TransferProgress {
+    pub fn new() -> Self {
+        Self {
+            enabled: !quiet() && std::io::stderr().is_terminal(),
+            meter: crate::ratelimit::SpeedMeter::default(),
+            last: None,
+            drawn: false,
+        }
+    }
+
+    pub fn update(&mut self, done: u64, total: u64) {
+        use std::io::Write;
+
+        if !self.enabled {
+            return;
+        }
+        let now = std::time::Instant::now();
+        let Some((at, bytes)) = self.last else {
+            self.last = Some((now, done));
+            return;
+        };
+        let elapsed = now - at;
+        if elapsed < std::time::Duration::from_millis(500) {
+            return;
+        }
+        self.last = Some((now, done));
+        let speed = self.meter.record(done.saturating_sub(bytes), elapsed);
+        eprint!("\r\x1b[2K  {}", progress_line(done, total, speed));
+        let _ = std::io::stderr().flush();
+        self.drawn = true;
+    }
+}
+
+impl Drop for TransferProgress {
+    fn drop(&mut self) {
+        if self.drawn {
+            eprint!("\r\x1b[2K");
+        }
+    }
+}
+
+fn progress_line(done: u64, total: u64, speed: f64) -> String {
+    let mut line = format!(
+        "{} / {}  {}/s",
+        format_size(done),
+        format_size(total),
+        format_size(speed as u64)
+    );
+    if let Some(pct) = (done * 100).checked_div(total) {
+        line = format!("{pct:>3}%  {line}");
+    }
+    if let Some(eta) = crate::ratelimit::eta_secs(total.saturating_sub(done), speed) {
+        line.push_str(&format!("  ETA {}", crate::ratelimit::format_duration(eta)));
+    }
+    line
+}
+
 /// Unicode-aware string truncation with ellipsis.
 pub fn truncate(s: &str, max: usize) -> String {
     use unicode_width::UnicodeWidthStr;
@@ -818,20 +1761,105 @@ pub fn truncate(s: &str, max: usize) -> String {
 }
 
 pub fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = 1024 * KB;
-    const GB: u64 = 1024 * MB;
-    const TB: u64 = 1024 * GB;
-
-    if bytes >= TB {
-        format!("{:.1} TB", bytes as f64 / TB as f64)
-    } else if bytes >= GB {
-        format!("{:.1} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.1} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.1} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
+    format_size_in(bytes, crate::config::size_units())
+}
+
+pub fn format_size_in(bytes: u64, units: crate::config::SizeUnits) -> String {
+    use crate::config::SizeUnits;
+
+    let (base, labels) = match units {
+        SizeUnits::Classic => (1024u64, ["KB", "MB", "GB", "TB"]),
+        SizeUnits::Binary => (1024u64, ["KiB", "MiB", "GiB", "TiB"]),
+        SizeUnits::Decimal => (1000u64, ["KB", "MB", "GB", "TB"]),
+    };
+
+    let mut unit = 1u64;
+    let mut label = None;
+    for l in labels {
+        if bytes < unit * base {
+            break;
+        }
+        unit *= base;
+        label = Some(l);
+    }
+    match label {
+        Some(l) => format!("{:.1} {}", bytes as f64 / unit as f64, l),
+        None => format!("{} B", bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn output_flags_are_taken_before_the_command_or_anywhere_after_a_data_one() {
+        let mut a = args(&["ls", "--no-icons", "/", "--color=never", "--", "--color"]);
+        let flags = take_output_flags(&mut a).unwrap();
+        assert_eq!(flags.color, ColorChoice::Never);
+        assert!(flags.no_icons);
+        assert_eq!(flags.format, OutputFormat::Human);
+        assert!(!flags.quiet);
+        assert_eq!(a, args(&["ls", "/", "--", "--color"]));
+
+        let mut a = args(&["--plain", "quota", "--", "-J"]);
+        assert_eq!(
+            take_output_flags(&mut a).unwrap().format,
+            OutputFormat::Plain
+        );
+        assert_eq!(a, args(&["quota", "--", "-J"]));
+
+        let mut a = args(&["--quiet", "rm", "/a", "--", "--quiet"]);
+        assert!(take_output_flags(&mut a).unwrap().quiet);
+        assert_eq!(a, args(&["rm", "/a", "--", "--quiet"]));
+        // After a command that doesn't print data they're its arguments.
+        let mut a = args(&["rename", "/a", "--plain", "--color"]);
+        let flags = take_output_flags(&mut a).unwrap();
+        assert_eq!(flags.format, OutputFormat::Human);
+        assert_eq!(flags.color, ColorChoice::Auto);
+        assert_eq!(a, args(&["rename", "/a", "--plain", "--color"]));
+        assert!(take_output_flags(&mut args(&["ls", "-J", "--plain"])).is_err());
+
+        let mut a = args(&["--color", "tree"]);
+        assert_eq!(
+            take_output_flags(&mut a).unwrap().color,
+            ColorChoice::Always
+        );
+        assert!(take_output_flags(&mut args(&["--color=sometimes"])).is_err());
+    }
+
+    #[test]
+    fn only_a_missing_destination_is_created() {
+        let root = std::env::temp_dir().join(format!("pikpaktui-dest-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let drive = pikpak::LocalBackend::new(root.clone());
+        assert_eq!(resolve_dest(&drive, "/new/sub", true, true).unwrap(), "");
+        assert!(resolve_dest(&drive, "/new/sub", false, false).is_err());
+
+        // A drive that can't be listed isn't a missing folder.
+        std::fs::remove_dir_all(&root).unwrap();
+        std::fs::write(&root, "").unwrap();
+        assert!(resolve_dest(&drive, "/new/sub", true, true).is_err());
+        std::fs::remove_file(&root).unwrap();
+    }
+
+    #[test]
+    fn plain_text_is_tab_separated_with_escapes() {
+        let rows = serde_json::json!([
+            {"name": "a\tb.txt", "size": 3, "media": {"codec": "h264"}},
+            {"name": "c", "size": null, "extra": [1, 2]},
+        ]);
+        assert_eq!(
+            plain_text(&rows),
+            "extra\tmedia.codec\tname\tsize\n\th264\ta\\tb.txt\t3\n[1,2]\t\tc\t\n"
+        );
+        let record = serde_json::json!({"ok": true, "storage": {"used": 5}});
+        assert_eq!(plain_text(&record), "ok\ttrue\nstorage.used\t5\n");
+        assert_eq!(plain_text(&serde_json::json!(["x", "y"])), "x\ny\n");
     }
 }
@@ -0,0 +1,147 @@
+use crate::pikpak::{Note, parse_labels};
+use anyhow::{Result, anyhow};
+
+const USAGE: &str = "Usage: pikpaktui note <path> [-l <labels>] [-r <labels>] [-t <text>] [--clear]\n       pikpaktui note -s [query]";
+
+pub fn run(args: &[String]) -> Result<()> {
+    let mut path: Option<&str> = None;
+    let mut add: Vec<String> = Vec::new();
+    let mut remove: Vec<String> = Vec::new();
+    let mut text: Option<String> = None;
+    let mut clear = false;
+    let mut search: Option<String> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        let value = |i: usize| {
+            args.get(i + 1)
+                .cloned()
+                .ok_or_else(|| anyhow!("missing value for {}\n{USAGE}", args[i]))
+        };
+        match args[i].as_str() {
+            "-l" | "--label" => {
+                add.extend(parse_labels(&value(i)?));
+                i += 1;
+            }
+            "-r" | "--remove" => {
+                remove.extend(parse_labels(&value(i)?));
+                i += 1;
+            }
+            "-t" | "--text" => {
+                text = Some(value(i)?);
+                i += 1;
+            }
+            "--clear" => clear = true,
+            "-s" | "--search" => {
+                // The query is optional: `note -s` lists every note.
+                search = Some(match args.get(i + 1) {
+                    Some(q) if !q.starts_with('-') => {
+                        i += 1;
+                        q.clone()
+                    }
+                    _ => String::new(),
+                });
+            }
+            s if s.starts_with('-') && s != "-" => {
+                return Err(anyhow!("unknown option: {s}\n{USAGE}"));
+            }
+            s if path.is_none() => path = Some(s),
+            _ => return Err(anyhow!(USAGE)),
+        }
+        i += 1;
+    }
+
+    let client = super::cli_client()?;
+    if let Some(query) = search {
+        if path.is_some() {
+            return Err(anyhow!(USAGE));
+        }
+        return list_notes(&client.notes()?, &query);
+    }
+
+    let path = path.ok_or_else(|| anyhow!(USAGE))?;
+    let (parent_path, name) = super::split_parent_name(path)?;
+    let parent_id = client.resolve_path(&parent_path)?;
+    let entry = super::find_entry(&client, &parent_id, &name)?;
+    let mut note = client.note(&entry.id)?.unwrap_or_default();
+
+    let editing = clear || text.is_some() || !add.is_empty() || !remove.is_empty();
+    if editing {
+        if clear {
+            note = Note::default();
+        }
+        note.add_labels(&add);
+        note.labels.retain(|l| !remove.contains(l));
+        if let Some(text) = text {
+            note.text = text;
+        }
+        note.path = format!("/{}", path.trim_matches('/'));
+        client.set_note(&entry.id, &note)?;
+    }
+
+    if super::output_format().is_machine() {
+        super::print_data(&serde_json::to_value(&note)?);
+    } else if note.is_empty() {
+        super::status(if editing {
+            format!("Removed the note on '{name}'")
+        } else {
+            format!("No note on '{name}'")
+        });
+    } else {
+        print_note(&note);
+    }
+    Ok(())
+}
+
+fn print_note(note: &Note) {
+    if !note.labels.is_empty() {
+        println!("Labels: {}", note.labels.join(", "));
+    }
+    if !note.text.is_empty() {
+        println!("Note:   {}", note.text);
+    }
+}
+
+fn list_notes(notes: &std::collections::HashMap<String, Note>, query: &str) -> Result<()> {
+    let mut found: Vec<(&String, &Note)> = notes
+        .iter()
+        .filter(|(_, n)| query.is_empty() || n.matches(query))
+        .collect();
+    found.sort_by(|a, b| a.1.path.cmp(&b.1.path));
+
+    if super::output_format().is_machine() {
+        let rows: Vec<serde_json::Value> = found
+            .iter()
+            .map(|(id, note)| {
+                let mut row = serde_json::to_value(note).unwrap_or_default();
+                row["id"] = serde_json::Value::from(id.as_str());
+                row
+            })
+            .collect();
+        super::print_data(&serde_json::Value::Array(rows));
+        return Ok(());
+    }
+    if found.is_empty() {
+        super::status("No notes");
+        return Ok(());
+    }
+    for (_, note) in found {
+        let labels = note
+            .labels
+            .iter()
+            .map(|l| format!("#{l}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = note.text.lines().next().unwrap_or_default();
+        println!(
+            "{}",
+            [note.path.as_str(), labels.as_str(), text]
+                .iter()
+                .filter(|s| !s.is_empty())
+                .copied()
+                .collect::<Vec<_>>()
+                .join("  ")
+        );
+    }
+    Ok(())
+}
@@ -1,20 +1,18 @@
-use anyhow::{Result, anyhow};
+use crate::pikpak::{OfflineTaskResponse, PikPak};
+use anyhow::{Context, Result, anyhow};
+use std::io::Read as _;
 
-pub fn run(args: &[String]) -> Result<()> {
-    if args.is_empty() {
-        return Err(anyhow!(
-            "Usage: pikpaktui offline [--dry-run] <url> [--to <path>] [--name <name>]"
-        ));
-    }
-
-    let client = super::cli_client()?;
+const USAGE: &str = "Usage: pikpaktui offline [--dry-run] <url> [--to <path>] [--name <name>]\n       pikpaktui offline [--dry-run] --torrent <file.torrent> [--to <path>] [--name <name>]\n       pikpaktui offline [--dry-run] --batch <file|-> [--to <path>]";
 
-    let file_url = &args[0];
+pub fn run(args: &[String]) -> Result<()> {
+    let mut file_url: Option<&str> = None;
+    let mut batch: Option<&str> = None;
+    let mut torrent: Option<&str> = None;
     let mut parent_path: Option<&str> = None;
     let mut name: Option<&str> = None;
-    let mut dry_run = false;
+    let mut dry_run = super::dry_run();
 
-    let mut i = 1;
+    let mut i = 0;
     while i < args.len() {
         match args[i].as_str() {
             "--to" | "-t" => {
@@ -33,35 +31,162 @@ pub fn run(args: &[String]) -> Result<()> {
                         .as_str(),
                 );
             }
+            "--batch" | "-b" => {
+                i += 1;
+                batch = Some(
+                    args.get(i)
+                        .ok_or_else(|| anyhow!("--batch requires a file (or - for stdin)"))?
+                        .as_str(),
+                );
+            }
+            "--torrent" => {
+                i += 1;
+                torrent = Some(
+                    args.get(i)
+                        .ok_or_else(|| anyhow!("--torrent requires a .torrent file"))?
+                        .as_str(),
+                );
+            }
             "--dry-run" | "-n" => dry_run = true,
-            other => {
+            other if other.starts_with('-') && other != "-" => {
                 return Err(anyhow!(
                     "unknown option: {other}\nRun `pikpaktui offline --help` for usage."
                 ));
             }
+            other if file_url.is_none() => file_url = Some(other),
+            _ => return Err(anyhow!("too many arguments\n{USAGE}")),
         }
         i += 1;
     }
 
+    let urls = match (batch, torrent, file_url) {
+        (None, None, None) => return Err(anyhow!("{USAGE}")),
+        (None, None, Some(url)) => vec![crate::torrent::resolve_link(url)?],
+        (None, Some(path), None) => {
+            vec![crate::torrent::load(std::path::Path::new(path))?.magnet()]
+        }
+        (Some(_), None, None) if name.is_some() => {
+            return Err(anyhow!("--name can't be used with --batch"));
+        }
+        (Some(source), None, None) => {
+            let text = if source == "-" {
+                let mut buf = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut buf)
+                    .context("failed to read links from stdin")?;
+                buf
+            } else {
+                std::fs::read_to_string(source)
+                    .with_context(|| format!("failed to read {source}"))?
+            };
+            // Local .torrent paths can be listed alongside links.
+            let urls = parse_links(&text)
+                .iter()
+                .map(|link| crate::torrent::resolve_link(link))
+                .collect::<Result<Vec<_>>>()?;
+            if urls.is_empty() {
+                return Err(anyhow!("no links found in {source}"));
+            }
+            urls
+        }
+        _ => return Err(anyhow!("give only one of a URL, --torrent or --batch")),
+    };
+
+    let client = super::cli_client()?;
+
     let parent_id = match parent_path {
         Some(p) => Some(client.resolve_path(p)?),
         None => None,
     };
 
+    let machine = super::output_format().is_machine();
     if dry_run {
         let dest_display = parent_path.unwrap_or("/");
-        print!("[dry-run] Would submit offline download: '{}'", file_url);
-        if let Some(n) = name {
-            print!(" as '{}'", n);
+        if machine {
+            let plan: Vec<_> = urls
+                .iter()
+                .map(|url| {
+                    serde_json::json!({
+                        "url": url,
+                        "name": name,
+                        "to": dest_display,
+                        "parent_id": parent_id,
+                    })
+                })
+                .collect();
+            super::print_data(&plan.into());
+            return Ok(());
+        }
+        for url in &urls {
+            print!("[dry-run] Would submit offline download: '{}'", url);
+            if let Some(n) = name {
+                print!(" as '{}'", n);
+            }
+            println!(" -> '{}'", dest_display);
         }
-        println!(" -> '{}'", dest_display);
         if let Some(id) = &parent_id {
             println!("  parent id: {}", id);
         }
         return Ok(());
     }
 
-    let resp = client.offline_download(file_url, parent_id.as_deref(), name)?;
+    if batch.is_none() {
+        return submit_one(&client, &urls[0], parent_id.as_deref(), name);
+    }
+
+    // Keep going past failures so one dead link doesn't strand the rest.
+    let mut failed = 0usize;
+    if machine {
+        let mut rows = Vec::new();
+        for url in &urls {
+            let result = client.offline_download(url, parent_id.as_deref(), None);
+            failed += usize::from(result.is_err());
+            rows.push(submitted_json(url, result));
+        }
+        super::print_data(&rows.into());
+        if failed > 0 {
+            return Err(anyhow!("{failed} link(s) failed"));
+        }
+        return Ok(());
+    }
+    for url in &urls {
+        match client.offline_download(url, parent_id.as_deref(), None) {
+            Ok(resp) => match &resp.task {
+                Some(task) => println!(
+                    "\x1b[32m✓\x1b[0m {}  \x1b[2mid={} phase={}\x1b[0m",
+                    task.name, task.id, task.phase
+                ),
+                None => println!("\x1b[32m✓\x1b[0m {}  \x1b[2msubmitted\x1b[0m", url),
+            },
+            Err(e) => {
+                failed += 1;
+                eprintln!("\x1b[31m✗\x1b[0m {}: {e:#}", url);
+            }
+        }
+    }
+    super::status(format!(
+        "Submitted {} of {} link(s)",
+        urls.len() - failed,
+        urls.len()
+    ));
+    if failed > 0 {
+        return Err(anyhow!("{failed} link(s) failed"));
+    }
+    Ok(())
+}
+
+fn submit_one(
+    client: &PikPak,
+    url: &str,
+    parent_id: Option<&str>,
+    name: Option<&str>,
+) -> Result<()> {
+    if super::output_format().is_machine() {
+        let resp = client.offline_download(url, parent_id, name)?;
+        super::print_data(&submitted_json(url, Ok(resp)));
+        return Ok(());
+    }
+    let resp = client.offline_download(url, parent_id, name)?;
     if let Some(task) = &resp.task {
         println!("Offline task created: {}", task.name);
         println!("  ID:    {}", task.id);
@@ -70,8 +195,62 @@ pub fn run(args: &[String]) -> Result<()> {
             println!("  File:  {}", fid);
         }
     } else {
-        println!("Offline download submitted");
+        super::status("Offline download submitted");
     }
-
     Ok(())
 }
+
+/// One submitted link for `--json` / `--plain`: the task it created, or
+/// why it failed.
+fn submitted_json(url: &str, result: Result<OfflineTaskResponse>) -> serde_json::Value {
+    match result {
+        Ok(resp) => {
+            let task = resp.task;
+            serde_json::json!({
+                "url": url,
+                "id": task.as_ref().map(|t| &t.id),
+                "name": task.as_ref().map(|t| &t.name),
+                "phase": task.as_ref().map(|t| &t.phase),
+                "file_id": task.as_ref().and_then(|t| t.file_id.as_ref()),
+                "error": null,
+            })
+        }
+        Err(e) => serde_json::json!({
+            "url": url,
+            "id": null,
+            "name": null,
+            "phase": null,
+            "file_id": null,
+            "error": format!("{e:#}"),
+        }),
+    }
+}
+
+/// Split pasted or file text into links: whitespace-separated, with blank
+/// lines and `#` comment lines skipped.
+pub fn parse_links(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .flat_map(str::split_whitespace)
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_links_skips_blanks_and_comments() {
+        let text = "# queue\r\nmagnet:?xt=urn:btih:abc\n\n  https://a.example/x.zip https://b.example/y.zip \n";
+        assert_eq!(
+            parse_links(text),
+            [
+                "magnet:?xt=urn:btih:abc",
+                "https://a.example/x.zip",
+                "https://b.example/y.zip"
+            ]
+        );
+    }
+}
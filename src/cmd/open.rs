@@ -0,0 +1,78 @@
+use crate::pikpak::{EntryKind, PikPak};
+use anyhow::{Result, anyhow};
+
+const USAGE: &str = "Usage: pikpaktui open [-p|--print] <path>";
+
+/// The web UI's view of the whole drive; a folder is `<this>/<id>`.
+const WEB_DRIVE: &str = "https://mypikpak.com/drive/all";
+
+pub fn run(args: &[String]) -> Result<()> {
+    let mut print = false;
+    let mut path: Option<&str> = None;
+    for arg in args {
+        match arg.as_str() {
+            "-p" | "--print" => print = true,
+            s if s.starts_with('-') && s != "-" => {
+                return Err(anyhow!("unknown option: {s}\n{USAGE}"));
+            }
+            s if path.is_none() => path = Some(s),
+            _ => return Err(anyhow!(USAGE)),
+        }
+    }
+    let path = path.ok_or_else(|| anyhow!(USAGE))?;
+
+    let client = super::cli_client()?;
+    let url = if path.trim().trim_end_matches('/').is_empty() {
+        web_url("")
+    } else {
+        let (parent_path, name) = super::split_parent_name(path)?;
+        let parent_id = client.resolve_path(&parent_path)?;
+        let entry = super::find_entry(&client, &parent_id, &name)?;
+        if entry.kind == EntryKind::Folder {
+            web_url(&entry.id)
+        } else {
+            if !print {
+                super::notice(format!("Opening the folder containing '{name}'"));
+            }
+            web_url(&parent_id)
+        }
+    };
+
+    if print {
+        println!("{url}");
+        return Ok(());
+    }
+    crate::local_path::open_default(url.as_ref())?;
+    super::status(&url);
+    Ok(())
+}
+
+/// The web UI page of a folder; `""` is the root.
+pub fn web_url(folder_id: &str) -> String {
+    if folder_id.is_empty() {
+        WEB_DRIVE.to_string()
+    } else {
+        format!("{WEB_DRIVE}/{folder_id}")
+    }
+}
+
+/// The web UI page showing a file or folder: a folder's own page, or the
+/// folder a file is in, as the web UI has no page for a single file.
+pub fn entry_web_url(client: &PikPak, id: &str, is_folder: bool) -> Result<String> {
+    if is_folder {
+        return Ok(web_url(id));
+    }
+    let info = client.file_info(id)?;
+    Ok(web_url(info.parent_id.as_deref().unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folders_map_to_drive_pages() {
+        assert_eq!(web_url(""), "https://mypikpak.com/drive/all");
+        assert_eq!(web_url("VNabc"), "https://mypikpak.com/drive/all/VNabc");
+    }
+}
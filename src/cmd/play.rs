@@ -1,4 +1,5 @@
 use anyhow::{Result, anyhow};
+use std::sync::Arc;
 
 use crate::pikpak::PikPak;
 
@@ -6,6 +7,8 @@ struct PlayOption {
     label: String,
     url: String,
     available: bool,
+    /// Media name of a transcoded stream; `None` for the original.
+    media: Option<String>,
 }
 
 fn build_play_options(client: &PikPak, file_id: &str) -> Result<Vec<PlayOption>> {
@@ -25,6 +28,7 @@ fn build_play_options(client: &PikPak, file_id: &str) -> Result<Vec<PlayOption>>
             label: format!("original ({})", size_str),
             url: url.clone(),
             available: true,
+            media: None,
         });
     }
 
@@ -48,6 +52,7 @@ fn build_play_options(client: &PikPak, file_id: &str) -> Result<Vec<PlayOption>>
                 label,
                 url,
                 available,
+                media: m.media_name.clone(),
             });
         }
     }
@@ -55,26 +60,42 @@ fn build_play_options(client: &PikPak, file_id: &str) -> Result<Vec<PlayOption>>
     Ok(options)
 }
 
+const USAGE: &str = "Usage: pikpaktui play <path> [quality] [-q <quality>] [--list-streams] [--player-args <args>]\n\n\
+     quality: \"original\", a stream name like \"720p\", \"1080p\", or its number\n\
+     omit quality (or pass --list-streams) to list available streams";
+
 pub fn run(args: &[String]) -> Result<()> {
-    if args.is_empty() {
-        return Err(anyhow!(
-            "Usage: pikpaktui play <path> [quality]\n\n\
-             quality: \"original\", or a stream name like \"720p\", \"1080p\"\n\
-             omit quality to list available streams"
-        ));
+    let mut path: Option<&str> = None;
+    let mut quality: Option<&str> = None;
+    let mut list_streams = false;
+    let mut player_args: Vec<String> = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-q" | "--quality" => {
+                quality = Some(
+                    iter.next()
+                        .ok_or_else(|| anyhow!("--quality requires a stream name or number"))?
+                        .as_str(),
+                );
+            }
+            "--list-streams" => list_streams = true,
+            "--player-args" => {
+                let val = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("--player-args requires a value"))?;
+                player_args.extend(crate::local_path::split_command(val));
+            }
+            _ if path.is_none() => path = Some(arg),
+            _ if quality.is_none() => quality = Some(arg),
+            _ => return Err(anyhow!("unexpected argument '{}'\n\n{}", arg, USAGE)),
+        }
     }
 
-    let path = &args[0];
-    let quality = args.get(1).map(|s| s.as_str());
-
-    let config = super::cli_config();
-    let player = config.player.ok_or_else(|| {
-        anyhow!(
-            "no player configured.\n\
-             Set `player` in ~/.config/pikpaktui/config.toml under [tui], e.g.:\n\n  \
-             player = \"mpv\""
-        )
-    })?;
+    let Some(path) = path else {
+        return Err(anyhow!(USAGE));
+    };
 
     let client = super::cli_client()?;
 
@@ -87,8 +108,9 @@ pub fn run(args: &[String]) -> Result<()> {
         return Err(anyhow!("no playable streams found for '{}'", name));
     }
 
-    match quality {
-        None => {
+    let q = match quality {
+        Some(q) if !list_streams => q,
+        _ => {
             println!("Available streams for '{}':", name);
             for (i, opt) in options.iter().enumerate() {
                 let status = if opt.available { "" } else { " (unavailable)" };
@@ -96,77 +118,99 @@ pub fn run(args: &[String]) -> Result<()> {
             }
             println!();
             println!("Run: pikpaktui play \"{}\" <quality>", path);
-            Ok(())
+            return Ok(());
         }
-        Some(q) => {
-            if let Ok(num) = q.parse::<usize>() {
-                if num >= 1 && num <= options.len() {
-                    let opt = &options[num - 1];
-                    if !opt.available {
-                        return Err(anyhow!(
-                            "stream '{}' is not available (cold storage)",
-                            opt.label
-                        ));
-                    }
-                    return launch_player(&player, &opt.url, &opt.label);
-                }
-                return Err(anyhow!(
+    };
+
+    let config = super::cli_config();
+    let player = config.player.ok_or_else(|| {
+        anyhow!(
+            "no player configured.\n\
+             Set `player` in ~/.config/pikpaktui/config.toml under [tui], e.g.:\n\n  \
+             player = \"mpv\""
+        )
+    })?;
+
+    let opt = select_option(&options, q)?;
+    if !opt.available {
+        return Err(anyhow!(
+            "stream '{}' is not available (cold storage)",
+            opt.label
+        ));
+    }
+    // Through the local proxy, an expired link is renewed mid-playback.
+    // It's kept until the player exits.
+    let proxy = if config.player_proxy {
+        Some(Arc::new(client).proxy_stream(
+            &entry.id,
+            opt.media.as_deref(),
+            &name,
+            opt.url.clone(),
+        )?)
+    } else {
+        None
+    };
+    let url = proxy.as_ref().map_or(&opt.url, |p| &p.url);
+    launch_player(&player, &player_args, url, &opt.label)
+}
+
+/// Pick a stream by 1-based number or by a case-insensitive substring of its
+/// label, which must match exactly one stream.
+fn select_option<'a>(options: &'a [PlayOption], q: &str) -> Result<&'a PlayOption> {
+    if let Ok(num) = q.parse::<usize>() {
+        return num
+            .checked_sub(1)
+            .and_then(|i| options.get(i))
+            .ok_or_else(|| {
+                anyhow!(
                     "invalid stream number: {}. Available: 1-{}",
                     num,
                     options.len()
-                ));
-            }
+                )
+            });
+    }
 
-            let q_lower = q.to_lowercase();
-            let matched: Vec<&PlayOption> = options
-                .iter()
-                .filter(|o| o.label.to_lowercase().contains(&q_lower))
-                .collect();
-
-            match matched.len() {
-                0 => {
-                    let available: Vec<&str> = options.iter().map(|o| o.label.as_str()).collect();
-                    Err(anyhow!(
-                        "no stream matching '{}'\nAvailable: {}",
-                        q,
-                        available.join(", ")
-                    ))
-                }
-                1 => {
-                    let opt = matched[0];
-                    if !opt.available {
-                        return Err(anyhow!(
-                            "stream '{}' is not available (cold storage)",
-                            opt.label
-                        ));
-                    }
-                    launch_player(&player, &opt.url, &opt.label)
-                }
-                _ => {
-                    let names: Vec<&str> = matched.iter().map(|o| o.label.as_str()).collect();
-                    Err(anyhow!(
-                        "'{}' matches multiple streams: {}\nBe more specific.",
-                        q,
-                        names.join(", ")
-                    ))
-                }
-            }
+    let q_lower = q.to_lowercase();
+    let matched: Vec<&PlayOption> = options
+        .iter()
+        .filter(|o| o.label.to_lowercase().contains(&q_lower))
+        .collect();
+
+    match matched[..] {
+        [] => {
+            let available: Vec<&str> = options.iter().map(|o| o.label.as_str()).collect();
+            Err(anyhow!(
+                "no stream matching '{}'\nAvailable: {}",
+                q,
+                available.join(", ")
+            ))
+        }
+        [opt] => Ok(opt),
+        _ => {
+            let names: Vec<&str> = matched.iter().map(|o| o.label.as_str()).collect();
+            Err(anyhow!(
+                "'{}' matches multiple streams: {}\nBe more specific.",
+                q,
+                names.join(", ")
+            ))
         }
     }
 }
 
-fn launch_player(player_cmd: &str, url: &str, label: &str) -> Result<()> {
-    let parts: Vec<&str> = player_cmd.split_whitespace().collect();
-    if parts.is_empty() {
+/// Run the player on `url`. `extra_args` go after the configured player's
+/// own arguments, before the URL.
+fn launch_player(player_cmd: &str, extra_args: &[String], url: &str, label: &str) -> Result<()> {
+    let mut args = crate::local_path::split_command(player_cmd);
+    if args.is_empty() {
         return Err(anyhow!("player command is empty"));
     }
-    let program = parts[0];
-    let mut args: Vec<&str> = parts[1..].to_vec();
-    args.push("--");
-    args.push(url);
+    let program = args.remove(0);
+    args.extend_from_slice(extra_args);
+    args.push("--".into());
+    args.push(url.into());
 
-    eprintln!("Playing '{}' with {}...", label, program);
-    let mut child = std::process::Command::new(program)
+    super::notice(format!("Playing '{}' with {}...", label, program));
+    let mut child = std::process::Command::new(&program)
         .args(&args)
         .spawn()
         .map_err(|e| anyhow!("failed to launch {}: {}", program, e))?;
@@ -174,3 +218,32 @@ fn launch_player(player_cmd: &str, url: &str, label: &str) -> Result<()> {
     child.wait().map_err(|e| anyhow!("player error: {}", e))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option(label: &str) -> PlayOption {
+        PlayOption {
+            label: label.into(),
+            url: format!("https://example.com/{label}"),
+            available: true,
+            media: None,
+        }
+    }
+
+    #[test]
+    fn streams_are_picked_by_number_or_unique_name() {
+        let options = [option("original (1.2 GB)"), option("720P"), option("1080P")];
+        assert_eq!(select_option(&options, "2").unwrap().label, "720P");
+        assert_eq!(select_option(&options, "1080p").unwrap().label, "1080P");
+        assert_eq!(
+            select_option(&options, "Original").unwrap().label,
+            "original (1.2 GB)"
+        );
+        assert!(select_option(&options, "0").is_err());
+        assert!(select_option(&options, "4").is_err());
+        assert!(select_option(&options, "P").is_err());
+        assert!(select_option(&options, "4k").is_err());
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::{Context, Result, anyhow};
+use std::io::Read as _;
+use std::path::PathBuf;
+
+use crate::tui::queue_file::{self, Handoff};
+
+const USAGE: &str = "Usage: pikpaktui queue [list]\n       pikpaktui queue export [-o <file>]\n       pikpaktui queue import [-t <dir>] <file|->";
+
+pub fn run(args: &[String]) -> Result<()> {
+    match args.first().map(String::as_str) {
+        None | Some("list" | "ls") if args.len() <= 1 => list(),
+        Some("export") => export(&args[1..]),
+        Some("import") => import(&args[1..]),
+        _ => Err(anyhow!(USAGE)),
+    }
+}
+
+fn list() -> Result<()> {
+    let queue = queue_file::unfinished();
+    if super::output_format().is_machine() {
+        super::print_data(&serde_json::to_value(&queue)?);
+        return Ok(());
+    }
+    if queue.is_empty() {
+        super::status("No downloads waiting");
+        return Ok(());
+    }
+    for item in &queue {
+        println!(
+            "{}  {}  {}",
+            item.name,
+            super::format_size(item.total_size),
+            item.dest_path.display()
+        );
+    }
+    Ok(())
+}
+
+fn export(args: &[String]) -> Result<()> {
+    let out = match args {
+        [] => None,
+        [flag, path] if flag == "-o" || flag == "--output" => Some(PathBuf::from(path)),
+        _ => return Err(anyhow!(USAGE)),
+    };
+    let queue = queue_file::unfinished();
+    let json = serde_json::to_string_pretty(&queue)?;
+    let Some(out) = out.filter(|p| p.as_os_str() != "-") else {
+        println!("{json}");
+        return Ok(());
+    };
+    std::fs::write(&out, json + "\n")
+        .with_context(|| format!("failed to write {}", out.display()))?;
+    super::status(format!(
+        "Exported {} download(s) to {}",
+        queue.len(),
+        out.display()
+    ));
+    Ok(())
+}
+
+fn import(args: &[String]) -> Result<()> {
+    super::refuse_machine_output("queue import")?;
+    let mut to: Option<PathBuf> = None;
+    let mut source: Option<&str> = None;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "-t" | "--to" => {
+                let dir = args
+                    .get(i + 1)
+                    .ok_or_else(|| anyhow!("missing value for {}\n{USAGE}", args[i]))?;
+                to = Some(std::path::absolute(dir)?);
+                i += 1;
+            }
+            s if s.starts_with('-') && s != "-" => {
+                return Err(anyhow!("unknown option: {s}\n{USAGE}"));
+            }
+            s if source.is_none() => source = Some(s),
+            _ => return Err(anyhow!(USAGE)),
+        }
+        i += 1;
+    }
+    let source = source.ok_or_else(|| anyhow!(USAGE))?;
+
+    let raw = if source == "-" {
+        let mut raw = String::new();
+        std::io::stdin()
+            .read_to_string(&mut raw)
+            .context("failed to read the queue from stdin")?;
+        raw
+    } else {
+        std::fs::read_to_string(source).with_context(|| format!("failed to read {source}"))?
+    };
+    let mut queue = queue_file::parse(&raw)?;
+    if queue.is_empty() {
+        super::status("Nothing to import");
+        return Ok(());
+    }
+    let dir = match to {
+        Some(dir) => dir,
+        None => queue_file::default_dir()
+            .ok_or_else(|| anyhow!("no downloads folder here; pass --to <dir>"))?,
+    };
+    queue_file::reroot(&mut queue, &dir)?;
+
+    let n = queue.len();
+    super::status(match queue_file::import(&queue)? {
+        Handoff::Running => format!("Handed {n} download(s) to the running pikpaktui"),
+        Handoff::Saved(0) => "Every download is already in the saved queue".to_string(),
+        Handoff::Saved(added) => format!(
+            "Added {added} download(s) to the saved queue; the TUI loads them, paused, when it next starts"
+        ),
+    });
+    Ok(())
+}
@@ -1,8 +1,6 @@
 use anyhow::Result;
 
-pub fn run(args: &[String]) -> Result<()> {
-    let json = args.iter().any(|a| a == "-J" || a == "--json");
-
+pub fn run() -> Result<()> {
     let client = super::cli_client()?;
 
     let spinner = super::Spinner::new("Fetching quota...");
@@ -10,7 +8,7 @@ pub fn run(args: &[String]) -> Result<()> {
     let tq = client.transfer_quota().ok();
     drop(spinner);
 
-    if json {
+    if super::output_format().is_machine() {
         let storage = quota.quota.as_ref().map(|d| {
             let limit = d
                 .limit
@@ -31,37 +29,24 @@ pub fn run(args: &[String]) -> Result<()> {
                 .parse::<u64>()
                 .unwrap_or(0);
             serde_json::json!({
-                "limit": limit,
-                "used":  used,
-                "trash": trash,
-                "free":  limit.saturating_sub(used),
+                "limit":     limit,
+                "used":      used,
+                "trash":     trash,
+                "free":      limit.saturating_sub(used),
+                "unlimited": d.is_unlimited,
             })
         });
 
-        let bandwidth = tq.as_ref().and_then(|t| t.base.as_ref()).map(|b| {
-            let band = |slot: Option<&crate::pikpak::TransferBand>| {
-                slot.map(|s| {
-                    serde_json::json!({
-                        "used":  s.assets.unwrap_or(0),
-                        "total": s.total_assets.unwrap_or(0),
-                    })
-                })
-            };
-            serde_json::json!({
-                "download":         band(b.download.as_ref()),
-                "download_daily":   band(b.download_daily.as_ref()),
-                "daily_resets_in_seconds": secs_to_daily_reset(),
-                "upload":           band(b.upload.as_ref()),
-                "offline":          band(b.offline.as_ref()),
-                "expire_time":      b.expire_time,
-            })
-        });
+        let bandwidth = tq
+            .as_ref()
+            .and_then(|t| t.base.as_ref())
+            .map(bandwidth_json);
 
         let out = serde_json::json!({
             "storage":   storage,
             "bandwidth": bandwidth,
         });
-        println!("{}", serde_json::to_string_pretty(&out)?);
+        super::print_data(&out);
         return Ok(());
     }
 
@@ -167,6 +152,41 @@ pub fn run(args: &[String]) -> Result<()> {
     Ok(())
 }
 
+/// Bandwidth allowances as JSON, shared with `vip --json`. Byte counts are
+/// raw; a `total` of 0 means no cap.
+pub(super) fn bandwidth_json(b: &crate::pikpak::TransferQuotaBase) -> serde_json::Value {
+    let band = |slot: Option<&crate::pikpak::TransferBand>| {
+        slot.map(|s| {
+            let used = s.assets.unwrap_or(0);
+            let total = s.total_assets.unwrap_or(0);
+            serde_json::json!({
+                "used":  used,
+                "total": total,
+                "left":  total.saturating_sub(used),
+            })
+        })
+    };
+    let mut out = serde_json::json!({
+        "download":         band(b.download.as_ref()),
+        "download_daily":   band(b.download_daily.as_ref()),
+        "daily_resets_in_seconds": secs_to_daily_reset(),
+        "upload":           band(b.upload.as_ref()),
+        "offline":          band(b.offline.as_ref()),
+    });
+    add_expiry(&mut out, b.expire_time.as_deref());
+    out
+}
+
+/// Add `expire_time` as the API gave it, plus `expire_unix` and
+/// `expires_in_seconds` (negative once past) so a script can compare
+/// numbers instead of parsing dates.
+pub(super) fn add_expiry(obj: &mut serde_json::Value, expire: Option<&str>) {
+    let unix = expire.and_then(crate::pikpak::iso_to_unix);
+    obj["expire_time"] = serde_json::json!(expire);
+    obj["expire_unix"] = serde_json::json!(unix);
+    obj["expires_in_seconds"] = serde_json::json!(unix.map(|t| t - super::now_unix()));
+}
+
 fn usage_bar(pct: u64, width: usize) -> String {
     let filled = (pct as usize * width / 100).min(width);
     let empty = width.saturating_sub(filled);
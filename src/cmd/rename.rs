@@ -1,7 +1,7 @@
 use anyhow::{Result, anyhow};
 
 pub fn run(args: &[String]) -> Result<()> {
-    let mut dry_run = false;
+    let mut dry_run = super::dry_run();
     let mut rest: Vec<&str> = Vec::new();
 
     for arg in args {
@@ -17,10 +17,10 @@ pub fn run(args: &[String]) -> Result<()> {
         ));
     }
 
-    let client = super::cli_client()?;
+    let client = super::cli_drive()?;
     let (parent, name) = super::split_parent_name(rest[0])?;
     let parent_id = client.resolve_path(&parent)?;
-    let entry = super::find_entry(&client, &parent_id, &name)?;
+    let entry = super::find_entry(&*client, &parent_id, &name)?;
 
     if dry_run {
         println!(
@@ -31,6 +31,6 @@ pub fn run(args: &[String]) -> Result<()> {
     }
 
     client.rename(&entry.id, rest[1])?;
-    println!("Renamed '{}' -> '{}'", name, rest[1]);
+    super::status(format!("Renamed '{}' -> '{}'", name, rest[1]));
     Ok(())
 }
@@ -1,31 +1,39 @@
 use crate::pikpak::EntryKind;
 use anyhow::{Result, anyhow};
+use std::io::{self, IsTerminal, Write};
+
+const USAGE: &str = "Usage: pikpaktui rm [-n] [-r] [--permanent [-y]] <path...>";
+
+/// What has to be typed to go ahead with a permanent delete.
+const CONFIRM_WORD: &str = "delete";
 
 pub fn run(args: &[String]) -> Result<()> {
     if args.is_empty() {
-        return Err(anyhow!("Usage: pikpaktui rm [-n] [-r] [-f] <path...>"));
+        return Err(anyhow!(USAGE));
     }
 
-    let mut force = false;
+    let mut permanent = false;
     let mut recursive = false;
-    let mut dry_run = false;
+    let mut dry_run = super::dry_run();
+    let mut yes = false;
     let mut paths: Vec<&str> = Vec::new();
 
     for arg in args {
         match arg.as_str() {
-            "-f" | "--force" => force = true,
+            "-f" | "--force" | "--permanent" => permanent = true,
             "-r" | "--recursive" => recursive = true,
             "-rf" | "-fr" => {
                 recursive = true;
-                force = true;
+                permanent = true;
             }
+            "-y" | "--yes" => yes = true,
             "-n" | "--dry-run" => dry_run = true,
             _ => paths.push(arg),
         }
     }
 
     if paths.is_empty() {
-        return Err(anyhow!("Usage: pikpaktui rm [-n] [-r] [-f] <path...>"));
+        return Err(anyhow!(USAGE));
     }
 
     let client = super::cli_client()?;
@@ -43,8 +51,12 @@ pub fn run(args: &[String]) -> Result<()> {
         let parent_id = client.resolve_path(&parent)?;
         let entry = super::find_entry(&client, &parent_id, &name)?;
 
-        if entry.kind == EntryKind::Folder && !recursive {
-            return Err(anyhow!("'{}' is a folder. Use -r to remove folders.", path));
+        // Like rmdir: an empty folder goes without -r, anything in it needs -r.
+        if entry.kind == EntryKind::Folder && !recursive && !client.ls(&entry.id)?.is_empty() {
+            return Err(anyhow!(
+                "'{}' is a non-empty folder. Use -r to remove it with its contents.",
+                path
+            ));
         }
         resolved.push(Resolved {
             path,
@@ -55,7 +67,11 @@ pub fn run(args: &[String]) -> Result<()> {
     }
 
     if dry_run {
-        let action = if force { "permanently delete" } else { "trash" };
+        let action = if permanent {
+            "permanently delete"
+        } else {
+            "trash"
+        };
         println!("[dry-run] Would {} {} item(s):", action, resolved.len());
         for r in &resolved {
             let kind_tag = if r.kind == EntryKind::Folder {
@@ -68,13 +84,42 @@ pub fn run(args: &[String]) -> Result<()> {
         return Ok(());
     }
 
+    if permanent && !yes {
+        if !io::stdin().is_terminal() {
+            return Err(anyhow!(
+                "Refusing to permanently delete without confirmation; pass --yes"
+            ));
+        }
+        println!("About to permanently delete {} item(s):", resolved.len());
+        for r in &resolved {
+            println!("  {} (id: {})", r.path, r.id);
+        }
+        if !confirm_typed()? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+    }
+
     let ids: Vec<&str> = resolved.iter().map(|r| r.id.as_str()).collect();
-    if force {
+    if permanent {
         client.delete_permanent(&ids)?;
-        println!("Permanently deleted {} item(s)", paths.len());
+        super::status(format!("Permanently deleted {} item(s)", resolved.len()));
     } else {
         client.remove(&ids)?;
-        println!("Removed {} item(s) (to trash)", paths.len());
+        // One line per item, ids included, so a script can `untrash` them.
+        for r in &resolved {
+            println!("Trashed {} (id: {})", r.path, r.id);
+        }
     }
     Ok(())
 }
+
+/// Ask for `CONFIRM_WORD` to be typed out; a bare `y` isn't enough for
+/// something that can't be undone.
+pub(super) fn confirm_typed() -> Result<bool> {
+    print!("This cannot be undone. Type '{CONFIRM_WORD}' to confirm: ");
+    io::stdout().flush()?;
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim() == CONFIRM_WORD)
+}
@@ -1,20 +1,37 @@
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
+use std::collections::HashSet;
 use std::io::Write as _;
+use std::path::{Path, PathBuf};
 
 pub fn run(args: &[String]) -> Result<()> {
     if args.is_empty() {
         return Err(anyhow!(
-            "Usage:\n  pikpaktui share [-p] [-d <days>] [-J] [-o <file>] <path...>\n  pikpaktui share -S [-n] [-p <code>] [-t <path>] [-J] <url>\n  pikpaktui share -l [-J]\n  pikpaktui share -D <share_id...>"
+            "Usage:\n  pikpaktui share [create] [-p] [-d <days> | --expires <7d>] [-J|--plain] [-o <file>] <path...>\n  pikpaktui share -S [-n] [-p <code>] [-t <path>] [-J|--plain] <url>\n  pikpaktui share list [-J|--plain]\n  pikpaktui share import --from-file <file> [-t <path>] [-n] [--restart]\n  pikpaktui share revoke <share_id|url...>"
         ));
     }
 
+    match args[0].as_str() {
+        "create" => return run_create(&args[1..]),
+        "list" | "ls" => return run_list(),
+        "revoke" => {
+            super::refuse_machine_output("share revoke")?;
+            return run_delete(&args[1..]);
+        }
+        "import" => {
+            super::refuse_machine_output("share import")?;
+            return run_import(&args[1..]);
+        }
+        _ => {}
+    }
+
     let list_mode = args.iter().any(|a| a == "-l" || a == "--list");
     let delete_mode = args.iter().any(|a| a == "-D" || a == "--delete");
     let save_mode = args.iter().any(|a| a == "-S" || a == "--save");
 
     if list_mode {
-        run_list(args)
+        run_list()
     } else if delete_mode {
+        super::refuse_machine_output("share --delete")?;
         run_delete(args)
     } else if save_mode {
         run_save(args)
@@ -23,24 +40,44 @@ pub fn run(args: &[String]) -> Result<()> {
     }
 }
 
+/// Parse a share lifetime: `7`, `7d`, `2w`, or `never` / `-1` for permanent.
+fn parse_expiry(s: &str) -> Result<i64> {
+    if s == "never" || s == "-1" {
+        return Ok(-1);
+    }
+    let (num, unit) = match s.strip_suffix(['d', 'w']) {
+        Some(num) => (num, if s.ends_with('w') { 7 } else { 1 }),
+        None => (s, 1),
+    };
+    match num.parse::<i64>() {
+        Ok(n) if n > 0 => Ok(n * unit),
+        _ => Err(anyhow!("invalid expiry '{}': use e.g. 7d, 2w, or never", s)),
+    }
+}
+
 fn run_create(args: &[String]) -> Result<()> {
     let mut paths: Vec<&str> = Vec::new();
     let mut need_password = false;
     let mut expiration_days: i64 = -1;
     let mut output_file: Option<&str> = None;
-    let mut json = false;
+    let json = super::output_format().is_machine();
     let mut iter = args.iter();
 
     while let Some(arg) = iter.next() {
         match arg.as_str() {
             "-p" | "--password" => need_password = true,
-            "-J" | "--json" => json = true,
             "-d" | "--days" => {
                 let val = iter.next().ok_or_else(|| anyhow!("-d requires a number"))?;
                 expiration_days = val
                     .parse::<i64>()
                     .map_err(|_| anyhow!("-d requires an integer"))?;
             }
+            "--expires" => {
+                let val = iter
+                    .next()
+                    .ok_or_else(|| anyhow!("--expires requires a duration"))?;
+                expiration_days = parse_expiry(val)?;
+            }
             "-o" => {
                 output_file = Some(
                     iter.next()
@@ -76,7 +113,7 @@ fn run_create(args: &[String]) -> Result<()> {
             "pass_code": if result.pass_code.is_empty() { None } else { Some(&result.pass_code) },
             "share_text": if result.share_text.is_empty() { None } else { Some(&result.share_text) },
         });
-        println!("{}", serde_json::to_string_pretty(&out)?);
+        super::print_data(&out);
     } else {
         println!("\x1b[1;36m{}\x1b[0m", result.share_url);
         if !result.pass_code.is_empty() {
@@ -85,6 +122,12 @@ fn run_create(args: &[String]) -> Result<()> {
                 result.pass_code
             );
         }
+        // The link is the point of the command, so a missing clipboard tool
+        // is only worth a note.
+        match super::link::copy_to_clipboard(&result.share_url) {
+            Ok(()) => super::notice("Copied to clipboard."),
+            Err(e) => eprintln!("Not copied: {e:#}"),
+        }
     }
 
     // Honor -o in both JSON and human-readable modes; the notice goes to stderr
@@ -96,7 +139,7 @@ fn run_create(args: &[String]) -> Result<()> {
         if !result.pass_code.is_empty() {
             writeln!(f, "Password: {}", result.pass_code)?;
         }
-        eprintln!("Written to '{}'", out_path);
+        super::notice(format!("Written to '{}'", out_path));
     }
 
     Ok(())
@@ -107,14 +150,13 @@ fn run_save(args: &[String]) -> Result<()> {
     let mut pass_code = "";
     let mut to_path: Option<&str> = None;
     let mut dry_run = false;
-    let mut json = false;
+    let json = super::output_format().is_machine();
     let mut iter = args.iter();
 
     while let Some(arg) = iter.next() {
         match arg.as_str() {
             "-S" | "--save" => {}
             "-n" | "--dry-run" => dry_run = true,
-            "-J" | "--json" => json = true,
             "-p" | "--pass-code" => {
                 pass_code = iter
                     .next()
@@ -140,12 +182,7 @@ fn run_save(args: &[String]) -> Result<()> {
 
     let share_url = share_url.ok_or_else(|| anyhow!("no share URL or ID provided"))?;
 
-    let share_id = if share_url.contains("mypikpak.com/s/") {
-        let trimmed = share_url.trim_end_matches('/');
-        trimmed.rsplit('/').next().unwrap_or(trimmed)
-    } else {
-        share_url
-    };
+    let share_id = share_id_of(share_url);
 
     let client = super::cli_client()?;
 
@@ -156,7 +193,7 @@ fn run_save(args: &[String]) -> Result<()> {
     let dest_display = to_path.unwrap_or("/");
 
     if !json {
-        println!("Fetching share info for '{}'...", share_id);
+        super::status(format!("Fetching share info for '{}'...", share_id));
     }
     let info = client.share_info(share_id, pass_code)?;
 
@@ -164,7 +201,7 @@ fn run_save(args: &[String]) -> Result<()> {
         return Err(anyhow!("share contains no files"));
     }
 
-    if dry_run || !json {
+    if dry_run || !(json || super::quiet()) {
         println!("Found {} item(s):", info.files.len());
         for f in &info.files {
             println!("  {}", f.name);
@@ -182,7 +219,7 @@ fn run_save(args: &[String]) -> Result<()> {
 
     let file_ids: Vec<&str> = info.files.iter().map(|f| f.id.as_str()).collect();
     if !json {
-        println!("Saving to '{}'...", dest_display);
+        super::status(format!("Saving to '{}'...", dest_display));
     }
     client.save_share(share_id, &info.pass_code_token, &file_ids, &to_parent_id)?;
 
@@ -195,35 +232,205 @@ fn run_save(args: &[String]) -> Result<()> {
                 "name": f.name,
             })).collect::<Vec<_>>(),
         });
-        println!("{}", serde_json::to_string_pretty(&out)?);
+        super::print_report(&out, "files");
     } else {
-        println!("Saved {} item(s) to '{}'", info.files.len(), dest_display);
+        super::status(format!(
+            "Saved {} item(s) to '{}'",
+            info.files.len(),
+            dest_display
+        ));
     }
 
     Ok(())
 }
 
-fn run_list(args: &[String]) -> Result<()> {
-    use unicode_width::UnicodeWidthStr;
+/// One line of an import list: a share and its pass code, if any.
+#[derive(Debug, PartialEq)]
+struct ShareLink {
+    id: String,
+    pass_code: String,
+}
 
-    let json = args.iter().any(|a| a == "-J" || a == "--json");
+/// Parse an import list: a share URL or id per line, optionally followed
+/// by its pass code (or with `?pwd=` in the URL). Blank lines, `#`
+/// comments and repeats are skipped.
+fn parse_share_list(text: &str) -> Vec<ShareLink> {
+    let mut seen = HashSet::new();
+    let mut links = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let Some(url) = words.next() else { continue };
+        let (url, query) = url.split_once('?').unwrap_or((url, ""));
+        let pass_code = words
+            .next()
+            .or_else(|| query.split('&').find_map(|kv| kv.strip_prefix("pwd=")))
+            .unwrap_or("")
+            .to_string();
+        let id = share_id_of(url).to_string();
+        if seen.insert(id.clone()) {
+            links.push(ShareLink { id, pass_code });
+        }
+    }
+    links
+}
 
-    let client = super::cli_client()?;
+/// Where `share import` records the shares it has saved into `dest`, so a
+/// rerun after an interruption or failures picks up with the rest. Each
+/// destination has its own record: the same list imported somewhere else
+/// starts from the top.
+fn import_progress_path(list: &Path, dest: &str) -> PathBuf {
+    use md5::{Digest, Md5};
+    let dest = format!("/{}", dest.trim().trim_matches('/'));
+    let hash = Md5::digest(dest.as_bytes());
+    let mut name = list.as_os_str().to_owned();
+    name.push(format!(
+        ".{:02x}{:02x}{:02x}{:02x}.done",
+        hash[0], hash[1], hash[2], hash[3]
+    ));
+    PathBuf::from(name)
+}
 
-    let spinner = super::Spinner::new("Fetching shares...");
-    let shares = client.list_shares()?;
-    drop(spinner);
+fn run_import(args: &[String]) -> Result<()> {
+    let mut from: Option<&str> = None;
+    let mut to_path: Option<&str> = None;
+    let mut dry_run = super::dry_run();
+    let mut restart = false;
+    let mut iter = args.iter();
 
-    if shares.is_empty() {
-        if json {
-            println!("[]");
-        } else {
-            println!("No shares found.");
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-f" | "--from-file" => {
+                from = Some(
+                    iter.next()
+                        .ok_or_else(|| anyhow!("--from-file requires a file"))?
+                        .as_str(),
+                );
+            }
+            "-t" | "--to" => {
+                to_path = Some(
+                    iter.next()
+                        .ok_or_else(|| anyhow!("-t requires a path"))?
+                        .as_str(),
+                );
+            }
+            "-n" | "--dry-run" => dry_run = true,
+            "--restart" => restart = true,
+            other => return Err(anyhow!("unexpected argument: {other}")),
         }
+    }
+
+    let from = from.ok_or_else(|| anyhow!("share import requires --from-file <file>"))?;
+    let text = std::fs::read_to_string(from).with_context(|| format!("failed to read {from}"))?;
+    let links = parse_share_list(&text);
+    if links.is_empty() {
+        return Err(anyhow!("no share links found in {from}"));
+    }
+
+    let progress = import_progress_path(Path::new(from), to_path.unwrap_or("/"));
+    if restart && !dry_run {
+        let _ = std::fs::remove_file(&progress);
+    }
+    let done: HashSet<String> = match std::fs::read_to_string(&progress) {
+        Ok(text) if !restart => text.lines().map(str::to_string).collect(),
+        _ => HashSet::new(),
+    };
+    let pending: Vec<&ShareLink> = links.iter().filter(|l| !done.contains(&l.id)).collect();
+    if pending.len() < links.len() {
+        super::status(format!(
+            "Resuming: {} of {} share(s) already imported (recorded in '{}')",
+            links.len() - pending.len(),
+            links.len(),
+            progress.display()
+        ));
+    }
+    if pending.is_empty() {
+        let _ = std::fs::remove_file(&progress);
         return Ok(());
     }
 
-    if json {
+    let client = super::cli_client()?;
+    let to_parent_id = match to_path {
+        Some(path) => client.resolve_path(path)?,
+        None => String::new(),
+    };
+    let dest_display = to_path.unwrap_or("/");
+
+    if dry_run {
+        for link in &pending {
+            println!("[dry-run] Would import '{}' -> '{}'", link.id, dest_display);
+        }
+        return Ok(());
+    }
+
+    let mut record = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&progress)
+        .with_context(|| format!("cannot write {}", progress.display()))?;
+
+    // One at a time, carrying on past failures; each saved share is
+    // recorded straight away so an interrupted run loses nothing.
+    let mut failed = 0usize;
+    for (i, link) in pending.iter().enumerate() {
+        let step = format!("[{}/{}]", i + 1, pending.len());
+        let result = client
+            .share_info(&link.id, &link.pass_code)
+            .and_then(|info| {
+                if info.files.is_empty() {
+                    return Err(anyhow!("share contains no files"));
+                }
+                let ids: Vec<&str> = info.files.iter().map(|f| f.id.as_str()).collect();
+                client.save_share(&link.id, &info.pass_code_token, &ids, &to_parent_id)?;
+                Ok(info.files)
+            });
+        match result {
+            Ok(files) => {
+                writeln!(record, "{}", link.id)?;
+                let more = match files.len() {
+                    1 => String::new(),
+                    n => format!(" and {} more", n - 1),
+                };
+                super::status(format!(
+                    "{step} \x1b[32m✓\x1b[0m {}  \x1b[2m{}{more}\x1b[0m",
+                    link.id, files[0].name
+                ));
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("{step} \x1b[31m✗\x1b[0m {}: {e:#}", link.id);
+            }
+        }
+    }
+
+    super::status(format!(
+        "Imported {} of {} share(s) to '{}'",
+        pending.len() - failed,
+        pending.len(),
+        dest_display
+    ));
+    if failed > 0 {
+        return Err(anyhow!(
+            "{failed} share(s) failed; run the same command again to retry them"
+        ));
+    }
+    drop(record);
+    let _ = std::fs::remove_file(&progress);
+    Ok(())
+}
+
+fn run_list() -> Result<()> {
+    use unicode_width::UnicodeWidthStr;
+
+    let client = super::cli_client()?;
+
+    let spinner = super::Spinner::new("Fetching shares...");
+    let shares = client.list_shares()?;
+    drop(spinner);
+
+    if super::output_format().is_machine() {
         let out: Vec<_> = shares
             .iter()
             .map(|s| {
@@ -242,7 +449,12 @@ fn run_list(args: &[String]) -> Result<()> {
                 })
             })
             .collect();
-        println!("{}", serde_json::to_string_pretty(&out)?);
+        super::print_data(&out.into());
+        return Ok(());
+    }
+
+    if shares.is_empty() {
+        println!("No shares found.");
         return Ok(());
     }
 
@@ -337,19 +549,88 @@ fn run_list(args: &[String]) -> Result<()> {
     Ok(())
 }
 
+/// The share id from a `https://mypikpak.com/s/<id>` URL; anything else is
+/// taken to be an id already.
+fn share_id_of(url_or_id: &str) -> &str {
+    if url_or_id.contains("mypikpak.com/s/") {
+        let trimmed = url_or_id.trim_end_matches('/');
+        trimmed.rsplit('/').next().unwrap_or(trimmed)
+    } else {
+        url_or_id
+    }
+}
+
 fn run_delete(args: &[String]) -> Result<()> {
     let ids: Vec<&str> = args
         .iter()
         .filter(|a| *a != "-D" && *a != "--delete")
-        .map(|a| a.as_str())
+        .map(|a| share_id_of(a))
         .collect();
 
     if ids.is_empty() {
-        return Err(anyhow!("share -D requires at least one share_id"));
+        return Err(anyhow!(
+            "share revoke requires at least one share id or URL"
+        ));
     }
 
     let client = super::cli_client()?;
     client.delete_shares(&ids)?;
-    println!("Deleted {} share(s).", ids.len());
+    super::status(format!("Deleted {} share(s).", ids.len()));
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ShareLink, import_progress_path, parse_expiry, parse_share_list, share_id_of};
+    use std::path::Path;
+
+    #[test]
+    fn import_progress_is_kept_per_destination() {
+        let list = Path::new("/tmp/shares.txt");
+        let root = import_progress_path(list, "/");
+        assert_eq!(root, import_progress_path(list, ""));
+        assert_eq!(
+            import_progress_path(list, "/Movies"),
+            import_progress_path(list, "Movies/")
+        );
+        assert_ne!(root, import_progress_path(list, "/Movies"));
+        let name = root.file_name().unwrap().to_str().unwrap();
+        assert!(
+            name.starts_with("shares.txt.") && name.ends_with(".done"),
+            "{name}"
+        );
+    }
+
+    #[test]
+    fn expiry_accepts_days_weeks_and_never() {
+        assert_eq!(parse_expiry("7").unwrap(), 7);
+        assert_eq!(parse_expiry("7d").unwrap(), 7);
+        assert_eq!(parse_expiry("2w").unwrap(), 14);
+        assert_eq!(parse_expiry("never").unwrap(), -1);
+        assert!(parse_expiry("0d").is_err());
+        assert!(parse_expiry("3h").is_err());
+    }
+
+    #[test]
+    fn share_id_comes_from_url_or_bare_id() {
+        assert_eq!(share_id_of("https://mypikpak.com/s/VNa1b2c3/"), "VNa1b2c3");
+        assert_eq!(share_id_of("VNa1b2c3"), "VNa1b2c3");
+    }
+
+    #[test]
+    fn share_list_reads_codes_and_skips_repeats() {
+        let text = "# from Sam\nhttps://mypikpak.com/s/VNaaa\n\nhttps://mypikpak.com/s/VNbbb 1234\nhttps://mypikpak.com/s/VNccc?pwd=wxyz\nVNaaa\n";
+        let link = |id: &str, code: &str| ShareLink {
+            id: id.into(),
+            pass_code: code.into(),
+        };
+        assert_eq!(
+            parse_share_list(text),
+            [
+                link("VNaaa", ""),
+                link("VNbbb", "1234"),
+                link("VNccc", "wxyz")
+            ]
+        );
+    }
+}
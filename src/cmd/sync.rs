@@ -0,0 +1,349 @@
+use anyhow::{Context, Result, anyhow};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use super::ls::{TreeNode, walk_sizes};
+use crate::pikpak::{Entry, EntryKind, pikpak_hash, sanitize_filename, unique_name};
+
+const USAGE: &str = "Usage: pikpaktui sync [-n|--dry-run] [--delete] <remote> <local>";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    /// Not present locally.
+    Download,
+    /// Present locally but differs in size, hash or kind.
+    Update,
+    /// Present locally only; removed with `--delete`.
+    Delete,
+}
+
+#[derive(Debug)]
+struct Step {
+    action: Action,
+    rel: PathBuf,
+    /// The remote side; `None` for deletions.
+    entry: Option<Entry>,
+}
+
+/// Remote items keyed by the relative path they map to locally, named the
+/// way `download` names them (sanitized, duplicates get ` (n)`).
+fn flatten(nodes: Vec<TreeNode>, prefix: &Path, out: &mut BTreeMap<PathBuf, Entry>) {
+    let mut taken = HashSet::new();
+    // Folders first, matching `download`, so a file can't take a folder's name.
+    let (folders, files): (Vec<_>, Vec<_>) = nodes
+        .into_iter()
+        .partition(|n| n.entry.kind == EntryKind::Folder);
+    for node in folders.into_iter().chain(files) {
+        let rel = prefix.join(unique_name(
+            &sanitize_filename(&node.entry.name),
+            &mut taken,
+        ));
+        flatten(node.children, &rel, out);
+        out.insert(rel, node.entry);
+    }
+}
+
+/// Everything under `root`, relative to it, with `true` for directories.
+pub(super) fn local_tree(root: &Path) -> Result<BTreeMap<PathBuf, bool>> {
+    fn walk(root: &Path, rel: &Path, out: &mut BTreeMap<PathBuf, bool>) -> Result<()> {
+        let dir = root.join(rel);
+        let entries =
+            std::fs::read_dir(&dir).with_context(|| format!("cannot read '{}'", dir.display()))?;
+        for entry in entries {
+            let entry = entry?;
+            let rel = rel.join(entry.file_name());
+            let is_dir = entry.file_type()?.is_dir();
+            out.insert(rel.clone(), is_dir);
+            if is_dir {
+                walk(root, &rel, out)?;
+            }
+        }
+        Ok(())
+    }
+    let mut out = BTreeMap::new();
+    if root.exists() {
+        walk(root, Path::new(""), &mut out)?;
+    }
+    Ok(out)
+}
+
+/// What it takes to make `local` mirror `remote`. A file of the same size is
+/// compared by hash when the server has one; `local_hash` computes the local
+/// side. A local folder missing remotely is deleted as a whole.
+fn plan(
+    remote: BTreeMap<PathBuf, Entry>,
+    local: &BTreeMap<PathBuf, bool>,
+    delete: bool,
+    mut local_hash: impl FnMut(&Path) -> Option<String>,
+    local_size: impl Fn(&Path) -> u64,
+) -> Vec<Step> {
+    let mut steps = Vec::new();
+    if delete {
+        let mut gone: Vec<&PathBuf> = Vec::new();
+        for rel in local.keys() {
+            if remote.contains_key(rel) || gone.iter().any(|g| rel.starts_with(g)) {
+                continue;
+            }
+            gone.push(rel);
+            steps.push(Step {
+                action: Action::Delete,
+                rel: rel.clone(),
+                entry: None,
+            });
+        }
+    }
+    for (rel, entry) in remote {
+        let folder = entry.kind == EntryKind::Folder;
+        let action = match local.get(&rel) {
+            None => Action::Download,
+            Some(&is_dir) if is_dir != folder => Action::Update,
+            Some(_) if folder => continue,
+            Some(_) => {
+                let same = local_size(&rel) == entry.size
+                    && (entry.hash.is_empty()
+                        || local_hash(&rel).is_some_and(|h| h.eq_ignore_ascii_case(&entry.hash)));
+                if same {
+                    continue;
+                }
+                Action::Update
+            }
+        };
+        steps.push(Step {
+            action,
+            rel,
+            entry: Some(entry),
+        });
+    }
+    steps
+}
+
+/// Where a newer copy of `dest` is downloaded before replacing it: next to
+/// it, so the rename stays on one filesystem, and hidden.
+fn part_path(dest: &Path) -> PathBuf {
+    let name = dest.file_name().unwrap_or_default().to_string_lossy();
+    dest.with_file_name(format!(".{name}.pikpaktui-part"))
+}
+
+fn remove(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    let mut dry_run = super::dry_run();
+    let mut delete = false;
+    let mut paths: Vec<&str> = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-n" | "--dry-run" => dry_run = true,
+            "--delete" => delete = true,
+            s if s.starts_with('-') => {
+                return Err(anyhow!("unknown option for sync: {s}\n{USAGE}"));
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let [remote_path, local_path] = paths[..] else {
+        return Err(anyhow!(
+            "sync needs a remote folder and a local directory\n{USAGE}"
+        ));
+    };
+    let local_root = PathBuf::from(local_path);
+    if local_root.exists() && !local_root.is_dir() {
+        return Err(anyhow!("'{}' is not a directory", local_root.display()));
+    }
+
+    let client = super::cli_client()?;
+    let folder_id = client.resolve_path(remote_path)?;
+    let spinner = super::Spinner::new("Comparing...");
+    let mut remote = BTreeMap::new();
+    flatten(walk_sizes(&client, &folder_id)?, Path::new(""), &mut remote);
+    let local = local_tree(&local_root)?;
+    let steps = plan(
+        remote,
+        &local,
+        delete,
+        |rel| pikpak_hash(&local_root.join(rel)).ok(),
+        |rel| {
+            local_root
+                .join(rel)
+                .metadata()
+                .map(|m| m.len())
+                .unwrap_or(0)
+        },
+    );
+    drop(spinner);
+
+    if steps.is_empty() {
+        super::status(format!("'{}' is up to date", local_root.display()));
+        return Ok(());
+    }
+
+    let mut bytes = 0u64;
+    let mut failed = 0usize;
+    for step in &steps {
+        let dest = local_root.join(&step.rel);
+        let (mark, detail) = match (&step.action, &step.entry) {
+            (Action::Delete, _) => ("\x1b[31m-\x1b[0m", String::new()),
+            (action, Some(e)) => (
+                if *action == Action::Download {
+                    "\x1b[32m+\x1b[0m"
+                } else {
+                    "\x1b[33m~\x1b[0m"
+                },
+                if e.kind == EntryKind::Folder {
+                    "/".to_string()
+                } else {
+                    format!(" ({})", super::format_size(e.size))
+                },
+            ),
+            (_, None) => unreachable!("only deletions have no remote entry"),
+        };
+        if dry_run || !super::quiet() {
+            println!(
+                "{}{} {}{}",
+                if dry_run { "[dry-run] " } else { "" },
+                mark,
+                step.rel.display(),
+                detail
+            );
+        }
+        if dry_run {
+            continue;
+        }
+
+        let result = (|| -> Result<()> {
+            let entry = match &step.entry {
+                Some(entry) if step.action == Action::Update && entry.kind == EntryKind::File => {
+                    // The local copy stays until the new one is complete.
+                    let part = part_path(&dest);
+                    bytes += client.download_to(&entry.id, &part)?;
+                    if dest.is_dir() {
+                        remove(&dest)
+                            .with_context(|| format!("cannot remove '{}'", dest.display()))?;
+                    }
+                    std::fs::rename(&part, &dest)
+                        .with_context(|| format!("cannot replace '{}'", dest.display()))?;
+                    return Ok(());
+                }
+                entry => entry,
+            };
+            if step.action != Action::Download && dest.exists() {
+                remove(&dest).with_context(|| format!("cannot remove '{}'", dest.display()))?;
+            }
+            let Some(entry) = entry else {
+                return Ok(());
+            };
+            if entry.kind == EntryKind::Folder {
+                std::fs::create_dir_all(&dest)?;
+                return Ok(());
+            }
+            if let Some(parent) = dest.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            bytes += client.download_to(&entry.id, &dest)?;
+            Ok(())
+        })();
+        if let Err(e) = result {
+            eprintln!("  [error] '{}': {e:#}", step.rel.display());
+            failed += 1;
+        }
+    }
+
+    let count = |a: Action| steps.iter().filter(|s| s.action == a).count();
+    let summary = format!(
+        "{} new, {} updated, {} deleted",
+        count(Action::Download),
+        count(Action::Update),
+        count(Action::Delete)
+    );
+    if dry_run {
+        println!("Would sync: {summary}");
+        return Ok(());
+    }
+    super::status(format!(
+        "Synced '{}' -> '{}': {summary} ({} downloaded)",
+        remote_path,
+        local_root.display(),
+        super::format_size(bytes)
+    ));
+    if failed > 0 {
+        return Err(anyhow!("{} item(s) failed to sync", failed));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::ls::node;
+
+    #[test]
+    fn plan_diffs_by_size_hash_and_presence() {
+        let mut remote = BTreeMap::new();
+        flatten(
+            vec![
+                node(
+                    Entry::folder("docs"),
+                    vec![
+                        node(
+                            Entry::file("same.txt").with_size(3).with_hash("AAA"),
+                            vec![],
+                        ),
+                        node(
+                            Entry::file("edited.txt").with_size(3).with_hash("BBB"),
+                            vec![],
+                        ),
+                    ],
+                ),
+                node(Entry::file("new.bin").with_size(9), vec![]),
+                node(Entry::file("grown.log").with_size(5), vec![]),
+            ],
+            Path::new(""),
+            &mut remote,
+        );
+        let local: BTreeMap<PathBuf, bool> = [
+            ("docs", true),
+            ("docs/same.txt", false),
+            ("docs/edited.txt", false),
+            ("grown.log", false),
+            ("old", true),
+            ("old/x.txt", false),
+        ]
+        .into_iter()
+        .map(|(p, d)| (PathBuf::from(p), d))
+        .collect();
+
+        let steps = plan(
+            remote,
+            &local,
+            true,
+            |_| Some("aaa".into()),
+            |rel| if rel == Path::new("grown.log") { 4 } else { 3 },
+        );
+        let got: Vec<(Action, &str)> = steps
+            .iter()
+            .map(|s| (s.action, s.rel.to_str().unwrap()))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                (Action::Delete, "old"),
+                (Action::Update, "docs/edited.txt"),
+                (Action::Update, "grown.log"),
+                (Action::Download, "new.bin"),
+            ]
+        );
+    }
+
+    #[test]
+    fn updates_download_next_to_the_file_they_replace() {
+        assert_eq!(
+            part_path(Path::new("/sync/shows/ep01.mkv")),
+            Path::new("/sync/shows/.ep01.mkv.pikpaktui-part")
+        );
+    }
+}
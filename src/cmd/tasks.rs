@@ -1,24 +1,35 @@
-use anyhow::Result;
+use super::metrics::Metrics;
+use crate::pikpak::{Direction, OfflineTask, PikPak, TransferRecord, load_history};
+use anyhow::{Result, anyhow};
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use unicode_width::UnicodeWidthStr;
 
 pub fn run(args: &[String]) -> Result<()> {
+    // Reads only the local transfer history, so it works signed out too.
+    if args.first().is_some_and(|a| a == "stats") {
+        return stats(&args[1..]);
+    }
+
     let client = super::cli_client()?;
 
+    if args.iter().any(|a| a == "--auto-fetch") {
+        super::refuse_machine_output("tasks --auto-fetch")?;
+        return auto_fetch(&client, args);
+    }
+
     let sub = args.first().map(|s| s.as_str()).unwrap_or("list");
     let rest = if args.is_empty() { &[][..] } else { &args[1..] };
 
     match sub {
         "list" | "ls" => {
             let mut limit = 50u32;
-            let mut json = false;
             for a in rest {
-                match a.as_str() {
-                    "-J" | "--json" => json = true,
-                    _ => {
-                        if let Ok(n) = a.parse::<u32>() {
-                            limit = n;
-                        }
-                    }
+                if let Ok(n) = a.parse::<u32>() {
+                    limit = n;
                 }
             }
 
@@ -33,9 +44,8 @@ pub fn run(args: &[String]) -> Result<()> {
             let resp = client.offline_list(limit, phases)?;
             drop(spinner);
 
-            if json {
-                let out = serde_json::to_string_pretty(&resp.tasks).unwrap_or_else(|_| "[]".into());
-                println!("{}", out);
+            if super::output_format().is_machine() {
+                super::print_data(&serde_json::to_value(&resp.tasks)?);
                 return Ok(());
             }
 
@@ -145,6 +155,7 @@ pub fn run(args: &[String]) -> Result<()> {
             Ok(())
         }
         "retry" => {
+            super::refuse_machine_output("tasks retry")?;
             let mut dry_run = false;
             let mut rest_args: Vec<&str> = Vec::new();
             for a in rest {
@@ -162,10 +173,11 @@ pub fn run(args: &[String]) -> Result<()> {
                 return Ok(());
             }
             client.offline_task_retry(task_id)?;
-            println!("Task {} retried", task_id);
+            super::status(format!("Task {} retried", task_id));
             Ok(())
         }
         "delete" | "rm" => {
+            super::refuse_machine_output("tasks delete")?;
             let mut dry_run = false;
             let mut ids: Vec<&str> = Vec::new();
             for a in rest {
@@ -187,11 +199,363 @@ pub fn run(args: &[String]) -> Result<()> {
                 return Ok(());
             }
             client.delete_tasks(&ids, false)?;
-            println!("Deleted {} task(s)", ids.len());
+            super::status(format!("Deleted {} task(s)", ids.len()));
             Ok(())
         }
         _ => Err(anyhow::anyhow!(
-            "unknown tasks sub-command: {sub}\nUsage: pikpaktui tasks [list|retry|delete]"
+            "unknown tasks sub-command: {sub}\nUsage: pikpaktui tasks [list|retry|delete|stats] | --auto-fetch <dir>"
         )),
     }
 }
+
+/// Totals of the transfers that finished in one day or week.
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+struct PeriodStats {
+    /// First day of the period, `YYYY-MM-DD` (UTC).
+    period: String,
+    downloads: usize,
+    download_bytes: u64,
+    uploads: usize,
+    upload_bytes: u64,
+    /// Bytes per second over the time spent transferring; instant uploads
+    /// don't count towards it.
+    avg_speed: u64,
+    /// Downloads whose hash didn't match PikPak's.
+    hash_mismatches: usize,
+}
+
+/// Group `records` by UTC day, or by week starting on Monday, newest first.
+fn period_stats(records: &[TransferRecord], weekly: bool) -> Vec<PeriodStats> {
+    let mut groups: BTreeMap<i64, (PeriodStats, f64, f64)> = BTreeMap::new();
+    for r in records {
+        let day = (r.finished_at / 86_400) as i64;
+        // 1970-01-01 was a Thursday, three days after a Monday.
+        let start = if weekly {
+            day - (day + 3).rem_euclid(7)
+        } else {
+            day
+        };
+        let (stats, bytes, secs) = groups.entry(start).or_default();
+        match r.direction {
+            Direction::Download => {
+                stats.downloads += 1;
+                stats.download_bytes += r.size;
+            }
+            Direction::Upload => {
+                stats.uploads += 1;
+                stats.upload_bytes += r.size;
+            }
+        }
+        stats.hash_mismatches += usize::from(r.verified == Some(false));
+        *bytes += r.avg_speed as f64 * r.duration_secs;
+        *secs += r.duration_secs;
+    }
+    groups
+        .into_iter()
+        .rev()
+        .map(|(start, (mut stats, bytes, secs))| {
+            let (y, m, d) = crate::pikpak::civil_from_days(start);
+            stats.period = format!("{y:04}-{m:02}-{d:02}");
+            if secs > 0.0 {
+                stats.avg_speed = (bytes / secs) as u64;
+            }
+            stats
+        })
+        .collect()
+}
+
+fn stats(args: &[String]) -> Result<()> {
+    let mut weekly = false;
+    let mut limit = 14usize;
+    for a in args {
+        match a.as_str() {
+            "-w" | "--week" | "--weekly" => weekly = true,
+            "-d" | "--day" | "--daily" => weekly = false,
+            _ => {
+                limit = a
+                    .parse()
+                    .map_err(|_| anyhow!("unknown option for tasks stats: {a}"))?;
+            }
+        }
+    }
+
+    let mut periods = period_stats(&load_history(), weekly);
+    periods.truncate(limit);
+    if super::output_format().is_machine() {
+        super::print_data(&serde_json::to_value(&periods)?);
+        return Ok(());
+    }
+    if periods.is_empty() {
+        println!("No transfers recorded yet");
+        return Ok(());
+    }
+
+    let count = |n: usize, bytes: u64| {
+        if n == 0 {
+            "-".to_string()
+        } else {
+            format!("{n} ({})", super::format_size(bytes))
+        }
+    };
+    let rows: Vec<[String; 5]> = periods
+        .iter()
+        .map(|p| {
+            [
+                p.period.clone(),
+                count(p.downloads, p.download_bytes),
+                count(p.uploads, p.upload_bytes),
+                if p.avg_speed == 0 {
+                    "-".to_string()
+                } else {
+                    crate::ratelimit::format_rate(p.avg_speed)
+                },
+                if p.hash_mismatches == 0 {
+                    String::new()
+                } else {
+                    format!("\x1b[31m{} hash mismatch(es)\x1b[0m", p.hash_mismatches)
+                },
+            ]
+        })
+        .collect();
+    let header = [
+        if weekly { "Week of" } else { "Day" },
+        "Downloads",
+        "Uploads",
+        "Avg speed",
+    ];
+    let widths: Vec<usize> = (0..4)
+        .map(|i| {
+            rows.iter()
+                .map(|r| r[i].width())
+                .chain([header[i].width()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    println!(
+        "\x1b[1m{:<w0$}  {:<w1$}  {:<w2$}  {:<w3$}\x1b[0m",
+        header[0],
+        header[1],
+        header[2],
+        header[3],
+        w0 = widths[0],
+        w1 = widths[1],
+        w2 = widths[2],
+        w3 = widths[3],
+    );
+    for r in &rows {
+        let line = format!(
+            "{:<w0$}  {:<w1$}  {:<w2$}  {:<w3$}  {}",
+            r[0],
+            r[1],
+            r[2],
+            r[3],
+            r[4],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3],
+        );
+        println!("{}", line.trim_end());
+    }
+
+    let total = |f: fn(&PeriodStats) -> (usize, u64)| {
+        periods
+            .iter()
+            .map(f)
+            .fold((0, 0), |a, b| (a.0 + b.0, a.1 + b.1))
+    };
+    let (downloads, down_bytes) = total(|p| (p.downloads, p.download_bytes));
+    let (uploads, up_bytes) = total(|p| (p.uploads, p.upload_bytes));
+    println!(
+        "\x1b[2mTotal: {} download(s) ({}), {} upload(s) ({})\x1b[0m",
+        downloads,
+        super::format_size(down_bytes),
+        uploads,
+        super::format_size(up_bytes)
+    );
+    Ok(())
+}
+
+/// Completed offline tasks that produced a file, optionally only those whose
+/// name matches the glob `pattern`.
+pub fn completed_tasks(client: &PikPak, pattern: Option<&str>) -> Result<Vec<OfflineTask>> {
+    Ok(client
+        .offline_list_all(&["PHASE_TYPE_COMPLETE"])?
+        .into_iter()
+        .filter(|t| t.file_id.as_deref().is_some_and(|id| !id.is_empty()))
+        .filter(|t| pattern.is_none_or(|p| super::glob_match(p, &t.name)))
+        .collect())
+}
+
+/// `tasks --auto-fetch <dir>`: poll for completed offline tasks, download
+/// each into `dir`, then drop it from the task list (the cloud copy stays).
+fn auto_fetch(client: &PikPak, args: &[String]) -> Result<()> {
+    let mut dir: Option<&str> = None;
+    let mut pattern: Option<&str> = None;
+    let mut interval = 60u64;
+    let mut once = false;
+    let mut metrics_addr: Option<&str> = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--auto-fetch" => {
+                dir = Some(
+                    iter.next()
+                        .ok_or_else(|| anyhow!("--auto-fetch requires a local directory"))?,
+                );
+            }
+            "--pattern" => {
+                pattern = Some(
+                    iter.next()
+                        .ok_or_else(|| anyhow!("--pattern requires a glob"))?,
+                );
+            }
+            "--interval" => {
+                interval = iter
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .filter(|&n| n > 0)
+                    .ok_or_else(|| anyhow!("--interval requires a number of seconds"))?;
+            }
+            "--once" => once = true,
+            "--metrics" => {
+                metrics_addr = Some(iter.next().ok_or_else(|| {
+                    anyhow!("--metrics requires an address, e.g. 127.0.0.1:9464")
+                })?);
+            }
+            other => return Err(anyhow!("unknown option for --auto-fetch: {other}")),
+        }
+    }
+    let dir = Path::new(dir.ok_or_else(|| anyhow!("--auto-fetch requires a local directory"))?);
+    std::fs::create_dir_all(dir)?;
+
+    let metrics = Arc::new(Metrics::default());
+    if let Some(addr) = metrics_addr {
+        let bound = super::metrics::serve(addr, Arc::clone(&metrics))?;
+        super::notice(format!("Serving metrics on http://{bound}/metrics"));
+    }
+
+    if !once {
+        super::notice(format!(
+            "Watching for completed offline tasks -> '{}' (every {}s, Ctrl+C to stop)",
+            dir.display(),
+            interval
+        ));
+    }
+    // A task that fails to download is left on the list and not retried until
+    // the next run, so one bad task doesn't get re-fetched every poll.
+    let mut failed: HashSet<String> = HashSet::new();
+    let set = |m: &AtomicU64, v: u64| m.store(v, Ordering::Relaxed);
+    loop {
+        set(&metrics.last_poll, super::now_unix() as u64);
+        if metrics_addr.is_some()
+            && let Ok(quota) = client.quota()
+            && let Some(d) = quota.quota
+        {
+            let num = |s: Option<String>| s.and_then(|s| s.parse().ok()).unwrap_or(0);
+            set(&metrics.storage_used, num(d.usage));
+            set(&metrics.storage_limit, num(d.limit));
+        }
+        match completed_tasks(client, pattern) {
+            Ok(tasks) => {
+                let mut waiting = tasks.iter().filter(|t| !failed.contains(&t.id)).count() as u64;
+                set(&metrics.queue_depth, waiting);
+                for task in &tasks {
+                    if failed.contains(&task.id) {
+                        continue;
+                    }
+                    match fetch_task(client, task, dir, &metrics) {
+                        Ok(count) => {
+                            if let Err(e) = client.delete_tasks(&[&task.id], false) {
+                                eprintln!("warning: could not remove task '{}': {e:#}", task.name);
+                            }
+                            super::status(format!(
+                                "\x1b[32m✓\x1b[0m {} ({} file(s))",
+                                task.name, count
+                            ));
+                            metrics.tasks_fetched.fetch_add(1, Ordering::Relaxed);
+                        }
+                        Err(e) => {
+                            eprintln!("\x1b[31m✗\x1b[0m {}: {e:#}", task.name);
+                            failed.insert(task.id.clone());
+                            metrics.tasks_failed.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    waiting -= 1;
+                    set(&metrics.queue_depth, waiting);
+                }
+            }
+            Err(e) => eprintln!("warning: failed to list offline tasks: {e:#}"),
+        }
+        set(&metrics.api_errors, client.api_errors());
+        set(&metrics.api_retries, client.api_retries());
+        if once {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_secs(interval));
+    }
+}
+
+fn fetch_task(client: &PikPak, task: &OfflineTask, dir: &Path, metrics: &Metrics) -> Result<usize> {
+    let files = client.offline_task_files(task)?;
+    for (entry, rel) in &files {
+        let dest = dir.join(rel);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        super::status(format!("  {}", dest.display()));
+        client.download_to_with_progress(&entry.id, &dest, metrics.download_progress())?;
+    }
+    Ok(files.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn record(direction: Direction, day: u64, size: u64, secs: u64) -> TransferRecord {
+        let mut r = TransferRecord::new(
+            direction,
+            "id",
+            "name",
+            size,
+            Path::new("/tmp/x"),
+            size,
+            Duration::from_secs(secs),
+        );
+        r.finished_at = day * 86_400 + 3600;
+        r
+    }
+
+    #[test]
+    fn stats_group_by_day_and_monday_week() {
+        // Day 20374 is Monday 2025-10-13; 20380 the Sunday after.
+        let mut bad = record(Direction::Download, 20380, 100, 1);
+        bad.verified = Some(false);
+        let records = vec![
+            record(Direction::Download, 20374, 1000, 10),
+            record(Direction::Upload, 20374, 500, 0),
+            bad,
+            record(Direction::Upload, 20381, 300, 3),
+        ];
+
+        let daily = period_stats(&records, false);
+        assert_eq!(
+            daily.iter().map(|p| p.period.as_str()).collect::<Vec<_>>(),
+            ["2025-10-20", "2025-10-19", "2025-10-13"]
+        );
+        assert_eq!(daily[2].downloads, 1);
+        assert_eq!(daily[2].uploads, 1);
+        assert_eq!(daily[2].avg_speed, 100);
+
+        let weekly = period_stats(&records, true);
+        assert_eq!(weekly.len(), 2);
+        assert_eq!(weekly[1].period, "2025-10-13");
+        assert_eq!(weekly[1].download_bytes, 1100);
+        assert_eq!(weekly[1].hash_mismatches, 1);
+        assert_eq!(weekly[1].avg_speed, 100);
+        assert_eq!(weekly[0].period, "2025-10-20");
+    }
+}
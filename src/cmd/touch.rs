@@ -0,0 +1,37 @@
+use anyhow::{Result, anyhow};
+
+const USAGE: &str = "Usage: pikpaktui touch [-n] <path...>";
+
+pub fn run(args: &[String]) -> Result<()> {
+    let mut dry_run = super::dry_run();
+    let mut paths: Vec<&str> = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "-n" | "--dry-run" => dry_run = true,
+            s if s.starts_with('-') => return Err(anyhow!("unknown option: {s}\n{USAGE}")),
+            s => paths.push(s),
+        }
+    }
+    if paths.is_empty() {
+        return Err(anyhow!(USAGE));
+    }
+
+    let client = super::cli_client()?;
+    for path in paths {
+        let (parent_path, name) = super::split_parent_name(path)?;
+        let parent_id = client.resolve_path(&parent_path)?;
+        // Like touch(1) an existing file is left alone, though its time
+        // can't be bumped remotely.
+        if client.ls(&parent_id)?.iter().any(|e| e.name == name) {
+            super::notice(format!("'{path}' already exists"));
+            continue;
+        }
+        if dry_run {
+            println!("[dry-run] Would create empty file '{name}' in '{parent_path}'");
+            continue;
+        }
+        client.create_file(Some(&parent_id), &name, b"")?;
+        super::status(format!("Created '{name}' in '{parent_path}'"));
+    }
+    Ok(())
+}
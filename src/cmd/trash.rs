@@ -6,13 +6,11 @@ pub fn run(args: &[String]) -> Result<()> {
     let nerd_font = config.cli_nerd_font;
 
     let mut long = false;
-    let mut json = false;
     let mut limit = 100u32;
 
     for arg in args {
         match arg.as_str() {
             "-l" | "--long" => long = true,
-            "-J" | "--json" => json = true,
             _ => {
                 if let Ok(n) = arg.parse::<u32>() {
                     limit = n;
@@ -23,8 +21,8 @@ pub fn run(args: &[String]) -> Result<()> {
 
     let entries = client.ls_trash(limit)?;
 
-    if json {
-        super::print_entries_json(&entries);
+    if super::output_format().is_machine() {
+        super::print_data(&serde_json::to_value(&entries)?);
         return Ok(());
     }
 
@@ -2,10 +2,10 @@ use anyhow::{Result, anyhow};
 
 pub fn run(args: &[String]) -> Result<()> {
     if args.is_empty() {
-        return Err(anyhow!("Usage: pikpaktui untrash [-n] <name...>"));
+        return Err(anyhow!("Usage: pikpaktui untrash [-n] <name|id...>"));
     }
 
-    let mut dry_run = false;
+    let mut dry_run = super::dry_run();
     let mut names: Vec<&str> = Vec::new();
 
     for arg in args {
@@ -16,7 +16,7 @@ pub fn run(args: &[String]) -> Result<()> {
     }
 
     if names.is_empty() {
-        return Err(anyhow!("Usage: pikpaktui untrash [-n] <name...>"));
+        return Err(anyhow!("Usage: pikpaktui untrash [-n] <name|id...>"));
     }
 
     let client = super::cli_client()?;
@@ -26,8 +26,8 @@ pub fn run(args: &[String]) -> Result<()> {
     for name in &names {
         let entry = trash_entries
             .iter()
-            .find(|e| e.name == *name)
-            .ok_or_else(|| anyhow!("'{}' not found in trash", name))?;
+            .find(|e| e.name == *name || e.id == *name)
+            .ok_or_else(|| crate::pikpak::not_found(format!("'{}' not found in trash", name)))?;
         ids.push(entry.id.clone());
     }
 
@@ -41,7 +41,7 @@ pub fn run(args: &[String]) -> Result<()> {
 
     let id_refs: Vec<&str> = ids.iter().map(|s| s.as_str()).collect();
     client.untrash(&id_refs)?;
-    println!("Restored {} item(s) from trash", ids.len());
+    super::status(format!("Restored {} item(s) from trash", ids.len()));
 
     Ok(())
 }
@@ -1,8 +1,8 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use self_update::cargo_crate_version;
 
-fn platform_target() -> &'static str {
-    match (std::env::consts::ARCH, std::env::consts::OS) {
+fn platform_target() -> Result<&'static str> {
+    let target = match (std::env::consts::ARCH, std::env::consts::OS) {
         ("x86_64", "linux") => "x86_64-linux",
         ("aarch64", "linux") => "aarch64-linux",
         ("x86_64", "macos") => "x86_64-macos",
@@ -10,11 +10,9 @@ fn platform_target() -> &'static str {
         ("x86_64", "windows") => "x86_64-windows",
         ("aarch64", "windows") => "aarch64-windows",
         ("x86_64", "freebsd") => "x86_64-freebsd",
-        (arch, os) => {
-            eprintln!("Unsupported platform: {}-{}", arch, os);
-            std::process::exit(1);
-        }
-    }
+        (arch, os) => return Err(anyhow!("unsupported platform: {}-{}", arch, os)),
+    };
+    Ok(target)
 }
 
 pub fn check_for_update() -> Option<String> {
@@ -48,23 +46,23 @@ fn version_newer(latest: &str, current: &str) -> bool {
 
 pub fn run() -> Result<()> {
     let current = cargo_crate_version!();
-    println!("Current version: {}", current);
-    println!("Checking for updates...");
+    super::status(format!("Current version: {}", current));
+    super::status("Checking for updates...");
 
     let status = self_update::backends::github::Update::configure()
         .repo_owner("Bengerthelorf")
         .repo_name("pikpaktui")
         .bin_name("pikpaktui")
-        .target(platform_target())
-        .show_download_progress(true)
+        .target(platform_target()?)
+        .show_download_progress(!super::quiet())
         .current_version(current)
         .build()?
         .update()?;
 
     if status.updated() {
-        println!("Updated to version {}!", status.version());
+        super::status(format!("Updated to version {}!", status.version()));
     } else {
-        println!("Already up to date.");
+        super::status("Already up to date.");
     }
 
     Ok(())
@@ -1,20 +1,26 @@
 use anyhow::{Result, anyhow};
 
+use crate::pikpak::HashAhead;
+
 pub fn run(args: &[String]) -> Result<()> {
     if args.is_empty() {
         return Err(anyhow!(
-            "Usage: pikpaktui upload [-n] <local> [remote]\n       pikpaktui upload [-n] -t <remote> <local...>"
+            "Usage: pikpaktui upload [-n] [-P] [--parents] <local> [remote]\n       pikpaktui upload [-n] [-P] [--parents] -t <remote> <local...>"
         ));
     }
 
     let mut target: Option<&str> = None;
-    let mut dry_run = false;
+    let mut dry_run = super::dry_run();
+    let mut provenance = false;
+    let mut parents = false;
     let mut paths: Vec<&str> = Vec::new();
     let mut iter = args.iter();
 
     while let Some(arg) = iter.next() {
         match arg.as_str() {
             "-n" | "--dry-run" => dry_run = true,
+            "-P" | "--provenance" => provenance = true,
+            "--parents" => parents = true,
             "-t" => {
                 target = Some(
                     iter.next()
@@ -33,22 +39,31 @@ pub fn run(args: &[String]) -> Result<()> {
         return Err(anyhow!("no file specified"));
     }
 
-    let client = super::cli_client()?;
+    let mut client = super::cli_client()?;
+    if provenance {
+        client.record_provenance = true;
+    }
 
     if let Some(dst) = target {
-        let parent_id = client.resolve_path(dst)?;
+        // Check every file first so a typo doesn't leave behind folders
+        // created by --parents.
         for path in &paths {
-            let local_path = std::path::PathBuf::from(path);
+            let local_path = std::path::Path::new(path);
             if !local_path.exists() {
-                return Err(anyhow!(
+                return Err(crate::pikpak::not_found(format!(
                     "local file '{}' does not exist",
                     local_path.display()
-                ));
+                )));
             }
             if !local_path.is_file() {
                 return Err(anyhow!("'{}' is not a file", local_path.display()));
             }
-
+        }
+        let parent_id = super::resolve_dest(&client, dst, parents, dry_run)?;
+        let mut hashes = (!dry_run)
+            .then(|| HashAhead::new(paths.iter().map(std::path::PathBuf::from).collect()));
+        for path in &paths {
+            let local_path = std::path::PathBuf::from(path);
             let file_size = std::fs::metadata(&local_path)?.len();
             let file_name = local_path.file_name().unwrap_or_default().to_string_lossy();
 
@@ -63,25 +78,29 @@ pub fn run(args: &[String]) -> Result<()> {
                 continue;
             }
 
-            eprintln!(
+            super::notice(format!(
                 "{} ({}) uploading...",
                 file_name,
                 super::format_size(file_size)
-            );
-            let (name, dedup) = client.upload_file(Some(&parent_id), &local_path)?;
+            ));
+            let hash = hashes
+                .as_mut()
+                .and_then(Iterator::next)
+                .ok_or_else(|| anyhow!("no hash for '{}'", local_path.display()))??;
+            let (name, dedup) = client.upload_file_hashed(Some(&parent_id), &local_path, hash)?;
             if dedup {
-                println!("{} - complete (dedup)", name);
+                super::status(format!("{} - instant (dedup)", name));
             } else {
-                println!("{} - done", name);
+                super::status(format!("{} - done", name));
             }
         }
     } else {
         let local_path = std::path::PathBuf::from(paths[0]);
         if !local_path.exists() {
-            return Err(anyhow!(
+            return Err(crate::pikpak::not_found(format!(
                 "local file '{}' does not exist",
                 local_path.display()
-            ));
+            )));
         }
         if !local_path.is_file() {
             return Err(anyhow!("'{}' is not a file", local_path.display()));
@@ -89,7 +108,7 @@ pub fn run(args: &[String]) -> Result<()> {
 
         let remote_dest = paths.get(1).copied();
         let parent_id = if let Some(dst) = remote_dest {
-            Some(client.resolve_path(dst)?)
+            Some(super::resolve_dest(&client, dst, parents, dry_run)?)
         } else {
             None
         };
@@ -112,16 +131,16 @@ pub fn run(args: &[String]) -> Result<()> {
             return Ok(());
         }
 
-        eprintln!(
+        super::notice(format!(
             "{} ({}) uploading...",
             file_name,
             super::format_size(file_size)
-        );
+        ));
         let (name, dedup) = client.upload_file(parent_id.as_deref(), &local_path)?;
         if dedup {
-            println!("{} - complete (dedup)", name);
+            super::status(format!("{} - instant (dedup)", name));
         } else {
-            println!("{} - done", name);
+            super::status(format!("{} - done", name));
         }
     }
     Ok(())
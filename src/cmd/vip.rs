@@ -4,6 +4,27 @@ pub fn run() -> Result<()> {
     let client = super::cli_client()?;
     let resp = client.vip_info()?;
 
+    if super::output_format().is_machine() {
+        let tq = client.transfer_quota().ok();
+        let mut membership = serde_json::json!({
+            "plan":   resp.data.as_ref().and_then(|d| d.vip_type.as_deref()),
+            "status": resp.data.as_ref().and_then(|d| d.status.as_deref()),
+        });
+        let expire = resp.data.as_ref().and_then(|d| d.expire.as_deref());
+        super::quota::add_expiry(&mut membership, expire.filter(|e| !e.is_empty()));
+
+        let out = serde_json::json!({
+            "membership":  membership,
+            "invite_code": client.invite_code().ok(),
+            "bandwidth":   tq
+                .as_ref()
+                .and_then(|t| t.base.as_ref())
+                .map(super::quota::bandwidth_json),
+        });
+        super::print_data(&out);
+        return Ok(());
+    }
+
     if let Some(data) = resp.data {
         let vip_type = data.vip_type.as_deref().unwrap_or("none");
         let status = data.status.as_deref().unwrap_or("unknown");
@@ -0,0 +1,139 @@
+use crate::pikpak::EntryKind;
+use anyhow::{Context, Result, anyhow};
+use std::io::{self, IsTerminal, Read};
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: pikpaktui write [-n] [-f] [--from <file>] <path> [text...]";
+
+pub fn run(args: &[String]) -> Result<()> {
+    let mut dry_run = super::dry_run();
+    let mut force = false;
+    let mut from: Option<&str> = None;
+    let mut rest: Vec<&str> = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-n" | "--dry-run" => dry_run = true,
+            "-f" | "--force" => force = true,
+            "--from" => {
+                from = Some(
+                    iter.next()
+                        .ok_or_else(|| anyhow!("--from requires a file or template name"))?,
+                );
+            }
+            s if s.starts_with('-') && rest.is_empty() => {
+                return Err(anyhow!("unknown option: {s}\n{USAGE}"));
+            }
+            s => rest.push(s),
+        }
+    }
+    let (path, text) = rest.split_first().ok_or_else(|| anyhow!(USAGE))?;
+
+    let content = match (from, text.is_empty()) {
+        (Some(_), false) => return Err(anyhow!("give either text or --from, not both")),
+        (Some(from), true) => {
+            let template = template_path(from)?;
+            std::fs::read(&template)
+                .with_context(|| format!("cannot read '{}'", template.display()))?
+        }
+        (None, false) => text_content(text).into_bytes(),
+        (None, true) if !io::stdin().is_terminal() => {
+            let mut buf = Vec::new();
+            io::stdin()
+                .read_to_end(&mut buf)
+                .context("failed to read stdin")?;
+            buf
+        }
+        (None, true) => {
+            return Err(anyhow!(
+                "nothing to write: give text, --from or stdin\n{USAGE}"
+            ));
+        }
+    };
+
+    let client = super::cli_client()?;
+    let (parent_path, name) = super::split_parent_name(path)?;
+    let parent_id = client.resolve_path(&parent_path)?;
+    let existing = client.ls(&parent_id)?.into_iter().find(|e| e.name == name);
+    if let Some(existing) = &existing {
+        if existing.kind == EntryKind::Folder {
+            return Err(anyhow!("'{path}' is a folder"));
+        }
+        if !force {
+            return Err(anyhow!("'{path}' already exists; pass -f to replace it"));
+        }
+    }
+
+    if dry_run {
+        let verb = if existing.is_some() {
+            "replace"
+        } else {
+            "create"
+        };
+        println!(
+            "[dry-run] Would {verb} '{name}' in '{parent_path}' ({})",
+            super::format_size(content.len() as u64)
+        );
+        return Ok(());
+    }
+
+    match existing {
+        None => client.create_file(Some(&parent_id), &name, &content)?,
+        // Uploaded under the real name, the new file would land next to the
+        // old one under another; it goes up under a temporary name instead,
+        // and the old file is trashed only once it's safely there.
+        Some(existing) => {
+            let staged = format!(".{name}.pikpaktui-{}", std::process::id());
+            client.create_file(Some(&parent_id), &staged, &content)?;
+            let new_id = client
+                .ls(&parent_id)?
+                .into_iter()
+                .find(|e| e.name == staged)
+                .ok_or_else(|| anyhow!("uploaded '{staged}' but can't find it in '{parent_path}'"))?
+                .id;
+            client.remove(&[existing.id.as_str()])?;
+            client.rename(&new_id, &name).with_context(|| {
+                format!("the new content is in '{staged}' and the old file in the trash")
+            })?;
+        }
+    }
+    super::status(format!(
+        "Wrote '{name}' in '{parent_path}' ({})",
+        super::format_size(content.len() as u64)
+    ));
+    Ok(())
+}
+
+/// Words given on the command line, as one line of text.
+fn text_content(words: &[&str]) -> String {
+    let mut text = words.join(" ");
+    text.push('\n');
+    text
+}
+
+/// `--from`: a local file, or else the name of one in the templates folder.
+fn template_path(from: &str) -> Result<PathBuf> {
+    let local = PathBuf::from(from);
+    if local.is_file() {
+        return Ok(local);
+    }
+    crate::config::templates_dir()
+        .map(|dir| dir.join(from))
+        .filter(|p| p.is_file())
+        .ok_or_else(|| {
+            crate::pikpak::not_found(format!(
+                "no file or template named '{from}' (templates live in ~/.config/pikpaktui/templates)"
+            ))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_become_one_line() {
+        assert_eq!(text_content(&["hello", "world"]), "hello world\n");
+        assert_eq!(text_content(&["# Notes"]), "# Notes\n");
+    }
+}
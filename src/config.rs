@@ -3,7 +3,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct AppConfig {
@@ -11,16 +12,115 @@ pub struct AppConfig {
     pub username: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub password: Option<String>,
+    /// Further accounts, `[profiles.<name>]`, picked with `--profile` or the
+    /// TUI account switcher. The top-level login is the `default` profile.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Credentials>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Credentials {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+}
+
+/// The account commands run as; `None` is the default profile.
+static PROFILE: RwLock<Option<String>> = RwLock::new(None);
+
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Switch the active profile. `default` (or `None`) is the top-level login.
+pub fn set_profile(name: Option<&str>) -> Result<()> {
+    let name = name.filter(|n| *n != DEFAULT_PROFILE);
+    if let Some(n) = name
+        && (n.is_empty()
+            || !n
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+    {
+        anyhow::bail!("invalid profile name '{n}': use letters, digits, '-' and '_'");
+    }
+    if let Ok(mut slot) = PROFILE.write() {
+        *slot = name.map(str::to_string);
+    }
+    Ok(())
+}
+
+pub fn profile() -> Option<String> {
+    PROFILE.read().ok().and_then(|slot| slot.clone())
+}
+
+/// `dir` for the default profile, `dir/profiles/<name>` for a named one:
+/// where the sessions, caches and download queues of different accounts
+/// are kept apart.
+pub fn profile_dir(dir: PathBuf) -> PathBuf {
+    profile_dir_for(dir, profile().as_deref())
+}
+
+pub fn profile_dir_for(dir: PathBuf, profile: Option<&str>) -> PathBuf {
+    match profile {
+        Some(name) => dir.join("profiles").join(name),
+        None => dir,
+    }
 }
 
 impl AppConfig {
+    /// Saved email and password of `profile` (`None` = default), if both
+    /// are set.
+    pub fn credentials_for(&self, profile: Option<&str>) -> Option<(String, String)> {
+        let (username, password) = match profile {
+            None => (self.username.clone(), self.password.clone()),
+            Some(name) => {
+                let c = self.profiles.get(name)?;
+                (c.username.clone(), c.password.clone())
+            }
+        };
+        let password = password.or_else(|| {
+            crate::secrets::use_keyring()
+                .then(|| {
+                    crate::secrets::get(&password_account(profile))
+                        .ok()
+                        .flatten()
+                })
+                .flatten()
+        });
+        match (username, password) {
+            (Some(u), Some(p)) if !u.is_empty() && !p.is_empty() => Some((u, p)),
+            _ => None,
+        }
+    }
+
+    /// Credentials of the active profile.
+    pub fn credentials(&self) -> Option<(String, String)> {
+        self.credentials_for(profile().as_deref())
+    }
+
+    /// The email saved for the active profile, with or without a password.
+    pub fn active_username(&self) -> Option<String> {
+        match profile() {
+            None => self.username.clone(),
+            Some(name) => self.profiles.get(&name).and_then(|c| c.username.clone()),
+        }
+        .filter(|u| !u.is_empty())
+    }
+
+    /// `default` followed by the named profiles.
+    pub fn profile_names(&self) -> Vec<String> {
+        std::iter::once(DEFAULT_PROFILE.to_string())
+            .chain(self.profiles.keys().cloned())
+            .collect()
+    }
+
     pub fn load() -> Result<Self> {
         let path = config_path()?;
         if path.exists() {
             let raw = fs::read_to_string(&path)
                 .with_context(|| format!("failed to read config {}", path.display()))?;
-            let cfg: AppConfig =
+            let mut cfg: AppConfig =
                 toml::from_str(&raw).with_context(|| "failed to parse login.toml")?;
+            cfg.move_passwords_to_keyring(&path);
             return Ok(cfg);
         }
         let legacy = path.with_file_name("login.yaml");
@@ -33,6 +133,33 @@ impl AppConfig {
         Ok(Self::default())
     }
 
+    /// With the keyring in use, move any password still in login.toml into
+    /// it. If the keyring can't be reached the file is left as it is.
+    fn move_passwords_to_keyring(&mut self, path: &PathBuf) {
+        if !crate::secrets::use_keyring() {
+            return;
+        }
+        let mut moved = false;
+        let logins = std::iter::once((None, &mut self.password)).chain(
+            self.profiles
+                .iter_mut()
+                .map(|(name, c)| (Some(name.as_str()), &mut c.password)),
+        );
+        for (profile, password) in logins {
+            let Some(secret) = password.as_deref() else {
+                continue;
+            };
+            if crate::secrets::set(&password_account(profile), secret).is_err() {
+                return;
+            }
+            *password = None;
+            moved = true;
+        }
+        if moved {
+            let _ = write_login(path, self);
+        }
+    }
+
     fn parse_legacy_yaml(raw: &str) -> Self {
         let mut cfg = Self::default();
         for line in raw.lines() {
@@ -59,27 +186,92 @@ impl AppConfig {
             AppConfig::default()
         };
 
-        cfg.username = Some(username.to_string());
-        cfg.password = Some(password.to_string());
+        // Saved under the active profile; the password goes to the keyring
+        // instead when that's in use.
+        let profile = profile();
+        let password = if crate::secrets::use_keyring() {
+            crate::secrets::set(&password_account(profile.as_deref()), password)?;
+            None
+        } else {
+            Some(password.to_string())
+        };
+        match profile {
+            None => {
+                cfg.username = Some(username.to_string());
+                cfg.password = password;
+            }
+            Some(name) => {
+                cfg.profiles.insert(
+                    name,
+                    Credentials {
+                        username: Some(username.to_string()),
+                        password,
+                    },
+                );
+            }
+        }
 
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("failed to create dir {}", parent.display()))?;
+        write_login(&path, &cfg)
+    }
+
+    /// Drop the active profile's saved email and password. Returns whether
+    /// there were any.
+    pub fn forget_credentials() -> Result<bool> {
+        let path = config_path()?;
+        let in_keyring = crate::secrets::use_keyring()
+            && crate::secrets::delete(&password_account(profile().as_deref()))?;
+        if !path.exists() {
+            return Ok(in_keyring);
+        }
+        let raw = fs::read_to_string(&path)
+            .with_context(|| format!("failed to read config {}", path.display()))?;
+        let mut cfg: AppConfig =
+            toml::from_str(&raw).with_context(|| format!("invalid config {}", path.display()))?;
+
+        let had = cfg.forget(profile().as_deref());
+        if had {
+            write_login(&path, &cfg)?;
         }
+        Ok(had || in_keyring)
+    }
 
-        let raw = toml::to_string_pretty(&cfg).context("failed to serialize config")?;
-        let tmp_path = path.with_extension("tmp");
-        write_owner_only(&tmp_path, raw.as_bytes())
-            .with_context(|| format!("failed to write config {}", tmp_path.display()))?;
-        fs::rename(&tmp_path, &path)
-            .with_context(|| format!("failed to rename config {}", path.display()))?;
-        set_file_owner_only(&path);
-        Ok(())
+    /// Clear `profile`'s email and password, keeping the profile itself.
+    /// Returns whether it had either.
+    fn forget(&mut self, profile: Option<&str>) -> bool {
+        let creds = match profile {
+            None => (&mut self.username, &mut self.password),
+            Some(name) => match self.profiles.get_mut(name) {
+                Some(c) => (&mut c.username, &mut c.password),
+                None => return false,
+            },
+        };
+        creds.0.take().is_some() | creds.1.take().is_some()
     }
 }
 
+/// The keyring entry of a profile's password.
+fn password_account(profile: Option<&str>) -> String {
+    format!("password:{}", profile.unwrap_or(DEFAULT_PROFILE))
+}
+
+fn write_login(path: &PathBuf, cfg: &AppConfig) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create dir {}", parent.display()))?;
+    }
+
+    let raw = toml::to_string_pretty(cfg).context("failed to serialize config")?;
+    let tmp_path = path.with_extension("tmp");
+    write_owner_only(&tmp_path, raw.as_bytes())
+        .with_context(|| format!("failed to write config {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("failed to rename config {}", path.display()))?;
+    set_file_owner_only(path);
+    Ok(())
+}
+
 pub fn config_path() -> Result<PathBuf> {
-    let base = home_config_dir().ok_or_else(|| anyhow::anyhow!("unable to locate config dir"))?;
+    let base = config_root().ok_or_else(|| anyhow::anyhow!("unable to locate config dir"))?;
     Ok(base.join("pikpaktui").join("login.toml"))
 }
 
@@ -95,7 +287,7 @@ fn set_file_owner_only(_path: &PathBuf) {}
 /// Write `data` to `path`, creating the file 0600 on unix so the stored
 /// credentials are never world-readable, even before the post-rename chmod.
 #[cfg(unix)]
-fn write_owner_only(path: &PathBuf, data: &[u8]) -> std::io::Result<()> {
+pub(crate) fn write_owner_only(path: &PathBuf, data: &[u8]) -> std::io::Result<()> {
     use std::io::Write as _;
     use std::os::unix::fs::OpenOptionsExt;
     let mut f = fs::OpenOptions::new()
@@ -108,12 +300,41 @@ fn write_owner_only(path: &PathBuf, data: &[u8]) -> std::io::Result<()> {
 }
 
 #[cfg(not(unix))]
-fn write_owner_only(path: &PathBuf, data: &[u8]) -> std::io::Result<()> {
+pub(crate) fn write_owner_only(path: &PathBuf, data: &[u8]) -> std::io::Result<()> {
     fs::write(path, data)
 }
 
-fn home_config_dir() -> Option<PathBuf> {
-    dirs::home_dir().map(|h| h.join(".config"))
+/// `~/.config/pikpaktui/templates/`: files to start a new remote text file
+/// from (`write --from`, the TUI's new file form).
+pub fn templates_dir() -> Option<PathBuf> {
+    config_root().map(|root| root.join("pikpaktui").join("templates"))
+}
+
+/// The files in [`templates_dir`], sorted by name; none if it's missing.
+pub fn templates() -> Vec<PathBuf> {
+    let Some(dir) = templates_dir() else {
+        return Vec::new();
+    };
+    let Ok(read) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = read
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.is_file())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// The directory holding `pikpaktui/`: `~/.config`, except on Windows,
+/// where it's `%APPDATA%` unless an earlier version already set up
+/// `~\.config\pikpaktui`.
+pub fn config_root() -> Option<PathBuf> {
+    let home = dirs::home_dir().map(|h| h.join(".config"));
+    if cfg!(windows) && !home.as_ref().is_some_and(|h| h.join("pikpaktui").exists()) {
+        return dirs::config_dir().or(home);
+    }
+    home
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
@@ -198,6 +419,42 @@ pub enum ColorScheme {
     Custom,
 }
 
+/// How many colours the terminal can show. RGB colours (custom scheme,
+/// syntax highlighting, half-block thumbnails) are mapped down to fit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorDepth {
+    /// Guess from `COLORTERM` / `TERM`.
+    #[default]
+    Auto,
+    Truecolor,
+    #[serde(rename = "256")]
+    Ansi256,
+    #[serde(rename = "16")]
+    Ansi16,
+}
+
+/// Which IP version the HTTP clients connect over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum IpFamily {
+    #[default]
+    Auto,
+    Ipv4,
+    Ipv6,
+}
+
+/// Where the session and saved password are kept (see `secrets`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum CredentialStore {
+    /// `session.json` and `login.toml`, readable only by the owner.
+    #[default]
+    File,
+    /// The OS keyring.
+    Keyring,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "kebab-case")]
 #[derive(Default)]
@@ -504,6 +761,10 @@ impl MoveMode {
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TuiConfig {
+    /// Files merged in underneath this one (keys, themes), relative to the
+    /// config directory. Settings here win over theirs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
     #[serde(default)]
     pub nerd_font: bool,
     #[serde(default)]
@@ -514,6 +775,10 @@ pub struct TuiConfig {
     pub quota_bar_style: QuotaBarStyle,
     #[serde(default)]
     pub cli_nerd_font: bool,
+    /// Two side-by-side browser panes instead of parent / current / preview;
+    /// F5 / F6 copy / move into the other pane's folder.
+    #[serde(default)]
+    pub commander_mode: bool,
     #[serde(default)]
     pub border_style: BorderStyle,
     #[serde(default)]
@@ -524,6 +789,10 @@ pub struct TuiConfig {
     pub lazy_preview: bool,
     #[serde(default = "default_preview_max_size")]
     pub preview_max_size: u64,
+    /// Preview videos without a thumbnail by pulling a frame out of their
+    /// first few MB with ffmpeg.
+    #[serde(default)]
+    pub video_frame_preview: bool,
     #[serde(default)]
     pub custom_colors: CustomColors,
     #[serde(default)]
@@ -541,10 +810,223 @@ pub struct TuiConfig {
     image_protocol: Option<ImageProtocol>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub player: Option<String>,
+    /// Hand the player a local proxy address rather than PikPak's link, so
+    /// an expired link is renewed mid-playback.
+    #[serde(default = "default_true")]
+    pub player_proxy: bool,
     #[serde(default = "default_download_jobs")]
     pub download_jobs: usize,
     #[serde(default)]
     pub update_check: UpdateCheck,
+    /// Record the local source path, mtime and hash of every upload in the
+    /// local database, keyed by remote file id.
+    #[serde(default)]
+    pub upload_provenance: bool,
+    #[serde(default)]
+    pub size_units: SizeUnits,
+    /// strftime-style pattern for dates (`%Y %m %d %H %M %S`).
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    /// Files at or above this many bytes are highlighted, and the cart warns
+    /// once its total reaches it. 0 disables the rule.
+    #[serde(default)]
+    pub large_file_threshold: u64,
+    /// How far back (in days) the virtual "Recent" folder looks.
+    #[serde(default = "default_recent_days")]
+    pub recent_days: u32,
+    /// Seconds a folder listing cached on disk may be shown while the live
+    /// listing loads. 0 disables the cache.
+    #[serde(default = "default_listing_cache_ttl")]
+    pub listing_cache_ttl: u64,
+    /// Report download progress to the terminal (OSC 9;4) so it shows in
+    /// the tab or taskbar.
+    #[serde(default = "default_true")]
+    pub terminal_progress: bool,
+    #[serde(default)]
+    pub color_depth: ColorDepth,
+    /// Queue completed offline tasks for download into this local directory
+    /// and remove them from the task list. Unset disables auto-fetch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_fetch_dir: Option<String>,
+    /// Only auto-fetch tasks whose name matches this glob.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_fetch_pattern: Option<String>,
+    /// Seconds between checks for completed offline tasks.
+    #[serde(default = "default_auto_fetch_interval")]
+    pub auto_fetch_interval: u64,
+    /// Build a local index of every file's hash and size in the background
+    /// while the TUI runs (`pikpaktui index` uses it).
+    #[serde(default)]
+    pub hash_index: bool,
+    /// Seconds between the folder listings of an index update.
+    #[serde(default = "default_hash_index_interval")]
+    pub hash_index_interval: u64,
+    /// Retention rules, `[[cleanup]]`, carried out by `pikpaktui cleanup`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cleanup: Vec<CleanupRule>,
+    /// Let the TUI run the trash rules of `cleanup` once a day.
+    #[serde(default)]
+    pub auto_cleanup: bool,
+    /// `pikpaktui check` thresholds: storage and bandwidth use in percent,
+    /// and days left on a VIP plan. 0 turns that check off.
+    #[serde(default = "default_alert_percent")]
+    pub alert_storage_percent: u64,
+    #[serde(default = "default_alert_percent")]
+    pub alert_bandwidth_percent: u64,
+    #[serde(default = "default_alert_vip_days")]
+    pub alert_vip_days: i64,
+    /// Lock the TUI after this many minutes without input (0 = never).
+    #[serde(default)]
+    pub lock_after_minutes: u64,
+    /// PIN that unlocks the idle lock instead of the account password.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_pin: Option<String>,
+    /// Download / upload bandwidth caps in bytes per second (0 = unlimited).
+    #[serde(default)]
+    pub max_download_speed: u64,
+    #[serde(default)]
+    pub max_upload_speed: u64,
+    /// Local time window downloads and uploads may run in, e.g.
+    /// `01:00-07:00`; outside it they're paused. Unset means any time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfer_window: Option<String>,
+    /// Days a finished download stays in the download view (and the saved
+    /// queue) before it's dropped; 0 keeps it until cleared.
+    #[serde(default = "default_finished_task_days")]
+    pub finished_task_days: u64,
+    /// Command a download set to "run hook" runs once it's done; `{path}`
+    /// and `{name}` are replaced by the local file's path and name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_hook: Option<String>,
+    /// Command the download view's "when all done: run command" runs once
+    /// every transfer has finished.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when_done_command: Option<String>,
+    /// HTTP timeouts in seconds (0 = none): connecting, each read/write of
+    /// an API call, and each read/write of a download or upload body.
+    #[serde(default = "default_connect_timeout")]
+    pub connect_timeout: u64,
+    #[serde(default = "default_request_timeout")]
+    pub request_timeout: u64,
+    #[serde(default = "default_transfer_timeout")]
+    pub transfer_timeout: u64,
+    /// Times an API call is sent again after a 429, a 5xx or a dropped
+    /// connection (0 = never), and the wait before the first retry in
+    /// milliseconds, doubled for each one after.
+    #[serde(default = "default_api_retries")]
+    pub api_retries: u32,
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    /// API requests a second across every worker (0 = no limit), and how
+    /// many may go at once after a quiet spell.
+    #[serde(default = "default_api_rate_limit")]
+    pub api_rate_limit: u64,
+    #[serde(default = "default_api_burst")]
+    pub api_burst: u64,
+    #[serde(default)]
+    pub ip_family: IpFamily,
+    /// Host → addresses (IPs or other hostnames) to use instead of DNS, e.g.
+    /// to route around a CDN node that misbehaves.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dns_overrides: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub credential_store: CredentialStore,
+    /// File browser action → key (`delete = "x"`, `downloads = "ctrl+d"`),
+    /// overriding the built-in bindings. See `tui::keymap`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "PreviewConfig::is_empty")]
+    pub preview: PreviewConfig,
+}
+
+/// What goes from a folder once it's old enough.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CleanupRule {
+    /// Folder on the drive the rule looks in.
+    pub path: String,
+    /// Age, from when the item arrived on the drive.
+    pub older_than_days: u64,
+    /// Only names matching this glob.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// Judge the files in subfolders too. Otherwise only what's directly
+    /// in `path`, folders included, is.
+    #[serde(default)]
+    pub recursive: bool,
+    #[serde(default)]
+    pub action: CleanupAction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CleanupAction {
+    #[default]
+    Trash,
+    /// Delete permanently, skipping the trash.
+    Delete,
+}
+
+/// The `[preview]` table.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct PreviewConfig {
+    /// Extension → command (`json = "jq -C ."`). The start of the file is
+    /// piped to the command and its output shown as the text preview.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub handlers: BTreeMap<String, String>,
+}
+
+impl PreviewConfig {
+    pub fn is_empty(&self) -> bool {
+        self.handlers.is_empty()
+    }
+
+    /// The handler for a file name, by its extension (any case).
+    pub fn handler_for(&self, name: &str) -> Option<&str> {
+        let (_, ext) = name.rsplit_once('.')?;
+        self.handlers
+            .iter()
+            .find(|(k, _)| k.trim_start_matches('.').eq_ignore_ascii_case(ext))
+            .map(|(_, cmd)| cmd.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum SizeUnits {
+    /// Powers of 1024, labelled KB / MB / GB / TB, as sizes were shown
+    /// before the units could be chosen.
+    Classic,
+    /// Powers of 1024, labelled KiB / MiB / GiB / TiB.
+    #[default]
+    Binary,
+    /// Powers of 1000, labelled KB / MB / GB / TB.
+    Decimal,
+}
+
+impl SizeUnits {
+    pub fn all() -> &'static [Self] {
+        &[Self::Binary, Self::Decimal, Self::Classic]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Classic => "Classic (1024, KB)",
+            Self::Binary => "Binary (KiB)",
+            Self::Decimal => "Decimal (KB)",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let all = Self::all();
+        let idx = all.iter().position(|s| s == self).unwrap();
+        all[(idx + 1) % all.len()]
+    }
+
+    pub fn prev(&self) -> Self {
+        let all = Self::all();
+        let idx = all.iter().position(|s| s == self).unwrap();
+        all[(idx + all.len() - 1) % all.len()]
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, Default)]
@@ -590,10 +1072,72 @@ impl UpdateCheck {
     }
 }
 
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+fn default_date_format() -> String {
+    DEFAULT_DATE_FORMAT.to_string()
+}
+
 fn default_download_jobs() -> usize {
     1
 }
 
+fn default_recent_days() -> u32 {
+    7
+}
+
+fn default_listing_cache_ttl() -> u64 {
+    86_400
+}
+
+fn default_hash_index_interval() -> u64 {
+    2
+}
+
+fn default_auto_fetch_interval() -> u64 {
+    60
+}
+
+fn default_alert_percent() -> u64 {
+    90
+}
+
+fn default_alert_vip_days() -> i64 {
+    7
+}
+
+fn default_connect_timeout() -> u64 {
+    crate::pikpak::DEFAULT_CONNECT_TIMEOUT
+}
+
+fn default_request_timeout() -> u64 {
+    crate::pikpak::DEFAULT_REQUEST_TIMEOUT
+}
+
+fn default_api_retries() -> u32 {
+    crate::pikpak::DEFAULT_API_RETRIES
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    crate::pikpak::DEFAULT_RETRY_BACKOFF_MS
+}
+
+fn default_api_rate_limit() -> u64 {
+    crate::pikpak::DEFAULT_API_RATE_LIMIT
+}
+
+fn default_api_burst() -> u64 {
+    crate::pikpak::DEFAULT_API_BURST
+}
+
+fn default_finished_task_days() -> u64 {
+    7
+}
+
+fn default_transfer_timeout() -> u64 {
+    crate::pikpak::DEFAULT_TRANSFER_TIMEOUT
+}
+
 fn default_preview_max_size() -> u64 {
     65536
 }
@@ -605,16 +1149,19 @@ fn default_true() -> bool {
 impl Default for TuiConfig {
     fn default() -> Self {
         Self {
+            include: Vec::new(),
             nerd_font: false,
             move_mode: MoveMode::default(),
             show_help_bar: true,
             quota_bar_style: QuotaBarStyle::default(),
             cli_nerd_font: false,
+            commander_mode: false,
             border_style: BorderStyle::default(),
             color_scheme: ColorScheme::default(),
             show_preview: true,
             lazy_preview: false,
             preview_max_size: default_preview_max_size(),
+            video_frame_preview: false,
             custom_colors: CustomColors::default(),
             thumbnail_mode: ThumbnailMode::default(),
             thumbnail_size: ThumbnailSize::default(),
@@ -623,8 +1170,47 @@ impl Default for TuiConfig {
             image_protocols: BTreeMap::new(),
             image_protocol: None,
             player: None,
+            player_proxy: true,
             download_jobs: 1,
             update_check: UpdateCheck::default(),
+            upload_provenance: false,
+            size_units: SizeUnits::default(),
+            date_format: default_date_format(),
+            large_file_threshold: 0,
+            recent_days: default_recent_days(),
+            listing_cache_ttl: default_listing_cache_ttl(),
+            terminal_progress: true,
+            color_depth: ColorDepth::default(),
+            auto_fetch_dir: None,
+            auto_fetch_pattern: None,
+            auto_fetch_interval: default_auto_fetch_interval(),
+            hash_index: false,
+            hash_index_interval: default_hash_index_interval(),
+            cleanup: Vec::new(),
+            auto_cleanup: false,
+            alert_storage_percent: default_alert_percent(),
+            alert_bandwidth_percent: default_alert_percent(),
+            alert_vip_days: default_alert_vip_days(),
+            lock_after_minutes: 0,
+            lock_pin: None,
+            max_download_speed: 0,
+            max_upload_speed: 0,
+            transfer_window: None,
+            finished_task_days: default_finished_task_days(),
+            download_hook: None,
+            when_done_command: None,
+            connect_timeout: default_connect_timeout(),
+            request_timeout: default_request_timeout(),
+            transfer_timeout: default_transfer_timeout(),
+            api_retries: default_api_retries(),
+            retry_backoff_ms: default_retry_backoff_ms(),
+            api_rate_limit: default_api_rate_limit(),
+            api_burst: default_api_burst(),
+            ip_family: IpFamily::default(),
+            dns_overrides: BTreeMap::new(),
+            credential_store: CredentialStore::default(),
+            keys: BTreeMap::new(),
+            preview: PreviewConfig::default(),
         }
     }
 }
@@ -636,7 +1222,7 @@ impl TuiConfig {
 
     /// Detect the current terminal emulator name via `TERM_PROGRAM`.
     pub fn detect_terminal() -> String {
-        env::var("TERM_PROGRAM").unwrap_or_else(|_| "unknown".to_string())
+        terminal_name(|name| env::var(name).ok(), cfg!(windows))
     }
 
     /// Return the image protocol configured for the current terminal,
@@ -659,8 +1245,80 @@ impl TuiConfig {
         term
     }
 
+    /// Timeouts, address family and DNS overrides for the HTTP clients.
+    /// Override targets that are hostnames are looked up here, once. An
+    /// override left with no address of the wanted family is skipped, so
+    /// the host resolves as usual; what was skipped comes back as warnings.
+    pub fn net_options(&self) -> (crate::pikpak::NetOptions, Vec<String>) {
+        use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs};
+
+        let wanted = |ip: &IpAddr| match self.ip_family {
+            IpFamily::Auto => true,
+            IpFamily::Ipv4 => ip.is_ipv4(),
+            IpFamily::Ipv6 => ip.is_ipv6(),
+        };
+        let mut resolve = Vec::new();
+        let mut warnings = Vec::new();
+        for (host, targets) in &self.dns_overrides {
+            let mut addrs = Vec::new();
+            for target in targets {
+                match target.parse::<IpAddr>() {
+                    Ok(ip) => addrs.push(SocketAddr::new(ip, 0)),
+                    Err(_) => match (target.as_str(), 0).to_socket_addrs() {
+                        Ok(found) => addrs.extend(found),
+                        Err(e) => warnings.push(format!(
+                            "dns_overrides: cannot resolve '{target}' for {host}: {e}"
+                        )),
+                    },
+                }
+            }
+            addrs.retain(|a| wanted(&a.ip()));
+            if addrs.is_empty() {
+                warnings.push(format!(
+                    "dns_overrides: no usable address for {host}; resolving it as usual"
+                ));
+                continue;
+            }
+            resolve.push((host.clone(), addrs));
+        }
+
+        let options = crate::pikpak::NetOptions {
+            connect_timeout: self.connect_timeout,
+            request_timeout: self.request_timeout,
+            transfer_timeout: self.transfer_timeout,
+            local_address: match self.ip_family {
+                IpFamily::Auto => None,
+                IpFamily::Ipv4 => Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+                IpFamily::Ipv6 => Some(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+            },
+            resolve,
+            api_retries: self.api_retries,
+            retry_backoff_ms: self.retry_backoff_ms,
+            api_rate_limit: self.api_rate_limit,
+            api_burst: self.api_burst,
+        };
+        (options, warnings)
+    }
+
+    /// Apply the client-side settings the TUI runs with. Returns the
+    /// network settings' warnings.
+    pub fn configure_client(&self, client: &mut crate::pikpak::PikPak) -> Result<Vec<String>> {
+        client.thumbnail_size = self.thumbnail_size.as_api_str().to_string();
+        client.record_provenance = self.upload_provenance;
+        client.listing_cache_ttl = self.listing_cache_ttl;
+        client.download_limiter.set_rate(self.max_download_speed);
+        client.upload_limiter.set_rate(self.max_upload_speed);
+        let (options, warnings) = self.net_options();
+        client.configure_network(&options)?;
+        Ok(warnings)
+    }
+
+    /// An RGB colour as this terminal can show it (see `color_depth`).
+    pub fn rgb(&self, r: u8, g: u8, b: u8) -> ratatui::style::Color {
+        crate::theme::rgb(r, g, b, self.color_depth)
+    }
+
     pub fn get_color(&self, category: crate::theme::FileCategory) -> ratatui::style::Color {
-        use ratatui::style::Color;
         if self.color_scheme == ColorScheme::Custom {
             let rgb = match category {
                 crate::theme::FileCategory::Folder => self.custom_colors.folder,
@@ -672,7 +1330,7 @@ impl TuiConfig {
                 crate::theme::FileCategory::Code => self.custom_colors.code,
                 crate::theme::FileCategory::Default => self.custom_colors.default,
             };
-            Color::Rgb(rgb.0, rgb.1, rgb.2)
+            self.rgb(rgb.0, rgb.1, rgb.2)
         } else {
             crate::theme::color_for_scheme(category, self.color_scheme)
         }
@@ -681,10 +1339,11 @@ impl TuiConfig {
 
 impl TuiConfig {
     pub fn load() -> Self {
-        let path = match home_config_dir() {
-            Some(base) => base.join("pikpaktui").join("config.toml"),
+        let dir = match config_root() {
+            Some(base) => base.join("pikpaktui"),
             None => return Self::default(),
         };
+        let path = dir.join("config.toml");
         if !path.exists() {
             return Self::default();
         }
@@ -692,7 +1351,16 @@ impl TuiConfig {
             Ok(r) => r,
             Err(_) => return Self::default(),
         };
-        let mut cfg: TuiConfig = match toml::from_str(&raw) {
+        let parsed = toml::from_str::<toml::Table>(&raw).and_then(|main| {
+            let main = hoist_tui(main);
+            let (mut merged, warnings) = included(&main, &dir);
+            for warning in warnings {
+                eprintln!("warning: {warning}");
+            }
+            merge_tables(&mut merged, main);
+            toml::Value::Table(merged).try_into::<TuiConfig>()
+        });
+        let mut cfg = match parsed {
             Ok(c) => c,
             Err(e) => {
                 eprintln!("warning: failed to parse config.toml, using defaults: {e}");
@@ -708,27 +1376,155 @@ impl TuiConfig {
         cfg
     }
 
-    pub fn save(&self) -> Result<()> {
-        let path = match home_config_dir() {
-            Some(base) => base.join("pikpaktui").join("config.toml"),
+    /// Write the settings to config.toml. Returns warnings about included
+    /// files that couldn't be read, for the caller to show.
+    pub fn save(&self) -> Result<Vec<String>> {
+        let dir = match config_root() {
+            Some(base) => base.join("pikpaktui"),
             None => return Err(anyhow::anyhow!("unable to locate config dir")),
         };
+        let path = dir.join("config.toml");
 
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)
                 .with_context(|| format!("failed to create dir {}", parent.display()))?;
         }
 
-        let raw = toml::to_string_pretty(self).context("failed to serialize config")?;
+        // Settings that came from an included file stay there, so editing
+        // keys.toml later still takes effect.
+        let mut table = match toml::Value::try_from(self).context("failed to serialize config")? {
+            toml::Value::Table(t) => t,
+            _ => unreachable!("TuiConfig serializes to a table"),
+        };
+        let (from_includes, warnings) = included(&table, &dir);
+        strip_included(&mut table, &from_includes);
+        let raw = toml::to_string_pretty(&table).context("failed to serialize config")?;
         let tmp_path = path.with_extension("tmp");
         fs::write(&tmp_path, &raw)
             .with_context(|| format!("failed to write config {}", tmp_path.display()))?;
         fs::rename(&tmp_path, &path)
             .with_context(|| format!("failed to rename config {}", path.display()))?;
-        Ok(())
+        Ok(warnings)
     }
 }
 
+/// `TERM_PROGRAM` where the terminal sets it. Windows Terminal (also
+/// under WSL) is known by `WT_SESSION`; on Windows a console with neither,
+/// nor the `TERM` that mintty and the like set, is the classic `conhost`.
+fn terminal_name(var: impl Fn(&str) -> Option<String>, windows: bool) -> String {
+    if let Some(name) = var("TERM_PROGRAM") {
+        return name;
+    }
+    if var("WT_SESSION").is_some() {
+        return "WindowsTerminal".to_string();
+    }
+    if windows && var("TERM").is_none() {
+        return "conhost".to_string();
+    }
+    "unknown".to_string()
+}
+
+/// The files named by `config`'s `include` list, merged in order (a later
+/// one wins). Paths are relative to `dir`; `~/` is the home directory.
+/// Included files can't include others. One that can't be read is skipped,
+/// with a warning for each in the list returned alongside.
+fn included(config: &toml::Table, dir: &Path) -> (toml::Table, Vec<String>) {
+    let mut merged = toml::Table::new();
+    let mut warnings = Vec::new();
+    let Some(list) = config.get("include").and_then(|v| v.as_array()) else {
+        return (merged, warnings);
+    };
+    for name in list.iter().filter_map(|v| v.as_str()) {
+        let path = dir.join(crate::local_path::expand_home(
+            name,
+            dirs::home_dir().as_deref(),
+            crate::local_path::Flavor::NATIVE,
+        ));
+        let table = fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|raw| Ok(toml::from_str::<toml::Table>(&raw)?));
+        match table {
+            Ok(table) => {
+                let mut table = hoist_tui(table);
+                table.remove("include");
+                merge_tables(&mut merged, table);
+            }
+            Err(e) => warnings.push(format!("skipping included {}: {e}", path.display())),
+        }
+    }
+    (merged, warnings)
+}
+
+/// Settings may sit under a `[tui]` table, as the docs write them; read
+/// them as if they were at the top level.
+fn hoist_tui(mut table: toml::Table) -> toml::Table {
+    if let Some(toml::Value::Table(tui)) = table.remove("tui") {
+        merge_tables(&mut table, tui);
+    }
+    table
+}
+
+/// Lay `over` on top of `base`; tables are merged key by key, anything
+/// else is replaced.
+fn merge_tables(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(b)), toml::Value::Table(o)) => merge_tables(b, o),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Drop from `table` what `included` already says, leaving only the
+/// settings that differ.
+fn strip_included(table: &mut toml::Table, included: &toml::Table) {
+    for (key, value) in included {
+        match (table.get_mut(key), value) {
+            (Some(toml::Value::Table(t)), toml::Value::Table(inc)) => {
+                strip_included(t, inc);
+                if t.is_empty() {
+                    table.remove(key);
+                }
+            }
+            (Some(v), inc) if v == inc => {
+                table.remove(key);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Process-wide size/date display settings shared by the CLI and TUI
+/// formatters. Unset means the defaults.
+static DISPLAY_FORMAT: RwLock<Option<(SizeUnits, String)>> = RwLock::new(None);
+
+/// Make `cfg`'s size units and date format the ones used by every
+/// `format_size` / `format_date` call from now on.
+pub fn apply_display_format(cfg: &TuiConfig) {
+    if let Ok(mut slot) = DISPLAY_FORMAT.write() {
+        *slot = Some((cfg.size_units, cfg.date_format.clone()));
+    }
+}
+
+pub fn size_units() -> SizeUnits {
+    DISPLAY_FORMAT
+        .read()
+        .ok()
+        .and_then(|slot| slot.as_ref().map(|(units, _)| *units))
+        .unwrap_or_default()
+}
+
+pub fn date_format() -> String {
+    DISPLAY_FORMAT
+        .read()
+        .ok()
+        .and_then(|slot| slot.as_ref().map(|(_, fmt)| fmt.clone()))
+        .filter(|fmt| !fmt.is_empty())
+        .unwrap_or_else(default_date_format)
+}
+
 /// Sort a list of entries in-place based on the given sort field and direction.
 /// For all sort modes except `None`, folders are always sorted before files.
 pub fn sort_entries(entries: &mut [crate::pikpak::Entry], field: SortField, reverse: bool) {
@@ -818,3 +1614,180 @@ fn category_order(cat: crate::theme::FileCategory) -> u8 {
         FileCategory::Default => 7,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_keep_their_own_credentials() {
+        let cfg: AppConfig = toml::from_str(
+            r#"
+            username = "me@example.com"
+            password = "pw"
+
+            [profiles.work]
+            username = "me@work.example"
+            password = "work-pw"
+
+            [profiles.half]
+            username = "only@user.example"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            cfg.credentials_for(None),
+            Some(("me@example.com".into(), "pw".into()))
+        );
+        assert_eq!(
+            cfg.credentials_for(Some("work")),
+            Some(("me@work.example".into(), "work-pw".into()))
+        );
+        assert_eq!(cfg.credentials_for(Some("half")), None);
+        assert_eq!(cfg.credentials_for(Some("missing")), None);
+        assert_eq!(cfg.profile_names(), ["default", "half", "work"]);
+
+        let mut cfg = cfg;
+        assert!(cfg.forget(Some("work")));
+        assert!(!cfg.forget(Some("work")));
+        assert!(!cfg.forget(Some("missing")));
+        assert_eq!(cfg.credentials_for(Some("work")), None);
+        assert_eq!(cfg.profile_names(), ["default", "half", "work"]);
+        assert!(cfg.forget(None));
+        assert_eq!(cfg.credentials_for(None), None);
+
+        assert!(set_profile(Some("../escape")).is_err());
+        assert!(set_profile(Some("")).is_err());
+        assert_eq!(
+            profile_dir_for(PathBuf::from("/cfg"), Some("work")),
+            PathBuf::from("/cfg/profiles/work")
+        );
+    }
+
+    #[test]
+    fn keyring_storage_is_opt_in_with_a_password_per_profile() {
+        assert_eq!(TuiConfig::default().credential_store, CredentialStore::File);
+        let cfg: TuiConfig = toml::from_str(r#"credential_store = "keyring""#).unwrap();
+        assert_eq!(cfg.credential_store, CredentialStore::Keyring);
+        assert_eq!(password_account(None), "password:default");
+        assert_eq!(password_account(Some("work")), "password:work");
+    }
+
+    #[test]
+    fn includes_merge_under_the_main_file() {
+        let dir = std::env::temp_dir().join(format!("pikpaktui-include-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("keys.toml"),
+            "[tui.keys]\ndelete = \"x\"\ndownloads = \"ctrl+d\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("theme.toml"),
+            "color_scheme = \"custom\"\nnerd_font = true\n",
+        )
+        .unwrap();
+        let main: toml::Table = toml::from_str(
+            r#"
+            include = ["keys.toml", "theme.toml", "missing.toml"]
+            nerd_font = false
+
+            [keys]
+            delete = "d"
+            "#,
+        )
+        .unwrap();
+
+        let main = hoist_tui(main);
+        let (inc, warnings) = included(&main, &dir);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("missing.toml"), "{warnings:?}");
+        let mut merged = inc.clone();
+        merge_tables(&mut merged, main);
+        let cfg: TuiConfig = toml::Value::Table(merged).try_into().unwrap();
+        assert_eq!(cfg.color_scheme, ColorScheme::Custom);
+        assert!(!cfg.nerd_font);
+        assert_eq!(cfg.keys["delete"], "d");
+        assert_eq!(cfg.keys["downloads"], "ctrl+d");
+
+        // Saving keeps what the includes provide out of config.toml.
+        let toml::Value::Table(mut saved) = toml::Value::try_from(&cfg).unwrap() else {
+            unreachable!()
+        };
+        strip_included(&mut saved, &inc);
+        assert!(saved.get("color_scheme").is_none());
+        assert_eq!(saved["nerd_font"].as_bool(), Some(false));
+        assert_eq!(saved["keys"].as_table().unwrap().len(), 1);
+        assert_eq!(saved["include"].as_array().unwrap().len(), 3);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn preview_handlers_match_by_extension() {
+        let main: toml::Table = toml::from_str(
+            r#"
+            [preview.handlers]
+            json = "jq -C ."
+            ".RS" = "bat --color=always"
+            "#,
+        )
+        .unwrap();
+        let cfg: TuiConfig = toml::Value::Table(hoist_tui(main)).try_into().unwrap();
+        assert_eq!(cfg.preview.handler_for("data.JSON"), Some("jq -C ."));
+        assert_eq!(
+            cfg.preview.handler_for("main.rs"),
+            Some("bat --color=always")
+        );
+        assert_eq!(cfg.preview.handler_for("json"), None);
+        assert_eq!(cfg.preview.handler_for("notes.txt"), None);
+    }
+
+    #[test]
+    fn terminals_are_told_apart_on_windows() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert_eq!(
+            terminal_name(env(&[("TERM_PROGRAM", "WezTerm")]), true),
+            "WezTerm"
+        );
+        assert_eq!(
+            terminal_name(env(&[("WT_SESSION", "1f0c")]), false),
+            "WindowsTerminal"
+        );
+        assert_eq!(terminal_name(env(&[]), true), "conhost");
+        assert_eq!(terminal_name(env(&[("TERM", "xterm")]), true), "unknown");
+        assert_eq!(terminal_name(env(&[]), false), "unknown");
+    }
+
+    #[test]
+    fn dns_overrides_that_cannot_be_used_are_skipped() {
+        let cfg: TuiConfig = toml::from_str(
+            r#"
+            ip_family = "ipv4"
+            [dns_overrides]
+            "api-drive.mypikpak.com" = ["1.2.3.4", "::1"]
+            "user.mypikpak.com" = ["::1"]
+            "download.example" = ["no-such-host.invalid"]
+            "#,
+        )
+        .unwrap();
+        let (options, warnings) = cfg.net_options();
+        assert_eq!(
+            options.resolve,
+            [(
+                "api-drive.mypikpak.com".to_string(),
+                vec!["1.2.3.4:0".parse().unwrap()]
+            )]
+        );
+        // The IPv6-only override and the unresolvable one each warn, the
+        // latter twice: for the name, then for being left empty.
+        assert_eq!(warnings.len(), 3, "{warnings:?}");
+        assert!(warnings.iter().any(|w| w.contains("user.mypikpak.com")));
+        assert!(warnings.iter().any(|w| w.contains("no-such-host.invalid")));
+    }
+}
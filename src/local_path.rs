@@ -0,0 +1,269 @@
+//! Local paths and command lines as the user types them. Windows takes `\`
+//! as well as `/`, drive letters, and file names without `<>:"|?*`; the
+//! helpers take the convention as a `Flavor` so both can be tested
+//! anywhere, with `Flavor::NATIVE` for real use.
+
+use anyhow::{Context, Result, anyhow};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flavor {
+    Unix,
+    Windows,
+}
+
+impl Flavor {
+    pub const NATIVE: Self = if cfg!(windows) {
+        Self::Windows
+    } else {
+        Self::Unix
+    };
+
+    pub fn is_separator(self, c: char) -> bool {
+        c == '/' || (self == Self::Windows && c == '\\')
+    }
+
+    /// The separator completion adds: `\` on Windows.
+    pub fn separator(self) -> char {
+        match self {
+            Self::Unix => '/',
+            Self::Windows => '\\',
+        }
+    }
+
+    pub fn ends_with_separator(self, path: &str) -> bool {
+        path.chars()
+            .next_back()
+            .is_some_and(|c| self.is_separator(c))
+    }
+}
+
+/// `path` as text ending in a separator, for a folder the user will type
+/// on from.
+pub fn dir_with_separator(path: &Path) -> String {
+    let mut s = path.to_string_lossy().into_owned();
+    if !Flavor::NATIVE.ends_with_separator(&s) {
+        s.push(Flavor::NATIVE.separator());
+    }
+    s
+}
+
+/// Split typed input into the folder part and the name being typed:
+/// `/home/me/Dow` is (`/home/me/`, `Dow`), `C:\Users\me\Dow` is
+/// (`C:\Users\me\`, `Dow`). A bare drive (`D:`) is its root.
+pub fn split_dir(input: &str, flavor: Flavor) -> (String, String) {
+    if flavor == Flavor::Windows && is_drive(input) {
+        return (format!("{input}\\"), String::new());
+    }
+    match input.rfind(|c| flavor.is_separator(c)) {
+        Some(at) => (input[..=at].to_string(), input[at + 1..].to_string()),
+        // `C:name` is relative to the drive's current folder.
+        None if flavor == Flavor::Windows && input.get(..2).is_some_and(is_drive) => {
+            (input[..2].to_string(), input[2..].to_string())
+        }
+        None => (String::new(), input.to_string()),
+    }
+}
+
+fn is_drive(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() == 2 && b[0].is_ascii_alphabetic() && b[1] == b':'
+}
+
+/// `name` inside the folder `base` (as typed).
+pub fn join(base: &str, name: &str, flavor: Flavor) -> String {
+    if base.is_empty() || flavor.ends_with_separator(base) || base.ends_with(':') {
+        format!("{base}{name}")
+    } else {
+        format!("{base}{}{name}", flavor.separator())
+    }
+}
+
+/// `~/x` (or `~\x` on Windows) as a path under `home`; anything else as
+/// it is.
+pub fn expand_home(path: &str, home: Option<&Path>, flavor: Flavor) -> PathBuf {
+    let Some(home) = home else {
+        return PathBuf::from(path);
+    };
+    if path == "~" {
+        return home.to_path_buf();
+    }
+    match path.strip_prefix('~') {
+        Some(rest) if rest.starts_with(|c| flavor.is_separator(c)) => home.join(&rest[1..]),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Names Windows keeps for devices, with or without an extension.
+const RESERVED: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// A cloud file name made safe as one local path component: no
+/// separators or `..`, and on Windows none of `<>:"|?*`, control
+/// characters, trailing dots or spaces, or device names like `CON`.
+pub fn sanitize_component(name: &str, flavor: Flavor) -> String {
+    let name = name.replace(['/', '\\'], "_").replace("..", "_");
+    if flavor == Flavor::Unix {
+        return name;
+    }
+    let mut name: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let kept = name.trim_end_matches(['.', ' ']).len();
+    if kept < name.len() {
+        name.truncate(kept);
+        name.push('_');
+    }
+    let stem = name.split('.').next().unwrap_or("");
+    if RESERVED
+        .iter()
+        .any(|r| r.eq_ignore_ascii_case(stem.trim_end()))
+    {
+        name.insert(0, '_');
+    }
+    name
+}
+
+/// Split a command line from the config (the player, extra arguments) into
+/// words. Double or single quotes keep spaces together, as in
+/// `"C:\Program Files\mpv\mpv.exe" --fs`; backslashes are left alone so
+/// Windows paths need no escaping.
+pub fn split_command(cmd: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for c in cmd.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+/// Open a URL, file or folder with whatever the system uses for it: `open`
+/// on macOS, `start` on Windows, `xdg-open` elsewhere.
+pub fn open_default(target: &OsStr) -> Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // `start`'s first quoted argument is a window title.
+        let mut c = Command::new("cmd");
+        c.args(["/C", "start", ""]);
+        c
+    } else {
+        Command::new("xdg-open")
+    };
+    // Quiet, so a chatty opener can't draw over the TUI.
+    let status = command
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .with_context(|| format!("failed to run {:?}", command.get_program()))?;
+    if !status.success() {
+        return Err(anyhow!("{:?} failed ({status})", command.get_program()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_and_joins_windows_paths() {
+        let w = Flavor::Windows;
+        assert_eq!(
+            split_dir(r"C:\Users\me\Dow", w),
+            (r"C:\Users\me\".into(), "Dow".into())
+        );
+        assert_eq!(
+            split_dir("C:/Users/me/", w),
+            ("C:/Users/me/".into(), String::new())
+        );
+        assert_eq!(split_dir("D:", w), (r"D:\".into(), String::new()));
+        assert_eq!(split_dir("D:Mov", w), ("D:".into(), "Mov".into()));
+        assert_eq!(join(r"C:\Users", "me", w), r"C:\Users\me");
+        assert_eq!(join("D:", "Movies", w), "D:Movies");
+        // A backslash is just a character in a Unix name.
+        assert_eq!(
+            split_dir(r"/tmp/a\b", Flavor::Unix),
+            ("/tmp/".into(), r"a\b".into())
+        );
+        assert_eq!(join("/tmp", "x", Flavor::Unix), "/tmp/x");
+    }
+
+    #[test]
+    fn expands_home_with_either_separator() {
+        let home = Path::new("/home/me");
+        assert_eq!(
+            expand_home(r"~\Videos", Some(home), Flavor::Windows),
+            home.join("Videos")
+        );
+        assert_eq!(
+            expand_home(r"~\Videos", Some(home), Flavor::Unix),
+            PathBuf::from(r"~\Videos")
+        );
+        assert_eq!(expand_home("~", Some(home), Flavor::Unix), home);
+        assert_eq!(
+            expand_home("~other/x", Some(home), Flavor::Unix),
+            PathBuf::from("~other/x")
+        );
+    }
+
+    #[test]
+    fn names_are_made_safe_for_windows() {
+        let w = Flavor::Windows;
+        assert_eq!(sanitize_component("a/b\\..c", Flavor::Unix), "a_b__c");
+        assert_eq!(
+            sanitize_component("Q: what?.txt", Flavor::Unix),
+            "Q: what?.txt"
+        );
+        assert_eq!(sanitize_component("Q: what?.txt", w), "Q_ what_.txt");
+        assert_eq!(sanitize_component("notes. ", w), "notes_");
+        assert_eq!(sanitize_component("con.txt", w), "_con.txt");
+        assert_eq!(sanitize_component("console.txt", w), "console.txt");
+    }
+
+    #[test]
+    fn command_lines_keep_quoted_paths_whole() {
+        assert_eq!(
+            split_command(r#""C:\Program Files\mpv\mpv.exe" --fs"#),
+            [r"C:\Program Files\mpv\mpv.exe", "--fs"]
+        );
+        assert_eq!(
+            split_command("mpv  --title='My Film' ''"),
+            ["mpv", "--title=My Film", ""]
+        );
+        assert!(split_command("   ").is_empty());
+    }
+}
@@ -1,7 +1,13 @@
 mod cmd;
 mod config;
+mod local_path;
 mod pikpak;
+mod ratelimit;
+mod rclone;
+mod secrets;
+mod store;
 mod theme;
+mod torrent;
 mod tui;
 
 use crate::config::{AppConfig, TuiConfig, UpdateCheck};
@@ -12,17 +18,87 @@ use std::process::exit;
 use std::sync::mpsc;
 
 fn main() {
-    if let Err(e) = entry() {
-        eprintln!("Error: {e:#}");
-        exit(1);
+    match entry() {
+        Ok(0) => {}
+        Ok(status) => exit(status),
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            exit(cmd::exit_code(&e));
+        }
     }
 }
 
-fn entry() -> Result<()> {
-    let args: Vec<String> = env::args().skip(1).collect();
+/// Returns the exit status of a command that finished without an error;
+/// only `check` has one other than 0.
+fn entry() -> Result<i32> {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    // Global options come before the command, in any order:
+    // `--profile <name>` picks the account for whatever follows, TUI
+    // included; `--dry-run` makes the command only print what it would do.
+    let mut dry_run = false;
+    loop {
+        match args.first().map(String::as_str) {
+            Some("--profile") => {
+                let name = args
+                    .get(1)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("--profile requires a profile name"))?;
+                crate::config::set_profile(Some(&name))?;
+                args.drain(..2);
+            }
+            Some(arg) if arg.starts_with("--profile=") => {
+                let name = arg["--profile=".len()..].to_string();
+                crate::config::set_profile(Some(&name))?;
+                args.remove(0);
+            }
+            Some("--dry-run") => {
+                dry_run = true;
+                args.remove(0);
+            }
+            _ => break,
+        }
+    }
+
+    let cmd::OutputFlags {
+        color,
+        no_icons,
+        format,
+        quiet,
+    } = cmd::take_output_flags(&mut args)?;
+
+    if dry_run {
+        match args.first() {
+            None => return Err(anyhow!("--dry-run needs a command; the TUI has none")),
+            Some(cmd) if !cmd::DRY_RUN_COMMANDS.contains(&cmd.as_str()) => {
+                return Err(anyhow!(
+                    "`{cmd}` doesn't support --dry-run (supported: {})",
+                    cmd::DRY_RUN_COMMANDS.join(", ")
+                ));
+            }
+            Some(_) => cmd::set_dry_run(),
+        }
+    }
+
+    if format.is_machine() {
+        let flag = match format {
+            cmd::OutputFormat::Plain => "--plain",
+            _ => "--json",
+        };
+        match args.first() {
+            None => return Err(anyhow!("{flag} needs a command; the TUI has none")),
+            Some(cmd) if !cmd::DATA_COMMANDS.contains(&cmd.as_str()) => {
+                return Err(anyhow!(
+                    "`{cmd}` doesn't support {flag} (supported: {})",
+                    cmd::DATA_COMMANDS.join(", ")
+                ));
+            }
+            Some(_) => cmd::set_output_format(format),
+        }
+    }
 
     if args.is_empty() {
-        return run_tui();
+        return run_tui().map(|()| 0);
     }
 
     if args.len() >= 2
@@ -32,11 +108,19 @@ fn entry() -> Result<()> {
             "--help" | "-h" | "help" | "--version" | "-V"
         )
     {
-        return cmd::print_command_help(&args[0]);
+        return cmd::print_command_help(&args[0]).map(|()| 0);
     }
 
+    let tui_config = TuiConfig::load();
+    crate::config::apply_display_format(&tui_config);
+    crate::secrets::set_store(tui_config.credential_store);
+    cmd::set_output_style(color, no_icons);
+    if quiet {
+        cmd::set_quiet();
+    }
     let update_rx = cli_update_check(&args);
 
+    let mut status = 0;
     let result = match args[0].as_str() {
         "--version" | "-V" | "version" => {
             println!("pikpaktui {}", env!("CARGO_PKG_VERSION"));
@@ -44,30 +128,45 @@ fn entry() -> Result<()> {
         }
         "--help" | "-h" | "help" => cmd::help::run(),
         "ls" => cmd::ls::run(&args[1..]),
+        "tree" => cmd::ls::run_tree(&args[1..]),
+        "check" => cmd::check::run(&args[1..]).map(|s| status = s),
+        "du" => cmd::du::run(&args[1..]),
+        "index" => cmd::index::run(&args[1..]),
         "mv" => cmd::mv::run(&args[1..]),
         "cp" => cmd::cp::run(&args[1..]),
         "rename" => cmd::rename::run(&args[1..]),
         "rm" => cmd::rm::run(&args[1..]),
         "mkdir" => cmd::mkdir::run(&args[1..]),
+        "touch" => cmd::touch::run(&args[1..]),
+        "write" => cmd::write::run(&args[1..]),
         "download" => cmd::download::run(&args[1..]),
+        "sync" => cmd::sync::run(&args[1..]),
+        "backup" => cmd::backup::run(&args[1..]),
         "upload" => cmd::upload::run(&args[1..]),
         "share" => cmd::share::run(&args[1..]),
-        "quota" => cmd::quota::run(&args[1..]),
+        "quota" => cmd::quota::run(),
         "offline" => cmd::offline::run(&args[1..]),
         "tasks" => cmd::tasks::run(&args[1..]),
         "star" => cmd::star::run(&args[1..]),
         "unstar" => cmd::unstar::run(&args[1..]),
         "starred" => cmd::starred::run(&args[1..]),
+        "note" => cmd::note::run(&args[1..]),
+        "queue" => cmd::queue::run(&args[1..]),
         "events" => cmd::events::run(&args[1..]),
         "trash" => cmd::trash::run(&args[1..]),
         "untrash" => cmd::untrash::run(&args[1..]),
+        "cleanup" => cmd::cleanup::run(&args[1..]),
         "empty" => cmd::empty::run(&args[1..]),
         "info" => cmd::info::run(&args[1..]),
         "link" => cmd::link::run(&args[1..]),
+        "open" => cmd::open::run(&args[1..]),
         "cat" => cmd::cat::run(&args[1..]),
         "play" => cmd::play::run(&args[1..]),
         "vip" => cmd::vip::run(),
         "login" => cmd::login::run(&args[1..]),
+        "logout" => cmd::logout::run(&args[1..]),
+        "import-rclone" => cmd::import_rclone::run(&args[1..]),
+        "export-rclone" => cmd::export_rclone::run(&args[1..]),
         "update" => cmd::update::run(),
         "completions" => cmd::completions::run(&args[1..]),
         "__complete_path" => cmd::complete_path::run(&args[1..]),
@@ -86,7 +185,7 @@ fn entry() -> Result<()> {
         );
     }
 
-    result
+    result.map(|()| status)
 }
 
 fn cli_update_check(args: &[String]) -> Option<mpsc::Receiver<Option<String>>> {
@@ -94,7 +193,7 @@ fn cli_update_check(args: &[String]) -> Option<mpsc::Receiver<Option<String>>> {
         args.first().map(|s| s.as_str()),
         Some("update" | "completions" | "__complete_path")
     );
-    if skip {
+    if skip || cmd::quiet() {
         return None;
     }
 
@@ -113,17 +212,14 @@ fn cli_update_check(args: &[String]) -> Option<mpsc::Receiver<Option<String>>> {
 fn run_tui() -> Result<()> {
     let mut client = PikPak::new()?;
     let tui_config = TuiConfig::load();
-    client.thumbnail_size = tui_config.thumbnail_size.as_api_str().to_string();
+    crate::config::apply_display_format(&tui_config);
+    crate::secrets::set_store(tui_config.credential_store);
+    let warnings = tui_config.configure_client(&mut client)?;
 
     if client.has_valid_session() {
-        return tui::run(client, tui_config);
+        return tui::run(client, tui_config, warnings);
     }
 
-    let cfg = AppConfig::load()?;
-    let credentials = match (cfg.username, cfg.password) {
-        (Some(u), Some(p)) if !u.is_empty() && !p.is_empty() => Some((u, p)),
-        _ => None,
-    };
-
-    tui::run_with_credentials(client, credentials, tui_config)
+    let credentials = AppConfig::load()?.credentials();
+    tui::run_with_credentials(client, credentials, tui_config, warnings)
 }
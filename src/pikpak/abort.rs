@@ -0,0 +1,240 @@
+//! Stopping API calls part-way. Work run under [`abort_when`] sends its
+//! requests through an async client on a small shared tokio runtime rather
+//! than the blocking one. Once the flag goes up the request future is
+//! dropped, which closes its connection, and the call fails with
+//! [`Aborted`]. Calls made outside `abort_when` go out as before.
+//!
+//! API calls (everything sent through `send_retrying`) and the short reads
+//! behind previews ([`PikPak::send_abortable`]) take this path; downloads
+//! and uploads keep the blocking transfer client.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::Result;
+use reqwest::blocking::{Request, RequestBuilder, Response};
+
+use super::PikPak;
+
+/// How often a call in flight looks at its flag.
+const POLL: Duration = Duration::from_millis(50);
+
+thread_local! {
+    static FLAG: RefCell<Option<Arc<AtomicBool>>> = const { RefCell::new(None) };
+}
+
+/// Run `f` on this thread, aborting whatever API call it has in flight
+/// once `flag` is set, and failing any it starts after that.
+pub fn abort_when<T>(flag: Arc<AtomicBool>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<Arc<AtomicBool>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FLAG.with(|cell| *cell.borrow_mut() = self.0.take());
+        }
+    }
+    let _restore = Restore(FLAG.with(|cell| cell.replace(Some(flag))));
+    f()
+}
+
+/// The flag of the `abort_when` this thread is in, if any.
+pub(super) fn current() -> Option<Arc<AtomicBool>> {
+    FLAG.with(|cell| cell.borrow().clone())
+}
+
+/// Whether the work this thread is doing has been called off.
+pub(super) fn requested() -> bool {
+    current().is_some_and(|flag| flag.load(Ordering::Relaxed))
+}
+
+/// What an aborted call fails with.
+#[derive(Debug)]
+pub(super) struct Aborted;
+
+impl fmt::Display for Aborted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("request aborted")
+    }
+}
+
+impl std::error::Error for Aborted {}
+
+fn runtime() -> Option<&'static tokio::runtime::Runtime> {
+    static RUNTIME: OnceLock<Option<tokio::runtime::Runtime>> = OnceLock::new();
+    RUNTIME
+        .get_or_init(|| {
+            tokio::runtime::Builder::new_multi_thread()
+                .worker_threads(2)
+                .thread_name("pikpak-io")
+                .enable_all()
+                .build()
+                .ok()
+        })
+        .as_ref()
+}
+
+/// Whether `request` can go out on the async client: its body, if any, is
+/// in memory rather than a stream.
+pub(super) fn can_abort(request: &Request) -> bool {
+    runtime().is_some() && request.body().is_none_or(|b| b.as_bytes().is_some())
+}
+
+/// Send `request` on `client` and read the answer, or its first `limit`
+/// bytes, giving up as soon as `flag` is set; `None` when it was.
+pub(super) fn execute(
+    client: &reqwest::Client,
+    request: &Request,
+    limit: Option<u64>,
+    flag: &AtomicBool,
+) -> Option<reqwest::Result<Response>> {
+    let mut builder = client
+        .request(request.method().clone(), request.url().clone())
+        .headers(request.headers().clone());
+    if let Some(body) = request.body().and_then(|b| b.as_bytes()) {
+        builder = builder.body(body.to_vec());
+    }
+    if let Some(timeout) = request.timeout() {
+        builder = builder.timeout(*timeout);
+    }
+    let call = async move {
+        let mut response = builder.send().await?;
+        let mut answer = http::Response::builder()
+            .status(response.status())
+            .version(response.version());
+        if let Some(headers) = answer.headers_mut() {
+            *headers = response.headers().clone();
+        }
+        let limit = limit.unwrap_or(u64::MAX) as usize;
+        let mut body = Vec::new();
+        while body.len() < limit
+            && let Some(chunk) = response.chunk().await?
+        {
+            body.extend_from_slice(&chunk[..chunk.len().min(limit - body.len())]);
+        }
+        Ok(Response::from(
+            answer
+                .body(body)
+                .expect("status and headers came from a response"),
+        ))
+    };
+    runtime()?.block_on(async {
+        let mut call = std::pin::pin!(call);
+        loop {
+            if flag.load(Ordering::Relaxed) {
+                return None;
+            }
+            if let Ok(result) = tokio::time::timeout(POLL, &mut call).await {
+                return Some(result);
+            }
+        }
+    })
+}
+
+impl PikPak {
+    /// Send a request for file bytes outside the API (a thumbnail, the start
+    /// of a file), keeping at most `limit` bytes of the answer if given.
+    /// Under `abort_when` it fails with [`Aborted`] once the flag goes up,
+    /// like an API call.
+    pub fn send_abortable(&self, request: RequestBuilder, limit: Option<u64>) -> Result<Response> {
+        let (client, request) = request.build_split();
+        let request = request?;
+        match current().filter(|_| can_abort(&request)) {
+            Some(flag) => execute(&self.abortable_http, &request, limit, &flag)
+                .ok_or(Aborted)?
+                .map_err(Into::into),
+            None => client.execute(request).map_err(Into::into),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pikpak::{PikPak, SessionToken, now_unix};
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::time::Instant;
+
+    fn is_aborted(err: &anyhow::Error) -> bool {
+        err.chain().any(|e| e.is::<Aborted>())
+    }
+
+    #[test]
+    fn an_aborted_call_drops_its_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        // Reads the request and never answers; tells when the client hangs up.
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(10)))
+                .unwrap();
+            let mut buf = [0u8; 4096];
+            while stream.read(&mut buf).is_ok_and(|n| n > 0) {}
+            Instant::now()
+        });
+
+        let dir = std::env::temp_dir().join(format!("pikpaktui-abort-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let client = PikPak::for_tests(base_url, dir.join("session.json"));
+        client
+            .save_session(&SessionToken {
+                access_token: "test-access".into(),
+                refresh_token: "test-refresh".into(),
+                expires_at_unix: now_unix() + 3600,
+                ..SessionToken::default()
+            })
+            .unwrap();
+
+        let flag = Arc::new(AtomicBool::new(false));
+        let raise = Arc::clone(&flag);
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            raise.store(true, Ordering::Relaxed);
+        });
+        let started = Instant::now();
+        let err = abort_when(flag, || client.ls("")).unwrap_err();
+        let aborted_at = Instant::now();
+        assert!(is_aborted(&err), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        // The server saw the connection close, rather than time out.
+        let hung_up = server.join().unwrap();
+        assert!(hung_up.duration_since(aborted_at) < Duration::from_secs(5));
+
+        // Outside `abort_when` nothing is in force.
+        assert!(current().is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn an_abortable_read_stops_at_its_limit() {
+        use std::io::Write;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/file", listener.local_addr().unwrap());
+        // Ignores Range and sends the whole file.
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf);
+            let body = vec![b'x'; 1 << 20];
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n",
+                body.len()
+            );
+            let _ = stream.write_all(&body);
+        });
+
+        let client = PikPak::for_tests("http://127.0.0.1:9".into(), std::env::temp_dir());
+        let flag = Arc::new(AtomicBool::new(false));
+        let response = abort_when(flag, || {
+            client.send_abortable(client.http().get(&url), Some(100))
+        })
+        .unwrap();
+        assert_eq!(response.bytes().unwrap().len(), 100);
+    }
+}
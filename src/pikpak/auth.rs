@@ -13,4 +13,6 @@ pub(super) struct CaptchaInitResponse {
     pub(super) captcha_token: Option<String>,
     #[serde(default)]
     pub(super) url: Option<String>,
+    #[serde(default)]
+    pub(super) expires_in: Option<u64>,
 }
@@ -0,0 +1,132 @@
+//! The file operations a drive has to offer, as a trait, so code that only
+//! walks and rearranges a tree can run against something other than PikPak.
+//! `PikPak` is the real one; `LocalBackend`, a directory on disk, stands in
+//! for it in tests and the CLI's demo mode.
+
+use anyhow::{Result, anyhow};
+use std::ops::ControlFlow;
+
+use super::files::path_components;
+use super::{Entry, EntryKind, PikPak, not_found};
+
+/// A drive of folders and files, addressed by id; `""` is the root.
+pub trait Backend: Send + Sync {
+    /// List a folder, giving `on_page` each page as it arrives. Breaking
+    /// out of it cancels the listing with an error.
+    fn ls_streaming(
+        &self,
+        parent_id: &str,
+        on_page: &mut dyn FnMut(&[Entry]) -> ControlFlow<()>,
+    ) -> Result<Vec<Entry>>;
+
+    fn ls(&self, parent_id: &str) -> Result<Vec<Entry>> {
+        self.ls_streaming(parent_id, &mut |_| ControlFlow::Continue(()))
+    }
+
+    /// A listing that may be served from a cache of recent ones.
+    fn ls_cached(&self, parent_id: &str) -> Result<Vec<Entry>> {
+        self.ls(parent_id)
+    }
+
+    fn mv(&self, ids: &[&str], to_parent_id: &str) -> Result<()>;
+
+    fn cp(&self, ids: &[&str], to_parent_id: &str) -> Result<()>;
+
+    fn rename(&self, id: &str, new_name: &str) -> Result<()>;
+
+    /// Put `ids` in the trash, or delete them where there is none.
+    fn remove(&self, ids: &[&str]) -> Result<()>;
+
+    /// Take `ids` back out of the trash, to where they were.
+    fn untrash(&self, ids: &[&str]) -> Result<()>;
+
+    /// The id of the folder `id` is in.
+    fn parent_id(&self, id: &str) -> Result<String>;
+
+    fn mkdir(&self, parent_id: &str, name: &str) -> Result<Entry>;
+
+    /// Like `mkdir -p`: walk `path` from the root, creating each folder
+    /// that doesn't exist yet. Returns the id of the last folder and how
+    /// many were created; a file standing where a folder should be is an
+    /// error.
+    fn mkdir_p(&self, path: &str) -> Result<(String, u32)> {
+        let mut current_id = String::new();
+        let mut created = 0;
+        for name in path_components(path) {
+            let existing = self.ls(&current_id)?.into_iter().find(|e| e.name == name);
+            current_id = match existing {
+                Some(e) if e.kind == EntryKind::Folder => e.id,
+                Some(_) => return Err(anyhow!("'{name}' in path '{path}' is a file")),
+                None => {
+                    created += 1;
+                    self.mkdir(&current_id, name)?.id
+                }
+            };
+        }
+        Ok((current_id, created))
+    }
+
+    /// The id of the item at the `/`-separated `path`.
+    fn resolve_path(&self, path: &str) -> Result<String> {
+        let mut current_id = String::new();
+        for seg in path_components(path) {
+            current_id = self
+                .ls(&current_id)?
+                .into_iter()
+                .find(|e| e.name == seg)
+                .ok_or_else(|| not_found(format!("not found: '{seg}' in path '{path}'")))?
+                .id;
+        }
+        Ok(current_id)
+    }
+}
+
+impl Backend for PikPak {
+    fn ls_streaming(
+        &self,
+        parent_id: &str,
+        on_page: &mut dyn FnMut(&[Entry]) -> ControlFlow<()>,
+    ) -> Result<Vec<Entry>> {
+        PikPak::ls_streaming(self, parent_id, on_page)
+    }
+
+    fn ls(&self, parent_id: &str) -> Result<Vec<Entry>> {
+        PikPak::ls(self, parent_id)
+    }
+
+    fn ls_cached(&self, parent_id: &str) -> Result<Vec<Entry>> {
+        PikPak::ls_cached(self, parent_id)
+    }
+
+    fn mv(&self, ids: &[&str], to_parent_id: &str) -> Result<()> {
+        PikPak::mv(self, ids, to_parent_id)
+    }
+
+    fn cp(&self, ids: &[&str], to_parent_id: &str) -> Result<()> {
+        PikPak::cp(self, ids, to_parent_id)
+    }
+
+    fn rename(&self, id: &str, new_name: &str) -> Result<()> {
+        PikPak::rename(self, id, new_name)
+    }
+
+    fn remove(&self, ids: &[&str]) -> Result<()> {
+        PikPak::remove(self, ids)
+    }
+
+    fn untrash(&self, ids: &[&str]) -> Result<()> {
+        PikPak::untrash(self, ids)
+    }
+
+    fn parent_id(&self, id: &str) -> Result<String> {
+        Ok(self.file_info(id)?.parent_id.unwrap_or_default())
+    }
+
+    fn mkdir(&self, parent_id: &str, name: &str) -> Result<Entry> {
+        PikPak::mkdir(self, parent_id, name)
+    }
+
+    fn resolve_path(&self, path: &str) -> Result<String> {
+        PikPak::resolve_path(self, path)
+    }
+}
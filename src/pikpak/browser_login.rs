@@ -0,0 +1,346 @@
+//! Signing in through the browser, for when password login runs into a
+//! captcha. PikPak's captcha page is opened with its `redirect_uri` pointed
+//! at `http://127.0.0.1:<port>/callback`, so once the captcha is solved the
+//! browser comes back with its token and the password sign-in goes on with
+//! it. The callback only counts with this login's `state` nonce, and never
+//! when another site's page sends it.
+
+use anyhow::{Context, Result, anyhow};
+use std::hash::{BuildHasher, RandomState};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+use super::{LoginStep, PikPak, md5_hex};
+
+/// Give up when nothing has come back for this long.
+pub const BROWSER_LOGIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+impl PikPak {
+    /// Sign in with a password like [`PikPak::start_login`], but when
+    /// PikPak wants a captcha solved, serve a local page that sends the
+    /// browser to it and wait, up to `timeout` or until `cancelled`, for
+    /// the solved captcha to come back. `on_ready` gets the page's address
+    /// to open.
+    pub fn login_in_browser(
+        &mut self,
+        email: &str,
+        password: &str,
+        timeout: Duration,
+        cancelled: impl Fn() -> bool,
+        on_ready: impl FnOnce(&str),
+    ) -> Result<LoginStep> {
+        if email.trim().is_empty() || password.is_empty() {
+            return Err(anyhow!("enter your email and password first"));
+        }
+        self.device_id = md5_hex(email);
+        let captcha = self.init_captcha(email)?;
+        if captcha.captcha_token.is_none() {
+            let challenge = captcha
+                .url
+                .as_deref()
+                .ok_or_else(|| anyhow!("PikPak gave no captcha page to open"))?;
+            self.captcha_token = solve_captcha(challenge, timeout, cancelled, on_ready)?;
+        }
+        self.start_login(email, password)
+    }
+}
+
+/// Serve the page that leads to `challenge` and wait for the captcha token
+/// it redirects back with.
+fn solve_captcha(
+    challenge: &str,
+    timeout: Duration,
+    cancelled: impl Fn() -> bool,
+    on_ready: impl FnOnce(&str),
+) -> Result<String> {
+    let listener =
+        TcpListener::bind("127.0.0.1:0").context("can't open a local port for the login")?;
+    let session = Session {
+        origin: format!("http://{}", listener.local_addr()?),
+        state: nonce(),
+        challenge: challenge.to_string(),
+    };
+    listener.set_nonblocking(true)?;
+    on_ready(&format!("{}/", session.origin));
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        if cancelled() {
+            return Err(anyhow!("browser login cancelled"));
+        }
+        match listener.accept() {
+            Ok((stream, _)) => {
+                // One broken request doesn't end the login.
+                if let Ok(Some(token)) = session.respond(stream) {
+                    return Ok(token);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                if Instant::now() >= deadline {
+                    return Err(anyhow!(
+                        "browser login timed out after {} minutes",
+                        timeout.as_secs() / 60
+                    ));
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => return Err(e).context("browser login listener failed"),
+        }
+    }
+}
+
+/// 128 bits no other page can guess, tying the callback to this login.
+fn nonce() -> String {
+    let now = Instant::now();
+    format!(
+        "{:016x}{:016x}",
+        RandomState::new().hash_one(now),
+        RandomState::new().hash_one(now)
+    )
+}
+
+/// One browser login's local server.
+struct Session {
+    /// `http://127.0.0.1:<port>`.
+    origin: String,
+    state: String,
+    challenge: String,
+}
+
+impl Session {
+    /// The captcha page, sending the browser back to us once it's solved.
+    fn challenge_url(&self) -> Result<String> {
+        let mut url = reqwest::Url::parse(&self.challenge).context("bad captcha page address")?;
+        let redirect = format!("{}/callback?state={}", self.origin, self.state);
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(k, _)| k != "redirect_uri")
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs)
+            .append_pair("redirect_uri", &redirect);
+        Ok(url.into())
+    }
+
+    /// Answer one request; the captcha token once one came back.
+    fn respond(&self, mut stream: TcpStream) -> Result<Option<String>> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+        let request = read_request(&mut stream)?;
+        let (status, location, body, token) = self.route(&request);
+        let location = location
+            .map(|l| format!("Location: {l}\r\n"))
+            .unwrap_or_default();
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\n{location}Content-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        Ok(token)
+    }
+
+    /// Status, redirect, body and the captcha token for `request`.
+    fn route(&self, request: &Request) -> (&'static str, Option<String>, String, Option<String>) {
+        // Browsers name the page a cross-site request came from; a
+        // top-level navigation back from PikPak sends none, or ours.
+        if request
+            .origin
+            .as_deref()
+            .is_some_and(|origin| origin != self.origin)
+        {
+            return ("403 Forbidden", None, page("Not allowed", ""), None);
+        }
+        match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/") => match self.challenge_url() {
+                Ok(url) => ("302 Found", Some(url), page("Opening PikPak…", ""), None),
+                Err(e) => (
+                    "500 Internal Server Error",
+                    None,
+                    page("Couldn't open the captcha", &format!("{e:#}")),
+                    None,
+                ),
+            },
+            ("GET", "/callback") => {
+                if request.param("state").as_deref() != Some(self.state.as_str()) {
+                    return (
+                        "403 Forbidden",
+                        None,
+                        page(
+                            "Not this login",
+                            "Start the browser login again from pikpaktui.",
+                        ),
+                        None,
+                    );
+                }
+                match request.param("captcha_token").filter(|t| !t.is_empty()) {
+                    Some(token) => (
+                        "200 OK",
+                        None,
+                        page(
+                            "Captcha solved",
+                            "You can close this tab and go back to pikpaktui.",
+                        ),
+                        Some(token),
+                    ),
+                    None => (
+                        "400 Bad Request",
+                        None,
+                        page("No captcha token came back", "Try the captcha again."),
+                        None,
+                    ),
+                }
+            }
+            _ => ("404 Not Found", None, page("Not found", ""), None),
+        }
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    /// Query string pairs, decoded.
+    params: Vec<(String, String)>,
+    origin: Option<String>,
+}
+
+impl Request {
+    fn param(&self, name: &str) -> Option<String> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.trim().to_string())
+    }
+}
+
+fn read_request(stream: &mut TcpStream) -> Result<Request> {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut request = parse_request(&request_line)?;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':')
+            && key.eq_ignore_ascii_case("origin")
+        {
+            request.origin = Some(value.trim().to_string());
+        }
+    }
+    Ok(request)
+}
+
+fn parse_request(request_line: &str) -> Result<Request> {
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let target = parts
+        .next()
+        .ok_or_else(|| anyhow!("malformed request line"))?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    // Borrow `Url`'s form decoding rather than pull in a crate for it.
+    let params = reqwest::Url::parse(&format!("http://localhost/?{query}"))
+        .map(|u| u.query_pairs().into_owned().collect())
+        .unwrap_or_default();
+    Ok(Request {
+        method,
+        path: path.to_string(),
+        params,
+        origin: None,
+    })
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn page(title: &str, text: &str) -> String {
+    format!(
+        "<!doctype html>\n<html><head><meta charset=\"utf-8\"><title>pikpaktui login</title></head>\n\
+         <body style=\"font-family: system-ui, sans-serif; margin: 3em auto; max-width: 40em\">\n\
+         <h1>{}</h1><p>{}</p>\n</body></html>\n",
+        html_escape(title),
+        html_escape(text)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> Session {
+        Session {
+            origin: "http://127.0.0.1:4000".into(),
+            state: "s3cret".into(),
+            challenge: "https://user.mypikpak.com/captcha?a=1&redirect_uri=xlaccsdk01%3A%2F%2Fcb"
+                .into(),
+        }
+    }
+
+    fn request(line: &str, origin: Option<&str>) -> Request {
+        Request {
+            origin: origin.map(String::from),
+            ..parse_request(line).unwrap()
+        }
+    }
+
+    #[test]
+    fn the_captcha_page_sends_the_browser_back_here() {
+        let url = reqwest::Url::parse(&session().challenge_url().unwrap()).unwrap();
+        let pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+        assert_eq!(
+            pairs,
+            [
+                ("a".to_string(), "1".to_string()),
+                (
+                    "redirect_uri".to_string(),
+                    "http://127.0.0.1:4000/callback?state=s3cret".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_this_logins_callback_is_taken() {
+        let session = session();
+        let taken = |line: &str, origin: Option<&str>| session.route(&request(line, origin)).3;
+
+        let good = "GET /callback?state=s3cret&captcha_token=ck%2B1 HTTP/1.1\r\n";
+        assert_eq!(taken(good, None).as_deref(), Some("ck+1"));
+        assert_eq!(
+            taken(good, Some("http://127.0.0.1:4000")).as_deref(),
+            Some("ck+1")
+        );
+        // Another site's page, or a guess at the state.
+        assert_eq!(taken(good, Some("https://evil.example")), None);
+        assert_eq!(
+            taken(
+                "GET /callback?state=guess&captcha_token=ck HTTP/1.1\r\n",
+                None
+            ),
+            None
+        );
+        assert_eq!(taken("GET /callback?state=s3cret HTTP/1.1\r\n", None), None);
+
+        assert!(parse_request("").is_err());
+        assert_ne!(nonce(), nonce());
+    }
+
+    #[test]
+    fn a_cancelled_login_stops_listening() {
+        let err = solve_captcha(
+            "https://example.com/",
+            BROWSER_LOGIN_TIMEOUT,
+            || true,
+            |_| {},
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("cancelled"));
+    }
+}
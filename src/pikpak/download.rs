@@ -1,10 +1,53 @@
 use anyhow::{Context, Result, anyhow};
+use std::collections::HashSet;
 use std::fs;
 use std::io;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
 
+use crate::ratelimit::Throttled;
+
+use super::history::{Direction, TransferRecord, verify_download};
 use super::{Entry, EntryKind, PikPak, sanitize_filename};
 
+/// `name`, or the first free `name (1).ext`, `name (2).ext`, ... when it is
+/// already in `taken`, which the result is added to. Compared ignoring case,
+/// since that's how macOS and Windows file systems compare names.
+pub fn unique_name(name: &str, taken: &mut HashSet<String>) -> String {
+    if taken.insert(name.to_lowercase()) {
+        return name.to_string();
+    }
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) if dot > 0 => name.split_at(dot),
+        _ => (name, ""),
+    };
+    (1..)
+        .map(|n| format!("{stem} ({n}){ext}"))
+        .find(|candidate| taken.insert(candidate.to_lowercase()))
+        .expect("an unbounded counter always finds a free name")
+}
+
+/// A writer that reports the running byte count after every write.
+struct Counting<W, F> {
+    inner: W,
+    written: u64,
+    on_write: F,
+}
+
+impl<W: io::Write, F: FnMut(u64)> io::Write for Counting<W, F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        (self.on_write)(self.written);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
 impl PikPak {
     /// Returns (download_url, total_size) for a file.
     pub fn download_url(&self, file_id: &str) -> Result<(String, u64)> {
@@ -44,7 +87,7 @@ impl PikPak {
         url: &str,
         existing_size: u64,
     ) -> Result<(reqwest::blocking::Response, u64)> {
-        let mut rb = self.http.get(url);
+        let mut rb = self.transfer_http.get(url);
         if existing_size > 0 {
             rb = rb.header("Range", format!("bytes={}-", existing_size));
         }
@@ -64,6 +107,19 @@ impl PikPak {
     }
 
     pub fn download_to(&self, file_id: &str, dest: &std::path::Path) -> Result<u64> {
+        self.download_to_with_progress(file_id, dest, |_, _| {})
+    }
+
+    /// `download_to`, calling `progress(bytes_on_disk, total_size)` as data
+    /// arrives (including once up front, with what a resume starts from).
+    /// The finished file is checked against PikPak's hash and recorded in the
+    /// transfer history.
+    pub fn download_to_with_progress(
+        &self,
+        file_id: &str,
+        dest: &std::path::Path,
+        mut progress: impl FnMut(u64, u64),
+    ) -> Result<u64> {
         let info = self.file_info(file_id)?;
         let download_url = info
             .download_url()
@@ -75,6 +131,7 @@ impl PikPak {
             return Ok(existing_size);
         }
 
+        let started = Instant::now();
         let (response, start_offset) = self.download_stream(download_url, existing_size)?;
         let mut file = if start_offset > 0 {
             fs::OpenOptions::new().append(true).open(dest)?
@@ -82,16 +139,57 @@ impl PikPak {
             fs::File::create(dest)?
         };
 
-        let mut reader: Box<dyn io::Read> = Box::new(response);
-        let bytes = io::copy(&mut reader, &mut file).context("download write failed")?;
+        progress(start_offset, total_size);
+        let mut reader = Throttled::new(response, Arc::clone(&self.download_limiter));
+        let mut writer = Counting {
+            inner: &mut file,
+            written: start_offset,
+            on_write: |written| progress(written, total_size),
+        };
+        let bytes = io::copy(&mut reader, &mut writer).context("download write failed")?;
+        drop(file);
+
+        let record = TransferRecord::new(
+            Direction::Download,
+            file_id,
+            &info.name,
+            start_offset + bytes,
+            dest,
+            bytes,
+            started.elapsed(),
+        );
+        verify_download(record, dest, info.hash.as_deref().unwrap_or_default())?;
         Ok(start_offset + bytes)
     }
 
+    /// Stream the whole file into `out`, e.g. stdout. Returns bytes written.
+    pub fn download_to_writer(&self, file_id: &str, out: &mut impl io::Write) -> Result<u64> {
+        let info = self.file_info(file_id)?;
+        let download_url = info
+            .download_url()
+            .ok_or_else(|| anyhow!("no download link for file {}", file_id))?;
+        let (response, _) = self.download_stream(download_url, 0)?;
+        let mut reader = Throttled::new(response, Arc::clone(&self.download_limiter));
+        io::copy(&mut reader, out).context("download write failed")
+    }
+
     pub fn fetch_text_preview(
         &self,
         file_id: &str,
         max_bytes: u64,
     ) -> Result<(String, String, u64, bool)> {
+        let (name, bytes, file_size, truncated) = self.fetch_preview_bytes(file_id, max_bytes)?;
+        let content = String::from_utf8_lossy(&bytes).into_owned();
+        Ok((name, content, file_size, truncated))
+    }
+
+    /// The first `max_bytes` of a file as they are, with its name, its full
+    /// size and whether that's more than was read.
+    pub fn fetch_preview_bytes(
+        &self,
+        file_id: &str,
+        max_bytes: u64,
+    ) -> Result<(String, Vec<u8>, u64, bool)> {
         let info = self.file_info(file_id)?;
         let url = info
             .download_url()
@@ -99,10 +197,12 @@ impl PikPak {
         let file_size = info.file_size();
 
         let response = self
-            .http
-            .get(url)
-            .header("Range", format!("bytes=0-{}", max_bytes.saturating_sub(1)))
-            .send()
+            .send_abortable(
+                self.http
+                    .get(url)
+                    .header("Range", format!("bytes=0-{}", max_bytes.saturating_sub(1))),
+                Some(max_bytes),
+            )
             .context("text preview request failed")?;
 
         let status = response.status();
@@ -112,9 +212,66 @@ impl PikPak {
 
         let bytes = response.bytes().context("text preview read failed")?;
         let truncated = file_size > bytes.len() as u64;
-        let content = String::from_utf8_lossy(&bytes).into_owned();
+        Ok((info.name, bytes.to_vec(), file_size, truncated))
+    }
+
+    /// The first `max_bytes` of a file, e.g. to pull a frame out of a video.
+    pub fn fetch_head(&self, file_id: &str, max_bytes: u64) -> Result<Vec<u8>> {
+        let info = self.file_info(file_id)?;
+        let url = info
+            .download_url()
+            .ok_or_else(|| anyhow!("no download link for file {}", file_id))?;
+
+        let response = self
+            .send_abortable(
+                self.transfer_http
+                    .get(url)
+                    .header("Range", format!("bytes=0-{}", max_bytes.saturating_sub(1))),
+                Some(max_bytes),
+            )
+            .context("ranged download request failed")?;
+
+        let status = response.status();
+        if !status.is_success() && status != reqwest::StatusCode::PARTIAL_CONTENT {
+            return Err(anyhow!("ranged download failed ({})", status));
+        }
+
+        // A server that ignores Range sends the whole file; stop reading at
+        // the limit either way.
+        use std::io::Read as _;
+        let mut head = Vec::new();
+        response
+            .take(max_bytes)
+            .read_to_end(&mut head)
+            .context("ranged download read failed")?;
+        Ok(head)
+    }
+
+    /// Bytes `start..=end` of a download link (as from `download_url`).
+    /// Unlike `fetch_head`, a server that ignores `Range` is an error: the
+    /// range may be far into a large file.
+    pub fn fetch_range(&self, url: &str, start: u64, end: u64) -> Result<Vec<u8>> {
+        let response = self
+            .send_abortable(
+                self.transfer_http
+                    .get(url)
+                    .header("Range", format!("bytes={start}-{end}")),
+                Some(end.saturating_sub(start) + 1),
+            )
+            .context("ranged download request failed")?;
+
+        let status = response.status();
+        if status != reqwest::StatusCode::PARTIAL_CONTENT {
+            return Err(anyhow!("ranged download failed ({})", status));
+        }
 
-        Ok((info.name, content, file_size, truncated))
+        use std::io::Read as _;
+        let mut bytes = Vec::new();
+        response
+            .take(end.saturating_sub(start) + 1)
+            .read_to_end(&mut bytes)
+            .context("ranged download read failed")?;
+        Ok(bytes)
     }
 
     pub fn download_dir(
@@ -160,10 +317,37 @@ impl PikPak {
             }
         }
 
+        // PikPak allows several items with one name in a folder; give the
+        // later ones `name (1)` etc. locally rather than mixing them up.
+        let mut taken = HashSet::new();
+        let mut local_name = |entry: &Entry| {
+            let name = unique_name(&sanitize_filename(&entry.name), &mut taken);
+            if name != sanitize_filename(&entry.name) && !self.quiet {
+                println!(
+                    "  '{}' has a duplicate name, saving as '{}'",
+                    entry.name, name
+                );
+            }
+            name
+        };
+        let folders: Vec<(Entry, String)> = folders
+            .into_iter()
+            .map(|f| {
+                let name = local_name(&f);
+                (f, name)
+            })
+            .collect();
+        let files: Vec<(Entry, String)> = files
+            .into_iter()
+            .map(|f| {
+                let name = local_name(&f);
+                (f, name)
+            })
+            .collect();
+
         let mut failed_count = 0usize;
-        for folder in &folders {
-            if let Err(e) = std::fs::create_dir_all(local_dir.join(sanitize_filename(&folder.name)))
-            {
+        for (folder, name) in &folders {
+            if let Err(e) = std::fs::create_dir_all(local_dir.join(name)) {
                 eprintln!("  [error] mkdir '{}': {}", folder.name, e);
                 failed_count += 1;
             }
@@ -171,9 +355,9 @@ impl PikPak {
 
         let ok = Arc::new(AtomicUsize::new(0));
         let failed = Arc::new(AtomicUsize::new(0));
-        let (tx, rx) = std::sync::mpsc::channel::<Entry>();
-        for entry in files {
-            tx.send(entry).ok();
+        let (tx, rx) = std::sync::mpsc::channel::<(Entry, String)>();
+        for file in files {
+            tx.send(file).ok();
         }
         drop(tx);
         let rx = Arc::new(Mutex::new(rx));
@@ -184,15 +368,21 @@ impl PikPak {
                 let ok = Arc::clone(&ok);
                 let failed = Arc::clone(&failed);
                 s.spawn(move || {
-                    while let Ok(entry) = rx.lock().unwrap_or_else(|e| e.into_inner()).recv() {
-                        let dest = local_dir.join(sanitize_filename(&entry.name));
+                    while let Ok((entry, name)) =
+                        rx.lock().unwrap_or_else(|e| e.into_inner()).recv()
+                    {
+                        let dest = local_dir.join(name);
                         let local_size = dest.metadata().map(|m| m.len()).unwrap_or(0);
                         if local_size > 0 && local_size == entry.size {
-                            println!("  skipping '{}' (already complete)", dest.display());
+                            if !self.quiet {
+                                println!("  skipping '{}' (already complete)", dest.display());
+                            }
                             ok.fetch_add(1, Ordering::Relaxed);
                             continue;
                         }
-                        println!("  {}", dest.display());
+                        if !self.quiet {
+                            println!("  {}", dest.display());
+                        }
                         match self.download_to(&entry.id, &dest) {
                             Ok(_) => {
                                 ok.fetch_add(1, Ordering::Relaxed);
@@ -210,8 +400,8 @@ impl PikPak {
         let mut total_ok = ok.load(Ordering::Relaxed);
         let mut total_failed = failed.load(Ordering::Relaxed) + failed_count;
 
-        for folder in folders {
-            let sub_dir = local_dir.join(sanitize_filename(&folder.name));
+        for (folder, name) in folders {
+            let sub_dir = local_dir.join(name);
             match self.download_dir_inner(&folder.id, &sub_dir, workers) {
                 Ok((sub_ok, sub_fail)) => {
                     total_ok += sub_ok;
@@ -227,3 +417,20 @@ impl PikPak {
         Ok((total_ok, total_failed))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_name_numbers_clashes_before_the_extension() {
+        let mut taken = HashSet::new();
+        assert_eq!(unique_name("ep01.mkv", &mut taken), "ep01.mkv");
+        assert_eq!(unique_name("EP01.mkv", &mut taken), "EP01 (1).mkv");
+        assert_eq!(unique_name("ep01.mkv", &mut taken), "ep01 (2).mkv");
+        assert_eq!(unique_name("README", &mut taken), "README");
+        assert_eq!(unique_name("README", &mut taken), "README (1)");
+        assert_eq!(unique_name(".env", &mut taken), ".env");
+        assert_eq!(unique_name(".env", &mut taken), ".env (1)");
+    }
+}
@@ -31,6 +31,10 @@ pub(super) struct DriveFile {
     tags: Vec<DriveFileTag>,
     #[serde(default)]
     thumbnail_link: Option<String>,
+    #[serde(default)]
+    delete_time: Option<String>,
+    #[serde(default)]
+    hash: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -59,6 +63,8 @@ impl DriveFile {
             modified_time: self.modified_time.unwrap_or_default(),
             starred,
             thumbnail_link: self.thumbnail_link,
+            purge_time: self.delete_time.unwrap_or_default(),
+            hash: self.hash.unwrap_or_default(),
         }
     }
 
@@ -73,6 +79,8 @@ impl DriveFile {
             modified_time: self.modified_time.unwrap_or_default(),
             starred,
             thumbnail_link: self.thumbnail_link,
+            purge_time: self.delete_time.unwrap_or_default(),
+            hash: self.hash.unwrap_or_default(),
         }
     }
 }
@@ -0,0 +1,205 @@
+//! What kind of failure an error is, so the CLI can exit with a code a
+//! script can act on. Errors stay plain `anyhow` errors; the kind rides
+//! along as a [`Failure`] somewhere in the chain.
+
+use std::fmt;
+
+use reqwest::StatusCode;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// A path, file, share or trashed item that isn't there.
+    NotFound,
+    /// Not logged in, or the session or credentials were rejected.
+    Auth,
+    /// The server couldn't be reached, timed out or failed on its side.
+    Network,
+    /// Out of space, or over a daily / concurrent-task limit.
+    Quota,
+}
+
+/// An error message tagged with its [`FailureKind`]; displays as just the
+/// message.
+#[derive(Debug)]
+struct Failure {
+    kind: FailureKind,
+    message: String,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Failure {}
+
+fn tagged(kind: FailureKind, message: impl Into<String>) -> anyhow::Error {
+    anyhow::Error::new(Failure {
+        kind,
+        message: message.into(),
+    })
+}
+
+/// The kind of the first tagged error in `err`'s chain; transport errors
+/// and missing local files count even when untagged.
+pub fn failure_kind(err: &anyhow::Error) -> Option<FailureKind> {
+    err.chain().find_map(|cause| {
+        if let Some(failure) = cause.downcast_ref::<Failure>() {
+            Some(failure.kind)
+        } else if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            (e.is_connect() || e.is_timeout() || e.is_request()).then_some(FailureKind::Network)
+        } else if let Some(e) = cause.downcast_ref::<std::io::Error>() {
+            (e.kind() == std::io::ErrorKind::NotFound).then_some(FailureKind::NotFound)
+        } else {
+            None
+        }
+    })
+}
+
+/// The `error` codes of a response body that say which kind of failure it
+/// was.
+const AUTH_CODES: &[&str] = &[
+    "unauthenticated",
+    "invalid_grant",
+    "invalid_account_or_password",
+    "captcha_invalid",
+];
+const NOT_FOUND_CODES: &[&str] = &[
+    "not_found",
+    "file_not_found",
+    "file_not_exist",
+    "share_not_exist",
+];
+const QUOTA_CODES: &[&str] = &[
+    "space_not_enough",
+    "file_space_not_enough",
+    "task_daily_create_limit",
+    "task_run_nums_limit",
+];
+
+/// The `error` code of a JSON error body, e.g. `file_space_not_enough`.
+fn error_code(body: &str) -> Option<String> {
+    let body: serde_json::Value = serde_json::from_str(body).ok()?;
+    body.get("error")?.as_str().map(str::to_lowercase)
+}
+
+fn classify(status: StatusCode, body: &str) -> Option<FailureKind> {
+    let code = error_code(body);
+    let is = |codes: &[&str]| code.as_deref().is_some_and(|c| codes.contains(&c));
+    if status == StatusCode::UNAUTHORIZED || is(AUTH_CODES) {
+        Some(FailureKind::Auth)
+    } else if status == StatusCode::NOT_FOUND || is(NOT_FOUND_CODES) {
+        Some(FailureKind::NotFound)
+    } else if is(QUOTA_CODES) {
+        Some(FailureKind::Quota)
+    } else if status.is_server_error() {
+        Some(FailureKind::Network)
+    } else {
+        None
+    }
+}
+
+/// `"{op} failed ({status}): {body}"`, tagged with the kind the status and
+/// body point to.
+pub(super) fn api_error(op: &str, status: StatusCode, body: &str) -> anyhow::Error {
+    let message = format!("{} failed ({}): {}", op, status, super::sanitize(body));
+    match classify(status, body) {
+        Some(kind) => tagged(kind, message),
+        None => anyhow::anyhow!(message),
+    }
+}
+
+/// An error for a path, file or item that isn't there.
+pub fn not_found(message: impl Into<String>) -> anyhow::Error {
+    tagged(FailureKind::NotFound, message)
+}
+
+/// An error that means the user has to log in (again).
+pub fn auth_error(message: impl Into<String>) -> anyhow::Error {
+    tagged(FailureKind::Auth, message)
+}
+
+/// A failed session renewal: an auth error asking for a new login, unless
+/// PikPak couldn't be reached, which a login won't fix either.
+pub(super) fn renewal_failed(err: anyhow::Error, what: &str) -> anyhow::Error {
+    if failure_kind(&err) == Some(FailureKind::Network) {
+        return err.context(format!("{what}: PikPak couldn't be reached"));
+    }
+    auth_error(format!("{what}: {err:#}\nPlease log in again."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn api_errors_are_classified_by_status_and_body() {
+        let kind = |status: u16, body: &str| {
+            failure_kind(&api_error(
+                "op",
+                StatusCode::from_u16(status).unwrap(),
+                body,
+            ))
+        };
+        assert_eq!(kind(401, ""), Some(FailureKind::Auth));
+        assert_eq!(
+            kind(400, r#"{"error":"invalid_grant"}"#),
+            Some(FailureKind::Auth)
+        );
+        assert_eq!(kind(404, ""), Some(FailureKind::NotFound));
+        assert_eq!(
+            kind(400, r#"{"error":"file_not_found"}"#),
+            Some(FailureKind::NotFound)
+        );
+        assert_eq!(
+            kind(403, r#"{"error":"file_space_not_enough"}"#),
+            Some(FailureKind::Quota)
+        );
+        assert_eq!(kind(503, "busy"), Some(FailureKind::Network));
+        assert_eq!(kind(400, r#"{"error":"invalid_argument"}"#), None);
+        // Only the code counts, not words that turn up in the message.
+        assert_eq!(
+            kind(
+                401,
+                r#"{"error":"unauthenticated","error_description":"quota check needs a login"}"#
+            ),
+            Some(FailureKind::Auth)
+        );
+        assert_eq!(
+            kind(
+                404,
+                r#"{"error":"not_found","error_description":"no capacity"}"#
+            ),
+            Some(FailureKind::NotFound)
+        );
+        assert_eq!(
+            kind(
+                400,
+                r#"{"error":"invalid_argument","error_description":"bad quota"}"#
+            ),
+            None
+        );
+        assert_eq!(kind(400, "over quota"), None);
+
+        let err = api_error("quota", StatusCode::INTERNAL_SERVER_ERROR, "oops");
+        assert_eq!(
+            err.to_string(),
+            "quota failed (500 Internal Server Error): oops"
+        );
+    }
+
+    #[test]
+    fn kind_survives_added_context() {
+        let err = tagged(FailureKind::NotFound, "'a' not found");
+        let err = Err::<(), _>(err).context("while moving").unwrap_err();
+        assert_eq!(failure_kind(&err), Some(FailureKind::NotFound));
+        assert_eq!(format!("{err:#}"), "while moving: 'a' not found");
+
+        let io = std::io::Error::from(std::io::ErrorKind::NotFound);
+        let err = anyhow::Error::new(io).context("open x");
+        assert_eq!(failure_kind(&err), Some(FailureKind::NotFound));
+        assert_eq!(failure_kind(&anyhow::anyhow!("plain")), None);
+    }
+}
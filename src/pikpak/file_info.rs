@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use super::{Entry, EntryKind};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MediaLink {
     #[serde(default)]
@@ -22,6 +24,37 @@ pub struct MediaVideo {
     pub audio_codec: Option<String>,
 }
 
+impl MediaVideo {
+    /// The fields the API filled in, labelled for display: duration,
+    /// resolution, codecs and bitrate.
+    pub fn details(&self) -> Vec<(&'static str, String)> {
+        let mut out = Vec::new();
+        if let Some(secs) = self.duration.filter(|d| *d > 0.0) {
+            let total = secs as u64;
+            let (h, m, s) = (total / 3600, (total % 3600) / 60, total % 60);
+            let text = if h > 0 {
+                format!("{h}:{m:02}:{s:02}")
+            } else {
+                format!("{m}:{s:02}")
+            };
+            out.push(("Duration", text));
+        }
+        if let (Some(w), Some(h)) = (self.width, self.height) {
+            out.push(("Resolution", format!("{w}x{h}")));
+        }
+        if let Some(codec) = self.video_codec.as_deref().filter(|c| !c.is_empty()) {
+            out.push(("Video", codec.to_string()));
+        }
+        if let Some(codec) = self.audio_codec.as_deref().filter(|c| !c.is_empty()) {
+            out.push(("Audio", codec.to_string()));
+        }
+        if let Some(rate) = self.bit_rate.filter(|r| *r > 0) {
+            out.push(("Bitrate", format!("{} kbps", rate / 1000)));
+        }
+        out
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MediaInfo {
     #[serde(default)]
@@ -41,6 +74,9 @@ pub struct FileInfoResponse {
     pub name: String,
     #[serde(default)]
     pub kind: Option<String>,
+    /// Empty for items in the root folder.
+    #[serde(default)]
+    pub parent_id: Option<String>,
     #[serde(default)]
     pub size: Option<String>,
     #[serde(default)]
@@ -59,6 +95,9 @@ pub struct FileInfoResponse {
     pub links: Option<std::collections::HashMap<String, LinkInfo>>,
     #[serde(default)]
     pub medias: Option<Vec<MediaInfo>>,
+    /// In the trash, waiting to be purged.
+    #[serde(default)]
+    pub trashed: bool,
 }
 
 impl FileInfoResponse {
@@ -71,12 +110,44 @@ impl FileInfoResponse {
             }))
     }
 
+    /// Video metadata of the original file, or failing that of the first
+    /// transcode that has any.
+    pub fn video(&self) -> Option<&MediaVideo> {
+        let medias = self.medias.as_deref()?;
+        medias
+            .iter()
+            .find(|m| m.is_origin == Some(true) && m.video.is_some())
+            .or_else(|| medias.iter().find(|m| m.video.is_some()))?
+            .video
+            .as_ref()
+    }
+
     pub fn file_size(&self) -> u64 {
         self.size
             .as_deref()
             .and_then(|s| s.parse::<u64>().ok())
             .unwrap_or(0)
     }
+
+    pub(super) fn into_entry(self) -> Entry {
+        let size = self.file_size();
+        Entry {
+            kind: if self.kind.as_deref().is_some_and(|k| k.contains("folder")) {
+                EntryKind::Folder
+            } else {
+                EntryKind::File
+            },
+            id: self.id.unwrap_or_default(),
+            name: self.name,
+            size,
+            created_time: self.created_time.unwrap_or_default(),
+            modified_time: self.modified_time.unwrap_or_default(),
+            starred: false,
+            thumbnail_link: self.thumbnail_link,
+            purge_time: String::new(),
+            hash: self.hash.unwrap_or_default(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -84,3 +155,36 @@ pub struct LinkInfo {
     #[serde(default)]
     pub url: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn video_details_skip_missing_fields() {
+        let video = MediaVideo {
+            height: Some(1080),
+            width: Some(1920),
+            duration: Some(5525.4),
+            bit_rate: Some(4_500_000),
+            video_codec: Some("h264".into()),
+            audio_codec: None,
+        };
+        assert_eq!(
+            video.details(),
+            [
+                ("Duration", "1:32:05".to_string()),
+                ("Resolution", "1920x1080".to_string()),
+                ("Video", "h264".to_string()),
+                ("Bitrate", "4500 kbps".to_string()),
+            ]
+        );
+        let short = MediaVideo {
+            duration: Some(245.0),
+            width: None,
+            ..video
+        };
+        assert_eq!(short.details()[0], ("Duration", "4:05".to_string()));
+        assert_eq!(short.details()[1].0, "Video");
+    }
+}
@@ -1,11 +1,31 @@
 use anyhow::{Context, Result, anyhow};
+use std::ops::ControlFlow;
 
 use super::drive::{DriveFileResponse, DriveListResponse};
-use super::{Entry, FileInfoResponse, PikPak, ensure_success, json_or_api_error};
+use super::{Entry, FileInfoResponse, PikPak, ensure_success, failure_kind, json_or_api_error};
+
+/// How far [`PikPak::empty_trash`] got.
+pub struct EmptiedTrash {
+    pub deleted: usize,
+    /// The trash stopped shrinking, so it gave up with items left.
+    pub stalled: bool,
+}
 
 impl PikPak {
     pub fn ls(&self, parent_id: &str) -> Result<Vec<Entry>> {
-        let token = self.access_token()?;
+        self.ls_streaming(parent_id, |_| ControlFlow::Continue(()))
+    }
+
+    /// List a folder, following `next_page_token` until every page is fetched.
+    /// `on_page` sees each page as it arrives, so a caller can render a large
+    /// folder progressively; the full listing is still returned at the end.
+    /// Returning `ControlFlow::Break` from `on_page` stops before the next page
+    /// is requested and yields an error (nothing is written to the cache).
+    pub fn ls_streaming(
+        &self,
+        parent_id: &str,
+        mut on_page: impl FnMut(&[Entry]) -> ControlFlow<()>,
+    ) -> Result<Vec<Entry>> {
         let url = self.drive_url("drive/v1/files");
 
         let filters = r#"{"trashed":{"eq":false}}"#;
@@ -13,22 +33,26 @@ impl PikPak {
         let mut page_token: Option<String> = None;
 
         loop {
-            let mut rb = self.http.get(&url).bearer_auth(&token).query(&[
-                ("parent_id", parent_id),
-                ("limit", "500"),
-                ("filters", filters),
-                ("thumbnail_size", self.thumbnail_size.as_str()),
-            ]);
-            if let Some(ref pt) = page_token {
-                rb = rb.query(&[("page_token", pt.as_str())]);
-            }
-            rb = self.authed_headers(rb);
-
-            let response = rb.send().context("ls request failed")?;
+            let response = self.send_authed("ls", || {
+                let mut rb = self.http.get(&url).query(&[
+                    ("parent_id", parent_id),
+                    ("limit", "500"),
+                    ("filters", filters),
+                    ("thumbnail_size", self.thumbnail_size.as_str()),
+                ]);
+                if let Some(ref pt) = page_token {
+                    rb = rb.query(&[("page_token", pt.as_str())]);
+                }
+                rb
+            })?;
             let payload: DriveListResponse = json_or_api_error(response, "ls")?;
             let next = payload.next_page_token.filter(|t| !t.is_empty());
 
+            let start = all_entries.len();
             all_entries.extend(payload.files.into_iter().map(|f| f.into_entry()));
+            if on_page(&all_entries[start..]).is_break() {
+                return Err(anyhow!("ls cancelled"));
+            }
 
             match next {
                 Some(t) => page_token = Some(t),
@@ -36,6 +60,8 @@ impl PikPak {
             }
         }
 
+        self.store_listing(parent_id, &all_entries);
+
         Ok(all_entries)
     }
 
@@ -78,7 +104,7 @@ impl PikPak {
             let child = entries
                 .into_iter()
                 .find(|e| e.name == name && e.kind == crate::pikpak::EntryKind::Folder)
-                .ok_or_else(|| anyhow!("folder not found: {name}"))?;
+                .ok_or_else(|| super::not_found(format!("folder not found: {name}")))?;
             breadcrumb.push((current_id, name.to_string()));
             current_id = child.id;
         }
@@ -86,27 +112,65 @@ impl PikPak {
         Ok((current_id, breadcrumb))
     }
 
+    /// Resolve a file or folder id like `resolve_path_nav` resolves a path,
+    /// walking parent ids up to the root. A file resolves to its folder and
+    /// is returned as the third element, so the caller can select it.
+    pub fn resolve_id_nav(&self, id: &str) -> Result<NavTarget> {
+        let info = self.file_info(id)?;
+        let parent_id = info.parent_id.clone().unwrap_or_default();
+        let entry = info.into_entry();
+        let mut breadcrumb = Vec::new();
+        let (folder_id, file) = if entry.kind == crate::pikpak::EntryKind::Folder {
+            breadcrumb.push((parent_id.clone(), entry.name.clone()));
+            (entry.id, None)
+        } else {
+            (parent_id.clone(), Some(entry))
+        };
+
+        let mut next = parent_id;
+        while !next.is_empty() {
+            if breadcrumb.len() >= MAX_NAV_DEPTH {
+                return Err(anyhow!("folder nesting too deep under '{id}'"));
+            }
+            let info = self.file_info(&next)?;
+            let parent = info.parent_id.unwrap_or_default();
+            breadcrumb.push((parent.clone(), info.name));
+            next = parent;
+        }
+        breadcrumb.reverse();
+        Ok((folder_id, breadcrumb, file))
+    }
+
+    /// `:goto` target: a file id when `target` looks like one (falling back
+    /// to a path if no such id exists), otherwise a path from the root.
+    pub fn resolve_nav(&self, target: &str) -> Result<NavTarget> {
+        if looks_like_file_id(target)
+            && let Ok(found) = self.resolve_id_nav(target)
+        {
+            return Ok(found);
+        }
+        let (folder_id, breadcrumb) = self.resolve_path_nav(target)?;
+        Ok((folder_id, breadcrumb, None))
+    }
+
     pub fn ls_trash(&self, limit: u32) -> Result<Vec<Entry>> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/files");
 
         let filters = r#"{"trashed":{"eq":true}}"#;
-        let mut rb = self.http.get(&url).bearer_auth(&token).query(&[
-            ("parent_id", "*"),
-            ("limit", &limit.to_string()),
-            ("filters", filters),
-            ("thumbnail_size", self.thumbnail_size.as_str()),
-        ]);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("ls_trash request failed")?;
+        let response = self.send_authed("ls_trash", || {
+            self.http.get(&url).query(&[
+                ("parent_id", "*"),
+                ("limit", &limit.to_string()),
+                ("filters", filters),
+                ("thumbnail_size", self.thumbnail_size.as_str()),
+            ])
+        })?;
         let payload: DriveListResponse = json_or_api_error(response, "ls_trash")?;
         let entries = payload.files.into_iter().map(|f| f.into_entry()).collect();
         Ok(entries)
     }
 
     pub fn mv(&self, ids: &[&str], to_parent_id: &str) -> Result<()> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/files:batchMove");
 
         let payload = serde_json::json!({
@@ -114,17 +178,13 @@ impl PikPak {
             "to": { "parent_id": to_parent_id },
         });
 
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("move request failed")?;
+        let response = self.send_authed("move", || self.http.post(&url).json(&payload))?;
         ensure_success(response, "move")?;
         self.clear_ls_cache();
         Ok(())
     }
 
     pub fn cp(&self, ids: &[&str], to_parent_id: &str) -> Result<()> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/files:batchCopy");
 
         let payload = serde_json::json!({
@@ -132,73 +192,99 @@ impl PikPak {
             "to": { "parent_id": to_parent_id },
         });
 
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("copy request failed")?;
+        let response = self.send_authed("copy", || self.http.post(&url).json(&payload))?;
         ensure_success(response, "copy")?;
         self.clear_ls_cache();
         Ok(())
     }
 
     pub fn rename(&self, file_id: &str, new_name: &str) -> Result<()> {
-        let token = self.access_token()?;
         let url = format!("{}/{}", self.drive_url("drive/v1/files"), file_id);
 
         let payload = serde_json::json!({ "name": new_name });
-        let mut rb = self.http.patch(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("rename request failed")?;
+        let response = self.send_authed("rename", || self.http.patch(&url).json(&payload))?;
         ensure_success(response, "rename")?;
         self.clear_ls_cache();
         Ok(())
     }
 
     pub fn remove(&self, ids: &[&str]) -> Result<()> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/files:batchTrash");
 
         let payload = serde_json::json!({ "ids": ids });
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("remove request failed")?;
+        let response = self.send_authed("remove", || self.http.post(&url).json(&payload))?;
         ensure_success(response, "remove")?;
         self.clear_ls_cache();
         Ok(())
     }
 
     pub fn delete_permanent(&self, ids: &[&str]) -> Result<()> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/files:batchDelete");
 
         let payload = serde_json::json!({ "ids": ids });
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("permanent delete request failed")?;
+        let response =
+            self.send_authed("permanent delete", || self.http.post(&url).json(&payload))?;
         ensure_success(response, "permanent delete")?;
         self.clear_ls_cache();
         Ok(())
     }
 
+    /// Permanently delete everything in the trash. `ls_trash` is
+    /// single-page, so this drains it: delete a page, re-list, repeat. If a
+    /// stale listing keeps returning the same ids it stops there, which
+    /// [`EmptiedTrash::stalled`] reports.
+    pub fn empty_trash(&self) -> Result<EmptiedTrash> {
+        let mut deleted = 0usize;
+        let mut batch = self.ls_trash(500)?;
+        while !batch.is_empty() {
+            let ids: Vec<&str> = batch.iter().map(|e| e.id.as_str()).collect();
+            self.delete_permanent(&ids)?;
+            deleted += ids.len();
+
+            let next = self.ls_trash(500)?;
+            if !next.is_empty() && next.iter().map(|e| &e.id).eq(batch.iter().map(|e| &e.id)) {
+                return Ok(EmptiedTrash {
+                    deleted,
+                    stalled: true,
+                });
+            }
+            batch = next;
+        }
+        Ok(EmptiedTrash {
+            deleted,
+            stalled: false,
+        })
+    }
+
     pub fn untrash(&self, ids: &[&str]) -> Result<()> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/files:batchUntrash");
 
         let payload = serde_json::json!({ "ids": ids });
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("untrash request failed")?;
+        let response = self.send_authed("untrash", || self.http.post(&url).json(&payload))?;
         ensure_success(response, "untrash")?;
         self.clear_ls_cache();
         Ok(())
     }
 
+    /// Restore trashed items into `parent_id` rather than where they were
+    /// deleted from. They're moved first, so it works even when their
+    /// original folder is gone; items the server won't move while trashed
+    /// are restored in place and then moved. A move that failed for a
+    /// known reason (auth, network, quota, missing folder) is returned as is.
+    pub fn untrash_to(&self, ids: &[&str], parent_id: &str) -> Result<()> {
+        match self.mv(ids, parent_id) {
+            Ok(()) => self.untrash(ids),
+            Err(e) if failure_kind(&e).is_some() => Err(e),
+            Err(first) => {
+                self.untrash(ids)
+                    .with_context(|| format!("move out of the trash failed: {first:#}"))?;
+                self.mv(ids, parent_id)
+                    .context("restored in place, but the move to the folder failed")
+            }
+        }
+    }
+
     pub fn mkdir(&self, parent_id: &str, name: &str) -> Result<Entry> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/files");
 
         let payload = serde_json::json!({
@@ -207,68 +293,51 @@ impl PikPak {
             "name": name,
         });
 
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("mkdir request failed")?;
+        let response = self.send_authed("mkdir", || self.http.post(&url).json(&payload))?;
         let resp: DriveFileResponse = json_or_api_error(response, "mkdir")?;
         self.clear_ls_cache();
         Ok(resp.file.into_folder_entry())
     }
 
     pub fn file_info(&self, file_id: &str) -> Result<FileInfoResponse> {
-        let token = self.access_token()?;
         let url = format!("{}/{}", self.drive_url("drive/v1/files"), file_id);
 
-        let mut rb = self.http.get(&url).bearer_auth(&token);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("file_info request failed")?;
+        let response = self.send_authed("file_info", || self.http.get(&url))?;
         json_or_api_error(response, "file_info")
     }
 
     pub fn star(&self, ids: &[&str]) -> Result<()> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/files:star");
 
         let payload = serde_json::json!({ "ids": ids });
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("star request failed")?;
+        let response = self.send_authed("star", || self.http.post(&url).json(&payload))?;
         ensure_success(response, "star")?;
         self.clear_ls_cache();
         Ok(())
     }
 
     pub fn unstar(&self, ids: &[&str]) -> Result<()> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/files:unstar");
 
         let payload = serde_json::json!({ "ids": ids });
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("unstar request failed")?;
+        let response = self.send_authed("unstar", || self.http.post(&url).json(&payload))?;
         ensure_success(response, "unstar")?;
         self.clear_ls_cache();
         Ok(())
     }
 
     pub fn starred_list(&self, limit: u32) -> Result<Vec<Entry>> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/files");
 
         let filters = r#"{"trashed":{"eq":false},"system_tag":{"in":"STAR"}}"#;
-        let mut rb = self.http.get(&url).bearer_auth(&token).query(&[
-            ("parent_id", "*"),
-            ("limit", &limit.to_string()),
-            ("filters", filters),
-            ("thumbnail_size", self.thumbnail_size.as_str()),
-        ]);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("starred list request failed")?;
+        let response = self.send_authed("starred list", || {
+            self.http.get(&url).query(&[
+                ("parent_id", "*"),
+                ("limit", &limit.to_string()),
+                ("filters", filters),
+                ("thumbnail_size", self.thumbnail_size.as_str()),
+            ])
+        })?;
         let payload: DriveListResponse = json_or_api_error(response, "starred list")?;
         let entries = payload
             .files
@@ -301,7 +370,9 @@ impl PikPak {
             let found = entries
                 .into_iter()
                 .find(|e| e.name == *seg)
-                .ok_or_else(|| anyhow!("not found: '{}' in path '{}'", seg, path))?;
+                .ok_or_else(|| {
+                    super::not_found(format!("not found: '{}' in path '{}'", seg, path))
+                })?;
             current_id = found.id;
         }
 
@@ -309,10 +380,64 @@ impl PikPak {
     }
 }
 
+/// Whether an untrash failed because the item's original folder no longer
+/// exists (deleted, or itself in the trash).
+pub fn is_parent_missing(err: &anyhow::Error) -> bool {
+    let msg = format!("{err:#}").to_lowercase();
+    msg.contains("parent")
+        && [
+            "not_found",
+            "not found",
+            "not exist",
+            "notfound",
+            "trashed",
+            "deleted",
+        ]
+        .iter()
+        .any(|m| msg.contains(m))
+}
+
+/// Where `:goto` lands: folder id, breadcrumb, and the file to select when
+/// the target was a file.
+pub type NavTarget = (String, Vec<(String, String)>, Option<Entry>);
+
+/// Guards `resolve_id_nav` against a parent chain that never reaches the root.
+const MAX_NAV_DEPTH: usize = 256;
+
+/// PikPak ids are 26 letters and digits (`VNayNjZqS7BZkv2vlpoPl3vCo1`).
+fn looks_like_file_id(s: &str) -> bool {
+    (20..=40).contains(&s.len())
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+        && s.bytes().any(|b| b.is_ascii_digit())
+}
+
 /// Split a cloud path into its non-empty `/`-separated components.
-fn path_components(path: &str) -> Vec<&str> {
+pub(super) fn path_components(path: &str) -> Vec<&str> {
     path.trim_matches('/')
         .split('/')
         .filter(|s| !s.is_empty())
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_ids_are_told_from_paths() {
+        assert!(looks_like_file_id("VNayNjZqS7BZkv2vlpoPl3vCo1"));
+        assert!(!looks_like_file_id("/My Pack/VNayNjZqS7BZkv2vlpoPl3vCo1"));
+        assert!(!looks_like_file_id("Movies"));
+        assert!(!looks_like_file_id("AVeryLongFolderNameWithoutDigits"));
+    }
+
+    #[test]
+    fn parent_missing_untrash_errors_are_recognised() {
+        let missing =
+            anyhow!(r#"untrash failed (400 Bad Request): {{"error":"file_parent_not_found"}}"#);
+        assert!(is_parent_missing(&missing));
+        let other = anyhow!("untrash failed (500 Internal Server Error): oops");
+        assert!(!is_parent_missing(&other));
+    }
+}
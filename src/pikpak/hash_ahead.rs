@@ -0,0 +1,209 @@
+//! Hashing the files of a batch upload ahead of the upload. Every upload
+//! starts by sending the file's PikPak hash, which for a large file takes
+//! longer to compute than a file PikPak already has takes to "upload"; a few
+//! threads hash the files still waiting while the current one is sent.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex, mpsc};
+use std::time::SystemTime;
+
+use super::pikpak_hash;
+
+/// Files hashed at the same time.
+const HASH_WORKERS: usize = 3;
+
+/// How far past the file being uploaded the hashing may run, so a long
+/// batch isn't read through (and its hashes held) well before it's needed.
+const LOOK_AHEAD: usize = 4;
+
+/// A file's size and modification time when it was hashed.
+type Stamp = Option<(u64, SystemTime)>;
+
+fn stamp(path: &Path) -> Stamp {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.len(), meta.modified().ok()?))
+}
+
+/// `hash`, or the file hashed again if it changed since `then`.
+fn fresh(path: &Path, then: Stamp, hash: Result<String>) -> Result<String> {
+    if then.is_some() && stamp(path) == then {
+        hash
+    } else {
+        pikpak_hash(path)
+    }
+}
+
+/// Which files the workers have taken, and which one the uploader is on.
+#[derive(Default)]
+struct Claims {
+    claimed: usize,
+    consumed: usize,
+    stop: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    claims: Mutex<Claims>,
+    moved: Condvar,
+}
+
+impl Shared {
+    /// The next file to hash, once it's within `LOOK_AHEAD` of the
+    /// uploader; `None` when there's nothing left or it's stopped.
+    fn claim(&self, len: usize) -> Option<usize> {
+        let mut claims = self.claims.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if claims.stop || claims.claimed >= len {
+                return None;
+            }
+            if claims.claimed < claims.consumed + LOOK_AHEAD {
+                claims.claimed += 1;
+                return Some(claims.claimed - 1);
+            }
+            claims = self.moved.wait(claims).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut Claims)) {
+        f(&mut self.claims.lock().unwrap_or_else(|e| e.into_inner()));
+        self.moved.notify_all();
+    }
+}
+
+/// The hashes of a list of files, in the order given, computed in the
+/// background a few files ahead. A file that changed after it was hashed
+/// is hashed again when its turn comes. Dropping it stops the hashing of
+/// files not yet started.
+pub struct HashAhead {
+    rx: mpsc::Receiver<(usize, Stamp, Result<String>)>,
+    ready: HashMap<usize, (Stamp, Result<String>)>,
+    files: Arc<Vec<PathBuf>>,
+    next: usize,
+    shared: Arc<Shared>,
+}
+
+impl HashAhead {
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        let (tx, rx) = mpsc::channel();
+        let len = files.len();
+        let files = Arc::new(files);
+        let shared = Arc::new(Shared::default());
+        for _ in 0..HASH_WORKERS.min(len) {
+            let (tx, files, shared) = (tx.clone(), Arc::clone(&files), Arc::clone(&shared));
+            std::thread::spawn(move || {
+                while let Some(i) = shared.claim(len) {
+                    let path = &files[i];
+                    let then = stamp(path);
+                    if tx.send((i, then, pikpak_hash(path))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        Self {
+            rx,
+            ready: HashMap::new(),
+            files,
+            next: 0,
+            shared,
+        }
+    }
+}
+
+impl Iterator for HashAhead {
+    type Item = Result<String>;
+
+    /// The next file's hash, waiting for it if it isn't done yet.
+    fn next(&mut self) -> Option<Self::Item> {
+        let i = self.next;
+        let path = self.files.get(i)?.clone();
+        self.next += 1;
+        self.shared.update(|c| c.consumed = i);
+        loop {
+            if let Some((then, hash)) = self.ready.remove(&i) {
+                return Some(fresh(&path, then, hash));
+            }
+            match self.rx.recv() {
+                Ok((j, then, hash)) => {
+                    self.ready.insert(j, (then, hash));
+                }
+                Err(_) => return Some(Err(anyhow::anyhow!("hashing stopped early"))),
+            }
+        }
+    }
+}
+
+impl Drop for HashAhead {
+    fn drop(&mut self) {
+        self.shared.update(|c| c.stop = true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_come_back_in_order() {
+        let dir = std::env::temp_dir().join(format!("pikpaktui-hash-ahead-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let files: Vec<PathBuf> = (0..7)
+            .map(|i| {
+                let path = dir.join(format!("{i}.bin"));
+                std::fs::write(&path, vec![i as u8; 1000 * i]).unwrap();
+                path
+            })
+            .collect();
+        let expected: Vec<String> = files.iter().map(|p| pikpak_hash(p).unwrap()).collect();
+
+        let mut missing = files.clone();
+        missing.push(dir.join("gone.bin"));
+        let hashes: Vec<Result<String>> = HashAhead::new(missing).collect();
+        assert_eq!(hashes.len(), 8);
+        for (hash, want) in hashes.iter().zip(&expected) {
+            assert_eq!(hash.as_ref().unwrap(), want);
+        }
+        assert!(hashes[7].is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn hashing_stays_a_few_files_ahead() {
+        let shared = Arc::new(Shared::default());
+        for i in 0..LOOK_AHEAD {
+            assert_eq!(shared.claim(100), Some(i));
+        }
+        // The next one waits for the uploader to move on.
+        let waiter = {
+            let shared = Arc::clone(&shared);
+            std::thread::spawn(move || shared.claim(100))
+        };
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        assert!(!waiter.is_finished());
+        shared.update(|c| c.consumed = 1);
+        assert_eq!(waiter.join().unwrap(), Some(LOOK_AHEAD));
+        shared.update(|c| c.stop = true);
+        assert_eq!(shared.claim(100), None);
+    }
+
+    #[test]
+    fn a_file_changed_since_hashing_is_hashed_again() {
+        let path = std::env::temp_dir().join(format!(
+            "pikpaktui-hash-ahead-changed-{}",
+            std::process::id()
+        ));
+        std::fs::write(&path, b"before").unwrap();
+        let then = stamp(&path);
+        let old = pikpak_hash(&path).unwrap();
+        assert_eq!(fresh(&path, then, Ok(old.clone())).unwrap(), old);
+
+        std::fs::write(&path, b"after, longer").unwrap();
+        let new = fresh(&path, then, Ok(old.clone())).unwrap();
+        assert_ne!(new, old);
+        assert_eq!(new, pikpak_hash(&path).unwrap());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,344 @@
+//! A local index of every file on the drive with its size and hash, so
+//! duplicates, diffs against a local folder and lookups by hash don't need
+//! to walk the drive each time. It's built by a crawl that lists one folder
+//! per step; the caller paces the steps, and the crawl's queue is kept in
+//! the database so it carries on where it stopped. The last complete
+//! index stays in use while a new crawl runs.
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{EntryKind, PikPak, now_unix};
+use crate::store::{OptionalExtension, Store, params, set_meta};
+
+/// One file in the index.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexedFile {
+    pub id: String,
+    /// Full path on the drive, from `/`.
+    pub path: String,
+    pub size: u64,
+    /// PikPak's hash; empty when the listing had none.
+    #[serde(default)]
+    pub hash: String,
+}
+
+/// What one crawl step did.
+#[derive(Debug)]
+pub enum IndexStep {
+    /// Listed `folder`; `pending` folders are left.
+    Listed {
+        folder: String,
+        files: usize,
+        pending: usize,
+    },
+    /// The crawl is complete and the new index holds `files` files.
+    Finished { files: usize },
+}
+
+/// The index in the database, and any crawl under way.
+#[derive(Debug, Default)]
+pub struct IndexStatus {
+    pub files: usize,
+    pub bytes: u64,
+    /// When the complete index was written.
+    pub built: Option<SystemTime>,
+    /// Folders listed so far and still to go, while a crawl is unfinished.
+    pub crawl: Option<(usize, usize)>,
+}
+
+/// `meta` keys: when the index was last completed, and how many folders
+/// the crawl under way has listed (absent when none is).
+const BUILT_AT: &str = "hash_index_built_at";
+const FOLDERS_DONE: &str = "hash_index_folders_done";
+
+impl PikPak {
+    /// The database, with a `hash_index.jsonl` from an older version
+    /// brought in.
+    fn index_store(&self) -> Result<Store> {
+        let mut store = self.store()?;
+        let legacy = self.session_path.with_file_name("hash_index.jsonl");
+        let built = fs::metadata(&legacy)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64);
+        store.import_legacy(&legacy, |tx, raw| {
+            // Unreadable lines are skipped; a repeated id keeps the last.
+            for file in raw.lines().filter_map(|l| serde_json::from_str(l).ok()) {
+                insert_file(tx, "hash_index", &file)?;
+            }
+            set_meta(tx, BUILT_AT, built)?;
+            Ok(())
+        })?;
+        Ok(store)
+    }
+
+    /// The last complete index; empty if none has been built.
+    pub fn hash_index(&self) -> Result<Vec<IndexedFile>> {
+        let store = self.index_store()?;
+        query_files(
+            store.conn(),
+            "SELECT id, path, size, hash FROM hash_index",
+            [],
+        )
+    }
+
+    /// Indexed files with this hash (any case).
+    pub fn hash_index_find(&self, hash: &str) -> Result<Vec<IndexedFile>> {
+        let store = self.index_store()?;
+        query_files(
+            store.conn(),
+            "SELECT id, path, size, hash FROM hash_index WHERE hash = ?1 COLLATE NOCASE
+             ORDER BY path",
+            [hash],
+        )
+    }
+
+    pub fn hash_index_status(&self) -> Result<IndexStatus> {
+        let store = self.index_store()?;
+        let (files, bytes): (usize, u64) = store.conn().query_row(
+            "SELECT COUNT(*), COALESCE(SUM(size), 0) FROM hash_index",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )?;
+        let crawl = match store.meta(FOLDERS_DONE)? {
+            Some(done) => Some((done as usize, pending(store.conn())?)),
+            None => None,
+        };
+        Ok(IndexStatus {
+            files,
+            bytes,
+            built: store
+                .meta(BUILT_AT)?
+                .map(|t| UNIX_EPOCH + Duration::from_secs(t.max(0) as u64)),
+            crawl,
+        })
+    }
+
+    /// Whether a crawl should run: one is unfinished, or the index is
+    /// missing or older than `max_age`.
+    pub fn hash_index_due(&self, max_age: Duration) -> Result<bool> {
+        let store = self.index_store()?;
+        if store.meta(FOLDERS_DONE)?.is_some() {
+            return Ok(true);
+        }
+        Ok(store
+            .meta(BUILT_AT)?
+            .is_none_or(|t| now_unix().saturating_sub(t) >= max_age.as_secs() as i64))
+    }
+
+    /// Drop an unfinished crawl, so the next step starts from the top.
+    pub fn restart_hash_index(&self) -> Result<()> {
+        let mut store = self.index_store()?;
+        let tx = store.transaction()?;
+        clear_crawl(&tx)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// List the next folder of the crawl, starting one if none is under
+    /// way. A folder that fails to list stays queued for the next step.
+    pub fn hash_index_step(&self) -> Result<IndexStep> {
+        let mut store = self.index_store()?;
+        if store.meta(FOLDERS_DONE)?.is_none() {
+            let tx = store.transaction()?;
+            clear_crawl(&tx)?;
+            tx.execute(
+                "INSERT INTO hash_index_queue (folder_id, path) VALUES ('', '/')",
+                [],
+            )?;
+            set_meta(&tx, FOLDERS_DONE, 0)?;
+            tx.commit()?;
+        }
+
+        // Depth first: the newest queued folder.
+        let next: Option<(i64, String, String)> = store
+            .conn()
+            .query_row(
+                "SELECT seq, folder_id, path FROM hash_index_queue ORDER BY seq DESC LIMIT 1",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+            )
+            .optional()?;
+        let Some((seq, id, path)) = next else {
+            return finish_crawl(&mut store);
+        };
+        let entries = self.ls(&id).with_context(|| format!("indexing '{path}'"))?;
+
+        let tx = store.transaction()?;
+        tx.execute("DELETE FROM hash_index_queue WHERE seq = ?1", [seq])?;
+        let mut files = 0;
+        for entry in entries {
+            let child = if path == "/" {
+                format!("/{}", entry.name)
+            } else {
+                format!("{path}/{}", entry.name)
+            };
+            match entry.kind {
+                EntryKind::Folder => {
+                    tx.execute(
+                        "INSERT INTO hash_index_queue (folder_id, path) VALUES (?1, ?2)",
+                        params![entry.id, child],
+                    )?;
+                }
+                EntryKind::File => {
+                    let file = IndexedFile {
+                        id: entry.id,
+                        path: child,
+                        size: entry.size,
+                        hash: entry.hash,
+                    };
+                    insert_file(&tx, "hash_index_next", &file)?;
+                    files += 1;
+                }
+            }
+        }
+        tx.execute(
+            "UPDATE meta SET value = value + 1 WHERE key = ?1",
+            [FOLDERS_DONE],
+        )?;
+        let pending = pending(&tx)?;
+        tx.commit()?;
+
+        if pending == 0 {
+            return finish_crawl(&mut store);
+        }
+        Ok(IndexStep::Listed {
+            folder: path,
+            files,
+            pending,
+        })
+    }
+}
+
+/// Swap the crawl's files in as the index.
+fn finish_crawl(store: &mut Store) -> Result<IndexStep> {
+    let tx = store.transaction()?;
+    tx.execute_batch(
+        "DELETE FROM hash_index;
+         INSERT INTO hash_index SELECT * FROM hash_index_next;",
+    )?;
+    let files: usize = tx.query_row("SELECT COUNT(*) FROM hash_index", [], |r| r.get(0))?;
+    clear_crawl(&tx)?;
+    set_meta(&tx, BUILT_AT, now_unix())?;
+    tx.commit()?;
+    Ok(IndexStep::Finished { files })
+}
+
+fn clear_crawl(conn: &Connection) -> Result<()> {
+    conn.execute_batch(
+        "DELETE FROM hash_index_next;
+         DELETE FROM hash_index_queue;",
+    )?;
+    conn.execute("DELETE FROM meta WHERE key = ?1", [FOLDERS_DONE])?;
+    Ok(())
+}
+
+fn pending(conn: &Connection) -> Result<usize> {
+    Ok(conn.query_row("SELECT COUNT(*) FROM hash_index_queue", [], |r| r.get(0))?)
+}
+
+/// Add (or replace) `file` in `table`, `hash_index` or `hash_index_next`.
+fn insert_file(conn: &Connection, table: &str, file: &IndexedFile) -> Result<()> {
+    conn.execute(
+        &format!("INSERT OR REPLACE INTO {table} (id, path, size, hash) VALUES (?1, ?2, ?3, ?4)"),
+        params![file.id, file.path, file.size, file.hash],
+    )?;
+    Ok(())
+}
+
+fn query_files(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<IndexedFile>> {
+    let mut stmt = conn.prepare(sql)?;
+    let files = stmt
+        .query_map(params, |r| {
+            Ok(IndexedFile {
+                id: r.get(0)?,
+                path: r.get(1)?,
+                size: r.get(2)?,
+                hash: r.get(3)?,
+            })
+        })?
+        .collect::<rusqlite::Result<_>>()?;
+    Ok(files)
+}
+
+/// Files sharing a hash, two or more to a group, the most space taken by
+/// the extra copies first.
+pub fn duplicates(files: &[IndexedFile]) -> Vec<Vec<&IndexedFile>> {
+    let mut by_hash: HashMap<&str, Vec<&IndexedFile>> = HashMap::new();
+    for file in files.iter().filter(|f| !f.hash.is_empty()) {
+        by_hash.entry(file.hash.as_str()).or_default().push(file);
+    }
+    let mut groups: Vec<Vec<&IndexedFile>> = by_hash
+        .into_values()
+        .filter(|g| g.len() > 1)
+        .map(|mut g| {
+            g.sort_by(|a, b| a.path.cmp(&b.path));
+            g
+        })
+        .collect();
+    let wasted = |g: &Vec<&IndexedFile>| g[0].size * (g.len() as u64 - 1);
+    groups.sort_by(|a, b| wasted(b).cmp(&wasted(a)).then(a[0].path.cmp(&b[0].path)));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(id: &str, path: &str, size: u64, hash: &str) -> IndexedFile {
+        IndexedFile {
+            id: id.into(),
+            path: path.into(),
+            size,
+            hash: hash.into(),
+        }
+    }
+
+    #[test]
+    fn duplicates_group_by_hash_biggest_waste_first() {
+        let files = [
+            file("1", "/a/x.mkv", 100, "H1"),
+            file("2", "/b/x.mkv", 100, "H1"),
+            file("3", "/c.iso", 900, "H2"),
+            file("4", "/d.iso", 900, "H2"),
+            file("5", "/e.txt", 5, "H3"),
+            file("6", "/f.txt", 5, ""),
+            file("7", "/g.txt", 5, ""),
+        ];
+        let groups = duplicates(&files);
+        let paths: Vec<Vec<&str>> = groups
+            .iter()
+            .map(|g| g.iter().map(|f| f.path.as_str()).collect())
+            .collect();
+        assert_eq!(paths, [["/c.iso", "/d.iso"], ["/a/x.mkv", "/b/x.mkv"]]);
+    }
+
+    #[test]
+    fn a_repeated_id_keeps_the_last() {
+        let dir = std::env::temp_dir().join(format!("pikpaktui-index-{}", std::process::id()));
+        let store = Store::open(&dir).unwrap();
+        let conn = store.conn();
+        for f in [
+            file("1", "/old.txt", 1, "AB"),
+            file("2", "/b.txt", 2, "CD"),
+            file("1", "/new.txt", 1, "AB"),
+        ] {
+            insert_file(conn, "hash_index", &f).unwrap();
+        }
+        let sql = "SELECT id, path, size, hash FROM hash_index WHERE hash = ?1 COLLATE NOCASE";
+        let found = query_files(conn, sql, ["ab"]).unwrap();
+        drop(store);
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(found, [file("1", "/new.txt", 1, "AB")]);
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::{Result, anyhow};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use super::{now_unix, pikpak_hash};
+use crate::store::{Store, params};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    #[default]
+    Download,
+    Upload,
+}
+
+/// A completed upload or download. The history is only ever added to, so
+/// it outlives tasks cleared from the TUI's download list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferRecord {
+    #[serde(default)]
+    pub direction: Direction,
+    pub file_id: String,
+    pub name: String,
+    pub size: u64,
+    #[serde(alias = "dest_path")]
+    pub local_path: String,
+    pub finished_at: u64,
+    /// PikPak hash of the local file; empty in records written before
+    /// hashes were kept.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hash: String,
+    /// Whether `hash` matched the server's. `None` for uploads and when the
+    /// server had no hash to compare with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    /// Seconds spent transferring; 0 for an instant (deduplicated) upload.
+    #[serde(default)]
+    pub duration_secs: f64,
+    /// Bytes per second over `duration_secs`. A resumed download only
+    /// counts what was fetched this time.
+    #[serde(default)]
+    pub avg_speed: u64,
+}
+
+impl TransferRecord {
+    pub fn new(
+        direction: Direction,
+        file_id: &str,
+        name: &str,
+        size: u64,
+        local_path: &Path,
+        transferred: u64,
+        elapsed: Duration,
+    ) -> Self {
+        let secs = elapsed.as_secs_f64();
+        Self {
+            direction,
+            file_id: file_id.to_string(),
+            name: name.to_string(),
+            size,
+            local_path: local_path.to_string_lossy().into_owned(),
+            finished_at: now_unix().max(0) as u64,
+            hash: String::new(),
+            verified: None,
+            duration_secs: secs,
+            avg_speed: if secs > 0.0 {
+                (transferred as f64 / secs) as u64
+            } else {
+                0
+            },
+        }
+    }
+}
+
+/// Where older versions wrote the history as JSON lines.
+fn legacy_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| crate::config::profile_dir(d.join("pikpaktui")))
+}
+
+/// The database, with any history an older version kept in JSON lines
+/// (`transfer_history.jsonl`, and the download-only
+/// `download_history.jsonl` before it) brought in.
+fn open() -> Result<Store> {
+    let mut store = Store::open_default()?;
+    if let Some(dir) = legacy_dir() {
+        for name in ["download_history.jsonl", "transfer_history.jsonl"] {
+            store.import_legacy(&dir.join(name), |tx, raw| {
+                // Unreadable lines are skipped.
+                for record in raw.lines().filter_map(|l| serde_json::from_str(l).ok()) {
+                    insert(tx, &record)?;
+                }
+                Ok(())
+            })?;
+        }
+    }
+    Ok(store)
+}
+
+fn insert(conn: &Connection, record: &TransferRecord) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT INTO transfers (direction, file_id, name, size, local_path, finished_at,
+         hash, verified, duration_secs, avg_speed)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            match record.direction {
+                Direction::Download => "download",
+                Direction::Upload => "upload",
+            },
+            record.file_id,
+            record.name,
+            record.size,
+            record.local_path,
+            record.finished_at,
+            record.hash,
+            record.verified,
+            record.duration_secs,
+            record.avg_speed
+        ],
+    )
+}
+
+/// Best-effort: the transfer itself already finished, so failing to note it
+/// down must not turn it into an error.
+pub(super) fn append(record: &TransferRecord) {
+    if let Ok(store) = open() {
+        let _ = insert(store.conn(), record);
+    }
+}
+
+/// Every recorded transfer of the active profile, oldest first.
+pub fn load_history() -> Vec<TransferRecord> {
+    let Ok(store) = open() else {
+        return Vec::new();
+    };
+    let Ok(mut stmt) = store.conn().prepare(
+        "SELECT direction, file_id, name, size, local_path, finished_at, hash, verified,
+         duration_secs, avg_speed FROM transfers ORDER BY finished_at, seq",
+    ) else {
+        return Vec::new();
+    };
+    stmt.query_map([], |r| {
+        Ok(TransferRecord {
+            direction: if r.get::<_, String>(0)? == "upload" {
+                Direction::Upload
+            } else {
+                Direction::Download
+            },
+            file_id: r.get(1)?,
+            name: r.get(2)?,
+            size: r.get(3)?,
+            local_path: r.get(4)?,
+            finished_at: r.get(5)?,
+            hash: r.get(6)?,
+            verified: r.get(7)?,
+            duration_secs: r.get(8)?,
+            avg_speed: r.get(9)?,
+        })
+    })
+    .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    .unwrap_or_default()
+}
+
+/// Hash a finished download, compare it with PikPak's `expected` hash and
+/// add it to the history. On a mismatch the file is removed, so a retry
+/// fetches it afresh, and an error says so.
+pub fn verify_download(mut record: TransferRecord, dest: &Path, expected: &str) -> Result<()> {
+    let hash = pikpak_hash(dest).unwrap_or_default();
+    record.verified =
+        (!expected.is_empty() && !hash.is_empty()).then(|| hash.eq_ignore_ascii_case(expected));
+    record.hash = hash;
+    append(&record);
+    if record.verified == Some(false) {
+        let _ = fs::remove_file(dest);
+        return Err(anyhow!(
+            "'{}' doesn't match PikPak's hash; removed the corrupt copy",
+            record.name
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn legacy_download_lines_still_parse() {
+        let old =
+            r#"{"file_id":"f1","name":"a.mkv","size":10,"dest_path":"/tmp/a.mkv","finished_at":5}"#;
+        let record: TransferRecord = serde_json::from_str(old).unwrap();
+        assert_eq!(record.direction, Direction::Download);
+        assert_eq!(record.local_path, "/tmp/a.mkv");
+        assert_eq!(record.verified, None);
+        assert_eq!(record.avg_speed, 0);
+
+        let new = TransferRecord::new(
+            Direction::Upload,
+            "f2",
+            "b.iso",
+            4096,
+            Path::new("/tmp/b.iso"),
+            4096,
+            Duration::from_secs(2),
+        );
+        assert_eq!(new.avg_speed, 2048);
+        let line = serde_json::to_string(&new).unwrap();
+        assert!(line.contains(r#""direction":"upload""#));
+        assert!(!line.contains("verified"));
+    }
+}
@@ -0,0 +1,55 @@
+use crate::store::{OptionalExtension, params};
+
+use super::{Entry, PikPak, now_unix};
+
+impl PikPak {
+    /// The last listing of `folder_id` fetched within `listing_cache_ttl`
+    /// seconds, if any. Callers should still refresh in the background: the
+    /// cache only makes the first paint instant.
+    pub fn cached_listing(&self, folder_id: &str) -> Option<Vec<Entry>> {
+        if self.listing_cache_ttl == 0 {
+            return None;
+        }
+        let (fetched_at, entries) = self.stored_listing(folder_id)?;
+        let age = now_unix().saturating_sub(fetched_at);
+        if age < 0 || age as u64 >= self.listing_cache_ttl {
+            return None;
+        }
+        Some(entries)
+    }
+
+    /// The last listing of `folder_id` ever fetched, however old: while
+    /// PikPak can't be reached it's the best there is.
+    pub fn last_listing(&self, folder_id: &str) -> Option<Vec<Entry>> {
+        self.stored_listing(folder_id).map(|(_, entries)| entries)
+    }
+
+    fn stored_listing(&self, folder_id: &str) -> Option<(i64, Vec<Entry>)> {
+        let store = self.store().ok()?;
+        let (fetched_at, raw): (i64, String) = store
+            .conn()
+            .query_row(
+                "SELECT fetched_at, entries FROM listings WHERE folder_id = ?1",
+                [folder_id],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .optional()
+            .ok()??;
+        Some((fetched_at, serde_json::from_str(&raw).ok()?))
+    }
+
+    /// Best-effort write of a fresh listing; a failed write only costs the
+    /// next instant render.
+    pub(super) fn store_listing(&self, folder_id: &str, entries: &[Entry]) {
+        if self.listing_cache_ttl == 0 {
+            return;
+        }
+        let (Ok(store), Ok(raw)) = (self.store(), serde_json::to_string(entries)) else {
+            return;
+        };
+        let _ = store.conn().execute(
+            "INSERT OR REPLACE INTO listings (folder_id, fetched_at, entries) VALUES (?1, ?2, ?3)",
+            params![folder_id, now_unix(), raw],
+        );
+    }
+}
@@ -0,0 +1,252 @@
+//! A directory on disk as a drive, for tests that need one without a
+//! server and for trying the CLI's file commands without an account
+//! (`PIKPAK_DEMO_DIR`).
+
+use anyhow::{Context, Result, anyhow, bail};
+use std::fs;
+use std::ops::ControlFlow;
+use std::path::{Component, Path, PathBuf};
+
+use super::{Backend, Entry, EntryKind, not_found, unix_to_iso};
+
+/// A directory on disk served as a drive. An item's id is its path below
+/// the root, `/`-separated, so ids change on rename and move, unlike
+/// PikPak's. There's no trash: `remove` deletes.
+pub struct LocalBackend {
+    root: PathBuf,
+}
+
+impl LocalBackend {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Where `id` is on disk; ids can't climb out of the root.
+    fn path(&self, id: &str) -> Result<PathBuf> {
+        let rel = Path::new(id);
+        if !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+            bail!("invalid id '{id}'");
+        }
+        Ok(self.root.join(rel))
+    }
+
+    fn id_in(parent_id: &str, name: &str) -> String {
+        if parent_id.is_empty() {
+            name.to_string()
+        } else {
+            format!("{parent_id}/{name}")
+        }
+    }
+
+    fn entry(&self, id: String, meta: &fs::Metadata) -> Entry {
+        let time = |t: std::io::Result<std::time::SystemTime>| {
+            t.ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or_else(String::new, |d| unix_to_iso(d.as_secs() as i64))
+        };
+        let name = id.rsplit('/').next().unwrap_or_default().to_string();
+        let kind = if meta.is_dir() {
+            EntryKind::Folder
+        } else {
+            EntryKind::File
+        };
+        Entry {
+            size: if meta.is_dir() { 0 } else { meta.len() },
+            created_time: time(meta.created().or_else(|_| meta.modified())),
+            modified_time: time(meta.modified()),
+            ..Entry::new(id, name, kind)
+        }
+    }
+
+    /// The path `id` would move to in `to_parent_id`, which must be free.
+    fn target(&self, id: &str, to_parent_id: &str) -> Result<(PathBuf, PathBuf)> {
+        let from = self.path(id)?;
+        let name = from
+            .file_name()
+            .ok_or_else(|| anyhow!("the root can't be moved or copied"))?;
+        let to = self.path(to_parent_id)?.join(name);
+        if !from.exists() {
+            return Err(not_found(format!("'{id}' not found")));
+        }
+        if to.exists() {
+            bail!("'{}' already exists", to.display());
+        }
+        Ok((from, to))
+    }
+}
+
+fn copy_tree(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return fs::copy(from, to).map(drop);
+    }
+    fs::create_dir(to)?;
+    for child in fs::read_dir(from)? {
+        let child = child?;
+        copy_tree(&child.path(), &to.join(child.file_name()))?;
+    }
+    Ok(())
+}
+
+impl Backend for LocalBackend {
+    fn ls_streaming(
+        &self,
+        parent_id: &str,
+        on_page: &mut dyn FnMut(&[Entry]) -> ControlFlow<()>,
+    ) -> Result<Vec<Entry>> {
+        let dir = self.path(parent_id)?;
+        let read = fs::read_dir(&dir).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => not_found(format!("folder '{parent_id}' not found")),
+            _ => anyhow::Error::new(e).context(format!("failed to list {}", dir.display())),
+        })?;
+        let mut entries = Vec::new();
+        for child in read {
+            let child = child?;
+            let name = child.file_name().to_string_lossy().into_owned();
+            entries.push(self.entry(Self::id_in(parent_id, &name), &child.metadata()?));
+        }
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        if on_page(&entries).is_break() {
+            bail!("ls cancelled");
+        }
+        Ok(entries)
+    }
+
+    fn mv(&self, ids: &[&str], to_parent_id: &str) -> Result<()> {
+        for id in ids {
+            let (from, to) = self.target(id, to_parent_id)?;
+            fs::rename(&from, &to).with_context(|| format!("failed to move '{id}'"))?;
+        }
+        Ok(())
+    }
+
+    fn cp(&self, ids: &[&str], to_parent_id: &str) -> Result<()> {
+        for id in ids {
+            let (from, to) = self.target(id, to_parent_id)?;
+            copy_tree(&from, &to).with_context(|| format!("failed to copy '{id}'"))?;
+        }
+        Ok(())
+    }
+
+    fn rename(&self, id: &str, new_name: &str) -> Result<()> {
+        if new_name.is_empty() || new_name.contains(['/', '\\']) {
+            bail!("invalid name '{new_name}'");
+        }
+        let from = self.path(id)?;
+        let to = from.with_file_name(new_name);
+        if !from.exists() {
+            return Err(not_found(format!("'{id}' not found")));
+        }
+        if to.exists() {
+            bail!("'{new_name}' already exists");
+        }
+        fs::rename(&from, &to).with_context(|| format!("failed to rename '{id}'"))
+    }
+
+    fn remove(&self, ids: &[&str]) -> Result<()> {
+        for id in ids {
+            if id.is_empty() {
+                bail!("the root can't be removed");
+            }
+            let path = self.path(id)?;
+            let removed = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            removed.map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => not_found(format!("'{id}' not found")),
+                _ => anyhow::Error::new(e).context(format!("failed to remove '{id}'")),
+            })?;
+        }
+        Ok(())
+    }
+
+    fn untrash(&self, _ids: &[&str]) -> Result<()> {
+        bail!("a local folder has no trash to restore from")
+    }
+
+    fn parent_id(&self, id: &str) -> Result<String> {
+        if !self.path(id)?.exists() {
+            return Err(not_found(format!("'{id}' not found")));
+        }
+        Ok(id
+            .rsplit_once('/')
+            .map_or("", |(parent, _)| parent)
+            .to_string())
+    }
+
+    fn mkdir(&self, parent_id: &str, name: &str) -> Result<Entry> {
+        let id = Self::id_in(parent_id, name);
+        let path = self.path(&id)?;
+        fs::create_dir(&path).with_context(|| format!("failed to create {}", path.display()))?;
+        Ok(self.entry(id, &fs::metadata(&path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pikpaktui-backend-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn names(backend: &dyn Backend, parent_id: &str) -> Vec<String> {
+        backend
+            .ls(parent_id)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect()
+    }
+
+    #[test]
+    fn local_backend_rearranges_a_tree() {
+        let root = temp_root("ops");
+        let drive = LocalBackend::new(&root);
+        let movies = drive.mkdir("", "Movies").unwrap();
+        assert_eq!(movies.kind, EntryKind::Folder);
+        drive.mkdir("", "Inbox").unwrap();
+        fs::write(root.join("Inbox/a.mkv"), "abc").unwrap();
+
+        let listed = drive.ls("Inbox").unwrap();
+        assert_eq!(listed[0].id, "Inbox/a.mkv");
+        assert_eq!(listed[0].size, 3);
+        assert_eq!(drive.resolve_path("/Inbox/a.mkv").unwrap(), "Inbox/a.mkv");
+        assert_eq!(drive.parent_id("Inbox/a.mkv").unwrap(), "Inbox");
+        assert_eq!(drive.parent_id("Inbox").unwrap(), "");
+
+        drive.cp(&["Inbox/a.mkv"], "Movies").unwrap();
+        drive.rename("Movies/a.mkv", "b.mkv").unwrap();
+        drive.mv(&["Movies/b.mkv"], "").unwrap();
+        assert_eq!(names(&drive, ""), ["Inbox", "Movies", "b.mkv"]);
+        assert!(drive.mv(&["b.mkv"], "").is_err());
+
+        drive.remove(&["Inbox"]).unwrap();
+        assert_eq!(names(&drive, ""), ["Movies", "b.mkv"]);
+        let missing = drive.ls("Inbox").unwrap_err();
+        assert_eq!(
+            crate::pikpak::failure_kind(&missing),
+            Some(crate::pikpak::FailureKind::NotFound)
+        );
+        assert!(drive.ls("../etc").is_err());
+
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn mkdir_p_creates_only_whats_missing() {
+        let root = temp_root("mkdir-p");
+        let drive = LocalBackend::new(&root);
+        drive.mkdir("", "A").unwrap();
+        assert_eq!(drive.mkdir_p("/A/B/C").unwrap(), ("A/B/C".to_string(), 2));
+        assert_eq!(drive.mkdir_p("A/B/").unwrap(), ("A/B".to_string(), 0));
+        fs::write(root.join("A/f"), "").unwrap();
+        assert!(drive.mkdir_p("A/f/g").is_err());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
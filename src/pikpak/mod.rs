@@ -1,33 +1,71 @@
+mod abort;
 mod account;
 mod auth;
+mod backend;
+mod browser_login;
 mod download;
 mod drive;
+mod failure;
 mod file_info;
 mod files;
+mod hash_ahead;
+mod hash_index;
+mod history;
+mod listing_cache;
+mod local_backend;
 mod models;
+mod notes;
 mod offline;
+mod playback;
+mod provenance;
 mod responses;
+mod retry;
 mod share;
+mod stream_proxy;
 mod upload;
+mod upload_manifest;
+mod verification;
 
+pub use abort::abort_when;
 use auth::{CaptchaInitResponse, SigninResponse};
-pub use file_info::FileInfoResponse;
+pub use backend::Backend;
+pub use browser_login::BROWSER_LOGIN_TIMEOUT;
+pub use download::unique_name;
+pub use failure::{FailureKind, auth_error, failure_kind, not_found};
+pub use file_info::{FileInfoResponse, MediaVideo};
+pub use files::{NavTarget, is_parent_missing};
+pub use hash_ahead::HashAhead;
+pub use hash_index::{IndexStep, duplicates};
+pub use history::{Direction, TransferRecord, load_history, verify_download};
+pub use local_backend::LocalBackend;
 pub use models::{Entry, EntryKind, SessionToken};
+pub use notes::{Note, parse_labels};
+pub use playback::Playback;
 pub use responses::{
-    CreateShareResponse, EventsResponse, MyShare, OfflineListResponse, OfflineTask,
+    CreateShareResponse, EventEntry, EventsResponse, MyShare, OfflineListResponse, OfflineTask,
     OfflineTaskResponse, QuotaInfo, ShareInfoResponse, ShareListResponse, TransferBand,
-    TransferQuotaResponse, VipInfoResponse,
+    TransferQuotaBase, TransferQuotaResponse, VipInfoResponse,
 };
+pub use retry::{DEFAULT_API_RETRIES, DEFAULT_RETRY_BACKOFF_MS};
+pub use stream_proxy::StreamProxy;
+pub use upload::pikpak_hash;
+pub use verification::{LoginStep, PendingLogin};
 
 use anyhow::{Context, Result, anyhow};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::env;
 use std::fmt::Write as _;
 use std::fs;
+use std::net::{IpAddr, SocketAddr};
 use std::path::{Path, PathBuf};
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::ratelimit::{RateLimiter, RequestLimiter};
+use crate::store::Store;
+use retry::RetryPolicy;
+
 const DEFAULT_AUTH_BASE_URL: &str = "https://user.mypikpak.com";
 const DEFAULT_DRIVE_BASE_URL: &str = "https://api-drive.mypikpak.com";
 const DEFAULT_CLIENT_ID: &str = "YNxT9w7GMdWvEOKa";
@@ -36,6 +74,12 @@ const USER_AGENT: &str = "ANDROID-com.pikcloud.pikpak/1.21.0";
 
 pub struct PikPak {
     pub(crate) http: reqwest::blocking::Client,
+    /// Client for file bodies (downloads, upload parts), which may stall far
+    /// longer than an API call on a slow link.
+    pub(crate) transfer_http: reqwest::blocking::Client,
+    /// `http`'s settings on an async client, for API calls that may be
+    /// aborted (see `abort`).
+    abortable_http: reqwest::Client,
     drive_base_url: String,
     auth_base_url: String,
     client_id: String,
@@ -44,19 +88,115 @@ pub struct PikPak {
     device_id: String,
     captcha_token: String,
     pub thumbnail_size: String,
+    /// Record source path / mtime / hash of uploads in the local provenance
+    /// manifest.
+    pub record_provenance: bool,
+    /// Print only errors from `download_dir`, for the CLI's `--quiet`.
+    pub quiet: bool,
+    /// Seconds an on-disk folder listing stays usable for instant rendering;
+    /// 0 disables the disk cache.
+    pub listing_cache_ttl: u64,
+    /// Bandwidth caps shared by every transfer made through this client.
+    pub download_limiter: Arc<RateLimiter>,
+    pub upload_limiter: Arc<RateLimiter>,
+    /// Paces the API calls of every thread sharing this client, so fast
+    /// browsing with previews doesn't get throttled by PikPak.
+    pub api_limiter: Arc<RequestLimiter>,
     ls_cache: Mutex<HashMap<String, Vec<Entry>>>,
     refresh_lock: Mutex<()>,
+    /// API calls that failed to send or came back with an error status.
+    api_errors: AtomicU64,
+    retry: RetryPolicy,
+    /// API calls sent again after a transient failure, and a line on each
+    /// retry not yet shown.
+    api_retries: AtomicU64,
+    retry_notes: Mutex<VecDeque<String>>,
+}
+
+pub const DEFAULT_CONNECT_TIMEOUT: u64 = 10;
+pub const DEFAULT_REQUEST_TIMEOUT: u64 = 60;
+pub const DEFAULT_TRANSFER_TIMEOUT: u64 = 600;
+pub const DEFAULT_API_RATE_LIMIT: u64 = 10;
+pub const DEFAULT_API_BURST: u64 = 20;
+
+/// How the HTTP clients connect. Timeouts are in seconds, 0 for none.
+#[derive(Debug, Clone)]
+pub struct NetOptions {
+    pub connect_timeout: u64,
+    /// Each read or write of an API call.
+    pub request_timeout: u64,
+    /// Each read or write of a download or upload body.
+    pub transfer_timeout: u64,
+    /// Local address to bind, e.g. `0.0.0.0` to only connect over IPv4.
+    pub local_address: Option<IpAddr>,
+    /// Hosts pinned to these addresses instead of looking them up.
+    pub resolve: Vec<(String, Vec<SocketAddr>)>,
+    /// Times an API call that hit a 429, a 5xx or a dropped connection is
+    /// sent again, and the wait before the first retry in milliseconds.
+    pub api_retries: u32,
+    pub retry_backoff_ms: u64,
+    /// API requests a second across all threads, 0 for no limit, and how
+    /// many may go at once after a quiet spell.
+    pub api_rate_limit: u64,
+    pub api_burst: u64,
+}
+
+impl Default for NetOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            transfer_timeout: DEFAULT_TRANSFER_TIMEOUT,
+            local_address: None,
+            resolve: Vec::new(),
+            api_retries: DEFAULT_API_RETRIES,
+            retry_backoff_ms: DEFAULT_RETRY_BACKOFF_MS,
+            api_rate_limit: DEFAULT_API_RATE_LIMIT,
+            api_burst: DEFAULT_API_BURST,
+        }
+    }
+}
+
+fn build_http(net: &NetOptions, timeout: u64) -> Result<reqwest::blocking::Client> {
+    let secs = |n: u64| (n > 0).then(|| std::time::Duration::from_secs(n));
+    let mut builder = reqwest::blocking::Client::builder()
+        .user_agent(USER_AGENT)
+        .connect_timeout(secs(net.connect_timeout))
+        .timeout(secs(timeout))
+        .local_address(net.local_address);
+    for (host, addrs) in &net.resolve {
+        builder = builder.resolve_to_addrs(host, addrs);
+    }
+    builder.build().context("failed to build http client")
+}
+
+/// `build_http` for the async client that abortable API calls go out on.
+fn build_abortable_http(net: &NetOptions) -> Result<reqwest::Client> {
+    let secs = |n: u64| (n > 0).then(|| std::time::Duration::from_secs(n));
+    let mut builder = reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .local_address(net.local_address);
+    if let Some(connect) = secs(net.connect_timeout) {
+        builder = builder.connect_timeout(connect);
+    }
+    // Like the blocking client's timeout, bound each read rather than the
+    // whole call, so a long preview read isn't cut off part-way.
+    if let Some(timeout) = secs(net.request_timeout) {
+        builder = builder.read_timeout(timeout);
+    }
+    for (host, addrs) in &net.resolve {
+        builder = builder.resolve_to_addrs(host, addrs);
+    }
+    builder.build().context("failed to build http client")
 }
 
 impl PikPak {
     pub fn new() -> Result<Self> {
+        let net = NetOptions::default();
         Ok(Self {
-            http: reqwest::blocking::Client::builder()
-                .user_agent(USER_AGENT)
-                .connect_timeout(std::time::Duration::from_secs(10))
-                .timeout(std::time::Duration::from_secs(300))
-                .build()
-                .context("failed to build http client")?,
+            http: build_http(&net, net.request_timeout)?,
+            transfer_http: build_http(&net, net.transfer_timeout)?,
+            abortable_http: build_abortable_http(&net)?,
             drive_base_url: env::var("PIKPAK_DRIVE_BASE_URL")
                 .unwrap_or_else(|_| DEFAULT_DRIVE_BASE_URL.to_string()),
             auth_base_url: env::var("PIKPAK_AUTH_BASE_URL")
@@ -69,12 +209,89 @@ impl PikPak {
             device_id: String::new(),
             captcha_token: String::new(),
             thumbnail_size: "SIZE_MEDIUM".to_string(),
+            record_provenance: false,
+            quiet: false,
+            listing_cache_ttl: 0,
+            download_limiter: Arc::default(),
+            upload_limiter: Arc::default(),
+            api_limiter: Arc::default(),
             ls_cache: Mutex::new(HashMap::new()),
             refresh_lock: Mutex::new(()),
+            api_errors: AtomicU64::new(0),
+            retry: RetryPolicy::default(),
+            api_retries: AtomicU64::new(0),
+            retry_notes: Mutex::new(VecDeque::new()),
         })
     }
 
+    /// A client for `base_url` that never retries, for tests.
+    #[cfg(test)]
+    pub(crate) fn for_tests(base_url: String, session_path: PathBuf) -> PikPak {
+        PikPak {
+            http: reqwest::blocking::Client::builder().build().unwrap(),
+            transfer_http: reqwest::blocking::Client::builder().build().unwrap(),
+            abortable_http: reqwest::Client::new(),
+            drive_base_url: base_url,
+            auth_base_url: String::new(),
+            client_id: String::new(),
+            client_secret: String::new(),
+            session_path,
+            device_id: String::new(),
+            captcha_token: String::new(),
+            thumbnail_size: "SIZE_MEDIUM".to_string(),
+            record_provenance: false,
+            quiet: false,
+            listing_cache_ttl: 0,
+            download_limiter: Arc::default(),
+            upload_limiter: Arc::default(),
+            api_limiter: Arc::default(),
+            ls_cache: Mutex::new(HashMap::new()),
+            refresh_lock: Mutex::new(()),
+            api_errors: AtomicU64::new(0),
+            retry: RetryPolicy {
+                retries: 0,
+                ..RetryPolicy::default()
+            },
+            api_retries: AtomicU64::new(0),
+            retry_notes: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Use the session of `profile` (`None` = default) instead of the active
+    /// profile's.
+    pub fn use_profile(&mut self, profile: Option<&str>) -> Result<()> {
+        self.session_path = session_path_for(profile)?;
+        self.clear_ls_cache();
+        Ok(())
+    }
+
+    /// The active profile's database, the same one the history and the
+    /// download queue use. Tests keep theirs next to the session file.
+    pub(crate) fn store(&self) -> Result<Store> {
+        #[cfg(test)]
+        if let Some(dir) = self.session_path.parent() {
+            return Store::open(dir);
+        }
+        Store::open_default()
+    }
+
     pub fn load_session(&self) -> Result<Option<SessionToken>> {
+        if crate::secrets::use_keyring() {
+            if let Some(raw) = crate::secrets::get(&self.session_account())? {
+                let token = serde_json::from_str(&raw).context("failed to parse session json")?;
+                return Ok(Some(token));
+            }
+            // A session left in the file from before the switch moves over.
+            let Some(token) = self.load_session_file()? else {
+                return Ok(None);
+            };
+            self.save_session(&token)?;
+            return Ok(Some(token));
+        }
+        self.load_session_file()
+    }
+
+    fn load_session_file(&self) -> Result<Option<SessionToken>> {
         if !self.session_path.exists() {
             return Ok(None);
         }
@@ -86,6 +303,15 @@ impl PikPak {
     }
 
     fn save_session(&self, token: &SessionToken) -> Result<()> {
+        if crate::secrets::use_keyring() {
+            let raw = serde_json::to_string(token).context("failed to encode session")?;
+            crate::secrets::set(&self.session_account(), &raw)?;
+            if self.session_path.exists() {
+                fs::remove_file(&self.session_path)
+                    .with_context(|| format!("failed to remove {}", self.session_path.display()))?;
+            }
+            return Ok(());
+        }
         if let Some(parent) = self.session_path.parent() {
             fs::create_dir_all(parent)
                 .with_context(|| format!("failed to create dir {}", parent.display()))?;
@@ -100,6 +326,52 @@ impl PikPak {
         Ok(())
     }
 
+    /// The session's keyring entry, named after the file it stands in for
+    /// so each profile has its own.
+    fn session_account(&self) -> String {
+        format!("session:{}", self.session_path.display())
+    }
+
+    /// Adopt a session obtained elsewhere (e.g. from rclone). A stale access
+    /// token is refreshed right away so a dead refresh token fails here rather
+    /// than on the first real command.
+    pub fn import_session(&self, token: &SessionToken) -> Result<()> {
+        self.save_session(token)?;
+        if token.is_expired(now_unix()) {
+            self.refresh_session(token)?;
+        }
+        Ok(())
+    }
+
+    /// Sign in with just a refresh token, e.g. one kept in a CI secret:
+    /// it's exchanged for a fresh session right away.
+    pub fn login_with_refresh_token(&self, refresh_token: &str) -> Result<()> {
+        let refresh_token = refresh_token.trim();
+        if refresh_token.is_empty() {
+            return Err(anyhow!("refresh token is empty"));
+        }
+        self.refresh_session(&SessionToken {
+            refresh_token: refresh_token.to_string(),
+            ..SessionToken::default()
+        })?;
+        Ok(())
+    }
+
+    /// Forget the saved session. Returns whether there was one.
+    pub fn logout(&self) -> Result<bool> {
+        let mut had =
+            crate::secrets::use_keyring() && crate::secrets::delete(&self.session_account())?;
+        if self.session_path.exists() {
+            fs::remove_file(&self.session_path)
+                .with_context(|| format!("failed to remove {}", self.session_path.display()))?;
+            had = true;
+        }
+        if had {
+            self.clear_ls_cache();
+        }
+        Ok(had)
+    }
+
     pub fn has_valid_session(&self) -> bool {
         match self.load_session() {
             Ok(Some(token)) => !token.is_expired(now_unix()),
@@ -107,7 +379,32 @@ impl PikPak {
         }
     }
 
+    /// Whether a session is saved at all, expired or not.
+    pub fn has_session(&self) -> bool {
+        matches!(self.load_session(), Ok(Some(token)) if !token.refresh_token.is_empty())
+    }
+
+    /// Password login for callers that can't ask for a verification code;
+    /// when PikPak wants one, this fails telling the user where to enter it.
     pub fn login(&mut self, email: &str, password: &str) -> Result<()> {
+        match self.start_login(email, password)? {
+            LoginStep::Done => Ok(()),
+            LoginStep::NeedsCode(pending) => Err(failure::auth_error(format!(
+                "PikPak sent a verification code to {}; run `pikpaktui login` at a terminal or log in from the TUI to enter it",
+                pending.email()
+            ))),
+        }
+    }
+
+    /// Sign in with a password, plus the token and code of a passed
+    /// verification if there was one. Returns false, without saving a
+    /// session, when PikPak asks for a verification code first.
+    fn signin(
+        &mut self,
+        email: &str,
+        password: &str,
+        verification: Option<(&str, &str)>,
+    ) -> Result<bool> {
         if email.trim().is_empty() {
             return Err(anyhow!("email is empty"));
         }
@@ -120,6 +417,7 @@ impl PikPak {
         let captcha = self.init_captcha(email)?;
         self.captcha_token = captcha
             .captcha_token
+            .clone()
             .or_else(|| env::var("PIKPAK_CAPTCHA_TOKEN").ok())
             .ok_or_else(|| {
                 let hint = captcha.url.as_deref().unwrap_or("<no challenge url>");
@@ -130,7 +428,7 @@ impl PikPak {
             })?;
 
         let url = self.auth_url("v1/auth/signin");
-        let payload = serde_json::json!({
+        let mut payload = serde_json::json!({
             "username": email,
             "password": password,
             "client_id": self.client_id,
@@ -138,19 +436,25 @@ impl PikPak {
             "captcha_token": self.captcha_token,
             "grant_type": "password",
         });
+        if let Some((token, code)) = verification {
+            payload["verification_token"] = token.into();
+            payload["verification_code"] = code.into();
+        }
 
-        let response = self
-            .http
-            .post(&url)
-            .header("x-device-id", &self.device_id)
-            .json(&payload)
-            .send()
-            .context("signin request failed")?;
+        let response = self.send_retrying("signin", || {
+            self.http
+                .post(&url)
+                .header("x-device-id", &self.device_id)
+                .json(&payload)
+        })?;
 
         let status = response.status();
         if !status.is_success() {
             let body = response.text().unwrap_or_default();
-            return Err(anyhow!("signin failed ({}): {}", status, sanitize(&body)));
+            if verification.is_none() && verification::is_challenge(&body) {
+                return Ok(false);
+            }
+            return Err(failure::api_error("signin", status, &body));
         }
 
         let signin: SigninResponse = response.json().context("invalid signin json")?;
@@ -161,30 +465,37 @@ impl PikPak {
             access_token: signin.access_token,
             refresh_token: signin.refresh_token,
             expires_at_unix: now.saturating_add(expires_in),
+            device_id: self.device_id.clone(),
+            captcha_token: self.captcha_token.clone(),
+            captcha_expires_at_unix: captcha_expiry(captcha.expires_in, now),
         };
 
         self.save_session(&token)?;
-        Ok(())
+        Ok(true)
     }
 
     fn init_captcha(&self, email: &str) -> Result<CaptchaInitResponse> {
         let url = self.auth_url("v1/shield/captcha/init");
         let action = format!("POST:{}", self.auth_url("v1/auth/signin"));
 
-        let payload = serde_json::json!({
+        // A token from a captcha solved in the browser is traded for one
+        // that passes the sign-in.
+        let mut payload = serde_json::json!({
             "action": action,
             "client_id": self.client_id,
             "device_id": self.device_id,
             "meta": { "username": email },
         });
+        if !self.captcha_token.is_empty() {
+            payload["captcha_token"] = self.captcha_token.clone().into();
+        }
 
-        let response = self
-            .http
-            .post(&url)
-            .header("x-device-id", &self.device_id)
-            .json(&payload)
-            .send()
-            .context("captcha init failed")?;
+        let response = self.send_retrying("captcha init", || {
+            self.http
+                .post(&url)
+                .header("x-device-id", &self.device_id)
+                .json(&payload)
+        })?;
 
         let status = response.status();
         if !status.is_success() {
@@ -201,88 +512,184 @@ impl PikPak {
             .context("invalid captcha json")
     }
 
-    fn access_token(&self) -> Result<String> {
+    /// The saved session, renewed first if the access token expires within
+    /// 5 minutes or the captcha token within a minute.
+    fn current_session(&self) -> Result<SessionToken> {
         let session = self
             .load_session()?
-            .ok_or_else(|| anyhow!("not logged in, please login first"))?;
+            .ok_or_else(|| failure::auth_error("not logged in, please login first"))?;
+        let due =
+            |s: &SessionToken| s.is_expired(now_unix() + 300) || s.captcha_expired(now_unix() + 60);
+        if !due(&session) {
+            return Ok(session);
+        }
 
-        // Refresh proactively if the token expires within 5 minutes.
+        // Serialize refresh attempts — only one thread refreshes at a time.
+        let _guard = self.refresh_lock.lock().unwrap_or_else(|e| e.into_inner());
+        // Re-check after acquiring lock: another thread may have refreshed already.
+        let mut session = self
+            .load_session()?
+            .ok_or_else(|| failure::auth_error("not logged in, please login first"))?;
         if session.is_expired(now_unix() + 300) {
-            // Serialize refresh attempts — only one thread refreshes at a time.
-            let _guard = self.refresh_lock.lock().unwrap_or_else(|e| e.into_inner());
-            // Re-check after acquiring lock: another thread may have refreshed already.
-            let session = self
-                .load_session()?
-                .ok_or_else(|| anyhow!("not logged in, please login first"))?;
-            if session.is_expired(now_unix() + 300) {
-                match self.refresh_session(&session.refresh_token) {
-                    Ok(new_token) => return Ok(new_token),
-                    Err(e) => {
-                        return Err(anyhow!(
-                            "session expired and token refresh failed: {e:#}\nPlease log in again."
-                        ));
-                    }
-                }
+            session = self.refresh_session(&session).map_err(|e| {
+                failure::renewal_failed(e, "session expired and token refresh failed")
+            })?;
+        }
+        if session.captcha_expired(now_unix() + 60) {
+            self.refresh_captcha(&mut session).map_err(|e| {
+                failure::renewal_failed(e, "captcha token expired and couldn't be renewed")
+            })?;
+        }
+        Ok(session)
+    }
+
+    /// Trade the session's captcha token for a fresh one before PikPak
+    /// starts answering `captcha_invalid`, with the device id it was issued
+    /// to. Fails when PikPak wants a challenge solved instead, which only a
+    /// new login can do.
+    fn refresh_captcha(&self, session: &mut SessionToken) -> Result<()> {
+        let payload = serde_json::json!({
+            "action": format!("GET:{}", self.drive_url("drive/v1/files")),
+            "client_id": self.client_id,
+            "device_id": session.device_id,
+            "captcha_token": session.captcha_token,
+            "meta": {},
+        });
+        let response = self.send_retrying("captcha refresh", || {
+            self.http
+                .post(self.auth_url("v1/shield/captcha/init"))
+                .header("x-device-id", &session.device_id)
+                .json(&payload)
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().unwrap_or_default();
+            return Err(failure::api_error("captcha refresh", status, &body));
+        }
+        let captcha: CaptchaInitResponse = response.json().context("invalid captcha json")?;
+        session.captcha_token = captcha
+            .captcha_token
+            .ok_or_else(|| anyhow!("PikPak asked for a captcha challenge"))?;
+        session.captcha_expires_at_unix = captcha_expiry(captcha.expires_in, now_unix());
+        self.save_session(session)
+    }
+
+    /// Recover from a 401 on `rejected`: refresh the session unless another
+    /// thread already replaced that token while we waited for the lock, in
+    /// which case its fresh token is reused instead of refreshing again.
+    fn refresh_after_unauthorized(&self, rejected: &str) -> Result<SessionToken> {
+        let _guard = self.refresh_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let session = self
+            .load_session()?
+            .ok_or_else(|| failure::auth_error("not logged in, please login first"))?;
+        if session.access_token != rejected {
+            return Ok(session);
+        }
+        self.refresh_session(&session)
+            .map_err(|e| failure::renewal_failed(e, "session rejected and token refresh failed"))
+    }
+
+    /// Send a drive request with the bearer token and device headers attached.
+    /// `build` returns the bare request and is called again for each retry:
+    /// transient failures are retried under the retry policy, and a 401
+    /// (token revoked or expired early) refreshes the session once and
+    /// replays the request with the new token.
+    fn send_authed(
+        &self,
+        op: &str,
+        build: impl Fn() -> reqwest::blocking::RequestBuilder,
+    ) -> Result<reqwest::blocking::Response> {
+        let send = |session: &SessionToken| {
+            let result = self.send_retrying(op, || {
+                self.authed_headers(build().bearer_auth(&session.access_token), session)
+            });
+            let failed = result.as_ref().map_or(true, |r| {
+                r.status() != reqwest::StatusCode::UNAUTHORIZED
+                    && (r.status().is_client_error() || r.status().is_server_error())
+            });
+            if failed {
+                self.api_errors.fetch_add(1, Ordering::Relaxed);
             }
-            return Ok(session.access_token);
+            result
+        };
+        let session = self.current_session()?;
+        let response = send(&session)?;
+        if response.status() != reqwest::StatusCode::UNAUTHORIZED {
+            return Ok(response);
         }
+        let session = self.refresh_after_unauthorized(&session.access_token)?;
+        let response = send(&session)?;
+        if response.status() == reqwest::StatusCode::UNAUTHORIZED {
+            self.api_errors.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(response)
+    }
 
-        Ok(session.access_token)
+    /// How many API calls have failed so far (transport errors and error
+    /// statuses; a 401 that a token refresh fixed doesn't count).
+    pub fn api_errors(&self) -> u64 {
+        self.api_errors.load(Ordering::Relaxed)
     }
 
     /// Use the refresh_token to obtain a new access_token without requiring
-    /// the user's password. Saves the updated session to disk and returns
-    /// the new access_token.
-    fn refresh_session(&self, refresh_token: &str) -> Result<String> {
+    /// the user's password. The device id and captcha token carry over from
+    /// `session`; the updated session is saved to disk and returned.
+    fn refresh_session(&self, session: &SessionToken) -> Result<SessionToken> {
         let url = self.auth_url("v1/auth/token");
 
         let payload = serde_json::json!({
             "grant_type": "refresh_token",
-            "refresh_token": refresh_token,
+            "refresh_token": session.refresh_token,
             "client_id": self.client_id,
             "client_secret": self.client_secret,
         });
 
-        let response = self
-            .http
-            .post(&url)
-            .json(&payload)
-            .send()
-            .context("token refresh request failed")?;
+        let response = self.send_retrying("token refresh", || {
+            let request = self.http.post(&url).json(&payload);
+            if session.device_id.is_empty() {
+                request
+            } else {
+                request.header("x-device-id", &session.device_id)
+            }
+        })?;
 
         let status = response.status();
         if !status.is_success() {
             let body = response.text().unwrap_or_default();
-            return Err(anyhow!(
-                "token refresh failed ({}): {}",
-                status,
-                sanitize(&body)
-            ));
+            return Err(failure::api_error("token refresh", status, &body));
         }
 
         let refreshed: SigninResponse = response.json().context("invalid token refresh json")?;
         let expires_in = i64::try_from(refreshed.expires_in).context("expires_in overflow")?;
 
         let token = SessionToken {
-            access_token: refreshed.access_token.clone(),
+            access_token: refreshed.access_token,
             refresh_token: refreshed.refresh_token,
             expires_at_unix: now_unix().saturating_add(expires_in),
+            ..session.clone()
         };
         self.save_session(&token)?;
 
-        Ok(refreshed.access_token)
+        Ok(token)
     }
 
+    /// The device and captcha headers of `session`, or of this client's own
+    /// login when the session predates keeping them.
     fn authed_headers(
         &self,
         rb: reqwest::blocking::RequestBuilder,
+        session: &SessionToken,
     ) -> reqwest::blocking::RequestBuilder {
+        let pick =
+            |saved: &'_ str, own: &'_ str| if saved.is_empty() { own } else { saved }.to_string();
+        let device_id = pick(&session.device_id, &self.device_id);
+        let captcha_token = pick(&session.captcha_token, &self.captcha_token);
         let mut rb = rb;
-        if !self.device_id.is_empty() {
-            rb = rb.header("x-device-id", &self.device_id);
+        if !device_id.is_empty() {
+            rb = rb.header("x-device-id", device_id);
         }
-        if !self.captcha_token.is_empty() {
-            rb = rb.header("x-captcha-token", &self.captcha_token);
+        if !captcha_token.is_empty() {
+            rb = rb.header("x-captcha-token", captcha_token);
         }
         rb
     }
@@ -309,19 +716,120 @@ impl PikPak {
         &self.http
     }
 
+    /// Rebuild the HTTP clients with new timeouts, address family and DNS
+    /// overrides, and take the retry policy and API rate limit.
+    pub fn configure_network(&mut self, net: &NetOptions) -> Result<()> {
+        self.api_limiter
+            .set_limit(net.api_rate_limit, net.api_burst);
+        self.http = build_http(net, net.request_timeout)?;
+        self.transfer_http = build_http(net, net.transfer_timeout)?;
+        self.abortable_http = build_abortable_http(net)?;
+        self.retry = RetryPolicy {
+            retries: net.api_retries,
+            backoff: std::time::Duration::from_millis(net.retry_backoff_ms),
+        };
+        Ok(())
+    }
+
     pub fn events(&self, limit: u32) -> Result<EventsResponse> {
-        let token = self.access_token()?;
-        let url = self.drive_url("drive/v1/events");
+        self.events_page(limit, "")
+    }
 
-        let mut rb = self.http.get(&url).bearer_auth(&token).query(&[
-            ("thumbnail_size", self.thumbnail_size.as_str()),
-            ("limit", &limit.to_string()),
-        ]);
-        rb = self.authed_headers(rb);
+    /// One page of the events feed, newest first; `page_token` is the
+    /// previous page's `next_page_token` (empty for the first page).
+    pub fn events_page(&self, limit: u32, page_token: &str) -> Result<EventsResponse> {
+        let url = self.drive_url("drive/v1/events");
 
-        let response = rb.send().context("events request failed")?;
+        let response = self.send_authed("events", || {
+            let mut rb = self.http.get(&url).query(&[
+                ("thumbnail_size", self.thumbnail_size.as_str()),
+                ("limit", &limit.to_string()),
+            ]);
+            if !page_token.is_empty() {
+                rb = rb.query(&[("page_token", page_token)]);
+            }
+            rb
+        })?;
         json_or_api_error(response, "events")
     }
+
+    /// Walk the events feed page by page, keeping those `keep` accepts,
+    /// until `max` are kept, the feed runs out, or an event is older than
+    /// `since_unix`.
+    pub fn events_matching(
+        &self,
+        since_unix: Option<i64>,
+        max: usize,
+        mut keep: impl FnMut(&EventEntry) -> bool,
+    ) -> Result<Vec<EventEntry>> {
+        const PAGE_SIZE: u32 = 100;
+        let mut out = Vec::new();
+        let mut token = String::new();
+        loop {
+            let page = self.events_page(PAGE_SIZE, &token)?;
+            for ev in page.events {
+                let time = ev.created_time.as_deref().and_then(iso_to_unix);
+                if let (Some(since), Some(t)) = (since_unix, time)
+                    && t < since
+                {
+                    return Ok(out);
+                }
+                if keep(&ev) {
+                    out.push(ev);
+                    if out.len() >= max {
+                        return Ok(out);
+                    }
+                }
+            }
+            if page.next_page_token.is_empty() || page.next_page_token == token {
+                return Ok(out);
+            }
+            token = page.next_page_token;
+        }
+    }
+
+    /// Files added since `since_unix`, newest first, synthesized from the
+    /// events feed's create and upload events. Each distinct file is looked
+    /// up once, a few at a time; files that have since been trashed or
+    /// deleted are skipped, and any other failed lookup fails the listing.
+    pub fn recent_files(&self, since_unix: i64) -> Result<Vec<Entry>> {
+        const MAX: usize = 200;
+        const LOOKUPS: usize = 8;
+        let mut seen = std::collections::HashSet::new();
+        let events = self.events_matching(Some(since_unix), MAX, |ev| {
+            is_added(ev)
+                && ev
+                    .file_id
+                    .as_deref()
+                    .is_some_and(|id| !id.is_empty() && seen.insert(id.to_string()))
+        })?;
+        let ids: Vec<String> = events.into_iter().filter_map(|ev| ev.file_id).collect();
+        let mut entries = Vec::new();
+        for chunk in ids.chunks(LOOKUPS) {
+            let infos: Vec<_> = std::thread::scope(|s| {
+                let handles: Vec<_> = chunk
+                    .iter()
+                    .map(|id| s.spawn(move || self.file_info(id)))
+                    .collect();
+                handles.into_iter().map(|h| h.join()).collect()
+            });
+            for info in infos {
+                match info.map_err(|_| anyhow!("file lookup panicked"))? {
+                    Ok(info) if !info.trashed => entries.push(info.into_entry()),
+                    Ok(_) => {}
+                    Err(e) if failure_kind(&e) == Some(FailureKind::NotFound) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Whether `ev` put a file in the drive: created it or uploaded it.
+fn is_added(ev: &EventEntry) -> bool {
+    let kind = ev.event_type.as_deref().unwrap_or("").to_uppercase();
+    kind.contains("CREATE") || kind.contains("UPLOAD")
 }
 
 // These two helpers cover the common drive/auth API error shape: a non-success
@@ -342,7 +850,7 @@ fn ensure_success(response: reqwest::blocking::Response, op: &str) -> Result<()>
         return Ok(());
     }
     let body = response.text().unwrap_or_default();
-    Err(anyhow!("{} failed ({}): {}", op, status, sanitize(&body)))
+    Err(failure::api_error(op, status, &body))
 }
 
 /// Decode a JSON success body into `T`, or turn a non-success status into an
@@ -356,7 +864,7 @@ fn json_or_api_error<T: serde::de::DeserializeOwned>(
     let status = response.status();
     if !status.is_success() {
         let body = response.text().unwrap_or_default();
-        return Err(anyhow!("{} failed ({}): {}", op, status, sanitize(&body)));
+        return Err(failure::api_error(op, status, &body));
     }
     response
         .json()
@@ -364,10 +872,14 @@ fn json_or_api_error<T: serde::de::DeserializeOwned>(
 }
 
 fn default_session_path() -> Result<PathBuf> {
-    let base = dirs::home_dir()
-        .map(|h| h.join(".config"))
-        .ok_or_else(|| anyhow!("unable to locate home dir"))?;
-    Ok(base.join("pikpaktui").join("session.json"))
+    session_path_for(crate::config::profile().as_deref())
+}
+
+/// `session.json` of `profile`; the listing cache and provenance records
+/// live next to it, so each account keeps its own.
+fn session_path_for(profile: Option<&str>) -> Result<PathBuf> {
+    let base = crate::config::config_root().ok_or_else(|| anyhow!("unable to locate home dir"))?;
+    Ok(crate::config::profile_dir_for(base.join("pikpaktui"), profile).join("session.json"))
 }
 
 #[cfg(unix)]
@@ -399,6 +911,65 @@ fn write_owner_only(path: &Path, data: &[u8]) -> std::io::Result<()> {
     fs::write(path, data)
 }
 
+/// Unix seconds as an API timestamp in UTC, like `2026-01-15T04:30:45Z`.
+pub(crate) fn unix_to_iso(secs: i64) -> String {
+    let rem = secs.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(secs.div_euclid(86400));
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+/// (year, month, day) of a count of days since 1970-01-01.
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    // Howard Hinnant's algorithm.
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Parse an API timestamp like `2026-01-15T12:30:45.000+08:00` (or `Z`) into
+/// unix seconds.
+pub(crate) fn iso_to_unix(iso: &str) -> Option<i64> {
+    let num = |range: std::ops::Range<usize>| iso.get(range)?.parse::<i64>().ok();
+    let (y, m, d) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hh, mm, ss) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    // Days from civil (Howard Hinnant's algorithm).
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * ((m + 9) % 12) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    let local = days * 86_400 + hh * 3600 + mm * 60 + ss;
+
+    // Skip fractional seconds, then apply the zone offset if present.
+    let zone = iso[19..].trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match zone.as_bytes().first() {
+        Some(&sign @ (b'+' | b'-')) => {
+            let oh: i64 = zone.get(1..3)?.parse().ok()?;
+            let om: i64 = zone.get(4..6).and_then(|s| s.parse().ok()).unwrap_or(0);
+            let secs = oh * 3600 + om * 60;
+            if sign == b'+' { secs } else { -secs }
+        }
+        _ => 0,
+    };
+    Some(local - offset)
+}
+
 fn now_unix() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -406,9 +977,17 @@ fn now_unix() -> i64 {
         .unwrap_or(0)
 }
 
+/// When a captcha token from `now` runs out; 0 (never renewed) when PikPak
+/// didn't say.
+fn captcha_expiry(expires_in: Option<u64>, now: i64) -> i64 {
+    expires_in
+        .and_then(|secs| i64::try_from(secs).ok())
+        .map_or(0, |secs| now.saturating_add(secs))
+}
+
 /// Sanitize a filename from an API response to prevent path traversal.
-fn sanitize_filename(name: &str) -> String {
-    name.replace(['/', '\\'], "_").replace("..", "_")
+pub fn sanitize_filename(name: &str) -> String {
+    crate::local_path::sanitize_component(name, crate::local_path::Flavor::NATIVE)
 }
 
 fn sanitize(s: &str) -> String {
@@ -434,7 +1013,6 @@ fn md5_hex(input: &str) -> String {
 mod tests {
     use super::drive::DriveListResponse;
     use super::*;
-    use std::collections::HashMap;
     use std::io::Write as _;
     use std::net::TcpListener;
     use std::sync::atomic::{AtomicUsize, Ordering};
@@ -447,24 +1025,13 @@ mod tests {
     }
 
     fn test_client(base_url: String, session_path: std::path::PathBuf) -> PikPak {
-        let client = PikPak {
-            http: reqwest::blocking::Client::builder().build().unwrap(),
-            drive_base_url: base_url,
-            auth_base_url: String::new(),
-            client_id: String::new(),
-            client_secret: String::new(),
-            session_path,
-            device_id: String::new(),
-            captcha_token: String::new(),
-            thumbnail_size: "SIZE_MEDIUM".to_string(),
-            ls_cache: Mutex::new(HashMap::new()),
-            refresh_lock: Mutex::new(()),
-        };
+        let client = PikPak::for_tests(base_url, session_path);
         client
             .save_session(&SessionToken {
                 access_token: "test-access".into(),
                 refresh_token: "test-refresh".into(),
                 expires_at_unix: now_unix() + 3600,
+                ..SessionToken::default()
             })
             .unwrap();
         client
@@ -592,12 +1159,28 @@ mod tests {
         (base_url, list_hits, handle)
     }
 
+    #[test]
+    fn iso_to_unix_handles_utc_and_offsets() {
+        assert_eq!(iso_to_unix("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(iso_to_unix("2024-03-01T12:00:00.123Z"), Some(1_709_294_400));
+        assert_eq!(
+            iso_to_unix("2024-03-01T20:00:00.000+08:00"),
+            Some(1_709_294_400)
+        );
+        assert_eq!(
+            iso_to_unix("2024-03-01T07:30:00-04:30"),
+            Some(1_709_294_400)
+        );
+        assert_eq!(iso_to_unix("garbage"), None);
+    }
+
     #[test]
     fn token_expiry_check() {
         let token = SessionToken {
             access_token: "a".into(),
             refresh_token: "r".into(),
             expires_at_unix: 100,
+            ..SessionToken::default()
         };
         assert!(!token.is_expired(99));
         assert!(token.is_expired(100));
@@ -628,6 +1211,7 @@ mod tests {
             access_token: "old".into(),
             refresh_token: "r".into(),
             expires_at_unix: now_unix() - 1,
+            ..SessionToken::default()
         };
         assert!(expired.is_expired(now_unix()));
 
@@ -635,6 +1219,7 @@ mod tests {
             access_token: "good".into(),
             refresh_token: "r".into(),
             expires_at_unix: now_unix() + 600,
+            ..SessionToken::default()
         };
         assert!(!valid.is_expired(now_unix() + 300));
     }
@@ -832,6 +1417,44 @@ mod tests {
         std::fs::remove_dir_all(dir).unwrap();
     }
 
+    #[test]
+    fn transient_failures_are_retried_and_noted() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let replies: [(u16, &str, &[u8]); 2] = [
+                (503, "Service Unavailable", b"busy"),
+                (200, "OK", br#"{"events":[]}"#),
+            ];
+            for ((code, reason, body), stream) in replies.into_iter().zip(listener.incoming()) {
+                let Ok(mut stream) = stream else { continue };
+                let mut buf = [0u8; 4096];
+                let _ = std::io::Read::read(&mut stream, &mut buf);
+                write_response(&mut stream, code, reason, body);
+            }
+        });
+        let dir = temp_test_dir("retry-transient");
+        let mut client = test_client(base_url, dir.join("session.json"));
+        client.retry = RetryPolicy {
+            retries: 2,
+            backoff: std::time::Duration::from_millis(1),
+        };
+
+        assert!(client.events(20).unwrap().events.is_empty());
+        assert_eq!(client.api_retries(), 1);
+        assert_eq!(client.api_errors(), 0);
+        let notes = client.take_retry_notes();
+        assert_eq!(notes.len(), 1);
+        assert!(
+            notes[0].starts_with("events: 503 Service Unavailable, retry 1/2"),
+            "got: {notes:?}"
+        );
+        assert!(client.take_retry_notes().is_empty());
+
+        handle.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
     #[test]
     fn starred_list_propagates_api_error() {
         let (base_url, handle) =
@@ -852,6 +1475,118 @@ mod tests {
         std::fs::remove_dir_all(dir).unwrap();
     }
 
+    #[test]
+    fn upload_progress_survives_until_the_file_changes() {
+        let dir = temp_test_dir("upload-manifest");
+        let client = test_client(String::new(), dir.join("session.json"));
+        let (a, b) = (dir.join("a.bin"), dir.join("b.bin"));
+        std::fs::write(&a, b"aaaa").unwrap();
+        std::fs::write(&b, b"bbbb").unwrap();
+
+        // Parts of a multipart upload, by file and destination.
+        let source = upload_manifest::Source::new(&a, &std::fs::metadata(&a).unwrap());
+        let partial = |parent_id: &str, upload_id: &str| upload_manifest::PartialUpload {
+            source: source.clone(),
+            parent_id: parent_id.into(),
+            file_id: "F".into(),
+            upload_id: upload_id.into(),
+            oss: upload::OssArgs {
+                endpoint: "oss.example".into(),
+                access_key_id: "id".into(),
+                access_key_secret: "oss-secret".into(),
+                security_token: "oss-token".into(),
+                bucket: "b".into(),
+                key: "k".into(),
+            },
+            etags: vec!["e1".into(), "e2".into()],
+        };
+        client.save_partial_upload(&partial("P", "U"));
+        client.save_partial_upload(&partial("Q", "U2"));
+        let saved = client.partial_upload(&source, "P").unwrap();
+        assert_eq!((saved.upload_id.as_str(), saved.etags.len()), ("U", 2));
+        assert_eq!(client.partial_upload(&source, "Q").unwrap().upload_id, "U2");
+        assert!(client.partial_upload(&source, "other").is_none());
+        client.forget_partial_upload(&source, "Q");
+        assert!(client.partial_upload(&source, "Q").is_none());
+        assert!(client.partial_upload(&source, "P").is_some());
+
+        // With the keyring in use, the OSS secrets stay out of the database.
+        crate::secrets::set_store(crate::config::CredentialStore::Keyring);
+        client.save_partial_upload(&partial("K", "UK"));
+        let stored: String = client
+            .store()
+            .unwrap()
+            .conn()
+            .query_row(
+                "SELECT oss FROM upload_parts WHERE parent_id = 'K'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert!(!stored.contains("oss-secret") && !stored.contains("oss-token"));
+        let saved = client.partial_upload(&source, "K").unwrap();
+        assert_eq!(
+            (
+                saved.oss.access_key_secret.as_str(),
+                saved.oss.security_token.as_str()
+            ),
+            ("oss-secret", "oss-token")
+        );
+        client.forget_partial_upload(&source, "K");
+        assert!(crate::secrets::get("upload:UK").unwrap().is_none());
+        crate::secrets::set_store(crate::config::CredentialStore::File);
+
+        std::fs::write(&a, b"changed").unwrap();
+        let changed = upload_manifest::Source::new(&a, &std::fs::metadata(&a).unwrap());
+        assert!(client.partial_upload(&changed, "P").is_none());
+
+        // The files of a folder upload, ticked off as they're sent.
+        let files = [a.as_path(), b.as_path()];
+        client
+            .begin_folder_upload("D", "folder 'x' to /x", &files)
+            .unwrap();
+        client.folder_file_sent("D", &a);
+        assert_eq!(client.sent_before("D", &files), [true, false]);
+        assert_eq!(
+            client.interrupted_uploads().unwrap(),
+            [("folder 'x' to /x".to_string(), 1)]
+        );
+        std::fs::write(&a, b"changed again").unwrap();
+        client
+            .begin_folder_upload("D", "folder 'x' to /x", &files)
+            .unwrap();
+        assert_eq!(client.sent_before("D", &files), [false, false]);
+        client.end_folder_upload("D");
+        assert!(client.interrupted_uploads().unwrap().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn instant_upload_records_provenance_by_file_id() {
+        let body = br#"{"file":{"id":"up1","phase":"PHASE_TYPE_COMPLETE"}}"#.to_vec();
+        let (base_url, handle) = start_canned_server(200, "OK", body);
+        let dir = temp_test_dir("upload-provenance");
+        let mut client = test_client(base_url, dir.join("session.json"));
+        client.record_provenance = true;
+        let local = dir.join("note.txt");
+        std::fs::write(&local, b"hello").unwrap();
+
+        let (name, dedup) = client.upload_file(None, &local).unwrap();
+        assert_eq!(name, "note.txt");
+        assert!(dedup);
+
+        let record = client.provenance("up1").expect("provenance recorded");
+        assert!(record.source_path.ends_with("note.txt"));
+        assert_eq!(record.size, 5);
+        assert_eq!(record.hash, upload::pikpak_hash(&local).unwrap());
+        assert!(record.mtime.is_some());
+        assert!(client.provenance("other").is_none());
+
+        handle.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
     #[test]
     fn ls_cached_is_invalidated_after_mutation() {
         // ls_cached #1 (GET), cached #2 (no request), rename (PATCH), ls_cached #3 (GET).
@@ -878,6 +1613,278 @@ mod tests {
         std::fs::remove_dir_all(dir).unwrap();
     }
 
+    #[test]
+    fn unauthorized_request_refreshes_and_retries_once() {
+        // remove (401 with the stale token), token refresh, remove again (200).
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut seen = Vec::new();
+            for stream in listener.incoming().take(3) {
+                let Ok(mut stream) = stream else { continue };
+                let mut buf = [0u8; 4096];
+                let n = std::io::Read::read(&mut stream, &mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let first_line = request.lines().next().unwrap_or_default().to_string();
+                if first_line.starts_with("post /v1/auth/token") {
+                    let body = r#"{"access_token":"fresh","refresh_token":"r2","expires_in":7200}"#;
+                    write_response(&mut stream, 200, "OK", body.as_bytes());
+                } else if request.contains("authorization: bearer fresh") {
+                    write_response(&mut stream, 200, "OK", b"{}");
+                } else {
+                    write_response(&mut stream, 401, "Unauthorized", b"{}");
+                }
+                seen.push(first_line);
+            }
+            seen
+        });
+        let dir = temp_test_dir("refresh-on-401");
+        let mut client = test_client(base_url.clone(), dir.join("session.json"));
+        client.auth_base_url = base_url;
+
+        client.remove(&["id1"]).unwrap();
+
+        let seen = handle.join().unwrap();
+        assert!(seen[0].starts_with("post /drive/v1/files:batchtrash"));
+        assert!(seen[1].starts_with("post /v1/auth/token"));
+        assert!(seen[2].starts_with("post /drive/v1/files:batchtrash"));
+        let session = client.load_session().unwrap().unwrap();
+        assert_eq!(session.access_token, "fresh");
+        assert_eq!(session.refresh_token, "r2");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ls_follows_page_tokens_and_reports_each_page() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                let Ok(mut stream) = stream else { continue };
+                let mut buf = [0u8; 4096];
+                let n = std::io::Read::read(&mut stream, &mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let body = if request.contains("page_token=p2") {
+                    r#"{"files":[{"id":"c","name":"c","kind":"drive#file"}],"next_page_token":""}"#
+                } else {
+                    r#"{"files":[{"id":"a","name":"a","kind":"drive#file"},{"id":"b","name":"b","kind":"drive#file"}],"next_page_token":"p2"}"#
+                };
+                write_response(&mut stream, 200, "OK", body.as_bytes());
+            }
+        });
+        let dir = temp_test_dir("ls-pages");
+        let client = test_client(base_url, dir.join("session.json"));
+
+        let mut pages = Vec::new();
+        let all = client
+            .ls_streaming("", |page| {
+                pages.push(page.len());
+                std::ops::ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(pages, vec![2, 1]);
+        let ids: Vec<_> = all.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b", "c"]);
+
+        handle.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn events_matching_walks_pages_until_since() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                let Ok(mut stream) = stream else { continue };
+                let mut buf = [0u8; 4096];
+                let n = std::io::Read::read(&mut stream, &mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let body = if request.contains("page_token=p2") {
+                    r#"{"events":[
+                        {"type":"TYPE_DELETE","file_name":"c","created_time":"2026-03-01T10:00:00Z"},
+                        {"type":"TYPE_UPLOAD","file_name":"old","created_time":"2026-01-01T10:00:00Z"}
+                    ],"next_page_token":"p3"}"#
+                } else {
+                    r#"{"events":[
+                        {"type":"TYPE_UPLOAD","file_name":"a","created_time":"2026-03-03T10:00:00Z"},
+                        {"type":"TYPE_RENAME","file_name":"b","created_time":"2026-03-02T10:00:00Z"}
+                    ],"next_page_token":"p2"}"#
+                };
+                write_response(&mut stream, 200, "OK", body.as_bytes());
+            }
+        });
+        let dir = temp_test_dir("events-pages");
+        let client = test_client(base_url, dir.join("session.json"));
+
+        // 2026-02-01T00:00:00Z: the walk stops at "old" without asking for p3.
+        let since = iso_to_unix("2026-02-01T00:00:00Z");
+        let events = client
+            .events_matching(since, usize::MAX, |ev| {
+                ev.event_type.as_deref() != Some("TYPE_RENAME")
+            })
+            .unwrap();
+        let names: Vec<_> = events
+            .iter()
+            .filter_map(|e| e.file_name.as_deref())
+            .collect();
+        assert_eq!(names, ["a", "c"]);
+
+        handle.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn recent_files_pages_through_added_events_only_and_skips_removed_files() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            // Two pages of events, then a lookup for each of "a", "c", "gone"
+            // and "binned".
+            for stream in listener.incoming().take(6) {
+                let Ok(mut stream) = stream else { continue };
+                let mut buf = [0u8; 4096];
+                let n = std::io::Read::read(&mut stream, &mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let path = request.split_whitespace().nth(1).unwrap_or_default();
+                if path.ends_with("/gone") {
+                    write_response(
+                        &mut stream,
+                        404,
+                        "Not Found",
+                        br#"{"error":"file_not_found"}"#,
+                    );
+                    continue;
+                }
+                let body = if let Some(id) = path.strip_prefix("/drive/v1/files/") {
+                    let trashed = id == "binned";
+                    format!(
+                        r#"{{"id":"{id}","name":"{id}.mkv","kind":"drive#file","trashed":{trashed}}}"#
+                    )
+                } else if request.contains("page_token=p2") {
+                    r#"{"events":[
+                        {"type":"TYPE_CREATE","file_id":"c","created_time":"2026-03-01T10:00:00Z"},
+                        {"type":"TYPE_UPLOAD","file_id":"gone","created_time":"2026-02-20T10:00:00Z"},
+                        {"type":"TYPE_UPLOAD","file_id":"binned","created_time":"2026-02-10T10:00:00Z"},
+                        {"type":"TYPE_UPLOAD","file_id":"old","created_time":"2026-01-01T10:00:00Z"}
+                    ],"next_page_token":"p3"}"#
+                        .to_string()
+                } else {
+                    r#"{"events":[
+                        {"type":"TYPE_UPLOAD","file_id":"a","created_time":"2026-03-03T10:00:00Z"},
+                        {"type":"TYPE_RENAME","file_id":"b","created_time":"2026-03-02T12:00:00Z"},
+                        {"type":"TYPE_UPLOAD","file_id":"a","created_time":"2026-03-02T10:00:00Z"}
+                    ],"next_page_token":"p2"}"#
+                        .to_string()
+                };
+                write_response(&mut stream, 200, "OK", body.as_bytes());
+            }
+        });
+        let dir = temp_test_dir("recent-files");
+        let client = test_client(base_url, dir.join("session.json"));
+
+        let since = iso_to_unix("2026-02-01T00:00:00Z").unwrap();
+        let recent = client.recent_files(since).unwrap();
+        let names: Vec<_> = recent.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["a.mkv", "c.mkv"]);
+
+        handle.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn offline_list_all_follows_page_tokens() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            for stream in listener.incoming().take(2) {
+                let Ok(mut stream) = stream else { continue };
+                let mut buf = [0u8; 4096];
+                let n = std::io::Read::read(&mut stream, &mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]);
+                let body = if request.contains("page_token=p2") {
+                    r#"{"tasks":[{"id":"t3","name":"c"}],"next_page_token":""}"#
+                } else {
+                    r#"{"tasks":[{"id":"t1","name":"a"},{"id":"t2","name":"b"}],"next_page_token":"p2"}"#
+                };
+                write_response(&mut stream, 200, "OK", body.as_bytes());
+            }
+        });
+        let dir = temp_test_dir("offline-pages");
+        let client = test_client(base_url, dir.join("session.json"));
+
+        let tasks = client.offline_list_all(&["PHASE_TYPE_COMPLETE"]).unwrap();
+        let ids: Vec<_> = tasks.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, ["t1", "t2", "t3"]);
+
+        handle.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn mkdir_p_reuses_existing_folders() {
+        let (base_url, hits, handle) = start_listing_server(1);
+        let dir = temp_test_dir("mkdir-p");
+        let client = test_client(base_url, dir.join("session.json"));
+
+        let (id, created) = client.mkdir_p("/A/").unwrap();
+        assert_eq!(id, "id1");
+        assert_eq!(created, 0);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        handle.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn cancelled_ls_stream_errors_without_caching() {
+        let (base_url, hits, handle) = start_listing_server(1);
+        let dir = temp_test_dir("ls-cancel");
+        let mut client = test_client(base_url, dir.join("session.json"));
+        client.listing_cache_ttl = 60;
+
+        let result = client.ls_streaming("", |_| std::ops::ControlFlow::Break(()));
+        assert!(result.is_err());
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+        assert!(client.cached_listing("").is_none());
+
+        handle.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn listing_cache_round_trips_and_honors_ttl() {
+        let (base_url, _hits, handle) = start_listing_server(1);
+        let dir = temp_test_dir("listing-cache");
+        let mut client = test_client(base_url, dir.join("session.json"));
+
+        // Disabled by default: nothing is written or read.
+        assert!(client.cached_listing("").is_none());
+
+        client.listing_cache_ttl = 60;
+        let fresh = client.ls("").unwrap();
+        let cached = client.cached_listing("").unwrap();
+        assert_eq!(cached.len(), fresh.len());
+        assert_eq!(cached[0].id, "id1");
+        assert!(client.cached_listing("other").is_none());
+
+        // A listing older than the TTL is ignored.
+        client.listing_cache_ttl = 1;
+        client
+            .store()
+            .unwrap()
+            .conn()
+            .execute("UPDATE listings SET fetched_at = ?1", [now_unix() - 10])
+            .unwrap();
+        assert!(client.cached_listing("").is_none());
+        // ...but still there for browsing offline.
+        assert_eq!(client.last_listing("").unwrap()[0].id, "id1");
+        assert!(client.last_listing("other").is_none());
+
+        handle.join().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
     #[cfg(unix)]
     #[test]
     fn save_session_writes_owner_only_file() {
@@ -890,4 +1897,155 @@ mod tests {
         assert_eq!(mode, 0o600, "session file must be owner-only");
         std::fs::remove_dir_all(dir).unwrap();
     }
+
+    #[test]
+    fn a_plaintext_session_moves_into_the_keyring() {
+        let dir = temp_test_dir("session-keyring");
+        let path = dir.join("session.json");
+        let client = test_client("http://unused".to_string(), path.clone());
+        assert!(path.exists());
+
+        crate::secrets::set_store(crate::config::CredentialStore::Keyring);
+        let token = client.load_session().unwrap().unwrap();
+        assert_eq!(token.access_token, "test-access");
+        assert!(!path.exists(), "the plaintext copy must be gone");
+        let kept = crate::secrets::get(&client.session_account())
+            .unwrap()
+            .unwrap();
+        assert!(kept.contains("test-refresh"));
+        assert_eq!(
+            client.load_session().unwrap().unwrap().refresh_token,
+            "test-refresh"
+        );
+
+        assert!(client.logout().unwrap());
+        assert!(
+            crate::secrets::get(&client.session_account())
+                .unwrap()
+                .is_none()
+        );
+        crate::secrets::set_store(crate::config::CredentialStore::File);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn login_asks_for_a_verification_code_when_challenged() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let signins = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&signins);
+        let handle = std::thread::spawn(move || {
+            for stream in listener.incoming().take(6) {
+                let Ok(mut stream) = stream else { continue };
+                let mut buf = [0u8; 4096];
+                let n = std::io::Read::read(&mut stream, &mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+                let first_line = request.lines().next().unwrap_or_default();
+                let (code, reason, body) = if first_line.starts_with("POST /v1/shield/captcha/init")
+                {
+                    (200, "OK", r#"{"captcha_token":"cap"}"#)
+                } else if first_line.starts_with("POST /v1/auth/signin") {
+                    let mut seen = seen.lock().unwrap();
+                    seen.push(request.clone());
+                    if seen.len() == 1 {
+                        (
+                            400,
+                            "Bad Request",
+                            r#"{"error":"verification_code_required"}"#,
+                        )
+                    } else {
+                        (
+                            200,
+                            "OK",
+                            r#"{"access_token":"a","refresh_token":"r","expires_in":3600}"#,
+                        )
+                    }
+                } else if first_line.starts_with("POST /v1/auth/verification/verify") {
+                    assert!(request.contains(r#""verification_id":"vid""#));
+                    (200, "OK", r#"{"verification_token":"vtok"}"#)
+                } else if first_line.starts_with("POST /v1/auth/verification") {
+                    (200, "OK", r#"{"verification_id":"vid"}"#)
+                } else {
+                    (404, "Not Found", "")
+                };
+                write_response(&mut stream, code, reason, body.as_bytes());
+            }
+        });
+
+        let dir = temp_test_dir("verify-login");
+        let session_path = dir.join("session.json");
+        let mut client = test_client(base_url.clone(), session_path.clone());
+        client.auth_base_url = base_url;
+        client.logout().unwrap();
+
+        let LoginStep::NeedsCode(pending) = client.start_login("a@b.c", "pw").unwrap() else {
+            panic!("expected a verification step");
+        };
+        assert_eq!(pending.email(), "a@b.c");
+        assert!(!session_path.exists());
+
+        client.finish_login(&pending, " 123456 ").unwrap();
+        handle.join().unwrap();
+        assert!(client.has_valid_session());
+        let signins = signins.lock().unwrap();
+        assert!(signins[1].contains(r#""verification_token":"vtok""#));
+        assert!(signins[1].contains(r#""verification_code":"123456""#));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn expiring_captcha_token_is_renewed_with_the_saved_device_id() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+        let handle = std::thread::spawn(move || {
+            for (i, stream) in listener.incoming().take(3).enumerate() {
+                let Ok(mut stream) = stream else { continue };
+                let mut buf = [0u8; 4096];
+                let n = std::io::Read::read(&mut stream, &mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+                seen.lock().unwrap().push(request.clone());
+                let body = match i {
+                    0 => r#"{"captcha_token":"new","expires_in":300}"#,
+                    1 => r#"{"files":[]}"#,
+                    // The second renewal wants a challenge solved.
+                    _ => r#"{"url":"https://example.com/challenge"}"#,
+                };
+                write_response(&mut stream, 200, "OK", body.as_bytes());
+            }
+        });
+
+        let dir = temp_test_dir("captcha-renew");
+        let mut client = test_client(base_url.clone(), dir.join("session.json"));
+        client.auth_base_url = base_url;
+        let stale = SessionToken {
+            access_token: "acc".into(),
+            refresh_token: "ref".into(),
+            expires_at_unix: now_unix() + 3600,
+            device_id: "dev".into(),
+            captcha_token: "old".into(),
+            captcha_expires_at_unix: now_unix() + 10,
+        };
+        client.save_session(&stale).unwrap();
+
+        client.ls("").unwrap();
+        let saved = client.load_session().unwrap().unwrap();
+        assert_eq!(saved.captcha_token, "new");
+        assert_eq!(saved.device_id, "dev");
+        assert!(saved.captcha_expires_at_unix > now_unix() + 200);
+
+        client.save_session(&stale).unwrap();
+        let err = client.ls("").unwrap_err();
+        assert_eq!(failure_kind(&err), Some(FailureKind::Auth));
+        handle.join().unwrap();
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("POST /v1/shield/captcha/init"));
+        assert!(requests[0].contains("x-device-id: dev"));
+        assert!(requests[0].contains(r#""captcha_token":"old""#));
+        assert!(requests[1].contains("x-captcha-token: new"));
+        assert!(requests[1].contains("x-device-id: dev"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -1,12 +1,12 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum EntryKind {
     Folder,
     File,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Entry {
     pub id: String,
     pub name: String,
@@ -16,17 +16,104 @@ pub struct Entry {
     pub modified_time: String,
     pub starred: bool,
     pub thumbnail_link: Option<String>,
+    /// When PikPak deletes a trashed item for good (the API's
+    /// `delete_time`); empty outside trash listings.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub purge_time: String,
+    /// PikPak's content hash (see `pikpak_hash`); empty for folders and when
+    /// the server didn't send one.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hash: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Entry {
+    /// An item known only by its id, name and kind.
+    pub fn new(id: impl Into<String>, name: impl Into<String>, kind: EntryKind) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            kind,
+            size: 0,
+            created_time: String::new(),
+            modified_time: String::new(),
+            starred: false,
+            thumbnail_link: None,
+            purge_time: String::new(),
+            hash: String::new(),
+        }
+    }
+}
+
+/// Entries for tests: `Entry::file("a.mkv").with_size(3)`. The id is the
+/// name unless set.
+#[cfg(test)]
+impl Entry {
+    pub fn file(name: &str) -> Self {
+        Self::new(name, name, EntryKind::File)
+    }
+
+    pub fn folder(name: &str) -> Self {
+        Self::new(name, name, EntryKind::Folder)
+    }
+
+    pub fn with_id(self, id: &str) -> Self {
+        Self {
+            id: id.into(),
+            ..self
+        }
+    }
+
+    pub fn with_size(self, size: u64) -> Self {
+        Self { size, ..self }
+    }
+
+    pub fn with_hash(self, hash: &str) -> Self {
+        Self {
+            hash: hash.into(),
+            ..self
+        }
+    }
+
+    /// Created and last modified at `time`.
+    pub fn with_time(self, time: &str) -> Self {
+        Self {
+            created_time: time.into(),
+            modified_time: time.into(),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SessionToken {
     pub access_token: String,
     pub refresh_token: String,
     pub expires_at_unix: i64,
+    /// The device id the session was signed in with; PikPak expects the
+    /// same one on every later call.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub device_id: String,
+    /// The captcha token from sign-in, renewed before it runs out.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub captcha_token: String,
+    /// 0 when the expiry is unknown, and the token is never renewed.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub captcha_expires_at_unix: i64,
+}
+
+fn is_zero(n: &i64) -> bool {
+    *n == 0
 }
 
 impl SessionToken {
     pub fn is_expired(&self, now_unix: i64) -> bool {
         now_unix >= self.expires_at_unix
     }
+
+    /// Whether the captcha token has an expiry and it has passed.
+    pub fn captcha_expired(&self, now_unix: i64) -> bool {
+        !self.captcha_token.is_empty()
+            && self.captcha_expires_at_unix > 0
+            && now_unix >= self.captcha_expires_at_unix
+    }
 }
@@ -0,0 +1,162 @@
+//! Notes and labels on files and folders — "watched", "seeding", "to sort"
+//! — which PikPak has no field for. They live only in the local database,
+//! keyed by file id so they follow an entry through renames and moves.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+
+use super::{PikPak, now_unix};
+use crate::store::{OptionalExtension, params};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Note {
+    /// Where the entry was when last noted, to list notes without looking
+    /// every entry up.
+    pub path: String,
+    pub labels: Vec<String>,
+    pub text: String,
+    pub updated_at: i64,
+}
+
+impl Note {
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty() && self.text.trim().is_empty()
+    }
+
+    /// Whether `query` is in a label, the text or the path, ignoring case.
+    /// `#name` matches only labels, by their start.
+    pub fn matches(&self, query: &str) -> bool {
+        let query = query.trim().to_lowercase();
+        if let Some(label) = query.strip_prefix('#') {
+            return self.labels.iter().any(|l| l.starts_with(label));
+        }
+        self.labels.iter().any(|l| l.contains(&query))
+            || self.text.to_lowercase().contains(&query)
+            || self.path.to_lowercase().contains(&query)
+    }
+
+    /// Add `labels`, keeping the ones already there first.
+    pub fn add_labels(&mut self, labels: &[String]) {
+        for label in labels {
+            if !self.labels.contains(label) {
+                self.labels.push(label.clone());
+            }
+        }
+    }
+}
+
+/// Labels as typed: split on commas and spaces, lowercased, a leading `#`
+/// dropped, duplicates removed.
+pub fn parse_labels(raw: &str) -> Vec<String> {
+    let mut labels: Vec<String> = Vec::new();
+    for label in raw.split([',', ' ']) {
+        let label = label.trim().trim_start_matches('#').to_lowercase();
+        if !label.is_empty() && !labels.contains(&label) {
+            labels.push(label);
+        }
+    }
+    labels
+}
+
+fn row_to_note(r: &rusqlite::Row) -> rusqlite::Result<Note> {
+    let labels: String = r.get("labels")?;
+    Ok(Note {
+        path: r.get("path")?,
+        labels: parse_labels(&labels),
+        text: r.get("note")?,
+        updated_at: r.get("updated_at")?,
+    })
+}
+
+impl PikPak {
+    /// Every note, by file id.
+    pub fn notes(&self) -> Result<HashMap<String, Note>> {
+        let store = self.store()?;
+        let mut stmt = store
+            .conn()
+            .prepare("SELECT file_id, path, labels, note, updated_at FROM notes")?;
+        let notes = stmt
+            .query_map([], |r| Ok((r.get("file_id")?, row_to_note(r)?)))?
+            .collect::<rusqlite::Result<_>>()
+            .context("failed to read notes")?;
+        Ok(notes)
+    }
+
+    pub fn note(&self, file_id: &str) -> Result<Option<Note>> {
+        let store = self.store()?;
+        store
+            .conn()
+            .query_row(
+                "SELECT path, labels, note, updated_at FROM notes WHERE file_id = ?1",
+                [file_id],
+                row_to_note,
+            )
+            .optional()
+            .context("failed to read note")
+    }
+
+    /// Save `note` for `file_id`, stamped with the current time; an empty
+    /// one removes it.
+    pub fn set_note(&self, file_id: &str, note: &Note) -> Result<()> {
+        let store = self.store()?;
+        if note.is_empty() {
+            store
+                .conn()
+                .execute("DELETE FROM notes WHERE file_id = ?1", [file_id])
+                .context("failed to remove note")?;
+            return Ok(());
+        }
+        store
+            .conn()
+            .execute(
+                "INSERT OR REPLACE INTO notes (file_id, path, labels, note, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    file_id,
+                    note.path,
+                    note.labels.join(","),
+                    note.text.trim(),
+                    now_unix()
+                ],
+            )
+            .context("failed to save note")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_are_normalized_and_searchable() {
+        assert_eq!(
+            parse_labels("Watched, #seeding  to-sort,watched"),
+            ["watched", "seeding", "to-sort"]
+        );
+        let mut note = Note {
+            path: "/Movies/Alien (1979).mkv".into(),
+            labels: parse_labels("watched"),
+            text: "Director's cut".into(),
+            ..Note::default()
+        };
+        note.add_labels(&parse_labels("seeding watched"));
+        assert_eq!(note.labels, ["watched", "seeding"]);
+
+        assert!(note.matches("#seed"));
+        assert!(!note.matches("#cut"));
+        assert!(note.matches("DIRECTOR"));
+        assert!(note.matches("alien"));
+        assert!(!note.matches("to-sort"));
+
+        assert!(Note::default().is_empty());
+        assert!(
+            Note {
+                text: "  ".into(),
+                ..Note::default()
+            }
+            .is_empty()
+        );
+    }
+}
@@ -1,6 +1,10 @@
-use anyhow::{Context, Result};
+use anyhow::{Result, anyhow};
+use std::path::PathBuf;
 
-use super::{OfflineListResponse, OfflineTaskResponse, PikPak, ensure_success, json_or_api_error};
+use super::{
+    Entry, EntryKind, OfflineListResponse, OfflineTask, OfflineTaskResponse, PikPak,
+    ensure_success, json_or_api_error, sanitize_filename,
+};
 
 impl PikPak {
     pub fn offline_download(
@@ -9,7 +13,6 @@ impl PikPak {
         parent_id: Option<&str>,
         name: Option<&str>,
     ) -> Result<OfflineTaskResponse> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/files");
 
         let mut payload = serde_json::json!({
@@ -27,36 +30,58 @@ impl PikPak {
             payload["name"] = serde_json::json!(n);
         }
 
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("offline download request failed")?;
+        let response =
+            self.send_authed("offline download", || self.http.post(&url).json(&payload))?;
         json_or_api_error(response, "offline download")
     }
 
     pub fn offline_list(&self, limit: u32, phases: &[&str]) -> Result<OfflineListResponse> {
-        let token = self.access_token()?;
+        self.offline_list_page(limit, phases, "")
+    }
+
+    pub fn offline_list_page(
+        &self,
+        limit: u32,
+        phases: &[&str],
+        page_token: &str,
+    ) -> Result<OfflineListResponse> {
         let url = self.drive_url("drive/v1/tasks");
 
         let filters = serde_json::json!({
             "phase": { "in": phases.join(",") }
         });
 
-        let mut rb = self.http.get(&url).bearer_auth(&token).query(&[
-            ("type", "offline"),
-            ("thumbnail_size", "SIZE_SMALL"),
-            ("limit", &limit.to_string()),
-            ("filters", &filters.to_string()),
-            ("with", "reference_resource"),
-        ]);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("offline list request failed")?;
+        let response = self.send_authed("offline list", || {
+            let mut rb = self.http.get(&url).query(&[
+                ("type", "offline"),
+                ("thumbnail_size", "SIZE_SMALL"),
+                ("limit", &limit.to_string()),
+                ("filters", &filters.to_string()),
+                ("with", "reference_resource"),
+            ]);
+            if !page_token.is_empty() {
+                rb = rb.query(&[("page_token", page_token)]);
+            }
+            rb
+        })?;
         json_or_api_error(response, "offline list")
     }
 
+    /// Every offline task in `phases`, however many pages that takes.
+    pub fn offline_list_all(&self, phases: &[&str]) -> Result<Vec<OfflineTask>> {
+        let mut tasks = Vec::new();
+        let mut token = String::new();
+        loop {
+            let page = self.offline_list_page(100, phases, &token)?;
+            tasks.extend(page.tasks);
+            if page.next_page_token.is_empty() || page.next_page_token == token {
+                return Ok(tasks);
+            }
+            token = page.next_page_token;
+        }
+    }
+
     pub fn offline_task_retry(&self, task_id: &str) -> Result<()> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/task");
 
         let payload = serde_json::json!({
@@ -65,15 +90,12 @@ impl PikPak {
             "id": task_id,
         });
 
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("offline task retry request failed")?;
+        let response =
+            self.send_authed("offline task retry", || self.http.post(&url).json(&payload))?;
         ensure_success(response, "offline task retry")
     }
 
     pub fn delete_tasks(&self, task_ids: &[&str], delete_files: bool) -> Result<()> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/tasks");
 
         let mut pairs: Vec<(&str, String)> = task_ids
@@ -82,13 +104,39 @@ impl PikPak {
             .collect();
         pairs.push(("delete_files", delete_files.to_string()));
 
-        let mut rb = self.http.delete(&url).bearer_auth(&token);
-        for (k, v) in &pairs {
-            rb = rb.query(&[(k, v)]);
-        }
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("delete tasks request failed")?;
+        let response = self.send_authed("delete tasks", || {
+            let mut rb = self.http.delete(&url);
+            for (k, v) in &pairs {
+                rb = rb.query(&[(k, v)]);
+            }
+            rb
+        })?;
         ensure_success(response, "delete tasks")
     }
+
+    /// Every file a completed offline task produced, paired with its path
+    /// relative to the download directory. A folder (e.g. from a magnet with
+    /// several files) keeps its structure under its own name.
+    pub fn offline_task_files(&self, task: &OfflineTask) -> Result<Vec<(Entry, PathBuf)>> {
+        let file_id = task
+            .file_id
+            .as_deref()
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| anyhow!("task '{}' has no file", task.name))?;
+        let root = self.file_info(file_id)?.into_entry();
+        let mut files = Vec::new();
+        let mut pending = vec![(root, PathBuf::new())];
+        while let Some((entry, parent)) = pending.pop() {
+            let path = parent.join(sanitize_filename(&entry.name));
+            match entry.kind {
+                EntryKind::File => files.push((entry, path)),
+                EntryKind::Folder => {
+                    for child in self.ls(&entry.id)? {
+                        pending.push((child, path.clone()));
+                    }
+                }
+            }
+        }
+        Ok(files)
+    }
 }
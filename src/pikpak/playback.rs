@@ -0,0 +1,143 @@
+//! How far each video has been watched: the position the player last
+//! reported and whether it counts as seen. Like notes, it lives only in the
+//! local database, keyed by file id. Folders can be marked watched as a
+//! whole, which marks the videos in them and the folder itself.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+use super::{PikPak, now_unix};
+use crate::store::params;
+
+/// Share of a video that has to be played for it to count as watched, so
+/// the end credits can be skipped.
+const WATCHED_SHARE: f64 = 0.9;
+
+/// Positions closer to the start than this aren't worth resuming from.
+const MIN_RESUME_SECS: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Playback {
+    /// Seconds in; 0 when the player doesn't report it.
+    pub position: f64,
+    /// Seconds long; 0 when unknown.
+    pub duration: f64,
+    pub watched: bool,
+    pub updated_at: i64,
+}
+
+impl Playback {
+    /// Playback stopped at `position` of `duration`.
+    pub fn at(position: f64, duration: f64) -> Self {
+        Self {
+            position,
+            duration,
+            watched: duration > 0.0 && position >= duration * WATCHED_SHARE,
+            updated_at: 0,
+        }
+    }
+
+    /// How far in, from 0 to 1, for a video started but not finished.
+    pub fn progress(&self) -> Option<f64> {
+        (!self.watched && self.duration > 0.0)
+            .then(|| (self.position / self.duration).clamp(0.0, 1.0))
+    }
+
+    /// Where to pick up again, if anywhere.
+    pub fn resume_at(&self) -> Option<f64> {
+        (!self.watched && self.position >= MIN_RESUME_SECS).then_some(self.position)
+    }
+}
+
+impl PikPak {
+    /// Every entry with a recorded playback, by file id.
+    pub fn playback(&self) -> Result<HashMap<String, Playback>> {
+        let store = self.store()?;
+        let mut stmt = store
+            .conn()
+            .prepare("SELECT file_id, position, duration, watched, updated_at FROM playback")?;
+        let rows = stmt
+            .query_map([], |r| {
+                Ok((
+                    r.get("file_id")?,
+                    Playback {
+                        position: r.get("position")?,
+                        duration: r.get("duration")?,
+                        watched: r.get("watched")?,
+                        updated_at: r.get("updated_at")?,
+                    },
+                ))
+            })?
+            .collect::<rusqlite::Result<_>>()
+            .context("failed to read playback state")?;
+        Ok(rows)
+    }
+
+    /// Record where playback of `file_id` stopped. A video already watched
+    /// stays watched when it's played again and left early.
+    pub fn set_playback(&self, file_id: &str, playback: &Playback) -> Result<()> {
+        let store = self.store()?;
+        store
+            .conn()
+            .execute(
+                "INSERT INTO playback (file_id, position, duration, watched, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (file_id) DO UPDATE SET position = ?2, duration = ?3,
+                     watched = watched OR ?4, updated_at = ?5",
+                params![
+                    file_id,
+                    playback.position,
+                    playback.duration,
+                    playback.watched,
+                    now_unix()
+                ],
+            )
+            .context("failed to save playback state")?;
+        Ok(())
+    }
+
+    /// Mark `file_ids` watched, or clear everything recorded about them
+    /// (resume position included) with `watched` false.
+    pub fn set_watched(&self, file_ids: &[String], watched: bool) -> Result<()> {
+        let mut store = self.store()?;
+        let tx = store.transaction()?;
+        for id in file_ids {
+            if watched {
+                tx.execute(
+                    "INSERT INTO playback (file_id, watched, updated_at) VALUES (?1, 1, ?2)
+                     ON CONFLICT (file_id) DO UPDATE SET watched = 1, updated_at = ?2",
+                    params![id, now_unix()],
+                )
+            } else {
+                tx.execute("DELETE FROM playback WHERE file_id = ?1", [id])
+            }
+            .context("failed to save watched state")?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn near_the_end_counts_as_watched() {
+        let half = Playback::at(1800.0, 3600.0);
+        assert!(!half.watched);
+        assert_eq!(half.progress(), Some(0.5));
+        assert_eq!(half.resume_at(), Some(1800.0));
+
+        let credits = Playback::at(3300.0, 3600.0);
+        assert!(credits.watched);
+        assert_eq!(credits.progress(), None);
+        assert_eq!(credits.resume_at(), None);
+
+        // Another player: started, but nothing to resume from.
+        let unknown = Playback::at(0.0, 0.0);
+        assert!(!unknown.watched);
+        assert_eq!(unknown.progress(), None);
+        assert_eq!(Playback::at(4.0, 3600.0).resume_at(), None);
+    }
+}
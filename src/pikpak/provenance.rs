@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use super::{PikPak, now_unix};
+use crate::store::{OptionalExtension, Store, params};
+use rusqlite::Connection;
+
+/// Where an uploaded file came from. Kept in the local database only, keyed
+/// by remote file id so the record survives renames and moves on the drive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Provenance {
+    pub source_path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mtime: Option<i64>,
+    pub hash: String,
+    pub size: u64,
+    pub uploaded_at: i64,
+}
+
+impl Provenance {
+    pub(super) fn from_local(path: &Path, meta: &fs::Metadata, hash: &str) -> Self {
+        let source_path = fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .display()
+            .to_string();
+        let mtime = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+        Self {
+            source_path,
+            mtime,
+            hash: hash.to_string(),
+            size: meta.len(),
+            uploaded_at: now_unix(),
+        }
+    }
+}
+
+impl PikPak {
+    /// `provenance.json`, which older versions kept next to the session
+    /// file, moves into the database the first time it's needed.
+    fn provenance_store(&self) -> Result<Store> {
+        let mut store = self.store()?;
+        let legacy = self.session_path.with_file_name("provenance.json");
+        store.import_legacy(&legacy, |tx, raw| {
+            let manifest: BTreeMap<String, Provenance> = serde_json::from_str(raw)?;
+            for (file_id, record) in &manifest {
+                insert(tx, file_id, record)?;
+            }
+            Ok(())
+        })?;
+        Ok(store)
+    }
+
+    /// Look up the recorded provenance of a remote file, if it was uploaded
+    /// with provenance recording enabled.
+    pub fn provenance(&self, file_id: &str) -> Option<Provenance> {
+        let store = self.provenance_store().ok()?;
+        store
+            .conn()
+            .query_row(
+                "SELECT source_path, mtime, hash, size, uploaded_at FROM provenance
+                 WHERE file_id = ?1",
+                [file_id],
+                |r| {
+                    Ok(Provenance {
+                        source_path: r.get(0)?,
+                        mtime: r.get(1)?,
+                        hash: r.get(2)?,
+                        size: r.get(3)?,
+                        uploaded_at: r.get(4)?,
+                    })
+                },
+            )
+            .optional()
+            .ok()?
+    }
+
+    pub(super) fn record_provenance(&self, file_id: &str, record: Provenance) -> Result<()> {
+        let store = self.provenance_store()?;
+        insert(store.conn(), file_id, &record).context("failed to record provenance")?;
+        Ok(())
+    }
+}
+
+fn insert(conn: &Connection, file_id: &str, record: &Provenance) -> rusqlite::Result<usize> {
+    conn.execute(
+        "INSERT OR REPLACE INTO provenance
+         (file_id, source_path, mtime, hash, size, uploaded_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            file_id,
+            record.source_path,
+            record.mtime,
+            record.hash,
+            record.size,
+            record.uploaded_at
+        ],
+    )
+}
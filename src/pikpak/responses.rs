@@ -68,6 +68,9 @@ pub struct QuotaDetail {
     pub usage: Option<String>,
     #[serde(default)]
     pub usage_in_trash: Option<String>,
+    /// Set on plans without a storage cap, where `limit` means nothing.
+    #[serde(default)]
+    pub is_unlimited: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -123,12 +126,16 @@ pub struct OfflineTask {
 pub struct OfflineListResponse {
     #[serde(default)]
     pub tasks: Vec<OfflineTask>,
+    #[serde(default)]
+    pub next_page_token: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct EventsResponse {
     #[serde(default)]
     pub events: Vec<EventEntry>,
+    #[serde(default)]
+    pub next_page_token: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -138,6 +145,8 @@ pub struct EventEntry {
     #[serde(default)]
     pub type_name: Option<String>,
     #[serde(default)]
+    pub file_id: Option<String>,
+    #[serde(default)]
     pub file_name: Option<String>,
     #[serde(default)]
     pub created_time: Option<String>,
@@ -182,7 +191,8 @@ mod tests {
             "quota": {
                 "limit": "10737418240",
                 "usage": "2147483648",
-                "usage_in_trash": "1024"
+                "usage_in_trash": "1024",
+                "is_unlimited": false
             }
         }"#;
         let resp: QuotaInfo = serde_json::from_str(json).unwrap();
@@ -190,6 +200,7 @@ mod tests {
         assert_eq!(detail.limit.as_deref(), Some("10737418240"));
         assert_eq!(detail.usage.as_deref(), Some("2147483648"));
         assert_eq!(detail.usage_in_trash.as_deref(), Some("1024"));
+        assert!(!detail.is_unlimited);
     }
 
     #[test]
@@ -198,6 +209,26 @@ mod tests {
         assert!(resp.quota.is_none());
     }
 
+    #[test]
+    fn vip_info_parses_plan_and_expiry() {
+        let json = r#"{
+            "result": "ACCEPTED",
+            "data": {
+                "type": "platinum",
+                "status": "ok",
+                "expire": "2026-12-01T10:00:00+08:00",
+                "user_id": "U1"
+            }
+        }"#;
+        let data = serde_json::from_str::<VipInfoResponse>(json)
+            .unwrap()
+            .data
+            .expect("data present");
+        assert_eq!(data.vip_type.as_deref(), Some("platinum"));
+        assert_eq!(data.status.as_deref(), Some("ok"));
+        assert_eq!(data.expire.as_deref(), Some("2026-12-01T10:00:00+08:00"));
+    }
+
     #[test]
     fn offline_list_parses_tasks() {
         let json = r#"{
@@ -0,0 +1,233 @@
+//! Retrying API calls that failed for a reason that may pass by itself: a
+//! 429, a 5xx, or a connection that couldn't be made, timed out or was
+//! reset. Each retry waits longer than the last, with some jitter so
+//! parallel workers don't come back in lockstep, and leaves a line for the
+//! TUI's log. A request that changes something (a POST) is only sent again
+//! when it can't have been acted on: the connection was never made, or the
+//! answer was 429 or 503.
+
+use std::hash::{BuildHasher, RandomState};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::StatusCode;
+use reqwest::blocking::{RequestBuilder, Response};
+
+use super::{PikPak, abort};
+
+pub const DEFAULT_API_RETRIES: u32 = 3;
+pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 500;
+
+/// The longest wait before a retry, `Retry-After` included.
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Retry notes kept until the TUI collects them.
+const KEEP_NOTES: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct RetryPolicy {
+    /// Attempts after the first; 0 turns retrying off.
+    pub(super) retries: u32,
+    /// Wait before the first retry; doubled for each one after.
+    pub(super) backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: DEFAULT_API_RETRIES,
+            backoff: Duration::from_millis(DEFAULT_RETRY_BACKOFF_MS),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The wait before retry `n` (1 for the first): the backoff doubled
+    /// `n - 1` times, then between half and all of that, by `jitter` in
+    /// `0.0..1.0`.
+    fn delay(&self, n: u32, jitter: f64) -> Duration {
+        let full = self
+            .backoff
+            .saturating_mul(1 << (n - 1).min(16))
+            .min(MAX_DELAY);
+        full.mul_f64(0.5 + jitter / 2.0)
+    }
+}
+
+/// Whether a response with `status` may succeed if sent again.
+fn transient_status(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS
+        || (status.is_server_error() && status != StatusCode::NOT_IMPLEMENTED)
+}
+
+/// Whether to send a request answered with `status` again: any transient
+/// answer for an `idempotent` one, otherwise only those saying it wasn't
+/// taken on.
+fn resend_after_status(status: StatusCode, idempotent: bool) -> bool {
+    transient_status(status)
+        && (idempotent
+            || status == StatusCode::TOO_MANY_REQUESTS
+            || status == StatusCode::SERVICE_UNAVAILABLE)
+}
+
+/// Whether a request that failed with `err` may succeed if sent again.
+fn transient_error(err: &reqwest::Error) -> bool {
+    if err.is_connect() || err.is_timeout() {
+        return true;
+    }
+    let mut source = std::error::Error::source(err);
+    while let Some(cause) = source {
+        if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+            use std::io::ErrorKind::*;
+            return matches!(
+                io.kind(),
+                ConnectionReset | ConnectionAborted | BrokenPipe | UnexpectedEof
+            );
+        }
+        source = cause.source();
+    }
+    false
+}
+
+/// The seconds of a `Retry-After` header, if it gives any.
+fn retry_after(response: &Response) -> Option<Duration> {
+    let secs = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+fn jitter() -> f64 {
+    (RandomState::new().hash_one(std::time::Instant::now()) % 1000) as f64 / 1000.0
+}
+
+impl PikPak {
+    /// Send the request `build` returns, sending it again under the retry
+    /// policy while it fails transiently. The last response or error is
+    /// returned either way, so callers handle a final 503 like any other.
+    pub(super) fn send_retrying(
+        &self,
+        op: &str,
+        build: impl Fn() -> RequestBuilder,
+    ) -> Result<Response> {
+        let mut n = 0;
+        loop {
+            if abort::requested() {
+                return Err(abort::Aborted).with_context(|| format!("{op} request failed"));
+            }
+            self.api_limiter.acquire();
+            let (client, request) = build().build_split();
+            let request = request.with_context(|| format!("{op} request failed"))?;
+            let idempotent = request.method().is_idempotent();
+            let result = match abort::current().filter(|_| abort::can_abort(&request)) {
+                Some(flag) => match abort::execute(&self.abortable_http, &request, None, &flag) {
+                    Some(result) => result,
+                    None => {
+                        return Err(abort::Aborted).with_context(|| format!("{op} request failed"));
+                    }
+                },
+                None => client.execute(request),
+            };
+            let (reason, wait) = match &result {
+                Ok(r) if resend_after_status(r.status(), idempotent) => {
+                    (r.status().to_string(), retry_after(r))
+                }
+                Err(e) if transient_error(e) && (idempotent || e.is_connect()) => {
+                    (short_reason(e).to_string(), None)
+                }
+                _ => return result.with_context(|| format!("{op} request failed")),
+            };
+            if n >= self.retry.retries {
+                return result.with_context(|| format!("{op} request failed"));
+            }
+            n += 1;
+            let delay = self
+                .retry
+                .delay(n, jitter())
+                .max(wait.unwrap_or_default().min(MAX_DELAY));
+            self.api_retries.fetch_add(1, Ordering::Relaxed);
+            self.note_retry(format!(
+                "{op}: {reason}, retry {n}/{} in {:.1}s",
+                self.retry.retries,
+                delay.as_secs_f64()
+            ));
+            drop(result);
+            std::thread::sleep(delay);
+        }
+    }
+
+    fn note_retry(&self, note: String) {
+        let mut notes = self.retry_notes.lock().unwrap_or_else(|e| e.into_inner());
+        if notes.len() >= KEEP_NOTES {
+            notes.pop_front();
+        }
+        notes.push_back(note);
+    }
+
+    /// The retries made since the last call, one line each, for the log.
+    pub fn take_retry_notes(&self) -> Vec<String> {
+        let mut notes = self.retry_notes.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::take(&mut *notes).into()
+    }
+
+    /// How many API calls have been sent again after a transient failure.
+    pub fn api_retries(&self) -> u64 {
+        self.api_retries.load(Ordering::Relaxed)
+    }
+}
+
+fn short_reason(err: &reqwest::Error) -> &'static str {
+    if err.is_timeout() {
+        "timed out"
+    } else if err.is_connect() {
+        "couldn't connect"
+    } else {
+        "connection reset"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_with_jitter_and_is_capped() {
+        let policy = RetryPolicy {
+            retries: 5,
+            backoff: Duration::from_millis(500),
+        };
+        assert_eq!(policy.delay(1, 1.0), Duration::from_millis(500));
+        assert_eq!(policy.delay(1, 0.0), Duration::from_millis(250));
+        assert_eq!(policy.delay(3, 1.0), Duration::from_secs(2));
+        assert_eq!(policy.delay(40, 1.0), MAX_DELAY);
+    }
+
+    #[test]
+    fn only_throttling_and_server_errors_are_retried() {
+        assert!(transient_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(transient_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(transient_status(StatusCode::BAD_GATEWAY));
+        assert!(!transient_status(StatusCode::NOT_IMPLEMENTED));
+        assert!(!transient_status(StatusCode::NOT_FOUND));
+        assert!(!transient_status(StatusCode::UNAUTHORIZED));
+    }
+
+    #[test]
+    fn posts_are_resent_only_when_not_taken_on() {
+        assert!(resend_after_status(StatusCode::BAD_GATEWAY, true));
+        assert!(!resend_after_status(StatusCode::BAD_GATEWAY, false));
+        assert!(!resend_after_status(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            false
+        ));
+        assert!(resend_after_status(StatusCode::SERVICE_UNAVAILABLE, false));
+        assert!(resend_after_status(StatusCode::TOO_MANY_REQUESTS, false));
+        assert!(!resend_after_status(StatusCode::NOT_FOUND, true));
+    }
+}
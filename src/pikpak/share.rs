@@ -2,30 +2,24 @@ use anyhow::{Context, Result, anyhow};
 
 use super::{
     CreateShareResponse, MyShare, PikPak, ShareInfoResponse, ShareListResponse, ensure_success,
-    json_or_api_error, sanitize,
+    json_or_api_error,
 };
 
 impl PikPak {
     pub fn share_info(&self, share_id: &str, pass_code: &str) -> Result<ShareInfoResponse> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/share");
 
-        let mut rb = self.http.get(&url).bearer_auth(&token).query(&[
-            ("share_id", share_id),
-            ("pass_code", pass_code),
-            ("thumbnail_size", "SIZE_MEDIUM"),
-        ]);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("share info request failed")?;
+        let response = self.send_authed("share info", || {
+            self.http.get(&url).query(&[
+                ("share_id", share_id),
+                ("pass_code", pass_code),
+                ("thumbnail_size", "SIZE_MEDIUM"),
+            ])
+        })?;
         let status = response.status();
         if !status.is_success() {
             let body = response.text().unwrap_or_default();
-            return Err(anyhow!(
-                "share info failed ({}): {}",
-                status,
-                sanitize(&body)
-            ));
+            return Err(super::failure::api_error("share info", status, &body));
         }
 
         let info: ShareInfoResponse = response.json().context("invalid share info json")?;
@@ -45,7 +39,6 @@ impl PikPak {
         file_ids: &[&str],
         to_parent_id: &str,
     ) -> Result<()> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/share/restore");
 
         let payload = serde_json::json!({
@@ -55,10 +48,7 @@ impl PikPak {
             "to": { "parent_id": to_parent_id },
         });
 
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("save share request failed")?;
+        let response = self.send_authed("save share", || self.http.post(&url).json(&payload))?;
         let status = response.status();
         if !status.is_success() {
             let body = response.text().unwrap_or_default();
@@ -67,11 +57,7 @@ impl PikPak {
                     "cannot save: these files already belong to your account"
                 ));
             }
-            return Err(anyhow!(
-                "save share failed ({}): {}",
-                status,
-                sanitize(&body)
-            ));
+            return Err(super::failure::api_error("save share", status, &body));
         }
         self.clear_ls_cache();
         Ok(())
@@ -83,7 +69,6 @@ impl PikPak {
         need_password: bool,
         expiration_days: i64,
     ) -> Result<CreateShareResponse> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/share");
 
         let payload = serde_json::json!({
@@ -93,39 +78,28 @@ impl PikPak {
             "pass_code_option": if need_password { "REQUIRED" } else { "NOT_REQUIRED" },
         });
 
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("create share request failed")?;
+        let response = self.send_authed("create share", || self.http.post(&url).json(&payload))?;
         json_or_api_error(response, "create share")
     }
 
     pub fn list_shares(&self) -> Result<Vec<MyShare>> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/share/list");
 
-        let mut rb = self
-            .http
-            .get(&url)
-            .bearer_auth(&token)
-            .query(&[("limit", "100"), ("thumbnail_size", "SIZE_SMALL")]);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("list shares request failed")?;
+        let response = self.send_authed("list shares", || {
+            self.http
+                .get(&url)
+                .query(&[("limit", "100"), ("thumbnail_size", "SIZE_SMALL")])
+        })?;
         let resp: ShareListResponse = json_or_api_error(response, "list shares")?;
         Ok(resp.data)
     }
 
     pub fn delete_shares(&self, share_ids: &[&str]) -> Result<()> {
-        let token = self.access_token()?;
         let url = self.drive_url("drive/v1/share:batchDelete");
 
         let payload = serde_json::json!({ "ids": share_ids });
 
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-
-        let response = rb.send().context("delete shares request failed")?;
+        let response = self.send_authed("delete shares", || self.http.post(&url).json(&payload))?;
         ensure_success(response, "delete shares")
     }
 }
@@ -0,0 +1,382 @@
+//! A local HTTP endpoint for video playback. PikPak's download links expire,
+//! so a long film (or one paused for an hour) ends in a 403 halfway through.
+//! The player is given `http://127.0.0.1:<port>/<name>` instead; each request
+//! is forwarded upstream with its `Range` header, and on a 403 the proxy
+//! asks for a fresh link and tries again. Seeking is just another ranged
+//! request. The proxy runs until the player exits (its [`StreamProxy`] is
+//! dropped) and the last connection to it has gone quiet.
+
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::PikPak;
+
+/// Once the player has exited, the proxy stops after no connection has been
+/// open for this long. The grace covers a launcher that hands the link to
+/// a player and exits before the player has connected.
+const IDLE_SHUTDOWN: Duration = Duration::from_secs(60);
+
+/// Times one response may be picked up again after the upstream side
+/// breaks off.
+const MAX_RESUMES: u32 = 5;
+
+/// Response headers passed on to the player.
+const FORWARDED: &[&str] = &[
+    "content-type",
+    "content-length",
+    "content-range",
+    "accept-ranges",
+    "last-modified",
+    "etag",
+];
+
+type Refresh = Box<dyn Fn() -> Result<String> + Send + Sync>;
+
+/// A running proxy. Keep it for as long as the player runs; dropping it
+/// lets the proxy stop.
+pub struct StreamProxy {
+    /// The address to give the player.
+    pub url: String,
+    released: Arc<AtomicBool>,
+}
+
+impl Drop for StreamProxy {
+    fn drop(&mut self) {
+        self.released.store(true, Ordering::Relaxed);
+    }
+}
+
+struct Upstream {
+    http: reqwest::blocking::Client,
+    url: Mutex<String>,
+    refresh: Refresh,
+}
+
+impl Upstream {
+    fn get(&self, range: Option<&str>) -> Result<reqwest::blocking::Response> {
+        let response = self.send(range)?;
+        if !matches!(response.status().as_u16(), 401 | 403 | 410) {
+            return Ok(response);
+        }
+        let fresh = (self.refresh)().context("can't refresh the stream link")?;
+        *self.url.lock().unwrap_or_else(|e| e.into_inner()) = fresh;
+        self.send(range)
+    }
+
+    fn send(&self, range: Option<&str>) -> Result<reqwest::blocking::Response> {
+        let url = self.url.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut request = self.http.get(&url);
+        if let Some(range) = range {
+            request = request.header("Range", range);
+        }
+        request.send().context("stream request failed")
+    }
+}
+
+impl PikPak {
+    /// A fresh link for a file's original (`media: None`) or one of its
+    /// transcoded streams, by media name.
+    pub fn stream_url(&self, file_id: &str, media: Option<&str>) -> Result<String> {
+        let info = self.file_info(file_id)?;
+        let url = match media {
+            None => info.download_url().map(str::to_string),
+            Some(name) => info.medias.as_ref().and_then(|medias| {
+                medias
+                    .iter()
+                    .find(|m| m.media_name.as_deref() == Some(name))
+                    .and_then(|m| m.link.as_ref()?.url.clone())
+            }),
+        };
+        url.filter(|u| !u.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("no stream link for file {file_id}"))
+    }
+
+    /// Serve `url` (the link for `file_id` / `media`, as from `stream_url`)
+    /// on a local port.
+    pub fn proxy_stream(
+        self: &Arc<Self>,
+        file_id: &str,
+        media: Option<&str>,
+        name: &str,
+        url: String,
+    ) -> Result<StreamProxy> {
+        let client = Arc::clone(self);
+        let (file_id, media) = (file_id.to_string(), media.map(str::to_string));
+        let refresh = move || client.stream_url(&file_id, media.as_deref());
+        serve(
+            self.transfer_http.clone(),
+            url,
+            Box::new(refresh),
+            name,
+            IDLE_SHUTDOWN,
+        )
+    }
+}
+
+fn serve(
+    http: reqwest::blocking::Client,
+    url: String,
+    refresh: Refresh,
+    name: &str,
+    idle_shutdown: Duration,
+) -> Result<StreamProxy> {
+    let listener =
+        TcpListener::bind("127.0.0.1:0").context("can't open a local port for the stream")?;
+    let addr = listener.local_addr()?;
+    listener.set_nonblocking(true)?;
+    let upstream = Arc::new(Upstream {
+        http,
+        url: Mutex::new(url),
+        refresh,
+    });
+    let released = Arc::new(AtomicBool::new(false));
+    let done = Arc::clone(&released);
+    std::thread::spawn(move || accept_loop(listener, upstream, &done, idle_shutdown));
+    Ok(StreamProxy {
+        url: format!("http://{addr}/{}", path_segment(name)),
+        released,
+    })
+}
+
+fn accept_loop(
+    listener: TcpListener,
+    upstream: Arc<Upstream>,
+    released: &AtomicBool,
+    idle_shutdown: Duration,
+) {
+    let open = Arc::new(AtomicUsize::new(0));
+    let mut idle_since = Instant::now();
+    loop {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                open.fetch_add(1, Ordering::Relaxed);
+                let (open, upstream) = (Arc::clone(&open), Arc::clone(&upstream));
+                std::thread::spawn(move || {
+                    let _ = respond(stream, &upstream);
+                    open.fetch_sub(1, Ordering::Relaxed);
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                // While the player runs, a pause of any length is fine.
+                if open.load(Ordering::Relaxed) > 0 || !released.load(Ordering::Relaxed) {
+                    idle_since = Instant::now();
+                } else if idle_since.elapsed() >= idle_shutdown {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(_) => return,
+        }
+    }
+}
+
+fn respond(mut stream: TcpStream, upstream: &Upstream) -> Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(30)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let method = request_line.split_whitespace().next().unwrap_or("");
+    let mut range = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':')
+            && key.eq_ignore_ascii_case("range")
+        {
+            range = Some(value.trim().to_string());
+        }
+    }
+
+    if method != "GET" && method != "HEAD" {
+        stream.write_all(
+            b"HTTP/1.1 405 Method Not Allowed\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        )?;
+        return Ok(());
+    }
+    let mut response = match upstream.get(range.as_deref()) {
+        Ok(r) => r,
+        Err(e) => {
+            let body = format!("{e:#}\n");
+            write!(
+                stream,
+                "HTTP/1.1 502 Bad Gateway\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )?;
+            return Ok(());
+        }
+    };
+
+    let status = response.status();
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or("")
+    );
+    for name in FORWARDED {
+        if let Some(value) = response.headers().get(*name).and_then(|v| v.to_str().ok()) {
+            head.push_str(&format!("{name}: {value}\r\n"));
+        }
+    }
+    head.push_str("Connection: close\r\n\r\n");
+    stream.write_all(head.as_bytes())?;
+    if method != "GET" || !status.is_success() {
+        return Ok(());
+    }
+
+    // Copy until the player hangs up (a seek, or quitting). The upstream
+    // side can break first, on the client's transfer timeout or the link
+    // expiring; then pick up where it stopped with a fresh request.
+    // A range we can't parse (a suffix, several parts) isn't resumed.
+    let (mut pos, end, mut resumes) = match range.as_deref().map(parse_range) {
+        None => (0, None, 0),
+        Some(Some((start, end))) => (start, end, 0),
+        Some(None) => (0, None, MAX_RESUMES),
+    };
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = match response.read(&mut buf) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if resumes >= MAX_RESUMES => return Err(e.into()),
+            Err(_) => {
+                resumes += 1;
+                let rest = match end {
+                    Some(end) => format!("bytes={pos}-{end}"),
+                    None => format!("bytes={pos}-"),
+                };
+                response = upstream.get(Some(&rest))?;
+                if response.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+                    return Ok(());
+                }
+                continue;
+            }
+        };
+        stream.write_all(&buf[..n])?;
+        pos += n as u64;
+    }
+}
+
+/// `bytes=START-` or `bytes=START-END`, the forms players send.
+fn parse_range(range: &str) -> Option<(u64, Option<u64>)> {
+    let (start, end) = range.strip_prefix("bytes=")?.split_once('-')?;
+    let end = match end.trim() {
+        "" => None,
+        end => Some(end.parse().ok()?),
+    };
+    Some((start.trim().parse().ok()?, end))
+}
+
+/// The file name as a URL path segment, which players show as the title.
+fn path_segment(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for b in name.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An upstream that rejects `/old` like an expired link and serves
+    /// `/new` honouring a `bytes=N-` range.
+    fn fake_cdn() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0u8; 2048];
+                let n = stream.read(&mut buf).unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let body = b"0123456789";
+                let reply = if request.starts_with("get /old") {
+                    "HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n".to_string()
+                } else if let Some(start) = request
+                    .split("range: bytes=")
+                    .nth(1)
+                    .and_then(|r| r.split('-').next())
+                    .and_then(|s| s.parse::<usize>().ok())
+                {
+                    let part = String::from_utf8_lossy(&body[start..]).into_owned();
+                    format!(
+                        "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {start}-9/10\r\nContent-Length: {}\r\n\r\n{part}",
+                        part.len()
+                    )
+                } else {
+                    "HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\n0123456789".to_string()
+                };
+                let _ = stream.write_all(reply.as_bytes());
+            }
+        });
+        base
+    }
+
+    #[test]
+    fn ranges_parse_open_and_closed() {
+        assert_eq!(parse_range("bytes=100-"), Some((100, None)));
+        assert_eq!(parse_range("bytes=0-499"), Some((0, Some(499))));
+        assert_eq!(parse_range("bytes=-500"), None);
+        assert_eq!(parse_range("items=1-2"), None);
+    }
+
+    #[test]
+    fn refreshes_an_expired_link_and_forwards_ranges() {
+        let base = fake_cdn();
+        let fresh = format!("{base}/new");
+        let proxy = serve(
+            reqwest::blocking::Client::new(),
+            format!("{base}/old"),
+            Box::new(move || Ok(fresh.clone())),
+            "My Film.mkv",
+            IDLE_SHUTDOWN,
+        )
+        .unwrap();
+        let local = proxy.url.clone();
+        assert!(local.ends_with("/My%20Film.mkv"));
+
+        let http = reqwest::blocking::Client::new();
+        let whole = http.get(&local).send().unwrap();
+        assert_eq!(whole.status().as_u16(), 200);
+        assert_eq!(whole.text().unwrap(), "0123456789");
+
+        let part = http.get(&local).header("Range", "bytes=6-").send().unwrap();
+        assert_eq!(part.status().as_u16(), 206);
+        assert_eq!(part.headers()["content-range"], "bytes 6-9/10");
+        assert_eq!(part.text().unwrap(), "6789");
+    }
+
+    #[test]
+    fn stops_once_released_and_idle() {
+        let base = fake_cdn();
+        let proxy = serve(
+            reqwest::blocking::Client::new(),
+            format!("{base}/new"),
+            Box::new(|| anyhow::bail!("no refresh")),
+            "clip.mp4",
+            Duration::from_millis(200),
+        )
+        .unwrap();
+        let local = proxy.url.clone();
+        let http = reqwest::blocking::Client::new();
+
+        // Idle past the grace, but the player still holds it.
+        std::thread::sleep(Duration::from_millis(500));
+        assert_eq!(http.get(&local).send().unwrap().status().as_u16(), 200);
+
+        drop(proxy);
+        std::thread::sleep(Duration::from_millis(800));
+        assert!(http.get(&local).send().is_err());
+    }
+}
@@ -1,21 +1,77 @@
 use anyhow::{Context, Result, anyhow};
 use base64::Engine as _;
 use hmac::{Hmac, Mac};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sha1::Sha1;
 use std::fmt::Write as _;
 use std::fs;
-use std::io::Read as _;
+use std::io::{Read as _, Seek as _};
 use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use super::history::{self, Direction, TransferRecord};
+use super::provenance::Provenance;
+use super::upload_manifest::{PartialUpload, Source};
 use super::{PikPak, sanitize};
+use crate::ratelimit::Throttled;
+
+/// Size of each part of a multipart upload. A resumed upload relies on it
+/// to find where the parts sent so far end.
+const CHUNK_SIZE: u64 = 10 * 1024 * 1024;
 
 impl PikPak {
     pub fn upload_file(
         &self,
         parent_id: Option<&str>,
         local_path: &Path,
+    ) -> Result<(String, bool)> {
+        self.upload(parent_id, local_path, true, None)
+    }
+
+    /// `upload_file` for a file whose PikPak hash was already computed,
+    /// e.g. by `HashAhead`.
+    pub fn upload_file_hashed(
+        &self,
+        parent_id: Option<&str>,
+        local_path: &Path,
+        hash: String,
+    ) -> Result<(String, bool)> {
+        self.upload(parent_id, local_path, true, Some(hash))
+    }
+
+    /// Create `name` in `parent_id` holding `content`: a small file typed
+    /// in or filled from a template rather than one already on disk. It is
+    /// staged in a temporary folder to be uploaded, and isn't recorded in
+    /// the transfer history or provenance.
+    pub fn create_file(&self, parent_id: Option<&str>, name: &str, content: &[u8]) -> Result<()> {
+        if name.trim().is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            return Err(anyhow!("invalid file name '{name}'"));
+        }
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let staging =
+            std::env::temp_dir().join(format!("pikpaktui-new-{}-{nanos}", std::process::id()));
+        fs::create_dir_all(&staging)
+            .with_context(|| format!("cannot create '{}'", staging.display()))?;
+        let local_path = staging.join(name);
+        let result = fs::write(&local_path, content)
+            .with_context(|| format!("cannot write '{}'", local_path.display()))
+            .and_then(|()| self.upload(parent_id, &local_path, false, None));
+        let _ = fs::remove_dir_all(&staging);
+        result.map(|_| ())
+    }
+
+    /// `record` notes the upload in the transfer history and provenance.
+    /// `hash` is computed here when not given.
+    fn upload(
+        &self,
+        parent_id: Option<&str>,
+        local_path: &Path,
+        record: bool,
+        hash: Option<String>,
     ) -> Result<(String, bool)> {
         let file_name = local_path
             .file_name()
@@ -27,9 +83,93 @@ impl PikPak {
             .with_context(|| format!("cannot stat '{}'", local_path.display()))?;
         let file_size = meta.len();
 
-        let hash = pikpak_hash(local_path)?;
+        let hash = match hash {
+            Some(hash) => hash,
+            None => pikpak_hash(local_path)?,
+        };
+        let started = Instant::now();
+
+        // Carry on with an upload cut off part-way, while OSS still holds
+        // its parts and the saved credentials are good for them.
+        let source = Source::new(local_path, &meta);
+        let parent = parent_id.unwrap_or_default();
+        let resumed = match self.partial_upload(&source, parent) {
+            Some(upload) if self.oss_upload_alive(&upload.oss, &upload.upload_id)? => Some(upload),
+            Some(_) => {
+                self.forget_partial_upload(&source, parent);
+                None
+            }
+            None => None,
+        };
+
+        let mut upload = match resumed {
+            Some(upload) => upload,
+            None => {
+                let init = self.init_upload(parent_id, &file_name, file_size, &hash)?;
+
+                // Instant completion (hash dedup): the server already had
+                // this content, so there's nothing to upload.
+                if init.file.phase.as_deref() == Some("PHASE_TYPE_COMPLETE") {
+                    self.clear_ls_cache();
+                    if !record {
+                        return Ok((file_name, true));
+                    }
+                    self.note_provenance(init.file.id.as_deref(), local_path, &meta, &hash);
+                    note_upload(
+                        init.file.id.as_deref(),
+                        &file_name,
+                        local_path,
+                        file_size,
+                        hash,
+                        Duration::ZERO,
+                    );
+                    return Ok((file_name, true));
+                }
+
+                let resumable = init
+                    .resumable
+                    .ok_or_else(|| anyhow!("no resumable context in upload init response"))?;
+                let oss = OssArgs::from_params(resumable.params)?;
+                let upload_id = self.oss_initiate_multipart(&oss)?;
+                PartialUpload {
+                    source,
+                    parent_id: parent.to_string(),
+                    file_id: init.file.id.unwrap_or_default(),
+                    upload_id,
+                    oss,
+                    etags: Vec::new(),
+                }
+            }
+        };
+
+        self.oss_upload_chunks(&mut upload, local_path, file_size)?;
+        self.oss_complete_multipart(&upload.oss, &upload.upload_id, &upload.etags)?;
+        self.forget_partial_upload(&upload.source, &upload.parent_id);
+
+        self.clear_ls_cache();
+        if !record {
+            return Ok((file_name, false));
+        }
+        let file_id = Some(upload.file_id.as_str()).filter(|id| !id.is_empty());
+        self.note_provenance(file_id, local_path, &meta, &hash);
+        note_upload(
+            file_id,
+            &file_name,
+            local_path,
+            file_size,
+            hash,
+            started.elapsed(),
+        );
+        Ok((file_name, false))
+    }
 
-        let token = self.access_token()?;
+    fn init_upload(
+        &self,
+        parent_id: Option<&str>,
+        file_name: &str,
+        file_size: u64,
+        hash: &str,
+    ) -> Result<UploadInitResponse> {
         let url = self.drive_url("drive/v1/files");
         let mut payload = serde_json::json!({
             "kind": "drive#file",
@@ -43,102 +183,30 @@ impl PikPak {
             payload["parent_id"] = serde_json::json!(pid);
         }
 
-        let mut rb = self.http.post(&url).bearer_auth(&token).json(&payload);
-        rb = self.authed_headers(rb);
-        let response = rb.send().context("upload init request failed")?;
+        let response = self.send_authed("upload init", || self.http.post(&url).json(&payload))?;
         let status = response.status();
         if !status.is_success() {
             let body = response.text().unwrap_or_default();
-            return Err(anyhow!(
-                "upload init failed ({}): {}",
-                status,
-                sanitize(&body)
-            ));
+            return Err(super::failure::api_error("upload init", status, &body));
         }
-
-        let init: UploadInitResponse = response.json().context("invalid upload init json")?;
-
-        // Instant completion (hash dedup): the server already had this content,
-        // so there's nothing to upload.
-        if init.file.phase.as_deref() == Some("PHASE_TYPE_COMPLETE") {
-            self.clear_ls_cache();
-            return Ok((file_name, true));
-        }
-
-        let resumable = init
-            .resumable
-            .ok_or_else(|| anyhow!("no resumable context in upload init response"))?;
-
-        let oss_args = OssArgs {
-            endpoint: resumable
-                .params
-                .endpoint
-                .clone()
-                .filter(|s| !s.is_empty())
-                .ok_or_else(|| anyhow!("upload init response missing OSS endpoint"))?,
-            access_key_id: resumable.params.access_key_id.clone().unwrap_or_default(),
-            access_key_secret: resumable
-                .params
-                .access_key_secret
-                .clone()
-                .unwrap_or_default(),
-            security_token: resumable.params.security_token.clone().unwrap_or_default(),
-            bucket: resumable
-                .params
-                .bucket
-                .clone()
-                .filter(|s| !s.is_empty())
-                .ok_or_else(|| anyhow!("upload init response missing OSS bucket"))?,
-            key: resumable
-                .params
-                .key
-                .clone()
-                .filter(|s| !s.is_empty())
-                .ok_or_else(|| anyhow!("upload init response missing OSS key"))?,
-        };
-
-        let upload_id = self.oss_initiate_multipart(&oss_args)?;
-        let etags = self.oss_upload_chunks(&oss_args, &upload_id, local_path, file_size)?;
-        self.oss_complete_multipart(&oss_args, &upload_id, &etags)?;
-
-        self.clear_ls_cache();
-        Ok((file_name, false))
-    }
-
-    pub fn upload_dir(&self, parent_id: &str, local_dir: &Path) -> Result<(usize, usize)> {
-        let name = local_dir
-            .file_name()
-            .ok_or_else(|| anyhow!("directory has no name"))?
-            .to_string_lossy();
-        let folder = self.mkdir(parent_id, &name)?;
-        self.upload_dir_inner(&folder.id, local_dir)
+        response.json().context("invalid upload init json")
     }
 
-    fn upload_dir_inner(&self, parent_id: &str, local_dir: &Path) -> Result<(usize, usize)> {
-        let mut ok = 0usize;
-        let mut failed = 0usize;
-        let entries = std::fs::read_dir(local_dir)
-            .with_context(|| format!("cannot read dir: {}", local_dir.display()))?;
-        for entry in entries.flatten() {
-            let path = entry.path();
-            if path.is_dir() {
-                let name = path.file_name().unwrap_or_default().to_string_lossy();
-                match self.mkdir(parent_id, &name) {
-                    Ok(sub) => {
-                        let (sub_ok, sub_fail) = self.upload_dir_inner(&sub.id, &path)?;
-                        ok += sub_ok;
-                        failed += sub_fail;
-                    }
-                    Err(_) => failed += 1,
-                }
-            } else if path.is_file() {
-                match self.upload_file(Some(parent_id), &path) {
-                    Ok(_) => ok += 1,
-                    Err(_) => failed += 1,
-                }
-            }
+    /// Best-effort: the upload itself already succeeded, so a manifest write
+    /// failure must not turn it into an error.
+    fn note_provenance(
+        &self,
+        file_id: Option<&str>,
+        local_path: &Path,
+        meta: &fs::Metadata,
+        hash: &str,
+    ) {
+        if !self.record_provenance {
+            return;
+        }
+        if let Some(id) = file_id.filter(|id| !id.is_empty()) {
+            let _ = self.record_provenance(id, Provenance::from_local(local_path, meta, hash));
         }
-        Ok((ok, failed))
     }
 
     fn oss_initiate_multipart(&self, oss: &OssArgs) -> Result<String> {
@@ -183,15 +251,54 @@ impl PikPak {
             .ok_or_else(|| anyhow!("no UploadId in initiate multipart response"))
     }
 
+    /// Whether OSS still holds the multipart upload `upload_id` and takes
+    /// the credentials saved with it; a refusal means starting over.
+    fn oss_upload_alive(&self, oss: &OssArgs, upload_id: &str) -> Result<bool> {
+        let date = httpdate_now();
+        let auth = oss_hmac_auth(
+            "GET",
+            &date,
+            &oss.security_token,
+            &oss.access_key_id,
+            &oss.access_key_secret,
+            &format!("/{}/{}", oss.bucket, oss.key),
+            "",
+            &format!("?uploadId={}", upload_id),
+        );
+
+        let url = format!(
+            "https://{}/{}?uploadId={}",
+            oss.endpoint.trim_end_matches('/'),
+            oss.key,
+            upload_id
+        );
+        let response = self
+            .http
+            .get(&url)
+            .header("Authorization", auth)
+            .header("Date", &date)
+            .header("x-oss-security-token", &oss.security_token)
+            .send()
+            .context("OSS list parts failed")?;
+
+        let status = response.status();
+        if status.is_client_error() {
+            return Ok(false);
+        }
+        if !status.is_success() {
+            return Err(anyhow!("OSS list parts failed ({})", status));
+        }
+        Ok(true)
+    }
+
+    /// Send the parts of `local_path` that `upload` doesn't have yet,
+    /// saving it after each one.
     fn oss_upload_chunks(
         &self,
-        oss: &OssArgs,
-        upload_id: &str,
+        upload: &mut PartialUpload,
         local_path: &Path,
         file_size: u64,
-    ) -> Result<Vec<String>> {
-        const CHUNK_SIZE: u64 = 10 * 1024 * 1024;
-
+    ) -> Result<()> {
         let mut file = fs::File::open(local_path)
             .with_context(|| format!("cannot open '{}'", local_path.display()))?;
 
@@ -200,10 +307,13 @@ impl PikPak {
         } else {
             file_size.div_ceil(CHUNK_SIZE)
         };
+        let sent = upload.etags.len() as u64;
+        file.seek(std::io::SeekFrom::Start(sent * CHUNK_SIZE))
+            .with_context(|| format!("cannot seek in '{}'", local_path.display()))?;
+        self.save_partial_upload(upload);
+        let (oss, upload_id) = (&upload.oss, upload.upload_id.as_str());
 
-        let mut etags = Vec::new();
-
-        for part_num in 1..=num_parts {
+        for part_num in sent + 1..=num_parts {
             let remaining = if file_size == 0 {
                 0
             } else {
@@ -233,13 +343,16 @@ impl PikPak {
                 upload_id
             );
             let response = self
-                .http
+                .transfer_http
                 .put(&url)
                 .header("Authorization", auth)
                 .header("Date", &date)
                 .header("Content-Type", "application/octet-stream")
                 .header("x-oss-security-token", &oss.security_token)
-                .body(buf)
+                .body(reqwest::blocking::Body::sized(
+                    Throttled::new(std::io::Cursor::new(buf), Arc::clone(&self.upload_limiter)),
+                    remaining,
+                ))
                 .send()
                 .with_context(|| format!("OSS upload part {} failed", part_num))?;
 
@@ -261,10 +374,11 @@ impl PikPak {
                 .unwrap_or("")
                 .to_string();
 
-            etags.push(etag);
+            upload.etags.push(etag);
+            self.save_partial_upload(upload);
         }
 
-        Ok(etags)
+        Ok(())
     }
 
     fn oss_complete_multipart(
@@ -325,6 +439,29 @@ impl PikPak {
     }
 }
 
+/// Add a finished upload to the transfer history. Instant uploads pass a
+/// zero `elapsed`, which leaves their speed at 0.
+fn note_upload(
+    file_id: Option<&str>,
+    name: &str,
+    local_path: &Path,
+    size: u64,
+    hash: String,
+    elapsed: Duration,
+) {
+    let mut record = TransferRecord::new(
+        Direction::Upload,
+        file_id.unwrap_or_default(),
+        name,
+        size,
+        local_path,
+        size,
+        elapsed,
+    );
+    record.hash = hash;
+    history::append(&record);
+}
+
 /// Compute the PikPak proprietary file hash for upload deduplication.
 /// Algorithm: chunk the file, SHA1 each chunk, concatenate hex hashes, SHA1 the result.
 /// Chunk sizes follow PikPak's server-side spec (reverse-engineered from the Android client):
@@ -493,6 +630,7 @@ fn extract_xml_tag(xml: &str, tag: &str) -> Option<String> {
     Some(xml[start..end].to_string())
 }
 
+#[derive(Clone, Serialize, Deserialize)]
 pub(super) struct OssArgs {
     pub(super) endpoint: String,
     pub(super) access_key_id: String,
@@ -502,6 +640,28 @@ pub(super) struct OssArgs {
     pub(super) key: String,
 }
 
+impl OssArgs {
+    fn from_params(params: ResumableParams) -> Result<Self> {
+        Ok(Self {
+            endpoint: params
+                .endpoint
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| anyhow!("upload init response missing OSS endpoint"))?,
+            access_key_id: params.access_key_id.unwrap_or_default(),
+            access_key_secret: params.access_key_secret.unwrap_or_default(),
+            security_token: params.security_token.unwrap_or_default(),
+            bucket: params
+                .bucket
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| anyhow!("upload init response missing OSS bucket"))?,
+            key: params
+                .key
+                .filter(|s| !s.is_empty())
+                .ok_or_else(|| anyhow!("upload init response missing OSS key"))?,
+        })
+    }
+}
+
 #[derive(Debug, Deserialize)]
 pub(super) struct UploadInitResponse {
     pub(super) file: UploadFileInfo,
@@ -511,6 +671,8 @@ pub(super) struct UploadInitResponse {
 
 #[derive(Debug, Deserialize)]
 pub(super) struct UploadFileInfo {
+    #[serde(default)]
+    pub(super) id: Option<String>,
     #[serde(default)]
     pub(super) phase: Option<String>,
 }
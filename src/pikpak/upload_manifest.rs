@@ -0,0 +1,258 @@
+//! What lets an upload survive a crash or reboot. A multipart upload
+//! records each part as OSS accepts it, so sending the same file to the
+//! same folder again carries on from the next part instead of the first.
+//! A folder upload lists its files before it starts and ticks them off as
+//! they finish, so running it again skips those already sent. Both are
+//! keyed by the file's canonical path and where it goes, and dropped once
+//! done, or when the file has changed since. The OSS credentials a
+//! multipart upload carries on with go to the keyring when that's in use.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use super::upload::OssArgs;
+use super::{PikPak, now_unix};
+use crate::store::{OptionalExtension, params};
+
+/// A local file as an upload saw it: the same path with another size or
+/// modification time is a different file.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Source {
+    path: String,
+    size: u64,
+    mtime: i64,
+}
+
+impl Source {
+    pub(super) fn new(path: &Path, meta: &fs::Metadata) -> Self {
+        let path = fs::canonicalize(path)
+            .unwrap_or_else(|_| path.to_path_buf())
+            .display()
+            .to_string();
+        let mtime = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs() as i64);
+        Self {
+            path,
+            size: meta.len(),
+            mtime,
+        }
+    }
+
+    fn of(path: &Path) -> Option<Self> {
+        fs::metadata(path).ok().map(|meta| Self::new(path, &meta))
+    }
+}
+
+/// A multipart upload and the parts of it OSS already has.
+pub(super) struct PartialUpload {
+    pub(super) source: Source,
+    /// Folder it goes into; empty for the root.
+    pub(super) parent_id: String,
+    pub(super) file_id: String,
+    pub(super) upload_id: String,
+    pub(super) oss: OssArgs,
+    /// ETags of the parts sent, in order.
+    pub(super) etags: Vec<String>,
+}
+
+/// The parts of [`OssArgs`] that grant access, kept apart from the rest.
+#[derive(Serialize, Deserialize)]
+struct OssSecrets {
+    access_key_secret: String,
+    security_token: String,
+}
+
+/// The keyring entry of an upload's OSS secrets.
+fn secrets_account(upload_id: &str) -> String {
+    format!("upload:{upload_id}")
+}
+
+impl PikPak {
+    /// The unfinished upload of `source` into `parent_id`, if there is one.
+    pub(super) fn partial_upload(&self, source: &Source, parent_id: &str) -> Option<PartialUpload> {
+        let store = self.store().ok()?;
+        let row = store
+            .conn()
+            .query_row(
+                "SELECT file_id, upload_id, oss, etags FROM upload_parts
+                 WHERE source_path = ?1 AND size = ?2 AND mtime = ?3 AND parent_id = ?4",
+                params![source.path, source.size, source.mtime, parent_id],
+                |r| {
+                    Ok((
+                        r.get::<_, String>("file_id")?,
+                        r.get::<_, String>("upload_id")?,
+                        r.get::<_, String>("oss")?,
+                        r.get::<_, String>("etags")?,
+                    ))
+                },
+            )
+            .optional()
+            .ok()??;
+        let (file_id, upload_id, oss, etags) = row;
+        let mut oss: OssArgs = serde_json::from_str(&oss).ok()?;
+        if crate::secrets::use_keyring() {
+            let raw = crate::secrets::get(&secrets_account(&upload_id)).ok()??;
+            let secrets: OssSecrets = serde_json::from_str(&raw).ok()?;
+            oss.access_key_secret = secrets.access_key_secret;
+            oss.security_token = secrets.security_token;
+        }
+        Some(PartialUpload {
+            source: source.clone(),
+            parent_id: parent_id.to_string(),
+            file_id,
+            upload_id,
+            oss,
+            etags: serde_json::from_str(&etags).ok()?,
+        })
+    }
+
+    /// Best-effort, like provenance: failing to save only costs the resume.
+    pub(super) fn save_partial_upload(&self, upload: &PartialUpload) {
+        let oss = if crate::secrets::use_keyring() {
+            let secrets = OssSecrets {
+                access_key_secret: upload.oss.access_key_secret.clone(),
+                security_token: upload.oss.security_token.clone(),
+            };
+            let saved = serde_json::to_string(&secrets).is_ok_and(|raw| {
+                crate::secrets::set(&secrets_account(&upload.upload_id), &raw).is_ok()
+            });
+            if !saved {
+                return;
+            }
+            serde_json::to_string(&OssArgs {
+                access_key_secret: String::new(),
+                security_token: String::new(),
+                ..upload.oss.clone()
+            })
+        } else {
+            serde_json::to_string(&upload.oss)
+        };
+        let (Ok(store), Ok(oss), Ok(etags)) =
+            (self.store(), oss, serde_json::to_string(&upload.etags))
+        else {
+            return;
+        };
+        let _ = store.conn().execute(
+            "INSERT OR REPLACE INTO upload_parts
+                 (source_path, size, mtime, parent_id, file_id, upload_id, oss, etags, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                upload.source.path,
+                upload.source.size,
+                upload.source.mtime,
+                upload.parent_id,
+                upload.file_id,
+                upload.upload_id,
+                oss,
+                etags,
+                now_unix()
+            ],
+        );
+    }
+
+    pub(super) fn forget_partial_upload(&self, source: &Source, parent_id: &str) {
+        let Ok(store) = self.store() else {
+            return;
+        };
+        let upload_id: Option<String> = store
+            .conn()
+            .query_row(
+                "DELETE FROM upload_parts WHERE source_path = ?1 AND parent_id = ?2
+                 RETURNING upload_id",
+                params![source.path, parent_id],
+                |r| r.get(0),
+            )
+            .optional()
+            .ok()
+            .flatten();
+        if let Some(upload_id) = upload_id
+            && crate::secrets::use_keyring()
+        {
+            let _ = crate::secrets::delete(&secrets_account(&upload_id));
+        }
+    }
+
+    /// Which of `files` an earlier, unfinished upload into `dest_id` already
+    /// sent, unchanged since.
+    pub fn sent_before(&self, dest_id: &str, files: &[&Path]) -> Vec<bool> {
+        let Ok(store) = self.store() else {
+            return vec![false; files.len()];
+        };
+        files
+            .iter()
+            .map(|path| {
+                Source::of(path).is_some_and(|s| {
+                    store
+                        .conn()
+                        .query_row(
+                            "SELECT 1 FROM upload_manifest WHERE dest_id = ?1
+                             AND source_path = ?2 AND size = ?3 AND mtime = ?4 AND done",
+                            params![dest_id, s.path, s.size, s.mtime],
+                            |_| Ok(()),
+                        )
+                        .is_ok()
+                })
+            })
+            .collect()
+    }
+
+    /// List the files of a folder upload into `dest_id` before sending
+    /// any, so an interrupted one can be told apart and picked up. Files
+    /// already ticked off stay so unless they've changed.
+    pub fn begin_folder_upload(&self, dest_id: &str, label: &str, files: &[&Path]) -> Result<()> {
+        let mut store = self.store()?;
+        let tx = store.transaction()?;
+        for source in files.iter().filter_map(|p| Source::of(p)) {
+            tx.execute(
+                "INSERT INTO upload_manifest (dest_id, source_path, label, size, mtime)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (dest_id, source_path) DO UPDATE SET label = ?3,
+                     done = done AND size = ?4 AND mtime = ?5, size = ?4, mtime = ?5",
+                params![dest_id, source.path, label, source.size, source.mtime],
+            )
+            .context("failed to save the upload manifest")?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Tick `file` off the folder upload into `dest_id`.
+    pub fn folder_file_sent(&self, dest_id: &str, file: &Path) {
+        let (Ok(store), Some(source)) = (self.store(), Source::of(file)) else {
+            return;
+        };
+        let _ = store.conn().execute(
+            "UPDATE upload_manifest SET done = 1 WHERE dest_id = ?1 AND source_path = ?2",
+            params![dest_id, source.path],
+        );
+    }
+
+    /// Drop the manifest of a folder upload into `dest_id` that finished.
+    pub fn end_folder_upload(&self, dest_id: &str) {
+        if let Ok(store) = self.store() {
+            let _ = store
+                .conn()
+                .execute("DELETE FROM upload_manifest WHERE dest_id = ?1", [dest_id]);
+        }
+    }
+
+    /// Folder uploads that stopped before the end, as their label and how
+    /// many files they still had to send.
+    pub fn interrupted_uploads(&self) -> Result<Vec<(String, usize)>> {
+        let store = self.store()?;
+        let mut stmt = store.conn().prepare(
+            "SELECT label, SUM(NOT done) FROM upload_manifest
+             GROUP BY dest_id, label HAVING SUM(NOT done) > 0 ORDER BY label",
+        )?;
+        let rows = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get::<_, i64>(1)? as usize)))?
+            .collect::<rusqlite::Result<_>>()
+            .context("failed to read the upload manifest")?;
+        Ok(rows)
+    }
+}
@@ -0,0 +1,145 @@
+//! The emailed verification code PikPak asks for on some sign-ins (a new
+//! device, an unusual location). Password login becomes two steps: the
+//! first notices the challenge and has the code sent, the second checks
+//! the code and signs in again with the token it is traded for.
+
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+
+use super::{PikPak, failure};
+
+/// Signin error codes meaning a verification code is needed first.
+const CHALLENGE_MARKERS: &[&str] = &[
+    "verification_required",
+    "verification_code_required",
+    "need_verification",
+    "verify_required",
+];
+
+pub(super) fn is_challenge(body: &str) -> bool {
+    let body = body.to_lowercase();
+    CHALLENGE_MARKERS.iter().any(|m| body.contains(m))
+}
+
+/// How a password login went.
+pub enum LoginStep {
+    Done,
+    /// A code was emailed; pass it to [`PikPak::finish_login`].
+    NeedsCode(PendingLogin),
+}
+
+/// A login waiting on its verification code.
+pub struct PendingLogin {
+    email: String,
+    password: String,
+    verification_id: String,
+}
+
+impl PendingLogin {
+    #[cfg(test)]
+    pub fn for_tests(email: &str) -> Self {
+        Self {
+            email: email.into(),
+            password: "pw".into(),
+            verification_id: "v1".into(),
+        }
+    }
+
+    pub fn email(&self) -> &str {
+        &self.email
+    }
+
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+}
+
+#[derive(Deserialize)]
+struct VerificationResponse {
+    verification_id: String,
+}
+
+#[derive(Deserialize)]
+struct VerifyResponse {
+    verification_token: String,
+}
+
+impl PikPak {
+    /// Sign in with a password. If PikPak wants a verification code, one
+    /// is sent to the account's email and the login waits on it.
+    pub fn start_login(&mut self, email: &str, password: &str) -> Result<LoginStep> {
+        if self.signin(email, password, None)? {
+            return Ok(LoginStep::Done);
+        }
+        let verification_id = self.send_code(email)?;
+        Ok(LoginStep::NeedsCode(PendingLogin {
+            email: email.to_string(),
+            password: password.to_string(),
+            verification_id,
+        }))
+    }
+
+    /// Check the emailed code and finish the login. A wrong code can be
+    /// tried again with the same `pending`.
+    pub fn finish_login(&mut self, pending: &PendingLogin, code: &str) -> Result<()> {
+        let code = code.trim();
+        if code.is_empty() {
+            return Err(anyhow!("verification code is empty"));
+        }
+        let payload = serde_json::json!({
+            "verification_id": pending.verification_id,
+            "verification_code": code,
+            "client_id": self.client_id,
+        });
+        let verified: VerifyResponse = self.verification_post(
+            "v1/auth/verification/verify",
+            "verification code check",
+            &payload,
+        )?;
+        self.signin(
+            &pending.email,
+            &pending.password,
+            Some((&verified.verification_token, code)),
+        )?;
+        Ok(())
+    }
+
+    /// Have a code emailed to `email`; returns the id to check it against.
+    fn send_code(&self, email: &str) -> Result<String> {
+        let payload = serde_json::json!({
+            "email": email,
+            "target": "ANY",
+            "usage": "SIGN_IN",
+            "client_id": self.client_id,
+        });
+        let sent: VerificationResponse = self.verification_post(
+            "v1/auth/verification",
+            "sending verification code",
+            &payload,
+        )?;
+        Ok(sent.verification_id)
+    }
+
+    fn verification_post<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        op: &str,
+        payload: &serde_json::Value,
+    ) -> Result<T> {
+        let response = self.send_retrying(op, || {
+            self.http
+                .post(self.auth_url(path))
+                .header("x-device-id", &self.device_id)
+                .header("x-captcha-token", &self.captcha_token)
+                .json(payload)
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().unwrap_or_default();
+            return Err(failure::api_error(op, status, &body));
+        }
+        response
+            .json()
+            .with_context(|| format!("invalid {op} json"))
+    }
+}
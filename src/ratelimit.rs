@@ -0,0 +1,337 @@
+//! Token-bucket bandwidth and request limiting, and speed measurement,
+//! shared by the CLI and TUI transfers and API calls.
+
+use std::io::{self, Read};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Speeds offered when stepping a limit up or down, in bytes per second.
+/// 0 means unlimited.
+pub const SPEED_STEPS: &[u64] = &[
+    0,
+    256 << 10,
+    512 << 10,
+    1 << 20,
+    2 << 20,
+    5 << 20,
+    10 << 20,
+    20 << 20,
+    50 << 20,
+    100 << 20,
+];
+
+/// The next speed step above (`up`) or below `current`. Going down from the
+/// lowest limit wraps to unlimited, and up from unlimited starts at the lowest.
+pub fn step_speed(current: u64, up: bool) -> u64 {
+    let limited = &SPEED_STEPS[1..];
+    if up {
+        if current == 0 {
+            return limited[0];
+        }
+        limited
+            .iter()
+            .copied()
+            .find(|&s| s > current)
+            .unwrap_or(current)
+    } else {
+        limited
+            .iter()
+            .rev()
+            .copied()
+            .find(|&s| s < current)
+            .unwrap_or(0)
+    }
+}
+
+/// Shared limit for one direction of traffic. The rate can be changed while
+/// transfers are running; 0 turns limiting off.
+#[derive(Debug)]
+pub struct RateLimiter {
+    rate: AtomicU64,
+    bucket: Mutex<Bucket>,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    /// Spend `n` tokens at `rate` per second and return how long to wait
+    /// before using them. The bucket holds at most `capacity`, and can go
+    /// into debt so a chunk larger than that still gets through.
+    fn take(&mut self, n: u64, rate: u64, capacity: u64, now: Instant) -> Duration {
+        let rate = rate as f64;
+        let refill = now.saturating_duration_since(self.last).as_secs_f64() * rate;
+        self.tokens = (self.tokens + refill).min(capacity as f64);
+        self.last = now;
+        self.tokens -= n as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn new(rate: u64) -> Self {
+        Self {
+            rate: AtomicU64::new(rate),
+            bucket: Mutex::new(Bucket {
+                tokens: rate as f64,
+                last: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn rate(&self) -> u64 {
+        self.rate.load(Ordering::Relaxed)
+    }
+
+    pub fn set_rate(&self, rate: u64) {
+        self.rate.store(rate, Ordering::Relaxed);
+    }
+
+    /// Block until `n` more bytes fit under the limit.
+    pub fn acquire(&self, n: usize) {
+        let rate = self.rate();
+        if rate == 0 || n == 0 {
+            return;
+        }
+        let wait = match self.bucket.lock() {
+            // At most one second of traffic goes out in a burst.
+            Ok(mut bucket) => bucket.take(n as u64, rate, rate, Instant::now()),
+            Err(_) => return,
+        };
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Shared limit on API requests: `rate` a second on average, with up to
+/// `burst` sent at once after a quiet spell. Both can be changed while
+/// requests are waiting; a rate of 0 turns limiting off.
+#[derive(Debug)]
+pub struct RequestLimiter {
+    rate: AtomicU64,
+    burst: AtomicU64,
+    bucket: Mutex<Bucket>,
+    /// Requests blocked in `acquire` right now.
+    waiting: AtomicUsize,
+}
+
+impl RequestLimiter {
+    pub fn new(rate: u64, burst: u64) -> Self {
+        Self {
+            rate: AtomicU64::new(rate),
+            burst: AtomicU64::new(burst),
+            bucket: Mutex::new(Bucket {
+                tokens: burst as f64,
+                last: Instant::now(),
+            }),
+            waiting: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn rate(&self) -> u64 {
+        self.rate.load(Ordering::Relaxed)
+    }
+
+    pub fn set_limit(&self, rate: u64, burst: u64) {
+        self.rate.store(rate, Ordering::Relaxed);
+        self.burst.store(burst.max(1), Ordering::Relaxed);
+    }
+
+    /// How many requests are held back by the limit at the moment.
+    pub fn waiting(&self) -> usize {
+        self.waiting.load(Ordering::Relaxed)
+    }
+
+    /// Block until one more request fits under the limit.
+    pub fn acquire(&self) {
+        let rate = self.rate();
+        if rate == 0 {
+            return;
+        }
+        let burst = self.burst.load(Ordering::Relaxed);
+        let wait = match self.bucket.lock() {
+            Ok(mut bucket) => bucket.take(1, rate, burst, Instant::now()),
+            Err(_) => return,
+        };
+        if !wait.is_zero() {
+            self.waiting.fetch_add(1, Ordering::Relaxed);
+            std::thread::sleep(wait);
+            self.waiting.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Default for RequestLimiter {
+    fn default() -> Self {
+        Self::new(0, 1)
+    }
+}
+
+/// A reader that paces itself through a [`RateLimiter`].
+pub struct Throttled<R> {
+    inner: R,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<R> Throttled<R> {
+    pub fn new(inner: R, limiter: Arc<RateLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+
+impl<R: Read> Read for Throttled<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Small reads keep the pacing smooth at low rates.
+        let len = buf.len().min(64 * 1024);
+        let n = self.inner.read(&mut buf[..len])?;
+        self.limiter.acquire(n);
+        Ok(n)
+    }
+}
+
+/// `1.5 MB/s`-style label for a limit, `Unlimited` for 0.
+pub fn format_rate(rate: u64) -> String {
+    if rate == 0 {
+        "Unlimited".to_string()
+    } else {
+        format!("{}/s", crate::cmd::format_size(rate))
+    }
+}
+
+/// Roughly how many seconds of history [`SpeedMeter`] averages over.
+const SMOOTHING_SECS: f64 = 5.0;
+
+/// Transfer speed as an exponential moving average, so one slow read or a
+/// burst doesn't make the figure, and the ETA derived from it, jump around.
+#[derive(Debug, Default, Clone)]
+pub struct SpeedMeter {
+    speed: Option<f64>,
+}
+
+impl SpeedMeter {
+    /// Record `bytes` moved over `elapsed` and return the smoothed speed in
+    /// bytes per second. The first sample is taken as is.
+    pub fn record(&mut self, bytes: u64, elapsed: Duration) -> f64 {
+        let secs = elapsed.as_secs_f64();
+        if secs <= 0.0 {
+            return self.speed();
+        }
+        let sample = bytes as f64 / secs;
+        // Weight by elapsed time so irregular sampling averages the same.
+        let speed = match self.speed {
+            None => sample,
+            Some(prev) => prev + (1.0 - (-secs / SMOOTHING_SECS).exp()) * (sample - prev),
+        };
+        self.speed = Some(speed);
+        speed
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed.unwrap_or(0.0)
+    }
+}
+
+/// Seconds until `remaining` bytes are done at `speed` bytes per second;
+/// `None` while stalled.
+pub fn eta_secs(remaining: u64, speed: f64) -> Option<u64> {
+    (speed >= 1.0).then(|| (remaining as f64 / speed).ceil() as u64)
+}
+
+/// `1h 2m 3s`-style label for a duration in seconds.
+pub fn format_duration(secs: u64) -> String {
+    let hours = secs / 3600;
+    let mins = (secs % 3600) / 60;
+    let secs = secs % 60;
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, mins, secs)
+    } else if mins > 0 {
+        format!("{}m {}s", mins, secs)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_waits_once_the_burst_is_spent() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 1000.0,
+            last: start,
+        };
+        assert_eq!(bucket.take(1000, 1000, 1000, start), Duration::ZERO);
+        assert_eq!(
+            bucket.take(500, 1000, 1000, start),
+            Duration::from_millis(500)
+        );
+        // Half a second later the debt is paid off and nothing more is owed.
+        let later = start + Duration::from_millis(500);
+        assert_eq!(bucket.take(0, 1000, 1000, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn requests_queue_up_behind_the_burst() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 3.0,
+            last: start,
+        };
+        // Three go at once, then one every 1/5 s, each behind the last.
+        for _ in 0..3 {
+            assert_eq!(bucket.take(1, 5, 3, start), Duration::ZERO);
+        }
+        assert_eq!(bucket.take(1, 5, 3, start), Duration::from_millis(200));
+        assert_eq!(bucket.take(1, 5, 3, start), Duration::from_millis(400));
+        // A long pause refills only up to the burst.
+        let later = start + Duration::from_secs(60);
+        for _ in 0..3 {
+            assert_eq!(bucket.take(1, 5, 3, later), Duration::ZERO);
+        }
+        assert!(!bucket.take(1, 5, 3, later).is_zero());
+    }
+
+    #[test]
+    fn steps_walk_the_ladder_and_wrap_to_unlimited() {
+        assert_eq!(step_speed(0, true), 256 << 10);
+        assert_eq!(step_speed(256 << 10, false), 0);
+        assert_eq!(step_speed(3 << 20, true), 5 << 20);
+        assert_eq!(step_speed(3 << 20, false), 2 << 20);
+        assert_eq!(step_speed(100 << 20, true), 100 << 20);
+    }
+
+    #[test]
+    fn speed_meter_smooths_out_a_stall() {
+        let half = Duration::from_millis(500);
+        let mut meter = SpeedMeter::default();
+        assert_eq!(meter.record(500, half), 1000.0);
+        // One empty interval only pulls the average down a little.
+        let after_stall = meter.record(0, half);
+        assert!(after_stall > 900.0 && after_stall < 1000.0, "{after_stall}");
+        // A sustained change is followed within a few time constants.
+        for _ in 0..60 {
+            meter.record(1000, half);
+        }
+        assert!((meter.speed() - 2000.0).abs() < 10.0);
+        assert_eq!(eta_secs(5000, meter.speed()), Some(3));
+        assert_eq!(eta_secs(5000, 0.0), None);
+        assert_eq!(format_duration(3725), "1h 2m 5s");
+    }
+}
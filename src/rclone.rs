@@ -0,0 +1,279 @@
+//! Reading and updating rclone's INI-style config so a PikPak remote's login
+//! can be shared with pikpaktui.
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::PathBuf;
+
+use crate::pikpak::{self, SessionToken};
+
+/// One `[name]` section of rclone.conf, keys in file order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remote {
+    pub name: String,
+    pub options: Vec<(String, String)>,
+}
+
+impl Remote {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn is_pikpak(&self) -> bool {
+        self.get("type") == Some("pikpak")
+    }
+}
+
+/// The oauth2 token rclone stores as JSON in a remote's `token` key.
+#[derive(Debug, Serialize, Deserialize)]
+struct RcloneToken {
+    access_token: String,
+    #[serde(default = "default_token_type")]
+    token_type: String,
+    refresh_token: String,
+    #[serde(default)]
+    expiry: String,
+}
+
+fn default_token_type() -> String {
+    "Bearer".to_string()
+}
+
+/// `$RCLONE_CONFIG`, else rclone's default `~/.config/rclone/rclone.conf`
+/// (rclone uses that path on macOS too, not the platform config dir).
+pub fn default_config_path() -> Result<PathBuf> {
+    if let Ok(path) = env::var("RCLONE_CONFIG")
+        && !path.is_empty()
+    {
+        return Ok(PathBuf::from(path));
+    }
+    let home = dirs::home_dir().context("cannot determine home directory")?;
+    Ok(home.join(".config").join("rclone").join("rclone.conf"))
+}
+
+pub fn parse_config(raw: &str) -> Result<Vec<Remote>> {
+    if raw.trim_start().starts_with("RCLONE_ENCRYPT_V0:") {
+        return Err(anyhow!(
+            "rclone config is encrypted; decrypt it first with `rclone config encryption remove`"
+        ));
+    }
+    let mut remotes: Vec<Remote> = Vec::new();
+    for line in raw.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            remotes.push(Remote {
+                name: name.trim().to_string(),
+                options: Vec::new(),
+            });
+        } else if let Some((key, value)) = line.split_once('=')
+            && let Some(remote) = remotes.last_mut()
+        {
+            remote
+                .options
+                .push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    Ok(remotes)
+}
+
+/// Pick the PikPak remote to use: the one called `name`, or the only one.
+pub fn find_pikpak_remote<'a>(remotes: &'a [Remote], name: Option<&str>) -> Result<&'a Remote> {
+    let pikpak: Vec<&Remote> = remotes.iter().filter(|r| r.is_pikpak()).collect();
+    if let Some(name) = name {
+        let remote = remotes
+            .iter()
+            .find(|r| r.name == name)
+            .ok_or_else(|| anyhow!("no remote named '{name}' in rclone config"))?;
+        if !remote.is_pikpak() {
+            return Err(anyhow!("remote '{name}' is not a pikpak remote"));
+        }
+        return Ok(remote);
+    }
+    match pikpak.as_slice() {
+        [] => Err(anyhow!("no pikpak remote found in rclone config")),
+        [only] => Ok(only),
+        many => {
+            let names: Vec<&str> = many.iter().map(|r| r.name.as_str()).collect();
+            Err(anyhow!(
+                "several pikpak remotes found ({}); pick one with --remote",
+                names.join(", ")
+            ))
+        }
+    }
+}
+
+/// Convert a remote's `token` into a pikpaktui session. An unparseable
+/// expiry is treated as already expired, so the first use refreshes it.
+pub fn session_from_remote(remote: &Remote) -> Result<SessionToken> {
+    let raw = remote
+        .get("token")
+        .filter(|t| !t.is_empty())
+        .ok_or_else(|| {
+            anyhow!(
+                "remote '{}' has no token; run `rclone config reconnect {}:` first",
+                remote.name,
+                remote.name
+            )
+        })?;
+    let token: RcloneToken = serde_json::from_str(raw)
+        .with_context(|| format!("invalid token in remote '{}'", remote.name))?;
+    if token.refresh_token.is_empty() {
+        return Err(anyhow!(
+            "remote '{}' token has no refresh_token",
+            remote.name
+        ));
+    }
+    Ok(SessionToken {
+        access_token: token.access_token,
+        refresh_token: token.refresh_token,
+        expires_at_unix: pikpak::iso_to_unix(&token.expiry).unwrap_or(0),
+        device_id: remote.get("device_id").unwrap_or_default().to_string(),
+        ..SessionToken::default()
+    })
+}
+
+/// The `token` value rclone expects for `session`.
+pub fn token_for_session(session: &SessionToken) -> Result<String> {
+    let token = RcloneToken {
+        access_token: session.access_token.clone(),
+        token_type: default_token_type(),
+        refresh_token: session.refresh_token.clone(),
+        expiry: pikpak::unix_to_iso(session.expires_at_unix),
+    };
+    serde_json::to_string(&token).context("failed to encode rclone token")
+}
+
+/// Set `values` in the `[name]` section of `raw`, leaving every other line —
+/// other remotes, comments, unrelated keys — untouched. Missing keys are
+/// appended to the section; a missing section is appended to the file.
+pub fn upsert_remote(raw: &str, name: &str, values: &[(&str, &str)]) -> String {
+    let mut lines: Vec<String> = raw.lines().map(str::to_string).collect();
+    let header = |line: &str| {
+        line.trim()
+            .strip_prefix('[')
+            .and_then(|l| l.strip_suffix(']'))
+            .map(|n| n.trim().to_string())
+    };
+
+    let Some(start) = lines
+        .iter()
+        .position(|l| header(l).as_deref() == Some(name))
+    else {
+        while lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+        if !lines.is_empty() {
+            lines.push(String::new());
+        }
+        lines.push(format!("[{name}]"));
+        lines.extend(values.iter().map(|(k, v)| format!("{k} = {v}")));
+        return lines.join("\n") + "\n";
+    };
+
+    let end = lines[start + 1..]
+        .iter()
+        .position(|l| header(l).is_some())
+        .map_or(lines.len(), |p| start + 1 + p);
+    let mut missing = Vec::new();
+    for &(key, value) in values {
+        let existing = lines[start + 1..end].iter().position(|l| {
+            l.split_once('=')
+                .is_some_and(|(k, _)| k.trim() == key && !l.trim_start().starts_with(['#', ';']))
+        });
+        match existing {
+            Some(idx) => lines[start + 1 + idx] = format!("{key} = {value}"),
+            None => missing.push(format!("{key} = {value}")),
+        }
+    }
+    // Append after the section's last non-blank line.
+    let mut insert_at = end;
+    while insert_at > start + 1 && lines[insert_at - 1].trim().is_empty() {
+        insert_at -= 1;
+    }
+    lines.splice(insert_at..insert_at, missing);
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONF: &str = r#"
+[gdrive]
+type = drive
+token = {"access_token":"x"}
+
+# migrated from the web UI
+[pp]
+type = pikpak
+user = me@example.com
+pass = c2VjcmV0
+token = {"access_token":"acc","token_type":"Bearer","refresh_token":"ref","expiry":"2026-01-15T12:30:45.123+08:00"}
+"#;
+
+    #[test]
+    fn parses_sections_and_picks_the_pikpak_remote() {
+        let remotes = parse_config(CONF).unwrap();
+        assert_eq!(remotes.len(), 2);
+        let remote = find_pikpak_remote(&remotes, None).unwrap();
+        assert_eq!(remote.name, "pp");
+        assert_eq!(remote.get("user"), Some("me@example.com"));
+        assert!(find_pikpak_remote(&remotes, Some("gdrive")).is_err());
+        assert!(find_pikpak_remote(&remotes, Some("nope")).is_err());
+
+        let session = session_from_remote(remote).unwrap();
+        assert_eq!(session.access_token, "acc");
+        assert_eq!(session.refresh_token, "ref");
+        assert_eq!(session.expires_at_unix, 1_768_451_445);
+    }
+
+    #[test]
+    fn upsert_updates_in_place_and_appends_missing_keys() {
+        let updated = upsert_remote(CONF, "pp", &[("token", "{}"), ("device_id", "abc")]);
+        let remotes = parse_config(&updated).unwrap();
+        assert_eq!(remotes[0].options, parse_config(CONF).unwrap()[0].options);
+        assert_eq!(remotes[1].get("token"), Some("{}"));
+        assert_eq!(remotes[1].get("device_id"), Some("abc"));
+        assert_eq!(remotes[1].get("user"), Some("me@example.com"));
+        assert!(updated.contains("# migrated from the web UI"));
+
+        let added = upsert_remote(CONF, "new", &[("type", "pikpak")]);
+        assert!(added.ends_with("\n\n[new]\ntype = pikpak\n"));
+        assert_eq!(
+            upsert_remote("", "p", &[("type", "pikpak")]),
+            "[p]\ntype = pikpak\n"
+        );
+    }
+
+    #[test]
+    fn exported_token_round_trips() {
+        let session = SessionToken {
+            access_token: "acc".into(),
+            refresh_token: "ref".into(),
+            expires_at_unix: 1_768_451_445,
+            ..SessionToken::default()
+        };
+        let token = token_for_session(&session).unwrap();
+        assert!(token.contains(r#""expiry":"2026-01-15T04:30:45Z""#));
+        let remote = Remote {
+            name: "p".into(),
+            options: vec![("token".into(), token)],
+        };
+        let back = session_from_remote(&remote).unwrap();
+        assert_eq!(back.expires_at_unix, session.expires_at_unix);
+        assert_eq!(back.refresh_token, "ref");
+    }
+
+    #[test]
+    fn rejects_encrypted_configs() {
+        let err = parse_config("RCLONE_ENCRYPT_V0:\nabcdef").unwrap_err();
+        assert!(err.to_string().contains("encrypted"));
+    }
+}
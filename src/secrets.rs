@@ -0,0 +1,105 @@
+//! Where the session and the saved password are kept: plain files in the
+//! config directory (the default), or the OS keyring with
+//! `credential_store = "keyring"` — the Keychain on macOS, the Credential
+//! Manager on Windows, the Secret Service on Linux. Whatever is still in
+//! the plain files when the keyring is in use is moved into it the first
+//! time it's read.
+
+#[cfg(test)]
+pub use memory::{delete, get, set, set_store, use_keyring};
+#[cfg(not(test))]
+pub use os::{delete, get, set, set_store, use_keyring};
+
+#[cfg(not(test))]
+mod os {
+    use std::sync::RwLock;
+
+    use anyhow::{Context, Result};
+
+    use crate::config::CredentialStore;
+
+    const SERVICE: &str = "pikpaktui";
+
+    static STORE: RwLock<CredentialStore> = RwLock::new(CredentialStore::File);
+
+    /// Make `store` the one every session and password read or write uses
+    /// from now on.
+    pub fn set_store(store: CredentialStore) {
+        if let Ok(mut slot) = STORE.write() {
+            *slot = store;
+        }
+    }
+
+    pub fn use_keyring() -> bool {
+        STORE
+            .read()
+            .is_ok_and(|slot| *slot == CredentialStore::Keyring)
+    }
+
+    fn entry(account: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(SERVICE, account)
+            .with_context(|| format!("can't use the keyring entry for {account}"))
+    }
+
+    /// The secret kept under `account`, if there is one.
+    pub fn get(account: &str) -> Result<Option<String>> {
+        match entry(account)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("can't read {account} from the keyring")),
+        }
+    }
+
+    pub fn set(account: &str, secret: &str) -> Result<()> {
+        entry(account)?
+            .set_password(secret)
+            .with_context(|| format!("can't save {account} to the keyring"))
+    }
+
+    /// Remove `account`'s secret. Returns whether there was one.
+    pub fn delete(account: &str) -> Result<bool> {
+        match entry(account)?.delete_credential() {
+            Ok(()) => Ok(true),
+            Err(keyring::Error::NoEntry) => Ok(false),
+            Err(e) => Err(e).with_context(|| format!("can't remove {account} from the keyring")),
+        }
+    }
+}
+
+/// Tests run side by side, so each thread picks its own store and gets an
+/// in-memory keyring of its own instead of the OS one.
+#[cfg(test)]
+mod memory {
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+
+    use anyhow::Result;
+
+    use crate::config::CredentialStore;
+
+    thread_local! {
+        static STORE: Cell<CredentialStore> = const { Cell::new(CredentialStore::File) };
+        static KEYRING: RefCell<HashMap<String, String>> = RefCell::default();
+    }
+
+    pub fn set_store(store: CredentialStore) {
+        STORE.set(store);
+    }
+
+    pub fn use_keyring() -> bool {
+        STORE.get() == CredentialStore::Keyring
+    }
+
+    pub fn get(account: &str) -> Result<Option<String>> {
+        Ok(KEYRING.with_borrow(|k| k.get(account).cloned()))
+    }
+
+    pub fn set(account: &str, secret: &str) -> Result<()> {
+        KEYRING.with_borrow_mut(|k| k.insert(account.to_string(), secret.to_string()));
+        Ok(())
+    }
+
+    pub fn delete(account: &str) -> Result<bool> {
+        Ok(KEYRING.with_borrow_mut(|k| k.remove(account).is_some()))
+    }
+}
@@ -0,0 +1,320 @@
+//! The local database: download queue, transfer history, listing cache,
+//! upload provenance and resume state, the hash index, notes and watched
+//! state, in one SQLite file per profile (`pikpaktui.db`, next to
+//! `session.json`). The TUI, the CLI and a long-running `tasks --auto-fetch`
+//! may all have it open; WAL mode lets readers carry on while one of them
+//! writes, and a writer waits its turn rather than failing.
+//!
+//! The schema only grows through `MIGRATIONS`. State kept in JSON files
+//! by older versions is brought in the first time it's needed (see
+//! `Store::import_legacy`).
+
+use anyhow::{Context, Result};
+use rusqlite::{Connection, Transaction, TransactionBehavior};
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+pub use rusqlite::{OptionalExtension, params};
+
+const FILE_NAME: &str = "pikpaktui.db";
+
+/// How long a write waits for another process's to finish.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Schema changes, oldest first. The database's `user_version` is how many
+/// have been applied; never edit one that has shipped, add another.
+const MIGRATIONS: &[&str] = &[
+    // 1: everything that used to be a JSON file.
+    "CREATE TABLE downloads (
+        seq INTEGER PRIMARY KEY,
+        file_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        total_size INTEGER NOT NULL,
+        downloaded INTEGER NOT NULL,
+        dest_path TEXT NOT NULL,
+        status TEXT NOT NULL,
+        rate_limit INTEGER NOT NULL DEFAULT 0,
+        job TEXT,
+        finished_at INTEGER
+    );
+    CREATE TABLE transfers (
+        seq INTEGER PRIMARY KEY,
+        direction TEXT NOT NULL,
+        file_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        local_path TEXT NOT NULL,
+        finished_at INTEGER NOT NULL,
+        hash TEXT NOT NULL DEFAULT '',
+        verified INTEGER,
+        duration_secs REAL NOT NULL DEFAULT 0,
+        avg_speed INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX transfers_finished_at ON transfers (finished_at);
+    CREATE TABLE listings (
+        folder_id TEXT PRIMARY KEY,
+        fetched_at INTEGER NOT NULL,
+        entries TEXT NOT NULL
+    );
+    CREATE TABLE provenance (
+        file_id TEXT PRIMARY KEY,
+        source_path TEXT NOT NULL,
+        mtime INTEGER,
+        hash TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        uploaded_at INTEGER NOT NULL
+    );
+    CREATE TABLE hash_index (
+        id TEXT PRIMARY KEY,
+        path TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        hash TEXT NOT NULL DEFAULT ''
+    );
+    CREATE INDEX hash_index_hash ON hash_index (hash);
+    CREATE TABLE hash_index_next (
+        id TEXT PRIMARY KEY,
+        path TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        hash TEXT NOT NULL DEFAULT ''
+    );
+    CREATE TABLE hash_index_queue (
+        seq INTEGER PRIMARY KEY,
+        folder_id TEXT NOT NULL,
+        path TEXT NOT NULL
+    );
+    CREATE TABLE meta (
+        key TEXT PRIMARY KEY,
+        value INTEGER NOT NULL
+    );",
+    // 2: what a download does when it finishes.
+    "ALTER TABLE downloads ADD COLUMN on_complete TEXT NOT NULL DEFAULT '';",
+    // 3: notes and labels on drive entries.
+    "CREATE TABLE notes (
+        file_id TEXT PRIMARY KEY,
+        path TEXT NOT NULL,
+        labels TEXT NOT NULL DEFAULT '',
+        note TEXT NOT NULL DEFAULT '',
+        updated_at INTEGER NOT NULL
+    );",
+    // 4: how far each video has been watched.
+    "CREATE TABLE playback (
+        file_id TEXT PRIMARY KEY,
+        position REAL NOT NULL DEFAULT 0,
+        duration REAL NOT NULL DEFAULT 0,
+        watched INTEGER NOT NULL DEFAULT 0,
+        updated_at INTEGER NOT NULL
+    );",
+    // 5: uploads cut off part-way, and the files of folder uploads.
+    "CREATE TABLE upload_parts (
+        source_path TEXT PRIMARY KEY,
+        size INTEGER NOT NULL,
+        mtime INTEGER NOT NULL,
+        parent_id TEXT NOT NULL,
+        file_id TEXT NOT NULL,
+        upload_id TEXT NOT NULL,
+        oss TEXT NOT NULL,
+        etags TEXT NOT NULL,
+        updated_at INTEGER NOT NULL
+    );
+    CREATE TABLE upload_manifest (
+        dest_id TEXT NOT NULL,
+        source_path TEXT NOT NULL,
+        label TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        mtime INTEGER NOT NULL,
+        done INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (dest_id, source_path)
+    );",
+    // 6: partial uploads keyed by file and destination, their OSS secrets
+    // in the keyring when that's in use. Earlier rows start over.
+    "DROP TABLE upload_parts;
+    CREATE TABLE upload_parts (
+        source_path TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        mtime INTEGER NOT NULL,
+        parent_id TEXT NOT NULL,
+        file_id TEXT NOT NULL,
+        upload_id TEXT NOT NULL,
+        oss TEXT NOT NULL,
+        etags TEXT NOT NULL,
+        updated_at INTEGER NOT NULL,
+        PRIMARY KEY (source_path, parent_id)
+    );",
+];
+
+/// Databases this process has already set up; opening one again skips
+/// straight to the connection.
+static READY: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+/// Files older versions kept that the database makes redundant: caches
+/// and an unfinished crawl, which are simply rebuilt.
+const STALE_FILES: &[&str] = &["hash_index.partial.jsonl", "hash_index.crawl.json"];
+
+pub struct Store {
+    conn: Connection,
+}
+
+impl Store {
+    /// The database of the active profile.
+    pub fn open_default() -> Result<Self> {
+        Self::open(&data_dir().context("unable to locate config dir")?)
+    }
+
+    /// The database in `dir`, created and brought up to date as needed.
+    pub fn open(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("failed to create dir {}", dir.display()))?;
+        let path = dir.join(FILE_NAME);
+        let mut conn = Connection::open(&path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        let mut ready = READY.lock().unwrap_or_else(|e| e.into_inner());
+        if ready.contains(&path) {
+            return Ok(Self { conn });
+        }
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        let from =
+            migrate(&mut conn).with_context(|| format!("failed to migrate {}", path.display()))?;
+        if from == 0 {
+            let _ = fs::remove_dir_all(dir.join("cache").join("listings"));
+            for name in STALE_FILES {
+                let _ = fs::remove_file(dir.join(name));
+            }
+        }
+        ready.insert(path);
+        Ok(Self { conn })
+    }
+
+    pub fn conn(&self) -> &Connection {
+        &self.conn
+    }
+
+    pub fn transaction(&mut self) -> Result<Transaction<'_>> {
+        Ok(self.conn.transaction()?)
+    }
+
+    /// Bring in a JSON file an older version kept, once: `import` gets
+    /// its contents inside a transaction and, when that commits, the file
+    /// is renamed to `<name>.imported`. A missing file is nothing to do.
+    pub fn import_legacy(
+        &mut self,
+        path: &Path,
+        import: impl FnOnce(&Transaction, &str) -> Result<()>,
+    ) -> Result<()> {
+        let Ok(raw) = fs::read_to_string(path) else {
+            return Ok(());
+        };
+        let tx = self.conn.transaction()?;
+        import(&tx, &raw).with_context(|| format!("failed to import {}", path.display()))?;
+        tx.commit()?;
+        let mut done = path.as_os_str().to_owned();
+        done.push(".imported");
+        fs::rename(path, PathBuf::from(done))
+            .with_context(|| format!("failed to rename {}", path.display()))?;
+        Ok(())
+    }
+
+    /// A number kept in the `meta` table.
+    pub fn meta(&self, key: &str) -> Result<Option<i64>> {
+        Ok(self
+            .conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", [key], |r| r.get(0))
+            .optional()?)
+    }
+}
+
+/// Set a number in the `meta` table; `conn` may be a transaction.
+pub fn set_meta(conn: &Connection, key: &str, value: i64) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+/// The active profile's data directory, where `session.json` lives.
+pub fn data_dir() -> Option<PathBuf> {
+    crate::config::config_root().map(|d| crate::config::profile_dir(d.join("pikpaktui")))
+}
+
+/// Apply the migrations the database hasn't had yet. The version is read
+/// under the write lock, so when two processes open a new database at once
+/// the second waits and then finds nothing left to do. Returns the version
+/// it started at.
+fn migrate(conn: &mut Connection) -> Result<usize> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let from: usize = tx.pragma_query_value(None, "user_version", |r| r.get(0))?;
+    for sql in MIGRATIONS.iter().skip(from) {
+        tx.execute_batch(sql)?;
+    }
+    if from < MIGRATIONS.len() {
+        tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+    }
+    tx.commit()?;
+    Ok(from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_once_and_imports_legacy_files_once() {
+        let dir = std::env::temp_dir().join(format!("pikpaktui-store-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("hash_index.crawl.json"), "{}").unwrap();
+        let legacy = dir.join("old.json");
+        fs::write(&legacy, "3").unwrap();
+
+        let mut store = Store::open(&dir).unwrap();
+        assert!(!dir.join("hash_index.crawl.json").exists());
+        let import = |tx: &Transaction, raw: &str| {
+            let n: i64 = raw.trim().parse()?;
+            tx.execute("INSERT INTO meta (key, value) VALUES ('n', ?1)", [n])?;
+            Ok(())
+        };
+        store.import_legacy(&legacy, import).unwrap();
+        store.import_legacy(&legacy, import).unwrap();
+        assert_eq!(store.meta("n").unwrap(), Some(3));
+        assert!(dir.join("old.json.imported").exists());
+        drop(store);
+
+        // Reopening leaves the schema and the data as they were.
+        let store = Store::open(&dir).unwrap();
+        let version: usize = store
+            .conn()
+            .pragma_query_value(None, "user_version", |r| r.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+        assert_eq!(store.meta("n").unwrap(), Some(3));
+        drop(store);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn concurrent_first_opens_migrate_once() {
+        let dir = std::env::temp_dir().join(format!("pikpaktui-migrate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(FILE_NAME);
+        let froms: Vec<usize> = (0..4)
+            .map(|_| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let mut conn = Connection::open(path).unwrap();
+                    conn.busy_timeout(BUSY_TIMEOUT).unwrap();
+                    migrate(&mut conn).unwrap()
+                })
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|t| t.join().unwrap())
+            .collect();
+        // One of them did the work; the rest found it done.
+        assert_eq!(froms.iter().filter(|&&f| f == 0).count(), 1);
+        assert!(froms.iter().all(|&f| f == 0 || f == MIGRATIONS.len()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,8 @@
-use crate::config::ColorScheme;
+use crate::config::{ColorDepth, ColorScheme};
 use crate::pikpak::{Entry, EntryKind};
 use ratatui::style::Color;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FileCategory {
@@ -177,6 +179,22 @@ pub fn is_text_previewable(entry: &Entry) -> bool {
 }
 
 /// ANSI colored text for CLI output, using eza-style colors.
+/// Whether CLI listings are coloured; see `cmd::set_output_style`.
+static CLI_COLOR: AtomicBool = AtomicBool::new(true);
+
+pub fn set_cli_color(on: bool) {
+    CLI_COLOR.store(on, Ordering::Relaxed);
+}
+
+/// `text` in the ANSI style `code` (e.g. `1;34`), or plain with colour off.
+pub fn cli_paint(code: &str, text: &str) -> String {
+    if CLI_COLOR.load(Ordering::Relaxed) {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
 pub fn cli_colored(text: &str, category: FileCategory) -> String {
     let code = match category {
         FileCategory::Folder => "1;34",   // bold blue
@@ -188,5 +206,111 @@ pub fn cli_colored(text: &str, category: FileCategory) -> String {
         FileCategory::Code => "1;32",     // bold green
         FileCategory::Default => return text.to_string(),
     };
-    format!("\x1b[{}m{}\x1b[0m", code, text)
+    cli_paint(code, text)
+}
+
+static DETECTED_DEPTH: LazyLock<ColorDepth> = LazyLock::new(detect_color_depth);
+
+/// Best guess at the terminal's colour depth from the environment.
+fn detect_color_depth() -> ColorDepth {
+    let env = |key| std::env::var(key).unwrap_or_default().to_ascii_lowercase();
+    let colorterm = env("COLORTERM");
+    let term = env("TERM");
+    if colorterm == "truecolor"
+        || colorterm == "24bit"
+        || term.ends_with("-direct")
+        || std::env::var_os("WT_SESSION").is_some()
+    {
+        ColorDepth::Truecolor
+    } else if term.contains("256color") {
+        ColorDepth::Ansi256
+    } else {
+        ColorDepth::Ansi16
+    }
+}
+
+/// `Color::Rgb`, mapped to the nearest xterm-256 or ANSI-16 colour when the
+/// terminal can't show 24-bit colour.
+pub fn rgb(r: u8, g: u8, b: u8, depth: ColorDepth) -> Color {
+    let depth = match depth {
+        ColorDepth::Auto => *DETECTED_DEPTH,
+        other => other,
+    };
+    match depth {
+        ColorDepth::Auto | ColorDepth::Truecolor => Color::Rgb(r, g, b),
+        ColorDepth::Ansi256 => Color::Indexed(nearest_256(r, g, b)),
+        ColorDepth::Ansi16 => nearest_16(r, g, b),
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (i32::from(x) - i32::from(y)).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// Closest entry in the 6×6×6 colour cube (16–231) or the grey ramp (232–255).
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |v: u8| {
+        (0..6)
+            .min_by_key(|&i| (i32::from(LEVELS[i]) - i32::from(v)).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (LEVELS[ri], LEVELS[gi], LEVELS[bi]);
+    let cube_idx = 16 + 36 * ri + 6 * gi + bi;
+
+    let avg = (u32::from(r) + u32::from(g) + u32::from(b)) / 3;
+    let grey_i = (avg.saturating_sub(3) / 10).min(23);
+    let grey_v = (8 + grey_i * 10) as u8;
+
+    if distance((grey_v, grey_v, grey_v), (r, g, b)) < distance(cube, (r, g, b)) {
+        232 + grey_i as u8
+    } else {
+        cube_idx as u8
+    }
+}
+
+/// Closest of the 16 ANSI colours, using xterm's default palette.
+fn nearest_16(r: u8, g: u8, b: u8) -> Color {
+    const PALETTE: [(Color, (u8, u8, u8)); 16] = [
+        (Color::Black, (0, 0, 0)),
+        (Color::Red, (205, 0, 0)),
+        (Color::Green, (0, 205, 0)),
+        (Color::Yellow, (205, 205, 0)),
+        (Color::Blue, (0, 0, 238)),
+        (Color::Magenta, (205, 0, 205)),
+        (Color::Cyan, (0, 205, 205)),
+        (Color::Gray, (229, 229, 229)),
+        (Color::DarkGray, (127, 127, 127)),
+        (Color::LightRed, (255, 0, 0)),
+        (Color::LightGreen, (0, 255, 0)),
+        (Color::LightYellow, (255, 255, 0)),
+        (Color::LightBlue, (92, 92, 255)),
+        (Color::LightMagenta, (255, 0, 255)),
+        (Color::LightCyan, (0, 255, 255)),
+        (Color::White, (255, 255, 255)),
+    ];
+    PALETTE
+        .iter()
+        .min_by_key(|(_, c)| distance(*c, (r, g, b)))
+        .map_or(Color::Reset, |(color, _)| *color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgb_is_mapped_down_to_the_terminal_depth() {
+        assert_eq!(
+            rgb(92, 176, 255, ColorDepth::Truecolor),
+            Color::Rgb(92, 176, 255)
+        );
+        assert_eq!(rgb(255, 0, 0, ColorDepth::Ansi256), Color::Indexed(196));
+        assert_eq!(rgb(128, 128, 128, ColorDepth::Ansi256), Color::Indexed(244));
+        assert_eq!(rgb(102, 255, 255, ColorDepth::Ansi16), Color::LightCyan);
+        // base16-ocean's foreground must not collapse to black.
+        assert_eq!(rgb(192, 197, 206, ColorDepth::Ansi16), Color::Gray);
+    }
 }
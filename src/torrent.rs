@@ -0,0 +1,263 @@
+//! Just enough `.torrent` (bencode) parsing to turn a torrent file into a
+//! magnet link the offline download API accepts.
+
+use anyhow::{Context, Result, anyhow};
+use sha1::{Digest, Sha1};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Torrent {
+    pub name: String,
+    /// Hex SHA-1 of the bencoded `info` dictionary (the v1 info hash).
+    pub info_hash: String,
+    pub trackers: Vec<String>,
+    /// The `private` flag from `info`: peers come from the trackers only,
+    /// never DHT or peer exchange.
+    pub private: bool,
+}
+
+impl Torrent {
+    /// The magnet link. Magnet links have no standard private flag, so a
+    /// private torrent's is carried in the experimental `x.pr=1` parameter.
+    pub fn magnet(&self) -> String {
+        let mut out = format!("magnet:?xt=urn:btih:{}", self.info_hash);
+        if !self.name.is_empty() {
+            out.push_str("&dn=");
+            out.push_str(&percent_encode(&self.name));
+        }
+        for tracker in &self.trackers {
+            out.push_str("&tr=");
+            out.push_str(&percent_encode(tracker));
+        }
+        if self.private {
+            out.push_str("&x.pr=1");
+        }
+        out
+    }
+}
+
+/// Whether `link` names a local torrent file rather than a URL.
+pub fn is_torrent_path(link: &str) -> bool {
+    link.to_ascii_lowercase().ends_with(".torrent") && !link.contains("://")
+}
+
+/// Replace a local `.torrent` path with its magnet link; any other link is
+/// returned unchanged.
+pub fn resolve_link(link: &str) -> Result<String> {
+    if is_torrent_path(link) {
+        Ok(load(Path::new(link))?.magnet())
+    } else {
+        Ok(link.to_string())
+    }
+}
+
+pub fn load(path: &Path) -> Result<Torrent> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+    parse(&bytes).with_context(|| format!("{} is not a valid torrent", path.display()))
+}
+
+pub fn parse(bytes: &[u8]) -> Result<Torrent> {
+    let mut p = Parser { buf: bytes, pos: 0 };
+    p.expect(b'd')?;
+    let mut info: Option<(&[u8], Value)> = None;
+    let mut announce: Option<String> = None;
+    let mut tiers: Vec<String> = Vec::new();
+    while p.peek()? != b'e' {
+        let key = p.bytes()?;
+        let start = p.pos;
+        let value = p.value()?;
+        match key {
+            b"info" => info = Some((&bytes[start..p.pos], value)),
+            b"announce" => announce = value.as_str(),
+            b"announce-list" => {
+                if let Value::List(list) = value {
+                    for tier in list {
+                        if let Value::List(urls) = tier {
+                            tiers.extend(urls.iter().filter_map(Value::as_str));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let (raw_info, info) = info.ok_or_else(|| anyhow!("missing info dictionary"))?;
+    let Value::Dict(fields) = info else {
+        return Err(anyhow!("info is not a dictionary"));
+    };
+    let name = fields
+        .iter()
+        .find(|(k, _)| *k == b"name")
+        .and_then(|(_, v)| v.as_str())
+        .unwrap_or_default();
+    let private = fields
+        .iter()
+        .any(|(k, v)| *k == b"private" && matches!(v, Value::Int(1)));
+
+    let mut trackers = Vec::new();
+    for url in announce.into_iter().chain(tiers) {
+        if !trackers.contains(&url) {
+            trackers.push(url);
+        }
+    }
+
+    let info_hash = Sha1::digest(raw_info)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    Ok(Torrent {
+        name,
+        info_hash,
+        trackers,
+        private,
+    })
+}
+
+enum Value<'a> {
+    Int(i64),
+    Bytes(&'a [u8]),
+    List(Vec<Value<'a>>),
+    Dict(Vec<(&'a [u8], Value<'a>)>),
+}
+
+impl Value<'_> {
+    fn as_str(&self) -> Option<String> {
+        match self {
+            Value::Bytes(b) => Some(String::from_utf8_lossy(b).into_owned()),
+            _ => None,
+        }
+    }
+}
+
+struct Parser<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Result<u8> {
+        self.buf
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| anyhow!("unexpected end of data"))
+    }
+
+    fn expect(&mut self, c: u8) -> Result<()> {
+        if self.peek()? != c {
+            return Err(anyhow!("expected '{}' at byte {}", c as char, self.pos));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Digits up to `end`, consuming the terminator.
+    fn number(&mut self, end: u8) -> Result<i64> {
+        let start = self.pos;
+        while self.peek()? != end {
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.buf[start..self.pos])?;
+        self.pos += 1;
+        text.parse()
+            .map_err(|_| anyhow!("bad number at byte {start}"))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = usize::try_from(self.number(b':')?)?;
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&e| e <= self.buf.len())
+            .ok_or_else(|| anyhow!("string runs past end of data"))?;
+        let out = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn value(&mut self) -> Result<Value<'a>> {
+        match self.peek()? {
+            b'i' => {
+                self.pos += 1;
+                Ok(Value::Int(self.number(b'e')?))
+            }
+            b'l' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                while self.peek()? != b'e' {
+                    items.push(self.value()?);
+                }
+                self.pos += 1;
+                Ok(Value::List(items))
+            }
+            b'd' => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                while self.peek()? != b'e' {
+                    let key = self.bytes()?;
+                    fields.push((key, self.value()?));
+                }
+                self.pos += 1;
+                Ok(Value::Dict(fields))
+            }
+            b'0'..=b'9' => Ok(Value::Bytes(self.bytes()?)),
+            other => Err(anyhow!(
+                "unexpected '{}' at byte {}",
+                other as char,
+                self.pos
+            )),
+        }
+    }
+}
+
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_a_magnet_from_the_info_hash_and_trackers() {
+        let mut raw = b"d8:announce21:udp://t.example:1337/13:announce-listll21:udp://t.example:1337/el18:https://b.example/ee4:info".to_vec();
+        raw.extend_from_slice(b"d6:lengthi1024e4:name8:demo.iso12:piece lengthi16384e6:pieces20:");
+        raw.extend_from_slice(&[b'a'; 20]);
+        raw.extend_from_slice(b"ee");
+
+        let torrent = parse(&raw).unwrap();
+        assert_eq!(torrent.name, "demo.iso");
+        assert_eq!(
+            torrent.info_hash,
+            "81b9a007b46faaaccbcea22060da65395b1e83d3"
+        );
+        assert_eq!(
+            torrent.magnet(),
+            "magnet:?xt=urn:btih:81b9a007b46faaaccbcea22060da65395b1e83d3&dn=demo.iso\
+             &tr=udp%3A%2F%2Ft.example%3A1337%2F&tr=https%3A%2F%2Fb.example%2F"
+        );
+        assert!(parse(&raw[..raw.len() - 10]).is_err());
+        assert!(!torrent.private);
+    }
+
+    #[test]
+    fn a_private_torrent_stays_private_as_a_magnet() {
+        let raw = b"d8:announce18:https://p.example/4:infod6:lengthi1e4:name1:x7:privatei1eee";
+        let torrent = parse(raw).unwrap();
+        assert!(torrent.private);
+        assert!(
+            torrent
+                .magnet()
+                .ends_with("&tr=https%3A%2F%2Fp.example%2F&x.pr=1")
+        );
+    }
+}
@@ -0,0 +1,172 @@
+//! Activity overlay: the account's events feed, newest first. While it's
+//! open the feed is polled every `POLL_INTERVAL`, so new events (from this
+//! app, the web client or a phone) appear at the top as they happen.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use crossterm::event::KeyCode;
+
+use crate::pikpak::EventEntry;
+
+use super::{App, InputMode, OpResult};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Newest events asked for on each poll.
+const POLL_SIZE: u32 = 50;
+
+/// Events kept in the overlay; older ones fall off the bottom.
+const KEEP: usize = 300;
+
+#[derive(Default)]
+pub(super) struct ActivityFeed {
+    pub(super) events: Vec<EventEntry>,
+    /// How many of the top `events` arrived after the overlay was opened.
+    pub(super) fresh: usize,
+    /// A poll is in flight; its result is dropped if this was reset since.
+    busy: bool,
+    last_poll: Option<Instant>,
+}
+
+/// Events carry no id of their own; these fields tell them apart.
+fn same_event(a: &EventEntry, b: &EventEntry) -> bool {
+    a.created_time == b.created_time && a.file_id == b.file_id && a.event_type == b.event_type
+}
+
+impl ActivityFeed {
+    /// Put the events of `page` (newest first) that aren't shown yet on
+    /// top; returns how many were new.
+    fn merge(&mut self, page: Vec<EventEntry>) -> usize {
+        let new: Vec<EventEntry> = page
+            .into_iter()
+            .filter(|ev| !self.events.iter().any(|e| same_event(e, ev)))
+            .collect();
+        let count = new.len();
+        self.events.splice(0..0, new);
+        self.events.truncate(KEEP);
+        count
+    }
+}
+
+impl App {
+    /// `E`: show the activity overlay and fetch the latest events.
+    pub(super) fn open_activity_view(&mut self) {
+        self.activity.fresh = 0;
+        self.input = InputMode::ActivityView { selected: 0 };
+        self.poll_activity();
+    }
+
+    /// Poll again once the interval is up, while the overlay is open.
+    pub(super) fn maybe_poll_activity(&mut self) {
+        let due = self
+            .activity
+            .last_poll
+            .is_none_or(|t| t.elapsed() >= POLL_INTERVAL);
+        if due && matches!(self.input, InputMode::ActivityView { .. }) {
+            self.poll_activity();
+        }
+    }
+
+    fn poll_activity(&mut self) {
+        if self.activity.busy {
+            return;
+        }
+        self.activity.busy = true;
+        self.activity.last_poll = Some(Instant::now());
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        self.workers.spawn(move || {
+            let result = client.events(POLL_SIZE).map(|r| r.events);
+            let _ = tx.send(OpResult::Activity(result));
+        });
+    }
+
+    pub(super) fn apply_activity(&mut self, result: Result<Vec<EventEntry>>) {
+        if !std::mem::take(&mut self.activity.busy) {
+            return;
+        }
+        match result {
+            Ok(page) => {
+                let first_load = self.activity.events.is_empty();
+                let new = self.activity.merge(page);
+                if !first_load {
+                    self.activity.fresh += new;
+                    // Keep the cursor on the event it was on.
+                    if let InputMode::ActivityView { selected } = &mut self.input
+                        && *selected > 0
+                    {
+                        *selected = (*selected + new).min(self.activity.events.len() - 1);
+                    }
+                }
+            }
+            Err(e) => self.push_log(format!("Failed to load activity: {e:#}")),
+        }
+    }
+
+    pub(super) fn handle_activity_key(&mut self, code: KeyCode, selected: usize) {
+        let last = self.activity.events.len().saturating_sub(1);
+        let mut selected = selected.min(last);
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('E') => return,
+            KeyCode::Down | KeyCode::Char('j') => selected = (selected + 1).min(last),
+            KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+            KeyCode::Char('g') | KeyCode::Home => selected = 0,
+            KeyCode::Char('G') | KeyCode::End => selected = last,
+            KeyCode::Char('r') => self.poll_activity(),
+            KeyCode::Enter => {
+                // Jump to the file, as `:` does with a pasted file id.
+                match self
+                    .activity
+                    .events
+                    .get(selected)
+                    .and_then(|e| e.file_id.clone())
+                {
+                    Some(id) if !id.is_empty() => {
+                        self.loading = true;
+                        let client = Arc::clone(&self.client);
+                        let tx = self.result_tx.clone();
+                        self.workers.spawn(move || {
+                            let _ = tx.send(OpResult::GotoPath(client.resolve_nav(&id)));
+                        });
+                        return;
+                    }
+                    _ => self.push_log("That event has no file to go to".into()),
+                }
+            }
+            _ => {}
+        }
+        self.input = InputMode::ActivityView { selected };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(file_id: &str, time: &str) -> EventEntry {
+        serde_json::from_value(serde_json::json!({
+            "type": "TYPE_UPLOAD",
+            "file_id": file_id,
+            "created_time": time,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn merge_puts_only_unseen_events_on_top() {
+        let mut feed = ActivityFeed::default();
+        let older = vec![event("b", "t2"), event("a", "t1")];
+        assert_eq!(feed.merge(older), 2);
+
+        let page = vec![event("c", "t3"), event("b", "t2"), event("a", "t1")];
+        assert_eq!(feed.merge(page), 1);
+        let ids: Vec<_> = feed
+            .events
+            .iter()
+            .filter_map(|e| e.file_id.as_deref())
+            .collect();
+        assert_eq!(ids, ["c", "b", "a"]);
+    }
+}
@@ -0,0 +1,322 @@
+//! Listing the contents of a zip file without downloading it: the central
+//! directory sits at the end of the archive, so two ranged requests (the
+//! tail, then the directory itself) are enough. RAR and 7z keep their
+//! index in the middle of the data or compressed, so they aren't listed.
+
+use anyhow::{Result, anyhow};
+
+use crate::pikpak::PikPak;
+
+/// Zip-based formats, by extension.
+const ZIP_EXTENSIONS: &[&str] = &["zip", "cbz", "jar", "apk", "epub", "xpi", "whl"];
+
+/// The end-of-central-directory record is 22 bytes plus a comment of up
+/// to 64 KB, plus 20 for the zip64 locator in front of it.
+const TAIL_BYTES: u64 = 22 + 0xFFFF + 20;
+
+/// A bigger directory is only read this far.
+const MAX_DIRECTORY: u64 = 4 << 20;
+
+const EOCD: u32 = 0x0605_4b50;
+const EOCD64: u32 = 0x0606_4b50;
+const EOCD64_LOCATOR: u32 = 0x0706_4b50;
+const CENTRAL: u32 = 0x0201_4b50;
+
+#[derive(Debug)]
+pub(super) struct ArchiveEntry {
+    pub(super) name: String,
+    pub(super) size: u64,
+    pub(super) is_dir: bool,
+}
+
+#[derive(Debug)]
+pub(super) struct Listing {
+    pub(super) entries: Vec<ArchiveEntry>,
+    /// Entries the archive says it has; more than `entries` when the
+    /// directory was too big to read whole.
+    pub(super) total: u64,
+}
+
+impl Listing {
+    /// Files (not folders) listed, and their unpacked size.
+    pub(super) fn files(&self) -> (usize, u64) {
+        self.entries
+            .iter()
+            .filter(|e| !e.is_dir)
+            .fold((0, 0), |(n, size), e| (n + 1, size + e.size))
+    }
+}
+
+pub(super) fn is_zip(name: &str) -> bool {
+    name.rsplit_once('.')
+        .is_some_and(|(_, ext)| ZIP_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// List a zip file in the cloud.
+pub(super) fn list(client: &PikPak, file_id: &str) -> Result<Listing> {
+    let (url, size) = client.download_url(file_id)?;
+    if size < 22 {
+        return Err(anyhow!("too small to be a zip file"));
+    }
+    let tail_start = size.saturating_sub(TAIL_BYTES);
+    let tail = client.fetch_range(&url, tail_start, size - 1)?;
+    let dir = match find_directory(&tail, tail_start)? {
+        Directory::Located(dir) => dir,
+        // The zip64 record is further back than the tail we have.
+        Directory::Zip64At(offset) => {
+            let end = offset
+                .checked_add(56)
+                .filter(|&end| end <= size)
+                .ok_or_else(|| anyhow!("damaged zip64 record"))?;
+            let record = client.fetch_range(&url, offset, end - 1)?;
+            zip64_directory(&record).ok_or_else(|| anyhow!("damaged zip64 record"))?
+        }
+    };
+    let len = dir.size.min(MAX_DIRECTORY);
+    if len == 0 {
+        return Ok(Listing {
+            entries: Vec::new(),
+            total: 0,
+        });
+    }
+    let end = dir
+        .offset
+        .checked_add(len)
+        .filter(|&end| end <= size)
+        .ok_or_else(|| anyhow!("damaged zip (central directory past the end)"))?;
+    let bytes = match in_tail(&tail, tail_start, dir.offset) {
+        Some(rest) if rest.len() as u64 >= len => rest[..len as usize].to_vec(),
+        _ => client.fetch_range(&url, dir.offset, end - 1)?,
+    };
+    Ok(Listing {
+        entries: parse_directory(&bytes),
+        total: dir.count,
+    })
+}
+
+#[derive(Debug, PartialEq)]
+struct DirectoryInfo {
+    offset: u64,
+    size: u64,
+    count: u64,
+}
+
+#[derive(Debug, PartialEq)]
+enum Directory {
+    Located(DirectoryInfo),
+    /// A zip64 archive whose end record starts at this offset.
+    Zip64At(u64),
+}
+
+fn u16_at(b: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(b.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn u32_at(b: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(b.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn u64_at(b: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(b.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Where the central directory is, from the last bytes of the archive
+/// (`tail`, which starts at `tail_start` in the file).
+fn find_directory(tail: &[u8], tail_start: u64) -> Result<Directory> {
+    // The last end record whose comment runs exactly to the end; a
+    // signature inside the comment itself won't line up.
+    let eocd = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&at| {
+            u32_at(tail, at) == Some(EOCD)
+                && u16_at(tail, at + 20).is_some_and(|c| at + 22 + c as usize == tail.len())
+        })
+        .ok_or_else(|| anyhow!("not a zip file (no end of central directory)"))?;
+
+    let count = u16_at(tail, eocd + 10).unwrap_or(0);
+    let size = u32_at(tail, eocd + 12).unwrap_or(0);
+    let offset = u32_at(tail, eocd + 16).unwrap_or(0);
+    if count == 0xFFFF || size == u32::MAX || offset == u32::MAX {
+        let locator = eocd
+            .checked_sub(20)
+            .filter(|&at| u32_at(tail, at) == Some(EOCD64_LOCATOR))
+            .ok_or_else(|| anyhow!("zip64 archive without its locator"))?;
+        let record = u64_at(tail, locator + 8).unwrap_or(0);
+        if record >= tail_start {
+            return in_tail(tail, tail_start, record)
+                .and_then(zip64_directory)
+                .map(Directory::Located)
+                .ok_or_else(|| anyhow!("damaged zip64 record"));
+        }
+        return Ok(Directory::Zip64At(record));
+    }
+    Ok(Directory::Located(DirectoryInfo {
+        offset: offset as u64,
+        size: size as u64,
+        count: count as u64,
+    }))
+}
+
+/// What `tail` (starting at `tail_start` in the file) holds from `offset`
+/// on, if `offset` falls inside it.
+fn in_tail(tail: &[u8], tail_start: u64, offset: u64) -> Option<&[u8]> {
+    let at = usize::try_from(offset.checked_sub(tail_start)?).ok()?;
+    tail.get(at..)
+}
+
+fn zip64_directory(record: &[u8]) -> Option<DirectoryInfo> {
+    if u32_at(record, 0)? != EOCD64 {
+        return None;
+    }
+    Some(DirectoryInfo {
+        count: u64_at(record, 32)?,
+        size: u64_at(record, 40)?,
+        offset: u64_at(record, 48)?,
+    })
+}
+
+/// The entries of a central directory; stops at the first record that
+/// doesn't parse (or where a truncated read ends).
+fn parse_directory(dir: &[u8]) -> Vec<ArchiveEntry> {
+    let mut entries = Vec::new();
+    let mut at = 0;
+    while u32_at(dir, at) == Some(CENTRAL) {
+        let Some(entry) = parse_entry(dir, at) else {
+            break;
+        };
+        at = entry.1;
+        entries.push(entry.0);
+    }
+    entries
+}
+
+/// One central directory record at `at`, and where the next one starts.
+fn parse_entry(dir: &[u8], at: usize) -> Option<(ArchiveEntry, usize)> {
+    let mut size = u32_at(dir, at + 24)? as u64;
+    let name_len = u16_at(dir, at + 28)? as usize;
+    let extra_len = u16_at(dir, at + 30)? as usize;
+    let comment_len = u16_at(dir, at + 32)? as usize;
+    let name_start = at + 46;
+    let name = String::from_utf8_lossy(dir.get(name_start..name_start + name_len)?).into_owned();
+
+    // A zip64 size is in the extra field, tag 0x0001, first when present.
+    if size == u32::MAX as u64 {
+        let extra = dir.get(name_start + name_len..name_start + name_len + extra_len)?;
+        let mut i = 0;
+        while let (Some(tag), Some(len)) = (u16_at(extra, i), u16_at(extra, i + 2)) {
+            if tag == 0x0001 {
+                size = u64_at(extra, i + 4)?;
+                break;
+            }
+            i += 4 + len as usize;
+        }
+    }
+
+    let next = name_start + name_len + extra_len + comment_len;
+    let is_dir = name.ends_with('/');
+    Some((ArchiveEntry { name, size, is_dir }, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn central(name: &str, size: u32) -> Vec<u8> {
+        let mut r = CENTRAL.to_le_bytes().to_vec();
+        r.extend([0u8; 16]);
+        r.extend(size.to_le_bytes()); // compressed
+        r.extend(size.to_le_bytes());
+        r.extend((name.len() as u16).to_le_bytes());
+        r.extend([0u8; 16]);
+        r.extend(name.as_bytes());
+        r
+    }
+
+    /// A zip with no file data: the central directory and its end record,
+    /// with a comment.
+    fn archive(names: &[(&str, u32)]) -> Vec<u8> {
+        let mut zip = vec![0u8; 100];
+        let offset = zip.len() as u32;
+        for (name, size) in names {
+            zip.extend(central(name, *size));
+        }
+        let size = zip.len() as u32 - offset;
+        zip.extend(EOCD.to_le_bytes());
+        zip.extend([0u8; 4]);
+        zip.extend((names.len() as u16).to_le_bytes());
+        zip.extend((names.len() as u16).to_le_bytes());
+        zip.extend(size.to_le_bytes());
+        zip.extend(offset.to_le_bytes());
+        let comment = b"made with PK\x05\x06 inside";
+        zip.extend((comment.len() as u16).to_le_bytes());
+        zip.extend(comment);
+        zip
+    }
+
+    #[test]
+    fn lists_the_central_directory() {
+        let zip = archive(&[("docs/", 0), ("docs/a.txt", 1200), ("b.bin", 34)]);
+        let tail_start = 50;
+        let Directory::Located(dir) = find_directory(&zip[50..], tail_start).unwrap() else {
+            panic!("expected a plain zip");
+        };
+        assert_eq!(dir.count, 3);
+        let start = dir.offset as usize;
+        let entries = parse_directory(&zip[start..start + dir.size as usize]);
+        let names: Vec<_> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["docs/", "docs/a.txt", "b.bin"]);
+        assert!(entries[0].is_dir);
+        let listing = Listing {
+            entries,
+            total: dir.count,
+        };
+        assert_eq!(listing.files(), (2, 1234));
+
+        // A directory cut short keeps the entries read whole.
+        assert_eq!(parse_directory(&zip[start..start + 60]).len(), 1);
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_zip() {
+        assert!(find_directory(b"Rar!\x1a\x07\x01\x00 and so on", 0).is_err());
+        assert!(is_zip("Comics.CBZ"));
+        assert!(!is_zip("backup.7z"));
+    }
+
+    #[test]
+    fn a_damaged_zip64_locator_is_an_error() {
+        // A zip64 locator pointing at `record`, then an end record that
+        // defers to it.
+        let tail = |record: u64| {
+            let mut tail = EOCD64_LOCATOR.to_le_bytes().to_vec();
+            tail.extend([0u8; 4]);
+            tail.extend(record.to_le_bytes());
+            tail.extend([0u8; 4]);
+            tail.extend(EOCD.to_le_bytes());
+            tail.extend([0u8; 4]);
+            tail.extend([0xFF; 4]);
+            tail.extend([0xFF; 8]);
+            tail.extend(0u16.to_le_bytes());
+            tail
+        };
+        // Past the end of what was read, and past the end of any file.
+        assert!(find_directory(&tail(1100), 1000).is_err());
+        assert!(find_directory(&tail(u64::MAX), 1000).is_err());
+        // Inside it, but not a zip64 record.
+        assert!(find_directory(&tail(1004), 1000).is_err());
+        // Further back: fetched separately.
+        assert_eq!(
+            find_directory(&tail(10), 1000).unwrap(),
+            Directory::Zip64At(10)
+        );
+    }
+
+    #[test]
+    fn offsets_outside_the_tail_are_not_in_it() {
+        let tail = [1u8, 2, 3];
+        assert_eq!(in_tail(&tail, 10, 11), Some(&[2u8, 3][..]));
+        assert_eq!(in_tail(&tail, 10, 9), None);
+        assert_eq!(in_tail(&tail, 10, 14), None);
+        assert_eq!(in_tail(&tail, 10, u64::MAX), None);
+    }
+}
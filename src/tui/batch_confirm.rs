@@ -0,0 +1,311 @@
+//! The summary shown before a batch runs: how many items, how big, where
+//! they go and which names already exist there. Cart moves, copies,
+//! downloads and trashing, and F5 / F6 on marked items, all stop here;
+//! a batch with clashes needs `Y` rather than `y` to go ahead.
+
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::Result;
+use crossterm::event::KeyCode;
+
+use crate::pikpak::{Entry, EntryKind, PikPak};
+
+use super::download::OnComplete;
+use super::instance::QueuedDownload;
+use super::ops::{self, FileOp};
+use super::{App, InputMode, OpResult};
+
+pub(super) enum BatchAction {
+    /// `from` is the items' folder when they share one (marked items),
+    /// else looked up by the queue.
+    Move {
+        to: String,
+        dest_path: String,
+        from: Option<String>,
+    },
+    Copy {
+        to: String,
+        dest_path: String,
+    },
+    /// `on_complete` is cycled with `w` in the summary.
+    Download {
+        dest: PathBuf,
+        on_complete: OnComplete,
+    },
+    Trash,
+}
+
+impl BatchAction {
+    pub(super) fn verb(&self) -> &'static str {
+        match self {
+            Self::Move { .. } => "Move",
+            Self::Copy { .. } => "Copy",
+            Self::Download { .. } => "Download",
+            Self::Trash => "Trash",
+        }
+    }
+
+    /// Where the items go, as shown; `None` for trashing.
+    pub(super) fn destination(&self) -> Option<String> {
+        match self {
+            Self::Move { dest_path, .. } | Self::Copy { dest_path, .. } => Some(dest_path.clone()),
+            Self::Download { dest, .. } => Some(dest.display().to_string()),
+            Self::Trash => None,
+        }
+    }
+}
+
+pub(super) struct BatchSummary {
+    pub(super) action: BatchAction,
+    pub(super) entries: Vec<Entry>,
+    /// Names that clash at the destination, each with why; `None` while
+    /// the destination is still being listed.
+    pub(super) conflicts: Option<Vec<(String, &'static str)>>,
+    /// Opened from the cart view, which Esc goes back to.
+    pub(super) from_cart: bool,
+}
+
+impl BatchSummary {
+    pub(super) fn total_size(&self) -> u64 {
+        self.entries.iter().map(|e| e.size).sum()
+    }
+
+    pub(super) fn folders(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.kind == EntryKind::Folder)
+            .count()
+    }
+}
+
+/// Names used more than once in the batch, which can't all keep theirs.
+fn repeated_names(entries: &[Entry]) -> Vec<(String, &'static str)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for e in entries {
+        *counts.entry(e.name.as_str()).or_default() += 1;
+    }
+    let mut seen = HashSet::new();
+    entries
+        .iter()
+        .filter(|e| counts[e.name.as_str()] > 1 && seen.insert(e.name.as_str()))
+        .map(|e| (e.name.clone(), "more than once in the batch"))
+        .collect()
+}
+
+/// Clashes between the batch and what `listing` (the destination folder)
+/// already holds.
+fn cloud_conflicts(entries: &[Entry], listing: &[Entry]) -> Vec<(String, &'static str)> {
+    let mut conflicts = repeated_names(entries);
+    for e in entries {
+        if listing.iter().any(|l| l.id == e.id) {
+            conflicts.push((e.name.clone(), "already in that folder"));
+        } else if listing.iter().any(|l| l.name == e.name) {
+            conflicts.push((e.name.clone(), "name taken there"));
+        }
+    }
+    conflicts
+}
+
+/// Clashes with files already in the local download folder.
+fn local_conflicts(entries: &[Entry], dest: &std::path::Path) -> Vec<(String, &'static str)> {
+    let mut conflicts = repeated_names(entries);
+    for e in entries {
+        if dest.join(&e.name).exists() {
+            conflicts.push((e.name.clone(), "exists locally"));
+        }
+    }
+    conflicts
+}
+
+fn list_conflicts(
+    client: &PikPak,
+    to: &str,
+    entries: &[Entry],
+) -> Result<Vec<(String, &'static str)>> {
+    Ok(cloud_conflicts(entries, &client.ls(to)?))
+}
+
+impl App {
+    /// Show the summary for `entries`, listing a cloud destination in the
+    /// background to find clashes.
+    pub(super) fn open_batch_summary(
+        &mut self,
+        action: BatchAction,
+        entries: Vec<Entry>,
+        from_cart: bool,
+    ) {
+        let conflicts = match &action {
+            BatchAction::Move { to, .. } | BatchAction::Copy { to, .. } => {
+                let client = Arc::clone(&self.client);
+                let tx = self.result_tx.clone();
+                let to = to.clone();
+                let batch = entries.clone();
+                self.workers.spawn(move || {
+                    let result = list_conflicts(&client, &to, &batch);
+                    let _ = tx.send(OpResult::BatchConflicts(to, result));
+                });
+                None
+            }
+            BatchAction::Download { dest, .. } => Some(local_conflicts(&entries, dest)),
+            BatchAction::Trash => Some(Vec::new()),
+        };
+        self.input = InputMode::ConfirmBatch(Box::new(BatchSummary {
+            action,
+            entries,
+            conflicts,
+            from_cart,
+        }));
+    }
+
+    /// The destination listing for the summary that's open, if it's still
+    /// the one that asked.
+    pub(super) fn apply_batch_conflicts(
+        &mut self,
+        to: String,
+        result: Result<Vec<(String, &'static str)>>,
+    ) {
+        let InputMode::ConfirmBatch(summary) = &mut self.input else {
+            return;
+        };
+        let waiting = summary.conflicts.is_none()
+            && matches!(
+                &summary.action,
+                BatchAction::Move { to: t, .. } | BatchAction::Copy { to: t, .. } if *t == to
+            );
+        if !waiting {
+            return;
+        }
+        match result {
+            Ok(conflicts) => summary.conflicts = Some(conflicts),
+            Err(e) => {
+                let verb = summary.action.verb();
+                let from_cart = summary.from_cart;
+                self.input = if from_cart {
+                    InputMode::CartView
+                } else {
+                    InputMode::Normal
+                };
+                self.push_log(format!("{verb} failed: {e:#}"));
+            }
+        }
+    }
+
+    pub(super) fn handle_batch_summary_key(
+        &mut self,
+        code: KeyCode,
+        mut summary: Box<BatchSummary>,
+    ) {
+        if code == KeyCode::Char('w')
+            && let BatchAction::Download { on_complete, .. } = &mut summary.action
+        {
+            *on_complete = on_complete.next();
+            self.input = InputMode::ConfirmBatch(summary);
+            return;
+        }
+        let clashes = summary.conflicts.as_ref().map(|c| !c.is_empty());
+        match (code, clashes) {
+            (KeyCode::Char('y') | KeyCode::Enter, Some(false))
+            | (KeyCode::Char('Y'), Some(true)) => self.run_batch(*summary),
+            (KeyCode::Esc | KeyCode::Char('n') | KeyCode::Char('q'), _) => {
+                self.push_log(format!("{} cancelled", summary.action.verb()));
+                self.input = if summary.from_cart {
+                    InputMode::CartView
+                } else {
+                    InputMode::Normal
+                };
+            }
+            _ => self.input = InputMode::ConfirmBatch(summary),
+        }
+    }
+
+    fn run_batch(&mut self, summary: BatchSummary) {
+        let BatchSummary {
+            action, entries, ..
+        } = summary;
+        // Handed off, so they leave the cart.
+        for e in &entries {
+            self.cart_ids.remove(&e.id);
+        }
+        self.cart.retain(|e| self.cart_ids.contains(&e.id));
+        self.cart_selected = self.cart_selected.min(self.cart.len().saturating_sub(1));
+
+        let what = ops::describe(&entries);
+        match action {
+            BatchAction::Move {
+                to,
+                dest_path,
+                from,
+            } => {
+                let ids: Vec<String> = entries.iter().map(|e| e.id.clone()).collect();
+                self.queue_op(FileOp::Move {
+                    from: vec![from; ids.len()],
+                    ids,
+                    to,
+                    dest_path,
+                    what,
+                });
+            }
+            BatchAction::Copy { to, dest_path } => {
+                self.queue_op(FileOp::Copy {
+                    ids: entries.iter().map(|e| e.id.clone()).collect(),
+                    to,
+                    dest_path,
+                    what,
+                });
+            }
+            BatchAction::Download { dest, on_complete } => {
+                let count = entries.len();
+                let batch = entries
+                    .into_iter()
+                    .map(|item| QueuedDownload {
+                        dest_path: dest.join(&item.name),
+                        file_id: item.id,
+                        name: item.name,
+                        total_size: item.size,
+                        on_complete,
+                    })
+                    .collect();
+                self.push_log(format!("Queued {} files for download", count));
+                let label = format!("Cart: {} files → {}", count, dest.display());
+                self.enqueue_downloads(batch, &label);
+                self.open_download_view();
+            }
+            BatchAction::Trash => {
+                self.queue_op(FileOp::Trash {
+                    what,
+                    ids: entries.into_iter().map(|e| e.id).collect(),
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clashes_with_the_destination_and_within_the_batch() {
+        let batch = [
+            Entry::file("a.mkv").with_id("1").with_size(1),
+            Entry::file("b.mkv").with_id("2").with_size(1),
+            Entry::file("c.mkv").with_id("3").with_size(1),
+            Entry::file("c.mkv").with_id("4").with_size(1),
+        ];
+        let listing = [
+            Entry::file("a.mkv").with_id("1").with_size(1),
+            Entry::file("b.mkv").with_id("9").with_size(1),
+        ];
+        assert_eq!(
+            cloud_conflicts(&batch, &listing),
+            [
+                ("c.mkv".to_string(), "more than once in the batch"),
+                ("a.mkv".to_string(), "already in that folder"),
+                ("b.mkv".to_string(), "name taken there"),
+            ]
+        );
+        assert!(cloud_conflicts(&batch[..2], &[]).is_empty());
+    }
+}
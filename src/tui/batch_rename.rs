@@ -0,0 +1,453 @@
+//! Batch rename of the marked entries in the current folder: find / replace
+//! (plain or regex, `$1` / `${1}` for capture groups) with a `{n}` counter,
+//! optionally tidying names up as well (`Z`, or `Ctrl+N` while open), and
+//! a preview of every old → new name before anything is renamed.
+
+use std::collections::{HashMap, HashSet};
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use regex::Regex;
+
+use crate::pikpak::{Entry, EntryKind};
+
+use super::ops::FileOp;
+use super::{App, InputMode, handle_text_input};
+
+#[derive(Default)]
+pub(super) struct BatchRename {
+    /// The entries to rename, in listing order; `{n}` counts along it.
+    pub(super) targets: Vec<Entry>,
+    pub(super) find: String,
+    pub(super) replace: String,
+    pub(super) regex: bool,
+    /// Tidy the names up after find / replace, see [`normalize_name`].
+    pub(super) normalize: bool,
+    /// Typing into the replace field rather than find.
+    pub(super) on_replace: bool,
+    /// Names in the folder that aren't being renamed.
+    others: HashSet<String>,
+}
+
+/// One preview row.
+pub(super) struct RenameRow<'a> {
+    pub(super) old: &'a str,
+    pub(super) new: String,
+    pub(super) problem: Option<&'static str>,
+}
+
+/// Expand `{n}` (1, 2, …) and `{n:03}` (zero-padded to 3 digits) in `template`.
+fn expand_counter(template: &str, n: usize) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{n") {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start + 2..];
+        let width = if let Some(after) = tail.strip_prefix('}') {
+            rest = after;
+            Some(0)
+        } else if let Some(spec) = tail.strip_prefix(":0")
+            && let Some(end) = spec.find('}')
+            && let Ok(width) = spec[..end].parse::<usize>()
+        {
+            rest = &spec[end + 1..];
+            Some(width)
+        } else {
+            None
+        };
+        match width {
+            Some(width) => out.push_str(&format!("{n:0width$}")),
+            None => {
+                out.push_str("{n");
+                rest = tail;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Words title case leaves in lower case, unless they come first.
+const MINOR_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "in", "of", "on", "or", "the", "to", "vs",
+];
+
+/// Make a downloaded name readable: drop `[...]`, `{...}`, `【...】` and
+/// `(...)` tags (a `(year)` stays), turn dots and underscores into spaces,
+/// and title-case words that are all lower case. A file keeps its
+/// extension; a name that would end up empty is left alone.
+fn normalize_name(name: &str, is_folder: bool) -> String {
+    let (stem, ext) = match name.rsplit_once('.') {
+        Some((stem, ext))
+            if !is_folder
+                && !stem.is_empty()
+                && (1..=5).contains(&ext.len())
+                && ext.chars().all(|c| c.is_ascii_alphanumeric()) =>
+        {
+            (stem, Some(ext))
+        }
+        _ => (name, None),
+    };
+
+    let mut kept = String::with_capacity(stem.len());
+    let mut rest = stem;
+    while let Some(start) = rest.find(['[', '{', '(', '【']) {
+        let open = rest[start..].chars().next().unwrap_or_default();
+        let close = match open {
+            '[' => ']',
+            '{' => '}',
+            '(' => ')',
+            _ => '】',
+        };
+        let Some(len) = rest[start..].find(close) else {
+            break;
+        };
+        let inner = &rest[start + open.len_utf8()..start + len];
+        kept.push_str(&rest[..start]);
+        let year = open == '('
+            && inner.len() == 4
+            && inner.chars().all(|c| c.is_ascii_digit())
+            && (inner.starts_with("19") || inner.starts_with("20"));
+        if year {
+            kept.push_str(&rest[start..start + len + 1]);
+        }
+        kept.push(' ');
+        rest = &rest[start + len + close.len_utf8()..];
+    }
+    kept.push_str(rest);
+
+    let words: Vec<String> = kept
+        .replace(['.', '_'], " ")
+        .split_whitespace()
+        .enumerate()
+        .map(|(i, word)| {
+            let lower = word.chars().all(|c| c.is_lowercase());
+            if !lower || (i > 0 && MINOR_WORDS.contains(&word)) {
+                return word.to_string();
+            }
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect())
+                .unwrap_or_default()
+        })
+        .collect();
+    let stem = words.join(" ");
+    let stem = stem.trim_matches(|c: char| c == '-' || c.is_whitespace());
+    if stem.is_empty() {
+        return name.to_string();
+    }
+    match ext {
+        Some(ext) => format!("{stem}.{ext}"),
+        None => stem.to_string(),
+    }
+}
+
+/// `renames` (id, old name, new name) in an order that can be carried out
+/// one at a time: a name is only taken once the entry holding it has moved
+/// on, and a cycle, such as two names swapped, goes through a temporary
+/// name first.
+pub(super) fn rename_steps(renames: &[(String, String, String)]) -> Vec<(String, String, String)> {
+    let mut pending = renames.to_vec();
+    let mut steps = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let held: HashSet<&str> = pending.iter().map(|(_, old, _)| old.as_str()).collect();
+        let free = pending
+            .iter()
+            .position(|(_, _, new)| !held.contains(new.as_str()));
+        match free {
+            Some(i) => steps.push(pending.remove(i)),
+            None => {
+                // Every name wanted is still held: step one entry aside.
+                let (id, old, new) = pending.remove(0);
+                let temp = format!("{old}.renaming-{id}");
+                steps.push((id.clone(), old, temp.clone()));
+                pending.push((id, temp, new));
+            }
+        }
+    }
+    steps
+}
+
+impl BatchRename {
+    pub(super) fn new(targets: Vec<Entry>, folder: &[Entry]) -> Self {
+        let ids: HashSet<&str> = targets.iter().map(|e| e.id.as_str()).collect();
+        let others = folder
+            .iter()
+            .filter(|e| !ids.contains(e.id.as_str()))
+            .map(|e| e.name.clone())
+            .collect();
+        Self {
+            targets,
+            others,
+            ..Self::default()
+        }
+    }
+
+    /// The new name of every target, or why the pattern can't be used.
+    /// Names that would be empty, contain `/`, or clash with another name
+    /// in the folder are flagged. Targets may trade names among themselves;
+    /// [`rename_steps`] orders the renames so they don't collide.
+    pub(super) fn preview(&self) -> Result<Vec<RenameRow<'_>>, String> {
+        let re = if self.regex && !self.find.is_empty() {
+            Some(Regex::new(&self.find).map_err(|e| format!("Invalid regex: {e}"))?)
+        } else {
+            None
+        };
+        let mut rows: Vec<RenameRow> = self
+            .targets
+            .iter()
+            .enumerate()
+            .map(|(i, e)| {
+                let replace = expand_counter(&self.replace, i + 1);
+                let mut new = match &re {
+                    Some(re) => re.replace_all(&e.name, replace.as_str()).into_owned(),
+                    None if self.find.is_empty() => e.name.clone(),
+                    None => e.name.replace(&self.find, &replace),
+                };
+                if self.normalize {
+                    new = normalize_name(&new, e.kind == EntryKind::Folder);
+                }
+                RenameRow {
+                    old: &e.name,
+                    new,
+                    problem: None,
+                }
+            })
+            .collect();
+
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for row in &rows {
+            *seen.entry(row.new.clone()).or_default() += 1;
+        }
+        for row in &mut rows {
+            row.problem = if row.new.trim().is_empty() {
+                Some("empty name")
+            } else if row.new.contains('/') {
+                Some("contains '/'")
+            } else if seen[&row.new] > 1 || self.others.contains(&row.new) {
+                Some("name taken")
+            } else {
+                None
+            };
+        }
+        Ok(rows)
+    }
+}
+
+impl App {
+    /// `N`: batch rename the marked entries of the current folder.
+    pub(super) fn open_batch_rename(&mut self) {
+        let targets: Vec<Entry> = self
+            .entries
+            .iter()
+            .filter(|e| self.cart_ids.contains(&e.id))
+            .cloned()
+            .collect();
+        if targets.len() < 2 {
+            self.push_log("Mark two or more entries here with 'a' to batch rename".into());
+            return;
+        }
+        let folder = self.filter_source.as_ref().unwrap_or(&self.entries);
+        let state = BatchRename::new(targets, folder);
+        self.input = InputMode::BatchRename(Box::new(state));
+    }
+
+    /// `Z`: tidy up the names of the marked entries here, or of the one
+    /// under the cursor when none are marked, with a preview first.
+    pub(super) fn open_normalize_names(&mut self) {
+        let mut targets: Vec<Entry> = self
+            .entries
+            .iter()
+            .filter(|e| self.cart_ids.contains(&e.id))
+            .cloned()
+            .collect();
+        if targets.is_empty() {
+            targets.extend(self.current_entry().cloned());
+        }
+        if targets.is_empty() {
+            return;
+        }
+        let folder = self.filter_source.as_ref().unwrap_or(&self.entries);
+        let mut state = BatchRename::new(targets, folder);
+        state.normalize = true;
+        self.input = InputMode::BatchRename(Box::new(state));
+    }
+
+    pub(super) fn handle_batch_rename_key(
+        &mut self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+        mut state: Box<BatchRename>,
+    ) {
+        match code {
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Up | KeyCode::Down => {
+                state.on_replace = !state.on_replace;
+            }
+            KeyCode::Char('r') if modifiers.contains(KeyModifiers::CONTROL) => {
+                state.regex = !state.regex;
+            }
+            KeyCode::Char('n') if modifiers.contains(KeyModifiers::CONTROL) => {
+                state.normalize = !state.normalize;
+            }
+            _ => {
+                let field = if state.on_replace {
+                    &mut state.replace
+                } else {
+                    &mut state.find
+                };
+                match handle_text_input(field, code) {
+                    Some(false) => return,
+                    Some(true) if self.commit_batch_rename(&state) => return,
+                    _ => {}
+                }
+            }
+        }
+        self.input = InputMode::BatchRename(state);
+    }
+
+    /// Queue the renames; `false` (with the reason logged) if the preview
+    /// has problems or nothing would change.
+    fn commit_batch_rename(&mut self, state: &BatchRename) -> bool {
+        let rows = match state.preview() {
+            Ok(rows) => rows,
+            Err(e) => {
+                self.push_log(e);
+                return false;
+            }
+        };
+        if let Some(row) = rows.iter().find(|r| r.problem.is_some()) {
+            let problem = row.problem.unwrap_or_default();
+            self.push_log(format!("Can't rename '{}': {}", row.old, problem));
+            return false;
+        }
+        let renames: Vec<(String, String, String)> = state
+            .targets
+            .iter()
+            .zip(&rows)
+            .filter(|(_, row)| row.new != row.old)
+            .map(|(e, row)| (e.id.clone(), e.name.clone(), row.new.clone()))
+            .collect();
+        if renames.is_empty() {
+            self.push_log("Nothing to rename".into());
+            return false;
+        }
+        // The cart's copies still carry the old names.
+        for (id, ..) in &renames {
+            self.cart_ids.remove(id);
+        }
+        self.cart.retain(|e| self.cart_ids.contains(&e.id));
+        self.cart_selected = self.cart_selected.min(self.cart.len().saturating_sub(1));
+        self.queue_op(FileOp::BatchRename { renames });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn swaps_and_chains_rename_in_an_order_that_never_collides() {
+        let renames = |list: &[(&str, &str)]| -> Vec<(String, String, String)> {
+            list.iter()
+                .map(|&(old, new)| (old.to_string(), old.to_string(), new.to_string()))
+                .collect()
+        };
+        // Applies the steps to a folder, checking no step lands on a taken name.
+        let apply = |names: &[&str], steps: &[(String, String, String)]| {
+            let mut folder: HashSet<String> = names.iter().map(|n| n.to_string()).collect();
+            for (_, old, new) in steps {
+                assert!(folder.remove(old), "'{old}' isn't there");
+                assert!(folder.insert(new.clone()), "'{new}' is taken");
+            }
+            let mut folder: Vec<String> = folder.into_iter().collect();
+            folder.sort();
+            folder
+        };
+
+        // a -> b -> c: b has to move out of the way first.
+        let steps = rename_steps(&renames(&[("a", "b"), ("b", "c")]));
+        assert_eq!(apply(&["a", "b"], &steps), ["b", "c"]);
+
+        // A swap goes through a temporary name.
+        let steps = rename_steps(&renames(&[("a", "b"), ("b", "a")]));
+        assert_eq!(steps.len(), 3);
+        assert_eq!(apply(&["a", "b"], &steps), ["a", "b"]);
+        assert_eq!(steps.last().map(|s| s.2.as_str()), Some("b"));
+
+        // A rotation with an unrelated rename alongside.
+        let steps = rename_steps(&renames(&[("x", "y"), ("y", "z"), ("z", "x"), ("p", "q")]));
+        assert_eq!(apply(&["p", "x", "y", "z"], &steps), ["q", "x", "y", "z"]);
+    }
+
+    #[test]
+    fn counter_expands_with_optional_padding() {
+        assert_eq!(expand_counter("ep{n}", 7), "ep7");
+        assert_eq!(expand_counter("ep{n:03}-{n}", 7), "ep007-7");
+        assert_eq!(expand_counter("{n:x} {name}", 2), "{n:x} {name}");
+    }
+
+    #[test]
+    fn preview_applies_regex_groups_and_flags_clashes() {
+        let targets = vec![
+            Entry::file("Show.S01E01.mkv").with_id("1"),
+            Entry::file("Show.S01E02.mkv").with_id("2"),
+        ];
+        let folder = [targets.clone(), vec![Entry::file("E02.mkv").with_id("3")]].concat();
+        let mut state = BatchRename::new(targets, &folder);
+        state.regex = true;
+        state.find = r"^Show\.S(\d+)E(\d+)\.(\w+)$".into();
+        // `$1E` would name a group "1E"; braces end the group number.
+        state.replace = "{n:02} - S${1}E${2}.$3".into();
+        let rows = state.preview().unwrap();
+        assert_eq!(rows[0].new, "01 - S01E01.mkv");
+        assert_eq!(rows[1].new, "02 - S01E02.mkv");
+        assert!(rows.iter().all(|r| r.problem.is_none()));
+
+        state.regex = false;
+        state.find = "Show.S01".into();
+        state.replace = String::new();
+        let rows = state.preview().unwrap();
+        assert_eq!(rows[1].new, "E02.mkv");
+        assert_eq!(rows[1].problem, Some("name taken"));
+
+        state.regex = true;
+        state.find = "(".into();
+        assert!(state.preview().is_err());
+    }
+
+    #[test]
+    fn normalizing_strips_tags_and_title_cases() {
+        assert_eq!(
+            normalize_name(
+                "[SubsPlease] sousou.no.frieren.-.12.(1080p).[A1B2C3D4].mkv",
+                false
+            ),
+            "Sousou No Frieren - 12.mkv"
+        );
+        assert_eq!(
+            normalize_name(
+                "The.Lord.of.the.Rings.(2001).1080p.BluRay.x264-GROUP.mkv",
+                false
+            ),
+            "The Lord of the Rings (2001) 1080p BluRay x264-GROUP.mkv"
+        );
+        assert_eq!(
+            normalize_name("【字幕组】some_show_ep01.mp4", false),
+            // Words with digits (ep01, x264) are left as they are.
+            "Some Show ep01.mp4"
+        );
+        // A folder has no extension to keep.
+        assert_eq!(
+            normalize_name("my.holiday.photos", true),
+            "My Holiday Photos"
+        );
+        // Nothing left but tags: unchanged.
+        assert_eq!(normalize_name("[abc].txt", false), "[abc].txt");
+        // An unclosed bracket is kept as it is.
+        assert_eq!(
+            normalize_name("notes [draft.txt", false),
+            "Notes [draft.txt"
+        );
+    }
+}
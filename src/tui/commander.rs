@@ -0,0 +1,256 @@
+//! Commander mode: two browser panes side by side, orthodox-file-manager
+//! style. The panes are the first two tabs, so the focused one is the live
+//! browser state and the other is drawn from its parked tab, which is
+//! listed on its own (`OpResult::PaneLs`) to stay current. Further tabs
+//! stay parked, out of reach, until commander mode is turned off.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+
+use crate::pikpak::Entry;
+
+use super::batch_confirm::BatchAction;
+use super::ops::{self, FileOp};
+use super::tabs::BrowseTab;
+use super::{App, OpResult, RECENT_FOLDER_ID, is_virtual_folder, list_folder, worker};
+
+impl App {
+    /// Index of the pane without focus.
+    pub(super) fn idle_pane_index(&self) -> usize {
+        if self.active_tab == 0 { 1 } else { 0 }
+    }
+
+    /// The pane without focus, in commander mode.
+    pub(super) fn idle_pane(&self) -> Option<&BrowseTab> {
+        if !self.config.commander_mode {
+            return None;
+        }
+        self.tabs.get(self.idle_pane_index())
+    }
+
+    /// Make the tabs fit commander mode: the first two are the panes, with
+    /// focus on one of them. Any others are kept for when it's turned off.
+    /// A missing second pane opens on the current folder.
+    pub(super) fn ensure_commander_panes(&mut self) {
+        if !self.config.commander_mode {
+            return;
+        }
+        if self.active_tab > 1 {
+            self.switch_tab(0);
+        }
+        if self.tabs.len() > 2 {
+            self.push_log(format!(
+                "Commander mode shows the first two tabs as its panes; {} more tab(s) come back when it's off",
+                self.tabs.len() - 2
+            ));
+        }
+        if self.tabs.len() < 2 {
+            let pane = BrowseTab::at(
+                self.current_folder_id.clone(),
+                self.breadcrumb.clone(),
+                self.entries.clone(),
+            );
+            self.tabs.push(pane);
+            self.refresh_idle_pane();
+        }
+    }
+
+    /// Reload the pane without focus, if any.
+    pub(super) fn refresh_idle_pane(&self) {
+        let Some(pane) = self.idle_pane() else {
+            return;
+        };
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        let fid = pane.folder_id.clone();
+        let days = self.config.recent_days;
+        self.workers.spawn(move || {
+            let result = list_folder(&client, &fid, days, &worker::CancelToken::default());
+            let _ = tx.send(OpResult::PaneLs(fid, result));
+        });
+    }
+
+    /// Swap in a fresh listing of the pane without focus, keeping its
+    /// cursor on the same entry. Dropped if that pane has moved on since.
+    pub(super) fn apply_pane_listing(&mut self, folder_id: String, result: Result<Vec<Entry>>) {
+        let idx = self.idle_pane_index();
+        let mut entries = match result {
+            Ok(entries) => entries,
+            Err(e) if self.went_offline(&e) => match self.client.last_listing(&folder_id) {
+                Some(entries) => entries,
+                None => return,
+            },
+            Err(e) => {
+                self.push_log(format!("Refresh failed: {e:#}"));
+                return;
+            }
+        };
+        if folder_id != RECENT_FOLDER_ID {
+            crate::config::sort_entries(
+                &mut entries,
+                self.config.sort_field,
+                self.config.sort_reverse,
+            );
+        }
+        let Some(pane) = self.tabs.get_mut(idx).filter(|p| p.folder_id == folder_id) else {
+            return;
+        };
+        let prev_id = pane.entries.get(pane.selected).map(|e| e.id.clone());
+        pane.selected = prev_id
+            .and_then(|id| entries.iter().position(|e| e.id == id))
+            .unwrap_or_else(|| pane.selected.min(entries.len().saturating_sub(1)));
+        pane.entries = entries;
+        pane.filter_source = None;
+        pane.filter_query.clear();
+    }
+
+    /// F5 / F6: copy or move the marked entries of the focused pane (or the
+    /// highlighted one when none are marked) into the other pane's folder.
+    /// Marked entries get the batch summary first.
+    pub(super) fn commander_transfer(&mut self, is_move: bool) {
+        let Some(pane) = self.idle_pane() else {
+            return;
+        };
+        let dest_id = pane.folder_id.clone();
+        let dest_path = pane.path_display();
+        let op = if is_move { "Move" } else { "Copy" };
+        if is_virtual_folder(&dest_id) {
+            self.push_log(format!("Can't {} into {}", op.to_lowercase(), dest_path));
+            return;
+        }
+        if dest_id == self.current_folder_id {
+            self.push_log("Both panes show the same folder".into());
+            return;
+        }
+        let marked: Vec<Entry> = self
+            .entries
+            .iter()
+            .filter(|e| self.cart_ids.contains(&e.id))
+            .cloned()
+            .collect();
+        let from = (!self.in_virtual_folder()).then(|| self.current_folder_id.clone());
+        if !marked.is_empty() {
+            let action = if is_move {
+                BatchAction::Move {
+                    to: dest_id,
+                    dest_path,
+                    from,
+                }
+            } else {
+                BatchAction::Copy {
+                    to: dest_id,
+                    dest_path,
+                }
+            };
+            self.open_batch_summary(action, marked, false);
+            return;
+        }
+        let Some(target) = self.current_entry().cloned() else {
+            return;
+        };
+        let what = ops::describe(std::slice::from_ref(&target));
+        let ids = vec![target.id];
+        self.queue_op(if is_move {
+            FileOp::Move {
+                from: vec![from; ids.len()],
+                ids,
+                to: dest_id,
+                dest_path,
+                what,
+            }
+        } else {
+            FileOp::Copy {
+                ids,
+                to: dest_id,
+                dest_path,
+                what,
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::InputMode;
+
+    fn tab(folder_id: &str) -> BrowseTab {
+        BrowseTab::at(folder_id.into(), vec![], vec![])
+    }
+
+    #[test]
+    fn commander_mode_keeps_the_other_tabs_for_later() {
+        let mut app = App::for_tests();
+        app.tabs = vec![tab("a"), tab("b"), BrowseTab::default()];
+        app.active_tab = 2;
+        app.current_folder_id = "c".into();
+
+        app.config.commander_mode = true;
+        app.ensure_commander_panes();
+        assert_eq!(app.tabs.len(), 3);
+        assert_eq!((app.active_tab, app.current_folder_id.as_str()), (0, "a"));
+        assert!(
+            app.logs
+                .iter()
+                .any(|l| l.contains("1 more tab(s) come back"))
+        );
+
+        // Only the panes take focus.
+        app.cycle_tab(true);
+        assert_eq!(app.current_folder_id, "b");
+        app.cycle_tab(true);
+        assert_eq!(app.current_folder_id, "a");
+        app.switch_tab(2);
+        assert_eq!(app.active_tab, 0);
+
+        app.config.commander_mode = false;
+        app.switch_tab(2);
+        assert_eq!(app.current_folder_id, "c");
+    }
+
+    #[test]
+    fn a_single_tab_gets_a_second_pane_on_the_same_folder() {
+        let mut app = App::for_tests();
+        app.current_folder_id = "a".into();
+        app.config.commander_mode = true;
+        app.ensure_commander_panes();
+        assert_eq!(app.tabs.len(), 2);
+        assert_eq!(app.idle_pane().map(|p| p.folder_id.as_str()), Some("a"));
+    }
+
+    #[test]
+    fn transfers_go_to_the_other_panes_folder() {
+        let mut app = App::for_tests();
+        app.config.commander_mode = true;
+        app.current_folder_id = "a".into();
+        app.entries = vec![Entry::file("x.mkv").with_id("x")];
+        app.tabs = vec![BrowseTab::default(), tab("a")];
+
+        app.commander_transfer(false);
+        assert_eq!(
+            app.logs.back().map(String::as_str),
+            Some("Both panes show the same folder")
+        );
+
+        app.tabs[1] = tab(RECENT_FOLDER_ID);
+        app.commander_transfer(true);
+        assert!(
+            app.logs
+                .back()
+                .is_some_and(|l| l.starts_with("Can't move into"))
+        );
+
+        app.tabs[1] = tab("b");
+        app.commander_transfer(false);
+        assert!(matches!(
+            &app.jobs.jobs()[0].op,
+            FileOp::Copy { ids, to, .. } if ids == &["x"] && to == "b"
+        ));
+
+        // Marked entries get the summary first.
+        app.cart_ids.insert("x".into());
+        app.commander_transfer(true);
+        assert!(matches!(app.input, InputMode::ConfirmBatch(_)));
+    }
+}
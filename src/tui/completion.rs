@@ -23,6 +23,12 @@ impl PathInput {
 
 impl App {
     pub(super) fn tab_complete(&self, input: &mut PathInput) {
+        self.tab_complete_from(input, &self.current_folder_id);
+    }
+
+    /// Complete the folder name being typed; a path without a leading `/`
+    /// is completed inside `relative_to`.
+    pub(super) fn tab_complete_from(&self, input: &mut PathInput, relative_to: &str) {
         if !input.candidates.is_empty() {
             let idx = match input.candidate_idx {
                 Some(i) => (i + 1) % input.candidates.len(),
@@ -45,8 +51,7 @@ impl App {
         let (parent_path, prefix) = split_path_prefix(&input.value);
 
         let parent_id = if parent_path.is_empty() {
-            // Relative: use current folder
-            self.current_folder_id.clone()
+            relative_to.to_string()
         } else {
             match self.client.resolve_path(&parent_path) {
                 Ok(id) => id,
@@ -100,6 +105,44 @@ impl App {
     }
 }
 
+/// Views the `:` prompt opens by name, typed with a leading `:`.
+pub(super) const GOTO_VIEWS: &[&str] = &[
+    "starred",
+    "trash",
+    "offline",
+    "recent",
+    "downloads",
+    "shares",
+];
+
+/// Complete a `:view` name in the goto prompt, cycling through the matches
+/// on repeated Tab presses like folder completion does.
+pub(super) fn complete_goto_view(input: &mut PathInput) {
+    if !input.candidates.is_empty() {
+        let idx = input
+            .candidate_idx
+            .map_or(0, |i| (i + 1) % input.candidates.len());
+        input.candidate_idx = Some(idx);
+        input.value = format!(":{}", input.candidates[idx]);
+        return;
+    }
+    let prefix = input.value.trim_start_matches(':').to_ascii_lowercase();
+    let matches: Vec<String> = GOTO_VIEWS
+        .iter()
+        .filter(|v| v.starts_with(&prefix))
+        .map(|v| v.to_string())
+        .collect();
+    match matches.as_slice() {
+        [] => {}
+        [only] => input.value = format!(":{only}"),
+        [first, ..] => {
+            input.value = format!(":{first}");
+            input.candidates = matches;
+            input.candidate_idx = Some(0);
+        }
+    }
+}
+
 /// Split a path input into (parent_path, prefix).
 /// "/My Pack/sub" -> ("/My Pack", "sub")
 /// "/My Pack/"    -> ("/My Pack", "")
@@ -123,3 +166,28 @@ pub(super) fn split_path_prefix(input: &str) -> (String, String) {
         None => (String::new(), input.to_string()),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn goto_views_complete_and_cycle() {
+        let mut input = PathInput::new();
+        input.value = ":st".into();
+        complete_goto_view(&mut input);
+        assert_eq!(input.value, ":starred");
+        assert!(input.candidates.is_empty());
+
+        input.value = ":".into();
+        complete_goto_view(&mut input);
+        assert_eq!(input.value, ":starred");
+        complete_goto_view(&mut input);
+        assert_eq!(input.value, ":trash");
+
+        let mut input = PathInput::new();
+        input.value = ":nope".into();
+        complete_goto_view(&mut input);
+        assert_eq!(input.value, ":nope");
+    }
+}
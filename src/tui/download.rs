@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
 use std::io::{Read as _, Seek, SeekFrom, Write as _};
 use std::path::PathBuf;
@@ -7,9 +7,14 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Receiver, Sender};
 use std::time::Instant;
 
+use rusqlite::Connection;
 use serde::{Deserialize, Serialize};
 
-use crate::pikpak::PikPak;
+use crate::pikpak::{Direction, PikPak, TransferRecord, verify_download};
+use crate::ratelimit::{RateLimiter, SpeedMeter, eta_secs};
+use crate::store::{Store, params};
+
+use super::instance::QueuedDownload;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum TaskStatus {
@@ -20,6 +25,71 @@ pub enum TaskStatus {
     Failed(String),
 }
 
+/// What to do once a task has downloaded, chosen when it's queued and
+/// changed with `w` in the download view.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OnComplete {
+    #[default]
+    Nothing,
+    OpenFolder,
+    Play,
+    /// Run the `download_hook` command.
+    Hook,
+    /// Shut the computer down once nothing is left to download.
+    Shutdown,
+}
+
+impl OnComplete {
+    const ALL: [Self; 5] = [
+        Self::Nothing,
+        Self::OpenFolder,
+        Self::Play,
+        Self::Hook,
+        Self::Shutdown,
+    ];
+
+    /// As saved in the database.
+    fn key(self) -> &'static str {
+        match self {
+            Self::Nothing => "",
+            Self::OpenFolder => "open_folder",
+            Self::Play => "play",
+            Self::Hook => "hook",
+            Self::Shutdown => "shutdown",
+        }
+    }
+
+    fn from_key(key: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|a| a.key() == key)
+            .unwrap_or_default()
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Nothing => "nothing",
+            Self::OpenFolder => "open folder",
+            Self::Play => "play",
+            Self::Hook => "run hook",
+            Self::Shutdown => "shut down when all done",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&a| a == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+/// A finished task whose `on_complete` is still to be carried out.
+pub struct Completion {
+    pub action: OnComplete,
+    pub name: String,
+    pub dest_path: PathBuf,
+}
+
 #[derive(Clone)]
 pub struct DownloadTask {
     /// Stable routing id for worker messages; survives cancel/remove (a Vec
@@ -33,7 +103,74 @@ pub struct DownloadTask {
     pub status: TaskStatus,
     pub pause_flag: Arc<AtomicBool>,
     pub cancel_flag: Arc<AtomicBool>,
-    pub speed: f64, // bytes per second
+    pub speed: f64, // bytes per second, smoothed
+    /// Per-task cap on top of the global one, changed with `+`/`-`.
+    pub limit: Arc<RateLimiter>,
+    /// The job this task was queued with, if it came as part of a batch.
+    pub job: Option<u64>,
+    /// Unix time the download completed.
+    pub finished_at: Option<u64>,
+    pub on_complete: OnComplete,
+}
+
+impl DownloadTask {
+    /// Bytes left to fetch, counting only tasks that are queued or running.
+    pub fn remaining(&self) -> u64 {
+        match self.status {
+            TaskStatus::Pending | TaskStatus::Downloading => {
+                self.total_size.saturating_sub(self.downloaded)
+            }
+            _ => 0,
+        }
+    }
+
+    /// Time left for a running task at its smoothed speed.
+    pub fn eta(&self) -> Option<u64> {
+        if self.status != TaskStatus::Downloading {
+            return None;
+        }
+        eta_secs(self.remaining(), self.speed)
+    }
+}
+
+/// A batch of tasks queued together (a cart download, an auto-fetched
+/// offline task, ...), shown as one collapsible group.
+pub struct Job {
+    pub label: String,
+    pub collapsed: bool,
+}
+
+/// One line of the download list: a job's header or a task (by index).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Row {
+    Job(u64),
+    Task(usize),
+}
+
+/// Combined numbers for the tasks of one job.
+#[derive(Debug, Default, PartialEq)]
+pub struct JobProgress {
+    pub tasks: usize,
+    pub done: usize,
+    pub failed: usize,
+    pub active: usize,
+    pub downloaded: u64,
+    pub total_size: u64,
+    /// Bytes still to fetch by pending and running tasks.
+    pub remaining: u64,
+    pub speed: f64,
+}
+
+impl JobProgress {
+    pub fn percent(&self) -> u64 {
+        (self.downloaded * 100)
+            .checked_div(self.total_size)
+            .unwrap_or(0)
+    }
+
+    pub fn eta(&self) -> Option<u64> {
+        eta_secs(self.remaining, self.speed)
+    }
 }
 
 pub enum DownloadMsg {
@@ -55,15 +192,98 @@ pub enum DownloadMsg {
     },
 }
 
+/// Daily time-of-day window (local time) in which transfers may run, e.g.
+/// `01:00-07:00`. A window whose end is before its start wraps past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferWindow {
+    /// Minutes since midnight.
+    start: u16,
+    end: u16,
+}
+
+impl TransferWindow {
+    /// Parse `HH:MM-HH:MM`. Start and end must differ.
+    pub fn parse(s: &str) -> Option<Self> {
+        let minutes = |hm: &str| {
+            let (h, m) = hm.trim().split_once(':')?;
+            let (h, m): (u16, u16) = (h.parse().ok()?, m.parse().ok()?);
+            (h < 24 && m < 60).then_some(h * 60 + m)
+        };
+        let (start, end) = s.split_once('-')?;
+        let (start, end) = (minutes(start)?, minutes(end)?);
+        (start != end).then_some(Self { start, end })
+    }
+
+    pub fn contains(&self, minute: u16) -> bool {
+        if self.start < self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    pub fn start_label(&self) -> String {
+        format!("{:02}:{:02}", self.start / 60, self.start % 60)
+    }
+}
+
+impl std::fmt::Display for TransferWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}–{:02}:{:02}",
+            self.start_label(),
+            self.end / 60,
+            self.end % 60
+        )
+    }
+}
+
+/// Minutes since local midnight, in the time zone and DST rules in effect
+/// right now.
+pub fn local_minute_of_day() -> u16 {
+    use chrono::Timelike;
+    let now = chrono::Local::now();
+    (now.hour() * 60 + now.minute()) as u16
+}
+
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 pub struct DownloadState {
     pub tasks: Vec<DownloadTask>,
+    /// Jobs by id, in the order they were queued.
+    pub jobs: BTreeMap<u64, Job>,
+    /// Index into `rows()`, not `tasks`.
     pub selected: usize,
     pub msg_tx: Sender<DownloadMsg>,
     pub msg_rx: Receiver<DownloadMsg>,
     /// Task ids that currently have a live (running or parked-paused) worker.
     pub active_ids: HashSet<u64>,
     pub max_concurrent: usize,
+    /// Only run downloads inside this window; `None` runs them any time.
+    pub window: Option<TransferWindow>,
+    /// Whether the window was open at the last `apply_window`.
+    pub window_open: bool,
+    /// Tasks the schedule paused, to be resumed when the window opens (tasks
+    /// the user paused stay paused).
+    held: HashSet<u64>,
+    /// Finished tasks older than this many days are dropped (0 = kept until
+    /// cleared with `c`).
+    pub keep_finished_days: u64,
+    /// Finished tasks with something to do, taken by the TUI.
+    pub completions: Vec<Completion>,
+    /// The saved queue as of this instance's last load or save of it; any
+    /// other task found there was added by another instance.
+    saved: HashSet<SavedKey>,
+    /// A task set to shut down has finished.
+    shutdown_armed: bool,
     next_id: u64,
+    next_job: u64,
 }
 
 impl DownloadState {
@@ -71,12 +291,21 @@ impl DownloadState {
         let (tx, rx) = std::sync::mpsc::channel();
         Self {
             tasks: Vec::new(),
+            jobs: BTreeMap::new(),
             selected: 0,
             msg_tx: tx,
             msg_rx: rx,
             active_ids: HashSet::new(),
             max_concurrent: max_concurrent.max(1),
+            window: None,
+            window_open: true,
+            held: HashSet::new(),
+            keep_finished_days: 0,
+            completions: Vec::new(),
+            saved: HashSet::new(),
+            shutdown_armed: false,
             next_id: 0,
+            next_job: 0,
         }
     }
 
@@ -86,13 +315,185 @@ impl DownloadState {
         id
     }
 
-    /// Replace the task list (e.g. from persisted state), assigning fresh ids.
-    pub fn load_tasks(&mut self, mut tasks: Vec<DownloadTask>) {
-        for (i, t) in tasks.iter_mut().enumerate() {
-            t.id = i as u64;
+    /// Add persisted tasks after the current ones, assigning fresh ids,
+    /// regrouping them by their job label and skipping any already queued.
+    pub fn load_tasks(&mut self, tasks: Vec<(DownloadTask, Option<String>)>) {
+        let mut by_label: BTreeMap<String, u64> = BTreeMap::new();
+        for (mut task, label) in tasks {
+            let key = (
+                task.file_id.clone(),
+                task.dest_path.to_string_lossy().into_owned(),
+            );
+            self.saved.insert(key);
+            // Already here: queued in this instance before it took over.
+            if self
+                .tasks
+                .iter()
+                .any(|t| t.file_id == task.file_id && t.dest_path == task.dest_path)
+            {
+                continue;
+            }
+            task.id = self.alloc_id();
+            task.job = label.map(|label| match by_label.get(&label) {
+                Some(&job) => job,
+                None => {
+                    let job = self.add_job(label.clone());
+                    by_label.insert(label, job);
+                    job
+                }
+            });
+            self.tasks.push(task);
+        }
+    }
+
+    pub fn add_job(&mut self, label: String) -> u64 {
+        let job = self.next_job;
+        self.next_job += 1;
+        self.jobs.insert(
+            job,
+            Job {
+                label,
+                collapsed: false,
+            },
+        );
+        job
+    }
+
+    /// The list as shown: each job's header where its first task would be,
+    /// followed by its tasks unless it's collapsed.
+    pub fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        let mut seen = HashSet::new();
+        for (i, task) in self.tasks.iter().enumerate() {
+            match task.job.filter(|j| self.jobs.contains_key(j)) {
+                None => rows.push(Row::Task(i)),
+                Some(job) => {
+                    if !seen.insert(job) {
+                        continue;
+                    }
+                    rows.push(Row::Job(job));
+                    if !self.jobs[&job].collapsed {
+                        rows.extend(
+                            self.tasks
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, t)| t.job == Some(job))
+                                .map(|(i, _)| Row::Task(i)),
+                        );
+                    }
+                }
+            }
+        }
+        rows
+    }
+
+    pub fn selected_row(&self) -> Option<Row> {
+        self.rows().get(self.selected).copied()
+    }
+
+    /// The selected task, when the cursor is on a task rather than a job.
+    pub fn selected_task(&self) -> Option<usize> {
+        match self.selected_row() {
+            Some(Row::Task(i)) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Indices of the tasks a key acts on: the selected task, or every task
+    /// of the selected job.
+    pub fn selected_tasks(&self) -> Vec<usize> {
+        match self.selected_row() {
+            Some(Row::Task(i)) => vec![i],
+            Some(Row::Job(job)) => self
+                .tasks
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| t.job == Some(job))
+                .map(|(i, _)| i)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn job_progress(&self, job: u64) -> JobProgress {
+        let mut p = JobProgress::default();
+        for task in self.tasks.iter().filter(|t| t.job == Some(job)) {
+            p.tasks += 1;
+            p.downloaded += task.downloaded.min(task.total_size);
+            p.total_size += task.total_size;
+            p.remaining += task.remaining();
+            match task.status {
+                TaskStatus::Done => p.done += 1,
+                TaskStatus::Failed(_) => p.failed += 1,
+                TaskStatus::Downloading => {
+                    p.active += 1;
+                    p.speed += task.speed;
+                }
+                _ => {}
+            }
         }
-        self.next_id = tasks.len() as u64;
-        self.tasks = tasks;
+        p
+    }
+
+    /// Fold or unfold the job under the cursor (or the selected task's job),
+    /// leaving the cursor on its header.
+    pub fn toggle_job(&mut self) {
+        let job = match self.selected_row() {
+            Some(Row::Job(job)) => job,
+            Some(Row::Task(i)) => match self.tasks[i].job {
+                Some(job) => job,
+                None => return,
+            },
+            None => return,
+        };
+        if let Some(j) = self.jobs.get_mut(&job) {
+            j.collapsed = !j.collapsed;
+        }
+        if let Some(pos) = self.rows().iter().position(|r| *r == Row::Job(job)) {
+            self.selected = pos;
+        }
+    }
+
+    /// Drop tasks by id, forget jobs left empty and keep the cursor in range.
+    pub fn remove_tasks(&mut self, ids: &HashSet<u64>) {
+        self.tasks.retain(|t| !ids.contains(&t.id));
+        let tasks = &self.tasks;
+        self.jobs
+            .retain(|job, _| tasks.iter().any(|t| t.job == Some(*job)));
+        self.selected = self.selected.min(self.rows().len().saturating_sub(1));
+    }
+
+    /// Drop finished tasks that completed more than `keep_finished_days`
+    /// ago. Returns how many went.
+    pub fn prune_finished(&mut self, now: u64) -> usize {
+        if self.keep_finished_days == 0 {
+            return 0;
+        }
+        let cutoff = now.saturating_sub(self.keep_finished_days * 86_400);
+        let ids: HashSet<u64> = self
+            .tasks
+            .iter()
+            .filter(|t| t.status == TaskStatus::Done && t.finished_at.is_some_and(|f| f < cutoff))
+            .map(|t| t.id)
+            .collect();
+        if !ids.is_empty() {
+            self.remove_tasks(&ids);
+        }
+        ids.len()
+    }
+
+    /// Drop every finished task. Returns how many went.
+    pub fn clear_finished(&mut self) -> usize {
+        let ids: HashSet<u64> = self
+            .tasks
+            .iter()
+            .filter(|t| t.status == TaskStatus::Done)
+            .map(|t| t.id)
+            .collect();
+        if !ids.is_empty() {
+            self.remove_tasks(&ids);
+        }
+        ids.len()
     }
 
     pub fn done_count(&self) -> usize {
@@ -102,14 +503,117 @@ impl DownloadState {
             .count()
     }
 
+    /// Time to finish every pending and running task at the current
+    /// combined speed; `None` while nothing is moving.
+    pub fn queue_eta(&self) -> Option<u64> {
+        let remaining = self.tasks.iter().map(DownloadTask::remaining).sum();
+        let speed = self
+            .tasks
+            .iter()
+            .filter(|t| t.status == TaskStatus::Downloading)
+            .map(|t| t.speed)
+            .sum();
+        eta_secs(remaining, speed)
+    }
+
+    /// Whether any task is still pending, running or paused.
+    pub fn has_unfinished(&self) -> bool {
+        self.tasks.iter().any(|t| {
+            matches!(
+                t.status,
+                TaskStatus::Downloading | TaskStatus::Pending | TaskStatus::Paused
+            )
+        })
+    }
+
+    /// Whether a task set to shut down has finished since the last call.
+    pub fn take_shutdown(&mut self) -> bool {
+        std::mem::take(&mut self.shutdown_armed)
+    }
+
     pub fn has_active(&self) -> bool {
         self.tasks
             .iter()
             .any(|t| matches!(t.status, TaskStatus::Downloading | TaskStatus::Pending))
     }
 
-    /// Start pending tasks up to max_concurrent slots.
+    /// Combined progress of the unfinished (pending, downloading or paused)
+    /// tasks, or `None` when there are none.
+    pub fn overall_progress(&self) -> Option<super::term_status::Progress> {
+        let open: Vec<&DownloadTask> = self
+            .tasks
+            .iter()
+            .filter(|t| {
+                matches!(
+                    t.status,
+                    TaskStatus::Pending | TaskStatus::Downloading | TaskStatus::Paused
+                )
+            })
+            .collect();
+        if open.is_empty() {
+            return None;
+        }
+        let total: u64 = open.iter().map(|t| t.total_size).sum();
+        let done: u64 = open.iter().map(|t| t.downloaded.min(t.total_size)).sum();
+        let pct = if total == 0 {
+            0
+        } else {
+            (done as u128 * 100 / total as u128) as u8
+        };
+        let paused = open.iter().all(|t| t.status == TaskStatus::Paused);
+        Some((pct, paused))
+    }
+
+    /// Pause running tasks when the transfer window closes and resume them
+    /// when it opens again. Returns a log line when the window changes.
+    pub fn apply_window(&mut self, client: &Arc<PikPak>, minute: u16) -> Option<String> {
+        let window = self.window?;
+        let open = window.contains(minute);
+        if open == self.window_open {
+            return None;
+        }
+        self.window_open = open;
+        if open {
+            let held = std::mem::take(&mut self.held);
+            for task in &mut self.tasks {
+                if held.contains(&task.id) && task.status == TaskStatus::Paused {
+                    task.pause_flag.store(false, Ordering::Relaxed);
+                    // Same rule as a manual resume: a parked worker carries on,
+                    // otherwise the task goes back in the queue.
+                    task.status = if self.active_ids.contains(&task.id) {
+                        TaskStatus::Downloading
+                    } else {
+                        TaskStatus::Pending
+                    };
+                }
+            }
+            self.start_next(client);
+            Some(format!(
+                "Transfer window {} open, resuming downloads",
+                window
+            ))
+        } else {
+            for task in &mut self.tasks {
+                if task.status == TaskStatus::Downloading {
+                    task.pause_flag.store(true, Ordering::Relaxed);
+                    task.status = TaskStatus::Paused;
+                    self.held.insert(task.id);
+                }
+            }
+            Some(format!(
+                "Outside transfer window {}, downloads wait until {}",
+                window,
+                window.start_label()
+            ))
+        }
+    }
+
+    /// Start pending tasks up to max_concurrent slots. Nothing starts while
+    /// the transfer window is closed.
     pub fn start_next(&mut self, client: &Arc<PikPak>) {
+        if !self.window_open {
+            return;
+        }
         loop {
             let active = self
                 .tasks
@@ -137,6 +641,7 @@ impl DownloadState {
                         self.msg_tx.clone(),
                         Arc::clone(&self.tasks[idx].pause_flag),
                         Arc::clone(&self.tasks[idx].cancel_flag),
+                        Arc::clone(&self.tasks[idx].limit),
                     );
                 }
                 None => break,
@@ -168,7 +673,17 @@ impl DownloadState {
                     if let Some(task) = self.tasks.iter_mut().find(|t| t.id == id) {
                         task.status = TaskStatus::Done;
                         task.downloaded = task.total_size;
+                        task.finished_at = Some(unix_now());
                         logs.push(format!("Downloaded '{}'", task.name));
+                        match task.on_complete {
+                            OnComplete::Nothing => {}
+                            OnComplete::Shutdown => self.shutdown_armed = true,
+                            action => self.completions.push(Completion {
+                                action,
+                                name: task.name.clone(),
+                                dest_path: task.dest_path.clone(),
+                            }),
+                        }
                     }
                     self.active_ids.remove(&id);
                     self.start_next(client);
@@ -187,6 +702,7 @@ impl DownloadState {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_download_worker(
     client: Arc<PikPak>,
     id: u64,
@@ -195,6 +711,7 @@ fn spawn_download_worker(
     msg_tx: Sender<DownloadMsg>,
     pause_flag: Arc<AtomicBool>,
     cancel_flag: Arc<AtomicBool>,
+    limit: Arc<RateLimiter>,
 ) {
     std::thread::spawn(move || {
         if let Err(e) = download_worker(
@@ -205,6 +722,7 @@ fn spawn_download_worker(
             &msg_tx,
             &pause_flag,
             &cancel_flag,
+            &limit,
         ) {
             let _ = msg_tx.send(DownloadMsg::Failed {
                 id,
@@ -214,6 +732,7 @@ fn spawn_download_worker(
     });
 }
 
+#[allow(clippy::too_many_arguments)]
 fn download_worker(
     client: &PikPak,
     id: u64,
@@ -222,8 +741,14 @@ fn download_worker(
     msg_tx: &Sender<DownloadMsg>,
     pause_flag: &Arc<AtomicBool>,
     cancel_flag: &Arc<AtomicBool>,
+    limit: &RateLimiter,
 ) -> anyhow::Result<()> {
-    let (url, total_size) = client.download_url(file_id)?;
+    let info = client.file_info(file_id)?;
+    let url = info
+        .download_url()
+        .ok_or_else(|| anyhow::anyhow!("no download link for file {}", file_id))?
+        .to_string();
+    let total_size = info.file_size();
 
     let _ = msg_tx.send(DownloadMsg::Started { id, total_size });
 
@@ -239,6 +764,7 @@ fn download_worker(
     }
 
     // Shared range/resume contract with the CLI download (see download_stream).
+    let started = Instant::now();
     let (response, start_offset) = client.download_stream(&url, existing_size)?;
 
     let mut file = if start_offset > 0 {
@@ -258,6 +784,7 @@ fn download_worker(
     let mut buf = [0u8; 65536]; // 64KB chunks
     let mut last_report = Instant::now();
     let mut last_report_bytes = downloaded;
+    let mut meter = SpeedMeter::default();
     let speed_interval = std::time::Duration::from_millis(500);
 
     loop {
@@ -265,24 +792,30 @@ fn download_worker(
             return Ok(());
         }
 
-        while pause_flag.load(Ordering::Relaxed) {
-            if cancel_flag.load(Ordering::Relaxed) {
-                return Ok(());
+        if pause_flag.load(Ordering::Relaxed) {
+            while pause_flag.load(Ordering::Relaxed) {
+                if cancel_flag.load(Ordering::Relaxed) {
+                    return Ok(());
+                }
+                std::thread::sleep(std::time::Duration::from_millis(200));
             }
-            std::thread::sleep(std::time::Duration::from_millis(200));
+            // Time spent paused isn't a slowdown.
+            last_report = Instant::now();
         }
 
         let n = reader.read(&mut buf)?;
         if n == 0 {
             break;
         }
+        limit.acquire(n);
+        client.download_limiter.acquire(n);
 
         file.write_all(&buf[..n])?;
         downloaded += n as u64;
 
         let elapsed = last_report.elapsed();
         if elapsed >= speed_interval {
-            let speed = (downloaded - last_report_bytes) as f64 / elapsed.as_secs_f64();
+            let speed = meter.record(downloaded - last_report_bytes, elapsed);
             let _ = msg_tx.send(DownloadMsg::Progress {
                 id,
                 downloaded,
@@ -292,32 +825,136 @@ fn download_worker(
             last_report_bytes = downloaded;
         }
     }
+    drop(file);
 
+    let record = TransferRecord::new(
+        Direction::Download,
+        file_id,
+        &info.name,
+        downloaded,
+        dest,
+        downloaded - start_offset,
+        started.elapsed(),
+    );
+    verify_download(record, dest, info.hash.as_deref().unwrap_or_default())?;
     let _ = msg_tx.send(DownloadMsg::Done { id });
     Ok(())
 }
 
-#[derive(Serialize, Deserialize)]
+/// Run the `download_hook` command for a finished download and wait for
+/// it.
+pub fn run_hook(command: &str, done: &Completion) -> anyhow::Result<()> {
+    let path = done.dest_path.to_string_lossy();
+    let words: Vec<String> = crate::local_path::split_command(command)
+        .into_iter()
+        .map(|w| w.replace("{path}", &path).replace("{name}", &done.name))
+        .collect();
+    let (program, args) = words
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("download_hook is empty"))?;
+    let status = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| anyhow::anyhow!("failed to run {program}: {e}"))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("{program} failed ({status})"));
+    }
+    Ok(())
+}
+
+/// A task as saved in the database (and, by older versions, in
+/// `downloads.json`).
+#[derive(Deserialize)]
 struct PersistedTask {
     file_id: String,
     name: String,
     total_size: u64,
     downloaded: u64,
     dest_path: String,
-    status: String, // "pending", "paused", "failed" (Done tasks aren't persisted)
+    status: String, // "pending", "paused", "failed", "done"
+    #[serde(default)]
+    limit: u64,
+    #[serde(default)]
+    job: Option<String>,
+    #[serde(default)]
+    finished_at: Option<u64>,
+    #[serde(default)]
+    on_complete: String,
+}
+
+/// A saved task's file id and destination, which tell tasks apart.
+type SavedKey = (String, String);
+
+impl PersistedTask {
+    fn key(&self) -> SavedKey {
+        (self.file_id.clone(), self.dest_path.clone())
+    }
+}
+
+/// Where older versions saved the queue.
+fn legacy_path() -> Option<PathBuf> {
+    dirs::config_dir()
+        .map(|d| crate::config::profile_dir(d.join("pikpaktui")).join("downloads.json"))
 }
 
-fn persist_path() -> Option<PathBuf> {
-    dirs::config_dir().map(|d| d.join("pikpaktui").join("downloads.json"))
+/// The database, with a `downloads.json` from an older version brought in.
+fn open_store() -> anyhow::Result<Store> {
+    let mut store = Store::open_default()?;
+    if let Some(path) = legacy_path() {
+        store.import_legacy(&path, |tx, raw| {
+            let persisted: Vec<PersistedTask> = serde_json::from_str(raw)?;
+            write_tasks(tx, &persisted)
+        })?;
+    }
+    Ok(store)
 }
 
-pub fn save_download_state(tasks: &[DownloadTask]) {
-    let Some(path) = persist_path() else {
+/// Replace the saved queue with `tasks`.
+fn write_tasks(conn: &Connection, tasks: &[PersistedTask]) -> anyhow::Result<()> {
+    conn.execute("DELETE FROM downloads", [])?;
+    let mut insert = conn.prepare(
+        "INSERT INTO downloads (seq, file_id, name, total_size, downloaded, dest_path, status,
+         rate_limit, job, finished_at, on_complete)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+    )?;
+    for (seq, t) in tasks.iter().enumerate() {
+        insert.execute(params![
+            seq as i64,
+            t.file_id,
+            t.name,
+            t.total_size,
+            t.downloaded,
+            t.dest_path,
+            t.status,
+            t.limit,
+            t.job,
+            t.finished_at,
+            t.on_complete
+        ])?;
+    }
+    Ok(())
+}
+
+/// Save the queue, keeping the tasks another instance added to the saved
+/// one since this one last loaded or saved it.
+pub fn save_download_state(state: &mut DownloadState) {
+    let Ok(mut store) = open_store() else {
         return;
     };
-    let persisted: Vec<PersistedTask> = tasks
+    if let Ok(tx) = store.transaction()
+        && save_tasks(&tx, state).is_ok()
+    {
+        let _ = tx.commit();
+    }
+}
+
+fn save_tasks(conn: &Connection, state: &mut DownloadState) -> anyhow::Result<()> {
+    let mut persisted: Vec<PersistedTask> = state
+        .tasks
         .iter()
-        .filter(|t| !matches!(t.status, TaskStatus::Done))
         .map(|t| PersistedTask {
             file_id: t.file_id.clone(),
             name: t.name.clone(),
@@ -331,47 +968,105 @@ pub fn save_download_state(tasks: &[DownloadTask]) {
                 // (no worker survives the restart).
                 TaskStatus::Downloading => "paused".into(),
                 TaskStatus::Paused => "paused".into(),
-                // Done tasks are filtered out above, so this is never reached.
-                TaskStatus::Done => unreachable!("Done tasks are not persisted"),
+                TaskStatus::Done => "done".into(),
                 TaskStatus::Failed(_) => "failed".into(),
             },
+            limit: t.limit.rate(),
+            job: t
+                .job
+                .and_then(|j| state.jobs.get(&j))
+                .map(|j| j.label.clone()),
+            finished_at: t.finished_at,
+            on_complete: t.on_complete.key().to_string(),
         })
         .collect();
+    let ours: HashSet<SavedKey> = persisted.iter().map(PersistedTask::key).collect();
+    let added_elsewhere = read_tasks(conn)
+        .into_iter()
+        .filter(|p| !state.saved.contains(&p.key()) && !ours.contains(&p.key()));
+    persisted.extend(added_elsewhere);
+    write_tasks(conn, &persisted)?;
+    state.saved = persisted.iter().map(PersistedTask::key).collect();
+    Ok(())
+}
 
-    if persisted.is_empty() {
-        let _ = fs::remove_file(&path);
-        return;
-    }
-
-    if let Some(parent) = path.parent() {
-        let _ = fs::create_dir_all(parent);
-    }
-    if let Ok(json) = serde_json::to_string_pretty(&persisted) {
-        let tmp_path = path.with_extension("tmp");
-        if fs::write(&tmp_path, &json).is_ok() {
-            let _ = fs::rename(&tmp_path, &path);
+/// Add `batch` after the saved queue, as a job called `label`, for the
+/// next instance to load. Tasks already saved are skipped; returns how many
+/// were added.
+pub(super) fn append_saved(batch: &[QueuedDownload], label: &str) -> anyhow::Result<usize> {
+    let mut store = open_store()?;
+    let tx = store.transaction()?;
+    let mut added = 0;
+    let next: i64 = tx.query_row("SELECT COALESCE(MAX(seq) + 1, 0) FROM downloads", [], |r| {
+        r.get(0)
+    })?;
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO downloads (seq, file_id, name, total_size, downloaded, dest_path, status,
+             rate_limit, job, on_complete)
+             SELECT ?1, ?2, ?3, ?4, 0, ?5, 'pending', 0, ?6, ?7
+             WHERE NOT EXISTS (SELECT 1 FROM downloads WHERE file_id = ?2 AND dest_path = ?5)",
+        )?;
+        for item in batch {
+            added += insert.execute(params![
+                next + added as i64,
+                item.file_id,
+                item.name,
+                item.total_size,
+                item.dest_path.to_string_lossy(),
+                label,
+                item.on_complete.key()
+            ])?;
         }
     }
+    tx.commit()?;
+    Ok(added)
 }
 
-pub fn load_download_state() -> Vec<DownloadTask> {
-    let Some(path) = persist_path() else {
-        return Vec::new();
-    };
-    let Ok(data) = fs::read_to_string(&path) else {
+/// The saved queue's rows, in order.
+fn read_tasks(conn: &Connection) -> Vec<PersistedTask> {
+    let Ok(mut stmt) = conn.prepare(
+        "SELECT file_id, name, total_size, downloaded, dest_path, status, rate_limit, job,
+         finished_at, on_complete FROM downloads ORDER BY seq",
+    ) else {
         return Vec::new();
     };
-    let Ok(persisted): Result<Vec<PersistedTask>, _> = serde_json::from_str(&data) else {
+    stmt.query_map([], |r| {
+        Ok(PersistedTask {
+            file_id: r.get(0)?,
+            name: r.get(1)?,
+            total_size: r.get(2)?,
+            downloaded: r.get(3)?,
+            dest_path: r.get(4)?,
+            status: r.get(5)?,
+            limit: r.get(6)?,
+            job: r.get(7)?,
+            finished_at: r.get(8)?,
+            on_complete: r.get(9)?,
+        })
+    })
+    .map(|rows| rows.filter_map(|r| r.ok()).collect())
+    .unwrap_or_default()
+}
+
+/// Saved tasks with the label of the job each belonged to.
+pub fn load_download_state() -> Vec<(DownloadTask, Option<String>)> {
+    let Ok(store) = open_store() else {
         return Vec::new();
     };
+    let persisted = read_tasks(store.conn());
 
     persisted
         .into_iter()
         .map(|p| {
-            // Everything reloads as Paused (no live worker survives a restart);
-            // the user resumes from the partial file.
-            let status = TaskStatus::Paused;
-            DownloadTask {
+            // Unfinished tasks reload as Paused (no live worker survives a
+            // restart); the user resumes from the partial file.
+            let status = if p.status == "done" {
+                TaskStatus::Done
+            } else {
+                TaskStatus::Paused
+            };
+            let task = DownloadTask {
                 id: 0, // reassigned by DownloadState::load_tasks
                 file_id: p.file_id,
                 name: p.name,
@@ -382,7 +1077,12 @@ pub fn load_download_state() -> Vec<DownloadTask> {
                 status,
                 cancel_flag: Arc::new(AtomicBool::new(false)),
                 speed: 0.0,
-            }
+                limit: Arc::new(RateLimiter::new(p.limit)),
+                job: None,
+                finished_at: p.finished_at,
+                on_complete: OnComplete::from_key(&p.on_complete),
+            };
+            (task, p.job)
         })
         .collect()
 }
@@ -403,9 +1103,42 @@ mod tests {
             pause_flag: Arc::new(AtomicBool::new(false)),
             cancel_flag: Arc::new(AtomicBool::new(false)),
             speed: 0.0,
+            limit: Arc::default(),
+            job: None,
+            finished_at: None,
+            on_complete: OnComplete::Nothing,
         }
     }
 
+    // Two instances share the saved queue: each keeps what the other added,
+    // and a task it removed itself stays removed.
+    #[test]
+    fn saving_keeps_tasks_another_instance_added() {
+        let dir = std::env::temp_dir().join(format!("pikpaktui-dl-merge-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let store = Store::open(&dir).unwrap();
+        let saved = |state: &mut DownloadState| {
+            save_tasks(store.conn(), state).unwrap();
+            read_tasks(store.conn())
+                .into_iter()
+                .map(|t| t.file_id)
+                .collect::<Vec<_>>()
+        };
+
+        let mut first = DownloadState::new(2);
+        first.tasks.push(downloading_task(0, "a"));
+        assert_eq!(saved(&mut first), ["a"]);
+
+        let mut second = DownloadState::new(2);
+        second.tasks.push(downloading_task(0, "b"));
+        assert_eq!(saved(&mut second), ["b", "a"]);
+
+        first.tasks.clear();
+        first.tasks.push(downloading_task(1, "c"));
+        assert_eq!(saved(&mut first), ["c", "b"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
     // Cancelling a task removes it from the Vec, shifting later positions. A
     // worker's message must still reach the right task by its stable id, not by
     // the now-stale position.
@@ -460,4 +1193,148 @@ mod tests {
         // No second worker: the task is left Pending, unspawned.
         assert_eq!(state.tasks[0].status, TaskStatus::Pending);
     }
+
+    #[test]
+    fn overall_progress_ignores_finished_tasks() {
+        let mut state = DownloadState::new(2);
+        assert_eq!(state.overall_progress(), None);
+        let mut a = downloading_task(0, "a");
+        a.downloaded = 50;
+        let mut b = downloading_task(1, "b");
+        b.total_size = 300;
+        b.downloaded = 150;
+        let mut done = downloading_task(2, "done");
+        done.status = TaskStatus::Done;
+        state.tasks = vec![a, b, done];
+        assert_eq!(state.overall_progress(), Some((50, false)));
+
+        for t in &mut state.tasks[..2] {
+            t.status = TaskStatus::Paused;
+        }
+        assert_eq!(state.overall_progress(), Some((50, true)));
+    }
+
+    #[test]
+    fn a_finished_shutdown_task_is_taken_once() {
+        let mut state = DownloadState::new(2);
+        assert!(!state.take_shutdown());
+        state.shutdown_armed = true;
+        assert!(state.take_shutdown());
+        assert!(!state.take_shutdown());
+        assert_eq!(
+            OnComplete::from_key(OnComplete::Hook.key()),
+            OnComplete::Hook
+        );
+        assert_eq!(OnComplete::from_key("bogus"), OnComplete::Nothing);
+    }
+
+    #[test]
+    fn transfer_window_parses_and_wraps_midnight() {
+        let night = TransferWindow::parse("23:30-07:00").unwrap();
+        assert!(night.contains(23 * 60 + 45));
+        assert!(night.contains(60));
+        assert!(!night.contains(7 * 60));
+        assert!(!night.contains(12 * 60));
+        assert_eq!(night.to_string(), "23:30–07:00");
+
+        let day = TransferWindow::parse(" 01:00 - 07:00 ").unwrap();
+        assert!(day.contains(60) && !day.contains(0));
+        assert_eq!(TransferWindow::parse("01:00-01:00"), None);
+        assert_eq!(TransferWindow::parse("25:00-07:00"), None);
+        assert_eq!(TransferWindow::parse("01:00"), None);
+    }
+
+    #[test]
+    fn closing_window_holds_running_tasks_until_it_reopens() {
+        let client = Arc::new(PikPak::new().unwrap());
+        let mut state = DownloadState::new(2);
+        state.window = TransferWindow::parse("01:00-07:00");
+        let mut mine = downloading_task(1, "paused-by-user");
+        mine.status = TaskStatus::Paused;
+        state.tasks = vec![downloading_task(0, "a"), mine];
+        state.active_ids.extend([0, 1]);
+
+        assert!(state.apply_window(&client, 8 * 60).is_some());
+        assert_eq!(state.tasks[0].status, TaskStatus::Paused);
+        assert!(state.tasks[0].pause_flag.load(Ordering::Relaxed));
+        assert!(state.apply_window(&client, 9 * 60).is_none());
+
+        assert!(state.apply_window(&client, 2 * 60).is_some());
+        assert_eq!(state.tasks[0].status, TaskStatus::Downloading);
+        assert!(!state.tasks[0].pause_flag.load(Ordering::Relaxed));
+        assert_eq!(state.tasks[1].status, TaskStatus::Paused);
+    }
+
+    // A job's header sits where its first task was queued; folding it hides
+    // the tasks but keeps ungrouped ones around it, and cancelling the last
+    // of its tasks drops the job.
+    #[test]
+    fn rows_group_tasks_under_jobs() {
+        let mut state = DownloadState::new(2);
+        for name in ["solo", "a", "b", "later"] {
+            let id = state.alloc_id();
+            state.tasks.push(downloading_task(id, name));
+        }
+        let job = state.add_job("Cart: 2 files".into());
+        state.tasks[1].job = Some(job);
+        state.tasks[2].job = Some(job);
+
+        assert_eq!(
+            state.rows(),
+            vec![
+                Row::Task(0),
+                Row::Job(job),
+                Row::Task(1),
+                Row::Task(2),
+                Row::Task(3)
+            ]
+        );
+        state.selected = 1;
+        assert_eq!(state.selected_tasks(), vec![1, 2]);
+        assert_eq!(state.job_progress(job).total_size, 200);
+
+        state.selected = 2;
+        state.toggle_job();
+        assert_eq!(
+            state.rows(),
+            vec![Row::Task(0), Row::Job(job), Row::Task(3)]
+        );
+        assert_eq!(state.selected_row(), Some(Row::Job(job)));
+
+        state.remove_tasks(&HashSet::from([1, 2]));
+        assert!(state.jobs.is_empty());
+        assert_eq!(state.rows(), vec![Row::Task(0), Row::Task(1)]);
+    }
+
+    // Only finished tasks past the retention go; unfinished ones and those
+    // without a completion time stay whatever their age.
+    #[test]
+    fn prune_finished_drops_only_old_done_tasks() {
+        let day = 86_400;
+        let now = 100 * day;
+        let mut state = DownloadState::new(1);
+        state.keep_finished_days = 7;
+        for (name, status, finished_at) in [
+            ("old", TaskStatus::Done, Some(now - 8 * day)),
+            ("recent", TaskStatus::Done, Some(now - day)),
+            ("unknown", TaskStatus::Done, None),
+            ("paused", TaskStatus::Paused, None),
+        ] {
+            let id = state.alloc_id();
+            let mut task = downloading_task(id, name);
+            task.status = status;
+            task.finished_at = finished_at;
+            state.tasks.push(task);
+        }
+
+        assert_eq!(state.prune_finished(now), 1);
+        let names: Vec<&str> = state.tasks.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["recent", "unknown", "paused"]);
+
+        assert_eq!(state.clear_finished(), 2);
+        assert_eq!(state.tasks.len(), 1);
+
+        state.keep_finished_days = 0;
+        assert_eq!(state.prune_finished(u64::MAX), 0);
+    }
 }
@@ -5,8 +5,10 @@ use ratatui::text::{Line, Span};
 use ratatui::widgets::{List, ListItem, ListState, Paragraph};
 use std::collections::VecDeque;
 
-use super::download::TaskStatus;
+use super::download::{OnComplete, Row, TaskStatus};
+use super::when_done::WhenDone;
 use super::{App, SPINNER_FRAMES, centered_rect, format_size, truncate_name};
+use crate::ratelimit::format_duration;
 
 /// Download view mode: collapsed (centered popup) or expanded (full screen)
 #[derive(Clone, Copy, PartialEq, Eq)]
@@ -17,6 +19,8 @@ pub enum DownloadViewMode {
 
 pub struct NetworkStats {
     pub speed_history: VecDeque<f64>, // Last N data points (MB/s)
+    /// API requests held back by the rate limit, sampled with the speed.
+    pub api_queue: VecDeque<usize>,
     pub max_history_points: usize,
 }
 
@@ -24,21 +28,39 @@ impl NetworkStats {
     pub fn new() -> Self {
         Self {
             speed_history: VecDeque::new(),
-            max_history_points: 60, // 30 seconds of history at 0.5s interval
+            api_queue: VecDeque::new(),
+            max_history_points: 600, // 5 minutes of history at 0.5s interval
         }
     }
 
-    pub fn update(&mut self, current_speed: f64) {
+    pub fn update(&mut self, current_speed: f64, api_waiting: usize) {
         self.speed_history.push_back(current_speed);
+        self.api_queue.push_back(api_waiting);
         if self.speed_history.len() > self.max_history_points {
             self.speed_history.pop_front();
+            self.api_queue.pop_front();
         }
     }
 
+    /// API requests waiting on the rate limit now, and at most over the
+    /// history window.
+    pub fn api_queue_depth(&self) -> (usize, usize) {
+        let now = self.api_queue.back().copied().unwrap_or(0);
+        (now, self.api_queue.iter().copied().max().unwrap_or(0))
+    }
+
     pub fn max_speed(&self) -> f64 {
         self.speed_history.iter().copied().fold(0.0, f64::max)
     }
 
+    pub fn min_speed(&self) -> f64 {
+        self.speed_history
+            .iter()
+            .copied()
+            .reduce(f64::min)
+            .unwrap_or(0.0)
+    }
+
     pub fn avg_speed(&self) -> f64 {
         if self.speed_history.is_empty() {
             return 0.0;
@@ -46,8 +68,41 @@ impl NetworkStats {
         let sum: f64 = self.speed_history.iter().sum();
         sum / self.speed_history.len() as f64
     }
+
+    /// The history squeezed into at most `width` columns, each the average
+    /// of the samples it covers, so the whole window always fits.
+    pub fn buckets(&self, width: usize) -> Vec<f64> {
+        if width == 0 || self.speed_history.is_empty() {
+            return Vec::new();
+        }
+        let per = self.speed_history.len().div_ceil(width);
+        let samples: Vec<f64> = self.speed_history.iter().copied().collect();
+        samples
+            .chunks(per)
+            .map(|c| c.iter().sum::<f64>() / c.len() as f64)
+            .collect()
+    }
+
+    /// One-line block sparkline of the history, scaled to its own peak.
+    pub fn sparkline(&self, width: usize) -> String {
+        let data = self.buckets(width);
+        let max = data.iter().copied().fold(0.0, f64::max);
+        data.iter()
+            .map(|&v| {
+                if max <= 0.0 || v <= 0.0 {
+                    ' '
+                } else {
+                    let level = ((v / max) * 7.0).round() as usize;
+                    EIGHTHS[level.min(7)]
+                }
+            })
+            .collect()
+    }
 }
 
+/// Block glyphs from 1/8 to 8/8 height.
+const EIGHTHS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
 impl App {
     /// Collapsed view: Cart-like centered popup with summary
     pub(super) fn draw_download_collapsed(&self, f: &mut Frame) {
@@ -126,6 +181,23 @@ impl App {
                 Style::default().fg(Color::Green),
             ),
         ]));
+        if let Some(eta) = ds.queue_eta() {
+            lines.push(Line::from(vec![
+                Span::styled("  ETA: ", Style::default().fg(Color::Cyan)),
+                Span::styled(format_duration(eta), Style::default().fg(Color::Yellow)),
+            ]));
+        }
+        lines.extend(self.when_done_line());
+        if self.network_stats.speed_history.len() > 1 {
+            lines.push(Line::from(vec![
+                Span::styled("  Trend: ", Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    self.network_stats.sparkline(bar_width),
+                    Style::default().fg(Color::Green),
+                ),
+            ]));
+            lines.push(self.speed_range_line());
+        }
         lines.push(Line::from(vec![
             Span::styled("  Active: ", Style::default().fg(Color::Cyan)),
             Span::styled(
@@ -133,6 +205,13 @@ impl App {
                 Style::default().fg(Color::Yellow),
             ),
         ]));
+        if let Some(schedule) = self.schedule_span() {
+            lines.push(Line::from(vec![
+                Span::styled("  Schedule: ", Style::default().fg(Color::Cyan)),
+                schedule,
+            ]));
+        }
+        lines.extend(self.transfer_quota_lines(bar_width));
         lines.push(Line::from(""));
 
         if !ds.tasks.is_empty() {
@@ -218,9 +297,16 @@ impl App {
             .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
             .split(main_area);
 
+        // Overall progress needs 8 lines plus borders and ETA, and three more
+        // when the transfer quota bar is shown.
+        let progress_height = if self.transfer_quota.is_some() {
+            14
+        } else {
+            11
+        };
         let left_chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(80), Constraint::Percentage(20)])
+            .constraints([Constraint::Min(5), Constraint::Length(progress_height)])
             .split(chunks[0]);
 
         let right_chunks = Layout::default()
@@ -246,12 +332,29 @@ impl App {
         }
     }
 
+    /// Transfer window state, e.g. `01:00–07:00 · waiting until 01:00`.
+    fn schedule_span(&self) -> Option<Span<'static>> {
+        let ds = &self.download_state;
+        let window = ds.window?;
+        Some(if ds.window_open {
+            Span::styled(
+                format!("{} · open", window),
+                Style::default().fg(Color::Green),
+            )
+        } else {
+            Span::styled(
+                format!("{} · waiting until {}", window, window.start_label()),
+                Style::default().fg(Color::Yellow),
+            )
+        })
+    }
+
     /// Draw download list (left top)
     fn draw_download_list(&self, f: &mut Frame, area: ratatui::layout::Rect) {
         let ds = &self.download_state;
         let done = ds.done_count();
         let total = ds.tasks.len();
-        let title = if self.loading {
+        let mut title = if self.loading {
             format!(
                 " {} Downloads ({}/{}) ",
                 SPINNER_FRAMES[self.spinner_idx], done, total
@@ -259,14 +362,66 @@ impl App {
         } else {
             format!(" Downloads ({}/{}) ", done, total)
         };
+        if let Some(schedule) = self.schedule_span() {
+            title.push_str(&format!("· {} ", schedule.content));
+        }
 
-        let items: Vec<ListItem> = ds
-            .tasks
+        let rows = ds.rows();
+        let items: Vec<ListItem> = rows
             .iter()
             .enumerate()
-            .map(|(i, task)| {
+            .map(|(i, row)| {
                 let is_sel = i == ds.selected;
                 let prefix = if is_sel { "› " } else { "  " };
+                let name_style = if is_sel {
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Reset)
+                };
+
+                let task = match *row {
+                    Row::Task(t) => &ds.tasks[t],
+                    Row::Job(job) => {
+                        let j = &ds.jobs[&job];
+                        let p = ds.job_progress(job);
+                        let pct = p.percent();
+                        let mut spans = vec![
+                            Span::styled(prefix, name_style),
+                            Span::styled(
+                                if j.collapsed { "▸ " } else { "▾ " },
+                                Style::default().fg(Color::DarkGray),
+                            ),
+                            Span::styled(
+                                truncate_name(&j.label, 40),
+                                name_style.add_modifier(Modifier::BOLD),
+                            ),
+                            Span::styled(
+                                format!(" {}/{} · {}%", p.done, p.tasks, pct),
+                                Style::default().fg(Color::DarkGray),
+                            ),
+                        ];
+                        if let Some(eta) = p.eta() {
+                            spans.push(Span::styled(
+                                format!(" · {} left", format_duration(eta)),
+                                Style::default().fg(Color::DarkGray),
+                            ));
+                        }
+                        if p.failed > 0 {
+                            spans.push(Span::styled(
+                                format!(" · {} failed", p.failed),
+                                Style::default().fg(Color::Red),
+                            ));
+                        }
+                        return ListItem::new(Line::from(spans));
+                    }
+                };
+                let indent = if task.job.is_some_and(|j| ds.jobs.contains_key(&j)) {
+                    "  "
+                } else {
+                    ""
+                };
 
                 let (status_icon, status_color) = match &task.status {
                     TaskStatus::Pending => ("⋯", Color::DarkGray),
@@ -282,23 +437,29 @@ impl App {
                     0
                 };
 
-                let name_style = if is_sel {
-                    Style::default()
-                        .fg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(Color::Reset)
-                };
-
-                ListItem::new(Line::from(vec![
+                let mut spans = vec![
                     Span::styled(prefix, name_style),
+                    Span::raw(indent),
                     Span::styled(
                         format!("{} ", status_icon),
                         Style::default().fg(status_color),
                     ),
                     Span::styled(truncate_name(&task.name, 40), name_style),
                     Span::styled(format!(" {}%", pct), Style::default().fg(Color::DarkGray)),
-                ]))
+                ];
+                if let Some(eta) = task.eta() {
+                    spans.push(Span::styled(
+                        format!(" · {} left", format_duration(eta)),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                if task.on_complete != OnComplete::Nothing {
+                    spans.push(Span::styled(
+                        format!(" → {}", task.on_complete.label()),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                ListItem::new(Line::from(spans))
             })
             .collect();
 
@@ -325,9 +486,7 @@ impl App {
             f.render_widget(empty_msg, area);
         } else {
             let mut state = ListState::default();
-            if !ds.tasks.is_empty() {
-                state.select(Some(ds.selected.min(ds.tasks.len() - 1)));
-            }
+            state.select(Some(ds.selected.min(rows.len() - 1)));
 
             let list = List::new(items)
                 .block(
@@ -408,15 +567,14 @@ impl App {
             ]),
         ];
 
-        if current_speed > 0.0 && total_size > total_downloaded {
-            let remaining = total_size - total_downloaded;
-            let eta_secs = remaining as f64 / current_speed;
-            let eta_str = format_duration(eta_secs as u64);
+        if let Some(eta) = ds.queue_eta() {
             lines.push(Line::from(vec![
                 Span::styled("  ETA: ", Style::default().fg(Color::Cyan)),
-                Span::styled(eta_str, Style::default().fg(Color::Yellow)),
+                Span::styled(format_duration(eta), Style::default().fg(Color::Yellow)),
             ]));
         }
+        lines.extend(self.when_done_line());
+        lines.extend(self.transfer_quota_lines(bar_width.saturating_sub(7)));
 
         let p = Paragraph::new(lines).block(
             self.styled_block()
@@ -427,6 +585,114 @@ impl App {
         f.render_widget(p, area);
     }
 
+    /// The queue-wide action set with `W`, if any.
+    fn when_done_line(&self) -> Option<Line<'static>> {
+        let action = self.queue_done.action;
+        (action != WhenDone::Nothing).then(|| {
+            Line::from(vec![
+                Span::styled("  When all done: ", Style::default().fg(Color::Cyan)),
+                Span::styled(action.label(), Style::default().fg(Color::Magenta)),
+            ])
+        })
+    }
+
+    /// Transfer quota bar: used (solid), still-queued downloads (shaded) and
+    /// free headroom, turning red when the queue would exceed what's left.
+    fn transfer_quota_lines(&self, bar_width: usize) -> Vec<Line<'static>> {
+        let Some((used, total)) = self.transfer_quota else {
+            return Vec::new();
+        };
+        let queued: u64 = self
+            .download_state
+            .tasks
+            .iter()
+            .filter(|t| {
+                matches!(
+                    t.status,
+                    TaskStatus::Pending | TaskStatus::Downloading | TaskStatus::Paused
+                )
+            })
+            .map(|t| t.total_size.saturating_sub(t.downloaded))
+            .sum();
+        let left = total.saturating_sub(used);
+        let over = queued > left;
+
+        let cells = |bytes: u64| ((bar_width as u128 * bytes as u128) / total as u128) as usize;
+        let used_cells = cells(used).min(bar_width);
+        let queued_cells = cells(queued).min(bar_width - used_cells);
+        let free_cells = bar_width - used_cells - queued_cells;
+        let queued_color = if over { Color::Red } else { Color::Yellow };
+
+        vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  Quota: ", Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    "\u{2588}".repeat(used_cells),
+                    Style::default().fg(Color::Blue),
+                ),
+                Span::styled(
+                    "\u{2593}".repeat(queued_cells),
+                    Style::default().fg(queued_color),
+                ),
+                Span::styled(
+                    "\u{2591}".repeat(free_cells),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("  Transfer left: ", Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    format!("{} of {}", format_size(left), format_size(total)),
+                    Style::default().fg(Color::Reset),
+                ),
+                Span::styled("  Queued: ", Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    if over {
+                        format!("{} (exceeds quota)", format_size(queued))
+                    } else {
+                        format_size(queued)
+                    },
+                    Style::default().fg(queued_color),
+                ),
+            ]),
+        ]
+    }
+
+    /// `Min / Avg / Max` throughput over the history window.
+    fn speed_range_line(&self) -> Line<'static> {
+        let stats = &self.network_stats;
+        let fmt = |mb: f64| format!("{}/s", format_size(mb_to_bytes(mb)));
+        Line::from(vec![
+            Span::styled("  Min ", Style::default().fg(Color::Cyan)),
+            Span::styled(fmt(stats.min_speed()), Style::default().fg(Color::DarkGray)),
+            Span::styled("  Avg ", Style::default().fg(Color::Cyan)),
+            Span::styled(fmt(stats.avg_speed()), Style::default().fg(Color::Reset)),
+            Span::styled("  Max ", Style::default().fg(Color::Cyan)),
+            Span::styled(fmt(stats.max_speed()), Style::default().fg(Color::Yellow)),
+        ])
+    }
+
+    /// API requests queued behind the rate limit, now and at the peak.
+    fn api_queue_line(&self) -> Line<'static> {
+        let (now, peak) = self.network_stats.api_queue_depth();
+        let limit = match self.client.api_limiter.rate() {
+            0 => "no limit".to_string(),
+            rate => format!("limit {rate}/s"),
+        };
+        Line::from(vec![
+            Span::styled("  API queue ", Style::default().fg(Color::Cyan)),
+            Span::styled(
+                now.to_string(),
+                Style::default().fg(if now > 0 { Color::Yellow } else { Color::Reset }),
+            ),
+            Span::styled(
+                format!("  peak {peak}  ({limit})"),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ])
+    }
+
     /// Draw network activity graph (right top)
     fn draw_network_activity(&self, f: &mut Frame, area: ratatui::layout::Rect) {
         let ds = &self.download_state;
@@ -438,9 +704,6 @@ impl App {
             .map(|t| t.speed / 1_048_576.0)
             .sum();
 
-        let history = &self.network_stats.speed_history;
-        let max_speed = self.network_stats.max_speed().max(1.0);
-
         let content_height = area.height.saturating_sub(2) as usize;
         let content_width = area.width.saturating_sub(4) as usize;
 
@@ -452,57 +715,35 @@ impl App {
         lines.push(Line::from(vec![
             Span::styled("  Current: ", Style::default().fg(Color::Cyan)),
             Span::styled(
-                format!("{:.2} MB/s", current_speed),
+                format!("{}/s", format_size(mb_to_bytes(current_speed))),
                 Style::default()
                     .fg(Color::Green)
                     .add_modifier(Modifier::BOLD),
             ),
         ]));
-        lines.push(Line::from(vec![
-            Span::styled("  Peak: ", Style::default().fg(Color::Cyan)),
-            Span::styled(
-                format!("{:.2} MB/s", max_speed),
-                Style::default().fg(Color::Yellow),
-            ),
-        ]));
-        lines.push(Line::from(vec![
-            Span::styled("  Avg: ", Style::default().fg(Color::Cyan)),
-            Span::styled(
-                format!("{:.2} MB/s", self.network_stats.avg_speed()),
-                Style::default().fg(Color::DarkGray),
-            ),
-        ]));
+        lines.push(self.speed_range_line());
+        lines.push(self.api_queue_line());
         lines.push(Line::from(""));
 
-        if history.len() > 1 && graph_height > 2 {
-            let points_to_show = history.len().min(content_width);
-            let data: Vec<f64> = if history.len() > points_to_show {
-                history
-                    .iter()
-                    .skip(history.len() - points_to_show)
-                    .copied()
-                    .collect()
-            } else {
-                history.iter().copied().collect()
-            };
-
+        let data = self.network_stats.buckets(content_width);
+        if data.len() > 1 && graph_height > 2 {
+            // Each row is one cell tall with 8 sub-steps, so partial tops use
+            // the eighth-block glyphs instead of rounding to a whole row.
+            let max_speed = data.iter().copied().fold(0.0, f64::max).max(f64::EPSILON);
+            let steps: Vec<usize> = data
+                .iter()
+                .map(|&v| ((v / max_speed) * (graph_height * 8) as f64).round() as usize)
+                .collect();
             for row in 0..graph_height {
-                let row_from_bottom = graph_height - 1 - row;
+                let floor = (graph_height - 1 - row) * 8;
                 let mut line_str = "  ".to_string();
-
-                for &value in &data {
-                    let bar_height = ((value / max_speed) * graph_height as f64) as usize;
-
-                    let ch = if bar_height > row_from_bottom {
-                        "⣿"
-                    } else if bar_height == row_from_bottom {
-                        "⣀"
-                    } else {
-                        " "
-                    };
-                    line_str.push_str(ch);
+                for &h in &steps {
+                    line_str.push(match h.saturating_sub(floor) {
+                        0 => ' ',
+                        n if n >= 8 => '█',
+                        n => EIGHTHS[n - 1],
+                    });
                 }
-
                 lines.push(Line::from(Span::styled(
                     line_str,
                     Style::default().fg(Color::Cyan),
@@ -517,7 +758,7 @@ impl App {
 
         let p = Paragraph::new(lines).block(
             self.styled_block()
-                .title(" Network Activity ")
+                .title(" Network Activity (last 5 min) ")
                 .title_style(Style::default().fg(Color::Cyan))
                 .border_style(Style::default().fg(Color::Cyan)),
         );
@@ -527,11 +768,62 @@ impl App {
     /// Draw file details (right bottom)
     fn draw_file_details(&self, f: &mut Frame, area: ratatui::layout::Rect) {
         let ds = &self.download_state;
-        let task = ds.tasks.get(ds.selected);
+        let row = ds.selected_row();
 
         let mut lines = vec![Line::from("")];
 
-        if let Some(task) = task {
+        if let Some(Row::Job(job)) = row {
+            let p = ds.job_progress(job);
+            lines.push(Line::from(vec![
+                Span::styled("  Group: ", Style::default().fg(Color::Cyan)),
+                Span::styled(&ds.jobs[&job].label, Style::default().fg(Color::Reset)),
+            ]));
+            lines.push(Line::from(""));
+            let mut status = vec![
+                Span::styled("  Tasks: ", Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    format!("{} done, {} active of {}", p.done, p.active, p.tasks),
+                    Style::default().fg(Color::Reset),
+                ),
+            ];
+            if p.failed > 0 {
+                status.push(Span::styled(
+                    format!(", {} failed", p.failed),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            lines.push(Line::from(status));
+            lines.push(Line::from(""));
+            let pct = p.percent();
+            lines.push(Line::from(vec![
+                Span::styled("  Downloaded: ", Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    format!(
+                        "{} of {} ({}%)",
+                        format_size(p.downloaded),
+                        format_size(p.total_size),
+                        pct
+                    ),
+                    Style::default().fg(Color::Reset),
+                ),
+            ]));
+            if p.speed > 0.0 {
+                lines.push(Line::from(vec![
+                    Span::styled("  Speed: ", Style::default().fg(Color::Cyan)),
+                    Span::styled(
+                        format!("{}/s", format_size(p.speed as u64)),
+                        Style::default().fg(Color::Green),
+                    ),
+                ]));
+                if let Some(eta) = p.eta() {
+                    lines.push(Line::from(vec![
+                        Span::styled("  ETA: ", Style::default().fg(Color::Cyan)),
+                        Span::styled(format_duration(eta), Style::default().fg(Color::Yellow)),
+                    ]));
+                }
+            }
+        } else if let Some(Row::Task(t)) = row {
+            let task = &ds.tasks[t];
             lines.push(Line::from(vec![
                 Span::styled("  File: ", Style::default().fg(Color::Cyan)),
                 Span::styled(&task.name, Style::default().fg(Color::Reset)),
@@ -596,17 +888,30 @@ impl App {
                     ),
                 ]));
 
-                if task.total_size > task.downloaded {
-                    let remaining = task.total_size - task.downloaded;
-                    let eta_secs = remaining as f64 / task.speed;
-                    let eta_str = format_duration(eta_secs as u64);
+                if let Some(eta) = task.eta() {
                     lines.push(Line::from(vec![
                         Span::styled("  ETA: ", Style::default().fg(Color::Cyan)),
-                        Span::styled(eta_str, Style::default().fg(Color::Yellow)),
+                        Span::styled(format_duration(eta), Style::default().fg(Color::Yellow)),
                     ]));
                 }
             }
 
+            let limit = task.limit.rate();
+            if limit > 0 {
+                lines.push(Line::from(vec![
+                    Span::styled("  Limit: ", Style::default().fg(Color::Cyan)),
+                    Span::styled(
+                        crate::ratelimit::format_rate(limit),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                ]));
+            }
+
+            lines.push(Line::from(vec![
+                Span::styled("  When done: ", Style::default().fg(Color::Cyan)),
+                Span::styled(task.on_complete.label(), Style::default().fg(Color::Reset)),
+            ]));
+
             lines.push(Line::from(""));
             lines.push(Line::from(vec![
                 Span::styled("  Path: ", Style::default().fg(Color::Cyan)),
@@ -632,15 +937,30 @@ impl App {
     }
 }
 
-fn format_duration(secs: u64) -> String {
-    let hours = secs / 3600;
-    let mins = (secs % 3600) / 60;
-    let secs = secs % 60;
-    if hours > 0 {
-        format!("{}h {}m {}s", hours, mins, secs)
-    } else if mins > 0 {
-        format!("{}m {}s", mins, secs)
-    } else {
-        format!("{}s", secs)
+/// The speed history is kept in MiB/s; convert back so it can go through the
+/// configured `format_size` units.
+fn mb_to_bytes(mb_per_sec: f64) -> u64 {
+    (mb_per_sec * 1_048_576.0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparkline_squeezes_history_into_width() {
+        let mut stats = NetworkStats::new();
+        for (i, v) in [0.0, 0.0, 2.0, 2.0, 4.0, 4.0, 8.0, 8.0]
+            .into_iter()
+            .enumerate()
+        {
+            stats.update(v, i % 3);
+        }
+        assert_eq!(stats.buckets(4), vec![0.0, 2.0, 4.0, 8.0]);
+        assert_eq!(stats.sparkline(4), " ▃▅█");
+        assert_eq!(stats.min_speed(), 0.0);
+        assert_eq!(stats.max_speed(), 8.0);
+        assert_eq!(stats.sparkline(0), "");
+        assert_eq!(stats.api_queue_depth(), (1, 2));
     }
 }
@@ -10,12 +10,23 @@ use crate::config::{BorderStyle, ColorScheme};
 use crate::pikpak::{Entry, EntryKind};
 use crate::theme;
 
+use super::archive::Listing;
+use super::batch_confirm::{BatchAction, BatchSummary};
+use super::batch_rename::BatchRename;
 use super::completion::PathInput;
+use super::gallery::{Gallery, Slot};
 use super::image_render::{
     center_image_rect, render_image_to_colored_lines, render_image_to_grayscale_lines,
     upscale_for_rect,
 };
+use super::keymap::{ACTIONS, Action};
 use super::local_completion::LocalPathInput;
+use super::new_file::NewFile;
+use super::notes::NoteForm;
+use super::ops::JobState;
+use super::subtitles::Subtitles;
+use super::tabs::BrowseTab;
+use super::upload::{UploadForm, UploadPlan};
 use super::widgets;
 use super::{
     App, InputMode, LoginField, PickerState, PreviewState, SPINNER_FRAMES, centered_rect,
@@ -40,15 +51,93 @@ impl App {
         !matches!(
             self.input,
             InputMode::Normal
+                | InputMode::Filter
                 | InputMode::Login { .. }
+                | InputMode::LoginCode { .. }
                 | InputMode::MovePicker { .. }
                 | InputMode::CopyPicker { .. }
+                | InputMode::OfflinePicker { .. }
+                | InputMode::RestorePicker { .. }
                 | InputMode::DownloadView
+                | InputMode::Gallery(_)
         )
     }
 
+    /// One trash listing row: mark, icon, name, size, and the purge countdown
+    /// (plus the purge date when there's room for it).
+    fn trash_row(
+        &self,
+        entry: &Entry,
+        is_sel: bool,
+        name_max: usize,
+        show_date: bool,
+        now: i64,
+    ) -> Line<'static> {
+        let prefix = if is_sel { " \u{203a} " } else { "   " };
+        let mark = if self.trash_marked.contains(&entry.id) {
+            "\u{25cf} "
+        } else {
+            "  "
+        };
+        let cat = theme::categorize(entry);
+        let icon = theme::cli_icon(cat, self.config.nerd_font);
+        let icon_color = self.file_color(cat);
+        let size_str = if entry.kind == EntryKind::Folder {
+            "-".to_string()
+        } else {
+            format_size(entry.size)
+        };
+        let name_style = if is_sel {
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Reset)
+        };
+        let mut spans = vec![
+            Span::styled(prefix, name_style),
+            Span::styled(mark, Style::default().fg(Color::Yellow)),
+            Span::styled(format!("{} ", icon), Style::default().fg(icon_color)),
+            Span::styled(truncate_name(&entry.name, name_max), name_style),
+            Span::styled(
+                format!("  {:>9}", size_str),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ];
+        if show_date && !entry.purge_time.is_empty() {
+            spans.push(Span::styled(
+                format!("  {}", crate::cmd::format_date(&entry.purge_time)),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        if let Some(days) = widgets::trash_days_left(&entry.purge_time, now) {
+            let color = match days {
+                0..=1 => Color::Red,
+                2..=3 => Color::Yellow,
+                _ => Color::DarkGray,
+            };
+            spans.push(Span::styled(
+                format!("  {:>3}d left", days),
+                Style::default().fg(color),
+            ));
+        }
+        Line::from(spans)
+    }
+
     fn draw_trash_view(&self, f: &mut Frame, entries: &[Entry], selected: usize, expanded: bool) {
-        let title = format!(" Trash ({}) ", entries.len());
+        let title = if self.trash_marked.is_empty() {
+            format!(" Trash ({}) ", entries.len())
+        } else {
+            format!(
+                " Trash ({}) \u{00b7} {} marked ",
+                entries.len(),
+                self.trash_marked.len()
+            )
+        };
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
         let (tr_bc, tr_tc) = if self.is_vibrant() {
             (Color::LightRed, Color::LightRed)
         } else {
@@ -71,7 +160,8 @@ impl App {
                 let mut lines = vec![Line::from("")];
                 let max_visible = list_area.height.saturating_sub(4) as usize;
                 let scroll_offset = widgets::scroll_offset(selected, max_visible);
-                let name_max = list_area.width.saturating_sub(20) as usize;
+                // Room for prefix, mark, icon, size, deletion date and countdown.
+                let name_max = list_area.width.saturating_sub(48) as usize;
 
                 for (i, entry) in entries
                     .iter()
@@ -79,32 +169,7 @@ impl App {
                     .skip(scroll_offset)
                     .take(max_visible)
                 {
-                    let is_sel = i == selected;
-                    let prefix = if is_sel { " \u{203a} " } else { "   " };
-                    let cat = theme::categorize(entry);
-                    let icon = theme::cli_icon(cat, self.config.nerd_font);
-                    let icon_color = self.file_color(cat);
-                    let size_str = if entry.kind == EntryKind::Folder {
-                        "-".to_string()
-                    } else {
-                        format_size(entry.size)
-                    };
-                    let name_style = if is_sel {
-                        Style::default()
-                            .fg(Color::Cyan)
-                            .add_modifier(Modifier::BOLD)
-                    } else {
-                        Style::default().fg(Color::Reset)
-                    };
-                    lines.push(Line::from(vec![
-                        Span::styled(prefix, name_style),
-                        Span::styled(format!("{} ", icon), Style::default().fg(icon_color)),
-                        Span::styled(truncate_name(&entry.name, name_max), name_style),
-                        Span::styled(
-                            format!("  {:>9}", size_str),
-                            Style::default().fg(Color::DarkGray),
-                        ),
-                    ]));
+                    lines.push(self.trash_row(entry, i == selected, name_max, true, now));
                 }
 
                 widgets::push_remaining_indicator(
@@ -161,32 +226,7 @@ impl App {
                     .skip(scroll_offset)
                     .take(max_visible)
                 {
-                    let is_sel = i == selected;
-                    let prefix = if is_sel { " \u{203a} " } else { "   " };
-                    let cat = theme::categorize(entry);
-                    let icon = theme::cli_icon(cat, self.config.nerd_font);
-                    let icon_color = self.file_color(cat);
-                    let size_str = if entry.kind == EntryKind::Folder {
-                        "-".to_string()
-                    } else {
-                        format_size(entry.size)
-                    };
-                    let name_style = if is_sel {
-                        Style::default()
-                            .fg(Color::Cyan)
-                            .add_modifier(Modifier::BOLD)
-                    } else {
-                        Style::default().fg(Color::Reset)
-                    };
-                    lines.push(Line::from(vec![
-                        Span::styled(prefix, name_style),
-                        Span::styled(format!("{} ", icon), Style::default().fg(icon_color)),
-                        Span::styled(truncate_name(&entry.name, 35), name_style),
-                        Span::styled(
-                            format!("  {:>9}", size_str),
-                            Style::default().fg(Color::DarkGray),
-                        ),
-                    ]));
+                    lines.push(self.trash_row(entry, i == selected, 35, false, now));
                 }
 
                 widgets::push_remaining_indicator(
@@ -200,10 +240,11 @@ impl App {
                 let hints = vec![
                     ("j/k", "nav"),
                     ("Enter", "expand"),
-                    ("Space", "info"),
+                    ("a", "mark"),
                     ("u", "restore"),
+                    ("U", "restore to"),
                     ("x", "delete"),
-                    ("r", "refresh"),
+                    ("E", "empty"),
                     ("Esc", "close"),
                 ];
                 let mut hint_spans = vec![Span::raw("  ")];
@@ -221,7 +262,29 @@ impl App {
         }
     }
 
-    fn draw_confirm_play_overlay(&self, f: &mut Frame, name: &str, _url: &str) {
+    /// "Subtitles: a.srt, b.ass (on)" for the play prompts; `None` when the
+    /// video has none next to it.
+    fn subtitles_line(subs: &Subtitles) -> Option<Line<'static>> {
+        if subs.found.is_empty() {
+            return None;
+        }
+        let names: Vec<&str> = subs.found.iter().map(|s| s.name.as_str()).collect();
+        let (state, color) = if subs.on {
+            ("on", Color::Green)
+        } else {
+            ("off", Color::DarkGray)
+        };
+        Some(Line::from(vec![
+            Span::styled("  Subtitles: ", Style::default().fg(Color::DarkGray)),
+            Span::styled(
+                truncate_name(&names.join(", "), 40),
+                Style::default().fg(Color::Reset),
+            ),
+            Span::styled(format!(" ({state})"), Style::default().fg(color)),
+        ]))
+    }
+
+    fn draw_confirm_play_overlay(&self, f: &mut Frame, name: &str, _url: &str, subs: &Subtitles) {
         let area = self.prepare_overlay(f, 60, 20);
         let player_display = self.config.player.as_deref().unwrap_or("not configured");
         let (bc, tc) = if self.is_vibrant() {
@@ -235,35 +298,41 @@ impl App {
         } else {
             name.to_string()
         };
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  Play ", Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    format!("\"{}\"", truncated_name),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("?", Style::default().fg(Color::Cyan)),
+            ]),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  Open with: ", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    player_display,
+                    if self.config.player.is_some() {
+                        Style::default().fg(Color::Green)
+                    } else {
+                        Style::default().fg(Color::Red)
+                    },
+                ),
+            ]),
+        ];
+        let mut hints = vec![("y/Enter", "play")];
+        if let Some(line) = Self::subtitles_line(subs) {
+            lines.push(line);
+            hints.push(("s", "subtitles"));
+        }
+        hints.push(("n/Esc", "cancel"));
+        lines.push(Line::from(""));
+        lines.push(Self::hint_line(&hints));
         f.render_widget(
-            Paragraph::new(vec![
-                Line::from(""),
-                Line::from(vec![
-                    Span::styled("  Play ", Style::default().fg(Color::Cyan)),
-                    Span::styled(
-                        format!("\"{}\"", truncated_name),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled("?", Style::default().fg(Color::Cyan)),
-                ]),
-                Line::from(""),
-                Line::from(vec![
-                    Span::styled("  Open with: ", Style::default().fg(Color::DarkGray)),
-                    Span::styled(
-                        player_display,
-                        if self.config.player.is_some() {
-                            Style::default().fg(Color::Green)
-                        } else {
-                            Style::default().fg(Color::Red)
-                        },
-                    ),
-                ]),
-                Line::from(""),
-                Self::hint_line(&[("y/Enter", "play"), ("n/Esc", "cancel")]),
-            ])
-            .block(self.overlay_block("Play Video", bc, tc)),
+            Paragraph::new(lines).block(self.overlay_block("Play Video", bc, tc)),
             area,
         );
     }
@@ -274,6 +343,7 @@ impl App {
         name: &str,
         medias: &[super::PlayOption],
         selected: usize,
+        subs: &Subtitles,
     ) {
         let height = std::cmp::min(50, 20 + medias.len() as u16 * 2);
         let area = centered_rect(60, height, f.area());
@@ -320,8 +390,15 @@ impl App {
             ]));
         }
 
+        let mut hints = vec![("Enter", "play")];
+        if let Some(line) = Self::subtitles_line(subs) {
+            lines.push(Line::from(""));
+            lines.push(line);
+            hints.push(("s", "subtitles"));
+        }
+        hints.push(("Esc", "cancel"));
         lines.push(Line::from(""));
-        lines.push(Self::hint_line(&[("Enter", "play"), ("Esc", "cancel")]));
+        lines.push(Self::hint_line(&hints));
 
         let (bc, tc) = if self.is_vibrant() {
             (Color::LightGreen, Color::LightGreen)
@@ -334,6 +411,45 @@ impl App {
         );
     }
 
+    fn draw_profile_picker_overlay(&self, f: &mut Frame, profiles: &[String], selected: usize) {
+        let height = std::cmp::min(50, 20 + profiles.len() as u16 * 2);
+        let area = centered_rect(50, height, f.area());
+        clear_overlay_area(f, area);
+
+        let current = crate::config::profile();
+        let current = current.as_deref().unwrap_or(crate::config::DEFAULT_PROFILE);
+        let mut lines = vec![Line::from("")];
+        for (i, name) in profiles.iter().enumerate() {
+            let is_selected = i == selected;
+            let prefix = if is_selected { " > " } else { "   " };
+            let style = if is_selected {
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Reset)
+            };
+            let suffix = if name == current { " (current)" } else { "" };
+            lines.push(Line::from(vec![
+                Span::styled(prefix, style),
+                Span::styled(name.clone(), style),
+                Span::styled(suffix, Style::default().fg(Color::DarkGray)),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Self::hint_line(&[("Enter", "switch"), ("Esc", "cancel")]));
+
+        let (bc, tc) = if self.is_vibrant() {
+            (Color::LightGreen, Color::LightGreen)
+        } else {
+            (Color::Cyan, Color::Yellow)
+        };
+        f.render_widget(
+            Paragraph::new(Text::from(lines)).block(self.overlay_block("Switch Account", bc, tc)),
+            area,
+        );
+    }
+
     fn draw_player_input_overlay(&self, f: &mut Frame, value: &str) {
         let area = self.prepare_overlay(f, 60, 20);
         let cur = if self.cursor_visible { "\u{2588}" } else { " " };
@@ -408,15 +524,26 @@ impl App {
         if !entry.created_time.is_empty() {
             lines.push(Line::from(vec![
                 Span::styled("  Created:", Style::default().fg(Color::Cyan)),
-                Span::styled(&entry.created_time, Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    crate::cmd::format_date(&entry.created_time),
+                    Style::default().fg(Color::DarkGray),
+                ),
             ]));
         }
         if !entry.modified_time.is_empty() {
             lines.push(Line::from(vec![
                 Span::styled("  Modified:", Style::default().fg(Color::Cyan)),
-                Span::styled(&entry.modified_time, Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    crate::cmd::format_date(&entry.modified_time),
+                    Style::default().fg(Color::DarkGray),
+                ),
             ]));
         }
+        if let Some((id, video)) = &self.preview_media
+            && *id == entry.id
+        {
+            lines.extend(video_lines(video));
+        }
         let mut markers = Vec::new();
         if entry.starred {
             markers.push(Span::styled(
@@ -440,16 +567,69 @@ impl App {
             line.extend(markers);
             lines.push(Line::from(line));
         }
+        if let Some(playback) = self.playback.get(&entry.id) {
+            let state = match (playback.watched, playback.resume_at()) {
+                (true, _) => "Watched".to_string(),
+                (false, Some(at)) => format!(
+                    "Stopped at {} ({:.0}%)",
+                    crate::ratelimit::format_duration(at as u64),
+                    playback.progress().unwrap_or_default() * 100.0
+                ),
+                (false, None) => "Started".to_string(),
+            };
+            lines.push(Line::from(vec![
+                Span::styled("  Playback:", Style::default().fg(Color::Cyan)),
+                Span::styled(format!(" {state}"), Style::default().fg(Color::DarkGray)),
+            ]));
+        }
+        if let Some(note) = self.notes.get(&entry.id) {
+            if !note.labels.is_empty() {
+                lines.push(Line::from(vec![
+                    Span::styled("  Labels:", Style::default().fg(Color::Cyan)),
+                    Span::styled(
+                        format!(" {}", note.labels.join(", ")),
+                        Style::default().fg(Color::Magenta),
+                    ),
+                ]));
+            }
+            if !note.text.is_empty() {
+                lines.push(Line::from(vec![
+                    Span::styled("  Note:", Style::default().fg(Color::Cyan)),
+                    Span::styled(
+                        format!(" {}", note.text),
+                        Style::default().fg(Color::DarkGray),
+                    ),
+                ]));
+            }
+        }
         lines
     }
 
     pub(super) fn draw(&self, f: &mut Frame) {
+        self.draw_screen(f);
+        if let Some(line) = self.queue_done.countdown(std::time::Instant::now()) {
+            self.draw_when_done_overlay(f, &line);
+        }
+    }
+
+    fn draw_screen(&self, f: &mut Frame) {
+        if let Some(lock) = &self.lock {
+            self.draw_lock_screen(f, lock);
+            return;
+        }
         match &self.input {
             InputMode::Login { .. } => self.draw_login_screen(f),
+            InputMode::LoginCode { .. } => self.draw_login_code_screen(f),
             InputMode::MovePicker { .. } | InputMode::CopyPicker { .. } => self.draw_picker(f),
             InputMode::CartMovePicker { .. } | InputMode::CartCopyPicker { .. } => {
                 self.draw_cart_picker(f)
             }
+            InputMode::OfflinePicker { urls, picker, .. } => {
+                self.draw_offline_picker(f, urls, picker)
+            }
+            InputMode::RestorePicker { targets, picker } => {
+                self.draw_restore_picker(f, targets, picker)
+            }
             InputMode::DownloadView => {
                 if self.download_view_mode == super::DownloadViewMode::Collapsed {
                     self.draw_main(f);
@@ -491,6 +671,7 @@ impl App {
                 info,
                 image,
                 has_thumbnail,
+                archive,
             } if !self.trash_entries.is_empty() => {
                 self.draw_trash_view(
                     f,
@@ -498,7 +679,7 @@ impl App {
                     self.trash_selected,
                     self.trash_expanded,
                 );
-                self.draw_info_overlay(f, info, image.as_ref(), *has_thumbnail);
+                self.draw_info_overlay(f, info, image.as_ref(), *has_thumbnail, archive.as_ref());
             }
             _ => self.draw_main(f),
         }
@@ -539,6 +720,33 @@ impl App {
     }
 
     /// Split the area into a main content region and an optional help bar row.
+    /// One line of numbered tab labels above the panes, the active tab
+    /// highlighted. Only drawn while more than one tab is open.
+    fn draw_tab_bar(&self, f: &mut Frame, area: Rect) {
+        let active_style = if self.is_vibrant() {
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightCyan)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        };
+        let mut spans = Vec::new();
+        for (i, title) in self.tab_titles().into_iter().enumerate() {
+            let label = format!(" {} {} ", i + 1, truncate_name(title, 20));
+            if i == self.active_tab {
+                spans.push(Span::styled(label, active_style));
+            } else {
+                spans.push(Span::styled(label, Style::default().fg(Color::DarkGray)));
+            }
+            spans.push(Span::raw(" "));
+        }
+        f.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+
     fn layout_with_help_bar(&self, area: Rect) -> (Rect, Option<Rect>) {
         if self.config.show_help_bar {
             let outer = Layout::default()
@@ -576,6 +784,11 @@ impl App {
         self.config.get_color(cat)
     }
 
+    /// Whether `size` reaches the configured large-file threshold.
+    fn is_large(&self, size: u64) -> bool {
+        self.config.large_file_threshold > 0 && size >= self.config.large_file_threshold
+    }
+
     /// Highlight style for selected items.
     fn highlight_style(&self) -> Style {
         if self.is_vibrant() {
@@ -638,8 +851,13 @@ impl App {
                 Line::from(""),
             ];
             if *logging_in {
+                let status = match self.browser_login.as_deref() {
+                    None => "  Logging in...".to_string(),
+                    Some("") => "  Starting browser login...".to_string(),
+                    Some(url) => format!("  Solve the captcha in your browser: {url}"),
+                };
                 lines.push(Line::from(Span::styled(
-                    "  Logging in...",
+                    status,
                     Style::default().fg(Color::Cyan),
                 )));
             } else if let Some(err) = error {
@@ -650,7 +868,16 @@ impl App {
                 lines.push(Line::from(""));
             }
             lines.push(Line::from(""));
-            let login_hints = vec![("Tab", "switch"), ("Enter", "login"), ("Esc", "quit")];
+            let login_hints = if *logging_in && self.browser_login.is_some() {
+                vec![("Esc", "cancel")]
+            } else {
+                vec![
+                    ("Tab", "switch"),
+                    ("Enter", "login"),
+                    ("Ctrl+B", "browser"),
+                    ("Esc", "quit"),
+                ]
+            };
             let mut hint_spans = vec![Span::raw("  ")];
             hint_spans.extend(Self::styled_help_spans(&login_hints));
             lines.push(Line::from(hint_spans));
@@ -668,10 +895,142 @@ impl App {
         }
     }
 
+    fn draw_login_code_screen(&self, f: &mut Frame) {
+        let InputMode::LoginCode {
+            pending,
+            code,
+            error,
+        } = &self.input
+        else {
+            return;
+        };
+        let bg = Block::default().style(Style::default().bg(Color::Reset));
+        f.render_widget(bg, f.area());
+        let area = centered_rect(50, 40, f.area());
+        clear_overlay_area(f, area);
+        let cur = if self.cursor_visible { "\u{2588}" } else { " " };
+        let yellow = Style::default().fg(Color::Yellow);
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(format!(
+                "  PikPak sent a verification code to {}.",
+                pending.email()
+            )),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  Code: ", yellow),
+                Span::styled(format!("{code}{cur}"), yellow),
+            ]),
+            Line::from(""),
+        ];
+        if let Some(err) = error {
+            lines.push(Line::from(Span::styled(
+                format!("  {}", err),
+                Style::default().fg(Color::Red),
+            )));
+            lines.push(Line::from(""));
+        }
+        let hints = vec![("Enter", "verify"), ("Esc", "back")];
+        let mut hint_spans = vec![Span::raw("  ")];
+        hint_spans.extend(Self::styled_help_spans(&hints));
+        lines.push(Line::from(hint_spans));
+
+        let (bc, tc) = self.themed_colors(Color::Cyan);
+        let p = Paragraph::new(Text::from(lines))
+            .block(
+                self.styled_block()
+                    .title(" Verification Code ")
+                    .title_style(Style::default().fg(tc))
+                    .border_style(Style::default().fg(bc)),
+            )
+            .wrap(Wrap { trim: false });
+        f.render_widget(p, area);
+    }
+
+    /// Idle lock: nothing of the drive is shown until it's unlocked.
+    fn draw_lock_screen(&self, f: &mut Frame, lock: &super::lock::LockScreen) {
+        f.render_widget(Clear, f.area());
+        let area = centered_rect(50, 30, f.area());
+        let cur = if self.cursor_visible { "\u{2588}" } else { " " };
+        let label = lock.unlock.label();
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(format!("  {label}: "), Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!("{}{}", "*".repeat(lock.value.chars().count()), cur),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ]),
+            Line::from(""),
+        ];
+        if lock.checking {
+            lines.push(Line::from(Span::styled(
+                "  Checking...",
+                Style::default().fg(Color::Cyan),
+            )));
+            lines.push(Line::from(""));
+        } else if let Some(err) = &lock.error {
+            lines.push(Line::from(Span::styled(
+                format!("  {}", err),
+                Style::default().fg(Color::Red),
+            )));
+            lines.push(Line::from(""));
+        }
+        let hints = vec![("Enter", "unlock"), ("Esc", "clear")];
+        let mut hint_spans = vec![Span::raw("  ")];
+        hint_spans.extend(Self::styled_help_spans(&hints));
+        lines.push(Line::from(hint_spans));
+
+        let (bc, tc) = self.themed_colors(Color::Cyan);
+        let p = Paragraph::new(Text::from(lines))
+            .block(
+                self.styled_block()
+                    .title(" Locked ")
+                    .title_style(Style::default().fg(tc))
+                    .border_style(Style::default().fg(bc)),
+            )
+            .wrap(Wrap { trim: false });
+        f.render_widget(p, area);
+    }
+
     fn draw_main(&self, f: &mut Frame) {
         let (main_area, help_bar_area) = self.layout_with_help_bar(f.area());
+        let main_area = if self.tabs.len() > 1 && !self.config.commander_mode {
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(1)])
+                .split(main_area);
+            self.draw_tab_bar(f, rows[0]);
+            rows[1]
+        } else {
+            main_area
+        };
+
+        if let Some(pane) = self.idle_pane() {
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .split(main_area);
+            // The first tab is always the left pane, whichever has focus.
+            let (active_area, idle_area) = if self.active_tab == 0 {
+                (chunks[0], chunks[1])
+            } else {
+                (chunks[1], chunks[0])
+            };
+
+            self.parent_pane_area.set(Rect::default());
+            self.current_pane_area.set(active_area);
+            self.preview_pane_area.set(Rect::default());
+            self.idle_pane_area.set(idle_area);
+
+            self.draw_current_pane(f, active_area);
+            self.draw_idle_pane(f, idle_area, pane);
 
-        if self.config.show_preview {
+            if self.show_logs_overlay {
+                self.draw_log_overlay(f, idle_area);
+            }
+        } else if self.preview_pane_shown() {
             let chunks = Layout::default()
                 .direction(Direction::Horizontal)
                 .constraints([
@@ -827,6 +1186,14 @@ impl App {
             let mut right_spans: Vec<Span<'static>> = Vec::new();
             let mut right_w: u16 = 0;
 
+            if self.offline.is_some() {
+                right_spans.push(Span::styled(" │ ", Style::default().fg(Color::DarkGray)));
+                right_spans.push(Span::styled(
+                    " offline ",
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ));
+                right_w += 12;
+            }
             if let Some((badge_spans, badge_w)) = update_badge {
                 right_spans.extend(badge_spans);
                 right_w += badge_w;
@@ -931,34 +1298,7 @@ impl App {
         let items: Vec<ListItem> = self
             .entries
             .iter()
-            .map(|e| {
-                let cat = theme::categorize(e);
-                let ico = theme::icon(cat, self.config.nerd_font);
-                let c = self.file_color(cat);
-                let size_str = match e.kind {
-                    EntryKind::Folder => String::new(),
-                    EntryKind::File => format!("  {}", format_size(e.size)),
-                };
-                let star_marker = if e.starred { "\u{2605} " } else { "" };
-                let cart_marker = if self.cart_ids.contains(&e.id) {
-                    "\u{2606} "
-                } else {
-                    ""
-                };
-                ListItem::new(Line::from(vec![
-                    Span::styled(ico, Style::default().fg(c)),
-                    Span::styled(" ", Style::default()),
-                    Span::styled(star_marker, Style::default().fg(Color::Yellow)),
-                    Span::styled(
-                        cart_marker,
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::DIM),
-                    ),
-                    Span::styled(&e.name, Style::default().fg(c)),
-                    Span::styled(size_str, Style::default().fg(Color::DarkGray)),
-                ]))
-            })
+            .map(|e| self.entry_list_item(e, true))
             .collect();
 
         let mut state = ListState::default();
@@ -971,24 +1311,325 @@ impl App {
         } else {
             (Color::Cyan, Color::Green)
         };
+        let mut block = self
+            .styled_block()
+            .title(title)
+            .title_style(Style::default().fg(file_tc))
+            .border_style(Style::default().fg(file_bc));
+        let typing = matches!(self.input, InputMode::Filter);
+        if typing || self.filter_source.is_some() {
+            let total = self.filter_source.as_ref().map_or(0, Vec::len);
+            let cur = if typing && self.cursor_visible {
+                "\u{2588}"
+            } else {
+                ""
+            };
+            block = block.title_bottom(Line::from(vec![
+                Span::styled(" /", Style::default().fg(Color::Yellow)),
+                Span::styled(
+                    format!("{}{}", self.filter_query, cur),
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(
+                    format!(" ({}/{}) ", self.entries.len(), total),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ]));
+        }
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Min(0)])
+            .split(inner);
+        f.render_widget(Paragraph::new(self.sort_header_line()), chunks[0]);
+
         let list = List::new(items)
-            .block(
-                self.styled_block()
-                    .title(title)
-                    .title_style(Style::default().fg(file_tc))
-                    .border_style(Style::default().fg(file_bc)),
-            )
             .highlight_style(self.highlight_style())
             .highlight_symbol("\u{203a} ");
-        f.render_stateful_widget(list, area, &mut state);
+        f.render_stateful_widget(list, chunks[1], &mut state);
         self.scroll_offset.set(state.offset());
-        self.list_area_height.set(area.height);
+        self.list_area_height.set(area.height.saturating_sub(1));
+    }
+
+    /// One row of a file list: icon, star / cart markers, name and size.
+    /// `filtered` underlines the characters the active filter matched.
+    fn entry_list_item<'a>(&self, e: &'a Entry, filtered: bool) -> ListItem<'a> {
+        let cat = theme::categorize(e);
+        let ico = theme::icon(cat, self.config.nerd_font);
+        let large = e.kind == EntryKind::File && self.is_large(e.size);
+        let c = if large {
+            Color::LightRed
+        } else {
+            self.file_color(cat)
+        };
+        let size_str = match e.kind {
+            EntryKind::Folder => String::new(),
+            EntryKind::File => format!("  {}", format_size(e.size)),
+        };
+        let size_style = if large {
+            Style::default()
+                .fg(Color::LightRed)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::DarkGray)
+        };
+        let star_marker = if e.starred { "\u{2605} " } else { "" };
+        let cart_marker = if self.cart_ids.contains(&e.id) {
+            "\u{2606} "
+        } else {
+            ""
+        };
+        let mut spans = vec![
+            Span::styled(ico, Style::default().fg(c)),
+            Span::styled(" ", Style::default()),
+            Span::styled(star_marker, Style::default().fg(Color::Yellow)),
+            Span::styled(
+                cart_marker,
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::DIM),
+            ),
+        ];
+        if filtered {
+            spans.extend(self.filter_name_spans(&e.name, Style::default().fg(c)));
+        } else {
+            spans.push(Span::styled(e.name.as_str(), Style::default().fg(c)));
+        }
+        if let Some(playback) = self.playback.get(&e.id) {
+            spans.push(watched_badge(playback));
+        }
+        if let Some(note) = self.notes.get(&e.id) {
+            let badge = Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::DIM);
+            for label in &note.labels {
+                spans.push(Span::styled(format!(" #{label}"), badge));
+            }
+            if !note.text.is_empty() {
+                spans.push(Span::styled(" \u{270e}", badge));
+            }
+        }
+        spans.push(Span::styled(size_str, size_style));
+        ListItem::new(Line::from(spans))
+    }
+
+    /// The commander pane without focus: its listing with a dimmed cursor
+    /// and border, so it's clear which pane the keys act on.
+    fn draw_idle_pane(&self, f: &mut Frame, area: Rect, pane: &BrowseTab) {
+        let items: Vec<ListItem> = pane
+            .entries
+            .iter()
+            .map(|e| self.entry_list_item(e, false))
+            .collect();
+        let mut state = ListState::default();
+        if !pane.entries.is_empty() {
+            state.select(Some(pane.selected.min(pane.entries.len() - 1)));
+        }
+        let block = self
+            .styled_block()
+            .title(format!(" {} ", pane.path_display()))
+            .title_style(Style::default().fg(Color::DarkGray))
+            .border_style(Style::default().fg(Color::DarkGray));
+        let list = List::new(items)
+            .block(block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED | Modifier::DIM))
+            .highlight_symbol("  ");
+        f.render_stateful_widget(list, area, &mut state);
+    }
+
+    /// Clickable column header above the file list; the active sort column
+    /// is highlighted and shows the current direction.
+    fn sort_header_line(&self) -> Line<'static> {
+        let active = self.config.sort_field;
+        let reverse = self.config.sort_reverse;
+        let mut spans = vec![Span::raw(" ".repeat(widgets::SORT_HEADER_INDENT))];
+        for (idx, &field) in widgets::SORT_HEADER_FIELDS.iter().enumerate() {
+            if idx > 0 {
+                spans.push(Span::raw(" ".repeat(widgets::SORT_HEADER_GAP)));
+            }
+            let style = if field == active {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            spans.push(Span::styled(
+                widgets::sort_header_label(field, active, reverse),
+                style,
+            ));
+        }
+        Line::from(spans)
+    }
+
+    /// Split `name` into spans with the characters matched by the active
+    /// filter query underlined, so it's clear why an entry is still listed.
+    fn filter_name_spans<'a>(&self, name: &'a str, style: Style) -> Vec<Span<'a>> {
+        let positions = if self.filter_query.is_empty() {
+            None
+        } else {
+            super::filter::fuzzy_match(name, &self.filter_query)
+        };
+        let Some(positions) = positions.filter(|p| !p.is_empty()) else {
+            return vec![Span::styled(name, style)];
+        };
+        let hit = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+        let mut spans = Vec::new();
+        let mut run = String::new();
+        let mut run_is_hit = false;
+        for (idx, ch) in name.chars().enumerate() {
+            let is_hit = positions.binary_search(&idx).is_ok();
+            if is_hit != run_is_hit && !run.is_empty() {
+                let s = if run_is_hit { hit } else { style };
+                spans.push(Span::styled(std::mem::take(&mut run), s));
+            }
+            run_is_hit = is_hit;
+            run.push(ch);
+        }
+        if !run.is_empty() {
+            spans.push(Span::styled(run, if run_is_hit { hit } else { style }));
+        }
+        spans
+    }
+
+    /// Render `image` into `area` with the configured thumbnail mode.
+    fn draw_image(&self, f: &mut Frame, image: &image::DynamicImage, area: Rect) {
+        use crate::config::ThumbnailRenderMode;
+        use ratatui_image::StatefulImage;
+
+        let render_mode = self.config.thumbnail_mode.should_use_color();
+
+        match render_mode {
+            ThumbnailRenderMode::Auto => {
+                let mut used_protocol = false;
+                if let Some(picker) = self.configured_image_picker() {
+                    let render_rect = center_image_rect(image, area);
+                    let img_display = upscale_for_rect(image, render_rect, picker.font_size());
+                    let mut protocol = picker.new_resize_protocol(img_display);
+                    let img_widget = StatefulImage::default();
+                    f.render_stateful_widget(img_widget, render_rect, &mut protocol);
+                    used_protocol = true;
+                }
+                // Fallback to halfblock when no protocol is available
+                if !used_protocol {
+                    let colored_lines = render_image_to_colored_lines(
+                        image,
+                        area.width as u32,
+                        area.height as u32,
+                        self.config.color_depth,
+                    );
+                    let colored_para = Paragraph::new(Text::from(colored_lines));
+                    f.render_widget(colored_para, area);
+                }
+            }
+            ThumbnailRenderMode::ColoredHalf => {
+                let colored_lines = render_image_to_colored_lines(
+                    image,
+                    area.width as u32,
+                    area.height as u32,
+                    self.config.color_depth,
+                );
+                let colored_para = Paragraph::new(Text::from(colored_lines));
+                f.render_widget(colored_para, area);
+            }
+            ThumbnailRenderMode::Grayscale => {
+                let ascii_lines =
+                    render_image_to_grayscale_lines(image, area.width as u32, area.height as u32);
+                let ascii_para = Paragraph::new(Text::from(ascii_lines))
+                    .style(Style::default().fg(Color::DarkGray));
+                f.render_widget(ascii_para, area);
+            }
+            ThumbnailRenderMode::Off => {}
+        }
+    }
+
+    /// Gallery mode: the current image over its size, dimensions and EXIF
+    /// date, with its place in the folder in the title.
+    fn draw_gallery(&self, f: &mut Frame, area: Rect, gallery: &Gallery) {
+        let entry = gallery.current();
+        let title = format!(
+            " \u{1f5bc} {}/{} {} ",
+            gallery.index + 1,
+            gallery.images.len(),
+            truncate_name(&entry.name, 25)
+        );
+        let border = self
+            .styled_block()
+            .title(title)
+            .title_style(
+                Style::default()
+                    .fg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .border_style(Style::default().fg(Color::DarkGray));
+        let inner = border.inner(area);
+        f.render_widget(border, area);
+
+        let picture = match gallery.slot() {
+            Some(Slot::Ready(picture)) => picture,
+            Some(Slot::Failed(e)) => {
+                let p = Paragraph::new(Text::from(vec![
+                    Line::from(""),
+                    Line::from(Span::styled(
+                        format!("  Can't show this image: {e}"),
+                        Style::default().fg(Color::Red),
+                    )),
+                ]))
+                .wrap(Wrap { trim: false });
+                f.render_widget(p, inner);
+                return;
+            }
+            Some(Slot::Loading) | None => {
+                let spinner = SPINNER_FRAMES[self.spinner_idx];
+                let p = Paragraph::new(Text::from(vec![
+                    Line::from(""),
+                    Line::from(Span::styled(
+                        format!("  {spinner} Loading..."),
+                        Style::default().fg(Color::Cyan),
+                    )),
+                ]));
+                f.render_widget(p, inner);
+                return;
+            }
+        };
+        let label = Style::default().fg(Color::DarkGray);
+        let mut info = vec![Line::from(vec![
+            Span::styled("  Size:  ", label),
+            Span::raw(format_size(entry.size)),
+        ])];
+        info.push(Line::from(vec![
+            Span::styled("  Dimensions:  ", label),
+            Span::raw(format!("{} \u{d7} {}", picture.width, picture.height)),
+        ]));
+        if let Some(taken) = &picture.taken {
+            info.push(Line::from(vec![
+                Span::styled("  Taken:  ", label),
+                Span::raw(taken.clone()),
+            ]));
+        }
+
+        let info_height = (info.len() as u16).min(inner.height / 2);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(info_height)])
+            .split(inner);
+        self.draw_image(f, &picture.image, chunks[0]);
+        f.render_widget(Paragraph::new(Text::from(info)), chunks[1]);
     }
 
     fn draw_preview_pane(&self, f: &mut Frame, area: ratatui::layout::Rect) {
+        if let InputMode::Gallery(gallery) = &self.input {
+            self.draw_gallery(f, area, gallery);
+            return;
+        }
         match &self.preview_state {
             PreviewState::Empty => {
-                let hint = if self.config.lazy_preview {
+                let hint = if self.preview_paused {
+                    "Auto-preview paused after failures; press p to resume"
+                } else if self.config.lazy_preview {
                     "Select an item"
                 } else {
                     "Press p to load preview"
@@ -1106,7 +1747,7 @@ impl App {
                     lines.extend(self.entry_info_lines(entry, wrap_w));
                     lines.push(Line::from(""));
                     let hint = if entry.kind == EntryKind::File
-                        && crate::theme::is_text_previewable(entry)
+                        && self.text_previewable(entry)
                         && entry.size > self.config.preview_max_size
                     {
                         "  Press p to preview (large file)"
@@ -1128,9 +1769,6 @@ impl App {
                 f.render_widget(p, area);
             }
             PreviewState::ThumbnailImage { image } if !self.has_overlay() => {
-                use crate::config::ThumbnailRenderMode;
-                use ratatui_image::StatefulImage;
-
                 let panel_width = area.width.saturating_sub(2);
                 let panel_height = area.height.saturating_sub(2);
                 let wrap_w = panel_width.max(1) as usize;
@@ -1162,52 +1800,7 @@ impl App {
                 let image_area = chunks[0];
                 let info_area = chunks[1];
 
-                let render_mode = self.config.thumbnail_mode.should_use_color();
-
-                match render_mode {
-                    ThumbnailRenderMode::Auto => {
-                        let mut used_protocol = false;
-                        if let Some(picker) = self.configured_image_picker() {
-                            let render_rect = center_image_rect(image, image_area);
-                            let img_display =
-                                upscale_for_rect(image, render_rect, picker.font_size());
-                            let mut protocol = picker.new_resize_protocol(img_display);
-                            let img_widget = StatefulImage::default();
-                            f.render_stateful_widget(img_widget, render_rect, &mut protocol);
-                            used_protocol = true;
-                        }
-                        // Fallback to halfblock when no protocol is available
-                        if !used_protocol {
-                            let colored_lines = render_image_to_colored_lines(
-                                image,
-                                image_area.width as u32,
-                                image_area.height as u32,
-                            );
-                            let colored_para = Paragraph::new(Text::from(colored_lines));
-                            f.render_widget(colored_para, image_area);
-                        }
-                    }
-                    ThumbnailRenderMode::ColoredHalf => {
-                        let colored_lines = render_image_to_colored_lines(
-                            image,
-                            image_area.width as u32,
-                            image_area.height as u32,
-                        );
-                        let colored_para = Paragraph::new(Text::from(colored_lines));
-                        f.render_widget(colored_para, image_area);
-                    }
-                    ThumbnailRenderMode::Grayscale => {
-                        let ascii_lines = render_image_to_grayscale_lines(
-                            image,
-                            image_area.width as u32,
-                            image_area.height as u32,
-                        );
-                        let ascii_para = Paragraph::new(Text::from(ascii_lines))
-                            .style(Style::default().fg(Color::DarkGray));
-                        f.render_widget(ascii_para, image_area);
-                    }
-                    ThumbnailRenderMode::Off => {}
-                }
+                self.draw_image(f, image, image_area);
 
                 let info_p = Paragraph::new(Text::from(info_lines));
                 f.render_widget(info_p, info_area);
@@ -1246,6 +1839,62 @@ impl App {
                 );
                 f.render_widget(p, area);
             }
+            PreviewState::ArchiveListing(listing) => {
+                let visible_h = area.height.saturating_sub(3) as usize;
+                let max_scroll = listing.entries.len().saturating_sub(visible_h.max(1));
+                let scroll = self.preview_scroll.min(max_scroll);
+                let name_w = (area.width as usize).saturating_sub(14);
+                let mut lines: Vec<Line> = listing
+                    .entries
+                    .iter()
+                    .skip(scroll)
+                    .take(visible_h)
+                    .map(|e| {
+                        let (size, color) = if e.is_dir {
+                            (String::new(), self.file_color(theme::FileCategory::Folder))
+                        } else {
+                            (format_size(e.size), Color::Reset)
+                        };
+                        Line::from(vec![
+                            Span::styled(
+                                format!(" {size:>9}  "),
+                                Style::default().fg(Color::DarkGray),
+                            ),
+                            Span::styled(
+                                truncate_name(&e.name, name_w),
+                                Style::default().fg(color),
+                            ),
+                        ])
+                    })
+                    .collect();
+                if listing.entries.is_empty() {
+                    lines.push(Line::from(Span::styled(
+                        "  (empty archive)",
+                        Style::default().fg(Color::DarkGray),
+                    )));
+                }
+                lines.push(Line::from(Span::styled(
+                    format!(" {}", archive_summary(listing)),
+                    Style::default().fg(Color::DarkGray),
+                )));
+
+                let title = self
+                    .entries
+                    .get(self.selected)
+                    .map(|e| format!(" {} ", truncate_name(&e.name, 25)))
+                    .unwrap_or_else(|| " Preview ".to_string());
+                let p = Paragraph::new(Text::from(lines)).block(
+                    self.styled_block()
+                        .title(title)
+                        .title_style(
+                            Style::default()
+                                .fg(Color::Cyan)
+                                .add_modifier(Modifier::BOLD),
+                        )
+                        .border_style(Style::default().fg(Color::DarkGray)),
+                );
+                f.render_widget(p, area);
+            }
             PreviewState::FileDetailedInfo(info) => {
                 let wrap_w = area.width.saturating_sub(2) as usize;
                 let mut lines = vec![Line::from("")];
@@ -1275,6 +1924,9 @@ impl App {
                         wrap_w,
                     ));
                 }
+                if let Some(video) = info.video() {
+                    lines.extend(video_lines(video));
+                }
                 if let Some(link) = &info.web_content_link {
                     lines.extend(wrap_labeled_field(
                         "  Link:  ",
@@ -1343,17 +1995,21 @@ impl App {
     pub(super) fn help_pairs(&self) -> Vec<(&str, &str)> {
         match &self.input {
             InputMode::Normal => {
+                let k = &self.keymap;
                 vec![
-                    ("j/k/g/G", "nav"),
+                    (k.nav_label(), "nav"),
                     ("Ctrl+U/D", "half page"),
-                    ("Enter", "open"),
-                    ("Bksp", "back"),
-                    (":", "goto"),
-                    ("u", "upload"),
-                    ("r", "refresh"),
-                    ("h", "help"),
-                    ("q", "quit"),
+                    (k.label(Action::Open), "open"),
+                    (k.label(Action::Back), "back"),
+                    (k.label(Action::Goto), "goto"),
+                    (k.label(Action::Upload), "upload"),
+                    (k.label(Action::Refresh), "refresh"),
+                    (k.label(Action::Help), "help"),
+                    (k.label(Action::Quit), "quit"),
                 ]
+                .into_iter()
+                .filter(|(key, _)| !key.is_empty())
+                .collect()
             }
             InputMode::MovePicker { .. } | InputMode::CopyPicker { .. } => vec![
                 ("j/k", "nav"),
@@ -1363,6 +2019,22 @@ impl App {
                 ("h", "help"),
                 ("Esc", "cancel"),
             ],
+            InputMode::OfflinePicker { .. } => vec![
+                ("j/k", "nav"),
+                ("Enter", "open"),
+                ("Bksp", "back"),
+                ("Space", "download here"),
+                ("h", "help"),
+                ("Esc", "back to links"),
+            ],
+            InputMode::RestorePicker { .. } => vec![
+                ("j/k", "nav"),
+                ("Enter", "open"),
+                ("Bksp", "back"),
+                ("Space", "restore here"),
+                ("h", "help"),
+                ("Esc", "back to trash"),
+            ],
             InputMode::MoveInput { .. }
             | InputMode::CopyInput { .. }
             | InputMode::CartMoveInput { .. }
@@ -1372,11 +2044,34 @@ impl App {
                 ("Ctrl+B", "picker"),
                 ("Esc", "cancel"),
             ],
-            InputMode::Rename { .. } | InputMode::Mkdir { .. } => {
+            InputMode::NewFile(_) => vec![
+                ("Tab", "name/text"),
+                ("Ctrl+T", "template"),
+                ("Ctrl+S", "create"),
+                ("Esc", "cancel"),
+            ],
+            InputMode::Note(_) => {
+                vec![("Tab", "labels/note"), ("Enter", "save"), ("Esc", "cancel")]
+            }
+            InputMode::BatchRename(_) => vec![
+                ("Tab", "find/replace"),
+                ("Ctrl+R", "regex"),
+                ("Ctrl+N", "tidy"),
+                ("Enter", "rename"),
+                ("Esc", "cancel"),
+            ],
+            InputMode::Rename { .. } | InputMode::Mkdir { .. } | InputMode::CartGlob { .. } => {
                 vec![("Enter", "confirm"), ("Esc", "cancel")]
             }
             InputMode::GotoPath { .. } => {
-                vec![("Enter", "go"), ("Esc", "cancel")]
+                vec![("Tab", "complete"), ("Enter", "go"), ("Esc", "cancel")]
+            }
+            InputMode::Filter => {
+                vec![
+                    ("\u{2191}/\u{2193}", "nav"),
+                    ("Enter", "keep"),
+                    ("Esc", "clear"),
+                ]
             }
             InputMode::ConfirmQuit => {
                 vec![("y", "quit"), ("n/Esc", "cancel")]
@@ -1384,7 +2079,9 @@ impl App {
             InputMode::ConfirmDelete => {
                 vec![("y", "confirm"), ("p", "permanent"), ("n/Esc", "cancel")]
             }
-            InputMode::ConfirmPermanentDelete { .. } => {
+            InputMode::ConfirmPermanentDelete { .. }
+            | InputMode::ConfirmPurge { .. }
+            | InputMode::ConfirmEmptyTrash { .. } => {
                 vec![("Enter", "confirm"), ("Esc", "cancel")]
             }
             InputMode::CartView => vec![
@@ -1407,21 +2104,46 @@ impl App {
                 ("Backspace", "go up"),
                 ("Esc", "cancel"),
             ],
-            InputMode::ConfirmCartDelete => {
-                vec![("y/Enter", "trash"), ("n/Esc", "cancel")]
-            }
-            InputMode::DownloadInput { .. } | InputMode::UploadInput { .. } => {
+            InputMode::ConfirmBatch(summary) => match &summary.conflicts {
+                None => vec![("Esc", "cancel")],
+                Some(c) if c.is_empty() => vec![("y/Enter", "go ahead"), ("n/Esc", "cancel")],
+                Some(_) => vec![("Y", "go ahead anyway"), ("n/Esc", "cancel")],
+            },
+            InputMode::DownloadInput { .. } => {
                 vec![("Tab", "complete"), ("Enter", "confirm"), ("Esc", "cancel")]
             }
+            InputMode::UploadInput(_) => vec![
+                ("Tab", "complete"),
+                ("\u{2191}/\u{2193}", "switch field"),
+                ("Enter", "upload"),
+                ("Esc", "cancel"),
+            ],
+            InputMode::Gallery(_) => vec![
+                ("\u{2190}/\u{2192}", "prev/next"),
+                ("g/G", "first/last"),
+                ("Esc", "close"),
+            ],
+            InputMode::UploadConflict(_) => vec![
+                ("o", "overwrite"),
+                ("k", "keep both"),
+                ("s", "skip"),
+                ("O/K/S", "all"),
+                ("Esc", "cancel"),
+            ],
             InputMode::DownloadView => vec![
                 ("j/k", "nav"),
                 ("Enter", "expand"),
+                ("Space", "fold group"),
                 ("p", "pause/resume"),
                 ("x", "cancel"),
                 ("r", "retry"),
+                ("+/-", "limit"),
+                ("w", "when done"),
+                ("W", "when all done"),
+                ("c", "clear done"),
                 ("Esc", "back"),
             ],
-            InputMode::OfflineInput { .. } => vec![("Enter", "submit"), ("Esc", "cancel")],
+            InputMode::OfflineInput { .. } => vec![("Enter", "choose folder"), ("Esc", "cancel")],
             InputMode::OfflineTasksView { .. } => vec![
                 ("j/k", "nav"),
                 ("r", "refresh"),
@@ -1434,8 +2156,11 @@ impl App {
                     vec![
                         ("j/k", "nav"),
                         ("Space", "info"),
+                        ("a/A", "mark/all"),
                         ("u", "restore"),
+                        ("U", "restore to"),
                         ("x", "delete"),
+                        ("E", "empty trash"),
                         ("r", "refresh"),
                         ("Enter", "collapse"),
                         ("Esc", "close"),
@@ -1445,8 +2170,11 @@ impl App {
                         ("j/k", "nav"),
                         ("Enter", "expand"),
                         ("Space", "info"),
+                        ("a/A", "mark/all"),
                         ("u", "restore"),
+                        ("U", "restore to"),
                         ("x", "delete"),
+                        ("E", "empty trash"),
                         ("r", "refresh"),
                         ("Esc", "close"),
                     ]
@@ -1485,6 +2213,7 @@ impl App {
                     ]
                 }
             }
+            InputMode::KeymapView { .. } => vec![("j/k", "scroll"), ("Esc", "back")],
             InputMode::ImageProtocolSettings { .. } => {
                 vec![
                     ("j/k", "nav"),
@@ -1493,12 +2222,38 @@ impl App {
                     ("Esc", "back"),
                 ]
             }
-            InputMode::ConfirmPlay { .. } => {
-                vec![("y/Enter", "play"), ("n/Esc", "cancel")]
+            InputMode::ConfirmPlay { subs, .. } => {
+                let mut hints = vec![("y/Enter", "play")];
+                if !subs.found.is_empty() {
+                    hints.push(("s", "subtitles"));
+                }
+                hints.push(("n/Esc", "cancel"));
+                hints
+            }
+            InputMode::PlayPicker { subs, .. } => {
+                let mut hints = vec![("j/k", "nav"), ("Enter", "play")];
+                if !subs.found.is_empty() {
+                    hints.push(("s", "subtitles"));
+                }
+                hints.push(("Esc", "cancel"));
+                hints
             }
-            InputMode::PlayPicker { .. } => {
-                vec![("j/k", "nav"), ("Enter", "play"), ("Esc", "cancel")]
+            InputMode::ProfilePicker { .. } => {
+                vec![("j/k", "nav"), ("Enter", "switch"), ("Esc", "cancel")]
             }
+            InputMode::JobsView { .. } => vec![
+                ("j/k", "nav"),
+                ("r", "retry"),
+                ("U", "undo last"),
+                ("c", "clear finished"),
+                ("Esc", "close"),
+            ],
+            InputMode::ActivityView { .. } => vec![
+                ("j/k", "nav"),
+                ("Enter", "go to file"),
+                ("r", "refresh"),
+                ("Esc", "close"),
+            ],
             InputMode::PlayerInput { .. } => {
                 vec![("Enter", "confirm"), ("Esc", "cancel")]
             }
@@ -1556,12 +2311,17 @@ impl App {
 
         match &self.input {
             InputMode::Normal
+            | InputMode::Filter
             | InputMode::Login { .. }
+            | InputMode::LoginCode { .. }
             | InputMode::MovePicker { .. }
             | InputMode::CopyPicker { .. }
             | InputMode::CartMovePicker { .. }
             | InputMode::CartCopyPicker { .. }
+            | InputMode::OfflinePicker { .. }
+            | InputMode::RestorePicker { .. }
             | InputMode::DownloadView
+            | InputMode::Gallery(_)
             | InputMode::MySharesView { .. } => {}
 
             InputMode::MoveInput { input, .. } => {
@@ -1591,11 +2351,23 @@ impl App {
             InputMode::Rename { value } => {
                 self.draw_rename_overlay(f, value, cur);
             }
+            InputMode::BatchRename(state) => {
+                self.draw_batch_rename_overlay(f, state, cur);
+            }
             InputMode::Mkdir { value } => {
                 self.draw_mkdir_overlay(f, value, cur);
             }
-            InputMode::GotoPath { query } => {
-                self.draw_goto_overlay(f, query, cur);
+            InputMode::NewFile(form) => {
+                self.draw_new_file_overlay(f, form, cur);
+            }
+            InputMode::Note(form) => {
+                self.draw_note_overlay(f, form, cur);
+            }
+            InputMode::CartGlob { pattern } => {
+                self.draw_cart_glob_overlay(f, pattern, cur);
+            }
+            InputMode::GotoPath { input } => {
+                self.draw_goto_overlay(f, input, cur);
             }
             InputMode::ConfirmQuit => {
                 self.draw_confirm_quit_overlay(f);
@@ -1604,19 +2376,42 @@ impl App {
                 self.draw_confirm_delete_overlay(f);
             }
             InputMode::ConfirmPermanentDelete { value } => {
-                self.draw_confirm_permanent_delete_overlay(f, value, cur);
+                let name = self
+                    .current_entry()
+                    .map(|e| format!("`{}`", e.name))
+                    .unwrap_or_else(|| "<none>".into());
+                self.draw_confirm_permanent_delete_overlay(f, &name, value, cur);
+            }
+            InputMode::ConfirmPurge { targets, value } => {
+                let what = match targets.as_slice() {
+                    [(_, name)] => format!("`{}`", name),
+                    many => format!("{} items", many.len()),
+                };
+                self.draw_confirm_permanent_delete_overlay(f, &what, value, cur);
+            }
+            InputMode::ConfirmEmptyTrash { value } => {
+                self.draw_confirm_empty_trash_overlay(f, value, cur);
             }
             InputMode::CartView => {
                 self.draw_cart_overlay(f);
             }
-            InputMode::ConfirmCartDelete => {
-                self.draw_confirm_cart_delete_overlay(f);
+            InputMode::JobsView { selected } => {
+                self.draw_jobs_overlay(f, *selected);
+            }
+            InputMode::ActivityView { selected } => {
+                self.draw_activity_overlay(f, *selected);
+            }
+            InputMode::ConfirmBatch(summary) => {
+                self.draw_batch_summary_overlay(f, summary);
             }
             InputMode::DownloadInput { input } => {
                 self.draw_download_input_overlay(f, input, cur);
             }
-            InputMode::UploadInput { input } => {
-                self.draw_upload_input_overlay(f, input, cur);
+            InputMode::UploadInput(form) => {
+                self.draw_upload_input_overlay(f, form, cur);
+            }
+            InputMode::UploadConflict(plan) => {
+                self.draw_upload_conflict_overlay(f, plan);
             }
             InputMode::OfflineInput { value } => {
                 self.draw_offline_input_overlay(f, value, cur);
@@ -1642,8 +2437,9 @@ impl App {
                 info,
                 image,
                 has_thumbnail,
+                archive,
             } => {
-                self.draw_info_overlay(f, info, image.as_ref(), *has_thumbnail);
+                self.draw_info_overlay(f, info, image.as_ref(), *has_thumbnail, archive.as_ref());
             }
             InputMode::InfoFolderView { name, entries } => {
                 self.draw_info_folder_overlay(f, name, entries);
@@ -1681,6 +2477,9 @@ impl App {
                     *rgb_component,
                 );
             }
+            InputMode::KeymapView { scroll, .. } => {
+                self.draw_keymap_overlay(f, *scroll);
+            }
             InputMode::ImageProtocolSettings {
                 selected,
                 draft,
@@ -1697,15 +2496,22 @@ impl App {
                     terminals,
                 );
             }
-            InputMode::ConfirmPlay { name, url } => {
-                self.draw_confirm_play_overlay(f, name, url);
+            InputMode::ConfirmPlay {
+                name, url, subs, ..
+            } => {
+                self.draw_confirm_play_overlay(f, name, url, subs);
             }
             InputMode::PlayPicker {
                 name,
                 medias,
                 selected,
+                subs,
+                ..
             } => {
-                self.draw_play_picker_overlay(f, name, medias, *selected);
+                self.draw_play_picker_overlay(f, name, medias, *selected, subs);
+            }
+            InputMode::ProfilePicker { profiles, selected } => {
+                self.draw_profile_picker_overlay(f, profiles, *selected);
             }
             InputMode::PlayerInput { value, .. } => {
                 self.draw_player_input_overlay(f, value);
@@ -1746,52 +2552,278 @@ impl App {
         );
     }
 
-    fn draw_mkdir_overlay(&self, f: &mut Frame, value: &str, cur: &str) {
-        let area = self.prepare_overlay(f, 60, 20);
+    fn draw_batch_rename_overlay(&self, f: &mut Frame, state: &BatchRename, cur: &str) {
+        let pct = widgets::dynamic_overlay_height(state.targets.len(), 10, f.area().height, 30, 75);
+        let area = centered_rect(75, pct, f.area());
+        clear_overlay_area(f, area);
         let (bc, tc) = if self.is_vibrant() {
             (Color::LightYellow, Color::LightYellow)
         } else {
             (Color::Cyan, Color::Yellow)
         };
-        f.render_widget(
-            Paragraph::new(vec![
-                Line::from(""),
-                Line::from(vec![
-                    Span::styled("  Folder name: ", Style::default().fg(Color::Cyan)),
-                    Span::styled(
-                        format!("{}{}", value, cur),
-                        Style::default().fg(Color::Yellow),
-                    ),
-                ]),
-                Line::from(""),
-                Self::hint_line(&[("Enter", "confirm"), ("Esc", "cancel")]),
-            ])
-            .block(self.overlay_block("New Folder", bc, tc)),
-            area,
-        );
+
+        let field = |label: &'static str, value: &str, active: bool| {
+            let label_style = if active {
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+            Line::from(vec![
+                Span::styled(label, label_style),
+                Span::styled(
+                    format!("{}{}", value, if active { cur } else { "" }),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ])
+        };
+        let mode = match (state.regex, state.normalize) {
+            (true, false) => "regex (${1} for groups)",
+            (false, false) => "plain text",
+            (true, true) => "regex, then tidied up",
+            (false, true) => "plain text, then tidied up",
+        };
+        let mut lines = vec![
+            Line::from(""),
+            field("  Find:    ", &state.find, !state.on_replace),
+            field("  Replace: ", &state.replace, state.on_replace),
+            Line::from(Span::styled(
+                format!("  Match: {mode} \u{00b7} {{n}} / {{n:03}} counts 1, 2, \u{2026}"),
+                Style::default().fg(Color::DarkGray),
+            )),
+            Line::from(""),
+        ];
+
+        let max_visible = (area.height as usize)
+            .saturating_sub(lines.len() + 4)
+            .max(1);
+        let name_w = (area.width as usize).saturating_sub(10) / 2;
+        match state.preview() {
+            Err(e) => lines.push(Line::from(Span::styled(
+                format!("  {e}"),
+                Style::default().fg(Color::Red),
+            ))),
+            Ok(rows) => {
+                for row in rows.iter().take(max_visible) {
+                    let (new_style, note) = match row.problem {
+                        Some(problem) => (Style::default().fg(Color::Red), format!("  {problem}")),
+                        None if row.new == row.old => {
+                            (Style::default().fg(Color::DarkGray), String::new())
+                        }
+                        None => (Style::default().fg(Color::Green), String::new()),
+                    };
+                    lines.push(Line::from(vec![
+                        Span::raw("  "),
+                        Span::styled(
+                            format!("{:<name_w$}", truncate_name(row.old, name_w)),
+                            Style::default().fg(Color::Reset),
+                        ),
+                        Span::styled(" \u{2192} ", Style::default().fg(Color::DarkGray)),
+                        Span::styled(truncate_name(&row.new, name_w), new_style),
+                        Span::styled(note, Style::default().fg(Color::Red)),
+                    ]));
+                }
+                widgets::push_remaining_indicator(&mut lines, rows.len(), 0, max_visible);
+            }
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Self::hint_line(&self.help_pairs()));
+        let title = format!("Batch Rename ({})", state.targets.len());
+        f.render_widget(
+            Paragraph::new(Text::from(lines)).block(self.overlay_block(&title, bc, tc)),
+            area,
+        );
     }
 
-    fn draw_goto_overlay(&self, f: &mut Frame, query: &str, cur: &str) {
-        let area = self.prepare_overlay(f, 70, 20);
+    fn draw_new_file_overlay(&self, f: &mut Frame, form: &NewFile, cur: &str) {
+        let area = self.prepare_overlay(f, 70, 60);
         let (bc, tc) = self.themed_colors(Color::Cyan);
+        let label_style = |active: bool| {
+            if active {
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            }
+        };
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  Name: ", label_style(!form.on_body)),
+                Span::styled(
+                    format!("{}{}", form.name, if form.on_body { "" } else { cur }),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ]),
+            Line::from(Span::styled(
+                match form.template_name() {
+                    Some(name) => format!("  Text (from template {name}):"),
+                    None => "  Text:".to_string(),
+                },
+                label_style(form.on_body),
+            )),
+        ];
+
+        // The end of the text, where typing goes, stays in view.
+        let mut body: Vec<String> = form.body.split('\n').map(|l| format!("    {l}")).collect();
+        if form.on_body
+            && let Some(last) = body.last_mut()
+        {
+            last.push_str(cur);
+        }
+        let max_visible = (area.height as usize)
+            .saturating_sub(lines.len() + 5)
+            .max(1);
+        let skip = body.len().saturating_sub(max_visible);
+        if skip > 0 {
+            lines.push(Line::from(Span::styled(
+                format!("    \u{2026} {skip} more line(s) above"),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        lines.extend(
+            body.into_iter()
+                .skip(skip + usize::from(skip > 0))
+                .map(|l| Line::from(Span::styled(l, Style::default().fg(Color::Yellow)))),
+        );
+
+        lines.push(Line::from(""));
+        lines.push(Self::hint_line(&self.help_pairs()));
+        let title = format!("New File in {}", self.current_path_display());
+        f.render_widget(
+            Paragraph::new(Text::from(lines)).block(self.overlay_block(&title, bc, tc)),
+            area,
+        );
+    }
+
+    fn draw_note_overlay(&self, f: &mut Frame, form: &NoteForm, cur: &str) {
+        let area = self.prepare_overlay(f, 60, 30);
+        let (bc, tc) = self.themed_colors(Color::Cyan);
+        let label_style = |active: bool| {
+            if active {
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            }
+        };
+        let lines = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  Labels: ", label_style(!form.on_text)),
+                Span::styled(
+                    format!("{}{}", form.labels, if form.on_text { "" } else { cur }),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ]),
+            Line::from(vec![
+                Span::styled("  Note:   ", label_style(form.on_text)),
+                Span::styled(
+                    format!("{}{}", form.text, if form.on_text { cur } else { "" }),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ]),
+            Line::from(Span::styled(
+                "  Labels are separated by commas or spaces; kept on this computer only.",
+                Style::default().fg(Color::DarkGray),
+            )),
+            Line::from(""),
+            Self::hint_line(&self.help_pairs()),
+        ];
+        let title = format!("Note on {}", form.name);
+        f.render_widget(
+            Paragraph::new(Text::from(lines))
+                .block(self.overlay_block(&title, bc, tc))
+                .wrap(Wrap { trim: false }),
+            area,
+        );
+    }
+
+    fn draw_mkdir_overlay(&self, f: &mut Frame, value: &str, cur: &str) {
+        let area = self.prepare_overlay(f, 60, 20);
+        let (bc, tc) = if self.is_vibrant() {
+            (Color::LightYellow, Color::LightYellow)
+        } else {
+            (Color::Cyan, Color::Yellow)
+        };
         f.render_widget(
             Paragraph::new(vec![
                 Line::from(""),
                 Line::from(vec![
-                    Span::styled("  Path: ", Style::default().fg(Color::Cyan)),
+                    Span::styled("  Folder name: ", Style::default().fg(Color::Cyan)),
                     Span::styled(
-                        format!("{}{}", query, cur),
+                        format!("{}{}", value, cur),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                ]),
+                Line::from(""),
+                Self::hint_line(&[("Enter", "confirm"), ("Esc", "cancel")]),
+            ])
+            .block(self.overlay_block("New Folder", bc, tc)),
+            area,
+        );
+    }
+
+    fn draw_cart_glob_overlay(&self, f: &mut Frame, pattern: &str, cur: &str) {
+        let area = self.prepare_overlay(f, 60, 20);
+        let (bc, tc) = self.themed_colors(Color::Cyan);
+        f.render_widget(
+            Paragraph::new(vec![
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled("  Pattern: ", Style::default().fg(Color::Cyan)),
+                    Span::styled(
+                        format!("{}{}", pattern, cur),
                         Style::default().fg(Color::Yellow),
                     ),
                 ]),
                 Line::from(Span::styled(
-                    "  e.g. /My Files/Movies",
+                    "  e.g. *.mkv, ep??.*",
                     Style::default().fg(Color::DarkGray),
                 )),
                 Line::from(""),
-                Self::hint_line(&[("Enter", "go"), ("Esc", "cancel")]),
+                Self::hint_line(&[("Enter", "add to cart"), ("Esc", "cancel")]),
             ])
-            .block(self.overlay_block("Go to Path", bc, tc)),
+            .block(self.overlay_block("Select by Glob", bc, tc)),
+            area,
+        );
+    }
+
+    fn draw_goto_overlay(&self, f: &mut Frame, input: &PathInput, cur: &str) {
+        let candidates = Self::completion_lines(input);
+        let pct = (((6 + candidates.len()) as u16 * 100) / f.area().height.max(1)).clamp(20, 60);
+        let area = self.prepare_overlay(f, 70, pct);
+        let (bc, tc) = self.themed_colors(Color::Cyan);
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  Path: ", Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    format!("{}{}", input.value, cur),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ]),
+        ];
+        if candidates.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "  e.g. /My Files/Movies, a file id, or :starred",
+                Style::default().fg(Color::DarkGray),
+            )));
+        } else {
+            lines.extend(candidates);
+        }
+        lines.push(Line::from(""));
+        lines.push(Self::hint_line(&[
+            ("Tab", "complete"),
+            ("Enter", "go"),
+            ("Esc", "cancel"),
+        ]));
+        f.render_widget(
+            Paragraph::new(lines).block(self.overlay_block("Go to Path", bc, tc)),
             area,
         );
     }
@@ -1876,12 +2908,61 @@ impl App {
         );
     }
 
-    fn draw_confirm_permanent_delete_overlay(&self, f: &mut Frame, value: &str, cur: &str) {
+    fn draw_confirm_empty_trash_overlay(&self, f: &mut Frame, value: &str, cur: &str) {
+        let area = self.prepare_overlay(f, 60, 55);
+        let mut lines = vec![Line::from("")];
+        lines.extend(warn_triangle_lines());
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled(
+                "      EMPTY TRASH ",
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("({} item(s) listed)", self.trash_entries.len()),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]));
+        lines.push(Line::from(Span::styled(
+            "        Everything in the trash is deleted. This cannot be undone!",
+            Style::default().fg(Color::Red),
+        )));
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled(
+                "  Type 'yes' to confirm: ",
+                Style::default().fg(Color::Reset),
+            ),
+            Span::styled(
+                format!("{}{}", value, cur),
+                Style::default().fg(Color::Yellow),
+            ),
+        ]));
+        lines.push(Line::from(""));
+        lines.push(Self::hint_line(&[("Enter", "confirm"), ("Esc", "cancel")]));
+        f.render_widget(
+            Paragraph::new(lines).block(
+                self.styled_block()
+                    .title(Span::styled(
+                        " \u{26a0} Empty Trash ",
+                        Style::default().fg(Color::Red),
+                    ))
+                    .border_style(Style::default().fg(Color::Red)),
+            ),
+            area,
+        );
+    }
+
+    fn draw_confirm_permanent_delete_overlay(
+        &self,
+        f: &mut Frame,
+        what: &str,
+        value: &str,
+        cur: &str,
+    ) {
         let area = self.prepare_overlay(f, 60, 55);
-        let name = self
-            .current_entry()
-            .map(|e| e.name.as_str())
-            .unwrap_or("<none>");
         let warn_lines = warn_triangle_lines();
         let mut lines = vec![Line::from("")];
         lines.extend(warn_lines);
@@ -1892,7 +2973,7 @@ impl App {
                 Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
             ),
             Span::styled(
-                format!("`{}`", name),
+                what.to_string(),
                 Style::default()
                     .fg(Color::Yellow)
                     .add_modifier(Modifier::BOLD),
@@ -1928,30 +3009,148 @@ impl App {
         );
     }
 
-    fn draw_confirm_cart_delete_overlay(&self, f: &mut Frame) {
-        let count = self.cart.len();
-        self.draw_simple_confirm(
-            f,
-            "Confirm Trash Cart",
-            vec![
-                Line::from(""),
-                Line::from(vec![
-                    Span::styled("  Trash ", Style::default().fg(Color::Red)),
-                    Span::styled(
-                        format!("{} item(s)", count),
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled(" from cart?", Style::default().fg(Color::Red)),
-                ]),
-                Line::from(""),
-                Self::hint_line(&[("y/Enter", "trash"), ("n/Esc", "cancel")]),
-            ],
-            Color::Red,
+    fn draw_batch_summary_overlay(&self, f: &mut Frame, summary: &BatchSummary) {
+        let verb = summary.action.verb();
+        let count = summary.entries.len();
+        let folders = summary.folders();
+        let mut what = format!("{count} item(s)");
+        if folders > 0 {
+            what.push_str(&format!(", {folders} folder(s)"));
+        }
+        let size = format_size(summary.total_size());
+        let size = if folders > 0 {
+            format!("{size} in files")
+        } else {
+            size
+        };
+        let dim = Style::default().fg(Color::DarkGray);
+        let mut body = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled(format!("  {verb} "), Style::default().fg(Color::Reset)),
+                Span::styled(
+                    what,
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(format!("  ({size})"), dim),
+            ]),
+        ];
+        if let Some(dest) = summary.action.destination() {
+            body.push(Line::from(vec![
+                Span::styled("  to ", Style::default().fg(Color::Reset)),
+                Span::styled(dest, Style::default().fg(Color::Cyan)),
+            ]));
+        }
+        if let BatchAction::Download { on_complete, .. } = &summary.action {
+            body.push(Line::from(vec![
+                Span::styled("  when done: ", Style::default().fg(Color::Reset)),
+                Span::styled(on_complete.label(), Style::default().fg(Color::Cyan)),
+                Span::styled("  (w to change)", dim),
+            ]));
+        }
+        let names: Vec<&str> = summary
+            .entries
+            .iter()
+            .take(3)
+            .map(|e| e.name.as_str())
+            .collect();
+        let mut listed = format!("  {}", names.join(", "));
+        if count > names.len() {
+            listed.push_str(&format!(" and {} more", count - names.len()));
+        }
+        body.push(Line::from(Span::styled(listed, dim)));
+        body.push(Line::from(""));
+
+        let base = match &summary.conflicts {
+            None => {
+                body.push(Line::from(Span::styled(
+                    "  Checking the destination for name clashes\u{2026}",
+                    dim,
+                )));
+                Color::Cyan
+            }
+            Some(conflicts) if conflicts.is_empty() => {
+                if summary.action.destination().is_some() {
+                    body.push(Line::from(Span::styled("  No name clashes", dim)));
+                }
+                if matches!(summary.action, BatchAction::Trash) {
+                    Color::Red
+                } else {
+                    Color::Cyan
+                }
+            }
+            Some(conflicts) => {
+                body.push(Line::from(Span::styled(
+                    format!("  {} name clash(es):", conflicts.len()),
+                    Style::default().fg(Color::Yellow),
+                )));
+                for (name, why) in conflicts.iter().take(6) {
+                    body.push(Line::from(vec![
+                        Span::styled(format!("    {name}"), Style::default().fg(Color::Reset)),
+                        Span::styled(format!("  {why}"), dim),
+                    ]));
+                }
+                if conflicts.len() > 6 {
+                    body.push(Line::from(Span::styled(
+                        format!("    and {} more", conflicts.len() - 6),
+                        dim,
+                    )));
+                }
+                Color::Yellow
+            }
+        };
+        body.push(Line::from(""));
+        body.push(match &summary.conflicts {
+            None => Self::hint_line(&[("Esc", "cancel")]),
+            Some(c) if c.is_empty() => {
+                Self::hint_line(&[("y/Enter", &verb.to_lowercase()), ("n/Esc", "cancel")])
+            }
+            Some(_) => Self::hint_line(&[("Y", "go ahead anyway"), ("n/Esc", "cancel")]),
+        });
+
+        let area = self.prepare_overlay(f, 60, 40);
+        let (bc, tc) = self.themed_colors(base);
+        f.render_widget(
+            Paragraph::new(body)
+                .wrap(Wrap { trim: false })
+                .block(self.overlay_block(&format!("Confirm {verb}"), bc, tc)),
+            area,
         );
     }
 
+    /// The completion candidates of a path input (up to 8, after a blank
+    /// line), or nothing when there are none.
+    fn completion_lines(input: &PathInput) -> Vec<Line<'static>> {
+        if input.candidates.is_empty() {
+            return Vec::new();
+        }
+        let mut lines = vec![Line::from("")];
+        for (i, name) in input.candidates.iter().enumerate().take(8) {
+            let is_sel = input.candidate_idx == Some(i);
+            let prefix = if is_sel { "  > " } else { "    " };
+            let style = if is_sel {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Blue)
+            };
+            lines.push(Line::from(Span::styled(
+                format!("{}{}/", prefix, name),
+                style,
+            )));
+        }
+        if input.candidates.len() > 8 {
+            lines.push(Line::from(Span::styled(
+                format!("    ... and {} more", input.candidates.len() - 8),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        lines
+    }
+
     fn draw_path_input_overlay(
         &self,
         f: &mut Frame,
@@ -1983,30 +3182,7 @@ impl App {
             ]),
         ];
 
-        if !input.candidates.is_empty() {
-            lines.push(Line::from(""));
-            for (i, name) in input.candidates.iter().enumerate().take(8) {
-                let is_sel = input.candidate_idx == Some(i);
-                let prefix = if is_sel { "  > " } else { "    " };
-                let style = if is_sel {
-                    Style::default()
-                        .fg(Color::Yellow)
-                        .add_modifier(Modifier::BOLD)
-                } else {
-                    Style::default().fg(Color::Blue)
-                };
-                lines.push(Line::from(Span::styled(
-                    format!("{}{}/", prefix, name),
-                    style,
-                )));
-            }
-            if input.candidates.len() > 8 {
-                lines.push(Line::from(Span::styled(
-                    format!("    ... and {} more", input.candidates.len() - 8),
-                    Style::default().fg(Color::DarkGray),
-                )));
-            }
-        }
+        lines.extend(Self::completion_lines(input));
 
         lines.push(Line::from(""));
         lines.push(Self::hint_line(&[
@@ -2085,7 +3261,7 @@ impl App {
         };
 
         let op = if is_move { "Move" } else { "Copy" };
-        self.draw_picker_right_pane(f, chunks[1], picker, is_move);
+        self.draw_picker_right_pane(f, chunks[1], picker, &format!("{} to", op));
 
         if self.config.show_help_bar {
             let pairs = self.help_pairs();
@@ -2151,7 +3327,7 @@ impl App {
         };
 
         let op = if is_move { "Move" } else { "Copy" };
-        self.draw_picker_right_pane(f, chunks[1], picker, is_move);
+        self.draw_picker_right_pane(f, chunks[1], picker, &format!("{} to", op));
 
         if self.config.show_help_bar {
             let pairs = self.help_pairs();
@@ -2174,20 +3350,106 @@ impl App {
         }
     }
 
-    /// Shared right-pane renderer for both single-file and cart picker views.
-    fn draw_picker_right_pane(
+    fn draw_offline_picker(&self, f: &mut Frame, urls: &[String], picker: &PickerState) {
+        let (outer, chunks) = self.build_picker_layout(f);
+
+        let width = chunks[0].width.saturating_sub(4) as usize;
+        let link_items: Vec<ListItem> = urls
+            .iter()
+            .map(|url| {
+                ListItem::new(Line::from(Span::styled(
+                    truncate_name(url, width),
+                    Style::default().fg(Color::Yellow),
+                )))
+            })
+            .collect();
+        let link_list = List::new(link_items).block(
+            self.styled_block()
+                .title(format!(" Links ({}) ", urls.len()))
+                .title_style(Style::default().fg(Color::DarkGray))
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(link_list, chunks[0]);
+
+        self.draw_picker_right_pane(f, chunks[1], picker, "Download to");
+
+        if self.config.show_help_bar {
+            let pairs = self.help_pairs();
+            let mut spans = vec![
+                Span::styled(
+                    format!(" Offline download {} link(s) ", urls.len()),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("│ ", Style::default().fg(Color::DarkGray)),
+            ];
+            spans.extend(Self::styled_help_spans(&pairs));
+            let bar = Paragraph::new(Line::from(spans));
+            f.render_widget(bar, outer[1]);
+        }
+
+        if self.show_help_sheet {
+            self.draw_help_sheet(f);
+        }
+    }
+
+    fn draw_restore_picker(
         &self,
         f: &mut Frame,
-        area: Rect,
+        targets: &[(String, String)],
         picker: &PickerState,
-        is_move: bool,
     ) {
-        let op = if is_move { "Move" } else { "Copy" };
+        let (outer, chunks) = self.build_picker_layout(f);
+
+        let width = chunks[0].width.saturating_sub(4) as usize;
+        let items: Vec<ListItem> = targets
+            .iter()
+            .map(|(_, name)| {
+                ListItem::new(Line::from(Span::styled(
+                    truncate_name(name, width),
+                    Style::default().fg(Color::Yellow),
+                )))
+            })
+            .collect();
+        let list = List::new(items).block(
+            self.styled_block()
+                .title(format!(" Restore ({}) ", targets.len()))
+                .title_style(Style::default().fg(Color::DarkGray))
+                .border_style(Style::default().fg(Color::DarkGray)),
+        );
+        f.render_widget(list, chunks[0]);
+
+        self.draw_picker_right_pane(f, chunks[1], picker, "Restore to");
+
+        if self.config.show_help_bar {
+            let pairs = self.help_pairs();
+            let mut spans = vec![
+                Span::styled(
+                    format!(" Restore {} item(s) ", targets.len()),
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled("│ ", Style::default().fg(Color::DarkGray)),
+            ];
+            spans.extend(Self::styled_help_spans(&pairs));
+            let bar = Paragraph::new(Line::from(spans));
+            f.render_widget(bar, outer[1]);
+        }
+
+        if self.show_help_sheet {
+            self.draw_help_sheet(f);
+        }
+    }
+
+    /// Shared right-pane renderer for the move/copy, cart, offline and restore pickers.
+    fn draw_picker_right_pane(&self, f: &mut Frame, area: Rect, picker: &PickerState, label: &str) {
         let pp = Self::picker_path_display(picker);
         let title = if picker.loading {
-            format!(" {} to: {} {} ", op, pp, SPINNER_FRAMES[self.spinner_idx])
+            format!(" {}: {} {} ", label, pp, SPINNER_FRAMES[self.spinner_idx])
         } else {
-            format!(" {} to: {} ", op, pp)
+            format!(" {}: {} ", label, pp)
         };
 
         let folders: Vec<&crate::pikpak::Entry> = picker
@@ -2256,55 +3518,136 @@ impl App {
                     ],
                 ),
             ],
+            InputMode::OfflinePicker { .. } => vec![
+                (
+                    "Navigation",
+                    vec![
+                        ("j / \u{2193}", "Move down"),
+                        ("k / \u{2191}", "Move up"),
+                        ("Enter", "Open folder"),
+                        ("Bksp", "Go back"),
+                    ],
+                ),
+                (
+                    "Actions",
+                    vec![
+                        ("Space", "Download here"),
+                        ("h", "Toggle help"),
+                        ("Esc", "Back to links"),
+                    ],
+                ),
+            ],
+            InputMode::RestorePicker { .. } => vec![
+                (
+                    "Navigation",
+                    vec![
+                        ("j / \u{2193}", "Move down"),
+                        ("k / \u{2191}", "Move up"),
+                        ("Enter", "Open folder"),
+                        ("Bksp", "Go back"),
+                    ],
+                ),
+                (
+                    "Actions",
+                    vec![
+                        ("Space", "Restore here"),
+                        ("h", "Toggle help"),
+                        ("Esc", "Back to trash"),
+                    ],
+                ),
+            ],
             _ => {
+                let k = &self.keymap;
+                // With more than one tab open the tab keys are listed.
+                let tabbed = self.tabs.len() > 1;
                 let mut nav: Vec<(&str, &str)> = vec![
-                    ("j / \u{2193}", "Move down"),
-                    ("k / \u{2191}", "Move up"),
-                    ("g / Home", "Jump to top"),
-                    ("G / End", "Jump to bottom"),
+                    (k.help_label(Action::Down), "Move down"),
+                    (k.help_label(Action::Up), "Move up"),
+                    (k.help_label(Action::Top), "Jump to top"),
+                    (k.help_label(Action::Bottom), "Jump to bottom"),
                     ("PgDn/Up", "Page scroll"),
-                    ("Enter", "Open / Play"),
-                    ("Bksp", "Go to parent"),
-                    ("r", "Refresh"),
-                    ("S", "Cycle sort"),
-                    ("R", "Reverse sort"),
+                    (k.help_label(Action::Open), "Open / Play"),
+                    (k.help_label(Action::Back), "Go to parent"),
+                    (k.help_label(Action::Refresh), "Refresh"),
+                    (k.help_label(Action::Sort), "Cycle sort"),
+                    (k.help_label(Action::Reverse), "Reverse sort"),
                 ];
-                if !self.config.show_preview {
-                    nav.push(("Space", "File info"));
+                nav.push(("1-5", "Sort by column"));
+                nav.extend([
+                    (k.help_label(Action::Filter), "Filter entries"),
+                    (k.help_label(Action::Recent), "Recent files"),
+                    (k.help_label(Action::Starred), "Starred files"),
+                ]);
+                let commander = self.config.commander_mode;
+                if commander {
+                    nav.push(("Tab", "Switch pane"));
+                } else {
+                    nav.push((k.help_label(Action::NewTab), "New tab"));
+                    nav.push((k.help_label(Action::CloseTab), "Close tab"));
+                    if tabbed {
+                        nav.push((k.help_label(Action::NextTab), "Next tab"));
+                        nav.push((k.help_label(Action::PrevTab), "Previous tab"));
+                        nav.push(("Alt+1-9", "Jump to tab"));
+                    }
+                }
+                if !self.preview_pane_shown() {
+                    nav.push((k.help_label(Action::Info), "File info"));
                 } else if !self.config.lazy_preview {
-                    nav.push(("Space", "Load preview"));
+                    nav.push((k.help_label(Action::Info), "Load preview"));
+                }
+                nav.push((k.help_label(Action::Preview), "Preview"));
+                if self.preview_pane_shown() {
+                    nav.push((k.help_label(Action::Gallery), "Image gallery"));
+                }
+                nav.push((k.help_label(Action::Play), "Watch (streams)"));
+                nav.push((k.help_label(Action::Watched), "Mark watched"));
+
+                let mut actions = vec![
+                    (k.help_label(Action::Copy), "Copy"),
+                    (k.help_label(Action::Move), "Move"),
+                    (k.help_label(Action::Rename), "Rename"),
+                    (k.help_label(Action::BatchRename), "Batch rename"),
+                    (k.help_label(Action::Normalize), "Tidy up names"),
+                    (k.help_label(Action::Delete), "Delete"),
+                    (k.help_label(Action::Undo), "Undo last op"),
+                    (k.help_label(Action::Mkdir), "New folder"),
+                    (k.help_label(Action::NewFile), "New text file"),
+                    (k.help_label(Action::Star), "Star / Unstar"),
+                    (k.help_label(Action::Note), "Note / labels"),
+                    (k.help_label(Action::CopyLink), "Copy link"),
+                    (k.help_label(Action::Share), "Share link"),
+                    (k.help_label(Action::OpenWeb), "Open in web UI"),
+                    (k.help_label(Action::UploadClipboard), "Paste image"),
+                    (k.help_label(Action::Cart), "Add to cart"),
+                    ("Ctrl+A", "Cart: all"),
+                    // Tab switches panes in commander mode.
+                    (if commander { "Ctrl+I" } else { "Tab" }, "Cart: invert"),
+                    (k.help_label(Action::CartGlob), "Cart: by glob"),
+                ];
+                if commander {
+                    actions.push(("F5", "Copy to other pane"));
+                    actions.push(("F6", "Move to other pane"));
                 }
-                nav.push(("p", "Preview"));
-                nav.push(("w", "Watch (streams)"));
 
                 vec![
                     ("Navigation", nav),
-                    (
-                        "Actions",
-                        vec![
-                            ("c", "Copy"),
-                            ("m", "Move"),
-                            ("n", "Rename"),
-                            ("d", "Delete"),
-                            ("f", "New folder"),
-                            ("s", "Star / Unstar"),
-                            ("y", "Copy link"),
-                            ("a", "Add to cart"),
-                        ],
-                    ),
+                    ("Actions", actions),
                     (
                         "Panels",
                         vec![
-                            ("D", "Downloads"),
-                            ("A", "View cart"),
-                            ("M", "My Shares"),
-                            ("o", "Cloud download"),
-                            ("O", "Offline tasks"),
-                            ("t", "Trash"),
-                            ("l", "Toggle logs"),
-                            (",", "Settings"),
-                            ("h", "Toggle help"),
-                            ("q", "Quit"),
+                            (k.help_label(Action::Downloads), "Downloads"),
+                            (k.help_label(Action::CartView), "View cart"),
+                            (k.help_label(Action::Jobs), "Operation queue"),
+                            (k.help_label(Action::Activity), "Activity"),
+                            (k.help_label(Action::Shares), "My Shares"),
+                            (k.help_label(Action::Offline), "Cloud download"),
+                            (k.help_label(Action::OfflineTasks), "Offline tasks"),
+                            (k.help_label(Action::Trash), "Trash"),
+                            (k.help_label(Action::Account), "Switch account"),
+                            (k.help_label(Action::Logs), "Toggle logs"),
+                            (k.help_label(Action::Settings), "Settings"),
+                            (k.help_label(Action::Help), "Toggle help"),
+                            (k.help_label(Action::Quit), "Quit"),
                         ],
                     ),
                 ]
@@ -2516,13 +3859,31 @@ impl App {
                     Style::default().fg(Color::Reset)
                 };
                 let size = format_size(entry.size);
+                let size_color = if self.is_large(entry.size) {
+                    Color::LightRed
+                } else {
+                    Color::DarkGray
+                };
                 lines.push(Line::from(vec![
                     Span::styled(prefix, style),
                     Span::styled(&entry.name, style),
-                    Span::styled(format!("  {}", size), Style::default().fg(Color::DarkGray)),
+                    Span::styled(format!("  {}", size), Style::default().fg(size_color)),
                 ]));
             }
             widgets::push_remaining_indicator(&mut lines, self.cart.len(), cart_offset, max_items);
+            if self.is_large(total_size) {
+                lines.push(Line::from(""));
+                lines.push(Line::from(Span::styled(
+                    format!(
+                        "  \u{26a0} Cart total {} exceeds the {} large-file threshold",
+                        format_size(total_size),
+                        format_size(self.config.large_file_threshold)
+                    ),
+                    Style::default()
+                        .fg(Color::LightRed)
+                        .add_modifier(Modifier::BOLD),
+                )));
+            }
         }
 
         lines.push(Line::from(""));
@@ -2653,9 +4014,15 @@ impl App {
         );
     }
 
-    fn draw_upload_input_overlay(&self, f: &mut Frame, input: &LocalPathInput, cur: &str) {
-        let candidate_lines = input.candidates.len().min(8);
-        let base_height = 7;
+    fn draw_upload_input_overlay(&self, f: &mut Frame, form: &UploadForm, cur: &str) {
+        let input = &form.local;
+        let candidate_lines = if form.on_dest {
+            form.dest.candidates.len()
+        } else {
+            input.candidates.len()
+        }
+        .min(8);
+        let base_height = 8;
         let total_lines = base_height
             + if candidate_lines > 0 {
                 candidate_lines + 1
@@ -2666,71 +4033,169 @@ impl App {
         let area = centered_rect(70, pct, f.area());
         clear_overlay_area(f, area);
 
-        let dest = self.current_path_display();
-        let mut lines = vec![
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("  Upload to: ", Style::default().fg(Color::DarkGray)),
-                Span::styled(dest, Style::default().fg(Color::Reset)),
-            ]),
+        let field = |label: &'static str, value: &str, focused: bool| {
+            let (label_color, value_color, cursor) = if focused {
+                (Color::Cyan, Color::Yellow, cur)
+            } else {
+                (Color::DarkGray, Color::Reset, "")
+            };
             Line::from(vec![
-                Span::styled("  File:      ", Style::default().fg(Color::Cyan)),
+                Span::styled(label, Style::default().fg(label_color)),
                 Span::styled(
-                    format!("{}{}", input.value, cur),
-                    Style::default().fg(Color::Yellow),
+                    format!("{}{}", value, cursor),
+                    Style::default().fg(value_color),
                 ),
-            ]),
+            ])
+        };
+        let mut lines = vec![
+            Line::from(""),
+            field("  File:      ", &input.value, !form.on_dest),
+            field("  Upload to: ", &form.dest.value, form.on_dest),
         ];
 
-        self.draw_candidate_list(&mut lines, &input.candidates, input.candidate_idx);
+        if form.on_dest {
+            lines.extend(Self::completion_lines(&form.dest));
+        } else {
+            self.draw_candidate_list(&mut lines, &input.candidates, input.candidate_idx);
+        }
 
         lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "  Several at once: a pattern (~/incoming/*.mkv) or @list.txt",
+            Style::default().fg(Color::DarkGray),
+        )));
         lines.push(Self::hint_line(&[
             ("Tab", "complete"),
+            ("\u{2191}/\u{2193}", "switch field"),
             ("Enter", "upload"),
             ("Esc", "cancel"),
         ]));
-
-        let (ul_bc, ul_tc) = self.themed_colors(Color::Yellow);
-        f.render_widget(
-            Paragraph::new(Text::from(lines)).block(
-                self.styled_block()
-                    .title(Span::styled(
-                        " Upload File ",
-                        Style::default().fg(ul_tc).add_modifier(Modifier::BOLD),
-                    ))
-                    .border_style(Style::default().fg(ul_bc)),
-            ),
-            area,
-        );
+
+        let (ul_bc, ul_tc) = self.themed_colors(Color::Yellow);
+        f.render_widget(
+            Paragraph::new(Text::from(lines)).block(
+                self.styled_block()
+                    .title(Span::styled(
+                        " Upload File ",
+                        Style::default().fg(ul_tc).add_modifier(Modifier::BOLD),
+                    ))
+                    .border_style(Style::default().fg(ul_bc)),
+            ),
+            area,
+        );
+    }
+
+    fn draw_upload_conflict_overlay(&self, f: &mut Frame, plan: &UploadPlan) {
+        let Some(idx) = plan.pending() else { return };
+        let file = &plan.files[idx];
+        let name = file
+            .local
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned();
+        let mut folder = plan.dest_path.trim_end_matches('/').to_string();
+        for part in &file.dir {
+            folder.push('/');
+            folder.push_str(part);
+        }
+        if folder.is_empty() {
+            folder.push('/');
+        }
+        let left = plan.files[idx..]
+            .iter()
+            .filter(|f| f.resolution.is_none())
+            .count();
+        let mut body = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("  ", Style::default()),
+                Span::styled(
+                    name,
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(" already exists in ", Style::default().fg(Color::Reset)),
+                Span::styled(folder, Style::default().fg(Color::Cyan)),
+            ]),
+        ];
+        if left > 1 {
+            body.push(Line::from(Span::styled(
+                format!("  {} more conflict(s) after this one", left - 1),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        body.push(Line::from(""));
+        body.push(Self::hint_line(&[
+            ("o", "overwrite"),
+            ("k", "keep both"),
+            ("s", "skip"),
+        ]));
+        body.push(Self::hint_line(&[
+            ("O/K/S", "same for all remaining"),
+            ("Esc", "cancel upload"),
+        ]));
+        self.draw_simple_confirm(f, "File Exists", body, Color::Yellow);
     }
 
     fn draw_offline_input_overlay(&self, f: &mut Frame, value: &str, cur: &str) {
-        let area = self.prepare_overlay(f, 70, 25);
+        let links = crate::cmd::offline::parse_links(value);
+        let pasted: Vec<&str> = value.lines().collect();
+        let area = if pasted.len() > 1 {
+            self.prepare_overlay(f, 70, 50)
+        } else {
+            self.prepare_overlay(f, 70, 25)
+        };
         let (bc, tc) = if self.is_vibrant() {
             (Color::LightCyan, Color::LightCyan)
         } else {
             (Color::Cyan, Color::Yellow)
         };
-        f.render_widget(
-            Paragraph::new(vec![
-                Line::from(""),
-                Line::from(Span::styled(
-                    "  Enter URL or magnet link for cloud download:",
-                    Style::default().fg(Color::Reset),
-                )),
-                Line::from(""),
-                Line::from(vec![
-                    Span::styled("  URL: ", Style::default().fg(Color::Cyan)),
-                    Span::styled(
-                        format!("{}{}", value, cur),
-                        Style::default().fg(Color::Yellow),
+        let mut lines = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                "  Enter or paste URLs / magnet links (one per line) for cloud download:",
+                Style::default().fg(Color::Reset),
+            )),
+            Line::from(""),
+        ];
+        if pasted.len() > 1 {
+            // Show the tail of a multi-line paste; the count covers the rest.
+            let visible = area.height.saturating_sub(8).max(1) as usize;
+            let skip = pasted.len().saturating_sub(visible);
+            for line in &pasted[skip..] {
+                lines.push(Line::from(Span::styled(
+                    format!(
+                        "  {}",
+                        truncate_name(line, area.width.saturating_sub(6) as usize)
                     ),
-                ]),
-                Line::from(""),
-                Self::hint_line(&[("Enter", "submit"), ("Esc", "cancel")]),
-            ])
-            .block(self.overlay_block("Offline Download", bc, tc)),
+                    Style::default().fg(Color::Yellow),
+                )));
+            }
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("  {} link(s) ", links.len()),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::styled(cur.to_string(), Style::default().fg(Color::Yellow)),
+            ]));
+        } else {
+            lines.push(Line::from(vec![
+                Span::styled("  URL: ", Style::default().fg(Color::Cyan)),
+                Span::styled(
+                    format!("{}{}", value, cur),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ]));
+        }
+        lines.push(Line::from(""));
+        lines.push(Self::hint_line(&[
+            ("Enter", "choose folder"),
+            ("Esc", "cancel"),
+        ]));
+        f.render_widget(
+            Paragraph::new(lines).block(self.overlay_block("Offline Download", bc, tc)),
             area,
         );
     }
@@ -2830,6 +4295,181 @@ impl App {
             );
         }
     }
+    fn draw_jobs_overlay(&self, f: &mut Frame, selected: usize) {
+        let jobs = self.jobs.jobs();
+        let pct = widgets::dynamic_overlay_height(jobs.len(), 15, f.area().height, 25, 75);
+        let area = centered_rect(70, pct, f.area());
+        clear_overlay_area(f, area);
+
+        let active = self.jobs.active();
+        let title = if active > 0 {
+            format!("Operation Queue ({} running)", active)
+        } else {
+            format!("Operation Queue ({})", jobs.len())
+        };
+        let (bc, tc) = self.themed_colors(Color::Cyan);
+
+        let mut lines = vec![Line::from("")];
+        if jobs.is_empty() {
+            lines.push(widgets::empty_state_line(
+                "No operations yet. Moves, copies, renames and deletes show up here.",
+            ));
+        } else {
+            let max_visible = 15;
+            let offset = widgets::scroll_offset(selected, max_visible);
+            for (i, job) in jobs.iter().enumerate().skip(offset).take(max_visible) {
+                let is_sel = i == selected;
+                let prefix = if is_sel { " \u{203a} " } else { "   " };
+                let (icon, color) = match job.state {
+                    JobState::Pending => ("\u{2026}", Color::DarkGray),
+                    JobState::Running => (SPINNER_FRAMES[self.spinner_idx], Color::Cyan),
+                    JobState::Done if job.undone => ("\u{21b6}", Color::DarkGray),
+                    JobState::Done => ("\u{2713}", Color::Green),
+                    JobState::Failed => ("\u{2717}", Color::Red),
+                };
+                let name_style = if is_sel {
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Reset)
+                };
+                let label = if job.is_undo {
+                    format!("Undo: {}", job.op.summary())
+                } else {
+                    job.op.summary()
+                };
+                let mut spans = vec![
+                    Span::styled(prefix, name_style),
+                    Span::styled(format!("{} ", icon), Style::default().fg(color)),
+                    Span::styled(truncate_name(&label, 50), name_style),
+                ];
+                if job.undone {
+                    spans.push(Span::styled(
+                        "  undone",
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                if let Some(err) = &job.error {
+                    spans.push(Span::styled(
+                        format!("  {}", truncate_name(err, 30)),
+                        Style::default().fg(Color::Red),
+                    ));
+                }
+                lines.push(Line::from(spans));
+            }
+            widgets::push_remaining_indicator(&mut lines, jobs.len(), offset, max_visible);
+        }
+
+        lines.push(Line::from(""));
+        let hints = self.help_pairs();
+        lines.push(Self::hint_line(&hints));
+        f.render_widget(
+            Paragraph::new(Text::from(lines)).block(self.overlay_block(&title, bc, tc)),
+            area,
+        );
+    }
+
+    fn draw_activity_overlay(&self, f: &mut Frame, selected: usize) {
+        let events = &self.activity.events;
+        let pct = widgets::dynamic_overlay_height(events.len(), 15, f.area().height, 25, 75);
+        let area = centered_rect(70, pct, f.area());
+        clear_overlay_area(f, area);
+
+        let title = if self.activity.fresh > 0 {
+            format!("Activity ({} new)", self.activity.fresh)
+        } else {
+            "Activity".to_string()
+        };
+        let (bc, tc) = self.themed_colors(Color::Cyan);
+
+        let mut lines = vec![Line::from("")];
+        if events.is_empty() {
+            lines.push(widgets::empty_state_line(
+                "No events yet. Uploads, moves, renames and deletes show up here.",
+            ));
+        } else {
+            let max_visible = 15;
+            let offset = widgets::scroll_offset(selected, max_visible);
+            for (i, ev) in events.iter().enumerate().skip(offset).take(max_visible) {
+                let is_sel = i == selected;
+                let prefix = if is_sel { " \u{203a} " } else { "   " };
+                let raw = ev.event_type.as_deref().unwrap_or("");
+                let kind = ev
+                    .type_name
+                    .as_deref()
+                    .filter(|n| !n.is_empty())
+                    .unwrap_or(raw);
+                let color = if raw.contains("DELETE") || raw.contains("TRASH") {
+                    Color::Red
+                } else if raw.contains("CREATE")
+                    || raw.contains("UPLOAD")
+                    || raw.contains("RESTORE")
+                {
+                    Color::Green
+                } else {
+                    Color::Yellow
+                };
+                let name_style = if is_sel {
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD)
+                } else if i < self.activity.fresh {
+                    Style::default().add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Reset)
+                };
+                let date = crate::cmd::format_date(ev.created_time.as_deref().unwrap_or(""));
+                lines.push(Line::from(vec![
+                    Span::styled(prefix, name_style),
+                    Span::styled(
+                        format!("{:<12} ", truncate_name(kind, 12)),
+                        Style::default().fg(color),
+                    ),
+                    Span::styled(
+                        format!(
+                            "{:<40}",
+                            truncate_name(ev.file_name.as_deref().unwrap_or("?"), 40)
+                        ),
+                        name_style,
+                    ),
+                    Span::styled(format!("  {date}"), Style::default().fg(Color::DarkGray)),
+                ]));
+            }
+            widgets::push_remaining_indicator(&mut lines, events.len(), offset, max_visible);
+        }
+
+        lines.push(Line::from(""));
+        let hints = self.help_pairs();
+        lines.push(Self::hint_line(&hints));
+        f.render_widget(
+            Paragraph::new(Text::from(lines)).block(self.overlay_block(&title, bc, tc)),
+            area,
+        );
+    }
+
+    /// The countdown before the "when all done" action, over everything.
+    fn draw_when_done_overlay(&self, f: &mut Frame, line: &str) {
+        let area = self.prepare_overlay(f, 45, 20);
+        let (bc, tc) = self.themed_colors(Color::Magenta);
+        let p = Paragraph::new(Text::from(vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("  {line}"),
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "  Press any key to cancel",
+                Style::default().fg(Color::DarkGray),
+            )),
+        ]))
+        .block(self.overlay_block("All transfers done", bc, tc));
+        f.render_widget(p, area);
+    }
+
     fn draw_info_loading_overlay(&self, f: &mut Frame) {
         let area = self.prepare_overlay(f, 45, 20);
 
@@ -2869,6 +4509,7 @@ impl App {
         info: &crate::pikpak::FileInfoResponse,
         image: Option<&image::DynamicImage>,
         has_thumbnail: bool,
+        archive: Option<&Listing>,
     ) {
         let has_thumb = has_thumbnail;
         let area = if has_thumb {
@@ -2958,6 +4599,31 @@ impl App {
             ));
         }
 
+        if let Some(video) = info.video() {
+            meta_lines.extend(video_lines(video));
+        }
+
+        if let Some(listing) = archive {
+            meta_lines.push(Line::from(vec![
+                Span::styled("  Contents:", Style::default().fg(Color::Cyan)),
+                Span::styled(archive_summary(listing), Style::default().fg(Color::Reset)),
+            ]));
+            const SHOWN: usize = 5;
+            for e in listing.entries.iter().take(SHOWN) {
+                meta_lines.push(Line::from(Span::styled(
+                    format!("    {}", truncate_name(&e.name, wrap_w.saturating_sub(4))),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+            let more = (listing.total as usize).saturating_sub(SHOWN);
+            if more > 0 {
+                meta_lines.push(Line::from(Span::styled(
+                    format!("    \u{2026} and {more} more"),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+        }
+
         if let Some(entry) = self.entries.get(self.selected) {
             let mut markers = Vec::new();
             if entry.starred {
@@ -3110,6 +4776,7 @@ impl App {
                                 img,
                                 thumb_col_w as u32,
                                 image_rows as u32,
+                                self.config.color_depth,
                             );
                             f.render_widget(Paragraph::new(Text::from(colored_lines)), img_rect);
                         }
@@ -3119,6 +4786,7 @@ impl App {
                             img,
                             thumb_col_w as u32,
                             image_rows as u32,
+                            self.config.color_depth,
                         );
                         f.render_widget(Paragraph::new(Text::from(colored_lines)), img_rect);
                     }
@@ -3315,7 +4983,7 @@ impl App {
                     (
                         "Preview Max Size".to_string(),
                         "Maximum bytes for text preview".to_string(),
-                        format!("{} KB", draft.preview_max_size / 1024),
+                        format!("{} KiB", draft.preview_max_size / 1024),
                     ),
                     (
                         "Thumbnail Mode".to_string(),
@@ -3367,6 +5035,16 @@ impl App {
                         }
                         .to_string(),
                     ),
+                    (
+                        "Commander Mode".to_string(),
+                        "Two side-by-side panes; F5/F6 copy/move to the other".to_string(),
+                        if draft.commander_mode {
+                            "[\u{2713}]"
+                        } else {
+                            "[ ]"
+                        }
+                        .to_string(),
+                    ),
                 ],
             ),
             (
@@ -3379,11 +5057,23 @@ impl App {
             ),
             (
                 "Download Settings",
-                vec![(
-                    "Concurrent Downloads".to_string(),
-                    "Simultaneous cart downloads (1 = sequential)".to_string(),
-                    draft.download_jobs.to_string(),
-                )],
+                vec![
+                    (
+                        "Concurrent Downloads".to_string(),
+                        "Simultaneous cart downloads (1 = sequential)".to_string(),
+                        draft.download_jobs.to_string(),
+                    ),
+                    (
+                        "Max Download Speed".to_string(),
+                        "Cap on all downloads together".to_string(),
+                        crate::ratelimit::format_rate(draft.max_download_speed),
+                    ),
+                    (
+                        "Max Upload Speed".to_string(),
+                        "Cap on all uploads together".to_string(),
+                        crate::ratelimit::format_rate(draft.max_upload_speed),
+                    ),
+                ],
             ),
             (
                 "Update Settings",
@@ -3393,6 +5083,38 @@ impl App {
                     draft.update_check.as_str().to_string(),
                 )],
             ),
+            (
+                "Display Settings",
+                vec![
+                    (
+                        "Size Units".to_string(),
+                        "Binary (1024) or decimal (1000) sizes".to_string(),
+                        draft.size_units.as_str().to_string(),
+                    ),
+                    (
+                        "Date Format".to_string(),
+                        "Pattern using %Y %m %d %H %M %S".to_string(),
+                        draft.date_format.clone(),
+                    ),
+                    (
+                        "Large File Threshold".to_string(),
+                        "Highlight files and cart totals above this size".to_string(),
+                        if draft.large_file_threshold == 0 {
+                            "Off".to_string()
+                        } else {
+                            format!("{} GiB", draft.large_file_threshold >> 30)
+                        },
+                    ),
+                ],
+            ),
+            (
+                "Keyboard",
+                vec![(
+                    "Key Bindings".to_string(),
+                    "File browser keys; change them under [tui.keys] in config.toml".to_string(),
+                    ">".to_string(),
+                )],
+            ),
         ]
     }
 
@@ -3451,7 +5173,7 @@ impl App {
                     Style::default().fg(Color::Green)
                 };
 
-                let is_text_input_item = name == "Player Command";
+                let is_text_input_item = name == "Player Command" || name == "Date Format";
                 let cur = if self.cursor_visible { "\u{2588}" } else { " " };
 
                 let mut name_value_spans = vec![
@@ -3461,7 +5183,11 @@ impl App {
 
                 if is_text_input_item && is_selected && editing {
                     name_value_spans.push(Span::styled(": ", Style::default().fg(Color::DarkGray)));
-                    let display_val = draft.player.as_deref().unwrap_or("");
+                    let display_val = if name == "Date Format" {
+                        draft.date_format.as_str()
+                    } else {
+                        draft.player.as_deref().unwrap_or("")
+                    };
                     name_value_spans.push(Span::styled(
                         format!("{}{}", display_val, cur),
                         Style::default().fg(Color::Yellow),
@@ -3621,6 +5347,64 @@ impl App {
         );
     }
 
+    /// Every remappable action with its current key and `[tui.keys]` name;
+    /// keys changed in `config.toml` are marked with `*`.
+    fn draw_keymap_overlay(&self, f: &mut Frame, scroll: usize) {
+        let area = centered_rect(70, 65, f.area());
+        clear_overlay_area(f, area);
+
+        let inner_width = area.width.saturating_sub(4) as usize;
+        let rows: Vec<Line> = ACTIONS
+            .iter()
+            .map(|&(action, name, _, desc)| {
+                let key = match self.keymap.label(action) {
+                    "" => "\u{2014}",
+                    key => key,
+                };
+                let custom = self.keymap.is_custom(action);
+                let marker = if custom { "*" } else { " " };
+                let key_style = if custom {
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Green)
+                };
+                let left = format!("  {:<10}{marker} {desc}", key);
+                let padding = inner_width.saturating_sub(left.chars().count() + name.len());
+                Line::from(vec![
+                    Span::styled(format!("  {:<10}", key), key_style),
+                    Span::styled(marker, Style::default().fg(Color::Yellow)),
+                    Span::styled(format!(" {desc}"), Style::default().fg(Color::Reset)),
+                    Span::raw(" ".repeat(padding)),
+                    Span::styled(name, Style::default().fg(Color::DarkGray)),
+                ])
+            })
+            .collect();
+
+        // Blank line, list, blank line, hint line inside the border.
+        let visible = area.height.saturating_sub(5) as usize;
+        let scroll = scroll.min(rows.len().saturating_sub(visible));
+        let mut lines = vec![Line::from("")];
+        lines.extend(rows.into_iter().skip(scroll).take(visible));
+        lines.push(Line::from(""));
+        lines.push(Self::hint_line(&[("j/k", "scroll"), ("Esc", "back")]));
+
+        let (st_bc, st_tc) = if self.is_vibrant() {
+            (Color::LightMagenta, Color::LightMagenta)
+        } else {
+            (Color::Cyan, Color::Yellow)
+        };
+        f.render_widget(
+            Paragraph::new(Text::from(lines)).block(self.overlay_block(
+                "Key Bindings",
+                st_bc,
+                st_tc,
+            )),
+            area,
+        );
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn draw_custom_color_overlay(
         &self,
@@ -3667,7 +5451,10 @@ impl App {
             let mut spans = vec![
                 Span::styled(prefix, name_style),
                 Span::styled(format!("{:<12}", name), name_style),
-                Span::styled(color_preview, Style::default().fg(Color::Rgb(*r, *g, *b))),
+                Span::styled(
+                    color_preview,
+                    Style::default().fg(self.config.rgb(*r, *g, *b)),
+                ),
                 Span::raw("  "),
                 Span::styled(rgb_text, Style::default().fg(Color::DarkGray)),
             ];
@@ -4074,6 +5861,49 @@ fn share_detail_lines(share: &crate::pikpak::MyShare, width: u16) -> Vec<Line<'s
     lines
 }
 
+/// Duration, resolution, codecs and bitrate of a video, one per line.
+fn video_lines(video: &crate::pikpak::MediaVideo) -> Vec<Line<'static>> {
+    video
+        .details()
+        .into_iter()
+        .map(|(label, value)| {
+            Line::from(vec![
+                Span::styled(format!("  {label}: "), Style::default().fg(Color::Cyan)),
+                Span::styled(value, Style::default().fg(Color::Reset)),
+            ])
+        })
+        .collect()
+}
+
+/// `12 files, 45.2 MB unpacked`, noting entries the listing couldn't reach.
+/// ` ✓` for a watched video or folder, ` ◐` and how far in for one
+/// started.
+fn watched_badge(playback: &crate::pikpak::Playback) -> Span<'static> {
+    if playback.watched {
+        return Span::styled(" \u{2713}", Style::default().fg(Color::Green));
+    }
+    let label = match playback.progress() {
+        Some(p) => format!(" \u{25d0} {:.0}%", p * 100.0),
+        None => " \u{25d0}".to_string(),
+    };
+    Span::styled(
+        label,
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::DIM),
+    )
+}
+
+fn archive_summary(listing: &Listing) -> String {
+    let (files, size) = listing.files();
+    let mut summary = format!("{files} file(s), {} unpacked", format_size(size));
+    let unread = listing.total.saturating_sub(listing.entries.len() as u64);
+    if unread > 0 {
+        summary.push_str(&format!(" ({unread} more not listed)"));
+    }
+    summary
+}
+
 pub(super) fn clear_overlay_area(f: &mut Frame, area: ratatui::layout::Rect) {
     let full = f.area();
     let extended = ratatui::layout::Rect {
@@ -0,0 +1,44 @@
+/// Case-insensitive subsequence match of `query` against `name`.
+///
+/// Returns the char indices of `name` that matched (for highlighting), or
+/// `None` when not every query character could be found in order. An empty
+/// query matches everything with no highlights.
+pub(super) fn fuzzy_match(name: &str, query: &str) -> Option<Vec<usize>> {
+    let mut positions = Vec::new();
+    let mut wanted = query.chars().flat_map(char::to_lowercase).peekable();
+    for (idx, c) in name.chars().enumerate() {
+        let Some(&next) = wanted.peek() else { break };
+        if c.to_lowercase().eq(std::iter::once(next)) {
+            positions.push(idx);
+            wanted.next();
+        }
+    }
+    wanted.peek().is_none().then_some(positions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(fuzzy_match("anything", ""), Some(vec![]));
+    }
+
+    #[test]
+    fn subsequence_is_case_insensitive() {
+        assert_eq!(fuzzy_match("My.Movie.mkv", "mmk"), Some(vec![0, 3, 10]));
+        assert_eq!(fuzzy_match("README.md", "rdm"), Some(vec![0, 3, 4]));
+    }
+
+    #[test]
+    fn out_of_order_does_not_match() {
+        assert_eq!(fuzzy_match("abc", "cb"), None);
+        assert_eq!(fuzzy_match("abc", "abcd"), None);
+    }
+
+    #[test]
+    fn positions_are_char_indices_not_bytes() {
+        assert_eq!(fuzzy_match("日本語.txt", "語t"), Some(vec![2, 4]));
+    }
+}
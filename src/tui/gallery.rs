@@ -0,0 +1,351 @@
+//! Gallery mode (`i`): the images of the current folder one at a time in
+//! the preview pane, at full resolution rather than the API's thumbnail.
+//! The neighbours on either side are fetched ahead so stepping through a
+//! folder of photos doesn't wait on the network each time.
+
+use anyhow::{Context, Result, anyhow};
+use image::{DynamicImage, ImageDecoder, ImageReader, metadata::Orientation};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use super::{App, InputMode, OpResult};
+use crate::pikpak::{Entry, EntryKind};
+use crate::theme;
+
+/// Larger files are left out rather than downloaded for a preview.
+const MAX_BYTES: u64 = 64 << 20;
+
+/// Decoded images are scaled down to fit this box; no terminal shows more.
+const MAX_SIDE: u32 = 2048;
+
+/// A decoded image and what's known about the original.
+pub(super) struct Picture {
+    pub(super) image: DynamicImage,
+    pub(super) width: u32,
+    pub(super) height: u32,
+    /// When the photo was taken, from its EXIF data.
+    pub(super) taken: Option<String>,
+}
+
+pub(super) enum Slot {
+    Loading,
+    Ready(Box<Picture>),
+    Failed(String),
+}
+
+pub(super) struct Gallery {
+    pub(super) images: Vec<Entry>,
+    pub(super) index: usize,
+    /// Only the current image and its neighbours are kept.
+    slots: HashMap<String, Slot>,
+}
+
+impl Gallery {
+    pub(super) fn current(&self) -> &Entry {
+        &self.images[self.index]
+    }
+
+    pub(super) fn slot(&self) -> Option<&Slot> {
+        self.slots.get(&self.current().id)
+    }
+
+    /// Ids of the current image and the ones either side of it.
+    fn window(&self) -> Vec<String> {
+        let n = self.images.len();
+        let mut ids = vec![self.images[self.index].id.clone()];
+        for i in [(self.index + 1) % n, (self.index + n - 1) % n] {
+            if !ids.contains(&self.images[i].id) {
+                ids.push(self.images[i].id.clone());
+            }
+        }
+        ids
+    }
+
+    /// Move by `step`, wrapping round; drops what's out of reach and returns
+    /// the entries that still need fetching.
+    fn step(&mut self, step: isize) -> Vec<Entry> {
+        let n = self.images.len() as isize;
+        self.index = (self.index as isize + step).rem_euclid(n) as usize;
+        self.missing()
+    }
+
+    fn missing(&mut self) -> Vec<Entry> {
+        let window = self.window();
+        self.slots.retain(|id, _| window.contains(id));
+        let mut wanted = Vec::new();
+        for id in window {
+            if !self.slots.contains_key(&id)
+                && let Some(entry) = self.images.iter().find(|e| e.id == id)
+            {
+                self.slots.insert(id, Slot::Loading);
+                wanted.push(entry.clone());
+            }
+        }
+        wanted
+    }
+
+    fn store(&mut self, id: &str, result: Result<Picture>) {
+        // An image scrolled past while it was loading isn't wanted any more.
+        if let Some(slot) = self.slots.get_mut(id) {
+            *slot = match result {
+                Ok(picture) => Slot::Ready(Box::new(picture)),
+                Err(e) => Slot::Failed(format!("{e:#}")),
+            };
+        }
+    }
+}
+
+/// The images in `entries`, in listing order.
+fn images(entries: &[Entry]) -> Vec<Entry> {
+    entries
+        .iter()
+        .filter(|e| e.kind == EntryKind::File && theme::categorize(e) == theme::FileCategory::Image)
+        .cloned()
+        .collect()
+}
+
+fn load(client: &crate::pikpak::PikPak, entry: &Entry) -> Result<Picture> {
+    if entry.size > MAX_BYTES {
+        return Err(anyhow!(
+            "too large to preview ({})",
+            super::format_size(entry.size)
+        ));
+    }
+    let bytes = client.fetch_head(&entry.id, MAX_BYTES)?;
+    decode(&bytes)
+}
+
+/// Decode an image, turned the way its EXIF orientation says, and scaled
+/// down to `MAX_SIDE`.
+fn decode(bytes: &[u8]) -> Result<Picture> {
+    let mut decoder = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .context("failed to guess image format")?
+        .into_decoder()
+        .context("unsupported image format")?;
+    let exif = decoder.exif_metadata().ok().flatten();
+    let orientation = exif
+        .as_deref()
+        .and_then(Orientation::from_exif_chunk)
+        .unwrap_or(Orientation::NoTransforms);
+    let mut image = DynamicImage::from_decoder(decoder).context("failed to decode image")?;
+    image.apply_orientation(orientation);
+    let (width, height) = (image.width(), image.height());
+    if width > MAX_SIDE || height > MAX_SIDE {
+        image = image.thumbnail(MAX_SIDE, MAX_SIDE);
+    }
+    Ok(Picture {
+        image,
+        width,
+        height,
+        taken: exif.as_deref().and_then(exif_date),
+    })
+}
+
+/// `DateTimeOriginal` (or failing that `DateTime`) from a raw EXIF block,
+/// as `YYYY-MM-DD HH:MM:SS`.
+fn exif_date(tiff: &[u8]) -> Option<String> {
+    let little = match tiff.get(..4)? {
+        [b'I', b'I', 42, 0] => true,
+        [b'M', b'M', 0, 42] => false,
+        _ => return None,
+    };
+    let u16_at = |at: usize| {
+        let b: [u8; 2] = tiff.get(at..at + 2)?.try_into().ok()?;
+        Some(if little {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        })
+    };
+    let u32_at = |at: usize| {
+        let b: [u8; 4] = tiff.get(at..at + 4)?.try_into().ok()?;
+        Some(if little {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        } as usize)
+    };
+    // The directory entry for `tag` in the IFD at `ifd`: (type, count,
+    // offset of the value field).
+    let find = |ifd: usize, tag: u16| {
+        let count = u16_at(ifd)? as usize;
+        (0..count).find_map(|i| {
+            let at = ifd + 2 + i * 12;
+            (u16_at(at)? == tag).then_some((u16_at(at + 2)?, u32_at(at + 4)?, at + 8))
+        })
+    };
+    let ascii = |(kind, count, field): (u16, usize, usize)| {
+        if kind != 2 {
+            return None;
+        }
+        let at = if count > 4 { u32_at(field)? } else { field };
+        let raw = tiff.get(at..at + count)?;
+        let text = std::str::from_utf8(raw).ok()?.trim_end_matches('\0').trim();
+        // `2024:05:01 18:30:00`; cameras without a clock write blanks or zeros.
+        let (date, time) = text.split_once(' ')?;
+        if date.len() != 10 || date.starts_with("0000") {
+            return None;
+        }
+        Some(format!("{} {time}", date.replace(':', "-")))
+    };
+
+    let ifd0 = u32_at(4)?;
+    let original = find(ifd0, 0x8769)
+        .and_then(|(_, _, field)| u32_at(field))
+        .and_then(|exif_ifd| find(exif_ifd, 0x9003))
+        .and_then(ascii);
+    original.or_else(|| find(ifd0, 0x0132).and_then(ascii))
+}
+
+impl App {
+    pub(super) fn open_gallery(&mut self) {
+        if !self.preview_pane_shown() {
+            self.push_log("Gallery needs the preview pane (turn it on in settings)".into());
+            return;
+        }
+        let images = images(&self.entries);
+        if images.is_empty() {
+            self.push_log("No images in this folder".into());
+            return;
+        }
+        let index = self
+            .current_entry()
+            .and_then(|cur| images.iter().position(|e| e.id == cur.id))
+            .unwrap_or(0);
+        self.preview_cancel.cancel();
+        let mut gallery = Gallery {
+            images,
+            index,
+            slots: HashMap::new(),
+        };
+        let wanted = gallery.missing();
+        self.input = InputMode::Gallery(Box::new(gallery));
+        self.follow_gallery();
+        self.fetch_gallery(wanted);
+    }
+
+    pub(super) fn handle_gallery_key(
+        &mut self,
+        code: crossterm::event::KeyCode,
+        mut gallery: Box<Gallery>,
+    ) {
+        use crossterm::event::KeyCode;
+        let wanted = match code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('i') => {
+                self.input = InputMode::Normal;
+                self.on_cursor_move();
+                return;
+            }
+            KeyCode::Right
+            | KeyCode::Down
+            | KeyCode::Char('l')
+            | KeyCode::Char('j')
+            | KeyCode::Char('n')
+            | KeyCode::Char(' ') => gallery.step(1),
+            KeyCode::Left
+            | KeyCode::Up
+            | KeyCode::Char('h')
+            | KeyCode::Char('k')
+            | KeyCode::Char('p')
+            | KeyCode::Backspace => gallery.step(-1),
+            KeyCode::Home | KeyCode::Char('g') => gallery.step(-(gallery.index as isize)),
+            KeyCode::End | KeyCode::Char('G') => {
+                let last = gallery.images.len() - 1;
+                gallery.step((last - gallery.index) as isize)
+            }
+            _ => Vec::new(),
+        };
+        self.input = InputMode::Gallery(gallery);
+        self.follow_gallery();
+        self.fetch_gallery(wanted);
+    }
+
+    pub(super) fn store_gallery_image(&mut self, id: String, result: Result<Picture>) {
+        if let InputMode::Gallery(gallery) = &mut self.input {
+            gallery.store(&id, result);
+        }
+    }
+
+    /// Keep the list cursor on the image being shown, so leaving the
+    /// gallery lands on it.
+    fn follow_gallery(&mut self) {
+        if let InputMode::Gallery(gallery) = &self.input
+            && let Some(i) = self
+                .entries
+                .iter()
+                .position(|e| e.id == gallery.current().id)
+        {
+            self.selected = i;
+        }
+    }
+
+    fn fetch_gallery(&mut self, wanted: Vec<Entry>) {
+        for entry in wanted {
+            let client = Arc::clone(&self.client);
+            let tx = self.result_tx.clone();
+            self.workers.spawn(move || {
+                let result = load(&client, &entry);
+                let _ = tx.send(OpResult::GalleryImage(entry.id, result));
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A little-endian EXIF block: IFD0 with `DateTime` and a pointer to an
+    /// Exif IFD holding `DateTimeOriginal`.
+    fn exif_block(original: &str) -> Vec<u8> {
+        let mut t = b"II\x2a\x00\x08\x00\x00\x00".to_vec();
+        let ifd0 = 8;
+        let exif_ifd = ifd0 + 2 + 2 * 12 + 4;
+        let dates = exif_ifd + 2 + 12 + 4;
+        let entry = |t: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: u32| {
+            t.extend(tag.to_le_bytes());
+            t.extend(kind.to_le_bytes());
+            t.extend(count.to_le_bytes());
+            t.extend(value.to_le_bytes());
+        };
+        t.extend(2u16.to_le_bytes());
+        entry(&mut t, 0x0132, 2, 20, dates as u32);
+        entry(&mut t, 0x8769, 4, 1, exif_ifd as u32);
+        t.extend(0u32.to_le_bytes());
+        t.extend(1u16.to_le_bytes());
+        entry(&mut t, 0x9003, 2, 20, dates as u32 + 20);
+        t.extend(0u32.to_le_bytes());
+        t.extend(b"2024:06:02 09:00:00\0");
+        t.extend(original.as_bytes());
+        t.push(0);
+        t
+    }
+
+    #[test]
+    fn exif_date_prefers_the_original() {
+        assert_eq!(
+            exif_date(&exif_block("2024:05:01 18:30:00")).as_deref(),
+            Some("2024-05-01 18:30:00")
+        );
+        // An unset camera clock falls back to the file's own date.
+        assert_eq!(
+            exif_date(&exif_block("0000:00:00 00:00:00")).as_deref(),
+            Some("2024-06-02 09:00:00")
+        );
+        assert_eq!(exif_date(b"not exif"), None);
+    }
+
+    #[test]
+    fn decodes_and_scales_large_images() {
+        let big = DynamicImage::new_rgb8(MAX_SIDE * 2, 10);
+        let mut png = Vec::new();
+        big.write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let picture = decode(&png).unwrap();
+        assert_eq!((picture.width, picture.height), (MAX_SIDE * 2, 10));
+        assert_eq!(picture.image.width(), MAX_SIDE);
+        assert!(picture.taken.is_none());
+    }
+}
@@ -1,16 +1,23 @@
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
 
-use crate::pikpak::{Entry, EntryKind};
+use crate::pikpak::{Entry, EntryKind, OfflineTask, PikPak, StreamProxy};
 use crate::theme;
 
-use super::completion::PathInput;
-use super::download::{DownloadTask, TaskStatus};
-use super::local_completion::LocalPathInput;
+use super::batch_confirm::BatchAction;
+use super::completion::{self, PathInput};
+use super::download::{DownloadTask, OnComplete, TaskStatus};
+use super::instance::QueuedDownload;
+use super::keymap::{self, Action};
+use super::local_completion::{LocalPathInput, expand_home};
+use super::ops::{self, FileOp};
+use super::playback;
+use super::subtitles;
 use super::{
     App, InputMode, LoginField, OpResult, PickerState, PlayOption, PreviewState, handle_text_input,
     widgets,
@@ -19,7 +26,10 @@ use super::{
 /// Index of the last selectable Settings row. MUST match the item layout in
 /// `draw::draw_settings_overlay`, the index match in `handle_settings_key`, and
 /// the click map / `bool_items` in `handle_mouse_click` — keep all four in sync.
-const SETTINGS_LAST_INDEX: usize = 16;
+const SETTINGS_LAST_INDEX: usize = KEYMAP_SETTINGS_INDEX;
+
+/// The "Key Bindings" row, which opens the read-only keymap viewer.
+const KEYMAP_SETTINGS_INDEX: usize = 23;
 
 enum PickerKeyResult {
     Navigated,
@@ -36,7 +46,7 @@ enum PathInputKeyResult {
     Cancelled,
 }
 
-enum LocalPathInputResult {
+pub(super) enum LocalPathInputResult {
     Updated,
     Confirmed(String), // final path value
     Cancelled,
@@ -48,6 +58,22 @@ enum PathInputContext {
 }
 
 impl App {
+    /// Bracketed paste. The offline prompt takes the text verbatim so several
+    /// links (one per line) can be pasted at once; anywhere else it's typed in
+    /// as before, minus line breaks that would otherwise act as Enter.
+    pub(super) fn handle_paste(&mut self, text: &str) -> Result<bool> {
+        if let InputMode::OfflineInput { value } = &mut self.input {
+            value.push_str(&text.replace("\r\n", "\n").replace('\r', "\n"));
+            return Ok(false);
+        }
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if self.handle_key(KeyCode::Char(c), KeyModifiers::NONE)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     pub(super) fn handle_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Result<bool> {
         if self.show_help_sheet {
             self.show_help_sheet = false;
@@ -73,12 +99,18 @@ impl App {
                 ..
             } => {
                 if logging_in {
+                    // A browser login can be given up on; a password one
+                    // finishes on its own.
+                    let cancel = code == KeyCode::Esc && self.browser_login.is_some();
+                    if cancel {
+                        self.cancel_browser_login();
+                    }
                     self.input = InputMode::Login {
                         field,
                         email,
                         password,
-                        error: None,
-                        logging_in: true,
+                        error: cancel.then(|| "Browser login cancelled".to_string()),
+                        logging_in: !cancel,
                     };
                     return Ok(false);
                 }
@@ -118,6 +150,21 @@ impl App {
                             self.attempt_login(&e, &p);
                         }
                     }
+                    KeyCode::Char('b') if modifiers.contains(KeyModifiers::CONTROL) => {
+                        let (e, p) = (email.clone(), password.clone());
+                        let ready = !e.trim().is_empty() && !p.is_empty();
+                        self.input = InputMode::Login {
+                            field,
+                            email,
+                            password,
+                            error: (!ready)
+                                .then(|| "Enter your email and password, then press Ctrl+B".into()),
+                            logging_in: ready,
+                        };
+                        if ready {
+                            self.start_browser_login(e, p);
+                        }
+                    }
                     KeyCode::Backspace => {
                         match field {
                             LoginField::Email => {
@@ -160,7 +207,73 @@ impl App {
                 }
                 Ok(false)
             }
+            InputMode::LoginCode {
+                pending,
+                code: mut entered,
+                error,
+            } => {
+                match code {
+                    KeyCode::Esc => {
+                        // Back to the form, to fix the email or password.
+                        self.input = InputMode::Login {
+                            field: LoginField::Email,
+                            email: pending.email().to_string(),
+                            password: pending.password().to_string(),
+                            error: None,
+                            logging_in: false,
+                        };
+                    }
+                    KeyCode::Enter if entered.trim().is_empty() => {
+                        self.input = InputMode::LoginCode {
+                            pending,
+                            code: entered,
+                            error: Some("Enter the code from the email".into()),
+                        };
+                    }
+                    KeyCode::Enter => self.submit_login_code(pending, entered),
+                    KeyCode::Backspace => {
+                        entered.pop();
+                        self.input = InputMode::LoginCode {
+                            pending,
+                            code: entered,
+                            error: None,
+                        };
+                    }
+                    KeyCode::Char(c) => {
+                        entered.push(c);
+                        self.input = InputMode::LoginCode {
+                            pending,
+                            code: entered,
+                            error: None,
+                        };
+                    }
+                    _ => {
+                        self.input = InputMode::LoginCode {
+                            pending,
+                            code: entered,
+                            error,
+                        };
+                    }
+                }
+                Ok(false)
+            }
             InputMode::Normal => self.handle_normal_key(code, modifiers),
+            InputMode::Filter => {
+                self.handle_filter_key(code, modifiers);
+                Ok(false)
+            }
+            InputMode::BatchRename(state) => {
+                self.handle_batch_rename_key(code, modifiers, state);
+                Ok(false)
+            }
+            InputMode::NewFile(form) => {
+                self.handle_new_file_key(code, modifiers, form);
+                Ok(false)
+            }
+            InputMode::Note(form) => {
+                self.handle_note_key(code, form);
+                Ok(false)
+            }
             InputMode::Rename { mut value } => {
                 if let Some(done) = handle_text_input(&mut value, code) {
                     if done && let Some(entry) = self.current_entry().cloned() {
@@ -187,6 +300,17 @@ impl App {
                 }
                 Ok(false)
             }
+            InputMode::CartGlob { mut pattern } => {
+                if let Some(done) = handle_text_input(&mut pattern, code) {
+                    let pattern = pattern.trim();
+                    if done && !pattern.is_empty() {
+                        self.cart_select_glob(pattern);
+                    }
+                } else {
+                    self.input = InputMode::CartGlob { pattern };
+                }
+                Ok(false)
+            }
             InputMode::ConfirmQuit => {
                 match code {
                     KeyCode::Char('y') => {
@@ -199,22 +323,31 @@ impl App {
                 }
                 Ok(false)
             }
-            InputMode::GotoPath { mut query } => {
-                match handle_text_input(&mut query, code) {
-                    Some(true) => {
-                        let q = query.trim().to_string();
-                        if !q.is_empty() {
-                            self.loading = true;
-                            let client = Arc::clone(&self.client);
-                            let tx = self.result_tx.clone();
-                            std::thread::spawn(move || {
-                                let _ = tx.send(OpResult::GotoPath(client.resolve_path_nav(&q)));
-                            });
-                        }
+            InputMode::GotoPath { mut input } => {
+                // Paths are taken from the root, as everywhere else, so
+                // that's also where a bare name is completed.
+                let result = if code == KeyCode::Tab && input.value.starts_with(':') {
+                    completion::complete_goto_view(&mut input);
+                    PathInputKeyResult::Updated
+                } else if code == KeyCode::Tab {
+                    self.tab_complete_from(&mut input, "");
+                    PathInputKeyResult::Updated
+                } else {
+                    self.apply_path_input_key(code, modifiers, &mut input)
+                };
+                match result {
+                    PathInputKeyResult::Confirmed(target) if self.open_goto_view(&target) => {}
+                    PathInputKeyResult::Confirmed(target) => {
+                        self.loading = true;
+                        let client = Arc::clone(&self.client);
+                        let tx = self.result_tx.clone();
+                        self.workers.spawn(move || {
+                            let _ = tx.send(OpResult::GotoPath(client.resolve_nav(&target)));
+                        });
                     }
-                    Some(false) => { /* ESC — Normal already set by mem::replace */ }
-                    None => {
-                        self.input = InputMode::GotoPath { query };
+                    PathInputKeyResult::Cancelled => {}
+                    PathInputKeyResult::Updated | PathInputKeyResult::SwitchToPicker => {
+                        self.input = InputMode::GotoPath { input };
                     }
                 }
                 Ok(false)
@@ -270,6 +403,82 @@ impl App {
                 }
                 Ok(false)
             }
+            InputMode::ConfirmPurge { targets, mut value } => {
+                match code {
+                    KeyCode::Enter if value == "yes" => self.run_trash_op(targets, false),
+                    KeyCode::Esc | KeyCode::Enter => {
+                        self.push_log(if code == KeyCode::Esc {
+                            "Permanent delete cancelled".into()
+                        } else {
+                            "Permanent delete cancelled (type 'yes' to confirm)".into()
+                        });
+                        self.reopen_trash_view();
+                    }
+                    KeyCode::Backspace => {
+                        value.pop();
+                        self.input = InputMode::ConfirmPurge { targets, value };
+                    }
+                    KeyCode::Char(c) => {
+                        value.push(c);
+                        self.input = InputMode::ConfirmPurge { targets, value };
+                    }
+                    _ => self.input = InputMode::ConfirmPurge { targets, value },
+                }
+                Ok(false)
+            }
+            InputMode::ConfirmEmptyTrash { mut value } => {
+                match code {
+                    KeyCode::Esc => {
+                        self.push_log("Empty trash cancelled".into());
+                        self.input = InputMode::TrashView {
+                            entries: self.trash_entries.clone(),
+                            selected: self.trash_selected,
+                            expanded: self.trash_expanded,
+                        };
+                    }
+                    KeyCode::Enter if value == "yes" => {
+                        self.input = InputMode::TrashView {
+                            entries: self.trash_entries.clone(),
+                            selected: self.trash_selected,
+                            expanded: self.trash_expanded,
+                        };
+                        self.loading = true;
+                        self.loading_label = Some("Emptying trash...".into());
+                        let client = Arc::clone(&self.client);
+                        let tx = self.result_tx.clone();
+                        self.workers.spawn(move || {
+                            let _ = tx.send(OpResult::TrashOp(match client.empty_trash() {
+                                Ok(e) if e.stalled => format!(
+                                    "Warning: trash did not shrink after delete; stopped at {} item(s)",
+                                    e.deleted
+                                ),
+                                Ok(e) => format!("Emptied trash ({} item(s))", e.deleted),
+                                Err(e) => format!("Empty trash failed: {e:#}"),
+                            }));
+                        });
+                    }
+                    KeyCode::Enter => {
+                        self.push_log("Empty trash cancelled (type 'yes' to confirm)".into());
+                        self.input = InputMode::TrashView {
+                            entries: self.trash_entries.clone(),
+                            selected: self.trash_selected,
+                            expanded: self.trash_expanded,
+                        };
+                    }
+                    KeyCode::Backspace => {
+                        value.pop();
+                        self.input = InputMode::ConfirmEmptyTrash { value };
+                    }
+                    KeyCode::Char(c) => {
+                        value.push(c);
+                        self.input = InputMode::ConfirmEmptyTrash { value };
+                    }
+                    _ => {
+                        self.input = InputMode::ConfirmEmptyTrash { value };
+                    }
+                }
+                Ok(false)
+            }
             InputMode::MoveInput { source, mut input } => {
                 self.handle_path_input_key(code, modifiers, source, &mut input, true);
                 Ok(false)
@@ -302,20 +511,43 @@ impl App {
                 self.handle_cart_picker_key(code, &mut picker, true);
                 Ok(false)
             }
+            InputMode::OfflinePicker {
+                urls,
+                mut picker,
+                show_tasks,
+            } => {
+                self.handle_offline_picker_key(code, urls, &mut picker, show_tasks);
+                Ok(false)
+            }
             InputMode::CartCopyPicker { mut picker } => {
                 self.handle_cart_picker_key(code, &mut picker, false);
                 Ok(false)
             }
-            InputMode::ConfirmCartDelete => {
-                self.handle_confirm_cart_delete_key(code);
+            InputMode::RestorePicker {
+                targets,
+                mut picker,
+            } => {
+                self.handle_restore_picker_key(code, targets, &mut picker);
+                Ok(false)
+            }
+            InputMode::ConfirmBatch(summary) => {
+                self.handle_batch_summary_key(code, summary);
                 Ok(false)
             }
             InputMode::DownloadInput { mut input } => {
                 self.handle_download_input_key(code, &mut input);
                 Ok(false)
             }
-            InputMode::UploadInput { mut input } => {
-                self.handle_upload_input_key(code, &mut input);
+            InputMode::UploadInput(form) => {
+                self.handle_upload_input_key(code, form);
+                Ok(false)
+            }
+            InputMode::UploadConflict(plan) => {
+                self.handle_upload_conflict_key(code, plan);
+                Ok(false)
+            }
+            InputMode::Gallery(gallery) => {
+                self.handle_gallery_key(code, gallery);
                 Ok(false)
             }
             InputMode::DownloadView => {
@@ -333,6 +565,14 @@ impl App {
                 self.handle_offline_tasks_key(code, &mut tasks, &mut selected);
                 Ok(false)
             }
+            InputMode::JobsView { selected } => {
+                self.handle_jobs_view_key(code, selected);
+                Ok(false)
+            }
+            InputMode::ActivityView { selected } => {
+                self.handle_activity_key(code, selected);
+                Ok(false)
+            }
             InputMode::TrashView {
                 mut entries,
                 mut selected,
@@ -357,29 +597,43 @@ impl App {
                 self.handle_my_shares_key(code, &mut shares, &mut selected, &mut confirm_delete);
                 Ok(false)
             }
-            InputMode::ConfirmPlay { name, url } => {
+            InputMode::ConfirmPlay {
+                file_id,
+                name,
+                url,
+                mut subs,
+            } => {
                 match code {
                     KeyCode::Enter | KeyCode::Char('y') => {
-                        if let Some(player) = self.config.player.clone() {
-                            self.spawn_player(&player, &url);
-                        } else {
-                            self.input = InputMode::PlayerInput {
-                                value: String::new(),
-                                pending_url: url,
-                            };
-                        }
+                        self.play_url(&file_id, &name, (url, None), &subs)
+                    }
+                    KeyCode::Char('s') => {
+                        subs.toggle();
+                        self.input = InputMode::ConfirmPlay {
+                            file_id,
+                            name,
+                            url,
+                            subs,
+                        };
                     }
                     KeyCode::Esc | KeyCode::Char('n') => {}
                     _ => {
-                        self.input = InputMode::ConfirmPlay { name, url };
+                        self.input = InputMode::ConfirmPlay {
+                            file_id,
+                            name,
+                            url,
+                            subs,
+                        };
                     }
                 }
                 Ok(false)
             }
             InputMode::PlayPicker {
+                file_id,
                 name,
                 medias,
                 mut selected,
+                mut subs,
             } => {
                 match code {
                     KeyCode::Down | KeyCode::Char('j') => {
@@ -391,9 +645,11 @@ impl App {
                             selected = next;
                         }
                         self.input = InputMode::PlayPicker {
+                            file_id,
                             name,
                             medias,
                             selected,
+                            subs,
                         };
                     }
                     KeyCode::Up | KeyCode::Char('k') => {
@@ -407,29 +663,36 @@ impl App {
                             }
                         }
                         self.input = InputMode::PlayPicker {
+                            file_id,
+                            name,
+                            medias,
+                            selected,
+                            subs,
+                        };
+                    }
+                    KeyCode::Char('s') => {
+                        subs.toggle();
+                        self.input = InputMode::PlayPicker {
+                            file_id,
                             name,
                             medias,
                             selected,
+                            subs,
                         };
                     }
                     KeyCode::Enter => {
                         if let Some(opt) = medias.get(selected) {
                             if opt.available {
-                                let url = opt.url.clone();
-                                if let Some(player) = self.config.player.clone() {
-                                    self.spawn_player(&player, &url);
-                                } else {
-                                    self.input = InputMode::PlayerInput {
-                                        value: String::new(),
-                                        pending_url: url,
-                                    };
-                                }
+                                let (url, proxy) = self.playback_url(&file_id, opt, &name);
+                                self.play_url(&file_id, &name, (url, proxy), &subs);
                             } else {
                                 self.push_log("Stream not available (cold storage)".into());
                                 self.input = InputMode::PlayPicker {
+                                    file_id,
                                     name,
                                     medias,
                                     selected,
+                                    subs,
                                 };
                             }
                         }
@@ -437,17 +700,48 @@ impl App {
                     KeyCode::Esc => {}
                     _ => {
                         self.input = InputMode::PlayPicker {
+                            file_id,
                             name,
                             medias,
                             selected,
+                            subs,
                         };
                     }
                 }
                 Ok(false)
             }
+            InputMode::ProfilePicker {
+                profiles,
+                mut selected,
+            } => {
+                match code {
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        selected = (selected + 1).min(profiles.len().saturating_sub(1));
+                        self.input = InputMode::ProfilePicker { profiles, selected };
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        selected = selected.saturating_sub(1);
+                        self.input = InputMode::ProfilePicker { profiles, selected };
+                    }
+                    KeyCode::Enter => {
+                        if let Some(name) = profiles.get(selected) {
+                            self.switch_profile(name);
+                        }
+                    }
+                    KeyCode::Esc => {}
+                    _ => {
+                        self.input = InputMode::ProfilePicker { profiles, selected };
+                    }
+                }
+                Ok(false)
+            }
             InputMode::PlayerInput {
                 mut value,
+                file_id,
+                name,
                 pending_url,
+                proxy,
+                subs,
             } => {
                 match code {
                     KeyCode::Esc => {}
@@ -455,29 +749,63 @@ impl App {
                         let cmd = value.trim().to_string();
                         if !cmd.is_empty() {
                             self.push_log(format!("Player set to: {}", cmd));
-                            self.spawn_player(&cmd, &pending_url);
+                            self.spawn_player(
+                                &cmd,
+                                (&pending_url, proxy),
+                                &subs,
+                                (&file_id, &name),
+                            );
                             self.config.player = Some(cmd);
-                            let _ = self.config.save();
+                            self.save_config();
                         } else {
-                            self.input = InputMode::PlayerInput { value, pending_url };
+                            self.input = InputMode::PlayerInput {
+                                value,
+                                file_id,
+                                name,
+                                pending_url,
+                                proxy,
+                                subs,
+                            };
                         }
                     }
                     KeyCode::Backspace => {
                         value.pop();
-                        self.input = InputMode::PlayerInput { value, pending_url };
+                        self.input = InputMode::PlayerInput {
+                            value,
+                            file_id,
+                            name,
+                            pending_url,
+                            proxy,
+                            subs,
+                        };
                     }
                     KeyCode::Char(c) => {
                         value.push(c);
-                        self.input = InputMode::PlayerInput { value, pending_url };
+                        self.input = InputMode::PlayerInput {
+                            value,
+                            file_id,
+                            name,
+                            pending_url,
+                            proxy,
+                            subs,
+                        };
                     }
                     _ => {
-                        self.input = InputMode::PlayerInput { value, pending_url };
+                        self.input = InputMode::PlayerInput {
+                            value,
+                            file_id,
+                            name,
+                            pending_url,
+                            proxy,
+                            subs,
+                        };
                     }
                 }
                 Ok(false)
             }
             InputMode::InfoLoading => {
                 if code == KeyCode::Esc {
+                    self.preview_cancel.cancel();
                     if !self.trash_entries.is_empty() {
                         self.input = InputMode::TrashView {
                             entries: std::mem::take(&mut self.trash_entries),
@@ -536,14 +864,25 @@ impl App {
                     Some(should_save) => {
                         if should_save {
                             match draft.save() {
-                                Ok(()) => {
+                                Ok(warnings) => {
+                                    for warning in warnings {
+                                        self.push_log(format!("Warning: {warning}"));
+                                    }
                                     self.config = draft;
+                                    crate::config::apply_display_format(&self.config);
                                     self.resort_entries();
+                                    self.ensure_commander_panes();
                                     // Apply the new concurrency immediately (it's
                                     // otherwise only read at startup) and let a
                                     // raised limit start more workers now.
                                     self.download_state.max_concurrent =
                                         self.config.download_jobs.max(1);
+                                    self.client
+                                        .download_limiter
+                                        .set_rate(self.config.max_download_speed);
+                                    self.client
+                                        .upload_limiter
+                                        .set_rate(self.config.max_upload_speed);
                                     self.download_state.start_next(&self.client);
                                     self.push_log("Settings saved to config.toml".into());
                                     self.input = InputMode::Normal;
@@ -601,31 +940,87 @@ impl App {
                 );
                 Ok(false)
             }
+            InputMode::KeymapView {
+                mut scroll,
+                draft,
+                modified,
+            } => {
+                match code {
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        scroll = (scroll + 1).min(keymap::ACTIONS.len().saturating_sub(1));
+                    }
+                    KeyCode::Up | KeyCode::Char('k') => scroll = scroll.saturating_sub(1),
+                    KeyCode::Esc | KeyCode::Backspace | KeyCode::Char('q') => {
+                        self.input = InputMode::Settings {
+                            selected: KEYMAP_SETTINGS_INDEX,
+                            editing: false,
+                            draft,
+                            modified,
+                        };
+                        return Ok(false);
+                    }
+                    _ => {}
+                }
+                self.input = InputMode::KeymapView {
+                    scroll,
+                    draft,
+                    modified,
+                };
+                Ok(false)
+            }
         }
     }
 
     #[allow(clippy::collapsible_match)]
     fn handle_normal_key(&mut self, code: KeyCode, modifiers: KeyModifiers) -> Result<bool> {
-        match code {
-            KeyCode::Char('q') => {
-                if self.download_state.has_active() {
-                    self.input = InputMode::ConfirmQuit;
-                } else {
-                    return Ok(true);
-                }
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+        let action = match code {
+            KeyCode::Down => Action::Down,
+            KeyCode::Up => Action::Up,
+            KeyCode::Home => Action::Top,
+            KeyCode::End => Action::Bottom,
+            KeyCode::Char('f') if ctrl => Action::Filter,
+            KeyCode::Char('a' | 'd' | 'i' | 'u') if ctrl => {
+                self.handle_fixed_normal_key(code);
+                return Ok(false);
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-                if !self.entries.is_empty() {
-                    self.selected = (self.selected + 1).min(self.entries.len() - 1);
-                    self.on_cursor_move();
-                }
+            KeyCode::F(n @ (5 | 6)) if self.config.commander_mode => {
+                self.commander_transfer(n == 6);
+                return Ok(false);
             }
-            KeyCode::Up | KeyCode::Char('k') => {
-                if self.selected > 0 {
-                    self.selected -= 1;
-                    self.on_cursor_move();
-                }
+            KeyCode::Char(c @ '1'..='9') if modifiers == KeyModifiers::ALT => {
+                self.switch_tab(c as usize - '1' as usize);
+                return Ok(false);
+            }
+            // In commander mode Tab moves the focus to the other pane.
+            KeyCode::Tab | KeyCode::BackTab
+                if self.config.commander_mode && self.tabs.len() > 1 =>
+            {
+                self.cycle_tab(code == KeyCode::Tab);
+                return Ok(false);
+            }
+            KeyCode::PageDown | KeyCode::PageUp | KeyCode::Tab | KeyCode::Esc => {
+                self.handle_fixed_normal_key(code);
+                return Ok(false);
+            }
+            KeyCode::Char(c @ '1'..='5') if modifiers.is_empty() => {
+                let idx = c as usize - '1' as usize;
+                self.sort_by_column(widgets::SORT_HEADER_FIELDS[idx]);
+                return Ok(false);
             }
+            _ => match self.keymap.action(code, modifiers) {
+                Some(action) => action,
+                None => return Ok(false),
+            },
+        };
+        self.run_normal_action(action)
+    }
+
+    /// Browser keys the keymap can't change: paging, the Ctrl shortcuts,
+    /// cart selection and Esc.
+    #[allow(clippy::collapsible_match)]
+    fn handle_fixed_normal_key(&mut self, code: KeyCode) {
+        match code {
             KeyCode::PageDown => {
                 if !self.entries.is_empty() {
                     let page = self.list_area_height.get().max(1) as usize;
@@ -640,33 +1035,77 @@ impl App {
                     self.on_cursor_move();
                 }
             }
-            KeyCode::Home => {
+            KeyCode::Char('d') => {
                 if !self.entries.is_empty() {
-                    self.selected = 0;
+                    let half = (self.list_area_height.get() / 2).max(1) as usize;
+                    self.selected = (self.selected + half).min(self.entries.len() - 1);
                     self.on_cursor_move();
                 }
             }
-            KeyCode::End => {
+            KeyCode::Char('u') => {
                 if !self.entries.is_empty() {
-                    self.selected = self.entries.len() - 1;
+                    let half = (self.list_area_height.get() / 2).max(1) as usize;
+                    self.selected = self.selected.saturating_sub(half);
+                    self.on_cursor_move();
+                }
+            }
+            KeyCode::Char('a') => {
+                self.cart_select_all();
+            }
+            // Terminals deliver Ctrl+I as Tab, so accept both.
+            KeyCode::Tab | KeyCode::Char('i') => {
+                self.cart_invert_selection();
+            }
+            KeyCode::Esc => {
+                if self.shares_pending {
+                    self.shares_pending = false;
+                    self.finish_loading();
+                } else if self.filter_source.is_some() {
+                    self.clear_filter();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    #[allow(clippy::collapsible_match)]
+    fn run_normal_action(&mut self, action: Action) -> Result<bool> {
+        match action {
+            Action::Quit => {
+                if self.download_state.has_active() {
+                    self.input = InputMode::ConfirmQuit;
+                } else {
+                    return Ok(true);
+                }
+            }
+            Action::Down => {
+                if !self.entries.is_empty() {
+                    self.selected = (self.selected + 1).min(self.entries.len() - 1);
+                    self.on_cursor_move();
+                }
+            }
+            Action::Up => {
+                if self.selected > 0 {
+                    self.selected -= 1;
                     self.on_cursor_move();
                 }
             }
-            KeyCode::Char('g') if !modifiers.contains(KeyModifiers::CONTROL) => {
+            Action::Top => {
                 if !self.entries.is_empty() {
                     self.selected = 0;
                     self.on_cursor_move();
                 }
             }
-            KeyCode::Char('G') => {
+            Action::Bottom => {
                 if !self.entries.is_empty() {
                     self.selected = self.entries.len() - 1;
                     self.on_cursor_move();
                 }
             }
-            KeyCode::Enter => {
+            Action::Open => {
                 if let Some(entry) = self.current_entry().cloned() {
                     if entry.kind == EntryKind::Folder {
+                        self.clear_filter();
                         let cached_children =
                             if self.preview_target_id.as_deref() == Some(&entry.id) {
                                 if let PreviewState::FolderListing(children) =
@@ -688,17 +1127,13 @@ impl App {
                         self.clear_preview();
 
                         if let Some(children) = cached_children {
+                            // A listing of the folder we just left is stale now.
+                            self.cancel_listing();
                             self.entries = children;
                             self.push_log(format!("Refreshed {}", self.current_path_display()));
                             self.on_cursor_move();
                         } else {
-                            self.loading = true;
-                            let client = Arc::clone(&self.client);
-                            let tx = self.result_tx.clone();
-                            let fid = self.current_folder_id.clone();
-                            std::thread::spawn(move || {
-                                let _ = tx.send(OpResult::Ls(client.ls(&fid)));
-                            });
+                            self.spawn_ls_streaming();
                         }
                     } else if entry.kind == EntryKind::File
                         && theme::categorize(&entry) == theme::FileCategory::Video
@@ -707,14 +1142,16 @@ impl App {
                         let client = Arc::clone(&self.client);
                         let tx = self.result_tx.clone();
                         let eid = entry.id.clone();
-                        std::thread::spawn(move || {
+                        self.workers.spawn(move || {
                             let _ = tx.send(OpResult::PlayInfo(client.file_info(&eid)));
                         });
                     }
                 }
             }
-            KeyCode::Backspace => {
+            Action::Back => {
                 if let Some((parent_id, _)) = self.breadcrumb.pop() {
+                    self.cancel_listing();
+                    self.clear_filter();
                     let leaving_id = std::mem::replace(&mut self.current_folder_id, parent_id);
                     let old_entries = std::mem::replace(
                         &mut self.entries,
@@ -726,7 +1163,7 @@ impl App {
                         self.selected = self.entries.len() - 1;
                     }
 
-                    if self.config.show_preview {
+                    if self.preview_pane_shown() {
                         self.preview_state = PreviewState::FolderListing(old_entries);
                         self.preview_target_id = Some(leaving_id);
                     } else {
@@ -744,48 +1181,66 @@ impl App {
                     }
                 }
             }
-            KeyCode::Char('l') => {
+            Action::Logs => {
                 self.show_logs_overlay = !self.show_logs_overlay;
                 self.logs_scroll = None;
             }
-            KeyCode::Char('r') => self.refresh(),
-            KeyCode::Char('m') => {
+            Action::Refresh => self.refresh(),
+            Action::Move => {
                 if let Some(entry) = self.current_entry().cloned() {
                     self.start_move_copy(entry, true);
                 }
             }
-            KeyCode::Char('c') => {
+            Action::Copy => {
                 if let Some(entry) = self.current_entry().cloned() {
                     self.start_move_copy(entry, false);
                 }
             }
-            KeyCode::Char('n') => {
+            Action::Rename => {
                 if self.current_entry().is_some() {
                     self.input = InputMode::Rename {
                         value: String::new(),
                     };
                 }
             }
-            KeyCode::Char('d') => {
-                if modifiers.contains(KeyModifiers::CONTROL) {
-                    if !self.entries.is_empty() {
-                        let half = (self.list_area_height.get() / 2).max(1) as usize;
-                        self.selected = (self.selected + half).min(self.entries.len() - 1);
-                        self.on_cursor_move();
-                    }
-                } else if self.current_entry().is_some() {
+            Action::BatchRename => self.open_batch_rename(),
+            Action::Normalize => self.open_normalize_names(),
+            Action::Delete => {
+                if self.current_entry().is_some() {
                     self.input = InputMode::ConfirmDelete;
                 }
             }
-            KeyCode::Char('f') => {
-                self.input = InputMode::Mkdir {
-                    value: String::new(),
-                };
+            Action::Filter => {
+                self.input = InputMode::Filter;
+            }
+            Action::Mkdir => {
+                if self.in_virtual_folder() {
+                    let msg = format!("Can't create folders in {}", self.current_folder_name());
+                    self.push_log(msg);
+                } else {
+                    self.input = InputMode::Mkdir {
+                        value: String::new(),
+                    };
+                }
+            }
+            Action::NewFile => self.open_new_file(),
+            Action::Note => self.open_note(),
+            Action::Watched => self.toggle_watched(),
+            Action::Recent => {
+                self.open_recent();
             }
-            KeyCode::Char('h') => {
+            Action::Starred => {
+                self.open_starred();
+            }
+            Action::Help => {
                 self.show_help_sheet = true;
             }
-            KeyCode::Char('a') => {
+            Action::CartGlob => {
+                self.input = InputMode::CartGlob {
+                    pattern: String::new(),
+                };
+            }
+            Action::Cart => {
                 if let Some(entry) = self.current_entry().cloned() {
                     if self.cart_ids.contains(&entry.id) {
                         self.cart_ids.remove(&entry.id);
@@ -798,21 +1253,24 @@ impl App {
                     }
                 }
             }
-            KeyCode::Char('A') => {
+            Action::CartView => {
                 self.input = InputMode::CartView;
             }
-            KeyCode::Char('D') => {
-                self.input = InputMode::DownloadView;
+            Action::Downloads => {
+                self.open_download_view();
             }
-            KeyCode::Char('M') => {
+            Action::Shares => {
                 self.open_my_shares_view();
             }
-            KeyCode::Char('s') => {
+            Action::Account => {
+                self.open_profile_picker();
+            }
+            Action::Star => {
                 if let Some(entry) = self.current_entry().cloned() {
                     self.spawn_star_toggle(entry);
                 }
             }
-            KeyCode::Char('y') => {
+            Action::CopyLink => {
                 if let Some(entry) = self.current_entry().cloned()
                     && entry.kind == EntryKind::File
                 {
@@ -820,7 +1278,7 @@ impl App {
                     let tx = self.result_tx.clone();
                     let eid = entry.id;
                     let ename = entry.name;
-                    std::thread::spawn(move || {
+                    self.workers.spawn(move || {
                         let _ = tx.send(match client.download_url(&eid) {
                             Ok((url, _)) => match write_clipboard(&url) {
                                 Ok(()) => OpResult::Ok(format!("Copied link: '{}'", ename)),
@@ -831,41 +1289,76 @@ impl App {
                     });
                 }
             }
-            KeyCode::Char('u') => {
-                if modifiers.contains(KeyModifiers::CONTROL) {
-                    if !self.entries.is_empty() {
-                        let half = (self.list_area_height.get() / 2).max(1) as usize;
-                        self.selected = self.selected.saturating_sub(half);
-                        self.on_cursor_move();
-                    }
+            Action::Share => {
+                if let Some(entry) = self.current_entry().cloned() {
+                    self.spawn_share_entry(entry);
+                }
+            }
+            Action::OpenWeb => {
+                // An empty listing opens the folder itself.
+                let target = match self.current_entry() {
+                    Some(e) => Some((e.id.clone(), e.kind == EntryKind::Folder, e.name.clone())),
+                    None if !self.in_virtual_folder() => Some((
+                        self.current_folder_id.clone(),
+                        true,
+                        self.current_folder_name().to_string(),
+                    )),
+                    None => None,
+                };
+                if let Some((id, is_folder, name)) = target {
+                    let client = Arc::clone(&self.client);
+                    let tx = self.result_tx.clone();
+                    self.workers.spawn(move || {
+                        let result = crate::cmd::open::entry_web_url(&client, &id, is_folder)
+                            .and_then(|url| crate::local_path::open_default(url.as_ref()));
+                        let _ = tx.send(OpResult::Log(match result {
+                            Ok(()) => format!("Opened '{name}' in the web UI"),
+                            Err(e) => format!("Open in web UI failed: {e:#}"),
+                        }));
+                    });
+                }
+            }
+            Action::Upload => {
+                if self.in_virtual_folder() {
+                    let msg = format!("Can't upload into {}", self.current_folder_name());
+                    self.push_log(msg);
                 } else {
-                    self.input = InputMode::UploadInput {
-                        input: LocalPathInput::new_for_upload(),
-                    };
+                    self.open_upload_input();
                 }
             }
-            KeyCode::Char('o') => {
+            Action::Undo => self.undo_last_op(),
+            Action::Jobs => self.open_jobs_view(),
+            Action::Activity => self.open_activity_view(),
+            Action::UploadClipboard => {
+                if self.in_virtual_folder() {
+                    let msg = format!("Can't upload into {}", self.current_folder_name());
+                    self.push_log(msg);
+                } else {
+                    self.upload_clipboard_image();
+                }
+            }
+            Action::Offline => {
                 self.input = InputMode::OfflineInput {
                     value: String::new(),
                 };
             }
-            KeyCode::Char('O') => {
+            Action::OfflineTasks => {
                 self.open_offline_tasks_view();
             }
-            KeyCode::Char('t') => {
+            Action::Trash => {
                 self.open_trash_view();
             }
-            KeyCode::Char('S') => {
+            Action::Sort => {
                 self.config.sort_field = self.config.sort_field.next();
                 self.resort_entries();
-                let _ = self.config.save();
+                self.save_config();
             }
-            KeyCode::Char('R') => {
+            Action::Reverse => {
                 self.config.sort_reverse = !self.config.sort_reverse;
                 self.resort_entries();
-                let _ = self.config.save();
+                self.save_config();
             }
-            KeyCode::Char('w') => {
+            Action::Play => {
                 if let Some(entry) = self.current_entry().cloned()
                     && entry.kind == EntryKind::File
                     && theme::categorize(&entry) == theme::FileCategory::Video
@@ -874,7 +1367,8 @@ impl App {
                     let client = Arc::clone(&self.client);
                     let tx = self.result_tx.clone();
                     let eid = entry.id.clone();
-                    std::thread::spawn(move || {
+                    let sub_files = subtitles::siblings(&entry, &self.entries);
+                    self.workers.spawn(move || {
                         let result = client.file_info(&eid);
                         let _ = tx.send(match result {
                             Ok(info) => {
@@ -892,6 +1386,7 @@ impl App {
                                         label: format!("Original ({})", size_str),
                                         url: url.clone(),
                                         available: true,
+                                        media: None,
                                     });
                                 }
                                 if let Some(ref medias) = info.medias {
@@ -918,38 +1413,38 @@ impl App {
                                             label,
                                             url,
                                             available,
+                                            media: m.media_name.clone(),
                                         });
                                     }
                                 }
-                                OpResult::PlayPickerInfo(Ok((info, options)))
+                                let subs = subtitles::fetch(&client, &sub_files);
+                                OpResult::PlayPickerInfo(Ok((info, options, subs)))
                             }
                             Err(e) => OpResult::PlayPickerInfo(Err(e)),
                         });
                     });
                 }
             }
-            KeyCode::Char('p') => {
+            Action::Gallery => self.open_gallery(),
+            Action::Preview => {
                 if let Some(entry) = self.current_entry().cloned() {
-                    if self.config.show_preview {
+                    if self.preview_pane_shown() {
+                        if self.preview_paused {
+                            self.preview_paused = false;
+                            self.preview_failures = 0;
+                            self.push_log("Auto-preview resumed".into());
+                        }
                         self.fetch_preview_for_selected();
-                    } else if entry.kind == EntryKind::File && theme::is_text_previewable(&entry) {
+                    } else if entry.kind == EntryKind::File && self.text_previewable(&entry) {
                         self.input = InputMode::InfoLoading;
                         self.loading = true;
                         self.loading_label = Some("Loading preview...".into());
-                        let client = Arc::clone(&self.client);
-                        let tx = self.result_tx.clone();
-                        let eid = entry.id.clone();
-                        let max_bytes = self.config.preview_max_size;
-                        std::thread::spawn(move || {
-                            let _ = tx.send(OpResult::PreviewText(
-                                eid.clone(),
-                                client.fetch_text_preview(&eid, max_bytes),
-                            ));
-                        });
+                        let cancel = self.preview_cancel.renew();
+                        self.spawn_text_preview(&entry, cancel);
                     }
                 }
             }
-            KeyCode::Char(',') => {
+            Action::Settings => {
                 self.input = InputMode::Settings {
                     selected: 0,
                     editing: false,
@@ -957,7 +1452,7 @@ impl App {
                     modified: false,
                 };
             }
-            KeyCode::Char(' ') => {
+            Action::Info => {
                 if let Some(entry) = self.current_entry().cloned() {
                     match entry.kind {
                         EntryKind::File => self.open_info_popup(entry),
@@ -965,20 +1460,77 @@ impl App {
                     }
                 }
             }
-            KeyCode::Char(':') => {
+            Action::Goto => {
                 self.input = InputMode::GotoPath {
-                    query: String::new(),
+                    input: PathInput::new(),
                 };
             }
-            KeyCode::Esc => {
-                if self.shares_pending {
-                    self.shares_pending = false;
-                    self.finish_loading();
+            Action::NewTab => self.open_tab(),
+            Action::CloseTab => self.close_tab(),
+            Action::NextTab => self.cycle_tab(true),
+            Action::PrevTab => self.cycle_tab(false),
+        }
+        Ok(false)
+    }
+
+    /// Open the view a `:name` target names (`:starred`, `:trash`,
+    /// `:offline`, `:recent`, `:downloads`, `:shares`). Returns false for
+    /// anything else, which is then taken as a path.
+    fn open_goto_view(&mut self, target: &str) -> bool {
+        let Some(name) = target.trim().strip_prefix(':') else {
+            return false;
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "starred" => self.open_starred(),
+            "trash" => self.open_trash_view(),
+            "offline" => self.open_offline_tasks_view(),
+            "recent" => self.open_recent(),
+            "downloads" => self.open_download_view(),
+            "shares" => self.open_my_shares_view(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys while typing an in-directory filter. Enter keeps the narrowed
+    /// listing (so open/info/download act on the filtered item), Esc restores
+    /// the full one.
+    fn handle_filter_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+        match (code, ctrl) {
+            (KeyCode::Esc, _) => {
+                self.clear_filter();
+                return;
+            }
+            (KeyCode::Enter, _) => {
+                if self.filter_query.is_empty() {
+                    self.clear_filter();
                 }
+                return;
+            }
+            (KeyCode::Down | KeyCode::Tab, _) | (KeyCode::Char('n'), true)
+                if !self.entries.is_empty() =>
+            {
+                self.selected = (self.selected + 1).min(self.entries.len() - 1);
+                self.on_cursor_move();
+            }
+            (KeyCode::Up | KeyCode::BackTab, _) | (KeyCode::Char('p'), true)
+                if self.selected > 0 =>
+            {
+                self.selected -= 1;
+                self.on_cursor_move();
+            }
+            (KeyCode::Backspace, _) => {
+                self.filter_query.pop();
+                self.apply_filter();
+            }
+            (KeyCode::Char(c), false) => {
+                self.filter_query.push(c);
+                self.apply_filter();
             }
             _ => {}
         }
-        Ok(false)
+        self.input = InputMode::Filter;
     }
 
     pub(super) fn start_move_copy(&mut self, source: Entry, is_move: bool) {
@@ -1125,8 +1677,17 @@ impl App {
     }
 
     fn build_picker_state(&mut self) -> Option<PickerState> {
-        let folder_id = self.current_folder_id.clone();
-        let breadcrumb = self.breadcrumb.clone();
+        // Under a virtual folder (Recent, Starred) there's no real path to
+        // start from (and it can't be a destination), so start at the root.
+        let under_virtual = self
+            .breadcrumb
+            .iter()
+            .any(|(id, _)| super::is_virtual_folder(id));
+        let (folder_id, breadcrumb) = if self.in_virtual_folder() || under_virtual {
+            (String::new(), Vec::new())
+        } else {
+            (self.current_folder_id.clone(), self.breadcrumb.clone())
+        };
         match self.client.ls(&folder_id) {
             Ok(mut entries) => {
                 crate::config::sort_entries(
@@ -1267,7 +1828,7 @@ impl App {
                         self.spawn_move_copy(source, dest_id, dest_path, is_move);
                     }
                     PathInputContext::Cart => {
-                        self.spawn_cart_move_copy(dest_id, dest_path, is_move);
+                        self.confirm_cart_move_copy(dest_id, dest_path, is_move);
                     }
                 }
             }
@@ -1327,36 +1888,34 @@ impl App {
         dest_path: String,
         is_move: bool,
     ) {
-        let client = Arc::clone(&self.client);
-        let tx = self.result_tx.clone();
-        let source_id = source.id;
-        let source_name = source.name;
-        let op = if is_move { "Move" } else { "Copy" };
-        self.loading = true;
-        std::thread::spawn(move || {
-            let result = if is_move {
-                client.mv(&[source_id.as_str()], &dest_id)
-            } else {
-                client.cp(&[source_id.as_str()], &dest_id)
-            };
-            let _ = tx.send(match result {
-                Ok(()) => OpResult::Ok(format!("{}d '{}' -> '{}'", op, source_name, dest_path)),
-                Err(e) => OpResult::Err(format!("{} failed: {e:#}", op)),
-            });
+        let what = ops::describe(std::slice::from_ref(&source));
+        let ids = vec![source.id];
+        self.queue_op(if is_move {
+            // Entries of a virtual folder live elsewhere; the queue looks
+            // their folder up.
+            let from = (!self.in_virtual_folder()).then(|| self.current_folder_id.clone());
+            FileOp::Move {
+                ids,
+                from: vec![from],
+                to: dest_id,
+                dest_path,
+                what,
+            }
+        } else {
+            FileOp::Copy {
+                ids,
+                to: dest_id,
+                dest_path,
+                what,
+            }
         });
     }
 
     pub(super) fn spawn_rename(&mut self, entry: Entry, new_name: String) {
-        let client = Arc::clone(&self.client);
-        let tx = self.result_tx.clone();
-        let eid = entry.id;
-        let old = entry.name;
-        self.loading = true;
-        std::thread::spawn(move || {
-            let _ = tx.send(match client.rename(&eid, &new_name) {
-                Ok(()) => OpResult::Ok(format!("Renamed '{}' -> '{}'", old, new_name)),
-                Err(e) => OpResult::Err(format!("Rename failed: {e:#}")),
-            });
+        self.queue_op(FileOp::Rename {
+            id: entry.id,
+            old: entry.name,
+            new: new_name,
         });
     }
 
@@ -1365,7 +1924,7 @@ impl App {
         let tx = self.result_tx.clone();
         let fid = self.current_folder_id.clone();
         self.loading = true;
-        std::thread::spawn(move || {
+        self.workers.spawn(move || {
             let _ = tx.send(match client.mkdir(&fid, &name) {
                 Ok(created) => OpResult::Ok(format!("Created folder '{}'", created.name)),
                 Err(e) => OpResult::Err(format!("Mkdir failed: {e:#}")),
@@ -1440,7 +1999,7 @@ impl App {
                     self.push_log("Cart is empty".into());
                     self.input = InputMode::CartView;
                 } else {
-                    self.input = InputMode::ConfirmCartDelete;
+                    self.open_batch_summary(BatchAction::Trash, self.cart.clone(), true);
                 }
             }
             KeyCode::Char('s') => {
@@ -1495,7 +2054,7 @@ impl App {
 
     fn execute_cart_move_copy(&mut self, target: &str, is_move: bool) {
         match self.client.resolve_path(target) {
-            Ok(dest_id) => self.spawn_cart_move_copy(dest_id, target.to_string(), is_move),
+            Ok(dest_id) => self.confirm_cart_move_copy(dest_id, target.to_string(), is_move),
             Err(e) => {
                 self.push_log(format!("Invalid path: {e:#}"));
                 self.input = InputMode::CartView;
@@ -1531,64 +2090,18 @@ impl App {
         };
     }
 
-    fn spawn_cart_move_copy(&mut self, dest_id: String, dest_path: String, is_move: bool) {
-        let (ids, names): (Vec<String>, Vec<String>) = self
-            .cart
-            .iter()
-            .map(|e| (e.id.clone(), e.name.clone()))
-            .unzip();
-        let client = Arc::clone(&self.client);
-        let tx = self.result_tx.clone();
-        let op = if is_move { "Move" } else { "Copy" };
-        let count = ids.len();
-        self.loading = true;
-        std::thread::spawn(move || {
-            let id_refs: Vec<&str> = ids.iter().map(|s| s.as_str()).collect();
-            let result = if is_move {
-                client.mv(&id_refs, &dest_id)
-            } else {
-                client.cp(&id_refs, &dest_id)
-            };
-            let _ = tx.send(match result {
-                Ok(()) => OpResult::Ok(format!("{}d {} item(s) -> '{}'", op, count, dest_path)),
-                Err(e) => OpResult::Err(format!("{} failed: {e:#}", op)),
-            });
-        });
-        self.cart.clear();
-        self.cart_ids.clear();
-        self.cart_selected = 0;
-        for name in &names {
-            self.push_log(format!("  {}", name));
-        }
-    }
-
-    fn handle_confirm_cart_delete_key(&mut self, code: KeyCode) {
-        match code {
-            KeyCode::Char('y') | KeyCode::Enter => {
-                self.spawn_cart_delete();
-            }
-            _ => {
-                self.input = InputMode::CartView;
+    fn confirm_cart_move_copy(&mut self, to: String, dest_path: String, is_move: bool) {
+        // Cart entries come from anywhere; the queue looks their folders up.
+        let action = if is_move {
+            BatchAction::Move {
+                to,
+                dest_path,
+                from: None,
             }
-        }
-    }
-
-    fn spawn_cart_delete(&mut self) {
-        let ids: Vec<String> = self.cart.iter().map(|e| e.id.clone()).collect();
-        let count = ids.len();
-        let client = Arc::clone(&self.client);
-        let tx = self.result_tx.clone();
-        self.loading = true;
-        std::thread::spawn(move || {
-            let id_refs: Vec<&str> = ids.iter().map(|s| s.as_str()).collect();
-            let _ = tx.send(match client.remove(&id_refs) {
-                Ok(()) => OpResult::Ok(format!("Trashed {} item(s)", count)),
-                Err(e) => OpResult::Err(format!("Trash failed: {e:#}")),
-            });
-        });
-        self.cart.clear();
-        self.cart_ids.clear();
-        self.cart_selected = 0;
+        } else {
+            BatchAction::Copy { to, dest_path }
+        };
+        self.open_batch_summary(action, self.cart.clone(), true);
     }
 
     fn handle_share_prompt_key(&mut self, code: KeyCode) {
@@ -1654,7 +2167,7 @@ impl App {
             let tx = self.result_tx.clone();
             let file_id = entry.id.clone();
             let title = entry.name.clone();
-            std::thread::spawn(move || {
+            self.workers.spawn(move || {
                 let result = client.create_share(&[file_id.as_str()], need_password, 0);
                 let msg = match result {
                     Ok(resp) => {
@@ -1673,6 +2186,28 @@ impl App {
         }
     }
 
+    /// Share one entry as a permanent public link, copy the URL and log it.
+    fn spawn_share_entry(&mut self, entry: Entry) {
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        self.workers.spawn(move || {
+            let msg = match client.create_share(&[entry.id.as_str()], false, -1) {
+                Ok(resp) => {
+                    let copied = match write_clipboard(&resp.share_url) {
+                        Ok(()) => "copied".to_string(),
+                        Err(e) => format!("clipboard failed: {e:#}"),
+                    };
+                    OpResult::Log(format!(
+                        "Shared '{}': {} ({copied})",
+                        entry.name, resp.share_url
+                    ))
+                }
+                Err(e) => OpResult::Err(format!("Share failed for '{}': {e:#}", entry.name)),
+            };
+            let _ = tx.send(msg);
+        });
+    }
+
     fn handle_my_shares_key(
         &mut self,
         code: KeyCode,
@@ -1697,7 +2232,7 @@ impl App {
                         selected: sel,
                         confirm_delete: None,
                     };
-                    std::thread::spawn(move || {
+                    self.workers.spawn(move || {
                         let msg = match client.delete_shares(&[share_id.as_str()]) {
                             Ok(()) => OpResult::MyShares(client.list_shares()),
                             Err(e) => OpResult::Err(format!("Delete failed: {e:#}")),
@@ -1810,7 +2345,7 @@ impl App {
                     selected: sel,
                     confirm_delete: None,
                 };
-                std::thread::spawn(move || {
+                self.workers.spawn(move || {
                     let _ = tx.send(OpResult::MyShares(client.list_shares()));
                 });
             }
@@ -1829,7 +2364,7 @@ impl App {
     /// Process a key event on a local-path input field (tab-completion, navigation, typing).
     /// Returns `Updated` for navigation/typing, `Confirmed(path)` on Enter with no candidate,
     /// or `Cancelled` on Esc with no candidates open.
-    fn apply_local_path_input_key(
+    pub(super) fn apply_local_path_input_key(
         code: KeyCode,
         input: &mut LocalPathInput,
     ) -> LocalPathInputResult {
@@ -1899,8 +2434,11 @@ impl App {
                     self.push_log("No destination path specified".into());
                     self.restore_download_input(input);
                 } else {
-                    self.start_cart_download(&dest);
-                    self.input = InputMode::DownloadView;
+                    let action = BatchAction::Download {
+                        dest: PathBuf::from(dest),
+                        on_complete: OnComplete::Nothing,
+                    };
+                    self.open_batch_summary(action, self.cart.clone(), true);
                 }
             }
             LocalPathInputResult::Cancelled => {
@@ -1914,113 +2452,472 @@ impl App {
         self.input = InputMode::DownloadInput { input: owned };
     }
 
-    fn restore_upload_input(&mut self, input: &mut LocalPathInput) {
-        let owned = std::mem::take(input);
-        self.input = InputMode::UploadInput { input: owned };
+    /// Upload the image on the system clipboard (e.g. a fresh screenshot) to
+    /// the current folder as `clipboard-YYYYMMDD-HHMMSS.png`. A screenshot
+    /// is small, so unlike file uploads it goes through the worker pool.
+    fn upload_clipboard_image(&mut self) {
+        let folder_id = self.current_folder_id.clone();
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        self.loading = true;
+        self.uploads_running += 1;
+        self.loading_label = Some("Uploading clipboard image…".into());
+        self.workers.spawn(move || {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs() as i64)
+                .unwrap_or(0);
+            let stamp =
+                crate::cmd::format_date_with(&crate::pikpak::unix_to_iso(now), "%Y%m%d-%H%M%S");
+            let local_path = std::env::temp_dir().join(format!("clipboard-{}.png", stamp));
+            let result = read_clipboard_image(&local_path).and_then(|_| {
+                client
+                    .upload_file(Some(&folder_id), &local_path)
+                    .map(|(name, _)| format!("Uploaded clipboard image as '{}'", name))
+            });
+            let _ = std::fs::remove_file(&local_path);
+            let _ = tx.send(OpResult::Upload(result));
+        });
     }
 
-    fn handle_upload_input_key(&mut self, code: KeyCode, input: &mut LocalPathInput) {
-        match Self::apply_local_path_input_key(code, input) {
-            LocalPathInputResult::Updated => self.restore_upload_input(input),
-            LocalPathInputResult::Confirmed(path_str) => {
-                let local_path = std::path::PathBuf::from(&path_str);
-                if !local_path.exists() {
-                    self.push_log(format!("File not found: {}", local_path.display()));
-                    self.restore_upload_input(input);
-                } else if local_path.is_dir() {
-                    let folder_id = self.current_folder_id.clone();
-                    let client = Arc::clone(&self.client);
-                    let tx = self.result_tx.clone();
-                    let name = local_path
-                        .file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_string();
-                    self.loading = true;
-                    self.loading_label = Some(format!("Uploading folder {}…", name));
-                    self.input = InputMode::Normal;
-                    std::thread::spawn(move || {
-                        let result =
-                            client
-                                .upload_dir(&folder_id, &local_path)
-                                .map(|(ok, failed)| {
-                                    if failed == 0 {
-                                        format!("Uploaded folder '{}' ({} files)", name, ok)
-                                    } else {
-                                        format!(
-                                            "Uploaded folder '{}' ({} ok, {} failed)",
-                                            name, ok, failed
-                                        )
-                                    }
-                                });
-                        let _ = tx.send(OpResult::Upload(result));
-                    });
-                } else if local_path.is_file() {
-                    let folder_id = self.current_folder_id.clone();
-                    let client = Arc::clone(&self.client);
+    /// Lock the screen once `lock_after_minutes` pass without input. Nothing
+    /// happens at the login prompt, or when there is no PIN or saved account
+    /// to unlock with.
+    pub(super) fn maybe_lock(&mut self) {
+        let minutes = self.config.lock_after_minutes;
+        if minutes == 0
+            || self.lock.is_some()
+            || matches!(
+                self.input,
+                InputMode::Login { .. } | InputMode::LoginCode { .. }
+            )
+            || self.last_input.elapsed() < Duration::from_secs(minutes * 60)
+        {
+            return;
+        }
+        match super::lock::Unlock::resolve(self.config.lock_pin.as_deref()) {
+            Some(unlock) => {
+                self.lock = Some(super::lock::LockScreen {
+                    unlock,
+                    value: String::new(),
+                    error: None,
+                    checking: false,
+                });
+            }
+            None => {
+                self.push_log(
+                    "Idle lock disabled: set lock_pin or save your login to use it".to_string(),
+                );
+                self.config.lock_after_minutes = 0;
+            }
+        }
+    }
+
+    pub(super) fn finish_unlock(&mut self, result: Result<()>) {
+        let Some(lock) = &mut self.lock else { return };
+        match result {
+            Ok(()) => self.lock = None,
+            Err(e) => {
+                lock.checking = false;
+                lock.error = Some(format!("{e:#}"));
+            }
+        }
+    }
+
+    pub(super) fn handle_lock_key(&mut self, code: KeyCode, modifiers: KeyModifiers) {
+        let Some(lock) = &mut self.lock else { return };
+        if lock.checking {
+            return;
+        }
+        match code {
+            KeyCode::Enter => match lock.unlock.check(&lock.value) {
+                Some(Ok(())) => self.lock = None,
+                Some(Err(e)) => {
+                    lock.value.clear();
+                    lock.error = Some(format!("{e:#}"));
+                }
+                None => {
+                    let super::lock::Unlock::Account(email) = &lock.unlock else {
+                        return;
+                    };
+                    let (email, password) = (email.clone(), std::mem::take(&mut lock.value));
+                    lock.checking = true;
+                    lock.error = None;
                     let tx = self.result_tx.clone();
-                    let name = local_path
-                        .file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_string();
-                    self.loading = true;
-                    self.loading_label = Some(format!("Uploading {}…", name));
-                    self.input = InputMode::Normal;
-                    std::thread::spawn(move || {
-                        let result = client.upload_file(Some(&folder_id), &local_path).map(
-                            |(name, dedup)| {
-                                if dedup {
-                                    format!("Uploaded '{}' (instant, dedup)", name)
-                                } else {
-                                    format!("Uploaded '{}'", name)
-                                }
-                            },
-                        );
-                        let _ = tx.send(OpResult::Upload(result));
+                    self.workers.spawn(move || {
+                        let result = PikPak::new().and_then(|mut c| c.login(&email, &password));
+                        let _ = tx.send(OpResult::Unlock(result));
                     });
+                }
+            },
+            KeyCode::Esc => {
+                lock.value.clear();
+                lock.error = None;
+            }
+            KeyCode::Backspace => {
+                lock.value.pop();
+            }
+            KeyCode::Char('u') if modifiers.contains(KeyModifiers::CONTROL) => lock.value.clear(),
+            KeyCode::Char(c) if !modifiers.contains(KeyModifiers::CONTROL) => lock.value.push(c),
+            _ => {}
+        }
+    }
+
+    /// Kick off a check for completed offline tasks when auto-fetch is on
+    /// and the interval has passed. Each matching task's files are listed,
+    /// then the task is removed from the offline list.
+    pub(super) fn maybe_auto_fetch(&mut self) {
+        // Only the instance that owns the download queue polls, so two
+        // instances never both claim the same completed task.
+        if self.config.auto_fetch_dir.is_none() || !self.instance.is_primary() {
+            return;
+        }
+        self.settle_auto_fetched();
+        let interval = Duration::from_secs(self.config.auto_fetch_interval.max(10));
+        if self.auto_fetch_busy || self.last_auto_fetch.elapsed() < interval {
+            return;
+        }
+        self.auto_fetch_busy = true;
+        self.last_auto_fetch = Instant::now();
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        let pattern = self.config.auto_fetch_pattern.clone();
+        let skip: HashSet<String> = self
+            .auto_fetched
+            .keys()
+            .chain(&self.auto_fetch_failed)
+            .cloned()
+            .collect();
+        self.workers.spawn(move || {
+            let mut batches = Vec::new();
+            match crate::cmd::tasks::completed_tasks(&client, pattern.as_deref()) {
+                Ok(tasks) => {
+                    for task in tasks.into_iter().filter(|t| !skip.contains(&t.id)) {
+                        match client.offline_task_files(&task) {
+                            Ok(files) => batches.push((task, files)),
+                            Err(e) => {
+                                let _ = tx.send(OpResult::Log(format!(
+                                    "Auto-fetch of '{}' failed: {e:#}",
+                                    task.name
+                                )));
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    let _ = tx.send(OpResult::Log(format!(
+                        "Auto-fetch: failed to list offline tasks: {e:#}"
+                    )));
+                }
+            }
+            let _ = tx.send(OpResult::AutoFetch(batches));
+        });
+    }
+
+    /// Remove auto-fetched tasks from PikPak's list once all their files
+    /// have downloaded. A task with a failed or removed download is left
+    /// there and not fetched again this session, like `tasks --auto-fetch`.
+    pub(super) fn settle_auto_fetched(&mut self) {
+        let mut done = Vec::new();
+        let mut failed = Vec::new();
+        for (task_id, fetch) in &self.auto_fetched {
+            let mut waiting = false;
+            for file_id in &fetch.file_ids {
+                let mut tasks = self
+                    .download_state
+                    .tasks
+                    .iter()
+                    .filter(|t| &t.file_id == file_id);
+                if tasks.clone().any(|t| t.status == TaskStatus::Done) {
+                    continue;
+                }
+                if tasks.any(|t| !matches!(t.status, TaskStatus::Failed(_))) {
+                    waiting = true;
                 } else {
-                    self.push_log(format!("Not a file or directory: {}", local_path.display()));
-                    self.restore_upload_input(input);
+                    failed.push(task_id.clone());
+                    break;
                 }
             }
-            LocalPathInputResult::Cancelled => {
-                self.input = InputMode::Normal;
+            if !waiting && !failed.contains(task_id) {
+                done.push(task_id.clone());
             }
         }
+        for task_id in failed {
+            if let Some(fetch) = self.auto_fetched.remove(&task_id) {
+                self.push_log(format!(
+                    "Auto-fetch of '{}' didn't finish; the offline task stays in PikPak",
+                    fetch.name
+                ));
+                self.auto_fetch_failed.insert(task_id);
+            }
+        }
+        for task_id in done {
+            let Some(fetch) = self.auto_fetched.remove(&task_id) else {
+                continue;
+            };
+            let client = Arc::clone(&self.client);
+            let tx = self.result_tx.clone();
+            self.workers.spawn(move || {
+                if let Err(e) = client.delete_tasks(&[&task_id], false) {
+                    let _ = tx.send(OpResult::Log(format!(
+                        "Auto-fetch: could not remove task '{}': {e:#}",
+                        fetch.name
+                    )));
+                }
+            });
+        }
     }
 
-    fn start_cart_download(&mut self, dest_dir: &str) {
-        let dest = PathBuf::from(dest_dir);
-        let cart_items: Vec<Entry> = self.cart.drain(..).collect();
-        self.cart_ids.clear();
-        self.cart_selected = 0;
+    /// Take the next step of the hash index when `hash_index` is on: one
+    /// folder listing every `hash_index_interval` seconds, so the crawl
+    /// never competes with browsing. A complete index is redone daily.
+    pub(super) fn maybe_index(&mut self) {
+        // Instances share the index; only the primary one builds it.
+        if !self.config.hash_index
+            || !self.instance.is_primary()
+            || self.index_busy
+            || Instant::now() < self.next_index_step
+        {
+            return;
+        }
+        self.index_busy = true;
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        self.workers.spawn(move || {
+            let step = match client.hash_index_due(Duration::from_secs(24 * 3600)) {
+                Ok(true) => Some(client.hash_index_step()),
+                Ok(false) => None,
+                Err(e) => Some(Err(e)),
+            };
+            let _ = tx.send(OpResult::IndexStep(step));
+        });
+    }
+
+    pub(super) fn on_index_step(&mut self, step: Option<Result<crate::pikpak::IndexStep>>) {
+        use crate::pikpak::IndexStep;
+        self.index_busy = false;
+        let wait = match step {
+            Some(Ok(IndexStep::Listed { .. })) => self.config.hash_index_interval.max(1),
+            Some(Ok(IndexStep::Finished { files })) => {
+                self.push_log(format!("Hash index updated: {files} file(s)"));
+                600
+            }
+            Some(Err(e)) => {
+                self.push_log(format!("Hash index: {e:#}; retrying in 5 minutes"));
+                300
+            }
+            None => 600,
+        };
+        self.next_index_step = Instant::now() + Duration::from_secs(wait);
+    }
 
-        let count = cart_items.len();
-        for item in cart_items {
-            let file_dest = dest.join(&item.name);
+    /// Run the trash rules of `cleanup` once a day when `auto_cleanup` is
+    /// on; whether a day has passed is kept in the database, so restarts
+    /// don't run them again.
+    pub(super) fn maybe_cleanup(&mut self) {
+        if !self.config.auto_cleanup
+            || self.config.cleanup.is_empty()
+            || !self.instance.is_primary()
+            || self.cleanup_busy
+            || Instant::now() < self.next_cleanup_check
+        {
+            return;
+        }
+        self.cleanup_busy = true;
+        self.next_cleanup_check = Instant::now() + Duration::from_secs(3600);
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        let rules = self.config.cleanup.clone();
+        self.workers.spawn(move || {
+            let result = crate::cmd::cleanup::auto_run(&client, &rules);
+            let _ = tx.send(OpResult::Cleanup(result));
+        });
+    }
+
+    pub(super) fn on_cleanup(&mut self, result: Result<Option<Vec<String>>>) {
+        self.cleanup_busy = false;
+        match result {
+            Ok(Some(lines)) if !lines.is_empty() => {
+                for line in lines {
+                    self.push_log(line);
+                }
+                self.refresh();
+            }
+            Ok(_) => {}
+            Err(e) => self.push_log(format!("Cleanup failed: {e:#}")),
+        }
+    }
+
+    pub(super) fn queue_auto_fetched(
+        &mut self,
+        batches: Vec<(OfflineTask, Vec<(Entry, PathBuf)>)>,
+    ) {
+        let Some(dir) = self.config.auto_fetch_dir.as_deref() else {
+            return;
+        };
+        let dir = expand_home(dir);
+        for (task, files) in batches {
+            let count = files.len();
+            let file_ids = files.iter().map(|(entry, _)| entry.id.clone()).collect();
+            let batch = files
+                .into_iter()
+                .map(|(entry, rel)| QueuedDownload {
+                    file_id: entry.id,
+                    name: entry.name,
+                    total_size: entry.size,
+                    dest_path: dir.join(rel),
+                    on_complete: OnComplete::Nothing,
+                })
+                .collect();
+            self.push_log(format!(
+                "Auto-fetch: queued {} file(s) from '{}'",
+                count, task.name
+            ));
+            self.enqueue_downloads(batch, &format!("Auto-fetch: {}", task.name));
+            self.auto_fetched.insert(
+                task.id,
+                super::AutoFetched {
+                    name: task.name,
+                    file_ids,
+                },
+            );
+        }
+    }
+
+    /// Carry out what finished downloads were set to do; one set to shut
+    /// down arms the when-all-done countdown.
+    pub(super) fn run_completions(&mut self) {
+        for done in std::mem::take(&mut self.download_state.completions) {
+            if done.action == OnComplete::Play
+                && let Some(player) = self.config.player.clone()
+            {
+                self.spawn_player(
+                    &player,
+                    (&done.dest_path.to_string_lossy(), None),
+                    &[],
+                    ("", ""),
+                );
+                continue;
+            }
+            let hook = self.config.download_hook.clone();
+            let tx = self.result_tx.clone();
+            self.workers.spawn(move || {
+                let result = match done.action {
+                    OnComplete::OpenFolder => match done.dest_path.parent() {
+                        Some(dir) => crate::local_path::open_default(dir.as_os_str()),
+                        None => Ok(()),
+                    },
+                    OnComplete::Play => crate::local_path::open_default(done.dest_path.as_os_str()),
+                    OnComplete::Hook => match hook {
+                        Some(hook) => super::download::run_hook(&hook, &done),
+                        None => Err(anyhow::anyhow!("no download_hook in config.toml")),
+                    },
+                    OnComplete::Nothing | OnComplete::Shutdown => Ok(()),
+                };
+                if let Err(e) = result {
+                    let _ = tx.send(OpResult::Log(format!(
+                        "'{}': {} failed: {e:#}",
+                        done.name,
+                        done.action.label()
+                    )));
+                }
+            });
+        }
+        if self.download_state.take_shutdown() {
+            self.arm_shutdown();
+        }
+    }
+
+    /// Queue downloads here, or hand them to the instance that owns the
+    /// queue when another pikpaktui is running. A file already queued for
+    /// the same destination is skipped; a different file whose destination
+    /// is taken (by the queue or earlier in the batch) is saved as
+    /// `name (1).ext` instead of overwriting it. When more than one task is
+    /// queued they're grouped under a job called `label`.
+    pub(super) fn enqueue_downloads(&mut self, batch: Vec<QueuedDownload>, label: &str) {
+        if let super::instance::Role::Secondary { socket, .. } = &self.instance {
+            match super::instance::send(socket, &batch) {
+                Ok(()) => {
+                    self.push_log(format!(
+                        "Handed {} download(s) to the running pikpaktui",
+                        batch.len()
+                    ));
+                    return;
+                }
+                Err(e) => self.push_log(format!(
+                    "Couldn't reach the other pikpaktui ({e:#}); downloading here"
+                )),
+            }
+        }
+        let mut taken: HashMap<PathBuf, HashSet<String>> = HashMap::new();
+        let first_new = self.download_state.tasks.len();
+        for mut item in batch {
+            if self
+                .download_state
+                .tasks
+                .iter()
+                .any(|t| t.file_id == item.file_id && t.dest_path == item.dest_path)
+            {
+                continue;
+            }
+            let dir = item
+                .dest_path
+                .parent()
+                .map(PathBuf::from)
+                .unwrap_or_default();
+            let names = taken.entry(dir.clone()).or_insert_with(|| {
+                self.download_state
+                    .tasks
+                    .iter()
+                    .filter(|t| t.dest_path.parent() == Some(dir.as_path()))
+                    .filter_map(|t| t.dest_path.file_name())
+                    .map(|n| n.to_string_lossy().to_lowercase())
+                    .collect()
+            });
+            let wanted = item
+                .dest_path
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let name = crate::pikpak::unique_name(&wanted, names);
+            if name != wanted {
+                self.push_log(format!(
+                    "'{}' is already taken in {}, saving as '{}'",
+                    wanted,
+                    dir.display(),
+                    name
+                ));
+                item.dest_path = dir.join(&name);
+            }
             let id = self.download_state.alloc_id();
-            let task = DownloadTask {
+            self.download_state.tasks.push(DownloadTask {
                 id,
-                file_id: item.id,
+                file_id: item.file_id,
                 name: item.name,
-                total_size: item.size,
+                total_size: item.total_size,
                 downloaded: 0,
-                dest_path: file_dest,
+                dest_path: item.dest_path,
                 status: TaskStatus::Pending,
                 pause_flag: Arc::new(AtomicBool::new(false)),
                 cancel_flag: Arc::new(AtomicBool::new(false)),
                 speed: 0.0,
-            };
-            self.download_state.tasks.push(task);
+                limit: Arc::default(),
+                job: None,
+                finished_at: None,
+                on_complete: item.on_complete,
+            });
+        }
+        if self.download_state.tasks.len() - first_new > 1 {
+            let job = self.download_state.add_job(label.to_string());
+            for task in &mut self.download_state.tasks[first_new..] {
+                task.job = Some(job);
+            }
         }
-
-        self.push_log(format!("Queued {} files for download", count));
         self.download_state.start_next(&self.client);
     }
 
     fn handle_download_view_key(&mut self, code: KeyCode) {
-        let task_count = self.download_state.tasks.len();
+        let row_count = self.download_state.rows().len();
 
         // Per-task keys (j/k/p/x/r) need the Expanded list's visible selection
         // cursor. The collapsed view is a summary with no cursor, so there only
@@ -2033,6 +2930,10 @@ impl App {
                 | KeyCode::Char('p')
                 | KeyCode::Char('x')
                 | KeyCode::Char('r')
+                | KeyCode::Char('+')
+                | KeyCode::Char('-')
+                | KeyCode::Char('w')
+                | KeyCode::Char(' ')
                 | KeyCode::Down
                 | KeyCode::Up
         ) && self.download_view_mode != crate::tui::DownloadViewMode::Expanded
@@ -2052,9 +2953,24 @@ impl App {
                 self.input = InputMode::DownloadView;
             }
             KeyCode::Down | KeyCode::Char('j') => {
-                if task_count > 0 {
+                if row_count > 0 {
                     self.download_state.selected =
-                        (self.download_state.selected + 1).min(task_count - 1);
+                        (self.download_state.selected + 1).min(row_count - 1);
+                }
+                self.input = InputMode::DownloadView;
+            }
+            KeyCode::Char(' ') => {
+                self.download_state.toggle_job();
+                self.input = InputMode::DownloadView;
+            }
+            KeyCode::Char('W') => {
+                self.cycle_when_done();
+                self.input = InputMode::DownloadView;
+            }
+            KeyCode::Char('c') => {
+                match self.download_state.clear_finished() {
+                    0 => self.push_log("No finished downloads to clear".into()),
+                    n => self.push_log(format!("Cleared {} finished download(s)", n)),
                 }
                 self.input = InputMode::DownloadView;
             }
@@ -2065,30 +2981,27 @@ impl App {
                 self.input = InputMode::DownloadView;
             }
             KeyCode::Char('p') => {
-                let sel = self.download_state.selected;
-                let mut log_msg = None;
+                let sel = self.download_state.selected_tasks();
+                // On a job, pause whatever is running; if nothing is, resume
+                // the paused ones.
+                let pausing = sel.iter().any(|&i| {
+                    matches!(self.download_state.tasks[i].status, TaskStatus::Downloading)
+                });
+                let mut names = Vec::new();
                 let mut need_start = false;
-                // Read fields up front so the task borrow isn't held across the
-                // active_ids check.
-                let info = self
-                    .download_state
-                    .tasks
-                    .get(sel)
-                    .map(|t| (t.status.clone(), t.id, t.name.clone()));
-                if let Some((status, id, name)) = info {
-                    match status {
-                        TaskStatus::Downloading => {
-                            let task = &mut self.download_state.tasks[sel];
+                for i in sel {
+                    let id = self.download_state.tasks[i].id;
+                    let worker_alive = self.download_state.active_ids.contains(&id);
+                    let task = &mut self.download_state.tasks[i];
+                    match task.status {
+                        TaskStatus::Downloading if pausing => {
                             task.pause_flag.store(true, Ordering::Relaxed);
                             task.status = TaskStatus::Paused;
-                            log_msg = Some(format!("Paused '{}'", name));
                         }
-                        TaskStatus::Paused => {
+                        TaskStatus::Paused if !pausing => {
                             // A parked worker resumes itself; spawning another
                             // would write the same file twice. Re-queue only when
                             // no worker exists (e.g. task restored from disk).
-                            let worker_alive = self.download_state.active_ids.contains(&id);
-                            let task = &mut self.download_state.tasks[sel];
                             task.pause_flag.store(false, Ordering::Relaxed);
                             if worker_alive {
                                 task.status = TaskStatus::Downloading;
@@ -2096,61 +3009,97 @@ impl App {
                                 task.status = TaskStatus::Pending;
                                 need_start = true;
                             }
-                            log_msg = Some(format!("Resumed '{}'", name));
                         }
-                        _ => {}
+                        _ => continue,
                     }
+                    names.push(task.name.clone());
                 }
-                if let Some(msg) = log_msg {
-                    self.push_log(msg);
+                let verb = if pausing { "Paused" } else { "Resumed" };
+                match names.as_slice() {
+                    [] => {}
+                    [name] => self.push_log(format!("{} '{}'", verb, name)),
+                    _ => self.push_log(format!("{} {} downloads", verb, names.len())),
                 }
                 if need_start {
                     self.download_state.start_next(&self.client);
                 }
                 self.input = InputMode::DownloadView;
             }
+            KeyCode::Char('w') => {
+                let sel = self.download_state.selected_tasks();
+                if let Some(&first) = sel.first() {
+                    // A group follows its first task, so all end up alike.
+                    let action = self.download_state.tasks[first].on_complete.next();
+                    for &i in &sel {
+                        self.download_state.tasks[i].on_complete = action;
+                    }
+                    let what = match sel.as_slice() {
+                        [i] => format!("'{}'", self.download_state.tasks[*i].name),
+                        _ => format!("{} downloads", sel.len()),
+                    };
+                    self.push_log(format!("{what}: when done, {}", action.label()));
+                }
+                self.input = InputMode::DownloadView;
+            }
+            KeyCode::Char(c @ ('+' | '-')) => {
+                if let Some(i) = self.download_state.selected_task() {
+                    let task = &self.download_state.tasks[i];
+                    let rate = crate::ratelimit::step_speed(task.limit.rate(), c == '+');
+                    task.limit.set_rate(rate);
+                    let msg = format!(
+                        "Speed limit for '{}': {}",
+                        task.name,
+                        crate::ratelimit::format_rate(rate)
+                    );
+                    self.push_log(msg);
+                }
+                self.input = InputMode::DownloadView;
+            }
             KeyCode::Char('x') => {
-                let sel = self.download_state.selected;
-                let cancel_info = self.download_state.tasks.get(sel).and_then(|t| {
-                    matches!(
+                let mut cancelled = Vec::new();
+                for i in self.download_state.selected_tasks() {
+                    let t = &self.download_state.tasks[i];
+                    if matches!(
                         t.status,
                         TaskStatus::Downloading | TaskStatus::Paused | TaskStatus::Pending
-                    )
-                    .then(|| (t.id, t.name.clone(), Arc::clone(&t.cancel_flag)))
-                });
-                if let Some((id, name, cancel_flag)) = cancel_info {
-                    // Worker stops on cancel_flag without a Done/Failed message,
-                    // so drop its active_ids entry here.
-                    cancel_flag.store(true, Ordering::Relaxed);
-                    self.download_state.active_ids.remove(&id);
-                    self.download_state.tasks.remove(sel);
-                    if self.download_state.selected >= self.download_state.tasks.len()
-                        && self.download_state.selected > 0
-                    {
-                        self.download_state.selected -= 1;
+                    ) {
+                        // Worker stops on cancel_flag without a Done/Failed
+                        // message, so drop its active_ids entry here.
+                        t.cancel_flag.store(true, Ordering::Relaxed);
+                        cancelled.push((t.id, t.name.clone()));
+                    }
+                }
+                if !cancelled.is_empty() {
+                    let ids: HashSet<u64> = cancelled.iter().map(|(id, _)| *id).collect();
+                    for id in &ids {
+                        self.download_state.active_ids.remove(id);
+                    }
+                    self.download_state.remove_tasks(&ids);
+                    match cancelled.as_slice() {
+                        [(_, name)] => self.push_log(format!("Cancelled '{}'", name)),
+                        _ => self.push_log(format!("Cancelled {} downloads", cancelled.len())),
                     }
-                    self.push_log(format!("Cancelled '{}'", name));
                     self.download_state.start_next(&self.client);
                 }
                 self.input = InputMode::DownloadView;
             }
             KeyCode::Char('r') => {
-                let sel = self.download_state.selected;
-                let mut log_msg = None;
-                let mut need_start = false;
-                if let Some(task) = self.download_state.tasks.get_mut(sel)
-                    && matches!(task.status, TaskStatus::Failed(_))
-                {
-                    task.status = TaskStatus::Pending;
-                    task.cancel_flag.store(false, Ordering::Relaxed);
-                    task.pause_flag.store(false, Ordering::Relaxed);
-                    log_msg = Some(format!("Retrying '{}'", task.name));
-                    need_start = true;
+                let mut names = Vec::new();
+                for i in self.download_state.selected_tasks() {
+                    let task = &mut self.download_state.tasks[i];
+                    if matches!(task.status, TaskStatus::Failed(_)) {
+                        task.status = TaskStatus::Pending;
+                        task.cancel_flag.store(false, Ordering::Relaxed);
+                        task.pause_flag.store(false, Ordering::Relaxed);
+                        names.push(task.name.clone());
+                    }
                 }
-                if let Some(msg) = log_msg {
-                    self.push_log(msg);
+                match names.as_slice() {
+                    [] => {}
+                    [name] => self.push_log(format!("Retrying '{}'", name)),
+                    _ => self.push_log(format!("Retrying {} downloads", names.len())),
                 }
-                if need_start {
+                if !names.is_empty() {
                     self.download_state.start_next(&self.client);
                 }
                 self.input = InputMode::DownloadView;
@@ -2168,7 +3117,7 @@ impl App {
         let eid = entry.id.clone();
         let name = entry.name.clone();
         self.loading = true;
-        std::thread::spawn(move || {
+        self.workers.spawn(move || {
             let result = if is_starred {
                 client.unstar(&[eid.as_str()])
             } else {
@@ -2188,14 +3137,32 @@ impl App {
                 self.push_log("Offline download cancelled".into());
             }
             KeyCode::Enter => {
-                let url = value.trim().to_string();
-                if url.is_empty() {
+                let links = crate::cmd::offline::parse_links(value);
+                let show_tasks = links.iter().any(|l| crate::torrent::is_torrent_path(l));
+                let resolved: Result<Vec<String>> = links
+                    .iter()
+                    .map(|l| crate::torrent::resolve_link(l))
+                    .collect();
+                if links.is_empty() {
                     self.push_log("No URL provided".into());
                     self.input = InputMode::OfflineInput {
                         value: std::mem::take(value),
                     };
+                } else if let Err(e) = &resolved {
+                    self.push_log(format!("{e:#}"));
+                    self.input = InputMode::OfflineInput {
+                        value: std::mem::take(value),
+                    };
+                } else if let Some(picker) = self.build_picker_state() {
+                    self.input = InputMode::OfflinePicker {
+                        urls: resolved.unwrap_or_default(),
+                        picker,
+                        show_tasks,
+                    };
                 } else {
-                    self.spawn_offline_download(url);
+                    self.input = InputMode::OfflineInput {
+                        value: std::mem::take(value),
+                    };
                 }
             }
             KeyCode::Backspace => {
@@ -2218,28 +3185,102 @@ impl App {
         }
     }
 
-    fn spawn_offline_download(&mut self, url: String) {
+    /// Second step of an offline download: pick the folder the links land in.
+    /// Esc goes back to the link prompt with the links intact.
+    fn handle_offline_picker_key(
+        &mut self,
+        code: KeyCode,
+        urls: Vec<String>,
+        picker: &mut PickerState,
+        show_tasks: bool,
+    ) {
+        match self.apply_picker_key(code, picker) {
+            PickerKeyResult::Confirmed(dest_id) => {
+                self.push_log(format!(
+                    "Offline download to {}",
+                    Self::picker_path_display(picker)
+                ));
+                // An empty id is the root, which the API takes as "no parent".
+                let parent_id = (!dest_id.is_empty()).then_some(dest_id);
+                if show_tasks {
+                    self.input = InputMode::InfoLoading;
+                }
+                self.spawn_offline_download(urls, parent_id, show_tasks);
+            }
+            PickerKeyResult::Cancelled => {
+                self.input = InputMode::OfflineInput {
+                    value: urls.join("\n"),
+                };
+            }
+            PickerKeyResult::ShowHelp => {
+                self.show_help_sheet = true;
+                self.restore_offline_picker(urls, picker, show_tasks);
+            }
+            PickerKeyResult::Navigated | PickerKeyResult::SwitchToTextInput => {
+                self.restore_offline_picker(urls, picker, show_tasks);
+            }
+        }
+    }
+
+    fn restore_offline_picker(
+        &mut self,
+        urls: Vec<String>,
+        picker: &mut PickerState,
+        show_tasks: bool,
+    ) {
+        let picker = std::mem::take(picker);
+        self.input = InputMode::OfflinePicker {
+            urls,
+            picker,
+            show_tasks,
+        };
+    }
+
+    /// Submit offline links one after another, logging each task's id and
+    /// initial phase as it's created. A failed link doesn't stop the rest.
+    /// With `show_tasks` the offline tasks view opens afterwards (the caller
+    /// puts the UI in `InfoLoading` for it).
+    fn spawn_offline_download(
+        &mut self,
+        urls: Vec<String>,
+        parent_id: Option<String>,
+        show_tasks: bool,
+    ) {
         let client = Arc::clone(&self.client);
         let tx = self.result_tx.clone();
-        let parent_id = if self.current_folder_id.is_empty() {
-            None
-        } else {
-            Some(self.current_folder_id.clone())
-        };
         self.loading = true;
-        std::thread::spawn(move || {
-            let result = client.offline_download(&url, parent_id.as_deref(), None);
-            let _ = tx.send(match result {
-                Ok(resp) => {
-                    let name = resp
-                        .task
-                        .as_ref()
-                        .map(|t| t.name.as_str())
-                        .unwrap_or("unknown");
-                    OpResult::Ok(format!("Offline task created: {}", name))
-                }
-                Err(e) => OpResult::Err(format!("Offline download failed: {e:#}")),
+        self.workers.spawn(move || {
+            let mut failed = 0usize;
+            for url in &urls {
+                let line = match client.offline_download(url, parent_id.as_deref(), None) {
+                    Ok(resp) => match resp.task {
+                        Some(task) => format!(
+                            "Offline task created: {} (id {}, {})",
+                            task.name, task.id, task.phase
+                        ),
+                        None => format!("Offline download submitted: {}", url),
+                    },
+                    Err(e) => {
+                        failed += 1;
+                        format!("Offline download failed for {}: {e:#}", url)
+                    }
+                };
+                let _ = tx.send(OpResult::Log(line));
+            }
+            let _ = tx.send(if failed == urls.len() {
+                OpResult::Err("Offline download failed".into())
+            } else if urls.len() == 1 {
+                OpResult::Ok("Offline download submitted".into())
+            } else {
+                OpResult::Ok(format!(
+                    "Submitted {} of {} offline link(s)",
+                    urls.len() - failed,
+                    urls.len()
+                ))
             });
+            if show_tasks {
+                let _ = tx.send(OpResult::OfflineTasks(fetch_offline_tasks(&client)));
+            }
         });
     }
 
@@ -2249,15 +3290,8 @@ impl App {
         self.loading_label = Some("Loading offline tasks...".into());
         let client = Arc::clone(&self.client);
         let tx = self.result_tx.clone();
-        std::thread::spawn(move || {
-            let phases = &[
-                "PHASE_TYPE_RUNNING",
-                "PHASE_TYPE_PENDING",
-                "PHASE_TYPE_COMPLETE",
-                "PHASE_TYPE_ERROR",
-            ];
-            let result = client.offline_list(50, phases).map(|r| r.tasks);
-            let _ = tx.send(OpResult::OfflineTasks(result));
+        self.workers.spawn(move || {
+            let _ = tx.send(OpResult::OfflineTasks(fetch_offline_tasks(&client)));
         });
     }
 
@@ -2301,7 +3335,7 @@ impl App {
                     self.input = InputMode::InfoLoading;
                     self.loading = true;
                     self.loading_label = Some("Retrying task...".into());
-                    std::thread::spawn(move || {
+                    self.workers.spawn(move || {
                         let msg = match client.offline_task_retry(&task_id) {
                             Ok(()) => format!("Retrying task: {}", task_name),
                             Err(e) => format!("Retry failed: {e:#}"),
@@ -2326,7 +3360,7 @@ impl App {
                     self.input = InputMode::InfoLoading;
                     self.loading = true;
                     self.loading_label = Some("Deleting task...".into());
-                    std::thread::spawn(move || {
+                    self.workers.spawn(move || {
                         let msg = match client.delete_tasks(&[task_id.as_str()], false) {
                             Ok(()) => format!("Deleted task: {}", task_name),
                             Err(e) => format!("Delete task failed: {e:#}"),
@@ -2353,6 +3387,7 @@ impl App {
         self.trash_entries.clear();
         self.trash_selected = 0;
         self.trash_expanded = false;
+        self.trash_marked.clear();
         self.input = InputMode::TrashView {
             entries: vec![],
             selected: 0,
@@ -2362,7 +3397,7 @@ impl App {
         self.loading_label = Some("Loading trash...".into());
         let client = Arc::clone(&self.client);
         let tx = self.result_tx.clone();
-        std::thread::spawn(move || {
+        self.workers.spawn(move || {
             let _ = tx.send(OpResult::TrashList(client.ls_trash(200)));
         });
     }
@@ -2431,29 +3466,11 @@ impl App {
                     expanded: new_expanded,
                 };
             }
-            KeyCode::Char('u') => {
-                if let Some(entry) = entries.get(*selected) {
-                    let client = Arc::clone(&self.client);
-                    let tx = self.result_tx.clone();
-                    let eid = entry.id.clone();
-                    let name = entry.name.clone();
-                    self.trash_entries = std::mem::take(entries);
-                    self.trash_selected = *selected;
-                    self.trash_expanded = expanded;
-                    self.input = InputMode::TrashView {
-                        entries: self.trash_entries.clone(),
-                        selected: *selected,
-                        expanded,
-                    };
-                    self.loading = true;
-                    self.loading_label = Some("Restoring...".into());
-                    std::thread::spawn(move || {
-                        let _ = tx.send(match client.untrash(&[eid.as_str()]) {
-                            Ok(()) => OpResult::TrashOp(format!("Restored '{}'", name)),
-                            Err(e) => OpResult::TrashOp(format!("Untrash failed: {e:#}")),
-                        });
-                    });
-                    return;
+            KeyCode::Char('a') => {
+                if let Some(entry) = entries.get(*selected)
+                    && !self.trash_marked.remove(&entry.id)
+                {
+                    self.trash_marked.insert(entry.id.clone());
                 }
                 self.input = InputMode::TrashView {
                     entries: std::mem::take(entries),
@@ -2461,35 +3478,67 @@ impl App {
                     expanded,
                 };
             }
-            KeyCode::Char('x') => {
-                if let Some(entry) = entries.get(*selected) {
-                    let client = Arc::clone(&self.client);
-                    let tx = self.result_tx.clone();
-                    let eid = entry.id.clone();
-                    let name = entry.name.clone();
-                    self.trash_entries = std::mem::take(entries);
-                    self.trash_selected = *selected;
-                    self.trash_expanded = expanded;
-                    self.input = InputMode::TrashView {
-                        entries: self.trash_entries.clone(),
-                        selected: *selected,
-                        expanded,
+            KeyCode::Char('A') => {
+                if entries.iter().all(|e| self.trash_marked.contains(&e.id)) {
+                    self.trash_marked.clear();
+                } else {
+                    self.trash_marked
+                        .extend(entries.iter().map(|e| e.id.clone()));
+                }
+                self.input = InputMode::TrashView {
+                    entries: std::mem::take(entries),
+                    selected: *selected,
+                    expanded,
+                };
+            }
+            KeyCode::Char(c @ ('u' | 'x')) => {
+                let targets = self.trash_targets(entries, *selected);
+                self.trash_entries = std::mem::take(entries);
+                self.trash_selected = *selected;
+                self.trash_expanded = expanded;
+                if targets.is_empty() {
+                    self.reopen_trash_view();
+                } else if c == 'u' {
+                    self.run_trash_op(targets, true);
+                } else {
+                    self.input = InputMode::ConfirmPurge {
+                        targets,
+                        value: String::new(),
+                    };
+                }
+            }
+            KeyCode::Char('U') => {
+                let targets = self.trash_targets(entries, *selected);
+                self.trash_entries = std::mem::take(entries);
+                self.trash_selected = *selected;
+                self.trash_expanded = expanded;
+                let picker = if targets.is_empty() {
+                    None
+                } else {
+                    self.build_picker_state()
+                };
+                match picker {
+                    Some(picker) => self.input = InputMode::RestorePicker { targets, picker },
+                    None => {
+                        self.input = InputMode::TrashView {
+                            entries: self.trash_entries.clone(),
+                            selected: self.trash_selected,
+                            expanded,
+                        }
+                    }
+                }
+            }
+            KeyCode::Char('E') => {
+                self.trash_entries = std::mem::take(entries);
+                self.trash_selected = *selected;
+                self.trash_expanded = expanded;
+                if self.trash_entries.is_empty() {
+                    self.open_trash_view_preserve();
+                } else {
+                    self.input = InputMode::ConfirmEmptyTrash {
+                        value: String::new(),
                     };
-                    self.loading = true;
-                    self.loading_label = Some("Deleting...".into());
-                    std::thread::spawn(move || {
-                        let _ = tx.send(match client.delete_permanent(&[eid.as_str()]) {
-                            Ok(()) => OpResult::TrashOp(format!("Permanently deleted '{}'", name)),
-                            Err(e) => OpResult::TrashOp(format!("Permanent delete failed: {e:#}")),
-                        });
-                    });
-                    return;
                 }
-                self.input = InputMode::TrashView {
-                    entries: std::mem::take(entries),
-                    selected: *selected,
-                    expanded,
-                };
             }
             KeyCode::Char(' ') => {
                 if let Some(entry) = entries.get(*selected).cloned() {
@@ -2503,6 +3552,7 @@ impl App {
                             crate::pikpak::EntryKind::Folder => "drive#folder".to_string(),
                             crate::pikpak::EntryKind::File => "drive#file".to_string(),
                         }),
+                        parent_id: None,
                         size: if entry.size > 0 {
                             Some(entry.size.to_string())
                         } else {
@@ -2524,6 +3574,7 @@ impl App {
                         thumbnail_link: entry.thumbnail_link,
                         links: None,
                         medias: None,
+                        trashed: true,
                     };
                     let thumb_url = info.thumbnail_link.clone().filter(|u| !u.is_empty());
                     let has_thumbnail = thumb_url.is_some();
@@ -2531,6 +3582,7 @@ impl App {
                         info,
                         image: None,
                         has_thumbnail,
+                        archive: None,
                     };
                     if let Some(url) = thumb_url {
                         self.spawn_thumbnail_fetch(url, super::OpResult::InfoThumbnail);
@@ -2557,6 +3609,122 @@ impl App {
         }
     }
 
+    /// Folder picker for restoring trash items somewhere other than where
+    /// they were deleted from. Esc goes back to the trash view.
+    fn handle_restore_picker_key(
+        &mut self,
+        code: KeyCode,
+        targets: Vec<(String, String)>,
+        picker: &mut PickerState,
+    ) {
+        match self.apply_picker_key(code, picker) {
+            PickerKeyResult::Confirmed(dest_id) => {
+                let dest = Self::picker_path_display(picker);
+                let what = match targets.as_slice() {
+                    [(_, name)] => format!("'{}'", name),
+                    many => format!("{} items", many.len()),
+                };
+                self.input = InputMode::TrashView {
+                    entries: self.trash_entries.clone(),
+                    selected: self.trash_selected,
+                    expanded: self.trash_expanded,
+                };
+                self.loading = true;
+                self.loading_label = Some("Restoring...".into());
+                let client = Arc::clone(&self.client);
+                let tx = self.result_tx.clone();
+                self.workers.spawn(move || {
+                    let ids: Vec<&str> = targets.iter().map(|(id, _)| id.as_str()).collect();
+                    let msg = match client.untrash_to(&ids, &dest_id) {
+                        Ok(()) => format!("Restored {what} to {dest}"),
+                        Err(e) => format!("Restore to {dest} failed: {e:#}"),
+                    };
+                    let _ = tx.send(OpResult::TrashOp(msg));
+                });
+            }
+            PickerKeyResult::Cancelled => {
+                self.input = InputMode::TrashView {
+                    entries: self.trash_entries.clone(),
+                    selected: self.trash_selected,
+                    expanded: self.trash_expanded,
+                };
+            }
+            PickerKeyResult::ShowHelp => {
+                self.show_help_sheet = true;
+                let picker = std::mem::take(picker);
+                self.input = InputMode::RestorePicker { targets, picker };
+            }
+            PickerKeyResult::Navigated | PickerKeyResult::SwitchToTextInput => {
+                let picker = std::mem::take(picker);
+                self.input = InputMode::RestorePicker { targets, picker };
+            }
+        }
+    }
+
+    /// Back to the trash view as it was left.
+    fn reopen_trash_view(&mut self) {
+        self.input = InputMode::TrashView {
+            entries: self.trash_entries.clone(),
+            selected: self.trash_selected,
+            expanded: self.trash_expanded,
+        };
+    }
+
+    /// Restore `targets`, or permanently delete them, from the trash view.
+    fn run_trash_op(&mut self, targets: Vec<(String, String)>, restore: bool) {
+        self.reopen_trash_view();
+        self.loading = true;
+        self.loading_label = Some(
+            if restore {
+                "Restoring..."
+            } else {
+                "Deleting..."
+            }
+            .into(),
+        );
+        let what = match targets.as_slice() {
+            [(_, name)] => format!("'{}'", name),
+            many => format!("{} items", many.len()),
+        };
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        self.workers.spawn(move || {
+            let ids: Vec<&str> = targets.iter().map(|(id, _)| id.as_str()).collect();
+            let msg = if restore {
+                match client.untrash(&ids) {
+                    Ok(()) => format!("Restored {}", what),
+                    Err(e) if crate::pikpak::is_parent_missing(&e) => format!(
+                        "Can't restore {what}: the folder it was in no longer exists. Press U to restore it elsewhere"
+                    ),
+                    Err(e) => format!("Untrash failed: {e:#}"),
+                }
+            } else {
+                match client.delete_permanent(&ids) {
+                    Ok(()) => format!("Permanently deleted {}", what),
+                    Err(e) => format!("Permanent delete failed: {e:#}"),
+                }
+            };
+            let _ = tx.send(OpResult::TrashOp(msg));
+        });
+    }
+
+    /// Items a trash action applies to: the marked ones, or else the one
+    /// under the cursor. Returns (id, name) pairs.
+    fn trash_targets(&self, entries: &[Entry], selected: usize) -> Vec<(String, String)> {
+        let marked: Vec<(String, String)> = entries
+            .iter()
+            .filter(|e| self.trash_marked.contains(&e.id))
+            .map(|e| (e.id.clone(), e.name.clone()))
+            .collect();
+        if !marked.is_empty() {
+            return marked;
+        }
+        entries
+            .get(selected)
+            .map(|e| vec![(e.id.clone(), e.name.clone())])
+            .unwrap_or_default()
+    }
+
     fn open_trash_view_preserve_expanded(&mut self) {
         self.input = InputMode::TrashView {
             entries: self.trash_entries.clone(),
@@ -2567,7 +3735,7 @@ impl App {
         self.loading_label = Some("Loading trash...".into());
         let client = Arc::clone(&self.client);
         let tx = self.result_tx.clone();
-        std::thread::spawn(move || {
+        self.workers.spawn(move || {
             let _ = tx.send(OpResult::TrashList(client.ls_trash(200)));
         });
     }
@@ -2580,8 +3748,15 @@ impl App {
         let tx = self.result_tx.clone();
         let eid = entry.id.clone();
         let thumb_fallback = entry.thumbnail_link.clone();
-        std::thread::spawn(move || {
-            let _ = tx.send(OpResult::Info(client.file_info(&eid), thumb_fallback));
+        let cancel = self.preview_cancel.renew();
+        self.workers.spawn(move || {
+            if cancel.is_cancelled() {
+                return;
+            }
+            let result = client.file_info(&eid);
+            if !cancel.is_cancelled() {
+                let _ = tx.send(OpResult::Info(result, thumb_fallback));
+            }
         });
     }
 
@@ -2594,22 +3769,133 @@ impl App {
         let client = Arc::clone(&self.client);
         let tx = self.result_tx.clone();
         let eid = entry.id.clone();
-        std::thread::spawn(move || {
-            let _ = tx.send(OpResult::PreviewLs(eid.clone(), client.ls(&eid)));
+        let cancel = self.preview_cancel.renew();
+        self.workers.spawn(move || {
+            if cancel.is_cancelled() {
+                return;
+            }
+            let result = client.ls_streaming(&eid, |_| super::continue_unless(&cancel));
+            if !cancel.is_cancelled() {
+                let _ = tx.send(OpResult::PreviewLs(eid.clone(), result));
+            }
         });
     }
 
-    fn spawn_player(&mut self, cmd: &str, url: &str) {
-        let parts: Vec<&str> = cmd.split_whitespace().collect();
-        if parts.is_empty() {
+    /// What to give the player for `opt`: a local proxy address when
+    /// `player_proxy` is on (falling back to the link itself if the proxy
+    /// can't start), otherwise PikPak's link. The proxy comes along so the
+    /// player can keep it running.
+    fn playback_url(
+        &mut self,
+        file_id: &str,
+        opt: &PlayOption,
+        name: &str,
+    ) -> (String, Option<StreamProxy>) {
+        if !self.config.player_proxy || file_id.is_empty() {
+            return (opt.url.clone(), None);
+        }
+        match self
+            .client
+            .proxy_stream(file_id, opt.media.as_deref(), name, opt.url.clone())
+        {
+            Ok(proxy) => (proxy.url.clone(), Some(proxy)),
+            Err(e) => {
+                self.push_log(format!(
+                    "Stream proxy unavailable, using the direct link: {e:#}"
+                ));
+                (opt.url.clone(), None)
+            }
+        }
+    }
+
+    /// Open `url`, the stream of `file_id`, in the configured player, or ask
+    /// for one first.
+    fn play_url(
+        &mut self,
+        file_id: &str,
+        name: &str,
+        (url, proxy): (String, Option<StreamProxy>),
+        subs: &subtitles::Subtitles,
+    ) {
+        let subs = subs.urls();
+        if let Some(player) = self.config.player.clone() {
+            self.spawn_player(&player, (&url, proxy), &subs, (file_id, name));
+        } else {
+            self.input = InputMode::PlayerInput {
+                value: String::new(),
+                file_id: file_id.to_string(),
+                name: name.to_string(),
+                pending_url: url,
+                proxy,
+                subs,
+            };
+        }
+    }
+
+    /// Launch `cmd` on `url`, keeping its stream proxy, if any, until the
+    /// player exits. `file` is the drive video's id and name, to track how
+    /// far it's watched (an empty id for a local file): mpv is followed over
+    /// its IPC socket and resumed where it was left.
+    pub(super) fn spawn_player(
+        &mut self,
+        cmd: &str,
+        (url, proxy): (&str, Option<StreamProxy>),
+        subs: &[String],
+        file: (&str, &str),
+    ) {
+        let mut args = crate::local_path::split_command(cmd);
+        if args.is_empty() {
             self.push_log("Player command is empty".into());
             return;
         }
-        let program = parts[0];
-        let mut args: Vec<&str> = parts[1..].to_vec();
-        args.push("--");
-        args.push(url);
-        match std::process::Command::new(program).args(&args).spawn() {
+        let program = args.remove(0);
+        let program = program.as_str();
+        let mut with_subs = 0;
+        if !subs.is_empty() {
+            match subtitles::player_args(program, subs) {
+                Some(sub_args) => {
+                    with_subs = sub_args.len();
+                    args.extend(sub_args);
+                }
+                None => self.push_log(format!(
+                    "Subtitles not passed: no known subtitle option for {}",
+                    program
+                )),
+            }
+        }
+        let tracked = !file.0.is_empty();
+        let socket = (tracked && playback::is_mpv(program)).then(playback::mpv_socket);
+        if let Some(ref socket) = socket {
+            args.push(format!("--input-ipc-server={socket}"));
+            if let Some(at) = self.resume_position(file.0) {
+                args.push(format!("--start={at:.0}"));
+                self.push_log(format!(
+                    "Resuming at {}",
+                    crate::ratelimit::format_duration(at as u64)
+                ));
+            }
+        }
+        args.push("--".into());
+        args.push(url.into());
+        let launched = std::process::Command::new(program).args(&args).spawn();
+        if tracked && launched.is_ok() {
+            self.track_playback(file.0, file.1, socket);
+        }
+        let launched = launched.map(|mut player| {
+            if let Some(proxy) = proxy {
+                std::thread::spawn(move || {
+                    let _ = player.wait();
+                    drop(proxy);
+                });
+            }
+        });
+        match launched {
+            Ok(_) if with_subs > 0 => {
+                self.push_log(format!(
+                    "Launched {} with video URL and {} subtitle(s)",
+                    program, with_subs
+                ));
+            }
             Ok(_) => {
                 self.push_log(format!("Launched {} with video URL", program));
             }
@@ -2620,16 +3906,9 @@ impl App {
     }
 
     pub(super) fn spawn_delete(&mut self, entry: Entry) {
-        let client = Arc::clone(&self.client);
-        let tx = self.result_tx.clone();
-        let eid = entry.id.clone();
-        let name = entry.name.clone();
-        self.loading = true;
-        std::thread::spawn(move || {
-            let _ = tx.send(match client.remove(&[eid.as_str()]) {
-                Ok(()) => OpResult::Ok(format!("Removed '{}' (to trash)", name)),
-                Err(e) => OpResult::Err(format!("Remove failed: {e:#}")),
-            });
+        self.queue_op(FileOp::Trash {
+            what: ops::describe(std::slice::from_ref(&entry)),
+            ids: vec![entry.id],
         });
     }
 
@@ -2710,6 +3989,11 @@ impl App {
                         lines.len().saturating_sub(visible)
                     }
                     PreviewState::FolderListing(children) => children.len().saturating_sub(visible),
+                    // One row goes to the summary line.
+                    PreviewState::ArchiveListing(listing) => listing
+                        .entries
+                        .len()
+                        .saturating_sub(visible.saturating_sub(1)),
                     _ => 0,
                 };
                 if up {
@@ -2738,7 +4022,7 @@ impl App {
                 self.cart_selected = (self.cart_selected + 1).min(self.cart.len() - 1);
             }
         } else if matches!(self.input, InputMode::DownloadView) {
-            let count = self.download_state.tasks.len();
+            let count = self.download_state.rows().len();
             if up {
                 if self.download_state.selected > 0 {
                     self.download_state.selected -= 1;
@@ -2827,6 +4111,7 @@ impl App {
                                 6 => draft.lazy_preview = !draft.lazy_preview,
                                 11 => draft.sort_reverse = !draft.sort_reverse,
                                 13 => draft.cli_nerd_font = !draft.cli_nerd_font,
+                                14 => draft.commander_mode = !draft.commander_mode,
                                 _ => {}
                             }
                             modified = true;
@@ -2853,15 +4138,33 @@ impl App {
         let parent_area = self.parent_pane_area.get();
         let preview_area = self.preview_pane_area.get();
 
-        if self.is_in_rect(col, row, current_area) {
-            let content_y = row.saturating_sub(current_area.y + 1) as usize;
+        // A click in the other commander pane moves the focus there.
+        if self.config.commander_mode && self.is_in_rect(col, row, self.idle_pane_area.get()) {
+            self.switch_tab(self.idle_pane_index());
+            return;
+        }
+
+        if self.is_in_rect(col, row, current_area) && row == current_area.y + 1 {
+            let active = self.config.sort_field;
+            let reverse = self.config.sort_reverse;
+            let labels: Vec<String> = widgets::SORT_HEADER_FIELDS
+                .iter()
+                .map(|&f| widgets::sort_header_label(f, active, reverse))
+                .collect();
+            let x = col.saturating_sub(current_area.x + 1) as usize;
+            if let Some(idx) = widgets::sort_header_hit(&labels, x) {
+                self.sort_by_column(widgets::SORT_HEADER_FIELDS[idx]);
+            }
+        } else if self.is_in_rect(col, row, current_area) {
+            // Skip the top border and the sort header row.
+            let content_y = row.saturating_sub(current_area.y + 2) as usize;
             let offset = self.scroll_offset.get();
             let clicked_idx = offset + content_y;
             if clicked_idx < self.entries.len() {
                 self.selected = clicked_idx;
                 self.on_cursor_move();
                 if double {
-                    let _ = self.handle_normal_key(KeyCode::Enter, KeyModifiers::NONE);
+                    let _ = self.run_normal_action(Action::Open);
                 }
             }
         } else if self.is_in_rect(col, row, parent_area) {
@@ -2871,13 +4174,13 @@ impl App {
             if clicked_idx < self.parent_entries.len() {
                 self.parent_selected = clicked_idx;
                 if double {
-                    let _ = self.handle_normal_key(KeyCode::Backspace, KeyModifiers::NONE);
+                    let _ = self.run_normal_action(Action::Back);
                     let is_folder = self
                         .entries
                         .get(self.selected)
                         .is_some_and(|e| e.kind == EntryKind::Folder);
                     if is_folder {
-                        let _ = self.handle_normal_key(KeyCode::Enter, KeyModifiers::NONE);
+                        let _ = self.run_normal_action(Action::Open);
                     }
                 }
             }
@@ -2889,9 +4192,9 @@ impl App {
             let has_entry = self.selected < self.entries.len();
             if has_entry {
                 if is_folder {
-                    let _ = self.handle_normal_key(KeyCode::Enter, KeyModifiers::NONE);
+                    let _ = self.run_normal_action(Action::Open);
                 } else {
-                    let _ = self.handle_normal_key(KeyCode::Char(' '), KeyModifiers::NONE);
+                    let _ = self.run_normal_action(Action::Info);
                 }
             }
         }
@@ -2907,7 +4210,7 @@ impl App {
         let eid = entry.id.clone();
         let name = entry.name.clone();
         self.loading = true;
-        std::thread::spawn(move || {
+        self.workers.spawn(move || {
             let _ = tx.send(match client.delete_permanent(&[eid.as_str()]) {
                 Ok(()) => OpResult::Ok(format!("Permanently deleted '{}'", name)),
                 Err(e) => OpResult::Err(format!("Permanent delete failed: {e:#}")),
@@ -2987,7 +4290,10 @@ impl App {
             KeyCode::Char('s') => {
                 if *modified {
                     match draft.save() {
-                        Ok(()) => {
+                        Ok(warnings) => {
+                            for warning in warnings {
+                                self.push_log(format!("Warning: {warning}"));
+                            }
                             self.config = draft.clone();
                             self.push_log("Image protocol settings saved to config.toml".into());
                             self.input = InputMode::Settings {
@@ -3163,7 +4469,10 @@ impl App {
                 KeyCode::Char('s') => {
                     if *modified {
                         match draft.save() {
-                            Ok(()) => {
+                            Ok(warnings) => {
+                                for warning in warnings {
+                                    self.push_log(format!("Warning: {warning}"));
+                                }
                                 self.config = draft.clone();
                                 self.push_log("Custom colors saved to config.toml".into());
                                 self.input = InputMode::Settings {
@@ -3450,6 +4759,17 @@ impl App {
                     _ => {}
                 },
                 14 => match code {
+                    KeyCode::Char(' ') | KeyCode::Enter | KeyCode::Left | KeyCode::Right => {
+                        draft.commander_mode = !draft.commander_mode;
+                        *modified = true;
+                        *editing = false;
+                    }
+                    KeyCode::Esc => {
+                        *editing = false;
+                    }
+                    _ => {}
+                },
+                15 => match code {
                     KeyCode::Esc => {
                         *editing = false;
                     }
@@ -3474,7 +4794,7 @@ impl App {
                     }
                     _ => {}
                 },
-                15 => match code {
+                16 => match code {
                     KeyCode::Char('+') | KeyCode::Up | KeyCode::Right => {
                         draft.download_jobs = (draft.download_jobs + 1).min(16);
                         *modified = true;
@@ -3488,7 +4808,39 @@ impl App {
                     }
                     _ => {}
                 },
-                16 => match code {
+                17 => match code {
+                    KeyCode::Char('+') | KeyCode::Up | KeyCode::Right => {
+                        draft.max_download_speed =
+                            crate::ratelimit::step_speed(draft.max_download_speed, true);
+                        *modified = true;
+                    }
+                    KeyCode::Char('-') | KeyCode::Down | KeyCode::Left => {
+                        draft.max_download_speed =
+                            crate::ratelimit::step_speed(draft.max_download_speed, false);
+                        *modified = true;
+                    }
+                    KeyCode::Enter | KeyCode::Esc => {
+                        *editing = false;
+                    }
+                    _ => {}
+                },
+                18 => match code {
+                    KeyCode::Char('+') | KeyCode::Up | KeyCode::Right => {
+                        draft.max_upload_speed =
+                            crate::ratelimit::step_speed(draft.max_upload_speed, true);
+                        *modified = true;
+                    }
+                    KeyCode::Char('-') | KeyCode::Down | KeyCode::Left => {
+                        draft.max_upload_speed =
+                            crate::ratelimit::step_speed(draft.max_upload_speed, false);
+                        *modified = true;
+                    }
+                    KeyCode::Enter | KeyCode::Esc => {
+                        *editing = false;
+                    }
+                    _ => {}
+                },
+                19 => match code {
                     KeyCode::Right | KeyCode::Char('+') | KeyCode::Char('l') => {
                         draft.update_check = draft.update_check.next();
                         *modified = true;
@@ -3502,6 +4854,64 @@ impl App {
                     }
                     _ => {}
                 },
+                20 => match code {
+                    KeyCode::Right | KeyCode::Char(' ') => {
+                        draft.size_units = draft.size_units.next();
+                        *modified = true;
+                    }
+                    KeyCode::Left => {
+                        draft.size_units = draft.size_units.prev();
+                        *modified = true;
+                    }
+                    KeyCode::Enter | KeyCode::Esc => {
+                        *editing = false;
+                    }
+                    _ => {}
+                },
+                21 => match code {
+                    KeyCode::Enter | KeyCode::Esc => {
+                        if draft.date_format.is_empty() {
+                            draft.date_format = crate::config::DEFAULT_DATE_FORMAT.to_string();
+                        }
+                        *editing = false;
+                    }
+                    KeyCode::Backspace => {
+                        draft.date_format.pop();
+                        *modified = true;
+                    }
+                    KeyCode::Char(c) => {
+                        draft.date_format.push(c);
+                        *modified = true;
+                    }
+                    _ => {}
+                },
+                22 => match code {
+                    KeyCode::Char('+') | KeyCode::Up | KeyCode::Right => {
+                        let gib = (draft.large_file_threshold >> 30) + 1;
+                        draft.large_file_threshold = gib.min(1024) << 30;
+                        *modified = true;
+                    }
+                    KeyCode::Char('-') | KeyCode::Down | KeyCode::Left => {
+                        let gib = (draft.large_file_threshold >> 30).saturating_sub(1);
+                        draft.large_file_threshold = gib << 30;
+                        *modified = true;
+                    }
+                    KeyCode::Enter | KeyCode::Esc => {
+                        *editing = false;
+                    }
+                    _ => {}
+                },
+                KEYMAP_SETTINGS_INDEX => {
+                    if matches!(code, KeyCode::Enter | KeyCode::Char(' ')) {
+                        self.input = InputMode::KeymapView {
+                            scroll: 0,
+                            draft: draft.clone(),
+                            modified: *modified,
+                        };
+                        return None;
+                    }
+                    *editing = false;
+                }
                 _ => {}
             }
             None
@@ -3533,6 +4943,14 @@ impl App {
                         };
                         return None;
                     }
+                    if *selected == KEYMAP_SETTINGS_INDEX {
+                        self.input = InputMode::KeymapView {
+                            scroll: 0,
+                            draft: draft.clone(),
+                            modified: *modified,
+                        };
+                        return None;
+                    }
                     *editing = true;
                     None
                 }
@@ -3550,6 +4968,112 @@ impl App {
     }
 }
 
+/// The most recent offline tasks in every phase, as the tasks view shows them.
+fn fetch_offline_tasks(client: &PikPak) -> Result<Vec<OfflineTask>> {
+    let phases = &[
+        "PHASE_TYPE_RUNNING",
+        "PHASE_TYPE_PENDING",
+        "PHASE_TYPE_COMPLETE",
+        "PHASE_TYPE_ERROR",
+    ];
+    client.offline_list(50, phases).map(|r| r.tasks)
+}
+
+/// Save the PNG image on the system clipboard to `path`. Like
+/// `write_clipboard`, this goes through the platform tools rather than
+/// talking to the display server.
+fn read_clipboard_image(path: &std::path::Path) -> anyhow::Result<()> {
+    use std::process::Command;
+
+    if cfg!(target_os = "macos") {
+        let out = Command::new("osascript")
+            .args(["-e", "the clipboard as «class PNGf»"])
+            .output()?;
+        if !out.status.success() {
+            return Err(anyhow::anyhow!("clipboard doesn't hold an image"));
+        }
+        std::fs::write(
+            path,
+            parse_osascript_png(&String::from_utf8_lossy(&out.stdout))?,
+        )?;
+        return Ok(());
+    }
+
+    if cfg!(windows) {
+        // Windows PowerShell ships with Windows, and its clipboard needs STA.
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             $i = [Windows.Forms.Clipboard]::GetImage(); if (-not $i) {{ exit 1 }}; \
+             $i.Save('{}', [Drawing.Imaging.ImageFormat]::Png)",
+            path.display().to_string().replace('\'', "''")
+        );
+        let out = Command::new("powershell")
+            .args(["-NoProfile", "-STA", "-Command", &script])
+            .output()
+            .map_err(|_| anyhow::anyhow!("no clipboard tool found (powershell)"))?;
+        if !out.status.success() {
+            return Err(anyhow::anyhow!("clipboard doesn't hold an image"));
+        }
+        return Ok(());
+    }
+
+    let candidates: &[(&str, &[&str])] = &[
+        ("wl-paste", &["--no-newline", "--type", "image/png"]),
+        (
+            "xclip",
+            &["-selection", "clipboard", "-t", "image/png", "-o"],
+        ),
+    ];
+    let outputs = candidates.iter().map(|&(cmd, args)| {
+        Command::new(cmd)
+            .args(args)
+            .output()
+            .ok()
+            .map(|out| (out.status.success(), out.stdout))
+    });
+    std::fs::write(path, first_png(outputs)?)?;
+    Ok(())
+}
+
+/// The PNG in osascript's `«data PNGf<hex>»` rendering of the clipboard.
+fn parse_osascript_png(text: &str) -> anyhow::Result<Vec<u8>> {
+    let hex = text
+        .trim()
+        .strip_prefix("«data PNGf")
+        .and_then(|h| h.strip_suffix('»'))
+        .ok_or_else(|| anyhow::anyhow!("clipboard doesn't hold an image"))?;
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|b| u8::from_str_radix(b, 16).ok())
+                .ok_or_else(|| anyhow::anyhow!("unexpected osascript output"))
+        })
+        .collect()
+}
+
+/// The first PNG among clipboard tools' outputs, tried in turn: whether the
+/// tool succeeded and what it printed, or `None` when it isn't installed. A
+/// tool that fails, such as wl-paste outside a Wayland session, falls
+/// through to the next.
+fn first_png(outputs: impl Iterator<Item = Option<(bool, Vec<u8>)>>) -> anyhow::Result<Vec<u8>> {
+    let mut ran = false;
+    for (ok, stdout) in outputs.flatten() {
+        // The tools exit non-zero when there's no PNG target on offer.
+        if ok && stdout.starts_with(b"\x89PNG") {
+            return Ok(stdout);
+        }
+        ran = true;
+    }
+    if ran {
+        Err(anyhow::anyhow!("clipboard doesn't hold an image"))
+    } else {
+        Err(anyhow::anyhow!(
+            "no clipboard tool found (wl-paste / xclip)"
+        ))
+    }
+}
+
 /// Write `text` to the system clipboard using the best available tool.
 fn write_clipboard(text: &str) -> anyhow::Result<()> {
     use std::io::Write;
@@ -3557,6 +5081,8 @@ fn write_clipboard(text: &str) -> anyhow::Result<()> {
 
     let candidates: &[(&str, &[&str])] = if cfg!(target_os = "macos") {
         &[("pbcopy", &[] as &[&str])]
+    } else if cfg!(windows) {
+        &[("clip", &[] as &[&str])]
     } else {
         &[
             ("wl-copy", &[] as &[&str]),
@@ -3576,6 +5102,175 @@ fn write_clipboard(text: &str) -> anyhow::Result<()> {
     }
 
     Err(anyhow::anyhow!(
-        "no clipboard tool found (pbcopy / wl-copy / xclip)"
+        "no clipboard tool found (pbcopy / clip / wl-copy / xclip)"
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn navigating_cached_folders_leaves_nothing_loading() {
+        let mut app = App::for_tests();
+        app.entries = vec![Entry::folder("Movies").with_id("a")];
+        // The root's own listing is still in flight.
+        app.loading = true;
+        app.preview_target_id = Some("a".into());
+        app.preview_state = PreviewState::FolderListing(vec![Entry::folder("2024").with_id("b")]);
+
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.current_folder_id, "a");
+        assert_eq!(app.entries[0].id, "b");
+        assert!(!app.loading);
+
+        app.loading = true;
+        app.handle_key(KeyCode::Backspace, KeyModifiers::NONE)
+            .unwrap();
+        assert_eq!(app.current_folder_id, "");
+        assert_eq!(app.entries[0].id, "a");
+        assert!(!app.loading);
+    }
+
+    #[test]
+    fn tab_switches_panes_in_commander_mode_and_inverts_the_cart_otherwise() {
+        let mut app = App::for_tests();
+        app.entries = vec![Entry::file("a.txt").with_id("a")];
+        app.config.commander_mode = true;
+        app.ensure_commander_panes();
+
+        app.handle_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.active_tab, 1);
+        app.handle_key(KeyCode::BackTab, KeyModifiers::SHIFT)
+            .unwrap();
+        assert_eq!(app.active_tab, 0);
+        assert!(app.cart.is_empty());
+
+        app.config.commander_mode = false;
+        app.entries = vec![Entry::file("a.txt").with_id("a")];
+        app.handle_key(KeyCode::Tab, KeyModifiers::NONE).unwrap();
+        assert_eq!(app.active_tab, 0);
+        assert_eq!(app.cart.len(), 1);
+    }
+
+    // `W` waits on uploads_running, so every upload that reports back must
+    // have counted itself in.
+    #[test]
+    fn clipboard_uploads_count_as_running() {
+        let mut app = App::for_tests();
+        app.upload_clipboard_image();
+        assert_eq!(app.uploads_running, 1);
+
+        let deadline = Instant::now() + Duration::from_secs(30);
+        while app.uploads_running > 0 && Instant::now() < deadline {
+            app.poll_results();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(app.uploads_running, 0);
+    }
+
+    #[test]
+    fn clipboard_images_fall_through_to_the_next_tool() {
+        let png = b"\x89PNG\r\n".to_vec();
+        // wl-paste outside Wayland fails; xclip still has the image.
+        let outputs = vec![Some((false, vec![])), Some((true, png.clone()))];
+        assert_eq!(first_png(outputs.into_iter()).unwrap(), png);
+        let outputs = vec![None, Some((true, png.clone()))];
+        assert_eq!(first_png(outputs.into_iter()).unwrap(), png);
+
+        let text = |outputs: Vec<Option<(bool, Vec<u8>)>>| {
+            first_png(outputs.into_iter()).unwrap_err().to_string()
+        };
+        assert!(text(vec![Some((true, b"hello".to_vec())), None]).contains("doesn't hold"));
+        assert!(text(vec![None, None]).contains("no clipboard tool"));
+    }
+
+    #[test]
+    fn osascript_clipboard_images_decode_from_hex() {
+        assert_eq!(
+            parse_osascript_png("«data PNGf89504E47»\n").unwrap(),
+            b"\x89PNG"
+        );
+        assert!(parse_osascript_png("some text").is_err());
+        assert!(parse_osascript_png("«data PNGf8Z»").is_err());
+    }
+
+    #[test]
+    fn deleting_from_the_trash_asks_first() {
+        let mut app = App::for_tests();
+        app.input = InputMode::TrashView {
+            entries: vec![Entry::file("old.mkv").with_id("t1")],
+            selected: 0,
+            expanded: false,
+        };
+        app.handle_key(KeyCode::Char('x'), KeyModifiers::NONE)
+            .unwrap();
+        assert!(matches!(
+            &app.input,
+            InputMode::ConfirmPurge { targets, .. } if targets[0].0 == "t1"
+        ));
+
+        for c in "no".chars() {
+            app.handle_key(KeyCode::Char(c), KeyModifiers::NONE)
+                .unwrap();
+        }
+        app.handle_key(KeyCode::Enter, KeyModifiers::NONE).unwrap();
+        assert!(matches!(app.input, InputMode::TrashView { .. }));
+        assert!(!app.loading);
+    }
+
+    #[test]
+    fn only_colon_names_open_views_from_goto() {
+        let mut app = App::for_tests();
+        assert!(!app.open_goto_view("Downloads"));
+        assert!(matches!(app.input, InputMode::Normal));
+        assert!(app.open_goto_view(" :downloads "));
+        assert!(matches!(app.input, InputMode::DownloadView));
+    }
+
+    #[test]
+    fn auto_fetched_tasks_leave_the_list_only_once_downloaded() {
+        let mut app = App::for_tests();
+        let dir = std::env::temp_dir().join(format!("pikpaktui-auto-fetch-{}", std::process::id()));
+        app.config.auto_fetch_dir = Some(dir.to_string_lossy().into_owned());
+        // Keep the queue from starting real downloads.
+        app.download_state.window_open = false;
+        let task = |id: &str| -> OfflineTask {
+            serde_json::from_value(serde_json::json!({ "id": id, "name": id })).unwrap()
+        };
+        app.queue_auto_fetched(vec![
+            (
+                task("t1"),
+                vec![(Entry::file("a.mkv").with_id("f1"), "a.mkv".into())],
+            ),
+            (
+                task("t2"),
+                vec![
+                    (Entry::file("b.mkv").with_id("f2"), "b/b.mkv".into()),
+                    (Entry::file("b.srt").with_id("f3"), "b/b.srt".into()),
+                ],
+            ),
+        ]);
+        assert_eq!(app.download_state.tasks.len(), 3);
+
+        app.settle_auto_fetched();
+        assert_eq!(app.auto_fetched.len(), 2, "nothing has downloaded yet");
+
+        let set = |app: &mut App, file_id: &str, status: TaskStatus| {
+            let t = app.download_state.tasks.iter_mut();
+            t.filter(|t| t.file_id == file_id)
+                .for_each(|t| t.status = status.clone());
+        };
+        set(&mut app, "f1", TaskStatus::Done);
+        set(&mut app, "f2", TaskStatus::Done);
+        app.settle_auto_fetched();
+        assert!(!app.auto_fetched.contains_key("t1"));
+        assert!(app.auto_fetched.contains_key("t2"), "b.srt is still queued");
+        assert!(app.auto_fetch_failed.is_empty());
+
+        set(&mut app, "f3", TaskStatus::Failed("gone".into()));
+        app.settle_auto_fetched();
+        assert!(app.auto_fetched.is_empty());
+        assert!(app.auto_fetch_failed.contains("t2"));
+    }
+}
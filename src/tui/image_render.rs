@@ -1,5 +1,5 @@
 use ratatui::layout::Rect;
-use ratatui::style::{Color, Style};
+use ratatui::style::Style;
 use ratatui::text::{Line, Span};
 
 /// Upscale `img` so it fills at least `area` terminal cells (using `font_size` px/cell).
@@ -56,6 +56,7 @@ pub(super) fn render_image_to_colored_lines(
     img: &image::DynamicImage,
     max_width: u32,
     max_height: u32,
+    depth: crate::config::ColorDepth,
 ) -> Vec<Line<'static>> {
     use image::GenericImageView;
 
@@ -104,11 +105,17 @@ pub(super) fn render_image_to_colored_lines(
             let span = Span::styled(
                 "▀",
                 Style::default()
-                    .fg(Color::Rgb(top_pixel[0], top_pixel[1], top_pixel[2]))
-                    .bg(Color::Rgb(
+                    .fg(crate::theme::rgb(
+                        top_pixel[0],
+                        top_pixel[1],
+                        top_pixel[2],
+                        depth,
+                    ))
+                    .bg(crate::theme::rgb(
                         bottom_pixel[0],
                         bottom_pixel[1],
                         bottom_pixel[2],
+                        depth,
                     )),
             );
             spans.push(span);
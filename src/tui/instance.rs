@@ -0,0 +1,305 @@
+//! Coordination between TUI instances. Whichever holds the exclusive lock
+//! on `tui.lock` owns the download queue and listens on a Unix socket next
+//! to it; the others hand their downloads to it, and run them themselves
+//! only when it can't be reached. Every instance saves what it runs into
+//! the shared queue, and when the owner exits the next instance to notice
+//! takes the lock and the queue over.
+
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use super::download::{self, OnComplete};
+use super::{App, OpResult};
+
+/// How often a secondary instance checks whether the primary has exited.
+const TAKEOVER_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A download handed from one instance to another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct QueuedDownload {
+    pub file_id: String,
+    pub name: String,
+    pub total_size: u64,
+    pub dest_path: PathBuf,
+    /// Absent from what older versions send.
+    #[serde(default)]
+    pub on_complete: OnComplete,
+}
+
+pub(super) enum Role {
+    /// This instance owns the download queue. The lock is released on drop;
+    /// `None` when there's no config directory to keep it in.
+    Primary { _lock: Option<Lock> },
+    /// Another instance owns the queue; downloads are sent to it.
+    Secondary {
+        pid: Option<u32>,
+        socket: PathBuf,
+        /// When this instance last tried to take the queue over.
+        checked: Instant,
+    },
+}
+
+impl Role {
+    pub(super) fn is_primary(&self) -> bool {
+        matches!(self, Role::Primary { .. })
+    }
+}
+
+pub(super) struct Lock {
+    /// `None` without socket support or when it couldn't be bound.
+    socket: Option<PathBuf>,
+    /// `tui.lock`, locked for as long as this instance owns the queue and
+    /// holding its pid. It stays behind on exit; only the lock matters.
+    _file: std::fs::File,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if let Some(socket) = &self.socket {
+            let _ = std::fs::remove_file(socket);
+        }
+    }
+}
+
+/// Lock `tui.lock` in `dir` and write our pid into it. `None` when another
+/// instance holds it.
+fn lock_file(dir: &Path) -> std::io::Result<Option<std::fs::File>> {
+    use std::io::Write as _;
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join("tui.lock"))?;
+    match file.try_lock() {
+        Ok(()) => {}
+        Err(std::fs::TryLockError::WouldBlock) => return Ok(None),
+        Err(std::fs::TryLockError::Error(e)) => return Err(e),
+    }
+    file.set_len(0)?;
+    write!(file, "{}", std::process::id())?;
+    Ok(Some(file))
+}
+
+/// Whether a running instance owns the saved queue, reachable or not.
+pub(super) fn queue_in_use() -> bool {
+    let Some(path) = state_dir().map(|d| d.join("tui.lock")) else {
+        return false;
+    };
+    let Ok(file) = std::fs::OpenOptions::new().write(true).open(path) else {
+        return false;
+    };
+    matches!(file.try_lock(), Err(std::fs::TryLockError::WouldBlock))
+}
+
+fn state_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| crate::config::profile_dir(d.join("pikpaktui")))
+}
+
+/// Become the primary instance, or find the one that already is.
+pub(super) fn claim(tx: Sender<OpResult>) -> Role {
+    match state_dir() {
+        Some(dir) => claim_in(&dir, tx),
+        None => Role::Primary { _lock: None },
+    }
+}
+
+/// Take the lock in `dir`, or find who holds it. The lock goes first, so
+/// two instances starting together can't both end up owning the queue; one
+/// that can't be taken at all (say, on a filesystem without locks) leaves
+/// this instance running its own queue as before.
+fn claim_in(dir: &Path, tx: Sender<OpResult>) -> Role {
+    let _ = std::fs::create_dir_all(dir);
+    let socket = dir.join("tui.sock");
+    let file = match lock_file(dir) {
+        Ok(Some(file)) => file,
+        Ok(None) => {
+            let pid = std::fs::read_to_string(dir.join("tui.lock"))
+                .ok()
+                .and_then(|s| s.trim().parse().ok());
+            return Role::Secondary {
+                pid,
+                socket,
+                checked: Instant::now(),
+            };
+        }
+        Err(_) => return Role::Primary { _lock: None },
+    };
+    Role::Primary {
+        _lock: Some(Lock {
+            socket: listen(socket, tx),
+            _file: file,
+        }),
+    }
+}
+
+/// Serve `socket` for other instances. With the lock held, a socket already
+/// there was left by an instance that's gone.
+#[cfg(unix)]
+fn listen(socket: PathBuf, tx: Sender<OpResult>) -> Option<PathBuf> {
+    let _ = std::fs::remove_file(&socket);
+    let listener = std::os::unix::net::UnixListener::bind(&socket).ok()?;
+    std::thread::spawn(move || serve(listener, tx));
+    Some(socket)
+}
+
+/// Without sockets there's no handing downloads over; the lock still keeps
+/// one instance running the queue and tells `queue import` it's in use.
+#[cfg(not(unix))]
+fn listen(_socket: PathBuf, _tx: Sender<OpResult>) -> Option<PathBuf> {
+    None
+}
+
+impl App {
+    /// While another instance owns the queue, take it over once it exits:
+    /// its saved tasks are loaded after the ones running here.
+    pub(super) fn maybe_take_over(&mut self) {
+        let Role::Secondary { checked, .. } = &mut self.instance else {
+            return;
+        };
+        if checked.elapsed() < TAKEOVER_INTERVAL {
+            return;
+        }
+        *checked = Instant::now();
+        let Some(dir) = state_dir() else {
+            return;
+        };
+        let role = claim_in(&dir, self.result_tx.clone());
+        if !role.is_primary() {
+            return;
+        }
+        self.instance = role;
+        self.download_state
+            .load_tasks(download::load_download_state());
+        self.push_log(
+            "The other pikpaktui exited; this one runs the download queue now".to_string(),
+        );
+    }
+}
+
+/// The socket of the primary instance, if one is running and answers.
+#[cfg(unix)]
+pub(super) fn running() -> Option<PathBuf> {
+    let socket = state_dir()?.join("tui.sock");
+    std::os::unix::net::UnixStream::connect(&socket)
+        .ok()
+        .map(|_| socket)
+}
+
+#[cfg(not(unix))]
+pub(super) fn running() -> Option<PathBuf> {
+    None
+}
+
+/// Accept batches of downloads, one JSON line per connection, and answer
+/// `ok` once they're passed to the UI thread. A connection that closes
+/// without sending anything is just another instance checking we're alive.
+#[cfg(unix)]
+fn serve(listener: std::os::unix::net::UnixListener, tx: Sender<OpResult>) {
+    use std::io::{BufRead as _, BufReader, Write as _};
+
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else { continue };
+        let _ = stream.set_read_timeout(Some(std::time::Duration::from_secs(5)));
+        let mut line = String::new();
+        let Ok(mut reader) = stream.try_clone().map(BufReader::new) else {
+            continue;
+        };
+        if reader.read_line(&mut line).unwrap_or(0) == 0 {
+            continue;
+        }
+        let reply = match serde_json::from_str::<Vec<QueuedDownload>>(&line) {
+            Ok(batch) => {
+                // The UI thread is gone, so this instance is exiting.
+                if tx.send(OpResult::Delegated(batch)).is_err() {
+                    break;
+                }
+                "ok\n".to_string()
+            }
+            Err(e) => format!("error: {e}\n"),
+        };
+        let _ = stream.write_all(reply.as_bytes());
+    }
+}
+
+/// Hand `batch` to the primary instance listening on `socket`.
+#[cfg(unix)]
+pub(super) fn send(socket: &std::path::Path, batch: &[QueuedDownload]) -> Result<()> {
+    use std::io::{BufRead as _, BufReader, Write as _};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(std::time::Duration::from_secs(5)))?;
+    let mut line = serde_json::to_string(batch)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    match reply.trim() {
+        "ok" => Ok(()),
+        "" => Err(anyhow!("the other instance closed the connection")),
+        other => Err(anyhow!("the other instance refused: {other}")),
+    }
+}
+
+#[cfg(not(unix))]
+pub(super) fn send(_socket: &std::path::Path, _batch: &[QueuedDownload]) -> Result<()> {
+    Err(anyhow!("instance coordination needs Unix sockets"))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_instance_holds_the_queue_until_it_exits() {
+        let dir = std::env::temp_dir().join(format!("pikpaktui-claim-{}", std::process::id()));
+        let (tx, _rx) = std::sync::mpsc::channel();
+
+        let first = claim_in(&dir, tx.clone());
+        assert!(first.is_primary());
+        assert!(dir.join("tui.sock").exists());
+        match claim_in(&dir, tx.clone()) {
+            Role::Secondary { pid, socket, .. } => {
+                assert_eq!(pid, Some(std::process::id()));
+                assert_eq!(socket, dir.join("tui.sock"));
+            }
+            Role::Primary { .. } => panic!("the lock is held"),
+        }
+
+        drop(first);
+        assert!(claim_in(&dir, tx).is_primary());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn batches_reach_the_listening_instance() {
+        let dir = std::env::temp_dir().join(format!("pikpaktui-instance-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join("tui.sock");
+        let _ = std::fs::remove_file(&socket);
+        let listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || serve(listener, tx));
+
+        // A bare connect (the liveness probe) must not wedge the listener.
+        drop(std::os::unix::net::UnixStream::connect(&socket).unwrap());
+
+        let batch = vec![QueuedDownload {
+            file_id: "f1".into(),
+            name: "a.mkv".into(),
+            total_size: 42,
+            dest_path: PathBuf::from("/tmp/a.mkv"),
+            on_complete: OnComplete::Play,
+        }];
+        send(&socket, &batch).unwrap();
+        match rx.recv().unwrap() {
+            OpResult::Delegated(got) => assert_eq!(got, batch),
+            _ => panic!("expected a delegated batch"),
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,459 @@
+//! Remappable keys of the file browser. `[tui.keys]` in `config.toml` maps action
+//! names to keys (`delete = "x"`, `downloads = "ctrl+d"`); anything not
+//! listed keeps its built-in key. Arrows, paging, `Tab`, `Esc`, `1`–`9`,
+//! `F5` / `F6` and the `Ctrl` shortcuts the browser already uses stay fixed.
+
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum Action {
+    Quit,
+    Down,
+    Up,
+    Top,
+    Bottom,
+    Open,
+    Back,
+    Logs,
+    Refresh,
+    Move,
+    Copy,
+    Rename,
+    BatchRename,
+    Normalize,
+    Delete,
+    Filter,
+    Mkdir,
+    NewFile,
+    Note,
+    Watched,
+    Recent,
+    Starred,
+    Help,
+    Cart,
+    CartGlob,
+    CartView,
+    Downloads,
+    Shares,
+    Account,
+    Star,
+    CopyLink,
+    Share,
+    OpenWeb,
+    Upload,
+    UploadClipboard,
+    Offline,
+    OfflineTasks,
+    Trash,
+    Sort,
+    Reverse,
+    Play,
+    Preview,
+    Gallery,
+    Settings,
+    Info,
+    Goto,
+    Undo,
+    Jobs,
+    Activity,
+    NewTab,
+    CloseTab,
+    NextTab,
+    PrevTab,
+}
+
+/// Every action with its config name, built-in key and what it does, in
+/// the order the keymap viewer lists them.
+pub(super) const ACTIONS: &[(Action, &str, &str, &str)] = &[
+    (Action::Down, "down", "j", "Move down"),
+    (Action::Up, "up", "k", "Move up"),
+    (Action::Top, "top", "g", "Jump to top"),
+    (Action::Bottom, "bottom", "G", "Jump to bottom"),
+    (Action::Open, "open", "Enter", "Open folder / play video"),
+    (Action::Back, "back", "Backspace", "Go to parent"),
+    (Action::Refresh, "refresh", "r", "Refresh"),
+    (Action::Filter, "filter", "/", "Filter entries"),
+    (Action::Goto, "goto", ":", "Go to path"),
+    (Action::Recent, "recent", "~", "Recent files"),
+    (Action::Starred, "starred", "*", "Starred files"),
+    (Action::NewTab, "new_tab", "T", "New tab"),
+    (Action::CloseTab, "close_tab", "X", "Close tab"),
+    (Action::NextTab, "next_tab", "]", "Next tab"),
+    (Action::PrevTab, "prev_tab", "[", "Previous tab"),
+    (Action::Sort, "sort", "S", "Cycle sort field"),
+    (Action::Reverse, "reverse", "R", "Reverse sort"),
+    (Action::Info, "info", "Space", "File info"),
+    (Action::Preview, "preview", "p", "Preview"),
+    (Action::Gallery, "gallery", "i", "Image gallery"),
+    (Action::Play, "play", "w", "Watch (streams)"),
+    (Action::Copy, "copy", "c", "Copy"),
+    (Action::Move, "move", "m", "Move"),
+    (Action::Rename, "rename", "n", "Rename"),
+    (
+        Action::BatchRename,
+        "batch_rename",
+        "N",
+        "Batch rename marked",
+    ),
+    (
+        Action::Normalize,
+        "normalize_names",
+        "Z",
+        "Tidy up names (marked or current)",
+    ),
+    (Action::Delete, "delete", "d", "Delete"),
+    (Action::Undo, "undo", "Ctrl+z", "Undo last operation"),
+    (Action::Jobs, "jobs", "J", "Operation queue"),
+    (Action::Activity, "activity", "E", "Activity (events)"),
+    (Action::Mkdir, "mkdir", "f", "New folder"),
+    (Action::NewFile, "new_file", "F", "New text file"),
+    (Action::Star, "star", "s", "Star / unstar"),
+    (Action::Note, "note", "L", "Note / labels"),
+    (Action::Watched, "watched", "v", "Mark watched / unwatched"),
+    (Action::CopyLink, "copy_link", "y", "Copy link"),
+    (Action::Share, "share", "Y", "Share link"),
+    (Action::OpenWeb, "open_web", "W", "Open in web UI"),
+    (Action::Upload, "upload", "u", "Upload"),
+    (
+        Action::UploadClipboard,
+        "upload_clipboard",
+        "U",
+        "Paste image",
+    ),
+    (Action::Cart, "cart", "a", "Add to cart"),
+    (Action::CartGlob, "cart_glob", "+", "Cart: by glob"),
+    (Action::CartView, "cart_view", "A", "View cart"),
+    (Action::Downloads, "downloads", "D", "Downloads"),
+    (Action::Shares, "shares", "M", "My Shares"),
+    (Action::Offline, "offline", "o", "Cloud download"),
+    (Action::OfflineTasks, "offline_tasks", "O", "Offline tasks"),
+    (Action::Trash, "trash", "t", "Trash"),
+    (Action::Account, "account", "P", "Switch account"),
+    (Action::Logs, "logs", "l", "Toggle logs"),
+    (Action::Settings, "settings", ",", "Settings"),
+    (Action::Help, "help", "h", "Toggle help"),
+    (Action::Quit, "quit", "q", "Quit"),
+];
+
+/// Fixed keys that do the same as an action, shown next to its own key.
+const ALTERNATES: &[(Action, &str)] = &[
+    (Action::Down, "\u{2193}"),
+    (Action::Up, "\u{2191}"),
+    (Action::Top, "Home"),
+    (Action::Bottom, "End"),
+    (Action::Filter, "Ctrl+F"),
+];
+
+type Key = (KeyCode, KeyModifiers);
+
+/// Parse `x`, `Enter`, `ctrl+x`, `alt+F5` and the like. Letters keep their
+/// case (`G` is Shift+g); modifier names are case-insensitive.
+fn parse_key(s: &str) -> Option<Key> {
+    let mut mods = KeyModifiers::NONE;
+    let mut rest = s;
+    while let Some((prefix, tail)) = rest.split_once('+')
+        && !tail.is_empty()
+    {
+        mods |= match prefix.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            _ => return None,
+        };
+        rest = tail;
+    }
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match rest.to_ascii_lowercase().as_str() {
+            "enter" => KeyCode::Enter,
+            "backspace" | "bksp" => KeyCode::Backspace,
+            "space" => KeyCode::Char(' '),
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            f => KeyCode::F(
+                f.strip_prefix('f')?
+                    .parse()
+                    .ok()
+                    .filter(|n| (1..=12).contains(n))?,
+            ),
+        },
+    };
+    Some((code, mods))
+}
+
+/// How a key is shown in the help bar, help sheet and keymap viewer.
+fn key_label((code, mods): Key) -> String {
+    let mut label = String::new();
+    if mods.contains(KeyModifiers::CONTROL) {
+        label.push_str("Ctrl+");
+    }
+    if mods.contains(KeyModifiers::ALT) {
+        label.push_str("Alt+");
+    }
+    match code {
+        KeyCode::Char(' ') => label.push_str("Space"),
+        KeyCode::Char(c) => label.push(c),
+        KeyCode::Enter => label.push_str("Enter"),
+        KeyCode::Backspace => label.push_str("Bksp"),
+        KeyCode::Delete => label.push_str("Del"),
+        KeyCode::Insert => label.push_str("Ins"),
+        KeyCode::F(n) => label.push_str(&format!("F{n}")),
+        other => label.push_str(&format!("{other:?}")),
+    }
+    label
+}
+
+/// Only Ctrl and Alt distinguish bindings; terminals differ on whether an
+/// uppercase letter also carries Shift.
+fn normalize(code: KeyCode, mods: KeyModifiers) -> Key {
+    (code, mods & (KeyModifiers::CONTROL | KeyModifiers::ALT))
+}
+
+/// Keys handled before the keymap is consulted, which can't be rebound.
+fn is_reserved((code, mods): Key) -> bool {
+    let ctrl = mods.contains(KeyModifiers::CONTROL);
+    match code {
+        KeyCode::Char('a' | 'c' | 'd' | 'f' | 'i' | 'u') => ctrl,
+        KeyCode::Char('1'..='9') => mods.is_empty() || mods == KeyModifiers::ALT,
+        KeyCode::F(5 | 6) => mods.is_empty(),
+        KeyCode::Up
+        | KeyCode::Down
+        | KeyCode::Left
+        | KeyCode::Right
+        | KeyCode::PageUp
+        | KeyCode::PageDown
+        | KeyCode::Home
+        | KeyCode::End
+        | KeyCode::Tab
+        | KeyCode::BackTab
+        | KeyCode::Esc => true,
+        _ => false,
+    }
+}
+
+pub(super) struct Keymap {
+    bindings: HashMap<Key, Action>,
+    keys: HashMap<Action, Key>,
+    labels: HashMap<Action, String>,
+    /// Labels including the fixed alternates (`j / ↓`), for the help sheet.
+    help_labels: HashMap<Action, String>,
+    /// `j/k/g/G`, for the help bar.
+    nav: String,
+}
+
+impl Keymap {
+    /// Build the keymap from the `[tui.keys]` table. Entries that can't be used
+    /// are skipped with a message saying why, leaving those actions on their
+    /// built-in keys.
+    pub(super) fn new(overrides: &BTreeMap<String, String>) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        let mut keys: HashMap<Action, Key> = ACTIONS
+            .iter()
+            .map(|&(action, _, default, _)| {
+                (action, parse_key(default).expect("built-in keys parse"))
+            })
+            .collect();
+
+        for (name, value) in overrides {
+            let Some(&(action, ..)) = ACTIONS.iter().find(|a| a.1 == name) else {
+                warnings.push(format!("[tui.keys] unknown action '{name}'"));
+                continue;
+            };
+            let Some(key) = parse_key(value.trim()) else {
+                warnings.push(format!("[tui.keys] {name}: can't read key '{value}'"));
+                continue;
+            };
+            let key = normalize(key.0, key.1);
+            if is_reserved(key) {
+                warnings.push(format!(
+                    "[tui.keys] {name}: '{value}' is a fixed key and can't be rebound"
+                ));
+                continue;
+            }
+            keys.insert(action, key);
+        }
+
+        // A key claimed twice goes to the action that was remapped to it;
+        // if both were (or neither), the earlier action in ACTIONS wins.
+        let mut bindings: HashMap<Key, Action> = HashMap::new();
+        for &(action, name, ..) in ACTIONS {
+            let key = keys[&action];
+            match bindings.get(&key) {
+                Some(&other)
+                    if overrides.contains_key(name) && !Self::is_override(overrides, other) =>
+                {
+                    bindings.insert(key, action);
+                    Self::unbind(&mut warnings, other, key);
+                }
+                Some(_) => Self::unbind(&mut warnings, action, key),
+                None => {
+                    bindings.insert(key, action);
+                }
+            }
+        }
+        keys.retain(|action, key| bindings.get(key) == Some(action));
+
+        let labels: HashMap<Action, String> = keys
+            .iter()
+            .map(|(&action, &key)| (action, key_label(key)))
+            .collect();
+        let help_labels = ACTIONS
+            .iter()
+            .map(|&(action, ..)| {
+                let own = labels.get(&action).map(String::as_str);
+                let alt = ALTERNATES.iter().find(|a| a.0 == action).map(|a| a.1);
+                let label = match (own, alt) {
+                    (Some(own), Some(alt)) => format!("{own} / {alt}"),
+                    (own, alt) => own.or(alt).unwrap_or("\u{2014}").to_string(),
+                };
+                (action, label)
+            })
+            .collect();
+        let nav = [Action::Down, Action::Up, Action::Top, Action::Bottom]
+            .iter()
+            .filter_map(|a| labels.get(a).map(String::as_str))
+            .collect::<Vec<_>>()
+            .join("/");
+        (
+            Self {
+                bindings,
+                keys,
+                labels,
+                help_labels,
+                nav,
+            },
+            warnings,
+        )
+    }
+
+    fn is_override(overrides: &BTreeMap<String, String>, action: Action) -> bool {
+        ACTIONS
+            .iter()
+            .any(|&(a, name, ..)| a == action && overrides.contains_key(name))
+    }
+
+    fn unbind(warnings: &mut Vec<String>, action: Action, key: Key) {
+        let name = ACTIONS.iter().find(|a| a.0 == action).map_or("?", |a| a.1);
+        warnings.push(format!(
+            "[tui.keys] '{}' is bound twice; {name} has no key now",
+            key_label(key)
+        ));
+    }
+
+    pub(super) fn action(&self, code: KeyCode, mods: KeyModifiers) -> Option<Action> {
+        self.bindings.get(&normalize(code, mods)).copied()
+    }
+
+    /// The key for `action`, e.g. `d` or `Ctrl+x`; empty when it has none.
+    pub(super) fn label(&self, action: Action) -> &str {
+        self.labels.get(&action).map_or("", String::as_str)
+    }
+
+    /// The key for `action` with any fixed alternate, e.g. `j / ↓`.
+    pub(super) fn help_label(&self, action: Action) -> &str {
+        self.help_labels.get(&action).map_or("", String::as_str)
+    }
+
+    /// The four navigation keys, `j/k/g/G`.
+    pub(super) fn nav_label(&self) -> &str {
+        &self.nav
+    }
+
+    /// Whether `action` is on a key other than its built-in one.
+    pub(super) fn is_custom(&self, action: Action) -> bool {
+        let default = ACTIONS
+            .iter()
+            .find(|a| a.0 == action)
+            .and_then(|a| parse_key(a.2));
+        self.keys.get(&action).copied() != default
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&BTreeMap::new()).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(a, k)| (a.to_string(), k.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn built_in_keys_are_distinct_and_free() {
+        let mut seen = HashMap::new();
+        for &(action, name, key, _) in ACTIONS {
+            let parsed = parse_key(key).unwrap_or_else(|| panic!("{name}: bad key {key}"));
+            assert!(
+                !is_reserved(parsed),
+                "{name}: {key} is handled before the keymap"
+            );
+            if let Some(other) = seen.insert(parsed, action) {
+                panic!("{name} and {other:?} both default to {key}");
+            }
+        }
+    }
+
+    #[test]
+    fn overrides_rebind_and_bad_entries_are_reported() {
+        let (map, warnings) = Keymap::new(&keys(&[
+            ("delete", "x"),
+            ("downloads", "ctrl+x"),
+            ("trash", "d"),
+            ("bogus", "z"),
+            ("quit", "ctrl+d"),
+            ("help", "hyper+h"),
+        ]));
+        assert_eq!(
+            map.action(KeyCode::Char('x'), KeyModifiers::NONE),
+            Some(Action::Delete)
+        );
+        assert_eq!(
+            map.action(KeyCode::Char('x'), KeyModifiers::CONTROL),
+            Some(Action::Downloads)
+        );
+        assert_eq!(
+            map.action(KeyCode::Char('d'), KeyModifiers::NONE),
+            Some(Action::Trash)
+        );
+        // `t` was trash's; nothing else took it.
+        assert_eq!(map.action(KeyCode::Char('t'), KeyModifiers::NONE), None);
+        // Unusable entries leave the built-in key in place.
+        assert_eq!(
+            map.action(KeyCode::Char('q'), KeyModifiers::NONE),
+            Some(Action::Quit)
+        );
+        assert_eq!(
+            map.action(KeyCode::Char('h'), KeyModifiers::NONE),
+            Some(Action::Help)
+        );
+        // Shift on an uppercase letter doesn't matter.
+        assert_eq!(
+            map.action(KeyCode::Char('G'), KeyModifiers::SHIFT),
+            Some(Action::Bottom)
+        );
+        assert_eq!(map.label(Action::Downloads), "Ctrl+x");
+        assert!(map.is_custom(Action::Delete));
+        assert!(!map.is_custom(Action::Open));
+        assert_eq!(warnings.len(), 3, "{warnings:?}");
+    }
+
+    #[test]
+    fn a_key_claimed_twice_goes_to_the_remapped_action() {
+        let (map, warnings) = Keymap::new(&keys(&[("downloads", "q")]));
+        assert_eq!(
+            map.action(KeyCode::Char('q'), KeyModifiers::NONE),
+            Some(Action::Downloads)
+        );
+        assert_eq!(map.label(Action::Quit), "");
+        assert_eq!(warnings.len(), 1);
+    }
+}
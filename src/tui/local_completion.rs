@@ -1,4 +1,6 @@
-use std::path::Path;
+use std::path::PathBuf;
+
+use crate::local_path::{Flavor, dir_with_separator, join, split_dir};
 
 pub(super) struct LocalPathInput {
     pub value: String,
@@ -18,13 +20,7 @@ impl LocalPathInput {
     pub fn new() -> Self {
         let default = dirs::download_dir()
             .or_else(dirs::home_dir)
-            .map(|p| {
-                let mut s = p.to_string_lossy().into_owned();
-                if !s.ends_with('/') {
-                    s.push('/');
-                }
-                s
-            })
+            .map(|p| dir_with_separator(&p))
             .unwrap_or_default();
 
         Self {
@@ -38,13 +34,7 @@ impl LocalPathInput {
 
     pub fn new_for_upload() -> Self {
         let default = dirs::home_dir()
-            .map(|p| {
-                let mut s = p.to_string_lossy().into_owned();
-                if !s.ends_with('/') {
-                    s.push('/');
-                }
-                s
-            })
+            .map(|p| dir_with_separator(&p))
             .unwrap_or_default();
 
         Self {
@@ -58,7 +48,7 @@ impl LocalPathInput {
 
     /// Populate candidates from the current value (does not modify value).
     pub fn open_candidates(&mut self) {
-        let (dir_part, prefix) = split_local_path(&self.value);
+        let (dir_part, prefix) = split_dir(&self.value, Flavor::NATIVE);
         let dir_path = if dir_part.is_empty() { "." } else { &dir_part };
         let Ok(read_dir) = std::fs::read_dir(dir_path) else {
             self.candidates.clear();
@@ -126,8 +116,11 @@ impl LocalPathInput {
         if let Some(idx) = self.candidate_idx
             && let Some((name, is_dir)) = self.candidates.get(idx)
         {
-            let suffix = if *is_dir { "/" } else { "" };
-            self.value = join_path(&self.completion_base, &format!("{}{}", name, suffix));
+            let mut name = name.clone();
+            if *is_dir {
+                name.push(Flavor::NATIVE.separator());
+            }
+            self.value = join(&self.completion_base, &name, Flavor::NATIVE);
             self.candidates.clear();
             self.candidate_idx = None;
             self.completion_base.clear();
@@ -143,38 +136,9 @@ impl LocalPathInput {
     }
 }
 
-fn join_path(base: &str, name: &str) -> String {
-    if base.is_empty() {
-        name.to_string()
-    } else if base.ends_with('/') {
-        format!("{}{}", base, name)
-    } else {
-        format!("{}/{}", base, name)
-    }
-}
-
-/// Split into (directory, prefix).
-/// "/Users/foo/Down" -> ("/Users/foo", "Down")
-/// "/Users/foo/"     -> ("/Users/foo/", "")
-fn split_local_path(input: &str) -> (String, String) {
-    if input.is_empty() {
-        return (String::new(), String::new());
-    }
-    if input.ends_with('/') {
-        return (input.to_string(), String::new());
-    }
-    let path = Path::new(input);
-    match path.parent() {
-        Some(parent) => {
-            let parent_str = parent.to_string_lossy().into_owned();
-            let name = path
-                .file_name()
-                .map(|n| n.to_string_lossy().into_owned())
-                .unwrap_or_default();
-            (parent_str, name)
-        }
-        None => (String::new(), input.to_string()),
-    }
+/// `~/x` as a path under the home folder; anything else as it is.
+pub(super) fn expand_home(path: &str) -> PathBuf {
+    crate::local_path::expand_home(path, dirs::home_dir().as_deref(), Flavor::NATIVE)
 }
 
 /// Fuzzy match score (both inputs pre-lowercased). Returns None if no subsequence match.
@@ -0,0 +1,72 @@
+use anyhow::{Result, anyhow};
+
+use crate::config::AppConfig;
+
+/// What it takes to dismiss the idle lock.
+pub(super) enum Unlock {
+    Pin(String),
+    /// The password saved in `login.toml`.
+    Password(String),
+    /// No password on disk: check it by signing in again as this account.
+    Account(String),
+}
+
+impl Unlock {
+    /// Prefer the configured PIN, then the saved password, then a fresh
+    /// sign-in. `None` when there is nothing to check against.
+    pub(super) fn resolve(pin: Option<&str>) -> Option<Self> {
+        if let Some(pin) = pin.filter(|p| !p.is_empty()) {
+            return Some(Self::Pin(pin.to_string()));
+        }
+        let saved = AppConfig::load().ok()?;
+        match saved.credentials() {
+            Some((_, password)) => Some(Self::Password(password)),
+            None => saved.active_username().map(Self::Account),
+        }
+    }
+
+    pub(super) fn label(&self) -> &'static str {
+        match self {
+            Self::Pin(_) => "PIN",
+            _ => "Password",
+        }
+    }
+
+    /// Check `input` against the PIN or saved password. `None` for an
+    /// account, whose sign-in goes to a worker.
+    pub(super) fn check(&self, input: &str) -> Option<Result<()>> {
+        match self {
+            Self::Pin(pin) if pin == input => Some(Ok(())),
+            Self::Pin(_) => Some(Err(anyhow!("Wrong PIN"))),
+            Self::Password(password) if password == input => Some(Ok(())),
+            Self::Password(_) => Some(Err(anyhow!("Wrong password"))),
+            Self::Account(_) => None,
+        }
+    }
+}
+
+pub(super) struct LockScreen {
+    pub(super) unlock: Unlock,
+    pub(super) value: String,
+    pub(super) error: Option<String>,
+    /// A sign-in checking the password is in flight.
+    pub(super) checking: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pin_takes_precedence_and_must_match_exactly() {
+        let unlock = Unlock::resolve(Some("2468")).unwrap();
+        assert_eq!(unlock.label(), "PIN");
+        assert!(unlock.check("2468").unwrap().is_ok());
+        assert!(unlock.check("2468 ").unwrap().is_err());
+        let password = Unlock::Password("hunter2".into());
+        assert!(password.check("hunter2").unwrap().is_ok());
+        assert!(password.check("").unwrap().is_err());
+        // Only a sign-in can tell.
+        assert!(Unlock::Account("a@b.c".into()).check("x").is_none());
+    }
+}
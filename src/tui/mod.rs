@@ -1,20 +1,47 @@
+mod activity;
+mod archive;
+mod batch_confirm;
+mod batch_rename;
+mod commander;
 mod completion;
 pub(crate) mod download;
 mod download_view;
 mod draw;
+mod filter;
+mod gallery;
 mod handler;
 mod image_render;
+mod instance;
+mod keymap;
 mod local_completion;
+mod lock;
+mod new_file;
+mod notes;
+mod offline;
+mod ops;
+mod playback;
+mod preview_handler;
+pub(crate) mod queue_file;
+mod subtitles;
+mod tabs;
+mod term_status;
+mod upload;
+mod video_frame;
+mod when_done;
 mod widgets;
+mod worker;
 
 pub use download_view::{DownloadViewMode, NetworkStats};
 
 use crate::config::{AppConfig, TuiConfig};
-use crate::pikpak::{Entry, EntryKind, FileInfoResponse, PikPak};
+use crate::pikpak::{
+    Entry, EntryKind, FileInfoResponse, LoginStep, PendingLogin, PikPak, StreamProxy,
+};
 use crate::theme;
 use anyhow::Result;
 use crossterm::event::{
-    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
+    self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    Event, KeyCode, KeyEventKind,
 };
 use crossterm::execute;
 use crossterm::terminal::{
@@ -23,7 +50,7 @@ use crossterm::terminal::{
 use ratatui::DefaultTerminal;
 use ratatui::layout::{Constraint, Direction, Layout};
 use std::cell::Cell;
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::io;
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::sync::{Arc, LazyLock};
@@ -37,24 +64,88 @@ pub type Credentials = (String, String);
 
 const SPINNER_FRAMES: &[&str] = &["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
-pub fn run(client: PikPak, config: TuiConfig) -> Result<()> {
-    run_terminal(App::new_authed(client, config))
+/// Background API calls (listings, previews, mutations) share this many
+/// threads; extra requests queue instead of spawning more.
+const WORKER_THREADS: usize = 8;
+
+/// Pane previews that may fail in a row before auto-preview is paused.
+const PREVIEW_FAILURE_LIMIT: u32 = 5;
+
+/// Folder id of the virtual "Recent" location. Not a real PikPak id, so it
+/// can never collide with one; `list_folder` routes it to the events feed.
+const RECENT_FOLDER_ID: &str = "@recent";
+
+/// Folder id of the virtual "Starred" location, listing every starred item.
+const STARRED_FOLDER_ID: &str = "@starred";
+
+/// How many starred items the virtual "Starred" folder shows.
+const STARRED_LIMIT: u32 = 500;
+
+/// Whether `folder_id` is one of the virtual locations rather than a real
+/// folder: nothing can be created in them and they can't be a destination.
+fn is_virtual_folder(folder_id: &str) -> bool {
+    folder_id == RECENT_FOLDER_ID || folder_id == STARRED_FOLDER_ID
+}
+
+/// List a folder by id, synthesizing the virtual "Recent" folder from files
+/// added in the last `recent_days` days and "Starred" from starred items.
+fn list_folder(
+    client: &PikPak,
+    folder_id: &str,
+    recent_days: u32,
+    cancel: &worker::CancelToken,
+) -> Result<Vec<Entry>> {
+    if folder_id == RECENT_FOLDER_ID {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+        client.recent_files(now - i64::from(recent_days) * 86_400)
+    } else if folder_id == STARRED_FOLDER_ID {
+        client.starred_list(STARRED_LIMIT)
+    } else {
+        client.ls_streaming(folder_id, |_| continue_unless(cancel))
+    }
+}
+
+/// `ls_streaming` page callback that stops paging once `cancel` fires.
+fn continue_unless(cancel: &worker::CancelToken) -> std::ops::ControlFlow<()> {
+    if cancel.is_cancelled() {
+        std::ops::ControlFlow::Break(())
+    } else {
+        std::ops::ControlFlow::Continue(())
+    }
+}
+
+/// `warnings` from setting up the client are shown in the log.
+pub fn run(client: PikPak, config: TuiConfig, warnings: Vec<String>) -> Result<()> {
+    run_terminal(App::new_authed(client, config), warnings)
 }
 
 pub fn run_with_credentials(
     client: PikPak,
     credentials: Option<Credentials>,
     config: TuiConfig,
+    warnings: Vec<String>,
 ) -> Result<()> {
-    run_terminal(App::new_login(client, credentials, config))
+    run_terminal(App::new_login(client, credentials, config), warnings)
 }
 
 fn restore_terminal() {
+    term_status::clear();
     let _ = disable_raw_mode();
-    let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
+    let _ = execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableBracketedPaste
+    );
 }
 
-fn run_terminal(mut app: App) -> Result<()> {
+fn run_terminal(mut app: App, warnings: Vec<String>) -> Result<()> {
+    for warning in warnings {
+        app.push_log(format!("Warning: {warning}"));
+    }
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
         restore_terminal();
@@ -62,7 +153,12 @@ fn run_terminal(mut app: App) -> Result<()> {
     }));
 
     enable_raw_mode()?;
-    execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        io::stdout(),
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = ratatui::backend::CrosstermBackend::new(io::stdout());
     let mut terminal = ratatui::Terminal::new(backend)?;
     let res = app.run(&mut terminal);
@@ -92,33 +188,68 @@ enum PreviewState {
     ThumbnailImage {
         image: image::DynamicImage,
     },
+    /// What's inside a zip file.
+    ArchiveListing(archive::Listing),
 }
 
 pub(crate) struct PlayOption {
     pub label: String,
     pub url: String,
     pub available: bool,
+    /// The transcoded stream's media name; `None` for the original file.
+    pub media: Option<String>,
 }
 
 enum OpResult {
     Ls(Result<Vec<Entry>>),
+    /// Listing of the commander pane without focus, tagged with its folder id.
+    PaneLs(String, Result<Vec<Entry>>),
+    /// One page of a folder listing still in progress, tagged with the folder id.
+    LsPage(String, Vec<Entry>),
     Ok(String),
     Err(String),
+    /// A finished job of the file-operation queue, with its inverse.
+    Job(u64, Result<Option<ops::FileOp>>),
+    /// A progress line from a multi-step job; logged without a refresh.
+    Log(String),
+    /// The newest page of the events feed, for the activity overlay.
+    Activity(Result<Vec<crate::pikpak::EventEntry>>),
     Info(Result<FileInfoResponse>, Option<String>),
     ParentLs(String, Result<Vec<Entry>>),
     PreviewLs(String, Result<Vec<Entry>>),
     PreviewInfo(String, Result<FileInfoResponse>),
-    PreviewText(String, Result<(String, String, u64, bool)>),
+    PreviewText(String, Result<preview_handler::TextPreview>),
     PreviewThumbnail(String, Result<image::DynamicImage>),
+    PreviewArchive(String, Result<archive::Listing>),
+    /// Video details for the file a thumbnail is shown for.
+    PreviewMedia(String, Option<crate::pikpak::MediaVideo>),
+    GalleryImage(String, Result<gallery::Picture>),
     OfflineTasks(Result<Vec<crate::pikpak::OfflineTask>>),
+    /// Files of completed offline tasks to queue: (task, [(file, path
+    /// relative to the auto-fetch dir)]).
+    AutoFetch(Vec<(crate::pikpak::OfflineTask, Vec<(Entry, std::path::PathBuf)>)>),
+    /// A step of the background hash index; `None` when it's up to date.
+    IndexStep(Option<Result<crate::pikpak::IndexStep>>),
+    /// Log lines of the daily cleanup run; `None` when it wasn't due.
+    Cleanup(Result<Option<Vec<String>>>),
+    /// Downloads handed over by another pikpaktui instance.
+    Delegated(Vec<instance::QueuedDownload>),
     PlayInfo(Result<FileInfoResponse>),
-    PlayPickerInfo(Result<(FileInfoResponse, Vec<PlayOption>)>),
+    PlayPickerInfo(Result<(FileInfoResponse, Vec<PlayOption>, Vec<subtitles::Subtitle>)>),
     TrashList(Result<Vec<Entry>>),
     TrashOp(String),
     OfflineOp(String),
     InfoThumbnail(Result<image::DynamicImage>),
-    GotoPath(Result<(String, Vec<(String, String)>)>),
+    InfoArchive(Result<archive::Listing>),
+    GotoPath(Result<crate::pikpak::NavTarget>),
     Quota(Result<crate::pikpak::QuotaInfo>),
+    TransferQuota(Result<crate::pikpak::TransferQuotaResponse>),
+    /// Name clashes found for an upload, to be answered before it starts.
+    UploadPlan(Result<Box<upload::UploadPlan>>),
+    /// Clashes in a batch's destination folder (its id).
+    BatchConflicts(String, Result<Vec<(String, &'static str)>>),
+    /// Status line of a multi-file upload, shown while it runs.
+    UploadProgress(String),
     Upload(Result<String>),
     ShareCreated {
         title: String,
@@ -127,6 +258,16 @@ enum OpResult {
     },
     MyShares(Result<Vec<crate::pikpak::MyShare>>),
     UpdateAvailable(Option<String>),
+    /// A client signed in to another profile (`None` = default).
+    ProfileSwitched(Option<String>, Result<PikPak>),
+    /// The local page of a browser login is up, at this address.
+    BrowserLoginReady(String),
+    /// The client the browser login signed in with, and how it went.
+    BrowserLogin(Box<PikPak>, Result<LoginStep>),
+    /// The sign-in checking the idle lock's password came back.
+    Unlock(Result<()>),
+    /// Watched state changed (`v`, or mpv quit); what to log.
+    Watched(Result<String>),
 }
 
 #[derive(Default)]
@@ -146,17 +287,39 @@ enum InputMode {
         error: Option<String>,
         logging_in: bool,
     },
+    /// PikPak emailed a verification code to finish the login with.
+    LoginCode {
+        pending: Box<PendingLogin>,
+        code: String,
+        error: Option<String>,
+    },
     Normal,
     Rename {
         value: String,
     },
+    BatchRename(Box<batch_rename::BatchRename>),
     Mkdir {
         value: String,
     },
+    NewFile(Box<new_file::NewFile>),
+    Note(Box<notes::NoteForm>),
+    CartGlob {
+        pattern: String,
+    },
     ConfirmDelete,
     ConfirmPermanentDelete {
         value: String,
     },
+    /// Typed "yes" confirmation before permanently deleting trash items,
+    /// as (id, name) pairs.
+    ConfirmPurge {
+        targets: Vec<(String, String)>,
+        value: String,
+    },
+    /// Typed "yes" confirmation before emptying the whole trash.
+    ConfirmEmptyTrash {
+        value: String,
+    },
     MoveInput {
         source: Entry,
         input: PathInput,
@@ -186,17 +349,32 @@ enum InputMode {
     CartCopyPicker {
         picker: PickerState,
     },
-    ConfirmCartDelete,
+    /// What a batch is about to do, before it runs.
+    ConfirmBatch(Box<batch_confirm::BatchSummary>),
     DownloadInput {
         input: LocalPathInput,
     },
-    UploadInput {
-        input: LocalPathInput,
-    },
+    UploadInput(Box<upload::UploadForm>),
+    /// A file being uploaded already exists in the destination.
+    UploadConflict(Box<upload::UploadPlan>),
+    /// The folder's images, one at a time in the preview pane.
+    Gallery(Box<gallery::Gallery>),
     DownloadView,
     OfflineInput {
         value: String,
     },
+    OfflinePicker {
+        urls: Vec<String>,
+        picker: PickerState,
+        /// Open the offline tasks view once submitted (for torrent files).
+        show_tasks: bool,
+    },
+    /// Picking the folder trashed items are restored into; `targets` are
+    /// (id, name) pairs.
+    RestorePicker {
+        targets: Vec<(String, String)>,
+        picker: PickerState,
+    },
     OfflineTasksView {
         tasks: Vec<crate::pikpak::OfflineTask>,
         selected: usize,
@@ -206,6 +384,8 @@ enum InputMode {
         info: FileInfoResponse,
         image: Option<image::DynamicImage>,
         has_thumbnail: bool,
+        /// The contents, for a zip file, once listed.
+        archive: Option<archive::Listing>,
     },
     InfoFolderView {
         name: String,
@@ -217,17 +397,33 @@ enum InputMode {
         truncated: bool,
     },
     ConfirmPlay {
+        file_id: String,
         name: String,
         url: String,
+        subs: subtitles::Subtitles,
     },
     PlayPicker {
+        file_id: String,
         name: String,
         medias: Vec<PlayOption>,
         selected: usize,
+        subs: subtitles::Subtitles,
     },
     PlayerInput {
         value: String,
+        /// The video being played, to track; an empty id for a local file.
+        file_id: String,
+        name: String,
         pending_url: String,
+        /// The local proxy behind `pending_url`, if any, for the player to hold.
+        proxy: Option<StreamProxy>,
+        /// Subtitle links to pass along once the player is known.
+        subs: Vec<String>,
+    },
+    /// Account switcher: `default` plus the profiles in `login.toml`.
+    ProfilePicker {
+        profiles: Vec<String>,
+        selected: usize,
     },
     TrashView {
         entries: Vec<Entry>,
@@ -244,9 +440,19 @@ enum InputMode {
         confirm_delete: Option<String>, // share_id pending delete confirmation
     },
     ConfirmQuit,
+    /// The file-operation queue; the jobs live in `App::jobs`.
+    JobsView {
+        selected: usize,
+    },
+    /// The events feed; the events live in `App::activity`.
+    ActivityView {
+        selected: usize,
+    },
     GotoPath {
-        query: String,
+        input: PathInput,
     },
+    /// Typing an in-directory filter; the query lives in `App::filter_query`.
+    Filter,
     Settings {
         selected: usize,
         editing: bool,
@@ -268,6 +474,12 @@ enum InputMode {
         current_terminal: String,
         terminals: Vec<String>,
     },
+    /// Read-only list of the file browser's key bindings, opened from Settings.
+    KeymapView {
+        scroll: usize,
+        draft: TuiConfig,
+        modified: bool,
+    },
 }
 
 struct App {
@@ -277,6 +489,9 @@ struct App {
     breadcrumb: Vec<(String, String)>,
     entries: Vec<Entry>,
     selected: usize,
+    /// Full listing of the current folder while a filter narrows `entries`.
+    filter_source: Option<Vec<Entry>>,
+    filter_query: String,
     logs: VecDeque<String>,
     input: InputMode,
     cursor_visible: bool,
@@ -284,20 +499,68 @@ struct App {
     loading: bool,
     spinner_idx: usize,
     last_spinner: Instant,
+    /// When completed offline tasks were last checked for auto-fetch, and
+    /// whether a check is still running.
+    last_auto_fetch: Instant,
+    auto_fetch_busy: bool,
+    /// Offline tasks whose files are queued, by task id, to be removed from
+    /// PikPak's list once those files have all downloaded.
+    auto_fetched: HashMap<String, AutoFetched>,
+    /// Offline tasks whose downloads failed; left on the list and not
+    /// fetched again until restart.
+    auto_fetch_failed: HashSet<String>,
+    /// When the background hash index may take its next step.
+    next_index_step: Instant,
+    index_busy: bool,
+    /// When to next see whether the daily cleanup is due.
+    next_cleanup_check: Instant,
+    cleanup_busy: bool,
+    /// Last key or mouse input, for the idle lock.
+    last_input: Instant,
+    lock: Option<lock::LockScreen>,
+    /// What happens once every transfer has finished (`W`).
+    queue_done: when_done::QueueDone,
+    /// Uploads started from the TUI that haven't finished.
+    uploads_running: usize,
+    /// A browser login (`Ctrl+B` on the login screen) being waited for:
+    /// the local page's address, empty until it's up.
+    browser_login: Option<String>,
+    /// Stops the browser login's local server (Esc, or another `Ctrl+B`).
+    browser_login_cancel: worker::CancelToken,
+    /// The client of a login waiting for its verification code: it holds
+    /// that login's device id and captcha token.
+    pending_login: Option<PikPak>,
     show_help_sheet: bool,
     result_rx: Receiver<OpResult>,
     result_tx: Sender<OpResult>,
+    workers: worker::WorkerPool,
+    /// Tokens for the latest listing of the current folder, of the parent
+    /// pane, and for the preview / info overlay. Issuing a new request of a
+    /// kind (or navigating away) cancels the previous one.
+    ls_cancel: worker::CancelToken,
+    parent_cancel: worker::CancelToken,
+    preview_cancel: worker::CancelToken,
     parent_entries: Vec<Entry>,
     parent_selected: usize,
     preview_state: PreviewState,
     preview_target_id: Option<String>,
     preview_target_name: Option<String>,
+    /// Duration, resolution and codecs of the last video previewed, by id.
+    preview_media: Option<(String, crate::pikpak::MediaVideo)>,
     show_logs_overlay: bool,
     last_cursor_move: Instant,
     pending_preview_fetch: bool,
+    /// Pane previews failed since the last one that worked.
+    preview_failures: u32,
+    /// Auto-preview stopped for the session after repeated failures.
+    preview_paused: bool,
     cart: Vec<Entry>,
     cart_ids: HashSet<String>,
     cart_selected: usize,
+    /// Local notes and labels, by file id.
+    notes: HashMap<String, crate::pikpak::Note>,
+    /// Watched state and resume positions, by file id.
+    playback: HashMap<String, crate::pikpak::Playback>,
     download_state: DownloadState,
     download_view_mode: DownloadViewMode,
     network_stats: NetworkStats,
@@ -305,6 +568,8 @@ struct App {
     current_pane_area: Cell<ratatui::layout::Rect>,
     parent_pane_area: Cell<ratatui::layout::Rect>,
     preview_pane_area: Cell<ratatui::layout::Rect>,
+    /// Where the commander pane without focus was drawn.
+    idle_pane_area: Cell<ratatui::layout::Rect>,
     scroll_offset: Cell<usize>,
     parent_scroll_offset: Cell<usize>,
     list_area_height: Cell<u16>,
@@ -318,75 +583,59 @@ struct App {
     trash_entries: Vec<Entry>,
     trash_selected: usize,
     trash_expanded: bool,
+    /// Trash items marked for a batch restore / delete.
+    trash_marked: HashSet<String>,
+    /// Last title / progress written to the terminal, to skip redundant writes.
+    term_title: String,
+    term_progress: Option<term_status::Progress>,
     loading_label: Option<String>,
     quota_used: Option<u64>,
     quota_limit: Option<u64>,
+    /// `(used, total)` bytes of the tightest download transfer band, or `None`
+    /// when unknown or uncapped.
+    transfer_quota: Option<(u64, u64)>,
     shares_pending: bool,
     update_available: Option<String>,
+    /// Set while PikPak can't be reached and listings come from the cache.
+    offline: Option<offline::Offline>,
     /// Terminal image-protocol picker, queried once at startup. Querying reads
     /// stdin, so it must NOT happen during draw — that races with key input.
     image_picker: Option<ratatui_image::picker::Picker>,
+    /// Whether this instance owns the download queue or hands downloads to
+    /// another running instance.
+    instance: instance::Role,
+    /// File browser keys, after the `[tui.keys]` overrides.
+    keymap: keymap::Keymap,
+    /// Browse tabs; the slot at `active_tab` is stale, its state being the
+    /// browser fields above.
+    tabs: Vec<tabs::BrowseTab>,
+    active_tab: usize,
+    /// Moves, copies, renames and trashing, run one at a time.
+    jobs: ops::OpQueue,
+    activity: activity::ActivityFeed,
+}
+
+/// An offline task being auto-fetched.
+struct AutoFetched {
+    name: String,
+    /// The files it gave, by file id.
+    file_ids: HashSet<String>,
 }
 
 impl App {
     fn new_authed(client: PikPak, config: TuiConfig) -> Self {
-        let (tx, rx) = mpsc::channel();
-        let mut dl_state = DownloadState::new(config.download_jobs);
-        dl_state.load_tasks(download::load_download_state());
-        let mut app = Self {
-            client: Arc::new(client),
-            config,
-            current_folder_id: String::new(),
-            breadcrumb: Vec::new(),
-            entries: Vec::new(),
-            selected: 0,
-            logs: VecDeque::new(),
-            input: InputMode::Normal,
-            cursor_visible: true,
-            last_blink: Instant::now(),
-            loading: false,
-            spinner_idx: 0,
-            last_spinner: Instant::now(),
-            show_help_sheet: false,
-            result_rx: rx,
-            result_tx: tx,
-            parent_entries: Vec::new(),
-            parent_selected: 0,
-            preview_state: PreviewState::Empty,
-            preview_target_id: None,
-            preview_target_name: None,
-            show_logs_overlay: false,
-            last_cursor_move: Instant::now(),
-            pending_preview_fetch: false,
-            cart: Vec::new(),
-            cart_ids: HashSet::new(),
-            cart_selected: 0,
-            download_state: dl_state,
-            download_view_mode: DownloadViewMode::Collapsed,
-            network_stats: NetworkStats::new(),
-            last_network_update: Instant::now(),
-            current_pane_area: Cell::new(ratatui::layout::Rect::default()),
-            parent_pane_area: Cell::new(ratatui::layout::Rect::default()),
-            preview_pane_area: Cell::new(ratatui::layout::Rect::default()),
-            scroll_offset: Cell::new(0),
-            parent_scroll_offset: Cell::new(0),
-            list_area_height: Cell::new(0),
-            last_click_time: Instant::now(),
-            last_click_pos: (0, 0),
-            preview_scroll: 0,
-            logs_scroll: None,
-            logs_overlay_area: Cell::new(ratatui::layout::Rect::default()),
-            settings_area: Cell::new(ratatui::layout::Rect::default()),
-            trash_entries: Vec::new(),
-            trash_selected: 0,
-            trash_expanded: false,
-            loading_label: None,
-            quota_used: None,
-            quota_limit: None,
-            shares_pending: false,
-            update_available: None,
-            image_picker: None,
-        };
+        let mut app = Self::base(client, config, InputMode::Normal, instance::claim);
+        if app.instance.is_primary() {
+            app.download_state
+                .load_tasks(download::load_download_state());
+        }
+        app.log_other_instance();
+        app.log_interrupted_uploads();
+        app.load_keymap();
+        app.load_download_settings();
+        app.reload_notes();
+        app.reload_playback();
+        app.ensure_commander_panes();
         app.refresh();
         app.fetch_quota();
         app.check_for_update_async();
@@ -410,9 +659,25 @@ impl App {
                 logging_in: false,
             },
         };
+        let mut app = Self::base(client, config, input, instance::claim);
+        app.log_other_instance();
+        app.log_interrupted_uploads();
+        app.load_keymap();
+        app.load_download_settings();
+        app
+    }
 
+    /// The state both start-ups share, before anything is loaded; `claim`
+    /// decides this instance's role given where the other ones report to.
+    fn base(
+        client: PikPak,
+        config: TuiConfig,
+        input: InputMode,
+        claim: impl FnOnce(mpsc::Sender<OpResult>) -> instance::Role,
+    ) -> Self {
         let (tx, rx) = mpsc::channel();
         let download_jobs = config.download_jobs;
+        let instance = claim(tx.clone());
         Self {
             client: Arc::new(client),
             config,
@@ -420,6 +685,8 @@ impl App {
             breadcrumb: Vec::new(),
             entries: Vec::new(),
             selected: 0,
+            filter_source: None,
+            filter_query: String::new(),
             logs: VecDeque::new(),
             input,
             cursor_visible: true,
@@ -427,19 +694,43 @@ impl App {
             loading: false,
             spinner_idx: 0,
             last_spinner: Instant::now(),
+            last_auto_fetch: Instant::now(),
+            auto_fetch_busy: false,
+            auto_fetched: HashMap::new(),
+            auto_fetch_failed: HashSet::new(),
+            next_index_step: Instant::now(),
+            index_busy: false,
+            next_cleanup_check: Instant::now(),
+            cleanup_busy: false,
+            last_input: Instant::now(),
+            lock: None,
+            queue_done: when_done::QueueDone::default(),
+            uploads_running: 0,
+            browser_login: None,
+            browser_login_cancel: worker::CancelToken::default(),
+            pending_login: None,
             show_help_sheet: false,
             result_rx: rx,
             result_tx: tx,
+            workers: worker::WorkerPool::new(WORKER_THREADS),
+            ls_cancel: worker::CancelToken::default(),
+            parent_cancel: worker::CancelToken::default(),
+            preview_cancel: worker::CancelToken::default(),
             parent_entries: Vec::new(),
             parent_selected: 0,
             preview_state: PreviewState::Empty,
             preview_target_id: None,
             preview_target_name: None,
+            preview_media: None,
             show_logs_overlay: false,
             last_cursor_move: Instant::now(),
             pending_preview_fetch: false,
+            preview_failures: 0,
+            preview_paused: false,
             cart: Vec::new(),
             cart_ids: HashSet::new(),
+            notes: HashMap::new(),
+            playback: HashMap::new(),
             cart_selected: 0,
             download_state: DownloadState::new(download_jobs),
             download_view_mode: DownloadViewMode::Collapsed,
@@ -448,6 +739,7 @@ impl App {
             current_pane_area: Cell::new(ratatui::layout::Rect::default()),
             parent_pane_area: Cell::new(ratatui::layout::Rect::default()),
             preview_pane_area: Cell::new(ratatui::layout::Rect::default()),
+            idle_pane_area: Cell::new(ratatui::layout::Rect::default()),
             scroll_offset: Cell::new(0),
             parent_scroll_offset: Cell::new(0),
             list_area_height: Cell::new(0),
@@ -460,12 +752,76 @@ impl App {
             trash_entries: Vec::new(),
             trash_selected: 0,
             trash_expanded: false,
+            trash_marked: HashSet::new(),
+            term_title: String::new(),
+            term_progress: None,
             loading_label: None,
             quota_used: None,
             quota_limit: None,
+            transfer_quota: None,
             shares_pending: false,
             update_available: None,
+            offline: None,
             image_picker: None,
+            instance,
+            keymap: keymap::Keymap::default(),
+            tabs: vec![tabs::BrowseTab::default()],
+            active_tab: 0,
+            jobs: ops::OpQueue::default(),
+            activity: activity::ActivityFeed::default(),
+        }
+    }
+
+    /// A logged-in app whose client can't reach anything and which owns its
+    /// download queue, for tests.
+    #[cfg(test)]
+    fn for_tests() -> Self {
+        let session =
+            std::env::temp_dir().join(format!("pikpaktui-app-{}/session.json", std::process::id()));
+        let client = PikPak::for_tests("http://127.0.0.1:9".into(), session);
+        Self::base(client, TuiConfig::default(), InputMode::Normal, |_| {
+            instance::Role::Primary { _lock: None }
+        })
+    }
+
+    fn load_keymap(&mut self) {
+        let (keymap, warnings) = keymap::Keymap::new(&self.config.keys);
+        self.keymap = keymap;
+        for warning in warnings {
+            self.push_log(warning);
+        }
+    }
+
+    fn load_download_settings(&mut self) {
+        self.download_state.keep_finished_days = self.config.finished_task_days;
+        self.prune_finished_downloads();
+        let Some(raw) = self.config.transfer_window.clone() else {
+            return;
+        };
+        match download::TransferWindow::parse(&raw) {
+            Some(window) => self.download_state.window = Some(window),
+            None => self.push_log(format!(
+                "Ignoring transfer_window '{raw}': expected HH:MM-HH:MM"
+            )),
+        }
+    }
+
+    fn prune_finished_downloads(&mut self) {
+        let pruned = self.download_state.prune_finished(download::unix_now());
+        if pruned > 0 {
+            self.push_log(format!(
+                "Removed {} finished download(s) older than {} days (see `tasks stats`)",
+                pruned, self.download_state.keep_finished_days
+            ));
+        }
+    }
+
+    fn log_other_instance(&mut self) {
+        if let instance::Role::Secondary { pid, .. } = &self.instance {
+            let who = pid.map_or_else(String::new, |p| format!(" (pid {p})"));
+            self.push_log(format!(
+                "pikpaktui is already running{who}; downloads started here are handed to it"
+            ));
         }
     }
 
@@ -480,12 +836,19 @@ impl App {
             let email = email.clone();
             let password = password.clone();
             self.attempt_login(&email, &password);
+        } else if matches!(self.input, InputMode::Login { .. }) {
+            self.resume_session();
         }
 
         // Query the terminal's image protocol and font size ONCE, before the
         // input loop. Doing it during draw reads stdin every frame and steals
         // keypresses — a race with event::read().
-        self.image_picker = ratatui_image::picker::Picker::from_query_stdio().ok();
+        // The classic Windows console draws no images and never answers.
+        self.image_picker = if TuiConfig::detect_terminal() == "conhost" {
+            None
+        } else {
+            ratatui_image::picker::Picker::from_query_stdio().ok()
+        };
 
         loop {
             if self.last_blink.elapsed() >= Duration::from_millis(500) {
@@ -497,9 +860,20 @@ impl App {
                 self.last_spinner = Instant::now();
             }
             self.poll_results();
+            self.maybe_auto_fetch();
+            self.maybe_index();
+            self.maybe_cleanup();
+            self.maybe_poll_activity();
+            self.maybe_lock();
+            self.maybe_reconnect();
+            self.maybe_take_over();
+            if self.tick_when_done() {
+                break;
+            }
 
             // Debounce: auto-fetch preview after 300ms if lazy_preview enabled
             if self.config.lazy_preview
+                && !self.preview_paused
                 && self.pending_preview_fetch
                 && self.last_cursor_move.elapsed() >= Duration::from_millis(300)
             {
@@ -507,7 +881,7 @@ impl App {
                 // Skip auto-loading for large text files
                 let skip = self.entries.get(self.selected).is_some_and(|e| {
                     e.kind == EntryKind::File
-                        && theme::is_text_previewable(e)
+                        && self.text_previewable(e)
                         && e.size > self.config.preview_max_size
                 });
                 if !skip {
@@ -516,6 +890,7 @@ impl App {
             }
 
             terminal.draw(|f| self.draw(f))?;
+            self.sync_terminal_status();
 
             if event::poll(Duration::from_millis(50))? {
                 match event::read()? {
@@ -525,45 +900,104 @@ impl App {
                         }
                         self.cursor_visible = true;
                         self.last_blink = Instant::now();
-                        if self.handle_key(key.code, key.modifiers)? {
+                        self.last_input = Instant::now();
+                        if self.queue_done.deadline.is_some() {
+                            self.cancel_when_done();
+                        } else if self.lock.is_some() {
+                            self.handle_lock_key(key.code, key.modifiers);
+                        } else if self.handle_key(key.code, key.modifiers)? {
                             break;
                         }
                     }
                     Event::Mouse(mouse) => {
-                        self.handle_mouse(mouse);
+                        self.last_input = Instant::now();
+                        if self.lock.is_none() {
+                            self.handle_mouse(mouse);
+                        }
+                    }
+                    Event::Paste(text) => {
+                        self.last_input = Instant::now();
+                        if let Some(lock) = &mut self.lock {
+                            if !lock.checking {
+                                lock.value.push_str(text.trim_end_matches(['\r', '\n']));
+                            }
+                        } else if self.handle_paste(&text)? {
+                            break;
+                        }
                     }
                     _ => {}
                 }
             }
         }
-        download::save_download_state(&self.download_state.tasks);
+        download::save_download_state(&mut self.download_state);
         Ok(())
     }
 
     fn poll_results(&mut self) {
+        // Workers' API calls that hit a transient failure and were sent
+        // again; they show here so a flaky network isn't silent.
+        for note in self.client.take_retry_notes() {
+            self.push_log(note);
+        }
         while let Ok(result) = self.result_rx.try_recv() {
             match result {
                 OpResult::Ls(Ok(mut entries)) => {
                     self.finish_loading();
-                    crate::config::sort_entries(
-                        &mut entries,
-                        self.config.sort_field,
-                        self.config.sort_reverse,
-                    );
+                    // Recent arrives newest-first; keep that order.
+                    if !self.in_recent() {
+                        crate::config::sort_entries(
+                            &mut entries,
+                            self.config.sort_field,
+                            self.config.sort_reverse,
+                        );
+                    }
                     // Keep the cursor on the same entry across a refresh — a
                     // re-sort or insert/delete shifts indices, so a fixed index
                     // would jump to a different file. Fall back to a clamp.
                     let prev_id = self.entries.get(self.selected).map(|e| e.id.clone());
-                    self.entries = entries;
+                    if self.filter_source.is_some() {
+                        self.filter_source = Some(entries);
+                        self.entries = self.filtered_entries();
+                    } else {
+                        self.entries = entries;
+                    }
                     self.selected = prev_id
                         .and_then(|id| self.entries.iter().position(|e| e.id == id))
                         .unwrap_or_else(|| self.selected.min(self.entries.len().saturating_sub(1)));
                     self.push_log(format!("Refreshed {}", self.current_path_display()));
                     self.on_cursor_move();
+                    self.back_online();
+                }
+                OpResult::LsPage(folder_id, page) => {
+                    // Only grow a listing that is still loading for the folder
+                    // we're in; the final `Ls` replaces it wholesale.
+                    if self.loading && folder_id == self.current_folder_id {
+                        let target = match self.filter_source.as_mut() {
+                            Some(source) => source,
+                            None => &mut self.entries,
+                        };
+                        target.extend(page);
+                        crate::config::sort_entries(
+                            target,
+                            self.config.sort_field,
+                            self.config.sort_reverse,
+                        );
+                        if self.filter_source.is_some() {
+                            self.entries = self.filtered_entries();
+                        }
+                    }
                 }
                 OpResult::Ls(Err(e)) => {
                     self.finish_loading();
-                    self.push_log(format!("Refresh failed: {e:#}"));
+                    if self.went_offline(&e) {
+                        self.show_offline_listing();
+                    } else {
+                        self.push_log(format!("Refresh failed: {e:#}"));
+                        self.prompt_relogin(&e);
+                    }
+                }
+                OpResult::PaneLs(folder_id, result) => {
+                    self.apply_pane_listing(folder_id, result);
                 }
                 OpResult::Ok(msg) => {
                     self.push_log(msg);
@@ -573,6 +1007,13 @@ impl App {
                     self.push_log(msg);
                     self.finish_loading();
                 }
+                OpResult::Log(msg) => {
+                    self.push_log(msg);
+                }
+                OpResult::Activity(result) => self.apply_activity(result),
+                OpResult::Job(id, result) => {
+                    self.finish_op(id, result);
+                }
                 OpResult::Info(Ok(info), thumb_fallback) => {
                     self.finish_loading();
                     if matches!(self.input, InputMode::InfoLoading) {
@@ -582,14 +1023,23 @@ impl App {
                             .filter(|u| !u.is_empty())
                             .or_else(|| thumb_fallback.filter(|u| !u.is_empty()));
                         let has_thumbnail = thumb_url.is_some();
+                        let zip_id = info.id.clone().filter(|_| archive::is_zip(&info.name));
                         self.input = InputMode::InfoView {
                             info,
                             image: None,
                             has_thumbnail,
+                            archive: None,
                         };
                         if let Some(url) = thumb_url {
                             self.spawn_thumbnail_fetch(url, OpResult::InfoThumbnail);
                         }
+                        if let Some(id) = zip_id {
+                            let client = Arc::clone(&self.client);
+                            let tx = self.result_tx.clone();
+                            self.workers.spawn(move || {
+                                let _ = tx.send(OpResult::InfoArchive(archive::list(&client, &id)));
+                            });
+                        }
                     }
                 }
                 OpResult::Info(Err(e), _) => {
@@ -599,28 +1049,17 @@ impl App {
                     }
                     self.push_log(format!("File info failed: {e:#}"));
                 }
-                OpResult::ParentLs(pid, Ok(mut entries)) => {
-                    let expected = self.breadcrumb.last().map(|(id, _)| id.as_str());
-                    if expected == Some(&pid) {
-                        crate::config::sort_entries(
-                            &mut entries,
-                            self.config.sort_field,
-                            self.config.sort_reverse,
-                        );
-                        self.parent_entries = entries;
-                        if let Some(pos) = self
-                            .parent_entries
-                            .iter()
-                            .position(|e| e.id == self.current_folder_id)
-                        {
-                            self.parent_selected = pos;
-                        }
-                    }
+                OpResult::ParentLs(pid, Ok(entries)) => {
+                    self.show_parent_listing(&pid, entries);
                 }
                 OpResult::ParentLs(pid, Err(e)) => {
                     let expected = self.breadcrumb.last().map(|(id, _)| id.as_str());
                     if expected == Some(&pid) {
-                        self.push_log(format!("Parent listing failed: {e:#}"));
+                        if !self.went_offline(&e) {
+                            self.push_log(format!("Parent listing failed: {e:#}"));
+                        } else if let Some(entries) = self.client.last_listing(&pid) {
+                            self.show_parent_listing(&pid, entries);
+                        }
                     }
                 }
                 OpResult::PreviewLs(id, Ok(mut children)) => {
@@ -638,20 +1077,27 @@ impl App {
                             name,
                             entries: children,
                         };
-                    } else if self.preview_target_id.as_deref() == Some(&id) {
-                        self.preview_state = PreviewState::FolderListing(children);
+                    } else {
+                        self.note_preview(false);
+                        if self.preview_target_id.as_deref() == Some(&id) {
+                            self.preview_state = PreviewState::FolderListing(children);
+                        }
                     }
                 }
                 OpResult::PreviewLs(id, Err(e)) => {
                     if matches!(self.input, InputMode::InfoLoading) {
                         self.finish_loading();
                         self.input = InputMode::Normal;
-                    } else if self.preview_target_id.as_deref() == Some(&id) {
-                        self.preview_state = PreviewState::Empty;
+                    } else {
+                        if self.preview_target_id.as_deref() == Some(&id) {
+                            self.preview_state = PreviewState::Empty;
+                        }
+                        self.note_preview(true);
                     }
                     self.push_log(format!("Folder listing failed: {e:#}"));
                 }
                 OpResult::PreviewInfo(id, Ok(info)) => {
+                    self.note_preview(false);
                     if self.preview_target_id.as_deref() == Some(&id) {
                         self.preview_state = PreviewState::FileDetailedInfo(info);
                     }
@@ -661,9 +1107,23 @@ impl App {
                         self.preview_state = PreviewState::Empty;
                     }
                     self.push_log(format!("Preview info failed: {e:#}"));
+                    self.note_preview(true);
                 }
-                OpResult::PreviewText(id, Ok((name, content, size, truncated))) => {
-                    let lines = highlight_content(&name, &content);
+                OpResult::PreviewText(id, Ok(preview)) => {
+                    if let Some(e) = &preview.handler_error {
+                        self.push_log(format!("Preview handler failed: {e}"));
+                    }
+                    let lines = if preview.handled {
+                        preview_handler::ansi_lines(&preview.content, self.config.color_depth)
+                    } else {
+                        highlight_content(&preview.name, &preview.content, self.config.color_depth)
+                    };
+                    let preview_handler::TextPreview {
+                        name,
+                        size,
+                        truncated,
+                        ..
+                    } = preview;
                     if matches!(self.input, InputMode::InfoLoading) {
                         self.finish_loading();
                         self.input = InputMode::TextPreviewView {
@@ -678,25 +1138,32 @@ impl App {
                             truncated,
                         };
                         self.preview_target_id = Some(id);
-                    } else if self.preview_target_id.as_deref() == Some(&id) {
-                        self.preview_state = PreviewState::FileTextPreview {
-                            name,
-                            lines,
-                            size,
-                            truncated,
-                        };
+                    } else {
+                        self.note_preview(false);
+                        if self.preview_target_id.as_deref() == Some(&id) {
+                            self.preview_state = PreviewState::FileTextPreview {
+                                name,
+                                lines,
+                                size,
+                                truncated,
+                            };
+                        }
                     }
                 }
                 OpResult::PreviewText(id, Err(e)) => {
                     if matches!(self.input, InputMode::InfoLoading) {
                         self.finish_loading();
                         self.input = InputMode::Normal;
-                    } else if self.preview_target_id.as_deref() == Some(&id) {
-                        self.preview_state = PreviewState::FileBasicInfo;
+                    } else {
+                        if self.preview_target_id.as_deref() == Some(&id) {
+                            self.preview_state = PreviewState::FileBasicInfo;
+                        }
+                        self.note_preview(true);
                     }
                     self.push_log(format!("Text preview failed: {e:#}"));
                 }
                 OpResult::PreviewThumbnail(id, Ok(image)) => {
+                    self.note_preview(false);
                     if self.preview_target_id.as_deref() == Some(&id) {
                         self.preview_state = PreviewState::ThumbnailImage { image };
                     }
@@ -706,13 +1173,46 @@ impl App {
                         self.preview_state = PreviewState::FileBasicInfo;
                     }
                     self.push_log(format!("Thumbnail preview failed: {e:#}"));
+                    self.note_preview(true);
+                }
+                OpResult::PreviewArchive(id, Ok(listing)) => {
+                    self.note_preview(false);
+                    if self.preview_target_id.as_deref() == Some(&id) {
+                        self.preview_state = PreviewState::ArchiveListing(listing);
+                    }
+                }
+                OpResult::PreviewArchive(id, Err(e)) => {
+                    if self.preview_target_id.as_deref() == Some(&id) {
+                        self.preview_state = PreviewState::FileBasicInfo;
+                    }
+                    self.push_log(format!("Archive preview failed: {e:#}"));
+                    self.note_preview(true);
+                }
+                OpResult::PreviewMedia(id, video) => {
+                    if let Some(video) = video {
+                        self.preview_media = Some((id, video));
+                    }
                 }
+                OpResult::GalleryImage(id, result) => self.store_gallery_image(id, result),
                 OpResult::OfflineTasks(Ok(tasks)) => {
                     self.finish_loading();
                     if matches!(self.input, InputMode::InfoLoading) {
                         self.input = InputMode::OfflineTasksView { tasks, selected: 0 };
                     }
                 }
+                OpResult::AutoFetch(batches) => {
+                    self.auto_fetch_busy = false;
+                    self.queue_auto_fetched(batches);
+                }
+                OpResult::IndexStep(step) => self.on_index_step(step),
+                OpResult::Cleanup(result) => self.on_cleanup(result),
+                OpResult::Delegated(batch) => {
+                    self.push_log(format!(
+                        "Queued {} download(s) from another pikpaktui window",
+                        batch.len()
+                    ));
+                    self.enqueue_downloads(batch, "From another pikpaktui window");
+                }
                 OpResult::OfflineTasks(Err(e)) => {
                     self.finish_loading();
                     if matches!(self.input, InputMode::InfoLoading) {
@@ -735,8 +1235,10 @@ impl App {
                         self.push_log("No playback URL available".into());
                     } else {
                         self.input = InputMode::ConfirmPlay {
+                            file_id: info.id.clone().unwrap_or_default(),
                             name: info.name.clone(),
                             url,
+                            subs: subtitles::Subtitles::none(),
                         };
                     }
                 }
@@ -744,16 +1246,18 @@ impl App {
                     self.finish_loading();
                     self.push_log(format!("Play info failed: {e:#}"));
                 }
-                OpResult::PlayPickerInfo(Ok((info, medias))) => {
+                OpResult::PlayPickerInfo(Ok((info, medias, subs))) => {
                     self.finish_loading();
                     if medias.is_empty() {
                         self.push_log("No playback streams available".into());
                     } else {
                         let first_avail = medias.iter().position(|m| m.available).unwrap_or(0);
                         self.input = InputMode::PlayPicker {
+                            file_id: info.id.clone().unwrap_or_default(),
                             name: info.name.clone(),
                             medias,
                             selected: first_avail,
+                            subs: subtitles::Subtitles::new(subs),
                         };
                     }
                 }
@@ -768,6 +1272,9 @@ impl App {
                     } else {
                         self.trash_expanded
                     };
+                    // Drop marks for items that were restored or purged.
+                    self.trash_marked
+                        .retain(|id| entries.iter().any(|e| &e.id == id));
                     self.trash_entries = entries.clone();
                     self.trash_selected = 0;
                     self.trash_expanded = expanded;
@@ -801,10 +1308,24 @@ impl App {
                 OpResult::InfoThumbnail(Err(e)) => {
                     self.push_log(format!("Info thumbnail failed: {e:#}"));
                 }
-                OpResult::GotoPath(Ok((folder_id, new_breadcrumb))) => {
+                OpResult::InfoArchive(Ok(listing)) => {
+                    if let InputMode::InfoView {
+                        ref mut archive, ..
+                    } = self.input
+                    {
+                        *archive = Some(listing);
+                    }
+                }
+                OpResult::InfoArchive(Err(e)) => {
+                    self.push_log(format!("Archive listing failed: {e:#}"));
+                }
+                OpResult::GotoPath(Ok((folder_id, new_breadcrumb, file))) => {
                     self.finish_loading();
+                    self.filter_source = None;
+                    self.filter_query.clear();
                     self.breadcrumb = new_breadcrumb;
-                    self.current_folder_id = folder_id.clone();
+                    self.current_folder_id = folder_id;
+                    self.entries.clear();
                     self.selected = 0;
                     self.parent_entries.clear();
                     self.parent_selected = 0;
@@ -812,12 +1333,14 @@ impl App {
                     // otherwise leaves it blank until the next move.
                     self.refresh_parent();
                     self.clear_preview();
-                    self.loading = true;
-                    let client = Arc::clone(&self.client);
-                    let tx = self.result_tx.clone();
-                    std::thread::spawn(move || {
-                        let _ = tx.send(OpResult::Ls(client.ls(&folder_id)));
-                    });
+                    if let Some(file) = file {
+                        // A full (cursor-preserving) reload keeps the cursor
+                        // on the file once the listing arrives.
+                        self.entries = vec![file];
+                        self.refresh_current();
+                    } else {
+                        self.spawn_ls_streaming();
+                    }
                 }
                 OpResult::GotoPath(Err(e)) => {
                     self.finish_loading();
@@ -832,12 +1355,38 @@ impl App {
                 OpResult::Quota(Err(e)) => {
                     self.push_log(format!("Quota fetch failed: {e:#}"));
                 }
+                OpResult::TransferQuota(Ok(resp)) => {
+                    // Daily and overall download caps can both apply; the one
+                    // with less headroom is what a queued download will hit.
+                    self.transfer_quota = resp.base.and_then(|base| {
+                        [base.download, base.download_daily]
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|band| {
+                                let total = band.total_assets.unwrap_or(0);
+                                (total > 0).then(|| (band.assets.unwrap_or(0), total))
+                            })
+                            .min_by_key(|&(used, total)| total.saturating_sub(used))
+                    });
+                }
+                OpResult::TransferQuota(Err(e)) => {
+                    self.push_log(format!("Transfer quota fetch failed: {e:#}"));
+                }
+                OpResult::UploadPlan(result) => self.apply_upload_plan(result),
+                OpResult::BatchConflicts(to, result) => self.apply_batch_conflicts(to, result),
+                OpResult::UploadProgress(label) => {
+                    if self.loading {
+                        self.loading_label = Some(label);
+                    }
+                }
                 OpResult::Upload(Ok(msg)) => {
+                    self.uploads_running = self.uploads_running.saturating_sub(1);
                     self.finish_loading();
                     self.push_log(msg);
                     self.refresh();
                 }
                 OpResult::Upload(Err(e)) => {
+                    self.uploads_running = self.uploads_running.saturating_sub(1);
                     self.finish_loading();
                     self.push_log(format!("Upload failed: {e:#}"));
                 }
@@ -889,6 +1438,31 @@ impl App {
                     self.update_available = Some(version);
                 }
                 OpResult::UpdateAvailable(None) => {}
+                OpResult::ProfileSwitched(profile, Ok(client)) => {
+                    self.finish_profile_switch(profile, client);
+                }
+                OpResult::ProfileSwitched(profile, Err(e)) => {
+                    self.push_log(format!(
+                        "Cannot switch to profile '{}': {e:#}",
+                        profile.as_deref().unwrap_or(crate::config::DEFAULT_PROFILE)
+                    ));
+                }
+                OpResult::BrowserLoginReady(url) => {
+                    if let Some(waiting) = &mut self.browser_login {
+                        *waiting = url;
+                    }
+                }
+                OpResult::Unlock(result) => self.finish_unlock(result),
+                OpResult::BrowserLogin(client, result) => {
+                    self.finish_browser_login(*client, result)
+                }
+                OpResult::Watched(Ok(msg)) => {
+                    self.reload_playback();
+                    self.push_log(msg);
+                }
+                OpResult::Watched(Err(e)) => {
+                    self.push_log(format!("Watched state failed: {e:#}"));
+                }
             }
         }
 
@@ -896,8 +1470,17 @@ impl App {
         for msg in logs {
             self.push_log(msg);
         }
+        self.run_completions();
+        if self.download_state.window.is_some()
+            && let Some(msg) = self
+                .download_state
+                .apply_window(&self.client, download::local_minute_of_day())
+        {
+            self.push_log(msg);
+        }
 
         if self.last_network_update.elapsed() >= Duration::from_millis(500) {
+            self.prune_finished_downloads();
             let current_speed: f64 = self
                 .download_state
                 .tasks
@@ -905,24 +1488,89 @@ impl App {
                 .filter(|t| t.status == download::TaskStatus::Downloading)
                 .map(|t| t.speed / 1_048_576.0) // Convert to MB/s
                 .sum();
-            self.network_stats.update(current_speed);
+            self.network_stats
+                .update(current_speed, self.client.api_limiter.waiting());
             self.last_network_update = Instant::now();
         }
     }
 
-    fn attempt_login(&mut self, email: &str, password: &str) {
-        let Some(client) = Arc::get_mut(&mut self.client) else {
-            self.push_log("Cannot login: client is in use by background tasks".to_string());
+    /// A client of its own to log in with, set up like the shared one.
+    /// Background tasks may still hold the shared client, so the login's
+    /// client takes its place once the login is done; the tasks read the
+    /// new session from disk on their next call.
+    fn login_client(&self) -> Result<PikPak> {
+        let mut client = PikPak::new()?;
+        // Its warnings were logged at startup.
+        self.config.configure_client(&mut client)?;
+        Ok(client)
+    }
+
+    /// Save the settings as they are now, logging anything that went wrong.
+    fn save_config(&mut self) {
+        match self.config.save() {
+            Ok(warnings) => {
+                for warning in warnings {
+                    self.push_log(format!("Warning: {warning}"));
+                }
+            }
+            Err(e) => self.push_log(format!("Failed to save config: {e:#}")),
+        }
+    }
+
+    /// Back to the login form when the session can no longer be renewed
+    /// (refresh or captcha token rejected), prefilled with the saved login,
+    /// rather than failing every call after.
+    fn prompt_relogin(&mut self, err: &anyhow::Error) {
+        if crate::pikpak::failure_kind(err) != Some(crate::pikpak::FailureKind::Auth)
+            || matches!(
+                self.input,
+                InputMode::Login { .. } | InputMode::LoginCode { .. }
+            )
+        {
             return;
+        }
+        let (email, password) = AppConfig::load()
+            .ok()
+            .and_then(|c| c.credentials())
+            .unwrap_or_default();
+        self.input = InputMode::Login {
+            field: if email.is_empty() {
+                LoginField::Email
+            } else {
+                LoginField::Password
+            },
+            email,
+            password,
+            error: Some("Session expired, please log in again".to_string()),
+            logging_in: false,
         };
-        match client.login(email, password) {
-            Ok(()) => {
-                if let Err(e) = AppConfig::save_credentials(email, password) {
-                    self.push_log(format!("Warning: failed to save config: {e:#}"));
-                }
-                self.input = InputMode::Normal;
-                self.refresh();
-                self.push_log("Login successful".to_string());
+    }
+
+    fn attempt_login(&mut self, email: &str, password: &str) {
+        let mut client = match self.login_client() {
+            Ok(client) => client,
+            Err(e) => {
+                self.push_log(format!("Cannot login: {e:#}"));
+                return;
+            }
+        };
+        match client.start_login(email, password) {
+            Ok(LoginStep::Done) => {
+                self.client = Arc::new(client);
+                self.logged_in(email, password);
+            }
+            Ok(LoginStep::NeedsCode(pending)) => {
+                self.pending_login = Some(client);
+                self.input = InputMode::LoginCode {
+                    pending: Box::new(pending),
+                    code: String::new(),
+                    error: None,
+                };
+            }
+            // No network but a saved session: browse the cache, and let
+            // that session be renewed once PikPak answers again.
+            Err(e) if self.client.has_session() && self.went_offline(&e) => {
+                self.browse_offline();
             }
             Err(e) => {
                 self.input = InputMode::Login {
@@ -936,6 +1584,176 @@ impl App {
         }
     }
 
+    fn show_parent_listing(&mut self, pid: &str, mut entries: Vec<Entry>) {
+        let expected = self.breadcrumb.last().map(|(id, _)| id.as_str());
+        if expected != Some(pid) {
+            return;
+        }
+        crate::config::sort_entries(
+            &mut entries,
+            self.config.sort_field,
+            self.config.sort_reverse,
+        );
+        self.parent_entries = entries;
+        if let Some(pos) = self
+            .parent_entries
+            .iter()
+            .position(|e| e.id == self.current_folder_id)
+        {
+            self.parent_selected = pos;
+        }
+    }
+
+    /// Finish a login with the emailed verification code; a wrong one
+    /// leaves the code prompt up to try again.
+    fn submit_login_code(&mut self, pending: Box<PendingLogin>, code: String) {
+        let client = match self.pending_login.take() {
+            Some(client) => Ok(client),
+            None => self.login_client(),
+        };
+        let mut client = match client {
+            Ok(client) => client,
+            Err(e) => {
+                self.push_log(format!("Cannot login: {e:#}"));
+                self.input = InputMode::LoginCode {
+                    pending,
+                    code,
+                    error: None,
+                };
+                return;
+            }
+        };
+        match client.finish_login(&pending, &code) {
+            Ok(()) => {
+                self.client = Arc::new(client);
+                self.logged_in(pending.email(), pending.password());
+            }
+            Err(e) => {
+                self.pending_login = Some(client);
+                self.input = InputMode::LoginCode {
+                    pending,
+                    code: String::new(),
+                    error: Some(format!("Verification failed: {e:#}")),
+                };
+            }
+        }
+    }
+
+    fn logged_in(&mut self, email: &str, password: &str) {
+        if let Err(e) = AppConfig::save_credentials(email, password) {
+            self.push_log(format!("Warning: failed to save config: {e:#}"));
+        }
+        self.input = InputMode::Normal;
+        self.reload_notes();
+        self.reload_playback();
+        self.refresh();
+        self.push_log("Login successful".to_string());
+    }
+
+    /// `Ctrl+B` on the login screen: log in with the email and password,
+    /// solving the captcha PikPak asks for in the browser. The wait can be
+    /// long, so it gets its own thread rather than a worker, and a client
+    /// of its own that's swapped in when it's done.
+    fn start_browser_login(&mut self, email: String, password: String) {
+        let cancel = self.browser_login_cancel.renew();
+        let mut client = match self.login_client() {
+            Ok(client) => client,
+            Err(e) => {
+                self.finish_browser_login_error(e);
+                return;
+            }
+        };
+        self.browser_login = Some(String::new());
+        let tx = self.result_tx.clone();
+        std::thread::spawn(move || {
+            let ready = tx.clone();
+            let result = client.login_in_browser(
+                &email,
+                &password,
+                crate::pikpak::BROWSER_LOGIN_TIMEOUT,
+                || cancel.is_cancelled(),
+                |url| {
+                    let _ = crate::local_path::open_default(url.as_ref());
+                    let _ = ready.send(OpResult::BrowserLoginReady(url.to_string()));
+                },
+            );
+            let _ = tx.send(OpResult::BrowserLogin(Box::new(client), result));
+        });
+    }
+
+    /// Esc while the browser login waits.
+    fn cancel_browser_login(&mut self) {
+        self.browser_login_cancel.cancel();
+        self.browser_login = None;
+    }
+
+    fn finish_browser_login(&mut self, client: PikPak, result: Result<LoginStep>) {
+        // Cancelled with Esc in the meantime.
+        if self.browser_login.take().is_none() {
+            return;
+        }
+        let (email, password) = match &self.input {
+            InputMode::Login {
+                email, password, ..
+            } => (email.clone(), password.clone()),
+            _ => return,
+        };
+        match result {
+            Ok(LoginStep::Done) => {
+                self.client = Arc::new(client);
+                self.logged_in(&email, &password);
+            }
+            Ok(LoginStep::NeedsCode(pending)) => {
+                self.pending_login = Some(client);
+                self.input = InputMode::LoginCode {
+                    pending: Box::new(pending),
+                    code: String::new(),
+                    error: None,
+                };
+            }
+            Err(e) => self.finish_browser_login_error(e),
+        }
+    }
+
+    fn finish_browser_login_error(&mut self, e: anyhow::Error) {
+        if let InputMode::Login {
+            error, logging_in, ..
+        } = &mut self.input
+        {
+            *error = Some(format!("Browser login failed: {e:#}"));
+            *logging_in = false;
+        }
+    }
+
+    /// Mirror the current path into the terminal title and download progress
+    /// into the tab/taskbar indicator, writing only when either changes.
+    fn sync_terminal_status(&mut self) {
+        if matches!(
+            self.input,
+            InputMode::Login { .. } | InputMode::LoginCode { .. }
+        ) {
+            return;
+        }
+        let title = if self.lock.is_some() {
+            "pikpaktui — locked".to_string()
+        } else {
+            format!("pikpaktui — {}", self.current_path_display())
+        };
+        if title != self.term_title {
+            term_status::set_title(&title);
+            self.term_title = title;
+        }
+        let progress = if self.config.terminal_progress {
+            self.download_state.overall_progress()
+        } else {
+            None
+        };
+        if progress != self.term_progress {
+            term_status::set_progress(progress);
+            self.term_progress = progress;
+        }
+    }
+
     fn current_path_display(&self) -> String {
         if self.breadcrumb.is_empty() {
             "/".to_string()
@@ -958,11 +1776,64 @@ impl App {
         self.entries.get(self.selected)
     }
 
+    /// Entries of `filter_source` matching the current filter query, in
+    /// listing order.
+    fn filtered_entries(&self) -> Vec<Entry> {
+        self.filter_source
+            .iter()
+            .flatten()
+            .filter(|e| {
+                // `#label` filters by the local labels instead of the name.
+                if self.filter_query.starts_with('#') {
+                    self.matches_label_query(e, &self.filter_query)
+                } else {
+                    filter::fuzzy_match(&e.name, &self.filter_query).is_some()
+                }
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Re-narrow `entries` after the filter query changed, keeping the cursor
+    /// on the same entry when it still matches.
+    fn apply_filter(&mut self) {
+        let prev_id = self.current_entry().map(|e| e.id.clone());
+        if self.filter_source.is_none() {
+            self.filter_source = Some(std::mem::take(&mut self.entries));
+        }
+        self.entries = self.filtered_entries();
+        self.selected = prev_id
+            .and_then(|id| self.entries.iter().position(|e| e.id == id))
+            .unwrap_or(0);
+        self.on_cursor_move();
+    }
+
+    /// Drop the filter and restore the full listing, keeping the cursor on the
+    /// selected entry.
+    fn clear_filter(&mut self) {
+        self.filter_query.clear();
+        let Some(source) = self.filter_source.take() else {
+            return;
+        };
+        let prev_id = self.current_entry().map(|e| e.id.clone());
+        self.entries = source;
+        self.selected = prev_id
+            .and_then(|id| self.entries.iter().position(|e| e.id == id))
+            .unwrap_or(0);
+    }
+
     fn finish_loading(&mut self) {
         self.loading = false;
         self.loading_label = None;
     }
 
+    /// Drop the current folder's listing still in flight; nothing is
+    /// loading any more until the next one starts.
+    fn cancel_listing(&mut self) {
+        self.ls_cancel.cancel();
+        self.finish_loading();
+    }
+
     fn push_log(&mut self, msg: String) {
         self.logs.push_back(msg);
         if self.logs.len() > 500 {
@@ -975,48 +1846,288 @@ impl App {
             return;
         }
         let tx = self.result_tx.clone();
-        std::thread::spawn(move || {
+        self.workers.spawn(move || {
             let _ = tx.send(OpResult::UpdateAvailable(
                 crate::cmd::update::check_for_update(),
             ));
         });
     }
 
+    fn open_profile_picker(&mut self) {
+        let profiles = match AppConfig::load() {
+            Ok(cfg) => cfg.profile_names(),
+            Err(e) => {
+                self.push_log(format!("Cannot read login.toml: {e:#}"));
+                return;
+            }
+        };
+        let current = crate::config::profile();
+        let selected = profiles
+            .iter()
+            .position(|p| Some(p) == current.as_ref())
+            .unwrap_or(0);
+        self.input = InputMode::ProfilePicker { profiles, selected };
+    }
+
+    /// Sign in to `name` in the background; the session is swapped in by
+    /// `finish_profile_switch`. Refused while downloads are unfinished or
+    /// uploads are running, as they belong to the current account.
+    fn switch_profile(&mut self, name: &str) {
+        let target = (name != crate::config::DEFAULT_PROFILE).then(|| name.to_string());
+        if target == crate::config::profile() {
+            return;
+        }
+        if self
+            .download_state
+            .tasks
+            .iter()
+            .any(|t| t.status != download::TaskStatus::Done)
+        {
+            self.push_log(
+                "Finish or remove the unfinished downloads before switching accounts".into(),
+            );
+            return;
+        }
+        if self.uploads_running > 0 {
+            self.push_log("Wait for the uploads to finish before switching accounts".into());
+            return;
+        }
+        self.push_log(format!("Switching to profile '{name}'..."));
+        let config = self.config.clone();
+        let tx = self.result_tx.clone();
+        self.workers.spawn(move || {
+            let result = (|| -> Result<PikPak> {
+                let mut client = PikPak::new()?;
+                config.configure_client(&mut client)?;
+                client.use_profile(target.as_deref())?;
+                if !client.has_valid_session() {
+                    let Some((user, password)) =
+                        AppConfig::load()?.credentials_for(target.as_deref())
+                    else {
+                        let flag = target
+                            .as_deref()
+                            .map_or_else(String::new, |t| format!("--profile {t} "));
+                        anyhow::bail!("no saved login; run `pikpaktui {flag}login` first");
+                    };
+                    client.login(&user, &password)?;
+                }
+                Ok(client)
+            })();
+            let _ = tx.send(OpResult::ProfileSwitched(target, result));
+        });
+    }
+
+    /// Stop what was started under the profile being left: listings and
+    /// previews stop paging, and every other worker's result goes to a
+    /// channel nobody reads any more, so none of it lands in the new one.
+    fn cancel_profile_work(&mut self) {
+        self.ls_cancel.cancel();
+        self.parent_cancel.cancel();
+        self.preview_cancel.cancel();
+        (self.result_tx, self.result_rx) = mpsc::channel();
+    }
+
+    /// Swap in the client of the new profile and start over at its root,
+    /// with that profile's download queue.
+    fn finish_profile_switch(&mut self, profile: Option<String>, client: PikPak) {
+        download::save_download_state(&mut self.download_state);
+        if let Err(e) = crate::config::set_profile(profile.as_deref()) {
+            self.push_log(format!("Switch failed: {e:#}"));
+            return;
+        }
+        self.client = Arc::new(client);
+        self.cancel_profile_work();
+        // Let go of this profile's queue before claiming the next one's,
+        // which may be the same.
+        self.instance = instance::Role::Primary { _lock: None };
+        self.instance = instance::claim(self.result_tx.clone());
+        self.download_state = DownloadState::new(self.config.download_jobs);
+        if self.instance.is_primary() {
+            self.download_state
+                .load_tasks(download::load_download_state());
+        }
+        self.load_download_settings();
+        self.log_other_instance();
+        self.log_interrupted_uploads();
+
+        self.clear_filter();
+        self.reset_tabs();
+        self.current_folder_id = String::new();
+        self.breadcrumb.clear();
+        self.entries.clear();
+        self.parent_entries.clear();
+        self.selected = 0;
+        self.parent_selected = 0;
+        self.cart.clear();
+        self.cart_ids.clear();
+        self.reload_notes();
+        self.reload_playback();
+        self.jobs = ops::OpQueue::default();
+        self.activity = activity::ActivityFeed::default();
+        self.trash_entries.clear();
+        self.trash_marked.clear();
+        self.quota_used = None;
+        self.quota_limit = None;
+        self.transfer_quota = None;
+        self.clear_preview();
+        self.ensure_commander_panes();
+        self.spawn_ls_streaming();
+        self.fetch_quota();
+        self.push_log(format!(
+            "Switched to profile '{}'",
+            profile.as_deref().unwrap_or(crate::config::DEFAULT_PROFILE)
+        ));
+    }
+
     fn fetch_quota(&mut self) {
         let client = Arc::clone(&self.client);
         let tx = self.result_tx.clone();
-        std::thread::spawn(move || {
+        self.workers.spawn(move || {
             let _ = tx.send(OpResult::Quota(client.quota()));
         });
     }
 
-    fn refresh(&mut self) {
+    fn fetch_transfer_quota(&mut self) {
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        self.workers.spawn(move || {
+            let _ = tx.send(OpResult::TransferQuota(client.transfer_quota()));
+        });
+    }
+
+    /// Open the download dashboard, refreshing the transfer quota it projects
+    /// the queue against.
+    fn open_download_view(&mut self) {
+        self.fetch_transfer_quota();
+        self.input = InputMode::DownloadView;
+    }
+
+    /// Load the (empty) current folder, showing entries page by page as they
+    /// stream in. Refreshes of an already-listed folder use `refresh` instead,
+    /// which swaps the listing in only once it's complete.
+    fn spawn_ls_streaming(&mut self) {
+        if self.in_virtual_folder() {
+            self.refresh();
+            return;
+        }
+        // Paint the last known listing right away, then reconcile with a
+        // normal (cursor-preserving) reload instead of streaming into it.
+        // Offline, any cached listing beats none, however old.
+        let cached = if self.offline.is_some() {
+            self.client.last_listing(&self.current_folder_id)
+        } else {
+            self.client.cached_listing(&self.current_folder_id)
+        };
+        if let Some(mut cached) = cached {
+            crate::config::sort_entries(
+                &mut cached,
+                self.config.sort_field,
+                self.config.sort_reverse,
+            );
+            self.entries = cached;
+            self.on_cursor_move();
+            self.refresh_current();
+            return;
+        }
         self.loading = true;
         let client = Arc::clone(&self.client);
         let tx = self.result_tx.clone();
         let fid = self.current_folder_id.clone();
-        std::thread::spawn(move || {
-            let _ = tx.send(OpResult::Ls(client.ls(&fid)));
+        let cancel = self.ls_cancel.renew();
+        self.workers.spawn_cancellable(cancel, move |cancel| {
+            let result = client.ls_streaming(&fid, |page| {
+                if !cancel.is_cancelled() {
+                    let _ = tx.send(OpResult::LsPage(fid.clone(), page.to_vec()));
+                }
+                continue_unless(cancel)
+            });
+            if !cancel.is_cancelled() {
+                let _ = tx.send(OpResult::Ls(result));
+            }
         });
+    }
+
+    fn refresh(&mut self) {
+        self.refresh_current();
         self.refresh_parent();
+        self.refresh_idle_pane();
         self.fetch_quota();
     }
 
+    /// Reload the current folder, superseding any listing still in flight.
+    fn refresh_current(&mut self) {
+        self.loading = true;
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        let fid = self.current_folder_id.clone();
+        let days = self.config.recent_days;
+        let cancel = self.ls_cancel.renew();
+        self.workers.spawn_cancellable(cancel, move |cancel| {
+            let result = list_folder(&client, &fid, days, cancel);
+            if !cancel.is_cancelled() {
+                let _ = tx.send(OpResult::Ls(result));
+            }
+        });
+    }
+
+    fn in_recent(&self) -> bool {
+        self.current_folder_id == RECENT_FOLDER_ID
+    }
+
+    fn in_virtual_folder(&self) -> bool {
+        is_virtual_folder(&self.current_folder_id)
+    }
+
+    /// Name of the current folder as shown in the breadcrumb.
+    fn current_folder_name(&self) -> &str {
+        self.breadcrumb
+            .last()
+            .map_or("/", |(_, name)| name.as_str())
+    }
+
+    /// Jump to the virtual "Recent" folder, placed directly under the root.
+    fn open_recent(&mut self) {
+        self.open_virtual_folder(RECENT_FOLDER_ID, "Recent");
+    }
+
+    /// Jump to the virtual "Starred" folder, placed directly under the root.
+    fn open_starred(&mut self) {
+        self.open_virtual_folder(STARRED_FOLDER_ID, "Starred");
+    }
+
+    fn open_virtual_folder(&mut self, folder_id: &str, name: &str) {
+        self.clear_filter();
+        self.breadcrumb = vec![(String::new(), name.to_string())];
+        self.current_folder_id = folder_id.to_string();
+        self.entries.clear();
+        self.selected = 0;
+        self.parent_selected = 0;
+        self.clear_preview();
+        self.refresh();
+    }
+
     fn refresh_parent(&mut self) {
         if let Some((parent_id, _)) = self.breadcrumb.last() {
             let client = Arc::clone(&self.client);
             let tx = self.result_tx.clone();
             let pid = parent_id.clone();
-            std::thread::spawn(move || {
-                let _ = tx.send(OpResult::ParentLs(pid.clone(), client.ls(&pid)));
+            let days = self.config.recent_days;
+            let cancel = self.parent_cancel.renew();
+            self.workers.spawn_cancellable(cancel, move |cancel| {
+                let result = list_folder(&client, &pid, days, cancel);
+                if !cancel.is_cancelled() {
+                    let _ = tx.send(OpResult::ParentLs(pid, result));
+                }
             });
         } else {
+            self.parent_cancel.cancel();
             self.parent_entries.clear();
             self.parent_selected = 0;
         }
     }
 
     fn clear_preview(&mut self) {
+        self.preview_cancel.cancel();
         self.preview_state = PreviewState::Empty;
         self.preview_target_id = None;
         self.preview_target_name = None;
@@ -1024,9 +2135,40 @@ impl App {
         self.preview_scroll = 0;
     }
 
+    /// Count a pane preview's outcome. After `PREVIEW_FAILURE_LIMIT`
+    /// failures in a row (a blocked CDN, say) auto-preview is paused for the
+    /// session instead of hitting the failing endpoint on every cursor move;
+    /// the preview key turns it back on.
+    fn note_preview(&mut self, failed: bool) {
+        if !failed {
+            self.preview_failures = 0;
+            return;
+        }
+        self.preview_failures += 1;
+        if self.preview_failures >= PREVIEW_FAILURE_LIMIT
+            && self.config.lazy_preview
+            && !self.preview_paused
+        {
+            self.preview_paused = true;
+            self.push_log(format!(
+                "{} previews failed in a row; auto-preview paused (press {} to resume)",
+                self.preview_failures,
+                self.keymap.label(keymap::Action::Preview)
+            ));
+        }
+    }
+
+    /// Whether the preview column is on screen; commander mode replaces it
+    /// with the second pane.
+    fn preview_pane_shown(&self) -> bool {
+        self.config.show_preview && !self.config.commander_mode
+    }
+
     fn on_cursor_move(&mut self) {
+        // Whatever was being previewed is no longer under the cursor.
+        self.preview_cancel.cancel();
         self.preview_scroll = 0;
-        if !self.config.show_preview {
+        if !self.preview_pane_shown() {
             return;
         }
         self.last_cursor_move = Instant::now();
@@ -1049,14 +2191,72 @@ impl App {
         }
     }
 
-    fn spawn_thumbnail_fetch<F>(&self, url: String, make_result: F)
+    fn spawn_thumbnail_fetch<F>(&mut self, url: String, make_result: F)
     where
         F: FnOnce(Result<image::DynamicImage>) -> OpResult + Send + 'static,
     {
         let client = Arc::clone(&self.client);
         let tx = self.result_tx.clone();
-        std::thread::spawn(move || {
-            let _ = tx.send(make_result(fetch_and_render_thumbnail(&url, &client)));
+        let cancel = self.preview_cancel.renew();
+        self.workers.spawn_cancellable(cancel, move |cancel| {
+            let result = fetch_and_render_thumbnail(&url, &client);
+            if !cancel.is_cancelled() {
+                let _ = tx.send(make_result(result));
+            }
+        });
+    }
+
+    /// Text files, and any file with a `[preview.handlers]` command.
+    fn text_previewable(&self, entry: &Entry) -> bool {
+        theme::is_text_previewable(entry)
+            || (entry.kind == EntryKind::File
+                && self.config.preview.handler_for(&entry.name).is_some())
+    }
+
+    /// Fetch the start of a file for the text preview, through its handler
+    /// if it has one.
+    fn spawn_text_preview(&self, entry: &Entry, cancel: worker::CancelToken) {
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        let eid = entry.id.clone();
+        let max_bytes = self.config.preview_max_size;
+        let handler = self
+            .config
+            .preview
+            .handler_for(&entry.name)
+            .map(str::to_string);
+        self.workers.spawn_cancellable(cancel, move |cancel| {
+            let result = preview_handler::fetch(&client, &eid, max_bytes, handler.as_deref());
+            if !cancel.is_cancelled() {
+                let _ = tx.send(OpResult::PreviewText(eid.clone(), result));
+            }
+        });
+    }
+
+    /// Video details to show with a thumbnail, which comes from the listing
+    /// without them. Shares the thumbnail's cancel token; a failure just
+    /// leaves them out.
+    fn fetch_preview_media(&mut self, entry: &Entry) {
+        if theme::categorize(entry) != theme::FileCategory::Video
+            || self
+                .preview_media
+                .as_ref()
+                .is_some_and(|(id, _)| *id == entry.id)
+        {
+            return;
+        }
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        let cancel = self.preview_cancel.clone();
+        let eid = entry.id.clone();
+        self.workers.spawn_cancellable(cancel, move |cancel| {
+            let video = client
+                .file_info(&eid)
+                .ok()
+                .and_then(|info| info.video().cloned());
+            if !cancel.is_cancelled() {
+                let _ = tx.send(OpResult::PreviewMedia(eid, video));
+            }
         });
     }
 
@@ -1073,8 +2273,12 @@ impl App {
         match entry.kind {
             EntryKind::Folder => {
                 // Folders always show content listing, never thumbnails
-                std::thread::spawn(move || {
-                    let _ = tx.send(OpResult::PreviewLs(eid.clone(), client.ls(&eid)));
+                let cancel = self.preview_cancel.renew();
+                self.workers.spawn_cancellable(cancel, move |cancel| {
+                    let result = client.ls_streaming(&eid, |_| continue_unless(cancel));
+                    if !cancel.is_cancelled() {
+                        let _ = tx.send(OpResult::PreviewLs(eid.clone(), result));
+                    }
                 });
             }
             EntryKind::File => {
@@ -1084,19 +2288,38 @@ impl App {
                     self.spawn_thumbnail_fetch(thumb_url.clone(), move |r| {
                         OpResult::PreviewThumbnail(eid.clone(), r)
                     });
+                    self.fetch_preview_media(&entry);
                     return;
                 }
-                if theme::is_text_previewable(&entry) {
-                    let max_bytes = self.config.preview_max_size;
-                    std::thread::spawn(move || {
-                        let _ = tx.send(OpResult::PreviewText(
-                            eid.clone(),
-                            client.fetch_text_preview(&eid, max_bytes),
-                        ));
+                let cancel = self.preview_cancel.renew();
+                if archive::is_zip(&entry.name) {
+                    self.workers.spawn_cancellable(cancel, move |cancel| {
+                        let result = archive::list(&client, &eid);
+                        if !cancel.is_cancelled() {
+                            let _ = tx.send(OpResult::PreviewArchive(eid.clone(), result));
+                        }
                     });
+                } else if self.config.video_frame_preview
+                    && theme::categorize(&entry) == theme::FileCategory::Video
+                    && video_frame::ffmpeg_available()
+                {
+                    self.workers.spawn_cancellable(cancel, move |cancel| {
+                        let result = client
+                            .fetch_head(&eid, video_frame::HEAD_BYTES)
+                            .and_then(|head| video_frame::extract(&eid, &head));
+                        if !cancel.is_cancelled() {
+                            let _ = tx.send(OpResult::PreviewThumbnail(eid.clone(), result));
+                        }
+                    });
+                    self.fetch_preview_media(&entry);
+                } else if self.text_previewable(&entry) {
+                    self.spawn_text_preview(&entry, cancel);
                 } else {
-                    std::thread::spawn(move || {
-                        let _ = tx.send(OpResult::PreviewInfo(eid.clone(), client.file_info(&eid)));
+                    self.workers.spawn_cancellable(cancel, move |cancel| {
+                        let result = client.file_info(&eid);
+                        if !cancel.is_cancelled() {
+                            let _ = tx.send(OpResult::PreviewInfo(eid.clone(), result));
+                        }
                     });
                 }
             }
@@ -1113,7 +2336,7 @@ impl App {
         self.loading_label = Some("Loading trash...".into());
         let client = Arc::clone(&self.client);
         let tx = self.result_tx.clone();
-        std::thread::spawn(move || {
+        self.workers.spawn(move || {
             let _ = tx.send(OpResult::TrashList(client.ls_trash(200)));
         });
     }
@@ -1124,7 +2347,7 @@ impl App {
         self.loading_label = Some("Loading shares...".into());
         let client = Arc::clone(&self.client);
         let tx = self.result_tx.clone();
-        std::thread::spawn(move || {
+        self.workers.spawn(move || {
             let _ = tx.send(OpResult::MyShares(client.list_shares()));
         });
     }
@@ -1135,6 +2358,9 @@ impl App {
             self.config.sort_field,
             self.config.sort_reverse,
         );
+        if let Some(source) = self.filter_source.as_mut() {
+            crate::config::sort_entries(source, self.config.sort_field, self.config.sort_reverse);
+        }
         if self.selected >= self.entries.len() {
             self.selected = self.entries.len().saturating_sub(1);
         }
@@ -1149,6 +2375,72 @@ impl App {
             arrow
         ));
     }
+
+    /// Add every entry in the current (possibly filtered) listing to the cart.
+    fn cart_select_all(&mut self) {
+        let mut added = 0;
+        for entry in &self.entries {
+            if self.cart_ids.insert(entry.id.clone()) {
+                self.cart.push(entry.clone());
+                added += 1;
+            }
+        }
+        self.push_log(format!("Added {} item(s) to cart", added));
+    }
+
+    /// Flip cart membership of every entry in the current listing; cart items
+    /// from other folders are left alone.
+    fn cart_invert_selection(&mut self) {
+        let (mut added, mut removed) = (0, 0);
+        for entry in &self.entries {
+            if self.cart_ids.remove(&entry.id) {
+                removed += 1;
+            } else {
+                self.cart_ids.insert(entry.id.clone());
+                self.cart.push(entry.clone());
+                added += 1;
+            }
+        }
+        let ids = &self.cart_ids;
+        self.cart.retain(|e| ids.contains(&e.id));
+        self.cart_selected = self.cart_selected.min(self.cart.len().saturating_sub(1));
+        self.push_log(format!(
+            "Inverted selection: +{} / -{} (cart: {})",
+            added,
+            removed,
+            self.cart.len()
+        ));
+    }
+
+    /// Add entries of the current listing whose name matches `pattern`.
+    fn cart_select_glob(&mut self, pattern: &str) {
+        let mut added = 0;
+        for entry in &self.entries {
+            if crate::cmd::glob_match(pattern, &entry.name)
+                && self.cart_ids.insert(entry.id.clone())
+            {
+                self.cart.push(entry.clone());
+                added += 1;
+            }
+        }
+        self.push_log(format!(
+            "Added {} item(s) matching '{}' to cart",
+            added, pattern
+        ));
+    }
+
+    /// Sort by a header column: picking the active column flips the
+    /// direction, a new column starts ascending. The choice is persisted.
+    fn sort_by_column(&mut self, field: crate::config::SortField) {
+        if self.config.sort_field == field {
+            self.config.sort_reverse = !self.config.sort_reverse;
+        } else {
+            self.config.sort_field = field;
+            self.config.sort_reverse = false;
+        }
+        self.resort_entries();
+        self.save_config();
+    }
 }
 
 static SYNTAX_SET: LazyLock<syntect::parsing::SyntaxSet> =
@@ -1156,7 +2448,11 @@ static SYNTAX_SET: LazyLock<syntect::parsing::SyntaxSet> =
 static THEME_SET: LazyLock<syntect::highlighting::ThemeSet> =
     LazyLock::new(syntect::highlighting::ThemeSet::load_defaults);
 
-fn highlight_content(name: &str, content: &str) -> Vec<ratatui::text::Line<'static>> {
+fn highlight_content(
+    name: &str,
+    content: &str,
+    depth: crate::config::ColorDepth,
+) -> Vec<ratatui::text::Line<'static>> {
     use ratatui::style::{Color, Style};
     use ratatui::text::{Line, Span};
     use syntect::easy::HighlightLines;
@@ -1182,7 +2478,7 @@ fn highlight_content(name: &str, content: &str) -> Vec<ratatui::text::Line<'stat
                         let fg = style.foreground;
                         spans.push(Span::styled(
                             text.to_string(),
-                            Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b)),
+                            Style::default().fg(theme::rgb(fg.r, fg.g, fg.b, depth)),
                         ));
                     }
                 }
@@ -1199,21 +2495,7 @@ fn highlight_content(name: &str, content: &str) -> Vec<ratatui::text::Line<'stat
 }
 
 fn format_size(bytes: u64) -> String {
-    const KB: u64 = 1024;
-    const MB: u64 = 1024 * KB;
-    const GB: u64 = 1024 * MB;
-    const TB: u64 = 1024 * GB;
-    if bytes >= TB {
-        format!("{:.1} TB", bytes as f64 / TB as f64)
-    } else if bytes >= GB {
-        format!("{:.1} GB", bytes as f64 / GB as f64)
-    } else if bytes >= MB {
-        format!("{:.1} MB", bytes as f64 / MB as f64)
-    } else if bytes >= KB {
-        format!("{:.1} KB", bytes as f64 / KB as f64)
-    } else {
-        format!("{} B", bytes)
-    }
+    crate::cmd::format_size(bytes)
 }
 
 fn truncate_name(name: &str, max_width: usize) -> String {
@@ -1284,9 +2566,7 @@ fn fetch_and_render_thumbnail(
     use std::io::Cursor;
 
     let response = client
-        .http()
-        .get(url)
-        .send()
+        .send_abortable(client.http().get(url), None)
         .context("failed to download thumbnail")?;
 
     if !response.status().is_success() {
@@ -1455,3 +2735,114 @@ mod wrap_tests {
         assert_eq!(bottom.last().unwrap(), &"last line");
     }
 }
+
+#[cfg(test)]
+mod login_tests {
+    use super::*;
+
+    // A background task holding the shared client mustn't cost the login
+    // its device id and captcha token between the password and the code.
+    #[test]
+    fn the_verification_code_goes_through_the_logins_own_client() {
+        let mut app = App::for_tests();
+        let _held = Arc::clone(&app.client);
+        let session = std::env::temp_dir().join(format!(
+            "pikpaktui-login-{}/session.json",
+            std::process::id()
+        ));
+        let mut client = PikPak::for_tests("http://127.0.0.1:9".into(), session);
+        client.thumbnail_size = "the login's".into();
+        app.pending_login = Some(client);
+
+        let pending = crate::pikpak::PendingLogin::for_tests("me@example.com");
+        app.submit_login_code(Box::new(pending), String::new());
+        assert!(matches!(
+            &app.input,
+            InputMode::LoginCode { error: Some(e), .. } if e.contains("empty")
+        ));
+        assert_eq!(
+            app.pending_login
+                .as_ref()
+                .map(|c| c.thumbnail_size.as_str()),
+            Some("the login's")
+        );
+    }
+}
+
+#[cfg(test)]
+mod profile_tests {
+    use super::*;
+
+    #[test]
+    fn the_old_profiles_workers_dont_report_into_the_new_one() {
+        let mut app = App::for_tests();
+        let old_tx = app.result_tx.clone();
+        let listing = app.ls_cancel.renew();
+        app.cancel_profile_work();
+
+        assert!(listing.is_cancelled());
+        let _ = old_tx.send(OpResult::Ls(Ok(vec![Entry::file("old.txt")])));
+        app.poll_results();
+        assert!(app.entries.is_empty());
+        // The new profile's own work still comes through.
+        let _ = app
+            .result_tx
+            .send(OpResult::Ls(Ok(vec![Entry::file("new.txt")])));
+        app.poll_results();
+        assert_eq!(app.entries.len(), 1);
+    }
+
+    #[test]
+    fn switching_waits_for_running_uploads() {
+        let mut app = App::for_tests();
+        app.uploads_running = 1;
+        app.switch_profile("work");
+        assert!(
+            app.logs
+                .back()
+                .is_some_and(|l| l.contains("Wait for the uploads"))
+        );
+    }
+}
+
+#[cfg(test)]
+mod preview_tests {
+    use super::*;
+
+    fn paused_logs(app: &App) -> usize {
+        app.logs
+            .iter()
+            .filter(|l| l.contains("auto-preview paused"))
+            .count()
+    }
+
+    #[test]
+    fn auto_preview_pauses_after_failures_in_a_row() {
+        let mut app = App::for_tests();
+        app.config.lazy_preview = true;
+        for _ in 1..PREVIEW_FAILURE_LIMIT {
+            app.note_preview(true);
+        }
+        app.note_preview(false);
+        for _ in 1..PREVIEW_FAILURE_LIMIT {
+            app.note_preview(true);
+        }
+        assert!(!app.preview_paused, "a success starts the count over");
+
+        app.note_preview(true);
+        assert!(app.preview_paused);
+        app.note_preview(true);
+        assert_eq!(paused_logs(&app), 1);
+    }
+
+    #[test]
+    fn previews_on_demand_never_pause() {
+        let mut app = App::for_tests();
+        app.config.lazy_preview = false;
+        for _ in 0..PREVIEW_FAILURE_LIMIT * 2 {
+            app.note_preview(true);
+        }
+        assert!(!app.preview_paused);
+        assert_eq!(paused_logs(&app), 0);
+    }
+}
@@ -0,0 +1,168 @@
+//! The `F` new file form: a name and a few lines of text, or a file from
+//! the templates folder (`Ctrl+T` cycles through them), uploaded straight
+//! into the folder being viewed.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use super::{App, InputMode, OpResult};
+
+#[derive(Default)]
+pub(super) struct NewFile {
+    pub(super) name: String,
+    pub(super) body: String,
+    /// Typing into the body rather than the name.
+    pub(super) on_body: bool,
+    pub(super) templates: Vec<PathBuf>,
+    /// The template last filled in, as an index into `templates`.
+    pub(super) template: Option<usize>,
+}
+
+impl NewFile {
+    /// Fill the body from the next template, and the name too if it's
+    /// empty or still the previous template's.
+    fn next_template(&mut self) -> anyhow::Result<()> {
+        if self.templates.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No templates in ~/.config/pikpaktui/templates"
+            ));
+        }
+        let previous = self.template.map(|i| file_name(&self.templates[i]));
+        let i = self.template.map_or(0, |i| (i + 1) % self.templates.len());
+        let path = &self.templates[i];
+        self.body = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Can't read template {}: {e}", path.display()))?;
+        if self.name.is_empty() || previous.as_deref() == Some(self.name.as_str()) {
+            self.name = file_name(path);
+        }
+        self.template = Some(i);
+        Ok(())
+    }
+
+    /// The template last filled in, by file name.
+    pub(super) fn template_name(&self) -> Option<String> {
+        self.template.map(|i| file_name(&self.templates[i]))
+    }
+}
+
+fn file_name(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+impl App {
+    pub(super) fn open_new_file(&mut self) {
+        if self.in_virtual_folder() {
+            let msg = format!("Can't create files in {}", self.current_folder_name());
+            self.push_log(msg);
+            return;
+        }
+        self.input = InputMode::NewFile(Box::new(NewFile {
+            templates: crate::config::templates(),
+            ..NewFile::default()
+        }));
+    }
+
+    pub(super) fn handle_new_file_key(
+        &mut self,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+        mut form: Box<NewFile>,
+    ) {
+        let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+        match code {
+            KeyCode::Esc => return,
+            KeyCode::Char('s') if ctrl && self.create_new_file(&form) => return,
+            KeyCode::Char('s') if ctrl => {}
+            KeyCode::Char('t') if ctrl => {
+                if let Err(e) = form.next_template() {
+                    self.push_log(format!("{e:#}"));
+                }
+            }
+            KeyCode::Tab | KeyCode::BackTab => form.on_body = !form.on_body,
+            KeyCode::Enter if form.on_body => form.body.push('\n'),
+            KeyCode::Enter => form.on_body = true,
+            KeyCode::Backspace if form.on_body => {
+                form.body.pop();
+            }
+            KeyCode::Backspace => {
+                form.name.pop();
+            }
+            KeyCode::Char(c) if form.on_body => form.body.push(c),
+            KeyCode::Char(c) => form.name.push(c),
+            _ => {}
+        }
+        self.input = InputMode::NewFile(form);
+    }
+
+    /// Upload the form's file; false (with a log line) if the name is
+    /// empty or taken, to keep the form open.
+    fn create_new_file(&mut self, form: &NewFile) -> bool {
+        let name = form.name.trim().to_string();
+        if name.is_empty() {
+            self.push_log("New file: give it a name".into());
+            return false;
+        }
+        if self.entries.iter().any(|e| e.name == name) {
+            self.push_log(format!("New file: '{name}' already exists here"));
+            return false;
+        }
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        let fid = self.current_folder_id.clone();
+        let body = form.body.clone();
+        self.loading = true;
+        self.workers.spawn(move || {
+            let _ = tx.send(
+                match client.create_file(Some(&fid), &name, body.as_bytes()) {
+                    Ok(()) => OpResult::Ok(format!("Created file '{name}'")),
+                    Err(e) => OpResult::Err(format!("New file failed: {e:#}")),
+                },
+            );
+        });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_fill_the_body_and_a_default_name() {
+        let dir = std::env::temp_dir().join(format!("pikpaktui-tpl-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("README.md"), "# Readme\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "notes\n").unwrap();
+        let mut form = NewFile {
+            templates: vec![dir.join("README.md"), dir.join("notes.txt")],
+            ..NewFile::default()
+        };
+
+        form.next_template().unwrap();
+        assert_eq!(
+            (form.name.as_str(), form.body.as_str()),
+            ("README.md", "# Readme\n")
+        );
+        // A name left as the template's follows the next one...
+        form.next_template().unwrap();
+        assert_eq!(
+            (form.name.as_str(), form.body.as_str()),
+            ("notes.txt", "notes\n")
+        );
+        // ...but one typed in is kept.
+        form.name = "TODO.txt".into();
+        form.next_template().unwrap();
+        assert_eq!(
+            (form.name.as_str(), form.body.as_str()),
+            ("TODO.txt", "# Readme\n")
+        );
+        assert_eq!(form.template_name().as_deref(), Some("README.md"));
+
+        assert!(NewFile::default().next_template().is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,89 @@
+//! The `L` note form: labels and a line of text on the current entry, kept
+//! in the local database (see `pikpak::notes`). Labels show as badges in
+//! the listing, and a filter query starting with `#` matches them.
+
+use crossterm::event::KeyCode;
+
+use crate::pikpak::{Entry, Note, parse_labels};
+
+use super::{App, InputMode};
+
+pub(super) struct NoteForm {
+    pub(super) file_id: String,
+    pub(super) name: String,
+    path: String,
+    pub(super) labels: String,
+    pub(super) text: String,
+    /// Typing into the text rather than the labels.
+    pub(super) on_text: bool,
+}
+
+impl App {
+    /// Every note of the profile, for the badges; read again after a
+    /// login or profile switch.
+    pub(super) fn reload_notes(&mut self) {
+        self.notes = self.client.notes().unwrap_or_default();
+    }
+
+    pub(super) fn open_note(&mut self) {
+        let Some(entry) = self.current_entry().cloned() else {
+            return;
+        };
+        let note = self.notes.get(&entry.id).cloned().unwrap_or_default();
+        let dir = self.current_path_display();
+        self.input = InputMode::Note(Box::new(NoteForm {
+            path: format!("{}/{}", dir.trim_end_matches('/'), entry.name),
+            file_id: entry.id,
+            name: entry.name,
+            labels: note.labels.join(", "),
+            text: note.text,
+            on_text: false,
+        }));
+    }
+
+    pub(super) fn handle_note_key(&mut self, code: KeyCode, mut form: Box<NoteForm>) {
+        match code {
+            KeyCode::Esc => return,
+            KeyCode::Enter => {
+                self.save_note(&form);
+                return;
+            }
+            KeyCode::Tab | KeyCode::BackTab => form.on_text = !form.on_text,
+            KeyCode::Backspace if form.on_text => {
+                form.text.pop();
+            }
+            KeyCode::Backspace => {
+                form.labels.pop();
+            }
+            KeyCode::Char(c) if form.on_text => form.text.push(c),
+            KeyCode::Char(c) => form.labels.push(c),
+            _ => {}
+        }
+        self.input = InputMode::Note(form);
+    }
+
+    fn save_note(&mut self, form: &NoteForm) {
+        let note = Note {
+            path: form.path.clone(),
+            labels: parse_labels(&form.labels),
+            text: form.text.trim().to_string(),
+            ..Note::default()
+        };
+        if let Err(e) = self.client.set_note(&form.file_id, &note) {
+            self.push_log(format!("Note failed: {e:#}"));
+            return;
+        }
+        if note.is_empty() {
+            self.notes.remove(&form.file_id);
+            self.push_log(format!("Removed the note on '{}'", form.name));
+        } else {
+            self.notes.insert(form.file_id.clone(), note);
+            self.push_log(format!("Saved the note on '{}'", form.name));
+        }
+    }
+
+    /// Whether `entry` passes a `#label` filter query.
+    pub(super) fn matches_label_query(&self, entry: &Entry, query: &str) -> bool {
+        self.notes.get(&entry.id).is_some_and(|n| n.matches(query))
+    }
+}
@@ -0,0 +1,198 @@
+//! Browsing without a network. When PikPak can't be reached the TUI stays
+//! up: folders show the last listing the metadata cache has of them, however
+//! old, the status bar says offline, and the current folder is tried again
+//! every few seconds. The first listing that comes back ends it, and the
+//! rest of the screen is reloaded.
+
+use std::time::{Duration, Instant};
+
+use crate::pikpak::{FailureKind, failure_kind};
+
+use super::{App, InputMode};
+
+/// How often to try PikPak again while offline.
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+pub(super) struct Offline {
+    last_probe: Instant,
+    /// A listing is in flight to see whether PikPak is back; its failure
+    /// isn't news.
+    probing: bool,
+}
+
+impl App {
+    /// Whether `err` means PikPak couldn't be reached, going offline if so.
+    pub(super) fn went_offline(&mut self, err: &anyhow::Error) -> bool {
+        if failure_kind(err) != Some(FailureKind::Network) {
+            return false;
+        }
+        match self.offline.as_mut() {
+            Some(offline) => offline.last_probe = Instant::now(),
+            None => {
+                self.push_log(format!(
+                    "Offline: {err:#}; showing cached listings until PikPak can be reached"
+                ));
+                self.offline = Some(Offline {
+                    last_probe: Instant::now(),
+                    probing: false,
+                });
+            }
+        }
+        true
+    }
+
+    /// Started on the login form with no password saved but a session
+    /// that has run out: renew it, or when PikPak can't be reached, browse
+    /// the cache until it can.
+    pub(super) fn resume_session(&mut self) {
+        if !self.client.has_session() {
+            return;
+        }
+        match self.client.quota() {
+            Ok(_) => {
+                self.input = InputMode::Normal;
+                self.reload_notes();
+                self.reload_playback();
+                self.refresh();
+            }
+            Err(e) if self.went_offline(&e) => self.browse_offline(),
+            Err(_) => {}
+        }
+    }
+
+    /// Leave the login form for the cached listings; the saved session is
+    /// renewed once PikPak answers again.
+    pub(super) fn browse_offline(&mut self) {
+        self.input = InputMode::Normal;
+        self.reload_notes();
+        self.reload_playback();
+        self.spawn_ls_streaming();
+    }
+
+    /// A listing came through: reload what was painted from the cache.
+    pub(super) fn back_online(&mut self) {
+        if self.offline.take().is_none() {
+            return;
+        }
+        self.push_log("Back online".to_string());
+        self.refresh_parent();
+        self.refresh_idle_pane();
+        self.fetch_quota();
+    }
+
+    pub(super) fn maybe_reconnect(&mut self) {
+        let Some(offline) = self.offline.as_mut() else {
+            return;
+        };
+        if self.loading || offline.last_probe.elapsed() < PROBE_INTERVAL {
+            return;
+        }
+        offline.last_probe = Instant::now();
+        offline.probing = true;
+        self.refresh_current();
+    }
+
+    /// Fill the current folder from the cache after its listing failed
+    /// offline, unless something is already on screen.
+    pub(super) fn show_offline_listing(&mut self) {
+        let probing = self
+            .offline
+            .as_mut()
+            .is_some_and(|o| std::mem::take(&mut o.probing));
+        if !self.entries.is_empty() {
+            return;
+        }
+        match self.client.last_listing(&self.current_folder_id) {
+            Some(mut entries) => {
+                crate::config::sort_entries(
+                    &mut entries,
+                    self.config.sort_field,
+                    self.config.sort_reverse,
+                );
+                self.entries = entries;
+                self.selected = 0;
+                self.on_cursor_move();
+            }
+            None if !probing => self.push_log(format!(
+                "{} hasn't been listed before, so there's nothing cached to show",
+                self.current_path_display()
+            )),
+            None => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network_error() -> anyhow::Error {
+        reqwest::blocking::get("http://127.0.0.1:9")
+            .unwrap_err()
+            .into()
+    }
+
+    #[test]
+    fn goes_offline_only_when_pikpak_cant_be_reached() {
+        let mut app = App::for_tests();
+        assert!(!app.went_offline(&anyhow::anyhow!("bad request")));
+        assert!(app.offline.is_none());
+
+        assert!(app.went_offline(&network_error()));
+        assert!(app.offline.is_some());
+        assert!(app.logs.iter().any(|l| l.starts_with("Offline:")));
+        // Failing again while offline isn't logged again.
+        let logged = app.logs.len();
+        assert!(app.went_offline(&network_error()));
+        assert_eq!(app.logs.len(), logged);
+    }
+
+    #[test]
+    fn probes_every_interval_until_back_online() {
+        let mut app = App::for_tests();
+        app.went_offline(&network_error());
+
+        // Too soon to try again.
+        app.maybe_reconnect();
+        assert!(!app.offline.as_ref().unwrap().probing);
+
+        app.offline.as_mut().unwrap().last_probe = Instant::now() - PROBE_INTERVAL;
+        app.maybe_reconnect();
+        assert!(app.offline.as_ref().unwrap().probing);
+
+        // A failed probe is quiet and leaves the app offline.
+        app.show_offline_listing();
+        assert!(!app.offline.as_ref().unwrap().probing);
+        assert!(!app.logs.iter().any(|l| l.contains("nothing cached")));
+
+        app.back_online();
+        assert!(app.offline.is_none());
+        assert_eq!(app.logs.back().map(String::as_str), Some("Back online"));
+        // Only once.
+        app.back_online();
+        assert_eq!(app.logs.iter().filter(|l| *l == "Back online").count(), 1);
+    }
+
+    #[test]
+    fn an_unlisted_folder_says_nothing_is_cached() {
+        let mut app = App::for_tests();
+        app.went_offline(&network_error());
+        app.show_offline_listing();
+        assert!(app.logs.iter().any(|l| l.contains("nothing cached")));
+    }
+
+    #[test]
+    fn without_a_session_the_login_form_stays() {
+        let mut app = App::for_tests();
+        app.input = InputMode::Login {
+            field: super::super::LoginField::Email,
+            email: String::new(),
+            password: String::new(),
+            error: None,
+            logging_in: false,
+        };
+        app.resume_session();
+        assert!(matches!(app.input, InputMode::Login { .. }));
+        assert!(app.offline.is_none());
+    }
+}
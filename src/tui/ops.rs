@@ -0,0 +1,508 @@
+//! The file-operation queue. Moves, copies, renames and trashing from the
+//! browser, the cart and the commander panes run through here one at a
+//! time. Each one that finishes keeps the operation that reverses it, so
+//! `U` can undo it; the jobs view (`J`) lists the queue and retries
+//! whatever failed.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use crossterm::event::KeyCode;
+
+use crate::pikpak::{Backend, Entry};
+
+use super::{App, InputMode, OpResult};
+
+/// Finished jobs kept around for the jobs view and undo.
+const KEEP_FINISHED: usize = 50;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum FileOp {
+    /// `from` is each item's folder where known; the others are looked up
+    /// before moving, so the move can be undone.
+    Move {
+        ids: Vec<String>,
+        from: Vec<Option<String>>,
+        to: String,
+        dest_path: String,
+        what: String,
+    },
+    Copy {
+        ids: Vec<String>,
+        to: String,
+        dest_path: String,
+        what: String,
+    },
+    Rename {
+        id: String,
+        old: String,
+        new: String,
+    },
+    /// Renames of entries in one folder: (id, old name, new name). They
+    /// may swap names; see `rename_steps` for the order they run in.
+    BatchRename {
+        renames: Vec<(String, String, String)>,
+    },
+    Trash {
+        ids: Vec<String>,
+        what: String,
+    },
+    /// Undoes a move: each group of ids goes back into its folder.
+    MoveBack {
+        groups: Vec<(String, Vec<String>)>,
+        what: String,
+    },
+    /// Undoes trashing.
+    Untrash {
+        ids: Vec<String>,
+        what: String,
+    },
+}
+
+/// How a batch is named in messages: `'name'` for one entry, a count for more.
+pub(super) fn describe(entries: &[Entry]) -> String {
+    match entries {
+        [one] => format!("'{}'", one.name),
+        many => format!("{} item(s)", many.len()),
+    }
+}
+
+/// Pair ids with their folders, one group per folder in first-seen order.
+fn group_by_parent(ids: &[String], parents: &[String]) -> Vec<(String, Vec<String>)> {
+    let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+    for (id, parent) in ids.iter().zip(parents) {
+        match groups.iter_mut().find(|(p, _)| p == parent) {
+            Some((_, group)) => group.push(id.clone()),
+            None => groups.push((parent.clone(), vec![id.clone()])),
+        }
+    }
+    groups
+}
+
+impl FileOp {
+    /// What the job does, for the jobs view.
+    pub(super) fn summary(&self) -> String {
+        match self {
+            FileOp::Move {
+                what, dest_path, ..
+            } => format!("Move {what} -> '{dest_path}'"),
+            FileOp::Copy {
+                what, dest_path, ..
+            } => format!("Copy {what} -> '{dest_path}'"),
+            FileOp::Rename { old, new, .. } => format!("Rename '{old}' -> '{new}'"),
+            FileOp::BatchRename { renames } => format!("Rename {} item(s)", renames.len()),
+            FileOp::Trash { what, .. } => format!("Trash {what}"),
+            FileOp::MoveBack { what, .. } => format!("Move {what} back"),
+            FileOp::Untrash { what, .. } => format!("Restore {what} from trash"),
+        }
+    }
+
+    /// The log line once it's done.
+    fn done_message(&self) -> String {
+        match self {
+            FileOp::Move {
+                what, dest_path, ..
+            } => format!("Moved {what} -> '{dest_path}'"),
+            FileOp::Copy {
+                what, dest_path, ..
+            } => format!("Copied {what} -> '{dest_path}'"),
+            FileOp::Rename { old, new, .. } => format!("Renamed '{old}' -> '{new}'"),
+            FileOp::BatchRename { renames } => format!("Renamed {} item(s)", renames.len()),
+            FileOp::Trash { what, .. } => format!("Trashed {what}"),
+            FileOp::MoveBack { what, .. } => format!("Moved {what} back"),
+            FileOp::Untrash { what, .. } => format!("Restored {what} from trash"),
+        }
+    }
+
+    fn verb(&self) -> &'static str {
+        match self {
+            FileOp::Move { .. } | FileOp::MoveBack { .. } => "Move",
+            FileOp::Copy { .. } => "Copy",
+            FileOp::Rename { .. } | FileOp::BatchRename { .. } => "Rename",
+            FileOp::Trash { .. } => "Trash",
+            FileOp::Untrash { .. } => "Restore",
+        }
+    }
+
+    /// Carry the operation out, returning what would reverse it. Copies
+    /// have no inverse: the server doesn't say which files it created.
+    fn run(&self, client: &dyn Backend) -> Result<Option<FileOp>> {
+        match self {
+            FileOp::Move {
+                ids,
+                from,
+                to,
+                what,
+                ..
+            } => {
+                let parents = ids
+                    .iter()
+                    .zip(from)
+                    .map(|(id, parent)| match parent {
+                        Some(p) => Ok(p.clone()),
+                        None => client.parent_id(id),
+                    })
+                    .collect::<Result<Vec<String>>>()?;
+                let id_refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+                client.mv(&id_refs, to)?;
+                Ok(Some(FileOp::MoveBack {
+                    groups: group_by_parent(ids, &parents),
+                    what: what.clone(),
+                }))
+            }
+            FileOp::Copy { ids, to, .. } => {
+                let id_refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+                client.cp(&id_refs, to)?;
+                Ok(None)
+            }
+            FileOp::Rename { id, old, new } => {
+                client.rename(id, new)?;
+                Ok(Some(FileOp::Rename {
+                    id: id.clone(),
+                    old: new.clone(),
+                    new: old.clone(),
+                }))
+            }
+            FileOp::BatchRename { renames } => {
+                let steps = super::batch_rename::rename_steps(renames);
+                for (i, (id, old, new)) in steps.iter().enumerate() {
+                    client.rename(id, new).map_err(|e| {
+                        e.context(format!("'{old}' ({i} of {} renamed)", steps.len()))
+                    })?;
+                }
+                Ok(Some(FileOp::BatchRename {
+                    renames: renames
+                        .iter()
+                        .rev()
+                        .map(|(id, old, new)| (id.clone(), new.clone(), old.clone()))
+                        .collect(),
+                }))
+            }
+            FileOp::Trash { ids, what } => {
+                let id_refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+                client.remove(&id_refs)?;
+                Ok(Some(FileOp::Untrash {
+                    ids: ids.clone(),
+                    what: what.clone(),
+                }))
+            }
+            FileOp::MoveBack { groups, .. } => {
+                for (parent, ids) in groups {
+                    let id_refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+                    client.mv(&id_refs, parent)?;
+                }
+                Ok(None)
+            }
+            FileOp::Untrash { ids, .. } => {
+                let id_refs: Vec<&str> = ids.iter().map(String::as_str).collect();
+                client.untrash(&id_refs)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum JobState {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+pub(super) struct Job {
+    id: u64,
+    pub(super) op: FileOp,
+    pub(super) state: JobState,
+    pub(super) error: Option<String>,
+    /// Whether this job undoes another one.
+    pub(super) is_undo: bool,
+    /// Done and since undone.
+    pub(super) undone: bool,
+    /// What reverses `op` once it's done; taken by an undo.
+    inverse: Option<FileOp>,
+}
+
+#[derive(Default)]
+pub(super) struct OpQueue {
+    jobs: Vec<Job>,
+    next_id: u64,
+}
+
+impl OpQueue {
+    /// Every job, oldest first.
+    pub(super) fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    /// Jobs not finished yet.
+    pub(super) fn active(&self) -> usize {
+        self.jobs
+            .iter()
+            .filter(|j| matches!(j.state, JobState::Pending | JobState::Running))
+            .count()
+    }
+
+    fn push(&mut self, op: FileOp, is_undo: bool) {
+        self.next_id += 1;
+        self.jobs.push(Job {
+            id: self.next_id,
+            op,
+            state: JobState::Pending,
+            error: None,
+            is_undo,
+            undone: false,
+            inverse: None,
+        });
+    }
+
+    /// The next pending job, marked running, unless one is running already.
+    fn start_next(&mut self) -> Option<(u64, FileOp)> {
+        if self.jobs.iter().any(|j| j.state == JobState::Running) {
+            return None;
+        }
+        let job = self
+            .jobs
+            .iter_mut()
+            .find(|j| j.state == JobState::Pending)?;
+        job.state = JobState::Running;
+        Some((job.id, job.op.clone()))
+    }
+
+    /// Record how job `id` went; `None` if it's no longer queued.
+    fn finish(&mut self, id: u64, result: Result<Option<FileOp>>) -> Option<&Job> {
+        let idx = self.jobs.iter().position(|j| j.id == id)?;
+        let job = &mut self.jobs[idx];
+        match result {
+            Ok(inverse) => {
+                job.state = JobState::Done;
+                job.inverse = if job.is_undo { None } else { inverse };
+            }
+            Err(e) => {
+                job.state = JobState::Failed;
+                job.error = Some(format!("{e:#}"));
+            }
+        }
+        self.prune();
+        self.jobs.iter().find(|j| j.id == id)
+    }
+
+    /// Take the inverse of the latest job that can still be undone, with
+    /// that job's summary.
+    fn take_undo(&mut self) -> Option<(String, FileOp)> {
+        let job = self
+            .jobs
+            .iter_mut()
+            .rev()
+            .find(|j| j.state == JobState::Done && j.inverse.is_some())?;
+        let inverse = job.inverse.take()?;
+        job.undone = true;
+        Some((job.op.summary(), inverse))
+    }
+
+    /// Queue failed job `idx` again.
+    fn retry(&mut self, idx: usize) -> bool {
+        match self.jobs.get_mut(idx) {
+            Some(job) if job.state == JobState::Failed => {
+                job.state = JobState::Pending;
+                job.error = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Forget finished jobs, failed ones included. Undo history goes too.
+    fn clear_finished(&mut self) {
+        self.jobs
+            .retain(|j| matches!(j.state, JobState::Pending | JobState::Running));
+    }
+
+    fn prune(&mut self) {
+        let finished = self
+            .jobs
+            .iter()
+            .filter(|j| j.state == JobState::Done)
+            .count();
+        let mut excess = finished.saturating_sub(KEEP_FINISHED);
+        self.jobs.retain(|j| {
+            if excess > 0 && j.state == JobState::Done {
+                excess -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+impl App {
+    /// Queue `op` behind whatever is already running.
+    pub(super) fn queue_op(&mut self, op: FileOp) {
+        self.jobs.push(op, false);
+        self.pump_ops();
+    }
+
+    /// Start the next queued job if nothing is running.
+    fn pump_ops(&mut self) {
+        let Some((id, op)) = self.jobs.start_next() else {
+            return;
+        };
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        self.loading = true;
+        self.workers.spawn(move || {
+            let _ = tx.send(OpResult::Job(id, op.run(&*client)));
+        });
+    }
+
+    pub(super) fn finish_op(&mut self, id: u64, result: Result<Option<FileOp>>) {
+        let msg = match self.jobs.finish(id, result) {
+            Some(job) => {
+                let prefix = if job.is_undo { "Undo: " } else { "" };
+                match &job.error {
+                    None => Ok(format!("{prefix}{}", job.op.done_message())),
+                    Some(e) => Err(format!("{prefix}{} failed: {e}", job.op.verb())),
+                }
+            }
+            // Queued before switching accounts.
+            None => return,
+        };
+        match msg {
+            Ok(msg) => {
+                self.push_log(msg);
+                self.refresh();
+            }
+            Err(msg) => {
+                self.push_log(format!("{msg} (J to retry)"));
+                self.finish_loading();
+            }
+        }
+        self.pump_ops();
+    }
+
+    /// `U`: reverse the latest operation that can be.
+    pub(super) fn undo_last_op(&mut self) {
+        match self.jobs.take_undo() {
+            Some((summary, inverse)) => {
+                self.push_log(format!("Undoing: {summary}"));
+                self.jobs.push(inverse, true);
+                self.pump_ops();
+            }
+            None => self.push_log("Nothing to undo".into()),
+        }
+    }
+
+    pub(super) fn open_jobs_view(&mut self) {
+        let selected = self.jobs.jobs().len().saturating_sub(1);
+        self.input = InputMode::JobsView { selected };
+    }
+
+    pub(super) fn handle_jobs_view_key(&mut self, code: KeyCode, selected: usize) {
+        let last = self.jobs.jobs().len().saturating_sub(1);
+        let mut selected = selected.min(last);
+        match code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('J') => return,
+            KeyCode::Down | KeyCode::Char('j') => selected = (selected + 1).min(last),
+            KeyCode::Up | KeyCode::Char('k') => selected = selected.saturating_sub(1),
+            KeyCode::Char('r') => {
+                if self.jobs.retry(selected) {
+                    self.pump_ops();
+                } else {
+                    self.push_log("Only failed jobs can be retried".into());
+                }
+            }
+            KeyCode::Char('c') => {
+                self.jobs.clear_finished();
+                selected = 0;
+            }
+            KeyCode::Char('U') => self.undo_last_op(),
+            _ => {}
+        }
+        self.input = InputMode::JobsView { selected };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trash(name: &str) -> FileOp {
+        FileOp::Trash {
+            ids: vec![name.to_string()],
+            what: format!("'{name}'"),
+        }
+    }
+
+    #[test]
+    fn moves_go_back_to_each_items_own_folder() {
+        let ids: Vec<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let parents: Vec<String> = ["p1", "p2", "p1"].iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            group_by_parent(&ids, &parents),
+            vec![
+                ("p1".to_string(), vec!["a".to_string(), "c".to_string()]),
+                ("p2".to_string(), vec!["b".to_string()]),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_move_looks_up_where_unknown_items_came_from() {
+        let root = std::env::temp_dir().join(format!("pikpaktui-ops-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("Inbox")).unwrap();
+        std::fs::create_dir_all(root.join("Movies")).unwrap();
+        std::fs::write(root.join("Inbox/a.mkv"), "a").unwrap();
+        std::fs::write(root.join("b.mkv"), "b").unwrap();
+
+        let drive = crate::pikpak::LocalBackend::new(&root);
+        let op = FileOp::Move {
+            ids: vec!["Inbox/a.mkv".into(), "b.mkv".into()],
+            from: vec![None, Some(String::new())],
+            to: "Movies".into(),
+            dest_path: "/Movies".into(),
+            what: "2 items".into(),
+        };
+        let inverse = op.run(&drive).unwrap();
+        assert_eq!(
+            inverse,
+            Some(FileOp::MoveBack {
+                groups: vec![
+                    ("Inbox".into(), vec!["Inbox/a.mkv".into()]),
+                    (String::new(), vec!["b.mkv".into()]),
+                ],
+                what: "2 items".into(),
+            })
+        );
+        assert!(root.join("Movies/a.mkv").exists() && root.join("Movies/b.mkv").exists());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn undo_takes_the_latest_reversible_job_once() {
+        let mut q = OpQueue::default();
+        q.push(trash("x"), false);
+        q.push(trash("y"), false);
+        let (first, _) = q.start_next().unwrap();
+        assert!(q.start_next().is_none(), "one job at a time");
+        let inverse = FileOp::Untrash {
+            ids: vec!["x".into()],
+            what: "'x'".into(),
+        };
+        q.finish(first, Ok(Some(inverse.clone())));
+        let (second, _) = q.start_next().unwrap();
+        q.finish(second, Err(anyhow::anyhow!("boom")));
+        assert_eq!(q.jobs()[1].error.as_deref(), Some("boom"));
+
+        // The failed job has nothing to undo, so the one before it is next.
+        assert_eq!(q.take_undo(), Some(("Trash 'x'".to_string(), inverse)));
+        assert!(q.jobs()[0].undone);
+        assert_eq!(q.take_undo(), None);
+
+        assert!(q.retry(1));
+        assert_eq!(q.start_next().map(|(id, _)| id), Some(second));
+        assert_eq!(q.active(), 1);
+    }
+}
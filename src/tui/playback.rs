@@ -0,0 +1,216 @@
+//! Watched state in the browser. `v` marks the marked videos, or the one
+//! under the cursor, watched (again to unmark); on a folder it does every
+//! video inside, at any depth. Played through mpv, a video is followed over
+//! mpv's IPC socket: where it stopped is kept, and the next play resumes
+//! there. Other players only mark it as started.
+
+use std::sync::Arc;
+
+use crate::pikpak::{Entry, EntryKind, PikPak, Playback};
+use crate::theme;
+
+use super::{App, OpResult};
+
+/// Whether `entry` can carry a watched state: a video or a folder.
+fn trackable(entry: &Entry) -> bool {
+    entry.kind == EntryKind::Folder || theme::categorize(entry) == theme::FileCategory::Video
+}
+
+/// `program`, if it's mpv, as a player whose playback can be followed.
+pub(super) fn is_mpv(program: &str) -> bool {
+    let name = std::path::Path::new(program)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    name.trim_end_matches(".exe") == "mpv"
+}
+
+impl App {
+    pub(super) fn reload_playback(&mut self) {
+        self.playback = self.client.playback().unwrap_or_default();
+    }
+
+    pub(super) fn toggle_watched(&mut self) {
+        let mut targets: Vec<Entry> = self
+            .entries
+            .iter()
+            .filter(|e| self.cart_ids.contains(&e.id) && trackable(e))
+            .cloned()
+            .collect();
+        if targets.is_empty() {
+            targets.extend(self.current_entry().filter(|e| trackable(e)).cloned());
+        }
+        if targets.is_empty() {
+            return;
+        }
+        let watched = !targets
+            .iter()
+            .all(|e| self.playback.get(&e.id).is_some_and(|p| p.watched));
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        self.workers.spawn(move || {
+            let result = mark_watched(&client, &targets, watched).map(|videos| {
+                let verb = if watched { "watched" } else { "unwatched" };
+                match targets.as_slice() {
+                    [one] if one.kind == EntryKind::File => format!("Marked '{}' {verb}", one.name),
+                    _ => format!("Marked {videos} video(s) {verb}"),
+                }
+            });
+            let _ = tx.send(OpResult::Watched(result));
+        });
+    }
+
+    /// Where to start `file_id` from, for the player's resume option.
+    pub(super) fn resume_position(&self, file_id: &str) -> Option<f64> {
+        self.playback.get(file_id).and_then(Playback::resume_at)
+    }
+
+    /// Note that `file_id` was played. With mpv listening on `socket`, follow
+    /// it until it quits and keep where it stopped; with any other player,
+    /// record that it was started (leaving an earlier position alone).
+    pub(super) fn track_playback(&mut self, file_id: &str, name: &str, socket: Option<String>) {
+        let Some(socket) = socket else {
+            if self.playback.contains_key(file_id) {
+                return;
+            }
+            let started = Playback::default();
+            if self.client.set_playback(file_id, &started).is_ok() {
+                self.playback.insert(file_id.to_string(), started);
+            }
+            return;
+        };
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        let (file_id, name) = (file_id.to_string(), name.to_string());
+        // mpv runs as long as the video does, so this gets its own thread
+        // rather than a worker.
+        std::thread::spawn(move || {
+            let Some(stopped) = follow_mpv(&socket) else {
+                return;
+            };
+            let result =
+                client
+                    .set_playback(&file_id, &stopped)
+                    .map(|()| match stopped.progress() {
+                        _ if stopped.watched => format!("Marked '{name}' watched"),
+                        Some(p) => format!("Stopped '{name}' at {:.0}%", p * 100.0),
+                        None => format!("Stopped '{name}'"),
+                    });
+            let _ = tx.send(OpResult::Watched(result));
+        });
+    }
+}
+
+/// A new IPC socket address for an mpv about to start.
+pub(super) fn mpv_socket() -> String {
+    let n = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    let name = format!("pikpaktui-mpv-{}-{n}", std::process::id());
+    if cfg!(windows) {
+        format!(r"\\.\pipe\{name}")
+    } else {
+        std::env::temp_dir()
+            .join(format!("{name}.sock"))
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+/// Mark `targets` (videos, and folders with every video in them) watched
+/// or not. Returns how many videos that was.
+fn mark_watched(client: &PikPak, targets: &[Entry], watched: bool) -> anyhow::Result<usize> {
+    let mut ids = Vec::new();
+    let mut videos = 0;
+    let mut pending: Vec<Entry> = targets.to_vec();
+    while let Some(entry) = pending.pop() {
+        if entry.kind == EntryKind::Folder {
+            pending.extend(client.ls(&entry.id)?.into_iter().filter(trackable));
+        } else {
+            videos += 1;
+        }
+        ids.push(entry.id);
+    }
+    client.set_watched(&ids, watched)?;
+    Ok(videos)
+}
+
+/// Where mpv stopped: its position and the video's length as it last
+/// reported them, or watched if it played to the end. `None` when it never
+/// opened the socket.
+fn follow_mpv(socket: &str) -> Option<Playback> {
+    use std::io::{BufRead, BufReader, Write};
+
+    let stream = connect(socket)?;
+    let mut reader = BufReader::new(stream.try_clone().ok()?);
+    let mut writer = stream;
+    writer
+        .write_all(
+            b"{\"command\":[\"observe_property\",1,\"time-pos\"]}\n\
+              {\"command\":[\"observe_property\",2,\"duration\"]}\n",
+        )
+        .ok()?;
+
+    let (mut position, mut duration, mut ended) = (0.0, 0.0, false);
+    let mut line = String::new();
+    // mpv closes the socket when it quits.
+    while reader.read_line(&mut line).is_ok_and(|n| n > 0) {
+        if let Ok(msg) = serde_json::from_str::<serde_json::Value>(&line) {
+            match msg["event"].as_str() {
+                Some("property-change") => match (msg["id"].as_i64(), msg["data"].as_f64()) {
+                    (Some(1), Some(t)) => position = t,
+                    (Some(2), Some(d)) => duration = d,
+                    _ => {}
+                },
+                Some("end-file") => ended = msg["reason"] == "eof",
+                _ => {}
+            }
+        }
+        line.clear();
+    }
+    if !cfg!(windows) {
+        let _ = std::fs::remove_file(socket);
+    }
+    Some(if ended {
+        Playback::at(duration, duration)
+    } else {
+        Playback::at(position, duration)
+    })
+}
+
+#[cfg(unix)]
+type IpcStream = std::os::unix::net::UnixStream;
+#[cfg(not(unix))]
+type IpcStream = std::fs::File;
+
+/// Open mpv's socket, giving it a few seconds to start.
+fn connect(socket: &str) -> Option<IpcStream> {
+    for _ in 0..50 {
+        #[cfg(unix)]
+        let stream = IpcStream::connect(socket);
+        #[cfg(not(unix))]
+        let stream = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(socket);
+        if let Ok(stream) = stream {
+            return Some(stream);
+        }
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_mpv_is_followed() {
+        assert!(is_mpv("mpv"));
+        assert!(is_mpv("/usr/local/bin/mpv"));
+        assert!(is_mpv("MPV.exe"));
+        assert!(!is_mpv("iina"));
+        assert!(!is_mpv("mpvpaper"));
+    }
+}
@@ -0,0 +1,308 @@
+//! Text previews through a local command from `[preview.handlers]`: the
+//! start of the file goes to the command's stdin and what it prints is
+//! shown instead, colours included, so `jq`, `bat` and the like can be
+//! used on cloud files.
+
+use anyhow::{Context, Result, anyhow};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use crate::config::ColorDepth;
+use crate::local_path::split_command;
+use crate::pikpak::PikPak;
+use crate::theme;
+
+/// What the text preview pane shows for a file.
+#[derive(Debug)]
+pub(super) struct TextPreview {
+    pub(super) name: String,
+    pub(super) content: String,
+    pub(super) size: u64,
+    pub(super) truncated: bool,
+    /// `content` is a handler's output rather than the file itself.
+    pub(super) handled: bool,
+    /// Why the handler couldn't be used; the file is shown as it is.
+    pub(super) handler_error: Option<String>,
+}
+
+/// Fetch the start of a file and, with a `handler` command, run it over
+/// that.
+pub(super) fn fetch(
+    client: &PikPak,
+    file_id: &str,
+    max_bytes: u64,
+    handler: Option<&str>,
+) -> Result<TextPreview> {
+    let (name, bytes, size, truncated) = client.fetch_preview_bytes(file_id, max_bytes)?;
+    let mut preview = TextPreview {
+        name,
+        content: String::new(),
+        size,
+        truncated,
+        handled: false,
+        handler_error: None,
+    };
+    match handler.map(|cmd| run(cmd, &preview.name, &bytes, HANDLER_TIMEOUT)) {
+        Some(Ok(output)) => {
+            preview.content = output;
+            preview.handled = true;
+        }
+        Some(Err(e)) => {
+            preview.handler_error = Some(format!("{e:#}"));
+            preview.content = String::from_utf8_lossy(&bytes).into_owned();
+        }
+        None => preview.content = String::from_utf8_lossy(&bytes).into_owned(),
+    }
+    Ok(preview)
+}
+
+/// How long a handler gets before it's killed and the file is shown as it
+/// is.
+const HANDLER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Run `command` with `input` on its stdin, killing it after `timeout`.
+/// `{name}` in its arguments is the file's name, for tools that pick a
+/// syntax by it.
+fn run(command: &str, name: &str, input: &[u8], timeout: Duration) -> Result<String> {
+    let words: Vec<String> = split_command(command)
+        .into_iter()
+        .map(|w| w.replace("{name}", name))
+        .collect();
+    let (program, args) = words
+        .split_first()
+        .ok_or_else(|| anyhow!("empty preview handler"))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run {program}"))?;
+    // Written and read from other threads so a command that prints before
+    // it has read everything can't block on a full pipe.
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || {
+        // A command that stops reading early (`head`) closes the pipe.
+        let _ = stdin.write_all(&input);
+    });
+    let stdout = drain(child.stdout.take().expect("stdout is piped"));
+    let stderr = drain(child.stderr.take().expect("stderr is piped"));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| format!("failed to run {program}"))?
+        {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(anyhow!(
+                "{program} took longer than {}s",
+                timeout.as_secs_f32()
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    let _ = writer.join();
+    let stdout = stdout.join().unwrap_or_default();
+    if !status.success() {
+        let err = String::from_utf8_lossy(&stderr.join().unwrap_or_default()).into_owned();
+        return Err(anyhow!(
+            "{program} failed ({}): {}",
+            status,
+            err.lines().next().unwrap_or("no output")
+        ));
+    }
+    Ok(String::from_utf8_lossy(&stdout).into_owned())
+}
+
+/// Read `pipe` to the end on its own thread.
+fn drain(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut out = Vec::new();
+        let _ = pipe.read_to_end(&mut out);
+        out
+    })
+}
+
+/// Lines of terminal output, with SGR colour and style codes turned into
+/// styles. Other escape sequences are dropped.
+pub(super) fn ansi_lines(text: &str, depth: ColorDepth) -> Vec<Line<'static>> {
+    let mut style = Style::default();
+    text.lines()
+        .map(|line| {
+            let mut spans = Vec::new();
+            let mut run = String::new();
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                if c != '\x1b' {
+                    if c != '\r' {
+                        run.push(c);
+                    }
+                    continue;
+                }
+                if chars.peek() != Some(&'[') {
+                    continue;
+                }
+                chars.next();
+                let mut params = String::new();
+                let mut end = None;
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        end = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if end == Some('m') {
+                    if !run.is_empty() {
+                        spans.push(Span::styled(std::mem::take(&mut run), style));
+                    }
+                    style = apply_sgr(style, &params, depth);
+                }
+            }
+            if !run.is_empty() {
+                spans.push(Span::styled(run, style));
+            }
+            Line::from(spans)
+        })
+        .collect()
+}
+
+const BASIC: [Color; 8] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+];
+
+const BRIGHT: [Color; 8] = [
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+fn apply_sgr(mut style: Style, params: &str, depth: ColorDepth) -> Style {
+    let codes: Vec<u16> = params
+        .split([';', ':'])
+        .map(|p| p.parse().unwrap_or(0))
+        .collect();
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 => style = Style::default(),
+            1 => style = style.add_modifier(Modifier::BOLD),
+            2 => style = style.add_modifier(Modifier::DIM),
+            3 => style = style.add_modifier(Modifier::ITALIC),
+            4 => style = style.add_modifier(Modifier::UNDERLINED),
+            7 => style = style.add_modifier(Modifier::REVERSED),
+            22 => style = style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style = style.remove_modifier(Modifier::ITALIC),
+            24 => style = style.remove_modifier(Modifier::UNDERLINED),
+            27 => style = style.remove_modifier(Modifier::REVERSED),
+            n @ 30..=37 => style = style.fg(BASIC[(n - 30) as usize]),
+            n @ 90..=97 => style = style.fg(BRIGHT[(n - 90) as usize]),
+            n @ 40..=47 => style = style.bg(BASIC[(n - 40) as usize]),
+            n @ 100..=107 => style = style.bg(BRIGHT[(n - 100) as usize]),
+            39 => style.fg = None,
+            49 => style.bg = None,
+            n @ (38 | 48) => {
+                let (color, used) = extended_color(&codes[i + 1..], depth);
+                i += used;
+                if let Some(color) = color {
+                    style = if n == 38 {
+                        style.fg(color)
+                    } else {
+                        style.bg(color)
+                    };
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    style
+}
+
+/// `5;n` or `2;r;g;b` after a 38 / 48, and how many codes that took.
+fn extended_color(codes: &[u16], depth: ColorDepth) -> (Option<Color>, usize) {
+    let byte = |i: usize| codes.get(i).map(|&c| c.min(255) as u8);
+    match codes.first() {
+        Some(5) => (byte(1).map(Color::Indexed), 2),
+        Some(2) => match (byte(1), byte(2), byte(3)) {
+            (Some(r), Some(g), Some(b)) => (Some(theme::rgb(r, g, b, depth)), 4),
+            _ => (None, codes.len()),
+        },
+        _ => (None, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn handlers_are_killed_after_the_timeout() {
+        let started = Instant::now();
+        let err = run("sleep 5", "a.json", b"{}", Duration::from_millis(100)).unwrap_err();
+        assert!(err.to_string().contains("took longer than"));
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        let out = run("cat", "a.json", b"{\"a\": 1}", Duration::from_secs(5)).unwrap();
+        assert_eq!(out, "{\"a\": 1}");
+        let err = run(
+            "sh -c 'echo nope >&2; exit 3'",
+            "a",
+            b"",
+            Duration::from_secs(5),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("nope"));
+    }
+
+    #[test]
+    fn sgr_codes_become_styles() {
+        let lines = ansi_lines(
+            "\x1b[1;34m\"key\"\x1b[0m: \x1b[38;2;10;20;30m1\x1b[K\x1b[39m,\r\nnext",
+            ColorDepth::Truecolor,
+        );
+        assert_eq!(lines.len(), 2);
+        let spans: Vec<(&str, Style)> = lines[0]
+            .spans
+            .iter()
+            .map(|s| (s.content.as_ref(), s.style))
+            .collect();
+        assert_eq!(
+            spans,
+            [
+                (
+                    "\"key\"",
+                    Style::default()
+                        .fg(Color::Blue)
+                        .add_modifier(Modifier::BOLD)
+                ),
+                (": ", Style::default()),
+                ("1", Style::default().fg(Color::Rgb(10, 20, 30))),
+                (",", Style::default()),
+            ]
+        );
+        assert_eq!(lines[1].spans[0].content, "next");
+    }
+}
@@ -0,0 +1,171 @@
+//! The download queue as a JSON file, for handing a queue built on one
+//! machine to pikpaktui on another. `queue export` writes the unfinished
+//! tasks of the saved queue; `queue import` gives them to the TUI running
+//! there, which starts them, or with none running adds them to the saved
+//! queue for the next one to load.
+
+use anyhow::{Context, Result, anyhow};
+use std::path::{Component, Path, PathBuf};
+
+use super::download::{OnComplete, TaskStatus, append_saved, load_download_state};
+pub(crate) use super::instance::QueuedDownload;
+
+/// Job label of imported tasks waiting in the saved queue.
+const IMPORTED_JOB: &str = "Imported queue";
+
+/// Where an imported queue went.
+pub(crate) enum Handoff {
+    /// To the running TUI.
+    Running,
+    /// Into the saved queue; this many were new to it.
+    Saved(usize),
+}
+
+/// The saved queue without the finished tasks.
+pub(crate) fn unfinished() -> Vec<QueuedDownload> {
+    load_download_state()
+        .into_iter()
+        .filter(|(task, _)| task.status != TaskStatus::Done)
+        .map(|(task, _)| QueuedDownload {
+            file_id: task.file_id,
+            name: task.name,
+            total_size: task.total_size,
+            dest_path: task.dest_path,
+            on_complete: task.on_complete,
+        })
+        .collect()
+}
+
+/// A queue from a file, with nothing set to happen on completion: what a
+/// download runs when it's done is chosen here, not by whoever wrote it.
+pub(crate) fn parse(raw: &str) -> Result<Vec<QueuedDownload>> {
+    let mut batch: Vec<QueuedDownload> =
+        serde_json::from_str(raw).context("not a pikpaktui download queue")?;
+    for item in &mut batch {
+        item.on_complete = OnComplete::Nothing;
+    }
+    Ok(batch)
+}
+
+/// Where an imported queue goes without `--to`.
+pub(crate) fn default_dir() -> Option<PathBuf> {
+    dirs::download_dir().or_else(dirs::home_dir)
+}
+
+/// Hand `batch` to the running TUI, or else add it to the saved queue.
+pub(crate) fn import(batch: &[QueuedDownload]) -> Result<Handoff> {
+    if let Some(socket) = super::instance::running() {
+        super::instance::send(&socket, batch)
+            .context("couldn't hand the queue to the running pikpaktui")?;
+        return Ok(Handoff::Running);
+    }
+    // It would overwrite the saved queue on exit, dropping what's added.
+    if super::instance::queue_in_use() {
+        return Err(anyhow!(
+            "a running pikpaktui owns the download queue and can't take an import; quit it and try again"
+        ));
+    }
+    let added = append_saved(batch, IMPORTED_JOB).context("failed to save the imported queue")?;
+    Ok(Handoff::Saved(added))
+}
+
+/// Move every destination under `dir` instead of the folder they share,
+/// keeping the layout below it: a queue from `/Users/me/Downloads` lands
+/// in `/srv/media` on the server. A path that would climb out of `dir`
+/// refuses the whole queue.
+pub(crate) fn reroot(batch: &mut [QueuedDownload], dir: &Path) -> Result<()> {
+    if let Some(item) = batch
+        .iter()
+        .find(|d| d.dest_path.components().any(|c| c == Component::ParentDir))
+    {
+        return Err(anyhow!(
+            "'{}' climbs out of its folder",
+            item.dest_path.display()
+        ));
+    }
+    let common = common_dir(batch.iter().map(|d| d.dest_path.as_path()));
+    for item in batch {
+        let rel = item
+            .dest_path
+            .strip_prefix(&common)
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|_| PathBuf::from(&item.name));
+        if !rel.components().all(|c| matches!(c, Component::Normal(_))) {
+            return Err(anyhow!("'{}' isn't a file name", rel.display()));
+        }
+        item.dest_path = dir.join(rel);
+    }
+    Ok(())
+}
+
+/// The deepest folder holding all of `paths`.
+fn common_dir<'a>(mut paths: impl Iterator<Item = &'a Path>) -> PathBuf {
+    let Some(first) = paths.next() else {
+        return PathBuf::new();
+    };
+    let mut common = first.parent().unwrap_or(first).to_path_buf();
+    for path in paths {
+        while !path.starts_with(&common) && common.pop() {}
+    }
+    common
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(dest: &str) -> QueuedDownload {
+        QueuedDownload {
+            file_id: "f".into(),
+            name: Path::new(dest)
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned(),
+            total_size: 1,
+            dest_path: PathBuf::from(dest),
+            on_complete: OnComplete::Nothing,
+        }
+    }
+
+    #[test]
+    fn reroot_keeps_the_layout_below_the_shared_folder() {
+        let mut batch = vec![
+            item("/Users/me/Downloads/Show/S01/e1.mkv"),
+            item("/Users/me/Downloads/Show/S02/e1.mkv"),
+            item("/Users/me/Downloads/movie.mkv"),
+        ];
+        reroot(&mut batch, Path::new("/srv/media")).unwrap();
+        let dests: Vec<&Path> = batch.iter().map(|d| d.dest_path.as_path()).collect();
+        assert_eq!(
+            dests,
+            [
+                Path::new("/srv/media/Show/S01/e1.mkv"),
+                Path::new("/srv/media/Show/S02/e1.mkv"),
+                Path::new("/srv/media/movie.mkv"),
+            ]
+        );
+
+        let mut one = vec![item("/home/a/x.iso")];
+        reroot(&mut one, Path::new("/data")).unwrap();
+        assert_eq!(one[0].dest_path, Path::new("/data/x.iso"));
+    }
+
+    #[test]
+    fn imports_stay_inside_the_download_folder() {
+        let mut climbing = vec![item("/srv/a.mkv"), item("/srv/x/../../etc/cron.d/job")];
+        assert!(reroot(&mut climbing, Path::new("/data")).is_err());
+
+        let mut named = vec![item("/srv/a.mkv"), item("/elsewhere/b.mkv")];
+        named[1].name = "../b.mkv".into();
+        named[1].dest_path = PathBuf::from("relative/b.mkv");
+        assert!(reroot(&mut named, Path::new("/data")).is_err());
+    }
+
+    #[test]
+    fn imported_tasks_do_nothing_on_completion() {
+        let raw = r#"[{"file_id":"f","name":"a.mkv","total_size":1,
+            "dest_path":"/tmp/a.mkv","on_complete":"shutdown"}]"#;
+        assert_eq!(parse(raw).unwrap()[0].on_complete, OnComplete::Nothing);
+    }
+}
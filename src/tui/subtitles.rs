@@ -0,0 +1,141 @@
+//! Subtitles for `p` (play): `.srt` / `.ass` files next to a video that
+//! share its name are handed to the player along with the stream.
+
+use crate::pikpak::{Entry, EntryKind, PikPak};
+
+const EXTENSIONS: &[&str] = &["srt", "ass", "ssa", "vtt", "sub"];
+
+pub(super) struct Subtitle {
+    pub(super) name: String,
+    pub(super) url: String,
+}
+
+/// The subtitles found for a video, and whether to pass them on (`s` in
+/// the play prompt turns them off and on again).
+pub(super) struct Subtitles {
+    pub(super) found: Vec<Subtitle>,
+    pub(super) on: bool,
+}
+
+impl Subtitles {
+    pub(super) fn new(found: Vec<Subtitle>) -> Self {
+        Self { found, on: true }
+    }
+
+    pub(super) fn none() -> Self {
+        Self::new(Vec::new())
+    }
+
+    pub(super) fn toggle(&mut self) {
+        self.on = !self.on;
+    }
+
+    /// Links to give the player: none while switched off.
+    pub(super) fn urls(&self) -> Vec<String> {
+        if !self.on {
+            return Vec::new();
+        }
+        self.found.iter().map(|s| s.url.clone()).collect()
+    }
+}
+
+fn split_ext(name: &str) -> (&str, &str) {
+    name.rsplit_once('.').unwrap_or((name, ""))
+}
+
+/// Subtitle files in `folder` named after `video`: `Movie.srt` or
+/// `Movie.en.ass` for `Movie.mkv`.
+pub(super) fn siblings(video: &Entry, folder: &[Entry]) -> Vec<Entry> {
+    let stem = split_ext(&video.name).0.to_lowercase();
+    folder
+        .iter()
+        .filter(|e| e.kind == EntryKind::File && e.id != video.id)
+        .filter(|e| {
+            let (sub_stem, ext) = split_ext(&e.name);
+            let sub_stem = sub_stem.to_lowercase();
+            EXTENSIONS.contains(&ext.to_lowercase().as_str())
+                && (sub_stem == stem
+                    || sub_stem
+                        .strip_prefix(&stem)
+                        .is_some_and(|rest| rest.starts_with('.')))
+        })
+        .cloned()
+        .collect()
+}
+
+/// Download links for `files`; one that can't be fetched is left out.
+pub(super) fn fetch(client: &PikPak, files: &[Entry]) -> Vec<Subtitle> {
+    files
+        .iter()
+        .filter_map(|e| {
+            let info = client.file_info(&e.id).ok()?;
+            let url = info.web_content_link.filter(|u| !u.is_empty())?;
+            Some(Subtitle {
+                name: e.name.clone(),
+                url,
+            })
+        })
+        .collect()
+}
+
+/// Arguments that load `urls` as subtitles in `program`, or `None` for a
+/// player we don't know the option for. VLC takes only one file.
+pub(super) fn player_args(program: &str, urls: &[String]) -> Option<Vec<String>> {
+    let name = std::path::Path::new(program)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let name = name.trim_end_matches(".exe");
+    let flag = match name {
+        "mpv" | "mpv.net" | "mpvnet" => "--sub-file=",
+        "iina" | "iina-cli" => "--mpv-sub-file=",
+        "vlc" => {
+            return Some(
+                urls.iter()
+                    .take(1)
+                    .map(|u| format!("--sub-file={u}"))
+                    .collect(),
+            );
+        }
+        _ => return None,
+    };
+    Some(urls.iter().map(|u| format!("{flag}{u}")).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_subtitles_named_after_the_video() {
+        let video = Entry::file("Show.S01E02.mkv").with_id("v");
+        let folder = [
+            video.clone(),
+            Entry::file("Show.S01E02.srt").with_id("a"),
+            Entry::file("show.s01e02.zh-CN.ASS").with_id("b"),
+            Entry::file("Show.S01E03.srt").with_id("c"),
+            Entry::file("Show.S01E02.nfo").with_id("d"),
+            Entry::file("Show.S01E020.srt").with_id("e"),
+        ];
+        let ids: Vec<_> = siblings(&video, &folder)
+            .into_iter()
+            .map(|e| e.id)
+            .collect();
+        assert_eq!(ids, ["a", "b"]);
+    }
+
+    #[test]
+    fn subtitle_options_per_player() {
+        let urls = vec!["https://x/1".to_string(), "https://x/2".to_string()];
+        assert_eq!(
+            player_args("/usr/bin/mpv", &urls).unwrap(),
+            ["--sub-file=https://x/1", "--sub-file=https://x/2"]
+        );
+        assert_eq!(
+            player_args("iina", &urls).unwrap()[0],
+            "--mpv-sub-file=https://x/1"
+        );
+        assert_eq!(player_args("VLC.exe", &urls).unwrap().len(), 1);
+        assert!(player_args("open", &urls).is_none());
+    }
+}
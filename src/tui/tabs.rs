@@ -0,0 +1,228 @@
+//! Browse tabs. Each tab has its own folder, listing, cursor, filter and
+//! preview. The active tab lives in `App`'s own browser fields, so nothing
+//! else needs to know tabs exist; the others are parked in `App::tabs`
+//! until switched to.
+
+use crate::pikpak::Entry;
+
+use super::{App, PreviewState};
+
+/// At most this many tabs, so `1`–`9` can reach every one of them.
+pub(super) const MAX_TABS: usize = 9;
+
+/// The browser state of a tab that isn't shown.
+pub(super) struct BrowseTab {
+    pub(super) folder_id: String,
+    pub(super) breadcrumb: Vec<(String, String)>,
+    pub(super) entries: Vec<Entry>,
+    pub(super) selected: usize,
+    pub(super) filter_source: Option<Vec<Entry>>,
+    pub(super) filter_query: String,
+    parent_entries: Vec<Entry>,
+    parent_selected: usize,
+    preview_state: PreviewState,
+    preview_target_id: Option<String>,
+    preview_target_name: Option<String>,
+    preview_scroll: usize,
+    pending_preview_fetch: bool,
+}
+
+impl Default for BrowseTab {
+    fn default() -> Self {
+        Self {
+            folder_id: String::new(),
+            breadcrumb: Vec::new(),
+            entries: Vec::new(),
+            selected: 0,
+            filter_source: None,
+            filter_query: String::new(),
+            parent_entries: Vec::new(),
+            parent_selected: 0,
+            preview_state: PreviewState::Empty,
+            preview_target_id: None,
+            preview_target_name: None,
+            preview_scroll: 0,
+            pending_preview_fetch: false,
+        }
+    }
+}
+
+impl BrowseTab {
+    /// A tab on `folder_id`, nothing selected or previewed yet.
+    pub(super) fn at(
+        folder_id: String,
+        breadcrumb: Vec<(String, String)>,
+        entries: Vec<Entry>,
+    ) -> Self {
+        Self {
+            folder_id,
+            breadcrumb,
+            entries,
+            ..Self::default()
+        }
+    }
+
+    /// Full path of the tab's folder, like `App::current_path_display`.
+    pub(super) fn path_display(&self) -> String {
+        let path: Vec<&str> = self.breadcrumb.iter().map(|(_, n)| n.as_str()).collect();
+        format!("/{}", path.join("/"))
+    }
+}
+
+/// Tab bar label: the folder's name, or `/` at the root.
+fn tab_title(breadcrumb: &[(String, String)]) -> &str {
+    breadcrumb.last().map_or("/", |(_, name)| name.as_str())
+}
+
+impl App {
+    /// Labels for the tab bar, in order; the active tab's comes from the
+    /// live browser state.
+    pub(super) fn tab_titles(&self) -> Vec<&str> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .map(|(i, tab)| {
+                if i == self.active_tab {
+                    tab_title(&self.breadcrumb)
+                } else {
+                    tab_title(&tab.breadcrumb)
+                }
+            })
+            .collect()
+    }
+
+    /// Open a new tab on the current folder, right after the active one.
+    pub(super) fn open_tab(&mut self) {
+        if self.config.commander_mode {
+            self.push_log("Commander mode uses its two panes instead of tabs".into());
+            return;
+        }
+        if self.tabs.len() >= MAX_TABS {
+            self.push_log(format!("At most {MAX_TABS} tabs can be open"));
+            return;
+        }
+        let tab = BrowseTab {
+            selected: self.selected,
+            filter_source: self.filter_source.clone(),
+            filter_query: self.filter_query.clone(),
+            parent_entries: self.parent_entries.clone(),
+            parent_selected: self.parent_selected,
+            ..BrowseTab::at(
+                self.current_folder_id.clone(),
+                self.breadcrumb.clone(),
+                self.entries.clone(),
+            )
+        };
+        let parked = self.park_tab();
+        self.tabs[self.active_tab] = parked;
+        self.active_tab += 1;
+        self.tabs.insert(self.active_tab, BrowseTab::default());
+        self.unpark_tab(tab);
+    }
+
+    /// Close the active tab and show its right-hand neighbour (or the left
+    /// one when it was the last). The only tab can't be closed.
+    pub(super) fn close_tab(&mut self) {
+        if self.config.commander_mode {
+            self.push_log("Commander mode uses its two panes instead of tabs".into());
+            return;
+        }
+        if self.tabs.len() <= 1 {
+            self.push_log("Only one tab is open".into());
+            return;
+        }
+        self.park_tab();
+        self.tabs.remove(self.active_tab);
+        self.active_tab = self.active_tab.min(self.tabs.len() - 1);
+        let tab = std::mem::take(&mut self.tabs[self.active_tab]);
+        self.unpark_tab(tab);
+    }
+
+    /// How many tabs can take focus: commander mode keeps to its two panes.
+    fn open_tabs(&self) -> usize {
+        if self.config.commander_mode {
+            self.tabs.len().min(2)
+        } else {
+            self.tabs.len()
+        }
+    }
+
+    /// Show tab `idx` (0-based); out-of-range indices are ignored.
+    pub(super) fn switch_tab(&mut self, idx: usize) {
+        if idx == self.active_tab || idx >= self.open_tabs() {
+            return;
+        }
+        let parked = self.park_tab();
+        self.tabs[self.active_tab] = parked;
+        self.active_tab = idx;
+        let tab = std::mem::take(&mut self.tabs[idx]);
+        self.unpark_tab(tab);
+    }
+
+    /// Step to the next (or previous) tab, wrapping around.
+    pub(super) fn cycle_tab(&mut self, forward: bool) {
+        let n = self.open_tabs();
+        let idx = if forward {
+            (self.active_tab + 1) % n
+        } else {
+            (self.active_tab + n - 1) % n
+        };
+        self.switch_tab(idx);
+    }
+
+    /// Forget every tab but the active one, e.g. after switching accounts,
+    /// when the other tabs' folder ids belong to someone else.
+    pub(super) fn reset_tabs(&mut self) {
+        self.tabs = vec![BrowseTab::default()];
+        self.active_tab = 0;
+    }
+
+    /// Move the browser state out of `App`, cancelling whatever it still
+    /// had in flight: those results would land in the next tab.
+    fn park_tab(&mut self) -> BrowseTab {
+        self.cancel_listing();
+        self.parent_cancel.cancel();
+        self.preview_cancel.cancel();
+        BrowseTab {
+            folder_id: std::mem::take(&mut self.current_folder_id),
+            breadcrumb: std::mem::take(&mut self.breadcrumb),
+            entries: std::mem::take(&mut self.entries),
+            selected: self.selected,
+            filter_source: self.filter_source.take(),
+            filter_query: std::mem::take(&mut self.filter_query),
+            parent_entries: std::mem::take(&mut self.parent_entries),
+            parent_selected: self.parent_selected,
+            preview_state: std::mem::replace(&mut self.preview_state, PreviewState::Empty),
+            preview_target_id: self.preview_target_id.take(),
+            preview_target_name: self.preview_target_name.take(),
+            preview_scroll: self.preview_scroll,
+            pending_preview_fetch: self.pending_preview_fetch,
+        }
+    }
+
+    /// Make `tab` the live browser state and reload its folder, which may
+    /// have changed while it was in the background.
+    fn unpark_tab(&mut self, tab: BrowseTab) {
+        self.current_folder_id = tab.folder_id;
+        self.breadcrumb = tab.breadcrumb;
+        self.entries = tab.entries;
+        self.selected = tab.selected;
+        self.filter_source = tab.filter_source;
+        self.filter_query = tab.filter_query;
+        self.parent_entries = tab.parent_entries;
+        self.parent_selected = tab.parent_selected;
+        self.preview_state = tab.preview_state;
+        self.preview_target_id = tab.preview_target_id;
+        self.preview_target_name = tab.preview_target_name;
+        self.preview_scroll = tab.preview_scroll;
+        self.pending_preview_fetch = tab.pending_preview_fetch;
+        self.scroll_offset.set(0);
+        self.parent_scroll_offset.set(0);
+        // A preview that was still loading was cancelled when parked.
+        if matches!(self.preview_state, PreviewState::Loading) || self.preview_target_id.is_none() {
+            self.on_cursor_move();
+        }
+        self.refresh_current();
+        self.refresh_parent();
+    }
+}
@@ -0,0 +1,40 @@
+//! Terminal title and taskbar/tab progress (OSC 9;4, understood by Windows
+//! Terminal, ConEmu and iTerm2), so the state is visible from another tab.
+
+use std::io::{self, Write as _};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crossterm::execute;
+use crossterm::terminal::SetTitle;
+
+/// Whether a progress indicator is currently shown, so `clear` only writes
+/// the reset sequence to terminals we've actually sent one to.
+static PROGRESS_SHOWN: AtomicBool = AtomicBool::new(false);
+
+/// Aggregate download progress: percent done, and whether every unfinished
+/// task is paused.
+pub type Progress = (u8, bool);
+
+pub fn set_title(title: &str) {
+    let _ = execute!(io::stdout(), SetTitle(title));
+}
+
+pub fn set_progress(progress: Option<Progress>) {
+    let seq = match progress {
+        // State 4 is "paused/warning", rendered yellow by most terminals.
+        Some((pct, true)) => format!("\x1b]9;4;4;{pct}\x07"),
+        Some((pct, false)) => format!("\x1b]9;4;1;{pct}\x07"),
+        None if PROGRESS_SHOWN.load(Ordering::Relaxed) => "\x1b]9;4;0\x07".to_string(),
+        None => return,
+    };
+    PROGRESS_SHOWN.store(progress.is_some(), Ordering::Relaxed);
+    let mut out = io::stdout();
+    let _ = out.write_all(seq.as_bytes());
+    let _ = out.flush();
+}
+
+/// Drop the progress indicator and blank the title on exit.
+pub fn clear() {
+    set_progress(None);
+    set_title("");
+}
@@ -0,0 +1,698 @@
+//! The `u` upload flow: a local path plus the remote folder to put it in
+//! (the folder being viewed, unless changed), then one prompt per file that
+//! already exists there before anything is sent.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result, anyhow};
+use crossterm::event::KeyCode;
+
+use crate::pikpak::{Backend, Entry, EntryKind, HashAhead, PikPak};
+
+use super::completion::PathInput;
+use super::download::{TransferWindow, local_minute_of_day};
+use super::handler::LocalPathInputResult;
+use super::local_completion::{LocalPathInput, expand_home};
+use super::{App, InputMode, OpResult};
+
+pub(super) struct UploadForm {
+    pub(super) local: LocalPathInput,
+    /// Remote folder to upload into; a path without a leading `/` is
+    /// relative to the folder being viewed.
+    pub(super) dest: PathInput,
+    /// Typing into the destination field rather than the local path.
+    pub(super) on_dest: bool,
+    /// What `dest` started as, i.e. the folder being viewed.
+    opened_in: String,
+}
+
+/// What to do with a file whose name is already taken in the destination.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum Resolution {
+    /// Upload it next to the existing one.
+    KeepBoth,
+    /// Trash the existing file, then upload.
+    Overwrite,
+    Skip,
+}
+
+pub(super) struct PlannedFile {
+    pub(super) local: PathBuf,
+    size: u64,
+    /// Folders under the destination it goes into, for a folder upload.
+    pub(super) dir: Vec<String>,
+    /// Id of the file already there under the same name.
+    pub(super) existing: Option<String>,
+    /// `None` until a conflict has been answered.
+    pub(super) resolution: Option<Resolution>,
+}
+
+/// Everything one upload will do, worked out before anything is sent.
+pub(super) struct UploadPlan {
+    dest_id: String,
+    pub(super) dest_path: String,
+    /// Remote folders that didn't exist when planned.
+    created: u32,
+    /// The (first) local file or folder's name.
+    name: String,
+    is_dir: bool,
+    /// How many local paths were given, e.g. by a glob.
+    sources: usize,
+    /// Folders to upload into, parents first, with the id of the remote
+    /// folder of the same name when there already is one.
+    folders: Vec<(Vec<String>, Option<String>)>,
+    pub(super) files: Vec<PlannedFile>,
+    /// Files an interrupted run of the same upload already sent, left out
+    /// of `files`.
+    resumed: usize,
+}
+
+impl UploadPlan {
+    /// The next conflict still to be answered.
+    pub(super) fn pending(&self) -> Option<usize> {
+        self.files.iter().position(|f| f.resolution.is_none())
+    }
+
+    /// Answer the conflict at `idx`, and every later one too if `all`.
+    fn resolve(&mut self, idx: usize, resolution: Resolution, all: bool) {
+        for file in self.files.iter_mut().skip(idx) {
+            if file.resolution.is_none() {
+                file.resolution = Some(resolution);
+                if !all {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// The absolute path `typed` names, read relative to `current` unless it
+/// starts with `/`.
+fn join_dest(current: &str, typed: &str) -> String {
+    let typed = typed.trim();
+    let joined = if typed.starts_with('/') {
+        typed.to_string()
+    } else {
+        format!("{}/{}", current.trim_end_matches('/'), typed)
+    };
+    let parts: Vec<&str> = joined.split('/').filter(|s| !s.is_empty()).collect();
+    format!("/{}", parts.join("/"))
+}
+
+/// The local paths `input` names: one path, a `*` / `?` pattern in its last
+/// component (`~/incoming/*.mkv`), or `@list.txt` for a file listing one
+/// path or pattern per line (blank lines and `#` comments skipped; relative
+/// paths are read from the list's folder).
+fn expand_sources(input: &str) -> Result<Vec<PathBuf>> {
+    let input = input.trim();
+    let Some(list) = input.strip_prefix('@') else {
+        return expand_one(input, Path::new("."));
+    };
+    let list = expand_home(list.trim());
+    let text = std::fs::read_to_string(&list)
+        .with_context(|| format!("cannot read list: {}", list.display()))?;
+    let base = list.parent().unwrap_or(Path::new("."));
+    let mut paths = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if !line.is_empty() && !line.starts_with('#') {
+            paths.extend(expand_one(line, base)?);
+        }
+    }
+    if paths.is_empty() {
+        return Err(anyhow!("{} lists no files", list.display()));
+    }
+    Ok(paths)
+}
+
+fn expand_one(spec: &str, base: &Path) -> Result<Vec<PathBuf>> {
+    let path = base.join(expand_home(spec));
+    let pattern = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    if !pattern.contains(['*', '?']) {
+        if !path.is_file() && !path.is_dir() {
+            return Err(anyhow!("File not found: {}", path.display()));
+        }
+        return Ok(vec![path]);
+    }
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut matches: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("cannot read dir: {}", dir.display()))?
+        .flatten()
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().into_owned();
+            (pattern.starts_with('.') || !name.starts_with('.'))
+                && crate::cmd::glob_match(&pattern, &name)
+        })
+        .map(|e| e.path())
+        .collect();
+    if matches.is_empty() {
+        return Err(anyhow!("Nothing matches {spec}"));
+    }
+    matches.sort();
+    Ok(matches)
+}
+
+/// Compare `sources` with what's already in `dest_id`. A local folder
+/// whose name matches a remote folder is merged into it, so the files
+/// inside are checked one by one.
+fn plan_upload(client: &PikPak, dest_id: &str, sources: &[PathBuf]) -> Result<UploadPlan> {
+    let first = &sources[0];
+    let name = first
+        .file_name()
+        .ok_or_else(|| anyhow!("invalid path: {}", first.display()))?
+        .to_string_lossy()
+        .into_owned();
+    let mut plan = UploadPlan {
+        dest_id: dest_id.to_string(),
+        dest_path: String::new(),
+        created: 0,
+        name,
+        is_dir: first.is_dir(),
+        sources: sources.len(),
+        folders: Vec::new(),
+        files: Vec::new(),
+        resumed: 0,
+    };
+    let listing = client.ls(dest_id)?;
+    for local in sources {
+        let name = local.file_name().unwrap_or_default().to_string_lossy();
+        if local.is_dir() {
+            let remote = find(&listing, true, &name);
+            plan_dir(client, &mut plan, remote, local, vec![name.into_owned()])?;
+        } else {
+            let existing = find(&listing, false, &name);
+            plan.files
+                .push(planned(local.clone(), Vec::new(), existing));
+        }
+    }
+    // Picking up an interrupted upload: what it sent is there now, and
+    // shouldn't come up as a name clash.
+    let locals: Vec<&Path> = plan.files.iter().map(|f| f.local.as_path()).collect();
+    let mut sent = client.sent_before(dest_id, &locals).into_iter();
+    let planned = plan.files.len();
+    plan.files.retain(|_| !sent.next().unwrap_or(false));
+    plan.resumed = planned - plan.files.len();
+    Ok(plan)
+}
+
+fn plan_dir(
+    client: &PikPak,
+    plan: &mut UploadPlan,
+    remote: Option<String>,
+    local: &Path,
+    dir: Vec<String>,
+) -> Result<()> {
+    // Below a folder that has to be created, nothing can clash.
+    let listing = match &remote {
+        Some(id) => client.ls(id)?,
+        None => Vec::new(),
+    };
+    plan.folders.push((dir.clone(), remote));
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(local)
+        .with_context(|| format!("cannot read dir: {}", local.display()))?
+        .flatten()
+        .map(|e| e.path())
+        .collect();
+    entries.sort();
+    for path in entries {
+        let child = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() {
+            let remote = find(&listing, true, &child);
+            let mut sub = dir.clone();
+            sub.push(child.into_owned());
+            plan_dir(client, plan, remote, &path, sub)?;
+        } else if path.is_file() {
+            let existing = find(&listing, false, &child);
+            plan.files.push(planned(path, dir.clone(), existing));
+        }
+    }
+    Ok(())
+}
+
+/// Id of the folder (or file) called `name` in `listing`.
+fn find(listing: &[Entry], folder: bool, name: &str) -> Option<String> {
+    listing
+        .iter()
+        .find(|e| (e.kind == EntryKind::Folder) == folder && e.name == name)
+        .map(|e| e.id.clone())
+}
+
+fn planned(local: PathBuf, dir: Vec<String>, existing: Option<String>) -> PlannedFile {
+    let size = std::fs::metadata(&local).map(|m| m.len()).unwrap_or(0);
+    let resolution = existing.is_none().then_some(Resolution::KeepBoth);
+    PlannedFile {
+        local,
+        size,
+        dir,
+        existing,
+        resolution,
+    }
+}
+
+/// Block while `window` is closed, checking the local time (`minute`) every
+/// `pause`, and say once what the upload is waiting for.
+fn wait_for_window(
+    window: Option<TransferWindow>,
+    minute: impl Fn() -> u16,
+    pause: Duration,
+    progress: &mut impl FnMut(String),
+) {
+    let Some(window) = window else { return };
+    let mut told = false;
+    while !window.contains(minute()) {
+        if !told {
+            progress(format!(
+                "Upload waiting for transfer window {window}, until {}…",
+                window.start_label()
+            ));
+            told = true;
+        }
+        std::thread::sleep(pause);
+    }
+}
+
+/// Carry out an answered plan, calling `progress` with a status line before
+/// each file; returns the line for the log. Outside `window` it waits
+/// before the next file, as downloads do.
+fn run_plan(
+    client: &PikPak,
+    plan: UploadPlan,
+    window: Option<TransferWindow>,
+    mut progress: impl FnMut(String),
+) -> Result<String> {
+    let mut ids: HashMap<Vec<String>, String> = HashMap::new();
+    ids.insert(Vec::new(), plan.dest_id.clone());
+    for (dir, existing) in plan.folders {
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                let parent = &ids[&dir[..dir.len() - 1]];
+                client.mkdir(parent, dir.last().unwrap())?.id
+            }
+        };
+        ids.insert(dir, id);
+    }
+
+    let queued: Vec<&PlannedFile> = plan
+        .files
+        .iter()
+        .filter(|f| f.resolution != Some(Resolution::Skip))
+        .collect();
+    let total_bytes: u64 = queued.iter().map(|f| f.size).sum();
+    let (count, mut sent) = (queued.len(), 0u64);
+    let (mut ok, mut failed, mut instant) = (0usize, 0usize, 0usize);
+    let skipped = plan.files.len() - count;
+    let to = &plan.dest_path;
+    let what = if plan.sources > 1 {
+        format!("{} items", plan.sources)
+    } else {
+        format!("folder '{}'", plan.name)
+    };
+    // A folder upload lists its files first, so a crash part-way through
+    // leaves a record of what's left for the next run to pick up.
+    let manifest = plan.is_dir || plan.sources > 1;
+    if manifest {
+        let files: Vec<&Path> = queued.iter().map(|f| f.local.as_path()).collect();
+        let _ = client.begin_folder_upload(&plan.dest_id, &format!("{what} to {to}"), &files);
+    }
+    // Files PikPak already has complete instantly once their hash is
+    // known, so hashing the next few while one is sent saves the most.
+    let mut hashes = HashAhead::new(queued.iter().map(|f| f.local.clone()).collect());
+    let mut last_err = None;
+    for (i, file) in queued.into_iter().enumerate() {
+        wait_for_window(
+            window,
+            local_minute_of_day,
+            Duration::from_secs(30),
+            &mut progress,
+        );
+        if count > 1 {
+            let instant = if instant > 0 {
+                format!(", {instant} instant")
+            } else {
+                String::new()
+            };
+            progress(format!(
+                "Uploading {}/{count} ({} of {}{instant}): {}…",
+                i + 1,
+                crate::cmd::format_size(sent),
+                crate::cmd::format_size(total_bytes),
+                file.local.file_name().unwrap_or_default().to_string_lossy()
+            ));
+        }
+        let parent = &ids[&file.dir];
+        let hash = hashes
+            .next()
+            .unwrap_or_else(|| Err(anyhow!("no hash for {}", file.local.display())));
+        let upload = |hash: Result<String>| {
+            hash.and_then(|h| client.upload_file_hashed(Some(parent), &file.local, h))
+        };
+        let result = match (file.resolution, &file.existing) {
+            // Trash the old copy only once the new one is safely up.
+            (Some(Resolution::Overwrite), Some(old)) => upload(hash).and_then(|uploaded| {
+                client.remove(&[old.as_str()])?;
+                Ok(uploaded)
+            }),
+            _ => upload(hash),
+        };
+        sent += file.size;
+        match result {
+            Ok((_, dedup)) => {
+                ok += 1;
+                instant += usize::from(dedup);
+                if manifest {
+                    client.folder_file_sent(&plan.dest_id, &file.local);
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                last_err = Some(e);
+            }
+        }
+    }
+
+    if manifest && failed == 0 {
+        client.end_folder_upload(&plan.dest_id);
+    }
+
+    let mut msg = if plan.sources == 1 && !plan.is_dir {
+        match (ok, skipped, last_err) {
+            (_, 0, Some(e)) => return Err(e),
+            (0, _, _) => format!("Skipped '{}': already in {to}", plan.name),
+            _ if instant > 0 => format!("Uploaded '{}' to {to} (instant, dedup)", plan.name),
+            _ => format!("Uploaded '{}' to {to}", plan.name),
+        }
+    } else if skipped == 0 && failed == 0 && instant > 0 {
+        format!("Uploaded {what} to {to} ({ok} files, {instant} instant)")
+    } else if skipped == 0 && failed == 0 {
+        format!("Uploaded {what} to {to} ({ok} files)")
+    } else {
+        format!(
+            "Uploaded {what} to {to} ({ok} ok, {instant} instant, {skipped} skipped, {failed} failed)"
+        )
+    };
+    if plan.created > 0 {
+        msg.push_str(&format!("; created {} folder(s)", plan.created));
+    }
+    if plan.resumed > 0 {
+        msg.push_str(&format!(
+            "; {} sent by the interrupted run before",
+            plan.resumed
+        ));
+    }
+    Ok(msg)
+}
+
+impl App {
+    /// Point out folder uploads a crash or reboot cut short; uploading the
+    /// same thing to the same folder again picks them up.
+    pub(super) fn log_interrupted_uploads(&mut self) {
+        for (label, left) in self.client.interrupted_uploads().unwrap_or_default() {
+            self.push_log(format!(
+                "Upload of {label} was interrupted with {left} file(s) to go; upload it again to carry on"
+            ));
+        }
+    }
+
+    /// `u`: ask for a local file or folder to upload into the folder being
+    /// viewed, or a destination typed in instead.
+    pub(super) fn open_upload_input(&mut self) {
+        let here = self.current_path_display();
+        let mut dest = PathInput::new();
+        dest.value = here.clone();
+        self.input = InputMode::UploadInput(Box::new(UploadForm {
+            local: LocalPathInput::new_for_upload(),
+            dest,
+            on_dest: false,
+            opened_in: here,
+        }));
+    }
+
+    pub(super) fn handle_upload_input_key(&mut self, code: KeyCode, mut form: Box<UploadForm>) {
+        let switch = matches!(code, KeyCode::Up | KeyCode::Down);
+        if form.on_dest {
+            let dest = &mut form.dest;
+            match code {
+                _ if switch && dest.candidates.is_empty() => form.on_dest = false,
+                KeyCode::Tab => self.tab_complete(dest),
+                KeyCode::Esc | KeyCode::Enter if !dest.candidates.is_empty() => {
+                    dest.candidates.clear();
+                    dest.candidate_idx = None;
+                }
+                KeyCode::Esc => {
+                    self.input = InputMode::Normal;
+                    return;
+                }
+                KeyCode::Enter => {
+                    let local = form.local.value.trim().to_string();
+                    if self.start_upload(&local, &form) {
+                        return;
+                    }
+                }
+                KeyCode::Backspace => {
+                    dest.value.pop();
+                    dest.candidates.clear();
+                    dest.candidate_idx = None;
+                }
+                KeyCode::Char(c) => {
+                    dest.value.push(c);
+                    dest.candidates.clear();
+                    dest.candidate_idx = None;
+                }
+                _ => {}
+            }
+        } else if switch && form.local.candidates.is_empty() {
+            form.on_dest = true;
+        } else {
+            match Self::apply_local_path_input_key(code, &mut form.local) {
+                LocalPathInputResult::Updated => {}
+                LocalPathInputResult::Confirmed(local) => {
+                    if self.start_upload(&local, &form) {
+                        return;
+                    }
+                }
+                LocalPathInputResult::Cancelled => {
+                    self.input = InputMode::Normal;
+                    return;
+                }
+            }
+        }
+        self.input = InputMode::UploadInput(form);
+    }
+
+    /// Find the local files `local` names and look for name clashes in the
+    /// destination in the background. `false` (with the reason logged)
+    /// leaves the form open.
+    fn start_upload(&mut self, local: &str, form: &UploadForm) -> bool {
+        let sources = match expand_sources(local) {
+            Ok(sources) => sources,
+            Err(e) => {
+                self.push_log(format!("{e:#}"));
+                return false;
+            }
+        };
+        let here = form.opened_in.clone();
+        let dest_path = join_dest(&here, &form.dest.value);
+        let current = (dest_path == here).then(|| self.current_folder_id.clone());
+        let client = Arc::clone(&self.client);
+        let tx = self.result_tx.clone();
+        self.loading = true;
+        self.loading_label = Some(format!("Checking {dest_path}…"));
+        self.input = InputMode::Normal;
+        self.workers.spawn(move || {
+            let result = match current {
+                Some(id) => Ok((id, 0)),
+                None => client.mkdir_p(&dest_path),
+            }
+            .and_then(|(dest_id, created)| {
+                let mut plan = plan_upload(&client, &dest_id, &sources)?;
+                plan.dest_path = dest_path;
+                plan.created = created;
+                Ok(plan)
+            });
+            let _ = tx.send(OpResult::UploadPlan(result.map(Box::new)));
+        });
+        true
+    }
+
+    pub(super) fn apply_upload_plan(&mut self, result: Result<Box<UploadPlan>>) {
+        self.finish_loading();
+        match result {
+            Ok(plan) if plan.pending().is_some() => self.input = InputMode::UploadConflict(plan),
+            Ok(plan) => self.run_upload(plan),
+            Err(e) => self.push_log(format!("Upload failed: {e:#}")),
+        }
+    }
+
+    pub(super) fn handle_upload_conflict_key(&mut self, code: KeyCode, mut plan: Box<UploadPlan>) {
+        let Some(idx) = plan.pending() else {
+            self.run_upload(plan);
+            return;
+        };
+        let (resolution, all) = match code {
+            KeyCode::Char('o') => (Resolution::Overwrite, false),
+            KeyCode::Char('k') => (Resolution::KeepBoth, false),
+            KeyCode::Char('s') => (Resolution::Skip, false),
+            KeyCode::Char('O') => (Resolution::Overwrite, true),
+            KeyCode::Char('K') => (Resolution::KeepBoth, true),
+            KeyCode::Char('S') => (Resolution::Skip, true),
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.push_log("Upload cancelled".into());
+                self.input = InputMode::Normal;
+                return;
+            }
+            _ => {
+                self.input = InputMode::UploadConflict(plan);
+                return;
+            }
+        };
+        plan.resolve(idx, resolution, all);
+        if plan.pending().is_some() {
+            self.input = InputMode::UploadConflict(plan);
+        } else {
+            self.input = InputMode::Normal;
+            self.run_upload(plan);
+        }
+    }
+
+    fn run_upload(&mut self, plan: Box<UploadPlan>) {
+        let client = Arc::clone(&self.client);
+        let window = self.download_state.window;
+        let tx = self.result_tx.clone();
+        self.loading = true;
+        self.uploads_running += 1;
+        self.loading_label = Some(if plan.sources > 1 {
+            format!("Uploading {} items…", plan.sources)
+        } else if plan.is_dir {
+            format!("Uploading folder {}…", plan.name)
+        } else {
+            format!("Uploading {}…", plan.name)
+        });
+        // An upload can wait hours for the transfer window, so it gets its
+        // own thread rather than a worker.
+        std::thread::spawn(move || {
+            let progress = |label| {
+                let _ = tx.send(OpResult::UploadProgress(label));
+            };
+            let result = run_plan(&client, *plan, window, progress);
+            let _ = tx.send(OpResult::Upload(result));
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(existing: &[bool]) -> UploadPlan {
+        UploadPlan {
+            dest_id: String::new(),
+            dest_path: "/".into(),
+            created: 0,
+            name: "dir".into(),
+            is_dir: true,
+            sources: 1,
+            folders: Vec::new(),
+            resumed: 0,
+            files: existing
+                .iter()
+                .enumerate()
+                .map(|(i, &taken)| {
+                    planned(
+                        PathBuf::from(format!("f{i}")),
+                        Vec::new(),
+                        taken.then(|| format!("id{i}")),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn dest_is_relative_to_the_viewed_folder() {
+        assert_eq!(join_dest("/Movies", "2024"), "/Movies/2024");
+        assert_eq!(join_dest("/Movies", "/Music/"), "/Music");
+        assert_eq!(join_dest("/", ""), "/");
+        assert_eq!(join_dest("/Movies", "  "), "/Movies");
+    }
+
+    #[test]
+    fn conflicts_are_answered_one_at_a_time_or_all_at_once() {
+        let mut p = plan(&[false, true, true, false, true]);
+        assert_eq!(p.pending(), Some(1));
+
+        p.resolve(1, Resolution::Skip, false);
+        assert_eq!(p.pending(), Some(2));
+
+        p.resolve(2, Resolution::Overwrite, true);
+        assert_eq!(p.pending(), None);
+        let answers: Vec<_> = p.files.iter().map(|f| f.resolution).collect();
+        assert_eq!(
+            answers,
+            [
+                Some(Resolution::KeepBoth),
+                Some(Resolution::Skip),
+                Some(Resolution::Overwrite),
+                Some(Resolution::KeepBoth),
+                Some(Resolution::Overwrite),
+            ]
+        );
+    }
+
+    #[test]
+    fn uploads_wait_for_the_transfer_window() {
+        let window = TransferWindow::parse("01:00-07:00");
+        let minutes = std::cell::Cell::new(0);
+        // Midnight, then still closed, then 01:00.
+        let clock = || {
+            minutes.set(minutes.get() + 1);
+            [0, 30, 60][minutes.get().min(3) - 1]
+        };
+        let mut said = Vec::new();
+        wait_for_window(window, clock, Duration::ZERO, &mut |line| said.push(line));
+        assert_eq!(minutes.get(), 3);
+        assert_eq!(
+            said,
+            ["Upload waiting for transfer window 01:00–07:00, until 01:00…"]
+        );
+
+        let mut said = Vec::new();
+        wait_for_window(None, || 0, Duration::ZERO, &mut |line| said.push(line));
+        wait_for_window(window, || 120, Duration::ZERO, &mut |line| said.push(line));
+        assert!(said.is_empty());
+    }
+
+    #[test]
+    fn sources_expand_from_globs_and_lists() {
+        let dir = std::env::temp_dir().join(format!("pikpaktui-upload-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        for name in ["a.mkv", "b.MKV", "c.srt", ".d.mkv"] {
+            std::fs::write(dir.join(name), b"x").unwrap();
+        }
+        let names = |paths: Vec<PathBuf>| -> Vec<String> {
+            paths
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+
+        let glob = format!("{}/*.mkv", dir.display());
+        assert_eq!(names(expand_sources(&glob).unwrap()), ["a.mkv", "b.MKV"]);
+
+        std::fs::write(dir.join("list.txt"), "# to send\nc.srt\n\nsub\n*.mkv\n").unwrap();
+        let list = format!("@{}/list.txt", dir.display());
+        assert_eq!(
+            names(expand_sources(&list).unwrap()),
+            ["c.srt", "sub", "a.mkv", "b.MKV"]
+        );
+
+        assert!(expand_sources(&format!("{}/*.iso", dir.display())).is_err());
+        assert!(expand_sources(&format!("{}/missing", dir.display())).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
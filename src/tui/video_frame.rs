@@ -0,0 +1,59 @@
+//! Preview frames for videos the API has no thumbnail for yet, cut by
+//! `ffmpeg` from the first few megabytes of the file.
+
+use anyhow::{Context, Result, anyhow};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// How much of the video to fetch. Enough for the opening seconds of most
+/// MKV/TS files and of MP4s with the index at the front.
+pub(super) const HEAD_BYTES: u64 = 8 << 20;
+
+/// Whether `ffmpeg` can be run, checked once.
+pub(super) fn ffmpeg_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        Command::new("ffmpeg")
+            .arg("-version")
+            .output()
+            .is_ok_and(|o| o.status.success())
+    })
+}
+
+/// Decode a representative frame from the start of a video. `head` is
+/// written to a temp file because ffmpeg can't probe most containers from a
+/// pipe.
+pub(super) fn extract(file_id: &str, head: &[u8]) -> Result<image::DynamicImage> {
+    let path = std::env::temp_dir().join(format!(
+        "pikpaktui-frame-{}-{}",
+        std::process::id(),
+        file_id
+    ));
+    std::fs::write(&path, head).context("failed to write video head")?;
+    let output = Command::new("ffmpeg")
+        .args(["-v", "error", "-i"])
+        .arg(&path)
+        .args([
+            "-vf",
+            "thumbnail",
+            "-frames:v",
+            "1",
+            "-f",
+            "image2pipe",
+            "-c:v",
+            "png",
+            "-",
+        ])
+        .output();
+    let _ = std::fs::remove_file(&path);
+    let output = output.context("failed to run ffmpeg")?;
+    if output.stdout.is_empty() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "ffmpeg found no frame in the first {} MB: {}",
+            HEAD_BYTES >> 20,
+            err.lines().last().unwrap_or("no output")
+        ));
+    }
+    image::load_from_memory(&output.stdout).context("failed to decode ffmpeg frame")
+}
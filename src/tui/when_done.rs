@@ -0,0 +1,318 @@
+//! What to do once every transfer has finished: quit, run a command,
+//! suspend or shut down the computer. Set for the whole queue with `W` in
+//! the download view, or by a finished task set to shut down; when the
+//! queue drains a countdown is shown, and any key stops it.
+
+use std::time::{Duration, Instant};
+
+use super::App;
+
+/// How long the countdown runs before the action is taken.
+const GRACE: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(super) enum WhenDone {
+    #[default]
+    Nothing,
+    Exit,
+    /// Run the `when_done_command` command.
+    Command,
+    Suspend,
+    Shutdown,
+}
+
+impl WhenDone {
+    const ALL: [Self; 5] = [
+        Self::Nothing,
+        Self::Exit,
+        Self::Command,
+        Self::Suspend,
+        Self::Shutdown,
+    ];
+
+    pub(super) fn label(self) -> &'static str {
+        match self {
+            Self::Nothing => "nothing",
+            Self::Exit => "quit pikpaktui",
+            Self::Command => "run command",
+            Self::Suspend => "suspend",
+            Self::Shutdown => "shut down",
+        }
+    }
+
+    /// As the countdown puts it: "{verb} in 42s".
+    fn verb(self) -> &'static str {
+        match self {
+            Self::Nothing => "",
+            Self::Exit => "Quitting",
+            Self::Command => "Running when_done_command",
+            Self::Suspend => "Suspending",
+            Self::Shutdown => "Shutting down",
+        }
+    }
+
+    fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&a| a == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+/// The queue-wide action and how far along it is.
+#[derive(Default)]
+pub(super) struct QueueDone {
+    pub(super) action: WhenDone,
+    /// Something was left to transfer since the action was set, so an
+    /// empty queue means it has drained rather than never started.
+    saw_work: bool,
+    /// When the action is taken; set once the queue drains.
+    pub(super) deadline: Option<Instant>,
+}
+
+impl QueueDone {
+    /// The countdown line, while one runs.
+    pub(super) fn countdown(&self, now: Instant) -> Option<String> {
+        let left = self.deadline?.saturating_duration_since(now);
+        Some(format!(
+            "{} in {}s",
+            self.action.verb(),
+            left.as_secs() + u64::from(left.subsec_nanos() > 0)
+        ))
+    }
+
+    /// Advance with whether anything is still transferring. Returns the
+    /// action once its countdown has run out.
+    fn tick(&mut self, busy: bool, now: Instant) -> Option<WhenDone> {
+        if self.action == WhenDone::Nothing {
+            return None;
+        }
+        if busy {
+            // Work queued during the countdown puts it off again.
+            self.saw_work = true;
+            self.deadline = None;
+            return None;
+        }
+        if !self.saw_work {
+            return None;
+        }
+        let deadline = *self.deadline.get_or_insert(now + GRACE);
+        if now < deadline {
+            return None;
+        }
+        let action = self.action;
+        *self = Self::default();
+        Some(action)
+    }
+}
+
+/// Put the computer to sleep.
+pub(super) fn suspend() -> anyhow::Result<()> {
+    let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        ("pmset", &["sleepnow"])
+    } else if cfg!(windows) {
+        ("rundll32.exe", &["powrprof.dll,SetSuspendState", "0,1,0"])
+    } else {
+        ("systemctl", &["suspend"])
+    };
+    let status = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| anyhow::anyhow!("failed to run {program}: {e}"))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("{program} failed ({status})"));
+    }
+    Ok(())
+}
+
+/// Shut the computer down. The countdown before it is the time to cancel.
+pub(super) fn shut_down() -> anyhow::Result<()> {
+    let (program, args): (&str, &[&str]) = if cfg!(target_os = "macos") {
+        (
+            "osascript",
+            &["-e", "tell application \"System Events\" to shut down"],
+        )
+    } else if cfg!(windows) {
+        ("shutdown", &["/s", "/t", "0"])
+    } else {
+        ("shutdown", &["-h", "now"])
+    };
+    let status = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| anyhow::anyhow!("failed to run {program}: {e}"))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("{program} failed ({status})"));
+    }
+    Ok(())
+}
+
+impl App {
+    /// `W` in the download view: cycle what happens once every transfer
+    /// has finished.
+    pub(super) fn cycle_when_done(&mut self) {
+        let action = self.queue_done.action.next();
+        self.queue_done = QueueDone {
+            action,
+            saw_work: self.transfers_busy(),
+            deadline: None,
+        };
+        if action == WhenDone::Command && self.config.when_done_command.is_none() {
+            self.push_log(
+                "When all done: run command — set when_done_command in config.toml".into(),
+            );
+        } else {
+            self.push_log(format!("When all done: {}", action.label()));
+        }
+    }
+
+    /// A finished task was set to shut down: do so once the rest of the
+    /// queue is through, after the same countdown as `W`.
+    pub(super) fn arm_shutdown(&mut self) {
+        if self.queue_done.action == WhenDone::Shutdown {
+            return;
+        }
+        self.queue_done = QueueDone {
+            action: WhenDone::Shutdown,
+            saw_work: true,
+            deadline: None,
+        };
+        self.push_log("When all done: shut down".into());
+    }
+
+    /// Whether any download is still to finish (paused ones included) or
+    /// an upload is running.
+    fn transfers_busy(&self) -> bool {
+        self.download_state.has_unfinished() || self.uploads_running > 0
+    }
+
+    /// Any key during the countdown stops it and turns the action off.
+    pub(super) fn cancel_when_done(&mut self) {
+        let action = self.queue_done.action;
+        self.queue_done = QueueDone::default();
+        self.push_log(format!("Cancelled: {} when all done", action.label()));
+    }
+
+    /// Start the countdown when the queue drains and take the action when
+    /// it runs out. Returns true to quit.
+    pub(super) fn tick_when_done(&mut self) -> bool {
+        let busy = self.transfers_busy();
+        let Some(action) = self.queue_done.tick(busy, Instant::now()) else {
+            return false;
+        };
+        match action {
+            WhenDone::Nothing => false,
+            WhenDone::Exit => true,
+            WhenDone::Command => {
+                let Some(command) = self.config.when_done_command.clone() else {
+                    self.push_log("All done, but no when_done_command in config.toml".into());
+                    return false;
+                };
+                self.push_log(format!("All done, running `{command}`"));
+                let tx = self.result_tx.clone();
+                self.workers.spawn(move || {
+                    if let Err(e) = run_command(&command) {
+                        let _ = tx.send(super::OpResult::Log(format!(
+                            "when_done_command failed: {e:#}"
+                        )));
+                    }
+                });
+                false
+            }
+            WhenDone::Suspend => {
+                self.push_log("All done, suspending".into());
+                let tx = self.result_tx.clone();
+                self.workers.spawn(move || {
+                    if let Err(e) = suspend() {
+                        let _ = tx.send(super::OpResult::Log(format!("Suspend failed: {e:#}")));
+                    }
+                });
+                false
+            }
+            WhenDone::Shutdown => {
+                self.push_log("All done, shutting down".into());
+                let tx = self.result_tx.clone();
+                self.workers.spawn(move || {
+                    if let Err(e) = shut_down() {
+                        let _ = tx.send(super::OpResult::Log(format!("Shutdown failed: {e:#}")));
+                    }
+                });
+                false
+            }
+        }
+    }
+}
+
+fn run_command(command: &str) -> anyhow::Result<()> {
+    let words = crate::local_path::split_command(command);
+    let (program, args) = words
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("when_done_command is empty"))?;
+    let status = std::process::Command::new(program)
+        .args(args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_err(|e| anyhow::anyhow!("failed to run {program}: {e}"))?;
+    if !status.success() {
+        return Err(anyhow::anyhow!("{program} failed ({status})"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_down_once_the_queue_drains() {
+        let start = Instant::now();
+        let mut done = QueueDone {
+            action: WhenDone::Suspend,
+            ..QueueDone::default()
+        };
+        // Nothing was queued yet, so an idle queue hasn't "drained".
+        assert_eq!(done.tick(false, start), None);
+        assert!(done.deadline.is_none());
+
+        assert_eq!(done.tick(true, start), None);
+        assert_eq!(done.tick(false, start), None);
+        assert_eq!(
+            done.countdown(start + Duration::from_millis(500))
+                .as_deref(),
+            Some("Suspending in 60s")
+        );
+        // New work puts the countdown off.
+        assert_eq!(done.tick(true, start + Duration::from_secs(10)), None);
+        assert!(done.deadline.is_none());
+
+        let drained = start + Duration::from_secs(20);
+        assert_eq!(done.tick(false, drained), None);
+        assert_eq!(done.tick(false, drained + GRACE), Some(WhenDone::Suspend));
+        // One-shot: it's off again afterwards.
+        assert_eq!(done.action, WhenDone::Nothing);
+        assert_eq!(done.tick(false, drained + GRACE * 2), None);
+    }
+
+    #[test]
+    fn a_task_set_to_shut_down_counts_down_after_the_rest() {
+        let mut app = App::for_tests();
+        app.uploads_running = 1;
+        app.arm_shutdown();
+        assert_eq!(app.queue_done.action, WhenDone::Shutdown);
+        assert!(!app.tick_when_done());
+        assert!(app.queue_done.deadline.is_none());
+
+        app.uploads_running = 0;
+        assert!(!app.tick_when_done());
+        assert!(app.queue_done.countdown(Instant::now()).is_some());
+        // Any key still stops it.
+        app.cancel_when_done();
+        assert_eq!(app.queue_done.action, WhenDone::Nothing);
+    }
+}
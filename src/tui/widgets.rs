@@ -1,6 +1,8 @@
 use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
 
+use crate::config::SortField;
+
 /// Compute the scroll offset so that `selected` is always visible
 /// within a window of `max_visible` items.
 pub(super) fn scroll_offset(selected: usize, max_visible: usize) -> usize {
@@ -103,17 +105,76 @@ pub(super) fn dynamic_overlay_height(
         .min(max_pct)
 }
 
+/// Sortable columns shown in the current pane's header, in display order
+/// (also the `1`..`5` sort keys).
+pub(super) const SORT_HEADER_FIELDS: [SortField; 5] = [
+    SortField::Name,
+    SortField::Size,
+    SortField::Created,
+    SortField::Type,
+    SortField::Extension,
+];
+
+/// Header label for `field`; the active sort column carries a direction arrow.
+pub(super) fn sort_header_label(field: SortField, active: SortField, reverse: bool) -> String {
+    let name = match field {
+        SortField::Name => "Name",
+        SortField::Size => "Size",
+        SortField::Created => "Created",
+        SortField::Type => "Type",
+        SortField::Extension => "Ext",
+        SortField::None => "None",
+    };
+    if field == active {
+        format!("{} {}", name, if reverse { "\u{2193}" } else { "\u{2191}" })
+    } else {
+        name.to_string()
+    }
+}
+
+/// Columns are drawn after a one-cell indent with a two-cell gap between
+/// labels. Returns the index of the label under column `x` (relative to the
+/// header's left edge), so clicks hit exactly what was drawn.
+pub(super) fn sort_header_hit(labels: &[String], x: usize) -> Option<usize> {
+    use unicode_width::UnicodeWidthStr;
+    let mut start = SORT_HEADER_INDENT;
+    for (idx, label) in labels.iter().enumerate() {
+        let end = start + label.width();
+        if x >= start && x < end {
+            return Some(idx);
+        }
+        start = end + SORT_HEADER_GAP;
+    }
+    None
+}
+
+/// Whole days until a trashed item is purged at `purge_time`, counting a
+/// partial day as a full one. `None` when the time is missing or
+/// unparseable.
+pub(super) fn trash_days_left(purge_time: &str, now_unix: i64) -> Option<i64> {
+    let remaining = crate::pikpak::iso_to_unix(purge_time)? - now_unix;
+    Some((remaining.max(0) + 86_399) / 86_400)
+}
+
+pub(super) const SORT_HEADER_INDENT: usize = 1;
+pub(super) const SORT_HEADER_GAP: usize = 2;
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // The real Settings overlay: 7 categories with these item counts (17 items).
-    const COUNTS: [usize; 7] = [5, 5, 2, 2, 1, 1, 1];
+    /// Item counts per category of the real Settings overlay.
+    fn counts() -> Vec<usize> {
+        crate::tui::App::settings_items(&crate::config::TuiConfig::default())
+            .iter()
+            .map(|(_, items)| items.len())
+            .collect()
+    }
 
     #[test]
     fn line_map_accounts_for_blank_and_headers() {
-        let map = settings_item_line_map(&COUNTS);
-        assert_eq!(map.len(), 17);
+        let map = settings_item_line_map(&counts());
+        assert_eq!(map.len(), counts().iter().sum::<usize>());
         // blank=0, cat0 header=1, item0 name=2, item0 desc=3, item1 name=4...
         assert_eq!(map[0], 2);
         assert_eq!(map[1], 4);
@@ -125,7 +186,7 @@ mod tests {
 
     #[test]
     fn item_at_row_unscrolled_maps_name_and_desc_rows() {
-        let map = settings_item_line_map(&COUNTS);
+        let map = settings_item_line_map(&counts());
         // Blank, category header, and a gap line select nothing.
         assert_eq!(settings_item_at_row(&map, 0, 0), None); // leading blank
         assert_eq!(settings_item_at_row(&map, 0, 1), None); // cat0 header
@@ -138,7 +199,7 @@ mod tests {
 
     #[test]
     fn item_at_row_honors_scroll_offset() {
-        let map = settings_item_line_map(&COUNTS);
+        let map = settings_item_line_map(&counts());
         // Scrolled so that line 13 (item5 name) is the first visible row.
         let scroll = 13;
         assert_eq!(settings_item_at_row(&map, scroll, 0), Some((5, true)));
@@ -149,12 +210,42 @@ mod tests {
 
     #[test]
     fn scroll_offset_keeps_selection_visible() {
-        let map = settings_item_line_map(&COUNTS);
+        let map = settings_item_line_map(&counts());
         let inner = 11;
         assert_eq!(settings_scroll_offset(&map, 0, inner), 0); // top item, no scroll
-        let off = settings_scroll_offset(&map, 16, inner); // last item
+        let last = map.len() - 1;
+        let off = settings_scroll_offset(&map, last, inner);
         assert!(off > 0);
         // The selected item's name row must be within the visible window.
-        assert!(map[16] >= off && map[16] < off + inner);
+        assert!(map[last] >= off && map[last] < off + inner);
+    }
+
+    #[test]
+    fn trash_days_left_rounds_up_and_clamps() {
+        let purge = "2026-01-20T00:00:00Z";
+        let t0 = crate::pikpak::iso_to_unix(purge).unwrap() - 10 * 86_400;
+        assert_eq!(trash_days_left(purge, t0), Some(10));
+        assert_eq!(trash_days_left(purge, t0 + 3600), Some(10));
+        assert_eq!(trash_days_left(purge, t0 + 9 * 86_400 + 1), Some(1));
+        assert_eq!(trash_days_left(purge, t0 + 20 * 86_400), Some(0));
+        assert_eq!(trash_days_left("", t0), None);
+    }
+
+    #[test]
+    fn sort_header_hit_matches_drawn_layout() {
+        let labels: Vec<String> = SORT_HEADER_FIELDS
+            .iter()
+            .map(|&f| sort_header_label(f, SortField::Size, true))
+            .collect();
+        assert_eq!(labels[1], "Size \u{2193}");
+        // " Name  Size ↓  Created ..."
+        assert_eq!(sort_header_hit(&labels, 0), None); // indent
+        assert_eq!(sort_header_hit(&labels, 1), Some(0));
+        assert_eq!(sort_header_hit(&labels, 4), Some(0));
+        assert_eq!(sort_header_hit(&labels, 5), None); // gap
+        assert_eq!(sort_header_hit(&labels, 7), Some(1));
+        assert_eq!(sort_header_hit(&labels, 12), Some(1)); // the arrow
+        assert_eq!(sort_header_hit(&labels, 15), Some(2));
+        assert_eq!(sort_header_hit(&labels, 200), None);
     }
 }
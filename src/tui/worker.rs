@@ -0,0 +1,182 @@
+//! Where the TUI's API calls run. Interactive requests (listings,
+//! previews, mutations) queue on [`WorkerPool`] and report back over the
+//! `OpResult` channel, which is the one message bus into the event loop.
+//! A job spawned with a [`CancelToken`] is skipped if the token fires
+//! before it starts, and once it fires any API call the job has in flight
+//! is aborted and its connection dropped (see `pikpak::abort_when`), so a
+//! superseded request stops rather than running to completion.
+
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Fixed-size pool for the TUI's background API calls. Rapid navigation used
+/// to spawn one thread per listing/preview request; queueing them here caps
+/// the number of in-flight requests instead. Results still come back over
+/// the `OpResult` channel.
+///
+/// Long transfers (downloads, uploads) keep their own threads so they never
+/// hold a worker that interactive requests are waiting for.
+pub(super) struct WorkerPool {
+    tx: mpsc::Sender<Job>,
+}
+
+impl WorkerPool {
+    pub(super) fn new(size: usize) -> Self {
+        let (tx, rx) = mpsc::channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        for i in 0..size.max(1) {
+            let rx = Arc::clone(&rx);
+            let _ = thread::Builder::new()
+                .name(format!("pikpak-worker-{i}"))
+                .spawn(move || {
+                    loop {
+                        // The guard is dropped at the end of this statement, so
+                        // only the wait for a job is serialized, not the job.
+                        let job = rx.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                        let Ok(job) = job else { break };
+                        // A panicking job must not take the worker down with it.
+                        let _ = std::panic::catch_unwind(AssertUnwindSafe(job));
+                    }
+                });
+        }
+        Self { tx }
+    }
+
+    pub(super) fn spawn(&self, job: impl FnOnce() + Send + 'static) {
+        let _ = self.tx.send(Box::new(job));
+    }
+
+    /// Queue `job` unless `cancel` fires first. Once it does, the API call
+    /// the job is waiting on fails straight away instead of running on.
+    pub(super) fn spawn_cancellable(
+        &self,
+        cancel: CancelToken,
+        job: impl FnOnce(&CancelToken) + Send + 'static,
+    ) {
+        self.spawn(move || {
+            if cancel.is_cancelled() {
+                return;
+            }
+            crate::pikpak::abort_when(Arc::clone(&cancel.0), || job(&cancel));
+        });
+    }
+}
+
+/// Shared flag that tells a queued or running job its result is no longer
+/// wanted. Under [`WorkerPool::spawn_cancellable`] it aborts the job's API
+/// call in flight; jobs also check it between pages and before sending
+/// their result, so a cancelled job never reaches the UI.
+#[derive(Clone, Default)]
+pub(super) struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub(super) fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub(super) fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Cancel whatever was issued with this token and hand out a fresh one
+    /// for the next operation of the same kind.
+    pub(super) fn renew(&mut self) -> CancelToken {
+        self.cancel();
+        *self = CancelToken::default();
+        self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn runs_every_job_with_bounded_concurrency() {
+        let pool = WorkerPool::new(2);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (done_tx, done_rx) = mpsc::channel();
+        for _ in 0..6 {
+            let (running, peak, done_tx) =
+                (Arc::clone(&running), Arc::clone(&peak), done_tx.clone());
+            pool.spawn(move || {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(20));
+                running.fetch_sub(1, Ordering::SeqCst);
+                done_tx.send(()).unwrap();
+            });
+        }
+        for _ in 0..6 {
+            done_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        }
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn renewing_a_token_cancels_only_the_previous_operation() {
+        let mut current = CancelToken::default();
+        let first = current.renew();
+        let second = current.renew();
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+        current.cancel();
+        assert!(second.is_cancelled());
+    }
+
+    #[test]
+    fn survives_a_panicking_job() {
+        let pool = WorkerPool::new(1);
+        pool.spawn(|| panic!("boom"));
+        let (tx, rx) = mpsc::channel();
+        pool.spawn(move || tx.send(42).unwrap());
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), 42);
+    }
+
+    #[test]
+    fn cancelling_a_job_aborts_its_preview_read() {
+        use std::io::Read;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/file", listener.local_addr().unwrap());
+        // Takes the request and never answers; reports when the client hangs up.
+        let (accepted_tx, accepted_rx) = mpsc::channel();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            accepted_tx.send(()).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(10)))
+                .unwrap();
+            let mut buf = [0u8; 4096];
+            while stream.read(&mut buf).is_ok_and(|n| n > 0) {}
+        });
+
+        let session = std::env::temp_dir().join(format!(
+            "pikpaktui-worker-{}/session.json",
+            std::process::id()
+        ));
+        let client = crate::pikpak::PikPak::for_tests("http://127.0.0.1:9".into(), session);
+        let pool = WorkerPool::new(1);
+        let mut current = CancelToken::default();
+        let (done_tx, done_rx) = mpsc::channel();
+        pool.spawn_cancellable(current.renew(), move |_| {
+            done_tx.send(client.fetch_range(&url, 0, 1023)).unwrap();
+        });
+
+        accepted_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        current.cancel();
+        let result = done_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(result.is_err());
+        // The connection was closed rather than left to time out.
+        server.join().unwrap();
+    }
+}